@@ -1,43 +1,46 @@
 #![no_main]
 
 pico_sdk::entrypoint!(main);
-use human_index_lib::{calculate_human_index, HumanIndexPublicInputs, PublicValues, VerificationResults};
+// The wide circuit reads and commits the same values as `u64`
+#[cfg(feature = "wide")]
+use human_index_lib::wide::{ProofInputs, PublicValues, calculate_human_index, commit_weights};
+#[cfg(not(feature = "wide"))]
+use human_index_lib::{ProofInputs, PublicValues, calculate_human_index, commit_weights};
 use pico_sdk::io::{commit, read_as};
 
 pub fn main() {
-    // Read private inputs (verification results) from the environment
-    let recaptcha_score: u32 = read_as();
-    let sms_verified: u32 = read_as();
-    let bio_verified: u32 = read_as();
-
-    let verification_results = VerificationResults {
-        recaptcha_score,
-        sms_verified,
-        bio_verified,
-    };
+    // Read the private verification results, then the public weights, expected
+    // output and validity window, in the order every host writes them
+    // (`ProofInputs::write_to`)
+    let ProofInputs {
+        results: verification_results,
+        inputs: public_inputs,
+        validity,
+        ..
+    } = ProofInputs::read_from(read_as);
 
-    // Read public inputs (weights and expected output) from the environment
-    let w1: u32 = read_as();
-    let w2: u32 = read_as();
-    let w3: u32 = read_as();
-    let w4: u32 = read_as();
-    let expected_output: u32 = read_as();
-
-    let public_inputs = HumanIndexPublicInputs {
-        w1,
-        w2,
-        w3,
-        w4,
-        expected_output,
-    };
+    // Refuse to prove an index that is never valid or valid for too long
+    if let Err(e) = validity.check() {
+        panic!("Invalid validity window: {}", e);
+    }
 
     // Compute the human index
     let computed_output = calculate_human_index(&verification_results, &public_inputs);
 
+    // Commit to the weight set so verifiers can check it against an approved hash
+    let weights_commitment = commit_weights(&public_inputs);
+
     // Commit all public values as a single struct to the proof
     let public_values = PublicValues {
         inputs: public_inputs,
         computed_output,
+        weights_commitment,
+        validity,
     };
+
+    // Refuse to prove an index that does not fit the 0-255 range consumers store
+    if let Err(e) = public_values.check() {
+        panic!("Invalid public values: {}", e);
+    }
     commit(&public_values);
 }
@@ -3,16 +3,21 @@ pragma solidity ^0.8.20;
 
 import "forge-std/Script.sol";
 import "../src/PicoVerifier.sol";
+import "../src/HumanIndexRegistry.sol";
 
 contract DeployPicoVerifier is Script {
     function run() external {
         uint256 deployerPrivateKey = vm.envUint("PRIVATE_KEY");
+        bytes32 riscvVkey = vm.envBytes32("RISCV_VKEY");
+        address submitter = vm.envAddress("SUBMITTER_ADDRESS");
 
         vm.startBroadcast(deployerPrivateKey);
 
         PicoVerifier picoVerifier = new PicoVerifier();
+        HumanIndexRegistry registry = new HumanIndexRegistry(picoVerifier, riscvVkey, submitter);
 
         console.log("PicoVerifier deployed to:", address(picoVerifier));
+        console.log("HumanIndexRegistry deployed to:", address(registry));
 
         vm.stopBroadcast();
     }
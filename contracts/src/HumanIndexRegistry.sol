@@ -0,0 +1,94 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import {IPicoVerifier} from "./IPicoVerifier.sol";
+
+/// @title Human Index Registry
+/// @notice Records the human index of each binding (e.g. a user) once its Pico
+/// proof verifies, so consumers can read it without verifying the proof themselves.
+contract HumanIndexRegistry {
+    /// @notice A verified human index.
+    struct Record {
+        uint32 humanIndex;
+        uint64 validUntil;
+        uint64 recordedAt;
+    }
+
+    /// @notice Thrown when a caller other than the submitter records an index.
+    error Unauthorized(address caller);
+
+    /// @notice Thrown when the proof is for another RISC-V program.
+    error UnknownProgram(bytes32 riscvVkey);
+
+    /// @notice Thrown when the public values are too short to hold the index.
+    error MalformedPublicValues(uint256 length);
+
+    /// @notice Emitted when the human index of `bindingId` is recorded.
+    event HumanIndexRecorded(bytes32 indexed bindingId, uint32 humanIndex, uint64 validUntil);
+
+    /// @dev Offset of `computed_output`, a little-endian uint32 after the inputs.
+    uint256 internal constant COMPUTED_OUTPUT_OFFSET = 20;
+
+    /// @dev Offset of `valid_until`, a little-endian uint64; see `PicoVerifier`.
+    uint256 internal constant VALID_UNTIL_OFFSET = 64;
+
+    /// @notice The verifier the proofs are checked with.
+    IPicoVerifier public immutable verifier;
+
+    /// @notice Verification key of the human index program.
+    bytes32 public immutable riscvVkey;
+
+    /// @notice The only account allowed to record indices.
+    /// @dev The binding is not committed in the proof, so an open registry would let
+    /// anyone record someone else's proof under their own binding.
+    address public immutable submitter;
+
+    /// @notice Latest verified index of each binding, keyed by keccak256 of its id.
+    mapping(bytes32 => Record) public records;
+
+    constructor(IPicoVerifier verifier_, bytes32 riscvVkey_, address submitter_) {
+        verifier = verifier_;
+        riscvVkey = riscvVkey_;
+        submitter = submitter_;
+    }
+
+    /// @notice Verifies a proof and records its human index for `bindingId`.
+    /// @dev Reverts with the verifier's error when the proof is invalid or expired.
+    /// @param bindingId keccak256 of the binding id of the request.
+    /// @param programVkey The verification key for the RISC-V program.
+    /// @param publicValues The public values encoded as bytes.
+    /// @param proof The proof of the riscv program execution in the Pico.
+    function recordHumanIndex(
+        bytes32 bindingId,
+        bytes32 programVkey,
+        bytes calldata publicValues,
+        uint256[8] calldata proof
+    ) external {
+        if (msg.sender != submitter) {
+            revert Unauthorized(msg.sender);
+        }
+        if (programVkey != riscvVkey) {
+            revert UnknownProgram(programVkey);
+        }
+        if (publicValues.length < VALID_UNTIL_OFFSET + 8) {
+            revert MalformedPublicValues(publicValues.length);
+        }
+        verifier.verifyPicoProof(programVkey, publicValues, proof);
+
+        uint32 humanIndex = uint32(readLittleEndian(publicValues, COMPUTED_OUTPUT_OFFSET, 4));
+        uint64 validUntil = uint64(readLittleEndian(publicValues, VALID_UNTIL_OFFSET, 8));
+        records[bindingId] = Record(humanIndex, validUntil, uint64(block.timestamp));
+        emit HumanIndexRecorded(bindingId, humanIndex, validUntil);
+    }
+
+    /// @notice Reads a little-endian unsigned integer from the public values.
+    function readLittleEndian(
+        bytes calldata publicValues,
+        uint256 offset,
+        uint256 size
+    ) internal pure returns (uint256 value) {
+        for (uint256 i = 0; i < size; i++) {
+            value |= uint256(uint8(publicValues[offset + i])) << (8 * i);
+        }
+    }
+}
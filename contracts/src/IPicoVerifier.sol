@@ -6,6 +6,7 @@ pragma solidity ^0.8.20;
 /// @notice This contract is the interface for the Pico Verifier.
 interface IPicoVerifier {
     /// @notice Verifies a proof with given public values and riscv verification key.
+    /// @dev Reverts once the committed `valid_until` has passed.
     /// @param riscvVkey The verification key for the RISC-V program.
     /// @param publicValues The public values encoded as bytes.
     /// @param proof The proof of the riscv program execution in the Pico.
@@ -11,6 +11,23 @@ contract PicoVerifier is Verifier, IPicoVerifier {
     /// @notice Thrown when the proof is invalid.
     error InvalidProof();
 
+    /// @notice Thrown when the proven human index is past its validity window.
+    error IndexExpired(uint64 validUntil);
+
+    /// @dev Offset of `valid_until` in the bincode-encoded public values, after the
+    /// inputs, output, weights commitment and `issued_at`.
+    uint256 internal constant VALID_UNTIL_OFFSET = 64;
+
+    /// @notice Reads `valid_until`, a little-endian uint64, from the public values.
+    /// @param publicValues The public values.
+    function validUntil(
+        bytes calldata publicValues
+    ) internal pure returns (uint64 value) {
+        for (uint256 i = 0; i < 8; i++) {
+            value |= uint64(uint8(publicValues[VALID_UNTIL_OFFSET + i])) << (8 * i);
+        }
+    }
+
     /// @notice Hashes the public values to a field elements inside Bn254.
     /// @param publicValues The public values.
     function hashPublicValues(
@@ -28,6 +45,10 @@ contract PicoVerifier is Verifier, IPicoVerifier {
         bytes calldata publicValues,
         uint256[8] calldata proof
     ) external view {
+        uint64 expiry = validUntil(publicValues);
+        if (block.timestamp >= expiry) {
+            revert IndexExpired(expiry);
+        }
         bytes32 publicValuesDigest = hashPublicValues(publicValues);
         uint256[2] memory inputs;
         inputs[0] = uint256(riscvVkey);
@@ -0,0 +1,125 @@
+use criterion::{BenchmarkId, Criterion, black_box, criterion_group, criterion_main};
+use human_index_lib::{
+    HumanIndexPublicInputs, PublicValues, SCALE, Validity, VerificationResults,
+    calculate_human_index, commit_weights,
+};
+
+/// Production weights (0.15 / 0.2 / 0.25 / 0.4)
+fn weights() -> HumanIndexPublicInputs {
+    HumanIndexPublicInputs {
+        w1: 1500,
+        w2: 2000,
+        w3: 2500,
+        w4: 4000,
+        expected_output: 0,
+    }
+}
+
+/// Deterministic spread of verification results covering the whole input range
+fn sample_results(count: usize) -> Vec<VerificationResults> {
+    let mut state: u32 = 0x1234_5678;
+    (0..count)
+        .map(|_| {
+            // xorshift32, good enough to spread inputs without a rand dependency
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            VerificationResults {
+                recaptcha_score: state % (SCALE + 1),
+                sms_verified: (state >> 16) & 1,
+                bio_verified: (state >> 17) & 1,
+            }
+        })
+        .collect()
+}
+
+fn bench_calculate(c: &mut Criterion) {
+    let inputs = weights();
+    let mut group = c.benchmark_group("calculate_human_index");
+
+    let cases = [
+        (
+            "zero_score",
+            VerificationResults {
+                recaptcha_score: 0,
+                sms_verified: 1,
+                bio_verified: 1,
+            },
+        ),
+        (
+            "mid_score",
+            VerificationResults {
+                recaptcha_score: 5000,
+                sms_verified: 1,
+                bio_verified: 0,
+            },
+        ),
+        (
+            "max_score",
+            VerificationResults {
+                recaptcha_score: SCALE,
+                sms_verified: 1,
+                bio_verified: 1,
+            },
+        ),
+    ];
+    for (name, results) in &cases {
+        group.bench_with_input(BenchmarkId::from_parameter(name), results, |b, results| {
+            b.iter(|| calculate_human_index(black_box(results), black_box(&inputs)))
+        });
+    }
+
+    let samples = sample_results(1024);
+    group.bench_function("distribution_1024", |b| {
+        b.iter(|| {
+            samples
+                .iter()
+                .map(|results| calculate_human_index(black_box(results), &inputs))
+                .sum::<u32>()
+        })
+    });
+
+    group.finish();
+}
+
+fn bench_public_values(c: &mut Criterion) {
+    let inputs = weights();
+    let results = VerificationResults {
+        recaptcha_score: 7500,
+        sms_verified: 1,
+        bio_verified: 1,
+    };
+    let public_values = PublicValues {
+        computed_output: calculate_human_index(&results, &inputs),
+        weights_commitment: commit_weights(&inputs),
+        inputs,
+        validity: Validity::clamped(1_700_000_000, u64::MAX),
+    };
+
+    let mut group = c.benchmark_group("public_values");
+
+    let bincode_bytes = bincode::serialize(&public_values).unwrap();
+    group.bench_function("bincode_serialize", |b| {
+        b.iter(|| bincode::serialize(black_box(&public_values)).unwrap())
+    });
+    group.bench_function("bincode_deserialize", |b| {
+        b.iter(|| bincode::deserialize::<PublicValues>(black_box(&bincode_bytes)).unwrap())
+    });
+
+    let json_bytes = serde_json::to_vec(&public_values).unwrap();
+    group.bench_function("json_serialize", |b| {
+        b.iter(|| serde_json::to_vec(black_box(&public_values)).unwrap())
+    });
+    group.bench_function("json_deserialize", |b| {
+        b.iter(|| serde_json::from_slice::<PublicValues>(black_box(&json_bytes)).unwrap())
+    });
+
+    group.bench_function("commit_weights", |b| {
+        b.iter(|| commit_weights(black_box(&public_values.inputs)))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_calculate, bench_public_values);
+criterion_main!(benches);
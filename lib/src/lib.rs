@@ -1,12 +1,69 @@
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
 use std::fs;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
-// Fixed-point scale factor for decimal precision (10,000 = 4 decimal places)
-const SCALE: u32 = 10_000;
+pub mod wide;
+
+/// Fixed-point scale factor for decimal precision (10,000 = 4 decimal places)
+pub const SCALE: u32 = 10_000;
+
+/// Largest human index the calculation can produce
+pub const MAX_HUMAN_INDEX: u32 = 255;
+
+/// Version of what the guest reads and commits; bump when `app/src/main.rs`
+/// changes either, so fixtures recorded from an older guest are reported stale
+pub const GUEST_VERSION: u32 = 2;
+
+/// A guest circuit, by the type of the values it reads from stdin
+///
+/// Each circuit has its own ELF, Groth16 setup and verifier: a proof of one never
+/// verifies under the keys of another.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CircuitId {
+    /// `u32` signals at `SCALE`, this module's types
+    #[default]
+    HumanIndex,
+    /// `u64` signals at `wide::SCALE`, the types in `wide`
+    Wide,
+}
+
+impl CircuitId {
+    pub const ALL: [CircuitId; 2] = [CircuitId::HumanIndex, CircuitId::Wide];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CircuitId::HumanIndex => "human-index",
+            CircuitId::Wide => "wide",
+        }
+    }
+
+    /// Version of what the circuit's guest reads and commits
+    pub fn version(self) -> u32 {
+        match self {
+            CircuitId::HumanIndex => GUEST_VERSION,
+            CircuitId::Wide => wide::GUEST_VERSION,
+        }
+    }
+
+    /// Fixed-point scale of the circuit's scores and weights
+    pub fn scale(self) -> u64 {
+        match self {
+            CircuitId::HumanIndex => SCALE as u64,
+            CircuitId::Wide => wide::SCALE,
+        }
+    }
+}
+
+/// Longest validity window a human index can be proven with: 30 days
+pub const MAX_VALIDITY_SECS: u64 = 30 * 24 * 60 * 60;
 
 /// Public inputs for the human index calculation
 #[repr(C)]
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct HumanIndexPublicInputs {
     pub w1: u32, // Weight 1 in fixed-point (e.g., 0.15 * 10000 = 1500)
     pub w2: u32, // Weight 2 in fixed-point (e.g., 0.2 * 10000 = 2000)
@@ -21,21 +78,475 @@ pub struct HumanIndexPublicInputs {
 pub struct PublicValues {
     pub inputs: HumanIndexPublicInputs,
     pub computed_output: u32,
+    pub weights_commitment: [u8; 32], // See `commit_weights`
+    pub validity: Validity,
+}
+
+impl PublicValues {
+    /// Checks what no honest guest commits: an output above `MAX_HUMAN_INDEX`
+    ///
+    /// The guest runs this before committing, so such values are unprovable; hosts
+    /// run it on decoded values so they never report a truncated index.
+    pub fn check(&self) -> Result<(), PublicValuesError> {
+        check_human_index(self.computed_output)
+    }
+
+    /// Checks that the committed values agree with each other, without the private inputs
+    ///
+    /// `weights_commitment` must commit to the committed weights, and
+    /// `computed_output` must be an index the formula gives for those weights with
+    /// some verification results the service proves: a score in 0..=`SCALE` and
+    /// each check 0 or 1. This re-validates the arithmetic only, not the proof.
+    pub fn check_internal_consistency(&self) -> Result<(), ConsistencyError> {
+        self.check().map_err(ConsistencyError::PublicValues)?;
+        if self.weights_commitment != commit_weights(&self.inputs) {
+            return Err(ConsistencyError::WeightsCommitmentMismatch);
+        }
+        if !is_achievable(&self.inputs, self.computed_output) {
+            let max = calculate_human_index(
+                &VerificationResults {
+                    recaptcha_score: SCALE,
+                    sms_verified: 1,
+                    bio_verified: 1,
+                },
+                &self.inputs,
+            );
+            return Err(ConsistencyError::Unachievable {
+                computed_output: self.computed_output,
+                max,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Whether some in-range verification results give `index` with `inputs`
+///
+/// A zero score always gives 0. Otherwise, for each value of the two checks the
+/// index never decreases as the score grows, so a binary search over the score
+/// finds the lowest score reaching `index`, which gives it exactly if any does.
+fn is_achievable(inputs: &HumanIndexPublicInputs, index: u32) -> bool {
+    if index == 0 {
+        return true;
+    }
+    [(0, 0), (0, 1), (1, 0), (1, 1)]
+        .into_iter()
+        .any(|(sms_verified, bio_verified)| {
+            let index_at = |recaptcha_score| {
+                let results = VerificationResults {
+                    recaptcha_score,
+                    sms_verified,
+                    bio_verified,
+                };
+                calculate_human_index(&results, inputs)
+            };
+            let (mut low, mut high) = (1, SCALE);
+            if index_at(high) < index {
+                return false;
+            }
+            while low < high {
+                let mid = low + (high - low) / 2;
+                if index_at(mid) < index {
+                    low = mid + 1;
+                } else {
+                    high = mid;
+                }
+            }
+            index_at(low) == index
+        })
+}
+
+/// Why committed public values disagree with each other
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsistencyError {
+    PublicValues(PublicValuesError),
+    /// `weights_commitment` is not the commitment of the committed weights
+    WeightsCommitmentMismatch,
+    /// No in-range verification results give `computed_output` with the committed
+    /// weights; `max` is the highest index they can give
+    Unachievable {
+        computed_output: u32,
+        max: u32,
+    },
+}
+
+impl fmt::Display for ConsistencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConsistencyError::PublicValues(e) => e.fmt(f),
+            ConsistencyError::WeightsCommitmentMismatch => {
+                write!(f, "weights commitment does not match the committed weights")
+            }
+            ConsistencyError::Unachievable {
+                computed_output,
+                max,
+            } => write!(
+                f,
+                "computed output {} is not an index the committed weights give (at most {})",
+                computed_output, max
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConsistencyError {}
+
+/// Checks that a committed output is at most `MAX_HUMAN_INDEX`
+pub fn check_human_index(computed_output: u32) -> Result<(), PublicValuesError> {
+    if computed_output > MAX_HUMAN_INDEX {
+        return Err(PublicValuesError::OutputOutOfRange { computed_output });
+    }
+    Ok(())
+}
+
+/// Why committed public values are invalid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PublicValuesError {
+    /// `computed_output` is above `MAX_HUMAN_INDEX`
+    OutputOutOfRange { computed_output: u32 },
+}
+
+impl fmt::Display for PublicValuesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PublicValuesError::OutputOutOfRange { computed_output } => write!(
+                f,
+                "computed output {} is above the maximum human index {}",
+                computed_output, MAX_HUMAN_INDEX
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PublicValuesError {}
+
+/// When a human index was issued and until when it is valid, in unix seconds
+///
+/// Committed in `PublicValues` so a verifier can reject a stale index; the guest
+/// refuses to prove a window that fails `check`.
+#[repr(C)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Validity {
+    pub issued_at: u64,
+    pub valid_until: u64,
+}
+
+impl Validity {
+    /// The window from `issued_at` to `valid_until`, shortened to `MAX_VALIDITY_SECS`
+    pub fn clamped(issued_at: u64, valid_until: u64) -> Self {
+        Self {
+            issued_at,
+            valid_until: valid_until.min(issued_at.saturating_add(MAX_VALIDITY_SECS)),
+        }
+    }
+
+    /// Checks that the window is non-empty and at most `MAX_VALIDITY_SECS` long
+    pub fn check(&self) -> Result<(), ValidityError> {
+        if self.valid_until <= self.issued_at {
+            return Err(ValidityError::Inverted {
+                issued_at: self.issued_at,
+                valid_until: self.valid_until,
+            });
+        }
+        let secs = self.valid_until - self.issued_at;
+        if secs > MAX_VALIDITY_SECS {
+            return Err(ValidityError::TooLong { secs });
+        }
+        Ok(())
+    }
+
+    /// Whether the index may still be used at `now`
+    pub fn is_valid_at(&self, now: u64) -> bool {
+        self.issued_at <= now && now < self.valid_until
+    }
+}
+
+/// Why a validity window cannot be proven
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidityError {
+    /// `valid_until` is not after `issued_at`
+    Inverted { issued_at: u64, valid_until: u64 },
+    /// Longer than `MAX_VALIDITY_SECS`
+    TooLong { secs: u64 },
+}
+
+impl fmt::Display for ValidityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidityError::Inverted {
+                issued_at,
+                valid_until,
+            } => write!(
+                f,
+                "valid_until {} is not after issued_at {}",
+                valid_until, issued_at
+            ),
+            ValidityError::TooLong { secs } => write!(
+                f,
+                "validity window of {}s is longer than the maximum {}s",
+                secs, MAX_VALIDITY_SECS
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ValidityError {}
+
+/// Commits to the weight set used for the calculation
+///
+/// SHA-256 over `w1 || w2 || w3 || w4 || SCALE`, each as a little-endian u32.
+/// A verifier compares this against the hash of a governance-approved weight
+/// set instead of trusting the individual weights.
+pub fn commit_weights(public_inputs: &HumanIndexPublicInputs) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(public_inputs.w1.to_le_bytes());
+    hasher.update(public_inputs.w2.to_le_bytes());
+    hasher.update(public_inputs.w3.to_le_bytes());
+    hasher.update(public_inputs.w4.to_le_bytes());
+    hasher.update(SCALE.to_le_bytes());
+    hasher.finalize().into()
+}
+
+/// A named weight set that requests can refer to instead of sending raw weights
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WeightsProfile {
+    pub id: &'static str,
+    /// `w1` to `w4` in fixed-point
+    pub weights: [u32; 4],
+}
+
+/// Known weights profiles
+///
+/// Entries are only ever added: a new weight set gets a new id, so a profile id
+/// always means the same weights.
+pub const WEIGHTS_PROFILES: [WeightsProfile; 1] = [WeightsProfile {
+    id: "v1",
+    weights: [1500, 2000, 2500, 4000],
+}];
+
+impl WeightsProfile {
+    pub fn from_id(id: &str) -> Option<Self> {
+        WEIGHTS_PROFILES
+            .into_iter()
+            .find(|profile| profile.id == id)
+    }
+
+    /// Comma-separated ids of all profiles, oldest first
+    pub fn ids() -> String {
+        WEIGHTS_PROFILES.map(|profile| profile.id).join(", ")
+    }
+
+    /// Whether `public_inputs` carries exactly this profile's weights
+    pub fn matches(&self, public_inputs: &HumanIndexPublicInputs) -> bool {
+        [
+            public_inputs.w1,
+            public_inputs.w2,
+            public_inputs.w3,
+            public_inputs.w4,
+        ] == self.weights
+    }
+
+    /// Set the weights of `public_inputs` to this profile's
+    pub fn apply(&self, public_inputs: &mut HumanIndexPublicInputs) {
+        [
+            public_inputs.w1,
+            public_inputs.w2,
+            public_inputs.w3,
+            public_inputs.w4,
+        ] = self.weights;
+    }
 }
 
 /// Private inputs (verification results)
-#[derive(Serialize, Deserialize, Debug, Clone)]
+///
+/// Zeroized on drop, so a proven request leaves no copy of them on the heap.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, Zeroize, ZeroizeOnDrop)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct VerificationResults {
     pub recaptcha_score: u32, // In fixed-point (0 to 10000 for 0.0 to 1.0)
     pub sms_verified: u32,    // 0 or 1
     pub bio_verified: u32,    // 0 or 1
 }
 
+/// A guest input, named independently of where it sits in the request JSON
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    Recaptcha,
+    Sms,
+    Bio,
+    W1,
+    W2,
+    W3,
+    W4,
+    ExpectedOutput,
+}
+
+/// Signals in the order the guest reads them from stdin
+///
+/// The only place the order is defined: `ProofInputs` writes and reads stdin by
+/// walking this table, whatever order the request's keys arrived in. The validity
+/// window follows the signals.
+pub const STDIN_ORDER: [Signal; 8] = [
+    Signal::Recaptcha,
+    Signal::Sms,
+    Signal::Bio,
+    Signal::W1,
+    Signal::W2,
+    Signal::W3,
+    Signal::W4,
+    Signal::ExpectedOutput,
+];
+
+impl Signal {
+    /// Key of the signal in a request's `signals` map
+    pub fn name(self) -> &'static str {
+        match self {
+            Signal::Recaptcha => "recaptcha",
+            Signal::Sms => "sms",
+            Signal::Bio => "bio",
+            Signal::W1 => "w1",
+            Signal::W2 => "w2",
+            Signal::W3 => "w3",
+            Signal::W4 => "w4",
+            Signal::ExpectedOutput => "expected_output",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        STDIN_ORDER.into_iter().find(|signal| signal.name() == name)
+    }
+
+    /// Comma-separated names of all signals, in stdin order
+    pub fn names() -> String {
+        STDIN_ORDER.map(Signal::name).join(", ")
+    }
+
+    pub fn get(
+        self,
+        verification_results: &VerificationResults,
+        public_inputs: &HumanIndexPublicInputs,
+    ) -> u32 {
+        match self {
+            Signal::Recaptcha => verification_results.recaptcha_score,
+            Signal::Sms => verification_results.sms_verified,
+            Signal::Bio => verification_results.bio_verified,
+            Signal::W1 => public_inputs.w1,
+            Signal::W2 => public_inputs.w2,
+            Signal::W3 => public_inputs.w3,
+            Signal::W4 => public_inputs.w4,
+            Signal::ExpectedOutput => public_inputs.expected_output,
+        }
+    }
+
+    pub fn set(
+        self,
+        verification_results: &mut VerificationResults,
+        public_inputs: &mut HumanIndexPublicInputs,
+        value: u32,
+    ) {
+        let field = match self {
+            Signal::Recaptcha => &mut verification_results.recaptcha_score,
+            Signal::Sms => &mut verification_results.sms_verified,
+            Signal::Bio => &mut verification_results.bio_verified,
+            Signal::W1 => &mut public_inputs.w1,
+            Signal::W2 => &mut public_inputs.w2,
+            Signal::W3 => &mut public_inputs.w3,
+            Signal::W4 => &mut public_inputs.w4,
+            Signal::ExpectedOutput => &mut public_inputs.expected_output,
+        };
+        *field = value;
+    }
+}
+
+/// Number of `u32` words `ProofInputs::write_to` writes
+///
+/// One per signal, then `issued_at` and `valid_until` as two words each.
+pub const STDIN_WORDS: usize = STDIN_ORDER.len() + 4;
+
+/// Everything the guest reads from stdin: the IO contract between host and guest
+///
+/// Hosts build stdin with `write_to` and the guest parses it with `read_from`;
+/// both walk `STDIN_ORDER`, so a value can only be read back as what it was
+/// written as. A different order would still prove, but over the wrong values.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProofInputs {
+    pub results: VerificationResults,
+    pub inputs: HumanIndexPublicInputs,
+    /// Index the host computed natively; written in place of `inputs.expected_output`
+    pub expected_output: u32,
+    pub validity: Validity,
+}
+
+impl ProofInputs {
+    /// Inputs for proving `results` under `inputs`, expecting the native index
+    pub fn new(
+        results: VerificationResults,
+        inputs: HumanIndexPublicInputs,
+        validity: Validity,
+    ) -> Self {
+        let expected_output = calculate_human_index(&results, &inputs);
+        Self {
+            results,
+            inputs,
+            expected_output,
+            validity,
+        }
+    }
+
+    /// Value written to stdin for `signal`
+    pub fn get(&self, signal: Signal) -> u32 {
+        match signal {
+            Signal::ExpectedOutput => self.expected_output,
+            signal => signal.get(&self.results, &self.inputs),
+        }
+    }
+
+    /// Write every input, in `STDIN_ORDER`, then the validity window
+    ///
+    /// Each `u64` is written as two words, the low one first.
+    pub fn write_to(&self, mut write: impl FnMut(&u32)) {
+        for signal in STDIN_ORDER {
+            write(&self.get(signal));
+        }
+        for value in [self.validity.issued_at, self.validity.valid_until] {
+            write(&(value as u32));
+            write(&((value >> 32) as u32));
+        }
+    }
+
+    /// Read the inputs `write_to` wrote, e.g. with `pico_sdk::io::read_as` in the guest
+    ///
+    /// The expected output is set both on `expected_output` and on
+    /// `inputs.expected_output`, which the guest commits.
+    pub fn read_from(mut read: impl FnMut() -> u32) -> Self {
+        let mut proof_inputs = Self::default();
+        for signal in STDIN_ORDER {
+            let value = read();
+            signal.set(&mut proof_inputs.results, &mut proof_inputs.inputs, value);
+            if signal == Signal::ExpectedOutput {
+                proof_inputs.expected_output = value;
+            }
+        }
+        let mut read_u64 = || read() as u64 | (read() as u64) << 32;
+        proof_inputs.validity.issued_at = read_u64();
+        proof_inputs.validity.valid_until = read_u64();
+        proof_inputs
+    }
+}
+
 /// Calculates the human index using fixed-point arithmetic
 ///
-/// Formula: floor((W1 + W2 * recaptchaScore + W3 * smsVerified + W4 * bioVerified) * 255)
+/// Formula: min(255, floor((W1 + W2 * recaptchaScore + W3 * smsVerified + W4 * bioVerified) * 255))
 ///
 /// All inputs are in fixed-point with SCALE = 10,000
+///
+/// The weights form an affine combination: they are not required to sum to SCALE,
+/// and each may individually exceed it. When the weighted sum is above SCALE
+/// (1.0) the index is clamped to `MAX_HUMAN_INDEX`, so e.g. weights summing to
+/// 1.2 * SCALE give 255 for a fully verified user rather than 306.
+///
+/// The result is exact for every `u32` input: nothing wraps or panics, so the
+/// host and the zkVM guest agree whatever their overflow checks.
 pub fn calculate_human_index(
     verification_results: &VerificationResults,
     public_inputs: &HumanIndexPublicInputs,
@@ -49,22 +560,205 @@ pub fn calculate_human_index(
         return 0;
     }
 
-    // Calculate sum in fixed-point arithmetic
+    // Calculate sum in fixed-point arithmetic, in u64 so products of two u32 are
+    // exact. Sums saturate; a saturated sum is far past the clamp below anyway.
     // sum = W1 + W2 * recaptchaScore + W3 * smsVerified + W4 * bioVerified
-    let mut sum = public_inputs.w1;
+    let mut sum = public_inputs.w1 as u64;
 
     // W2 * recaptchaScore (both in fixed-point, so divide by SCALE)
-    sum += (public_inputs.w2 * recaptcha_score) / SCALE;
+    sum += (public_inputs.w2 as u64 * recaptcha_score as u64) / SCALE as u64;
 
     // W3 * smsVerified (sms_verified is 0 or 1, w3 is in fixed-point)
-    sum += public_inputs.w3 * sms_verified;
+    sum = sum.saturating_add(public_inputs.w3 as u64 * sms_verified as u64);
 
     // W4 * bioVerified (bio_verified is 0 or 1, w4 is in fixed-point)
-    sum += public_inputs.w4 * bio_verified;
+    sum = sum.saturating_add(public_inputs.w4 as u64 * bio_verified as u64);
 
     // Multiply by 255 and divide by SCALE to convert back from fixed-point
-    // floor(sum * 255) where sum is in fixed-point
-    (sum * 255) / SCALE
+    // floor(sum * 255) where sum is in fixed-point, clamped to the index range
+    let index = sum.saturating_mul(MAX_HUMAN_INDEX as u64) / SCALE as u64;
+    index.min(MAX_HUMAN_INDEX as u64) as u32
+}
+
+/// A sample's human index under the old and the new weights
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexShift {
+    pub old: u32,
+    pub new: u32,
+}
+
+impl IndexShift {
+    /// Change of the index, negative when the new weights lower it
+    pub fn delta(&self) -> i32 {
+        self.new as i32 - self.old as i32
+    }
+}
+
+/// Samples whose index moved across a threshold
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ThresholdCrossings {
+    /// Below the threshold with the old weights, at or above it with the new
+    pub up: usize,
+    /// At or above the threshold with the old weights, below it with the new
+    pub down: usize,
+}
+
+/// How a change of weights shifts the human index across a set of samples
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeightDiffReport {
+    /// One entry per sample, in sample order
+    pub shifts: Vec<IndexShift>,
+    /// Mean of the deltas; 0.0 without samples
+    pub mean_shift: f64,
+    /// Largest absolute delta
+    pub max_shift: u32,
+}
+
+impl WeightDiffReport {
+    /// Change of the index per sample, in sample order
+    pub fn deltas(&self) -> Vec<i32> {
+        self.shifts.iter().map(IndexShift::delta).collect()
+    }
+
+    /// Count the samples that pass `threshold` under one set of weights but not
+    /// the other, where passing means an index of at least `threshold`
+    pub fn crossings(&self, threshold: u32) -> ThresholdCrossings {
+        let mut crossings = ThresholdCrossings::default();
+        for shift in &self.shifts {
+            match (shift.old >= threshold, shift.new >= threshold) {
+                (false, true) => crossings.up += 1,
+                (true, false) => crossings.down += 1,
+                _ => {}
+            }
+        }
+        crossings
+    }
+}
+
+/// Compares the human index of each sample under `old` and `new` weights
+///
+/// Both use `calculate_human_index`; the `expected_output` of either is ignored.
+pub fn compare_weights(
+    old: &HumanIndexPublicInputs,
+    new: &HumanIndexPublicInputs,
+    samples: &[VerificationResults],
+) -> WeightDiffReport {
+    let shifts: Vec<IndexShift> = samples
+        .iter()
+        .map(|sample| IndexShift {
+            old: calculate_human_index(sample, old),
+            new: calculate_human_index(sample, new),
+        })
+        .collect();
+
+    let total: i64 = shifts.iter().map(|shift| shift.delta() as i64).sum();
+    let mean_shift = if shifts.is_empty() {
+        0.0
+    } else {
+        total as f64 / shifts.len() as f64
+    };
+    let max_shift = shifts
+        .iter()
+        .map(|shift| shift.delta().unsigned_abs())
+        .max()
+        .unwrap_or(0);
+
+    WeightDiffReport {
+        shifts,
+        mean_shift,
+        max_shift,
+    }
+}
+
+/// Error converting a decimal value to fixed-point
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConvertError {
+    /// NaN or infinite
+    NotFinite,
+    /// Below zero
+    Negative(f64),
+    /// Above the largest value allowed for this input
+    OutOfRange { value: f64, max: f64 },
+    /// Finer than one unit of the scale, e.g. 0.12345 with `SCALE`
+    TooPrecise { value: f64, scale: u32 },
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConvertError::NotFinite => write!(f, "value is not a finite number"),
+            ConvertError::Negative(value) => write!(f, "value {} is negative", value),
+            ConvertError::OutOfRange { value, max } => {
+                write!(f, "value {} is above the maximum {}", value, max)
+            }
+            ConvertError::TooPrecise { value, scale } => {
+                write!(
+                    f,
+                    "value {} is not a whole number of 1/{} units",
+                    value, scale
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConvertError {}
+
+/// Converts a decimal value to fixed-point with the given scale
+///
+/// Rounds to the nearest integer, halves away from zero, so binary floating-point
+/// error cannot lose a unit: 0.57 * 10000 is 5699.999... and converts to 5700,
+/// where truncating with `as u32` would give 5699. Use `SCALE` as the scale for
+/// weights and scores.
+pub fn to_fixed(value: f64, scale: u32) -> Result<u32, ConvertError> {
+    if !value.is_finite() {
+        return Err(ConvertError::NotFinite);
+    }
+    if value < 0.0 {
+        return Err(ConvertError::Negative(value));
+    }
+
+    let fixed = (value * scale as f64).round();
+    if fixed > u32::MAX as f64 {
+        return Err(ConvertError::OutOfRange {
+            value,
+            max: u32::MAX as f64 / scale as f64,
+        });
+    }
+    Ok(fixed as u32)
+}
+
+/// Converts a score in 0.0..=1.0, such as `recaptcha_score`, to fixed-point with `SCALE`
+pub fn score_to_fixed(value: f64) -> Result<u32, ConvertError> {
+    if value > 1.0 {
+        return Err(ConvertError::OutOfRange { value, max: 1.0 });
+    }
+    to_fixed(value, SCALE)
+}
+
+/// Like `to_fixed`, but fails instead of rounding a value finer than one unit
+///
+/// Rounding error from the decimal-to-binary conversion is still absorbed, so 0.57
+/// converts to 5700 with `SCALE`, but 0.12345 fails rather than becoming 1235.
+pub fn to_fixed_exact(value: f64, scale: u32) -> Result<u32, ConvertError> {
+    let fixed = to_fixed(value, scale)?;
+    if (value * scale as f64 - fixed as f64).abs() > 1e-6 {
+        return Err(ConvertError::TooPrecise { value, scale });
+    }
+    Ok(fixed)
+}
+
+/// Like `score_to_fixed`, but fails on more than four decimal places
+pub fn score_to_fixed_exact(value: f64) -> Result<u32, ConvertError> {
+    if value > 1.0 {
+        return Err(ConvertError::OutOfRange { value, max: 1.0 });
+    }
+    to_fixed_exact(value, SCALE)
+}
+
+/// Converts a fixed-point value back to a decimal, e.g. 1500 with `SCALE` to 0.15
+pub fn from_fixed(fixed: u32, scale: u32) -> f64 {
+    fixed as f64 / scale as f64
 }
 
 /// Loads an ELF file from the specified path.
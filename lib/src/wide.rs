@@ -0,0 +1,207 @@
+//! The wide circuit: every signal and weight is a `u64`
+//!
+//! Same formula, stdin order and committed layout as the crate root, but stdin
+//! values are read as `u64` and the fixed-point scale is 10^12 rather than 10^4, so
+//! scores and weights may carry twelve decimal places. Changing the value type
+//! changes the circuit: a guest built with the `wide` feature of `app` reads these
+//! types, and needs its own ELF, Groth16 setup and verifier (`CircuitId::Wide`).
+
+use crate::{PublicValuesError, STDIN_ORDER, Signal, Validity, check_human_index};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// Fixed-point scale factor (10^12 = 12 decimal places)
+pub const SCALE: u64 = 1_000_000_000_000;
+
+/// Version of what the wide guest reads and commits; see `crate::GUEST_VERSION`
+pub const GUEST_VERSION: u32 = 1;
+
+/// Public inputs for the human index calculation, in fixed-point with `SCALE`
+#[repr(C)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct HumanIndexPublicInputs {
+    pub w1: u64,
+    pub w2: u64,
+    pub w3: u64,
+    pub w4: u64,
+    pub expected_output: u64,
+}
+
+/// Private inputs (verification results), zeroized on drop
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, Zeroize, ZeroizeOnDrop)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct VerificationResults {
+    pub recaptcha_score: u64, // In fixed-point (0 to SCALE for 0.0 to 1.0)
+    pub sms_verified: u64,    // 0 or 1
+    pub bio_verified: u64,    // 0 or 1
+}
+
+impl From<&crate::VerificationResults> for VerificationResults {
+    /// The same results, with the score rescaled from `crate::SCALE` to `SCALE`
+    fn from(results: &crate::VerificationResults) -> Self {
+        Self {
+            recaptcha_score: rescale(results.recaptcha_score),
+            sms_verified: results.sms_verified.into(),
+            bio_verified: results.bio_verified.into(),
+        }
+    }
+}
+
+impl From<&crate::HumanIndexPublicInputs> for HumanIndexPublicInputs {
+    /// The same inputs, with the weights rescaled from `crate::SCALE` to `SCALE`
+    fn from(inputs: &crate::HumanIndexPublicInputs) -> Self {
+        Self {
+            w1: rescale(inputs.w1),
+            w2: rescale(inputs.w2),
+            w3: rescale(inputs.w3),
+            w4: rescale(inputs.w4),
+            expected_output: inputs.expected_output.into(),
+        }
+    }
+}
+
+fn rescale(value: u32) -> u64 {
+    value as u64 * (SCALE / crate::SCALE as u64)
+}
+
+/// All public values committed to a wide proof
+#[repr(C)]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PublicValues {
+    pub inputs: HumanIndexPublicInputs,
+    pub computed_output: u32,
+    pub weights_commitment: [u8; 32], // See `commit_weights`
+    pub validity: Validity,
+}
+
+impl PublicValues {
+    /// Checks what no honest guest commits; see `crate::PublicValues::check`
+    pub fn check(&self) -> Result<(), PublicValuesError> {
+        check_human_index(self.computed_output)
+    }
+}
+
+/// Commits to the weight set used for the calculation
+///
+/// SHA-256 over `w1 || w2 || w3 || w4 || SCALE`, each as a little-endian u64, so
+/// it never equals the commitment of the u32 circuit.
+pub fn commit_weights(public_inputs: &HumanIndexPublicInputs) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(public_inputs.w1.to_le_bytes());
+    hasher.update(public_inputs.w2.to_le_bytes());
+    hasher.update(public_inputs.w3.to_le_bytes());
+    hasher.update(public_inputs.w4.to_le_bytes());
+    hasher.update(SCALE.to_le_bytes());
+    hasher.finalize().into()
+}
+
+/// Number of `u64` values `ProofInputs::write_to` writes: one per signal, then
+/// `issued_at` and `valid_until`
+pub const STDIN_VALUES: usize = STDIN_ORDER.len() + 2;
+
+/// Everything the wide guest reads from stdin; see `crate::ProofInputs`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProofInputs {
+    pub results: VerificationResults,
+    pub inputs: HumanIndexPublicInputs,
+    /// Index the host computed natively; written in place of `inputs.expected_output`
+    pub expected_output: u64,
+    pub validity: Validity,
+}
+
+impl ProofInputs {
+    /// Inputs for proving `results` under `inputs`, expecting the native index
+    pub fn new(
+        results: VerificationResults,
+        inputs: HumanIndexPublicInputs,
+        validity: Validity,
+    ) -> Self {
+        let expected_output = calculate_human_index(&results, &inputs).into();
+        Self {
+            results,
+            inputs,
+            expected_output,
+            validity,
+        }
+    }
+
+    /// Value written to stdin for `signal`
+    pub fn get(&self, signal: Signal) -> u64 {
+        match signal {
+            Signal::Recaptcha => self.results.recaptcha_score,
+            Signal::Sms => self.results.sms_verified,
+            Signal::Bio => self.results.bio_verified,
+            Signal::W1 => self.inputs.w1,
+            Signal::W2 => self.inputs.w2,
+            Signal::W3 => self.inputs.w3,
+            Signal::W4 => self.inputs.w4,
+            Signal::ExpectedOutput => self.expected_output,
+        }
+    }
+
+    /// Write every input, in `STDIN_ORDER`, then the validity window
+    pub fn write_to(&self, mut write: impl FnMut(&u64)) {
+        for signal in STDIN_ORDER {
+            write(&self.get(signal));
+        }
+        write(&self.validity.issued_at);
+        write(&self.validity.valid_until);
+    }
+
+    /// Read the inputs `write_to` wrote, e.g. with `pico_sdk::io::read_as` in the guest
+    ///
+    /// The expected output is set both on `expected_output` and on
+    /// `inputs.expected_output`, which the guest commits.
+    pub fn read_from(mut read: impl FnMut() -> u64) -> Self {
+        let mut proof_inputs = Self::default();
+        for signal in STDIN_ORDER {
+            let value = read();
+            let field = match signal {
+                Signal::Recaptcha => &mut proof_inputs.results.recaptcha_score,
+                Signal::Sms => &mut proof_inputs.results.sms_verified,
+                Signal::Bio => &mut proof_inputs.results.bio_verified,
+                Signal::W1 => &mut proof_inputs.inputs.w1,
+                Signal::W2 => &mut proof_inputs.inputs.w2,
+                Signal::W3 => &mut proof_inputs.inputs.w3,
+                Signal::W4 => &mut proof_inputs.inputs.w4,
+                Signal::ExpectedOutput => {
+                    proof_inputs.expected_output = value;
+                    &mut proof_inputs.inputs.expected_output
+                }
+            };
+            *field = value;
+        }
+        proof_inputs.validity.issued_at = read();
+        proof_inputs.validity.valid_until = read();
+        proof_inputs
+    }
+}
+
+/// Calculates the human index; `crate::calculate_human_index` at `SCALE`
+///
+/// Products of two `u64` are exact in `u128`, and sums saturate far past the clamp
+/// to `MAX_HUMAN_INDEX`, so the result is exact for every `u64` input.
+pub fn calculate_human_index(
+    verification_results: &VerificationResults,
+    public_inputs: &HumanIndexPublicInputs,
+) -> u32 {
+    let recaptcha_score = verification_results.recaptcha_score as u128;
+    let sms_verified = verification_results.sms_verified as u128;
+    let bio_verified = verification_results.bio_verified as u128;
+    let scale = SCALE as u128;
+
+    if recaptcha_score == 0 {
+        return 0;
+    }
+
+    // sum = W1 + W2 * recaptchaScore + W3 * smsVerified + W4 * bioVerified
+    let mut sum = public_inputs.w1 as u128;
+    sum += public_inputs.w2 as u128 * recaptcha_score / scale;
+    sum = sum.saturating_add(public_inputs.w3 as u128 * sms_verified);
+    sum = sum.saturating_add(public_inputs.w4 as u128 * bio_verified);
+
+    let index = sum.saturating_mul(crate::MAX_HUMAN_INDEX as u128) / scale;
+    index.min(crate::MAX_HUMAN_INDEX as u128) as u32
+}
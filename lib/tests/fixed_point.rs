@@ -0,0 +1,49 @@
+// Decimal to fixed-point conversion at values binary floating point cannot represent:
+// 0.07 and 0.29 scale to a hair off their unit, and the conversion must round them to
+// it where truncating could lose it, and 1.0 is the largest score accepted.
+
+use human_index_lib::{ConvertError, SCALE, score_to_fixed, to_fixed};
+
+/// (decimal, fixed-point with `SCALE`)
+const EDGES: [(f64, u32); 3] = [(0.07, 700), (0.29, 2900), (1.0, 10000)];
+
+#[test]
+fn edge_values_convert_exactly() {
+    for (value, fixed) in EDGES {
+        assert_eq!(to_fixed(value, SCALE), Ok(fixed), "{}", value);
+        assert_eq!(score_to_fixed(value), Ok(fixed), "{}", value);
+    }
+}
+
+#[test]
+fn truncation_would_lose_a_unit() {
+    // Why the conversion rounds: 0.29 * 100 is 28.999999999999996 and 0.57 * 10000 is
+    // 5699.999999999999
+    assert_eq!((0.29 * 100.0) as u32, 28);
+    assert_eq!(to_fixed(0.29, 100), Ok(29));
+    assert_eq!((0.57 * SCALE as f64) as u32, 5699);
+    assert_eq!(to_fixed(0.57, SCALE), Ok(5700));
+}
+
+#[test]
+fn rounding_is_to_nearest() {
+    assert_eq!(to_fixed(0.07004, SCALE), Ok(700));
+    assert_eq!(to_fixed(0.06996, SCALE), Ok(700));
+    assert_eq!(to_fixed(0.28996, SCALE), Ok(2900));
+}
+
+#[test]
+fn scores_stop_at_one() {
+    assert_eq!(score_to_fixed(1.0), Ok(SCALE));
+    assert_eq!(
+        score_to_fixed(1.0001),
+        Err(ConvertError::OutOfRange {
+            value: 1.0001,
+            max: 1.0
+        })
+    );
+    assert_eq!(score_to_fixed(-0.07), Err(ConvertError::Negative(-0.07)));
+    assert_eq!(to_fixed(f64::NAN, SCALE), Err(ConvertError::NotFinite));
+    // Weights are not scores, and may exceed 1.0
+    assert_eq!(to_fixed(1.29, SCALE), Ok(12900));
+}
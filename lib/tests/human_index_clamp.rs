@@ -0,0 +1,77 @@
+// `calculate_human_index` clamps to MAX_HUMAN_INDEX once the weighted sum reaches
+// SCALE. The clamp is pinned at weights summing just below SCALE, at SCALE and at
+// 1.2 * SCALE, against the index the formula gives without it.
+
+use human_index_lib::{
+    HumanIndexPublicInputs, MAX_HUMAN_INDEX, SCALE, VerificationResults, calculate_human_index,
+};
+
+/// The formula in exact arithmetic, without the clamp
+fn unclamped_index(results: &VerificationResults, inputs: &HumanIndexPublicInputs) -> u32 {
+    if results.recaptcha_score == 0 {
+        return 0;
+    }
+    let sum = inputs.w1 as u64
+        + inputs.w2 as u64 * results.recaptcha_score as u64 / SCALE as u64
+        + inputs.w3 as u64 * results.sms_verified as u64
+        + inputs.w4 as u64 * results.bio_verified as u64;
+    (sum * MAX_HUMAN_INDEX as u64 / SCALE as u64) as u32
+}
+
+/// A fully verified user under weights summing to `sum`, split 15/20/25/40
+fn fully_verified(sum: u32) -> (VerificationResults, HumanIndexPublicInputs) {
+    let results = VerificationResults {
+        recaptcha_score: SCALE,
+        sms_verified: 1,
+        bio_verified: 1,
+    };
+    // 15% / 20% / 25% / the rest, so the parts add up to `sum` exactly
+    let (w1, w2, w3) = (sum * 15 / 100, sum * 20 / 100, sum * 25 / 100);
+    let inputs = HumanIndexPublicInputs {
+        w1,
+        w2,
+        w3,
+        w4: sum - w1 - w2 - w3,
+        expected_output: 0,
+    };
+    (results, inputs)
+}
+
+#[test]
+fn clamp_boundaries() {
+    // (weight sum, index, index without the clamp)
+    let cases = [
+        (SCALE - 40, 253, 253),
+        (SCALE - 1, 254, 254),
+        (SCALE, MAX_HUMAN_INDEX, MAX_HUMAN_INDEX),
+        (SCALE + 1, MAX_HUMAN_INDEX, MAX_HUMAN_INDEX),
+        (SCALE + 40, MAX_HUMAN_INDEX, 256),
+        (SCALE * 12 / 10, MAX_HUMAN_INDEX, 306),
+    ];
+    for (sum, index, unclamped) in cases {
+        let (results, inputs) = fully_verified(sum);
+        assert_eq!(
+            calculate_human_index(&results, &inputs),
+            index,
+            "weights summing to {}",
+            sum
+        );
+        assert_eq!(
+            unclamped_index(&results, &inputs),
+            unclamped,
+            "weights summing to {}",
+            sum
+        );
+    }
+}
+
+#[test]
+fn partial_verification_is_not_clamped() {
+    // Only the reCAPTCHA term, at 1.2 * SCALE: 0.18 + 0.24 * 0.5 = 0.3 of SCALE
+    let (mut results, inputs) = fully_verified(SCALE * 12 / 10);
+    results.recaptcha_score = SCALE / 2;
+    results.sms_verified = 0;
+    results.bio_verified = 0;
+    assert_eq!(calculate_human_index(&results, &inputs), 76);
+    assert_eq!(unclamped_index(&results, &inputs), 76);
+}
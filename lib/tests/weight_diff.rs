@@ -0,0 +1,87 @@
+// Comparing two weight configurations over a small sample population: per-sample
+// deltas, mean and max shift, and samples crossing an acceptance threshold.
+
+use human_index_lib::{
+    HumanIndexPublicInputs, IndexShift, ThresholdCrossings, VerificationResults, compare_weights,
+};
+
+fn weights(w1: u32, w2: u32, w3: u32, w4: u32) -> HumanIndexPublicInputs {
+    HumanIndexPublicInputs {
+        w1,
+        w2,
+        w3,
+        w4,
+        expected_output: 0,
+    }
+}
+
+fn sample(recaptcha_score: u32, sms_verified: u32, bio_verified: u32) -> VerificationResults {
+    VerificationResults {
+        recaptcha_score,
+        sms_verified,
+        bio_verified,
+    }
+}
+
+fn population() -> Vec<VerificationResults> {
+    vec![
+        sample(10000, 1, 0),
+        sample(5000, 0, 1),
+        sample(0, 1, 1),
+        sample(10000, 1, 1),
+        sample(8000, 1, 0),
+    ]
+}
+
+#[test]
+fn reports_deltas_and_shifts() {
+    let old = weights(1500, 2000, 2500, 4000);
+    // Favour SMS over biometrics
+    let new = weights(1000, 3000, 4000, 2000);
+
+    let report = compare_weights(&old, &new, &population());
+    assert_eq!(
+        report.shifts,
+        [(153, 204), (165, 114), (0, 0), (255, 255), (142, 188)]
+            .map(|(old, new)| IndexShift { old, new })
+    );
+    assert_eq!(report.deltas(), [51, -51, 0, 0, 46]);
+    assert!(
+        (report.mean_shift - 9.2).abs() < 1e-9,
+        "{}",
+        report.mean_shift
+    );
+    assert_eq!(report.max_shift, 51);
+}
+
+#[test]
+fn counts_threshold_crossings_both_ways() {
+    let old = weights(1500, 2000, 2500, 4000);
+    let new = weights(1000, 3000, 4000, 2000);
+    let report = compare_weights(&old, &new, &population());
+
+    assert_eq!(report.crossings(160), ThresholdCrossings { up: 2, down: 1 });
+    // An index equal to the threshold passes it
+    assert_eq!(report.crossings(204), ThresholdCrossings { up: 1, down: 0 });
+    assert_eq!(report.crossings(100), ThresholdCrossings::default());
+    // Unchanged at the maximum, so nothing crosses 255
+    assert_eq!(report.crossings(255), ThresholdCrossings::default());
+}
+
+#[test]
+fn identical_weights_do_not_shift() {
+    let weights = weights(1500, 2000, 2500, 4000);
+    let report = compare_weights(&weights, &weights, &population());
+    assert!(report.deltas().iter().all(|&delta| delta == 0));
+    assert_eq!(report.mean_shift, 0.0);
+    assert_eq!(report.max_shift, 0);
+    assert_eq!(report.crossings(150), ThresholdCrossings::default());
+}
+
+#[test]
+fn no_samples_give_an_empty_report() {
+    let report = compare_weights(&weights(1500, 2000, 2500, 4000), &weights(0, 0, 0, 0), &[]);
+    assert!(report.shifts.is_empty());
+    assert_eq!(report.mean_shift, 0.0);
+    assert_eq!(report.max_shift, 0);
+}
@@ -0,0 +1,64 @@
+// `commit_weights` as a verifier relies on it: each weight, and the order of the
+// weights, changes the commitment, and the approved weight set commits to a
+// pinned SHA-256 that contracts and off-chain verifiers compare against.
+
+use human_index_lib::{HumanIndexPublicInputs, commit_weights};
+
+/// sha256(1500 || 2000 || 2500 || 4000 || 10000), little-endian u32 each
+const APPROVED_COMMITMENT: &str =
+    "bc1bcc071585bc39aa2c15ce83b3db02fa699d8a853b11c99cc8fd8a7bde54ac";
+
+fn inputs(weights: [u32; 4]) -> HumanIndexPublicInputs {
+    let [w1, w2, w3, w4] = weights;
+    HumanIndexPublicInputs {
+        w1,
+        w2,
+        w3,
+        w4,
+        expected_output: 0,
+    }
+}
+
+#[test]
+fn approved_weight_set_commitment_is_pinned() {
+    assert_eq!(
+        hex::encode(commit_weights(&inputs([1500, 2000, 2500, 4000]))),
+        APPROVED_COMMITMENT
+    );
+}
+
+#[test]
+fn different_weight_sets_commit_differently() {
+    let v1 = [1500, 2000, 2500, 4000];
+    let mut sets = vec![v1];
+    // One weight off by one, in each position
+    for position in 0..4 {
+        let mut weights = v1;
+        weights[position] += 1;
+        sets.push(weights);
+    }
+    // The same weights in another order, and weight moved between signals
+    sets.push([4000, 2500, 2000, 1500]);
+    sets.push([2000, 1500, 2500, 4000]);
+    sets.push([1500, 2000, 2000, 4500]);
+
+    let commitments: Vec<_> = sets
+        .iter()
+        .map(|set| commit_weights(&inputs(*set)))
+        .collect();
+    for (i, a) in commitments.iter().enumerate() {
+        for (j, b) in commitments.iter().enumerate().skip(i + 1) {
+            assert_ne!(a, b, "{:?} and {:?} share a commitment", sets[i], sets[j]);
+        }
+    }
+}
+
+#[test]
+fn expected_output_is_not_committed() {
+    let mut with_output = inputs([1500, 2000, 2500, 4000]);
+    with_output.expected_output = 73;
+    assert_eq!(
+        commit_weights(&with_output),
+        commit_weights(&inputs([1500, 2000, 2500, 4000]))
+    );
+}
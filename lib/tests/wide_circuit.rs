@@ -0,0 +1,216 @@
+// The wide circuit reads `u64` signals at a 10^12 scale: weights and scores the
+// `u32` circuit cannot even represent give the exact index, and inputs it can
+// represent give the same index once rescaled.
+
+use human_index_lib::wide::{
+    HumanIndexPublicInputs, ProofInputs, PublicValues, SCALE, STDIN_VALUES, VerificationResults,
+    calculate_human_index, commit_weights,
+};
+use human_index_lib::{CircuitId, MAX_HUMAN_INDEX, Validity};
+use std::collections::VecDeque;
+
+const VALIDITY: Validity = Validity {
+    issued_at: 1_790_000_000,
+    valid_until: 1_790_086_400,
+};
+
+/// Production weights (0.15 / 0.2 / 0.25 / 0.4) at `SCALE`
+fn weights() -> HumanIndexPublicInputs {
+    HumanIndexPublicInputs {
+        w1: 150_000_000_000,
+        w2: 200_000_000_000,
+        w3: 250_000_000_000,
+        w4: 400_000_000_000,
+        expected_output: 0,
+    }
+}
+
+fn results(recaptcha_score: u64, sms_verified: u64, bio_verified: u64) -> VerificationResults {
+    VerificationResults {
+        recaptcha_score,
+        sms_verified,
+        bio_verified,
+    }
+}
+
+/// The index in exact arithmetic, as `guest_arithmetic` computes it for `u32`
+///
+/// A sum past `u128::MAX` is far past the clamp, so it gives `MAX_HUMAN_INDEX`.
+fn exact_index(results: &VerificationResults, inputs: &HumanIndexPublicInputs) -> u32 {
+    if results.recaptcha_score == 0 {
+        return 0;
+    }
+    let terms = [
+        inputs.w1 as u128,
+        inputs.w2 as u128 * results.recaptcha_score as u128 / SCALE as u128,
+        inputs.w3 as u128 * results.sms_verified as u128,
+        inputs.w4 as u128 * results.bio_verified as u128,
+    ];
+    terms
+        .into_iter()
+        .try_fold(0u128, u128::checked_add)
+        .and_then(|sum| sum.checked_mul(MAX_HUMAN_INDEX as u128))
+        .map_or(MAX_HUMAN_INDEX, |scaled| {
+            (scaled / SCALE as u128).min(MAX_HUMAN_INDEX as u128) as u32
+        })
+}
+
+/// Run `app/src/main.rs`, built with the `wide` feature, against the host's stdin
+fn guest_main(host_inputs: &ProofInputs) -> PublicValues {
+    let mut stdin = VecDeque::new();
+    host_inputs.write_to(|value| stdin.push_back(*value));
+    assert_eq!(stdin.len(), STDIN_VALUES);
+    let read_as = || {
+        stdin
+            .pop_front()
+            .expect("guest reads only what the host wrote")
+    };
+
+    let ProofInputs {
+        results: verification_results,
+        inputs: public_inputs,
+        validity,
+        ..
+    } = ProofInputs::read_from(read_as);
+    validity.check().unwrap();
+    let public_values = PublicValues {
+        weights_commitment: commit_weights(&public_inputs),
+        computed_output: calculate_human_index(&verification_results, &public_inputs),
+        inputs: public_inputs,
+        validity,
+    };
+    public_values.check().unwrap();
+    bincode::deserialize(&bincode::serialize(&public_values).unwrap()).unwrap()
+}
+
+#[test]
+fn twelve_decimal_scores_give_the_exact_index() {
+    let inputs = weights();
+    // Neither the weights nor the score fit in a u32
+    assert!(u32::try_from(inputs.w1).is_err());
+    let score = 123_456_789_012;
+    assert!(u32::try_from(score).is_err());
+
+    // (0.15 + 0.2 * 0.123456789012 + 0.25) * 255 = 108.296...
+    let results = results(score, 1, 0);
+    assert_eq!(calculate_human_index(&results, &inputs), 108);
+    assert_eq!(exact_index(&results, &inputs), 108);
+
+    // Fully verified, and one unit of score short of 1.0
+    assert_eq!(
+        calculate_human_index(&self::results(SCALE, 1, 1), &inputs),
+        255
+    );
+    assert_eq!(
+        calculate_human_index(&self::results(SCALE - 1, 1, 1), &inputs),
+        254
+    );
+}
+
+#[test]
+fn large_values_neither_overflow_nor_wrap() {
+    let edges = [
+        0,
+        1,
+        SCALE - 1,
+        SCALE,
+        u32::MAX as u64 + 1,
+        u64::MAX / MAX_HUMAN_INDEX as u64,
+        u64::MAX - 1,
+        u64::MAX,
+    ];
+    for &w in &edges {
+        for &score in &edges {
+            for flag in [0, 1, u64::MAX] {
+                let inputs = HumanIndexPublicInputs {
+                    w1: w,
+                    w2: w,
+                    w3: w,
+                    w4: w,
+                    expected_output: 0,
+                };
+                let results = results(score, flag, flag);
+                let index = calculate_human_index(&results, &inputs);
+                assert_eq!(
+                    index,
+                    exact_index(&results, &inputs),
+                    "w {} score {} flag {}",
+                    w,
+                    score,
+                    flag
+                );
+                assert!(index <= MAX_HUMAN_INDEX);
+            }
+        }
+    }
+}
+
+#[test]
+fn rescaled_u32_inputs_give_the_same_index() {
+    let narrow_inputs = human_index_lib::HumanIndexPublicInputs {
+        w1: 1500,
+        w2: 2000,
+        w3: 2500,
+        w4: 4000,
+        expected_output: 0,
+    };
+    let inputs = HumanIndexPublicInputs::from(&narrow_inputs);
+    assert_eq!(inputs, weights());
+
+    // w2 * score is a whole number of units for every score that is a multiple of 5
+    for recaptcha_score in (0..=human_index_lib::SCALE).step_by(5) {
+        for (sms_verified, bio_verified) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            let narrow = human_index_lib::VerificationResults {
+                recaptcha_score,
+                sms_verified,
+                bio_verified,
+            };
+            assert_eq!(
+                calculate_human_index(&VerificationResults::from(&narrow), &inputs),
+                human_index_lib::calculate_human_index(&narrow, &narrow_inputs),
+                "{:?}",
+                narrow
+            );
+        }
+    }
+}
+
+#[test]
+fn guest_commits_what_the_host_expects() {
+    let host_inputs = ProofInputs::new(results(987_654_321_098, 1, 0), weights(), VALIDITY);
+    assert_eq!(host_inputs.expected_output, 152);
+
+    let public_values = guest_main(&host_inputs);
+    assert_eq!(
+        public_values.computed_output as u64,
+        host_inputs.expected_output
+    );
+    assert_eq!(
+        public_values.inputs.expected_output,
+        host_inputs.expected_output
+    );
+    assert_eq!(public_values.inputs.w1, weights().w1);
+    assert_eq!(public_values.validity, VALIDITY);
+    assert_eq!(public_values.weights_commitment, commit_weights(&weights()));
+}
+
+#[test]
+fn wide_circuit_is_distinct() {
+    assert_ne!(CircuitId::Wide.name(), CircuitId::HumanIndex.name());
+    assert_eq!(CircuitId::default(), CircuitId::HumanIndex);
+    assert_eq!(CircuitId::Wide.scale(), SCALE);
+    assert_eq!(serde_json::to_string(&CircuitId::Wide).unwrap(), "\"wide\"");
+
+    // The same weights commit differently in each circuit
+    let narrow_inputs = human_index_lib::HumanIndexPublicInputs {
+        w1: 1500,
+        w2: 2000,
+        w3: 2500,
+        w4: 4000,
+        expected_output: 0,
+    };
+    assert_ne!(
+        commit_weights(&HumanIndexPublicInputs::from(&narrow_inputs)),
+        human_index_lib::commit_weights(&narrow_inputs)
+    );
+}
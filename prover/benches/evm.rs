@@ -0,0 +1,68 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use human_index_lib::{commit_weights, HumanIndexPublicInputs, PublicValues, Validity};
+use prover::evm::{build_verify_calldata, decode_calldata, VerifyCall};
+use prover::prover::encode_proof_data;
+use prover::types::ProofData;
+
+/// Production weights (0.15 / 0.2 / 0.25 / 0.4)
+fn weights() -> HumanIndexPublicInputs {
+    HumanIndexPublicInputs {
+        w1: 1500,
+        w2: 2000,
+        w3: 2500,
+        w4: 4000,
+        expected_output: 0,
+    }
+}
+
+/// A `ProofData` shaped like a real one, with `public_values_len` bytes of public values
+fn proof_data(public_values_len: usize) -> ProofData {
+    let inputs = weights();
+    let proof_words: Vec<String> = (0u8..8)
+        .map(|i| format!("0x{}", hex::encode([i.wrapping_mul(31) | 1; 32])))
+        .collect();
+
+    let mut public_values = bincode::serialize(&PublicValues {
+        inputs: inputs.clone(),
+        computed_output: 173,
+        weights_commitment: commit_weights(&inputs),
+        validity: Validity::clamped(1_700_000_000, u64::MAX),
+    })
+    .expect("public values serialize");
+    public_values.resize(public_values_len.max(public_values.len()), 0);
+
+    encode_proof_data(
+        &proof_words,
+        &format!("0x{}", hex::encode(&public_values)),
+        &format!("0x{}", hex::encode([0x42; 32])),
+        173,
+        &commit_weights(&inputs),
+    )
+    .expect("proof data encodes")
+}
+
+fn bench_abi(c: &mut Criterion) {
+    let mut group = c.benchmark_group("verify_calldata");
+
+    // Current public values, and larger payloads for future batch guests
+    for len in [0usize, 1024, 16 * 1024] {
+        let data = proof_data(len);
+        let call = VerifyCall::from_proof_data(&data).expect("valid proof data");
+        let calldata = call.encode();
+
+        group.bench_with_input(BenchmarkId::new("build", len), &data, |b, data| {
+            b.iter(|| build_verify_calldata(black_box(data)))
+        });
+        group.bench_with_input(BenchmarkId::new("encode", len), &call, |b, call| {
+            b.iter(|| black_box(call).encode())
+        });
+        group.bench_with_input(BenchmarkId::new("decode", len), &calldata, |b, calldata| {
+            b.iter(|| decode_calldata(black_box(calldata)))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_abi);
+criterion_main!(benches);
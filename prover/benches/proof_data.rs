@@ -0,0 +1,89 @@
+// Compares the peak heap allocation of assembling `ProofData` from an inputs.json
+// before and after streaming the proof into base64.
+//
+// Run with `cargo bench --bench proof_data`.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use prover::prover::{read_proof_artifacts, ArtifactEncoding, ProofJsonLayout};
+use serde_json::{json, Value};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// System allocator that tracks live and peak bytes
+struct PeakAlloc;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for PeakAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let live = LIVE.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(live, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static ALLOC: PeakAlloc = PeakAlloc;
+
+/// Bytes allocated at the peak of `f`, above what was live when it started
+fn peak_allocation<T>(f: impl FnOnce() -> T) -> usize {
+    let start = LIVE.load(Ordering::Relaxed);
+    PEAK.store(start, Ordering::Relaxed);
+    drop(f());
+    PEAK.load(Ordering::Relaxed) - start
+}
+
+/// The encoding before streaming: whole file as a string, a `Value`, then a JSON string
+fn reference_encoding(inputs_path: &Path) -> String {
+    let content = std::fs::read_to_string(inputs_path).unwrap();
+    let inputs: Value = serde_json::from_str(&content).unwrap();
+    let proof_json = serde_json::to_string(inputs["proof"].as_array().unwrap()).unwrap();
+    STANDARD.encode(proof_json.as_bytes())
+}
+
+fn main() {
+    let dir = std::env::temp_dir().join(format!("prover-bench-proof-data-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("inputs.json");
+
+    println!(
+        "{:>10} {:>12} {:>12} {:>12}",
+        "words", "file", "before", "after"
+    );
+    // A Groth16 proof has 8 words; the larger sizes stand in for bigger artifacts
+    for words in [8usize, 10_000, 100_000, 1_000_000] {
+        let proof: Vec<String> = (0..words)
+            .map(|i| format!("0x{}", hex::encode([(i % 251) as u8; 32])))
+            .collect();
+        let inputs = json!({ "proof": proof, "publicValues": "0x00", "riscvVKey": "0x00" });
+        std::fs::write(&path, serde_json::to_vec(&inputs).unwrap()).unwrap();
+        drop((proof, inputs));
+
+        let before = peak_allocation(|| reference_encoding(&path));
+        let after = peak_allocation(|| {
+            read_proof_artifacts(
+                &path,
+                u64::MAX,
+                ProofJsonLayout::PicoV1,
+                ArtifactEncoding::Sequential,
+                0,
+                &[0; 32],
+            )
+            .unwrap()
+        });
+        let file = std::fs::metadata(&path).unwrap().len();
+        println!("{:>10} {:>12} {:>12} {:>12}", words, file, before, after);
+    }
+
+    std::fs::remove_dir_all(&dir).ok();
+}
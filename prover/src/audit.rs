@@ -0,0 +1,514 @@
+use crate::canonical::canonical_bytes;
+use crate::error::ServiceError;
+use crate::types::{ProofStatus, ProverRequest, ProverResponse};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{NaiveDate, Utc};
+use google_cloud_googleapis::pubsub::v1::PubsubMessage;
+use google_cloud_pubsub::client::Client;
+use google_cloud_pubsub::publisher::Publisher;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use thiserror::Error;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::error;
+
+/// `prev_hash` of the first record in a chain
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Where audit records are written
+#[derive(Debug, Clone)]
+pub enum AuditSink {
+    /// Directory holding the daily `audit-YYYY-MM-DD.jsonl` files
+    Dir(PathBuf),
+    /// Full path of the Pub/Sub topic receiving one message per record
+    Topic(String),
+}
+
+/// Configuration for the audit log, loaded from environment variables
+#[derive(Clone)]
+pub struct AuditConfig {
+    pub sink: AuditSink,
+
+    /// Secret key for hashing private inputs
+    pub input_hash_key: Vec<u8>,
+}
+
+impl AuditConfig {
+    /// Load audit settings; returns `None` when neither `AUDIT_LOG_DIR` nor
+    /// `AUDIT_TOPIC` is set
+    pub fn from_env() -> Result<Option<Self>, ServiceError> {
+        let sink = match (env::var("AUDIT_LOG_DIR"), env::var("AUDIT_TOPIC")) {
+            (Ok(_), Ok(_)) => {
+                return Err(ServiceError::Config(
+                    "Set only one of AUDIT_LOG_DIR and AUDIT_TOPIC".to_string(),
+                ))
+            }
+            (Ok(dir), Err(_)) => AuditSink::Dir(PathBuf::from(dir)),
+            (Err(_), Ok(topic)) => {
+                let project_id = env::var("GCP_PROJECT_ID")
+                    .map_err(|_| ServiceError::Config("GCP_PROJECT_ID not set".to_string()))?;
+                AuditSink::Topic(format!("projects/{}/topics/{}", project_id, topic))
+            }
+            (Err(_), Err(_)) => return Ok(None),
+        };
+
+        // Private inputs have very few possible values, so a plain hash could be
+        // reversed by enumeration; a keyed hash cannot without the key
+        let input_hash_key = env::var("AUDIT_INPUT_HASH_KEY")
+            .map_err(|_| ServiceError::Config("AUDIT_INPUT_HASH_KEY not set".to_string()))?
+            .into_bytes();
+
+        Ok(Some(Self {
+            sink,
+            input_hash_key,
+        }))
+    }
+}
+
+/// Kind of audit event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditEvent {
+    /// A request was parsed and accepted for proving
+    Accepted,
+    /// Proving finished, successfully or not
+    Completed,
+    /// A message could not be parsed into a request
+    Rejected,
+}
+
+/// Content of one audit record, excluding the chain fields
+///
+/// Never holds private inputs in clear: `input_hash` is an HMAC over them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: String,
+    pub event: AuditEvent,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_request_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<ProofStatus>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub human_index: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vkey_hash: Option<String>,
+    pub worker_id: String,
+}
+
+/// One line of the audit log
+///
+/// `hash = sha256(prev_hash || json(entry))`, so editing, removing or reordering
+/// any line breaks every hash after it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditRecord {
+    #[serde(flatten)]
+    pub entry: AuditEntry,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl AuditRecord {
+    fn new(entry: AuditEntry, prev_hash: String) -> Result<Self, serde_json::Error> {
+        let hash = chain_hash(&prev_hash, &entry)?;
+        Ok(Self {
+            entry,
+            prev_hash,
+            hash,
+        })
+    }
+}
+
+fn chain_hash(prev_hash: &str, entry: &AuditEntry) -> Result<String, serde_json::Error> {
+    let mut hasher = Sha256::new();
+    hasher.update(prev_hash.as_bytes());
+    hasher.update(serde_json::to_vec(entry)?);
+    Ok(hex::encode(hasher.finalize()))
+}
+
+struct AuditState {
+    writer: AuditWriter,
+    prev_hash: String,
+}
+
+enum AuditWriter {
+    File {
+        dir: PathBuf,
+        date: NaiveDate,
+        file: File,
+    },
+    /// Records are handed to a task that publishes them in order
+    Topic {
+        records: Option<mpsc::UnboundedSender<Vec<u8>>>,
+    },
+}
+
+/// Append-only, hash-chained audit log, rotated daily when written to files
+pub struct AuditLog {
+    worker_id: String,
+    input_hash_key: Vec<u8>,
+    state: Mutex<AuditState>,
+    publish_task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl AuditLog {
+    /// Open the audit log
+    ///
+    /// A directory sink continues the chain from the newest existing file. A topic
+    /// sink starts a new chain, published with `worker_id` as the ordering key.
+    pub fn open(
+        config: &AuditConfig,
+        worker_id: &str,
+        client: &Client,
+    ) -> Result<Self, ServiceError> {
+        let topic_path = match &config.sink {
+            AuditSink::Dir(dir) => return Self::open_dir(dir, &config.input_hash_key, worker_id),
+            AuditSink::Topic(topic_path) => topic_path,
+        };
+
+        let publisher = client.topic(topic_path).new_publisher(None);
+        let (records, receiver) = mpsc::unbounded_channel();
+        let task = tokio::spawn(publish_records(publisher, receiver, worker_id.to_string()));
+        let writer = AuditWriter::Topic {
+            records: Some(records),
+        };
+        Ok(Self::with_writer(
+            writer,
+            GENESIS_HASH.to_string(),
+            Some(task),
+            &config.input_hash_key,
+            worker_id,
+        ))
+    }
+
+    /// Open the audit log in `dir`, continuing the chain from its newest file
+    pub fn open_dir(
+        dir: &Path,
+        input_hash_key: &[u8],
+        worker_id: &str,
+    ) -> Result<Self, ServiceError> {
+        fs::create_dir_all(dir)?;
+
+        let prev_hash = match log_files(dir)?.last() {
+            Some(latest) => last_hash(latest)?.unwrap_or_else(|| GENESIS_HASH.to_string()),
+            None => GENESIS_HASH.to_string(),
+        };
+
+        let date = Utc::now().date_naive();
+        let file = open_for_date(dir, date)?;
+        let writer = AuditWriter::File {
+            dir: dir.to_path_buf(),
+            date,
+            file,
+        };
+        Ok(Self::with_writer(
+            writer,
+            prev_hash,
+            None,
+            input_hash_key,
+            worker_id,
+        ))
+    }
+
+    fn with_writer(
+        writer: AuditWriter,
+        prev_hash: String,
+        publish_task: Option<JoinHandle<()>>,
+        input_hash_key: &[u8],
+        worker_id: &str,
+    ) -> Self {
+        Self {
+            worker_id: worker_id.to_string(),
+            input_hash_key: input_hash_key.to_vec(),
+            state: Mutex::new(AuditState { writer, prev_hash }),
+            publish_task: Mutex::new(publish_task),
+        }
+    }
+
+    /// Record that a request was accepted for proving
+    pub fn record_accepted(&self, request: &ProverRequest) {
+        let mut entry = self.entry(AuditEvent::Accepted);
+        entry.request_id = Some(request.request_id.clone());
+        entry.client_id = request.client_id.clone();
+        entry.trace_id = request.trace_id.clone();
+        entry.parent_request_id = request.parent_request_id.clone();
+        entry.input_hash = Some(self.input_hash(request));
+        self.append(entry);
+    }
+
+    /// Record the outcome of a request
+    pub fn record_completed(
+        &self,
+        request: &ProverRequest,
+        response: &ProverResponse,
+        duration_ms: u64,
+    ) {
+        let mut entry = self.entry(AuditEvent::Completed);
+        entry.request_id = Some(request.request_id.clone());
+        entry.client_id = request.client_id.clone();
+        entry.trace_id = request.trace_id.clone();
+        entry.parent_request_id = request.parent_request_id.clone();
+        entry.input_hash = Some(self.input_hash(request));
+        entry.outcome = Some(response.status.clone());
+        entry.human_index = response.proof_data.as_ref().map(|p| p.human_index);
+        entry.error_type = response.error.as_ref().map(|e| e.error_type.clone());
+        entry.duration_ms = Some(duration_ms);
+        entry.vkey_hash = response
+            .proof_data
+            .as_ref()
+            .and_then(|p| STANDARD.decode(&p.verification_key).ok())
+            .map(|vkey| String::from_utf8_lossy(&vkey).into_owned());
+        self.append(entry);
+    }
+
+    /// Record a message that could not be parsed
+    pub fn record_rejected(&self, error: &ServiceError) {
+        let mut entry = self.entry(AuditEvent::Rejected);
+        entry.error_type = Some(error.error_type());
+        self.append(entry);
+    }
+
+    /// Flush records to disk or wait for them to be published
+    ///
+    /// Call once at shutdown: a topic sink accepts no records afterwards.
+    pub async fn flush(&self) -> Result<(), ServiceError> {
+        {
+            let mut state = self.state.lock().expect("audit lock poisoned");
+            match &mut state.writer {
+                AuditWriter::File { file, .. } => file.sync_all()?,
+                // Closing the channel ends the publish task once it is drained
+                AuditWriter::Topic { records } => drop(records.take()),
+            }
+        }
+
+        let task = self
+            .publish_task
+            .lock()
+            .expect("audit lock poisoned")
+            .take();
+        if let Some(task) = task {
+            task.await
+                .map_err(|e| ServiceError::PubSub(format!("Audit publish task failed: {}", e)))?;
+        }
+        Ok(())
+    }
+
+    fn entry(&self, event: AuditEvent) -> AuditEntry {
+        AuditEntry {
+            timestamp: Utc::now().to_rfc3339(),
+            event,
+            request_id: None,
+            client_id: None,
+            trace_id: None,
+            parent_request_id: None,
+            input_hash: None,
+            outcome: None,
+            human_index: None,
+            error_type: None,
+            duration_ms: None,
+            vkey_hash: None,
+            worker_id: self.worker_id.clone(),
+        }
+    }
+
+    /// Keyed hash of the private and public inputs
+    fn input_hash(&self, request: &ProverRequest) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.input_hash_key)
+            .expect("HMAC accepts keys of any length");
+        mac.update(&canonical_bytes(request));
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    /// Append an entry; failures are logged and never fail the request
+    fn append(&self, entry: AuditEntry) {
+        if let Err(e) = self.try_append(entry) {
+            error!("Failed to write audit record: {}", e);
+        }
+    }
+
+    fn try_append(&self, entry: AuditEntry) -> Result<(), ServiceError> {
+        let mut state = self.state.lock().expect("audit lock poisoned");
+
+        let record = AuditRecord::new(entry, state.prev_hash.clone())?;
+        let mut line = serde_json::to_vec(&record)?;
+
+        match &mut state.writer {
+            AuditWriter::File { dir, date, file } => {
+                let today = Utc::now().date_naive();
+                if today != *date {
+                    *file = open_for_date(dir, today)?;
+                    *date = today;
+                }
+                line.push(b'\n');
+                file.write_all(&line)?;
+                file.flush()?;
+            }
+            AuditWriter::Topic { records } => {
+                records
+                    .as_ref()
+                    .and_then(|records| records.send(line).ok())
+                    .ok_or_else(|| ServiceError::PubSub("Audit log is closed".to_string()))?;
+            }
+        }
+        state.prev_hash = record.hash;
+        Ok(())
+    }
+}
+
+/// Publish audit records in the order they were appended
+async fn publish_records(
+    mut publisher: Publisher,
+    mut records: mpsc::UnboundedReceiver<Vec<u8>>,
+    ordering_key: String,
+) {
+    while let Some(data) = records.recv().await {
+        let message = PubsubMessage {
+            data,
+            ordering_key: ordering_key.clone(),
+            ..Default::default()
+        };
+        if let Err(e) = publisher.publish(message).await.get().await {
+            error!("Failed to publish audit record: {}", e);
+        }
+    }
+    publisher.shutdown().await;
+}
+
+fn open_for_date(dir: &Path, date: NaiveDate) -> Result<File, ServiceError> {
+    let path = dir.join(format!("audit-{}.jsonl", date.format("%Y-%m-%d")));
+    Ok(OpenOptions::new().create(true).append(true).open(path)?)
+}
+
+/// Audit log files in `dir`, oldest first
+pub fn log_files(dir: &Path) -> Result<Vec<PathBuf>, std::io::Error> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("audit-") && n.ends_with(".jsonl"))
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn last_hash(path: &Path) -> Result<Option<String>, ServiceError> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut last = None;
+    for line in reader.lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            last = Some(line);
+        }
+    }
+    match last {
+        Some(line) => Ok(Some(serde_json::from_str::<AuditRecord>(&line)?.hash)),
+        None => Ok(None),
+    }
+}
+
+/// Error found while verifying an audit chain
+#[derive(Debug, Error)]
+pub enum ChainError {
+    #[error("{file}: {source}")]
+    Io {
+        file: String,
+        source: std::io::Error,
+    },
+
+    #[error("{file}:{line}: invalid record: {message}")]
+    Parse {
+        file: String,
+        line: usize,
+        message: String,
+    },
+
+    #[error("{file}:{line}: {reason}")]
+    Broken {
+        file: String,
+        line: usize,
+        reason: String,
+    },
+}
+
+/// Verify the hash chain across `files`, given oldest first
+/// Returns the number of records checked
+pub fn verify_chain(files: &[PathBuf]) -> Result<usize, ChainError> {
+    let mut prev_hash = GENESIS_HASH.to_string();
+    let mut count = 0;
+
+    for path in files {
+        let file = path.display().to_string();
+        let reader = BufReader::new(File::open(path).map_err(|source| ChainError::Io {
+            file: file.clone(),
+            source,
+        })?);
+
+        for (index, line) in reader.lines().enumerate() {
+            let line_no = index + 1;
+            let line = line.map_err(|source| ChainError::Io {
+                file: file.clone(),
+                source,
+            })?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let record: AuditRecord =
+                serde_json::from_str(&line).map_err(|e| ChainError::Parse {
+                    file: file.clone(),
+                    line: line_no,
+                    message: e.to_string(),
+                })?;
+
+            if record.prev_hash != prev_hash {
+                return Err(ChainError::Broken {
+                    file,
+                    line: line_no,
+                    reason: format!(
+                        "prev_hash {} does not match preceding record {}",
+                        record.prev_hash, prev_hash
+                    ),
+                });
+            }
+
+            let expected =
+                chain_hash(&record.prev_hash, &record.entry).map_err(|e| ChainError::Parse {
+                    file: file.clone(),
+                    line: line_no,
+                    message: e.to_string(),
+                })?;
+            if record.hash != expected {
+                return Err(ChainError::Broken {
+                    file,
+                    line: line_no,
+                    reason: "record hash does not match its contents".to_string(),
+                });
+            }
+
+            prev_hash = record.hash;
+            count += 1;
+        }
+    }
+
+    Ok(count)
+}
@@ -0,0 +1,97 @@
+// Compares two `cycle-report` outputs and fails on cycle regressions
+// Usage: compare-cycles <BASELINE> <CURRENT> [--threshold PERCENT]
+
+use clap::Parser;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(name = "compare-cycles", about = "Diff two guest cycle reports")]
+struct Args {
+    /// Baseline report
+    baseline: PathBuf,
+
+    /// Report to check against the baseline
+    current: PathBuf,
+
+    /// Maximum allowed cycle increase per vector, in percent
+    #[arg(long, default_value_t = 5.0)]
+    threshold: f64,
+}
+
+#[derive(Deserialize)]
+struct CycleReport {
+    vectors: Vec<VectorCycles>,
+}
+
+#[derive(Deserialize)]
+struct VectorCycles {
+    name: String,
+    cycles: u64,
+}
+
+fn read_report(path: &PathBuf) -> Result<CycleReport, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    serde_json::from_slice(&bytes).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let (baseline, current) = match (read_report(&args.baseline), read_report(&args.current)) {
+        (Ok(baseline), Ok(current)) => (baseline, current),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("Failed to read report {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let current: HashMap<&str, u64> = current
+        .vectors
+        .iter()
+        .map(|v| (v.name.as_str(), v.cycles))
+        .collect();
+
+    let mut regressions = 0;
+    println!(
+        "{:<24} {:>12} {:>12} {:>9}",
+        "vector", "baseline", "current", "change"
+    );
+    for vector in &baseline.vectors {
+        let Some(&cycles) = current.get(vector.name.as_str()) else {
+            println!(
+                "{:<24} {:>12} {:>12} {:>9}",
+                vector.name, vector.cycles, "missing", "-"
+            );
+            regressions += 1;
+            continue;
+        };
+
+        let change = if vector.cycles == 0 {
+            0.0
+        } else {
+            (cycles as f64 - vector.cycles as f64) / vector.cycles as f64 * 100.0
+        };
+        let flag = if change > args.threshold {
+            regressions += 1;
+            "  REGRESSION"
+        } else {
+            ""
+        };
+        println!(
+            "{:<24} {:>12} {:>12} {:>+8.2}%{}",
+            vector.name, vector.cycles, cycles, change, flag
+        );
+    }
+
+    if regressions > 0 {
+        eprintln!(
+            "{} vector(s) regressed by more than {}% or are missing",
+            regressions, args.threshold
+        );
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
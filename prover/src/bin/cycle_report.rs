@@ -0,0 +1,124 @@
+// Measures guest cycle counts for the golden vectors by emulating the ELF
+// Writes a JSON report that `compare-cycles` can diff against a baseline
+
+use clap::Parser;
+use human_index_lib::{
+    load_elf, HumanIndexPublicInputs, ProofInputs, PublicValues, VerificationResults,
+};
+use pico_sdk::client::DefaultProverClient;
+use prover::fixtures::FIXTURE_VALIDITY;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(
+    name = "cycle-report",
+    about = "Record guest cycle counts for the golden vectors"
+)]
+struct Args {
+    /// Guest ELF to emulate
+    #[arg(long, default_value = "../app/elf/riscv32im-pico-zkvm-elf")]
+    elf: String,
+
+    /// Golden vectors file
+    #[arg(long, default_value = "testdata/golden_vectors.json")]
+    vectors: PathBuf,
+
+    /// Where to write the report (stdout when omitted)
+    #[arg(long)]
+    out: Option<PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct GoldenVector {
+    name: String,
+    verification_results: VerificationResults,
+    public_inputs: HumanIndexPublicInputs,
+}
+
+#[derive(Serialize)]
+struct CycleReport {
+    elf_sha256: String,
+    vectors: Vec<VectorCycles>,
+}
+
+#[derive(Serialize)]
+struct VectorCycles {
+    name: String,
+    cycles: u64,
+    human_index: u32,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let vectors: Vec<GoldenVector> = match std::fs::read(&args.vectors)
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| serde_json::from_slice(&bytes).map_err(|e| e.to_string()))
+    {
+        Ok(vectors) => vectors,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", args.vectors.display(), e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let elf = load_elf(&args.elf);
+    let client = DefaultProverClient::new(&elf);
+
+    let mut report = CycleReport {
+        elf_sha256: hex::encode(Sha256::digest(&elf)),
+        vectors: Vec::with_capacity(vectors.len()),
+    };
+
+    for vector in vectors {
+        let proof_inputs = ProofInputs::new(
+            vector.verification_results,
+            vector.public_inputs,
+            FIXTURE_VALIDITY,
+        );
+        let expected_output = proof_inputs.expected_output;
+
+        let mut stdin_builder = client.new_stdin_builder();
+        proof_inputs.write_to(|value| stdin_builder.write(value));
+
+        let (cycles, public_buffer) = client.emulate(stdin_builder);
+
+        let public_values: PublicValues = match bincode::deserialize(&public_buffer) {
+            Ok(public_values) => public_values,
+            Err(e) => {
+                eprintln!("{}: invalid public values: {}", vector.name, e);
+                return ExitCode::FAILURE;
+            }
+        };
+        if public_values.computed_output != expected_output {
+            eprintln!(
+                "{}: guest computed {}, native computed {}",
+                vector.name, public_values.computed_output, expected_output
+            );
+            return ExitCode::FAILURE;
+        }
+
+        eprintln!("{}: {} cycles", vector.name, cycles);
+        report.vectors.push(VectorCycles {
+            name: vector.name,
+            cycles,
+            human_index: expected_output,
+        });
+    }
+
+    let json = serde_json::to_string_pretty(&report).expect("report serializes");
+    match &args.out {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, json + "\n") {
+                eprintln!("Failed to write {}: {}", path.display(), e);
+                return ExitCode::FAILURE;
+            }
+            eprintln!("Report written to {}", path.display());
+        }
+        None => println!("{}", json),
+    }
+    ExitCode::SUCCESS
+}
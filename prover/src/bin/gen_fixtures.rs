@@ -0,0 +1,189 @@
+// Records proof fixtures for the golden vectors under `fixtures/`
+// Tests load them through `prover::fixtures`; see the README for when to regenerate
+
+use clap::{Parser, ValueEnum};
+use human_index_lib::{load_elf, ProofInputs, PublicValues, GUEST_VERSION};
+use pico_sdk::client::DefaultProverClient;
+use prover::fixtures::{
+    read_golden_vectors, FixtureKind, GoldenVector, Manifest, FIXTURE_VALIDITY, MANIFEST_FILE,
+};
+use prover::mock::MockProofGenerator;
+use prover::prover::{CachedElf, ProofBackend, ProofContext, ProofGenerator, ProverProfile};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[derive(Parser)]
+#[command(
+    name = "gen-fixtures",
+    about = "Record proof fixtures for the golden vectors"
+)]
+struct Args {
+    /// Which fixtures to record; `all` skips the SDK modes when the ELF is missing
+    #[arg(long, value_enum)]
+    mode: Mode,
+
+    /// Guest ELF for the `execute` and `fast` modes
+    #[arg(long, default_value = "../app/elf/riscv32im-pico-zkvm-elf")]
+    elf: String,
+
+    /// Golden vectors file
+    #[arg(long, default_value = "testdata/golden_vectors.json")]
+    vectors: PathBuf,
+
+    /// Fixtures directory
+    #[arg(long, default_value = "fixtures")]
+    out: PathBuf,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Mode {
+    /// Public values streams from emulating the guest
+    Execute,
+    /// Proofs from the fast profile (no Groth16 wrap)
+    Fast,
+    /// Proofs from the mock backend
+    Mock,
+    All,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    let vectors_bytes = std::fs::read(&args.vectors)
+        .map_err(|e| format!("Failed to read {}: {}", args.vectors.display(), e))?;
+    let vectors = read_golden_vectors(&args.vectors).map_err(|e| e.to_string())?;
+    let vectors_sha256 = hex::encode(Sha256::digest(&vectors_bytes));
+
+    // Keep fixtures of other kinds, unless they were recorded for other inputs
+    let manifest_path = args.out.join(MANIFEST_FILE);
+    let mut manifest = std::fs::read(&manifest_path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<Manifest>(&bytes).ok())
+        .filter(|manifest| {
+            manifest.guest_version == GUEST_VERSION && manifest.vectors_sha256 == vectors_sha256
+        })
+        .unwrap_or_else(|| Manifest {
+            guest_version: GUEST_VERSION,
+            vectors_sha256,
+            fixtures: Vec::new(),
+        });
+
+    let sdk_available = Path::new(&args.elf).exists();
+    let wants = |mode: Mode| args.mode == mode || args.mode == Mode::All;
+    for (mode, needs_sdk) in [
+        (Mode::Execute, true),
+        (Mode::Fast, true),
+        (Mode::Mock, false),
+    ] {
+        if !wants(mode) {
+            continue;
+        }
+        if needs_sdk && !sdk_available {
+            if args.mode == Mode::All {
+                eprintln!("Skipping {} fixtures: no ELF at {}", mode.name(), args.elf);
+                continue;
+            }
+            return Err(format!("No ELF at {}; build the guest first", args.elf));
+        }
+        for vector in &vectors {
+            let (kind, contents) = match mode {
+                Mode::Execute => (FixtureKind::PvStream, execute(&args.elf, vector)?),
+                Mode::Fast => (FixtureKind::FastProof, fast_proof(&args.elf, vector)?),
+                Mode::Mock => (FixtureKind::MockProof, mock_proof(vector)?),
+                Mode::All => unreachable!("not a recording mode"),
+            };
+            write(&args.out.join(kind.path(&vector.name)), &contents)?;
+            manifest.record(&vector.name, kind, &contents);
+            eprintln!("{}: {} recorded", vector.name, kind);
+        }
+    }
+
+    let json = serde_json::to_string_pretty(&manifest).expect("manifest serializes");
+    write(&manifest_path, (json + "\n").as_bytes())?;
+    eprintln!("Manifest written to {}", manifest_path.display());
+    Ok(())
+}
+
+impl Mode {
+    fn name(self) -> &'static str {
+        match self {
+            Mode::Execute => "execute",
+            Mode::Fast => "fast",
+            Mode::Mock => "mock",
+            Mode::All => "all",
+        }
+    }
+}
+
+/// Emulate the guest and check its output against the native calculation
+fn execute(elf: &str, vector: &GoldenVector) -> Result<Vec<u8>, String> {
+    let client = DefaultProverClient::new(&load_elf(elf));
+    let proof_inputs = ProofInputs::new(
+        vector.verification_results.clone(),
+        vector.public_inputs.clone(),
+        FIXTURE_VALIDITY,
+    );
+    let expected_output = proof_inputs.expected_output;
+
+    let mut stdin_builder = client.new_stdin_builder();
+    proof_inputs.write_to(|value| stdin_builder.write(value));
+    let (_, pv_stream) = client.emulate(stdin_builder);
+
+    let public_values: PublicValues = bincode::deserialize(&pv_stream)
+        .map_err(|e| format!("{}: invalid public values: {}", vector.name, e))?;
+    if public_values.computed_output != expected_output {
+        return Err(format!(
+            "{}: guest computed {}, native computed {}",
+            vector.name, public_values.computed_output, expected_output
+        ));
+    }
+    Ok(pv_stream)
+}
+
+fn fast_proof(elf: &str, vector: &GoldenVector) -> Result<Vec<u8>, String> {
+    let cached_elf = CachedElf::load(elf).map_err(|e| e.to_string())?;
+    let output_dir = std::env::temp_dir().join("gen-fixtures");
+    let backend = ProofGenerator::new(
+        Arc::new(cached_elf),
+        output_dir,
+        ProverProfile::Fast,
+        u64::MAX,
+        u64::MAX,
+        None,
+    );
+    prove(&backend, vector)
+}
+
+fn mock_proof(vector: &GoldenVector) -> Result<Vec<u8>, String> {
+    let backend = MockProofGenerator::new(Duration::ZERO, Duration::from_secs(60));
+    prove(&backend, vector)
+}
+
+fn prove(backend: &impl ProofBackend, vector: &GoldenVector) -> Result<Vec<u8>, String> {
+    let context = ProofContext::new(Instant::now() + Duration::from_secs(3600), 0);
+    let proof = backend
+        .generate_proof(vector.request(), &context)
+        .map_err(|e| format!("{}: {} backend failed: {}", vector.name, backend.name(), e))?;
+    let json = serde_json::to_string_pretty(&proof).expect("proof serializes");
+    Ok((json + "\n").into_bytes())
+}
+
+fn write(path: &Path, contents: &[u8]) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    std::fs::write(path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
@@ -0,0 +1,82 @@
+// Summarizes the metrics snapshots the service writes with METRICS_SNAPSHOT_MINS
+// Usage: metrics [--dir DIR] report [--from RFC3339] [--to RFC3339] [--json]
+
+use chrono::{DateTime, TimeDelta, Utc};
+use clap::{Parser, Subcommand};
+use prover::metrics_log::{read_snapshots, MetricsReport, METRICS_DIR};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(
+    name = "metrics",
+    about = "Report on the proofs recorded in metrics snapshots"
+)]
+struct Args {
+    /// Snapshot directory (default OUTPUT_DIR/metrics)
+    #[arg(long)]
+    dir: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print proofs and their durations by outcome, proof type and client, and by hour
+    Report {
+        /// Start of the range (RFC 3339); 24 hours before `--to` when omitted
+        #[arg(long)]
+        from: Option<DateTime<Utc>>,
+
+        /// End of the range (RFC 3339); now when omitted
+        #[arg(long)]
+        to: Option<DateTime<Utc>>,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+fn main() -> ExitCode {
+    dotenvy::dotenv().ok();
+    let args = Args::parse();
+
+    let dir = args.dir.unwrap_or_else(|| {
+        let output_dir = std::env::var("OUTPUT_DIR")
+            .unwrap_or_else(|_| format!("{}/data", env!("CARGO_MANIFEST_DIR")));
+        PathBuf::from(output_dir).join(METRICS_DIR)
+    });
+
+    match args.command {
+        Command::Report { from, to, json } => {
+            let to = to.unwrap_or_else(Utc::now);
+            let from = from.unwrap_or(to - TimeDelta::hours(24));
+            let report =
+                read_snapshots(&dir, from, to).and_then(|snapshots| MetricsReport::of(&snapshots));
+            let report = match report {
+                Ok(report) => report,
+                Err(e) => {
+                    eprintln!("Failed to read {}: {}", dir.display(), e);
+                    return ExitCode::FAILURE;
+                }
+            };
+
+            if json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&report).expect("report serializes")
+                );
+            } else {
+                println!(
+                    "Snapshots ending after {} and by {}",
+                    from.to_rfc3339(),
+                    to.to_rfc3339()
+                );
+                print!("{}", report);
+            }
+            ExitCode::SUCCESS
+        }
+    }
+}
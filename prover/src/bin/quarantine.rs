@@ -0,0 +1,89 @@
+// Lists and releases requests quarantined after repeated failed attempts
+// Usage: quarantine [--dir DIR] list | release <HASH...>
+
+use clap::{Parser, Subcommand};
+use prover::quarantine::Quarantine;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(name = "quarantine", about = "Manage quarantined proof requests")]
+struct Args {
+    /// Quarantine directory (default QUARANTINE_DIR, else OUTPUT_DIR/quarantine)
+    #[arg(long)]
+    dir: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the hashes of quarantined requests
+    List,
+    /// Allow quarantined requests to be attempted again
+    Release {
+        #[arg(required = true)]
+        hashes: Vec<String>,
+    },
+}
+
+fn main() -> ExitCode {
+    dotenvy::dotenv().ok();
+    let args = Args::parse();
+
+    let dir = args.dir.unwrap_or_else(|| {
+        std::env::var("QUARANTINE_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| {
+                let output_dir = std::env::var("OUTPUT_DIR")
+                    .unwrap_or_else(|_| format!("{}/data", env!("CARGO_MANIFEST_DIR")));
+                PathBuf::from(output_dir).join("quarantine")
+            })
+    });
+
+    // Attempt limits only matter to the service
+    let quarantine = match Quarantine::open(&dir, 1) {
+        Ok(quarantine) => quarantine,
+        Err(e) => {
+            eprintln!("Failed to open {}: {}", dir.display(), e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match args.command {
+        Command::List => match quarantine.list() {
+            Ok(hashes) => {
+                for hash in hashes {
+                    println!("{}", hash);
+                }
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("Failed to list {}: {}", dir.display(), e);
+                ExitCode::FAILURE
+            }
+        },
+        Command::Release { hashes } => {
+            let mut failed = 0usize;
+            for hash in &hashes {
+                match quarantine.release(hash) {
+                    Ok(true) => println!("Released {}", hash),
+                    Ok(false) => {
+                        eprintln!("Not quarantined: {}", hash);
+                        failed += 1;
+                    }
+                    Err(e) => {
+                        eprintln!("FAILED {}: {}", hash, e);
+                        failed += 1;
+                    }
+                }
+            }
+            if failed == 0 {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+    }
+}
@@ -0,0 +1,178 @@
+// Republishes historical proof requests to the prover topic
+// Usage: replay --topic <TOPIC> [--rps N] [--rewrite-ids] [--dry-run] <FILE...>
+
+use clap::Parser;
+use google_cloud_googleapis::pubsub::v1::PubsubMessage;
+use prover::service::pubsub_client;
+use prover::types::{derived_request_id, ProverRequest};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+use tokio::time::{interval, MissedTickBehavior};
+
+#[derive(Parser)]
+#[command(
+    name = "replay",
+    about = "Republish proof requests to the prover topic"
+)]
+struct Args {
+    /// Newline-delimited JSON files of `ProverRequest`s
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+
+    /// Topic the prover subscription is attached to (project from GCP_PROJECT_ID)
+    #[arg(long, required_unless_present = "dry_run")]
+    topic: Option<String>,
+
+    /// Only replay requests from this client
+    #[arg(long)]
+    client: Option<String>,
+
+    /// Maximum requests published per second
+    #[arg(long, default_value_t = 1.0)]
+    rps: f64,
+
+    /// Give each request a new UUID derived from the original ID
+    #[arg(long)]
+    rewrite_ids: bool,
+
+    /// Print the requests that would be sent without publishing
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    dotenvy::dotenv().ok();
+    let args = Args::parse();
+
+    if args.rps <= 0.0 || !args.rps.is_finite() {
+        eprintln!("--rps must be a positive number");
+        return ExitCode::FAILURE;
+    }
+
+    let mut failed = 0usize;
+    let mut requests = Vec::new();
+    for path in &args.files {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("FAILED {}: {}", path.display(), e);
+                failed += 1;
+                continue;
+            }
+        };
+        for (index, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match ProverRequest::from_json(line.as_bytes()) {
+                Ok(request) => requests.push(request),
+                Err(e) => {
+                    eprintln!("FAILED {}:{}: {}", path.display(), index + 1, e);
+                    failed += 1;
+                }
+            }
+        }
+    }
+
+    if let Some(client) = &args.client {
+        requests.retain(|request| request.client_id.as_deref() == Some(client.as_str()));
+    }
+
+    let now = chrono::Utc::now();
+    let replay_tag = now.timestamp();
+    for request in &mut requests {
+        // A replay is new work; keep MAX_REQUEST_AGE_SECS from expiring it
+        if request.submitted_at.is_some() {
+            request.submitted_at = Some(now.to_rfc3339());
+        }
+        if args.rewrite_ids {
+            let original = std::mem::take(&mut request.request_id);
+            request.request_id = derived_request_id(&format!("{}-replay-{}", original, replay_tag));
+            request.replay_of = Some(original);
+        }
+    }
+
+    if args.dry_run {
+        for request in &requests {
+            match serde_json::to_string(request) {
+                Ok(json) => println!("{}", json),
+                Err(e) => {
+                    eprintln!("FAILED {}: {}", request.request_id, e);
+                    failed += 1;
+                }
+            }
+        }
+        eprintln!("Dry run: {} requests, {} failed", requests.len(), failed);
+        return exit_code(failed);
+    }
+
+    let project_id = match std::env::var("GCP_PROJECT_ID") {
+        Ok(project_id) => project_id,
+        Err(_) => {
+            eprintln!("GCP_PROJECT_ID not set");
+            return ExitCode::FAILURE;
+        }
+    };
+    let topic_path = format!(
+        "projects/{}/topics/{}",
+        project_id,
+        args.topic.as_deref().expect("required unless dry run")
+    );
+
+    let client = match pubsub_client().await {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let publisher = client.topic(&topic_path).new_publisher(None);
+
+    let mut ticker = interval(Duration::from_secs_f64(1.0 / args.rps));
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    let mut published = 0usize;
+    for request in &requests {
+        ticker.tick().await;
+
+        let data = match serde_json::to_vec(request) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("FAILED {}: {}", request.request_id, e);
+                failed += 1;
+                continue;
+            }
+        };
+        let message = PubsubMessage {
+            data,
+            ..Default::default()
+        };
+
+        match publisher.publish(message).await.get().await {
+            Ok(message_id) => {
+                println!("OK {} ({})", request.request_id, message_id);
+                published += 1;
+            }
+            Err(e) => {
+                eprintln!("FAILED {}: {}", request.request_id, e);
+                failed += 1;
+            }
+        }
+    }
+
+    eprintln!(
+        "Replayed {} requests to {}, {} failed",
+        published, topic_path, failed
+    );
+    exit_code(failed)
+}
+
+fn exit_code(failed: usize) -> ExitCode {
+    if failed == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,148 @@
+// Proves one request more than once with its validity window pinned, and reports
+// whether the committed public values and human index came out the same
+// Usage: reproduce <request.json> [--runs N] [--mock] [--vk PATH] [--at UNIX_SECS]
+
+use clap::Parser;
+use prover::mock::MockProofGenerator;
+use prover::prover::{CachedElf, ProofGenerator, ProverProfile};
+use prover::reproduce::{Deterministic, Reproduction};
+use prover::response_check::Outcome;
+use prover::types::ProverRequest;
+use prover::verifier::VerifyingKey;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Parser)]
+#[command(
+    name = "reproduce",
+    about = "Prove a request twice and compare what the proofs commit"
+)]
+struct Args {
+    /// Request JSON, as published to the prover topic
+    request: PathBuf,
+
+    /// How many times to prove it
+    #[arg(long, default_value_t = 2)]
+    runs: u32,
+
+    /// Prove with the mock backend instead of the SDK
+    #[arg(long)]
+    mock: bool,
+
+    /// Guest ELF
+    #[arg(long, default_value = "../app/elf/riscv32im-pico-zkvm-elf")]
+    elf: String,
+
+    /// Directory holding `vm_pk`/`vm_vk`, where proofs are written
+    #[arg(long, default_value = "./data")]
+    output_dir: PathBuf,
+
+    /// Proving profile, `evm` or `fast`
+    #[arg(long, default_value = "evm")]
+    profile: ProverProfile,
+
+    /// Groth16 verifying key (`vm_vk`) each proof must verify under
+    #[arg(long)]
+    vk: Option<PathBuf>,
+
+    /// Pin a missing `issued_at`, and check the window, at this unix time instead of now
+    #[arg(long, value_name = "UNIX_SECS")]
+    at: Option<u64>,
+
+    /// Deadline of each proof, in seconds
+    #[arg(long, default_value_t = 600)]
+    timeout_secs: u64,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    match run(&args) {
+        Ok(reproduction) => report(&reproduction),
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &Args) -> Result<Reproduction, String> {
+    let data = std::fs::read(&args.request)
+        .map_err(|e| format!("Failed to read {}: {}", args.request.display(), e))?;
+    let request = ProverRequest::from_json(&data)
+        .and_then(ProverRequest::resolve_inputs)
+        .map_err(|e| format!("Invalid request: {}", e))?;
+    let vk = args
+        .vk
+        .as_deref()
+        .map(VerifyingKey::from_file)
+        .transpose()
+        .map_err(|e| format!("Failed to read the verifying key: {}", e))?;
+
+    let deterministic = Deterministic {
+        vk: vk.as_ref(),
+        now: args
+            .at
+            .unwrap_or_else(|| chrono::Utc::now().timestamp() as u64),
+        timeout: Duration::from_secs(args.timeout_secs),
+    };
+    let reproduction = if args.mock {
+        let backend = MockProofGenerator::new(Duration::ZERO, deterministic.timeout);
+        deterministic.reproduce(&backend, request, args.runs)
+    } else {
+        let cached_elf = CachedElf::load(&args.elf).map_err(|e| e.to_string())?;
+        let backend = ProofGenerator::new(
+            Arc::new(cached_elf),
+            args.output_dir.clone(),
+            args.profile,
+            u64::MAX,
+            u64::MAX,
+            None,
+        );
+        deterministic.reproduce(&backend, request, args.runs)
+    };
+    reproduction.map_err(|e| format!("Proving failed: {}", e))
+}
+
+fn report(reproduction: &Reproduction) -> ExitCode {
+    for (i, run) in reproduction.runs.iter().enumerate() {
+        let failed: Vec<_> = run
+            .checks
+            .iter()
+            .filter(|(_, outcome)| *outcome == Outcome::Fail)
+            .map(|(name, _)| *name)
+            .collect();
+        println!(
+            "run {}: human_index {} in {} ms, failed checks: {:?}",
+            i + 1,
+            run.proof_data.human_index,
+            run.duration_ms,
+            failed
+        );
+    }
+    println!(
+        "proof bytes {}",
+        match reproduction.proofs_identical() {
+            true => "identical",
+            false => "differ (not guaranteed)",
+        }
+    );
+
+    let differences = reproduction.differences();
+    let verified = reproduction.runs.iter().all(|run| run.passed);
+    match (differences.is_empty(), verified) {
+        (true, true) => {
+            println!("reproduced");
+            ExitCode::SUCCESS
+        }
+        (true, false) => {
+            println!("reproduced, but a check failed");
+            ExitCode::FAILURE
+        }
+        (false, _) => {
+            println!("NOT reproduced: {} differ", differences.join(", "));
+            ExitCode::FAILURE
+        }
+    }
+}
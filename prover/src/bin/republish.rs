@@ -0,0 +1,115 @@
+// Publishes journaled results again, for consumers that missed them
+// Usage: republish [--journal PATH] [--print] <REQUEST_ID...>
+
+use clap::Parser;
+use prover::delayed::{DelayedQueue, ResultLookup};
+use prover::service::{pubsub_client, republished_message};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(
+    name = "republish",
+    about = "Publish journaled results to their result topic again"
+)]
+struct Args {
+    /// Request IDs of the results
+    #[arg(required = true)]
+    request_ids: Vec<String>,
+
+    /// Journal the results are kept in (default DELAYED_QUEUE_PATH, else
+    /// OUTPUT_DIR/delayed.sqlite)
+    #[arg(long)]
+    journal: Option<PathBuf>,
+
+    /// Print each result as it was published instead of publishing it
+    #[arg(long)]
+    print: bool,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    dotenvy::dotenv().ok();
+    let args = Args::parse();
+
+    let path = args.journal.unwrap_or_else(|| {
+        std::env::var("DELAYED_QUEUE_PATH")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| {
+                let output_dir = std::env::var("OUTPUT_DIR")
+                    .unwrap_or_else(|_| format!("{}/data", env!("CARGO_MANIFEST_DIR")));
+                PathBuf::from(output_dir).join("delayed.sqlite")
+            })
+    });
+
+    // The service may be running on the same journal
+    let journal = match DelayedQueue::open_shared(&path) {
+        Ok(journal) => journal,
+        Err(e) => {
+            eprintln!("Failed to open {}: {}", path.display(), e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut results = Vec::new();
+    let mut failed = 0usize;
+    for request_id in &args.request_ids {
+        match journal.result(request_id) {
+            Ok(ResultLookup::Completed(result)) => results.push(result),
+            Ok(ResultLookup::InProgress) => {
+                eprintln!("In progress: {}", request_id);
+                failed += 1;
+            }
+            Ok(ResultLookup::Unknown) => {
+                eprintln!("Not found: {}", request_id);
+                failed += 1;
+            }
+            Err(e) => {
+                eprintln!("FAILED {}: {}", request_id, e);
+                failed += 1;
+            }
+        }
+    }
+
+    if args.print {
+        for result in &results {
+            println!("{}", String::from_utf8_lossy(&result.data));
+        }
+        return exit_code(failed);
+    }
+    if results.is_empty() {
+        return exit_code(failed);
+    }
+
+    let client = match pubsub_client().await {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    for result in &results {
+        let mut publisher = client.topic(&result.topic_path).new_publisher(None);
+        let message = republished_message(result, chrono::Utc::now());
+        match publisher.publish(message).await.get().await {
+            Ok(message_id) => println!(
+                "OK {} to {} ({})",
+                result.request_id, result.topic_path, message_id
+            ),
+            Err(e) => {
+                eprintln!("FAILED {}: {}", result.request_id, e);
+                failed += 1;
+            }
+        }
+        publisher.shutdown().await;
+    }
+    exit_code(failed)
+}
+
+fn exit_code(failed: usize) -> ExitCode {
+    if failed == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
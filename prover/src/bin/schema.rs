@@ -0,0 +1,61 @@
+// Writes the JSON Schemas of the message types for publishers and consumers in
+// other languages
+// Usage: schema [--out DIR] [--print NAME]
+
+use clap::Parser;
+use prover::schema::schemas;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(name = "schema", about = "Write the JSON Schemas of the message types")]
+struct Args {
+    /// Directory to write the `*.schema.json` files to
+    #[arg(long, default_value = "schemas")]
+    out: PathBuf,
+
+    /// Print the schema NAME (e.g. `prover_response`) to stdout instead of writing files
+    #[arg(long, value_name = "NAME")]
+    print: Option<String>,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    if let Some(name) = args.print {
+        let file = format!("{}.schema.json", name);
+        let Some((_, schema)) = schemas().into_iter().find(|(f, _)| *f == file) else {
+            let names: Vec<_> = schemas()
+                .iter()
+                .map(|(f, _)| f.trim_end_matches(".schema.json"))
+                .collect();
+            eprintln!(
+                "Unknown schema '{}', expected one of {}",
+                name,
+                names.join(", ")
+            );
+            return ExitCode::FAILURE;
+        };
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&schema).expect("schema serializes")
+        );
+        return ExitCode::SUCCESS;
+    }
+
+    if let Err(e) = std::fs::create_dir_all(&args.out) {
+        eprintln!("Failed to create {}: {}", args.out.display(), e);
+        return ExitCode::FAILURE;
+    }
+
+    for (file, schema) in schemas() {
+        let path = args.out.join(file);
+        let json = serde_json::to_string_pretty(&schema).expect("schema serializes");
+        if let Err(e) = std::fs::write(&path, json + "\n") {
+            eprintln!("Failed to write {}: {}", path.display(), e);
+            return ExitCode::FAILURE;
+        }
+        println!("{}", path.display());
+    }
+    ExitCode::SUCCESS
+}
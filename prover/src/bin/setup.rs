@@ -1,18 +1,47 @@
 // One-time Groth16 setup script
 // Generates vm_pk, vm_vk, and Groth16Verifier.sol
+//
+// `setup --export-verifier [PATH]` only regenerates Groth16Verifier.sol from the
+// existing vm_vk, without re-running the trusted setup
+//
+// `setup --intern` moves the keys of every circuit in OUTPUT_DIR into the setup
+// store, so circuits with identical keys share them on disk
 
-use human_index_lib::{calculate_human_index, load_elf, HumanIndexPublicInputs, VerificationResults};
+use human_index_lib::{load_elf, CircuitId};
 use pico_sdk::client::DefaultProverClient;
-use std::path::PathBuf;
+use prover::prover::{setup_inputs, InputLayout, ProofGenerator};
+use prover::setup_store::SetupStore;
+use prover::verifier::export_verifier;
+use std::path::{Path, PathBuf};
 
 fn main() {
+    let output_dir = std::env::var("OUTPUT_DIR")
+        .unwrap_or_else(|_| "prover/data".to_string());
+
+    let mut args = std::env::args().skip(1);
+    if let Some(arg) = args.next() {
+        if arg == "--intern" {
+            run_intern(Path::new(&output_dir));
+            return;
+        }
+        if arg != "--export-verifier" {
+            eprintln!("Unknown argument: {}", arg);
+            eprintln!("Usage: setup [--export-verifier [PATH] | --intern]");
+            std::process::exit(2);
+        }
+        let out_path = args
+            .next()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| Path::new(&output_dir).join("Groth16Verifier.sol"));
+        run_export_verifier(Path::new(&output_dir), &out_path);
+        return;
+    }
+
     println!("=== Pico Groth16 Setup ===\n");
 
     // Paths
     let elf_path = std::env::var("ELF_PATH")
         .unwrap_or_else(|_| "./app/elf/riscv32im-pico-zkvm-elf".to_string());
-    let output_dir = std::env::var("OUTPUT_DIR")
-        .unwrap_or_else(|_| "prover/data".to_string());
 
     let output_path = PathBuf::from(&output_dir)
         .canonicalize()
@@ -35,49 +64,92 @@ fn main() {
     let client = DefaultProverClient::new(&elf_data);
     let mut stdin_builder = client.new_stdin_builder();
 
-    // Use dummy test inputs for setup (the actual values don't matter for setup)
-    let verification_results = VerificationResults {
-        recaptcha_score: 75,
-        sms_verified: 1,
-        bio_verified: 1,
-    };
-    let public_inputs = HumanIndexPublicInputs {
-        w1: 10,
-        w2: 30,
-        w3: 30,
-        w4: 30,
-        expected_output: 0, // Will be calculated
-    };
-
-    // Write private inputs
-    stdin_builder.write(&verification_results.recaptcha_score);
-    stdin_builder.write(&verification_results.sms_verified);
-    stdin_builder.write(&verification_results.bio_verified);
-
-    // Calculate expected output
-    let expected_output = calculate_human_index(&verification_results, &public_inputs);
-    println!("Test human index: {}\n", expected_output);
-
-    // Write public inputs
-    stdin_builder.write(&public_inputs.w1);
-    stdin_builder.write(&public_inputs.w2);
-    stdin_builder.write(&public_inputs.w3);
-    stdin_builder.write(&public_inputs.w4);
-    stdin_builder.write(&expected_output);
+    // Sample inputs for setup (the actual values don't matter for setup)
+    let proof_inputs = setup_inputs();
+    println!("Test human index: {}\n", proof_inputs.expected_output);
+
+    let mut layout = InputLayout::default();
+    proof_inputs.write_to(|value| {
+        layout.record(value);
+        stdin_builder.write(value)
+    });
+
+    // Keys set up over another input layout would not match runtime proofs
+    if let Err(e) = layout.check_runtime() {
+        eprintln!("Setup inputs do not match the runtime inputs: {}", e);
+        std::process::exit(1);
+    }
+    println!("Input layout matches runtime ({} values)\n", layout.arity());
 
     // Run prove_evm with need_setup=true
     println!("Running Groth16 setup (this may take a while)...");
     println!("This will generate: vm_pk, vm_vk, Groth16Verifier.sol\n");
 
+    // Never write the new keys through links into shared blobs
+    let store = SetupStore::new(&output_path);
+    if let Err(e) = store.detach(ProofGenerator::CIRCUIT) {
+        eprintln!("Failed to unlink the previous keys: {}", e);
+        std::process::exit(1);
+    }
+
     client
         .prove_evm(stdin_builder, true, output_path.clone(), "kb")
         .expect("prove_evm with setup failed");
 
+    // Keys identical to another circuit's are stored once
+    if let Err(e) = store.intern_circuit(ProofGenerator::CIRCUIT) {
+        eprintln!("Failed to move the keys into the setup store: {}", e);
+        std::process::exit(1);
+    }
+
     println!("\n=== Setup Complete ===");
     println!("Generated files in {}:", output_path.display());
-    println!("  - vm_pk (proving key)");
-    println!("  - vm_vk (verification key)");
+    println!("  - vm_pk (proving key, linked to setup/<sha256>)");
+    println!("  - vm_vk (verification key, linked to setup/<sha256>)");
     println!("  - Groth16Verifier.sol (verifier contract)");
     println!("  - inputs.json (test proof data)");
     println!("\nNext step: Copy Groth16Verifier.sol to contracts/src/");
 }
+
+/// Regenerate Groth16Verifier.sol from the verification key in `output_dir`
+fn run_export_verifier(output_dir: &Path, out_path: &Path) {
+    println!("=== Export Groth16 Verifier ===\n");
+
+    let vk_path = output_dir.join("vm_vk");
+    println!("Verification key: {}", vk_path.display());
+
+    if let Err(e) = export_verifier(&vk_path, out_path) {
+        eprintln!("Failed to export verifier: {}", e);
+        std::process::exit(1);
+    }
+
+    println!("Verifier contract written to {}", out_path.display());
+}
+
+/// Move the keys of every circuit found in `output_dir` into the setup store
+fn run_intern(output_dir: &Path) {
+    println!("=== Intern Setup Files ===\n");
+
+    let store = SetupStore::new(output_dir);
+    for circuit in CircuitId::ALL {
+        if !store.circuit_dir(circuit).join("vm_pk").exists() {
+            continue;
+        }
+        match store.intern_circuit(circuit) {
+            Ok(blobs) => {
+                for blob in blobs {
+                    println!("{}: {}", circuit.name(), blob.display());
+                }
+            }
+            Err(e) => {
+                eprintln!("Failed to intern the keys of {}: {}", circuit.name(), e);
+                std::process::exit(1);
+            }
+        }
+    }
+    println!(
+        "\n{} blobs in {}",
+        store.blobs().len(),
+        store.root().display()
+    );
+}
@@ -0,0 +1,50 @@
+// Verifies the hash chain of the prover audit log
+// Usage: verify-audit-log <dir | file...>
+
+use clap::Parser;
+use prover::audit::{log_files, verify_chain};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(
+    name = "verify-audit-log",
+    about = "Verify the prover audit log hash chain"
+)]
+struct Args {
+    /// Audit log directory, or individual files in chain order
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let files = if args.paths.len() == 1 && args.paths[0].is_dir() {
+        match log_files(&args.paths[0]) {
+            Ok(files) => files,
+            Err(e) => {
+                eprintln!("Failed to list {}: {}", args.paths[0].display(), e);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        args.paths
+    };
+
+    if files.is_empty() {
+        eprintln!("No audit log files found");
+        return ExitCode::FAILURE;
+    }
+
+    match verify_chain(&files) {
+        Ok(count) => {
+            println!("OK: {} records across {} files", count, files.len());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("Chain verification failed: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
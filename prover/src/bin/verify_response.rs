@@ -0,0 +1,151 @@
+// Checks a published ProverResponse end to end: decodes the proof, verifies it
+// against the Groth16 verifying key, decodes the public values and recomputes the
+// human index from the original request, then prints a report per check
+// Usage: verify-response <response.json> [--vk PATH] [--request PATH] [--json]
+
+use clap::Parser;
+use prover::response_check::{check_response, CheckOptions, Outcome};
+use prover::types::{ProverRequest, ProverResponse};
+use prover::verifier::VerifyingKey;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(
+    name = "verify-response",
+    about = "Check a prover response against the Groth16 verifier"
+)]
+struct Args {
+    /// Response JSON, as published to the result topic
+    response: PathBuf,
+
+    /// Groth16 verifying key (`vm_vk`) the proof must verify under
+    #[arg(long)]
+    vk: Option<PathBuf>,
+
+    /// Original request JSON, to recompute the human index from its inputs
+    #[arg(long)]
+    request: Option<PathBuf>,
+
+    /// Check the validity window at this unix time instead of now
+    #[arg(long, value_name = "UNIX_SECS")]
+    at: Option<u64>,
+
+    /// Print only the JSON summary
+    #[arg(long)]
+    json: bool,
+
+    /// Address the attestation signature must recover to
+    #[cfg(feature = "attestation")]
+    #[arg(long, requires_all = ["chain_id", "verifying_contract"])]
+    signer: Option<alloy_primitives::Address>,
+
+    /// EIP-712 domain chain ID of the attestation
+    #[cfg(feature = "attestation")]
+    #[arg(long)]
+    chain_id: Option<u64>,
+
+    /// EIP-712 domain verifying contract of the attestation
+    #[cfg(feature = "attestation")]
+    #[arg(long)]
+    verifying_contract: Option<alloy_primitives::Address>,
+
+    /// EIP-712 domain name of the attestation
+    #[cfg(feature = "attestation")]
+    #[arg(long, default_value = "HumanIndex")]
+    domain_name: String,
+
+    /// EIP-712 domain version of the attestation
+    #[cfg(feature = "attestation")]
+    #[arg(long, default_value = "1")]
+    domain_version: String,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let response = match std::fs::read(&args.response)
+        .map_err(|e| e.to_string())
+        .and_then(|data| serde_json::from_slice::<ProverResponse>(&data).map_err(|e| e.to_string()))
+    {
+        Ok(response) => response,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", args.response.display(), e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let vk = match args.vk.as_deref().map(VerifyingKey::from_file).transpose() {
+        Ok(vk) => vk,
+        Err(e) => {
+            eprintln!("Failed to read the verifying key: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let request = match args.request.as_deref().map(read_request).transpose() {
+        Ok(request) => request,
+        Err(e) => {
+            eprintln!("Failed to read the request: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let options = CheckOptions {
+        vk: vk.as_ref(),
+        request: request.as_ref(),
+        now: Some(
+            args.at
+                .unwrap_or_else(|| chrono::Utc::now().timestamp() as u64),
+        ),
+        #[cfg(feature = "attestation")]
+        attestation: attestation_key(&args),
+    };
+    let report = check_response(&response, &options);
+
+    if !args.json {
+        for check in &report.checks {
+            let outcome = match check.outcome {
+                Outcome::Pass => "PASS",
+                Outcome::Fail => "FAIL",
+                Outcome::Skip => "SKIP",
+            };
+            println!("{}  {:<18} {}", outcome, check.name, check.detail);
+        }
+        println!();
+    }
+    println!(
+        "{}",
+        serde_json::to_string(&report).expect("report serializes")
+    );
+
+    match report.passed {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::FAILURE,
+    }
+}
+
+fn read_request(path: &std::path::Path) -> Result<ProverRequest, String> {
+    let data = std::fs::read(path).map_err(|e| e.to_string())?;
+    ProverRequest::from_json(&data)
+        .and_then(ProverRequest::resolve_inputs)
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(feature = "attestation")]
+fn attestation_key(args: &Args) -> Option<prover::response_check::AttestationKey> {
+    use alloy_primitives::U256;
+    use std::borrow::Cow;
+
+    let signer = args.signer?;
+    Some(prover::response_check::AttestationKey {
+        domain: alloy_sol_types::Eip712Domain::new(
+            Some(Cow::Owned(args.domain_name.clone())),
+            Some(Cow::Owned(args.domain_version.clone())),
+            args.chain_id.map(U256::from),
+            args.verifying_contract,
+            None,
+        ),
+        signer,
+    })
+}
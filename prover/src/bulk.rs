@@ -0,0 +1,392 @@
+use crate::audit::AuditLog;
+use crate::config::Config;
+use crate::error::ServiceError;
+use crate::lease::GlobalAdmission;
+use crate::object_store::{GcsObject, ObjectStore, GCS_SCHEME};
+use crate::prover::ProofBackend;
+use crate::service::ProverService;
+use crate::types::{
+    derived_request_id, BulkJob, BulkJobCompletion, BulkJobSummary, BulkLineError, ProofReference,
+    ProofStatus, ProverResponse,
+};
+use chrono::Utc;
+use google_cloud_googleapis::pubsub::v1::PubsubMessage;
+use human_index_lib::WEIGHTS_PROFILES;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+/// Scheme of local paths in bulk jobs, accepted with `BULK_ALLOW_FILE_URIS`
+pub const FILE_SCHEME: &str = "file://";
+
+/// Names of the files a bulk job writes under its `output_uri`
+pub const RESULTS_FILE: &str = "results.jsonl";
+pub const ERRORS_FILE: &str = "errors.jsonl";
+pub const SUMMARY_FILE: &str = "summary.json";
+
+/// `message_type` attribute of a published `BulkJobCompletion`
+pub const COMPLETION_MESSAGE_TYPE: &str = "bulk_job_completed";
+
+/// Where a bulk job reads its input or writes its output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobLocation {
+    Gcs(GcsObject),
+    File(PathBuf),
+}
+
+impl JobLocation {
+    /// Parse a `gs://` URL, or a `file://` path when `allow_files`
+    pub fn parse(uri: &str, allow_files: bool) -> Result<Self, ServiceError> {
+        if let Some(object) = GcsObject::parse(uri) {
+            return object.map(Self::Gcs);
+        }
+        match uri.strip_prefix(FILE_SCHEME) {
+            Some(path) if allow_files && !path.is_empty() => Ok(Self::File(PathBuf::from(path))),
+            Some(_) if allow_files => Err(ServiceError::Config(format!(
+                "Invalid file URI '{}', expected 'file://<path>'",
+                uri
+            ))),
+            Some(_) => Err(ServiceError::Config(format!(
+                "File URI '{}' refused; set BULK_ALLOW_FILE_URIS=true to accept them",
+                uri
+            ))),
+            None => Err(ServiceError::Config(format!(
+                "Unsupported URI '{}', expected '{}' or '{}'",
+                uri, GCS_SCHEME, FILE_SCHEME
+            ))),
+        }
+    }
+
+    /// The file or object `name` under this location as a prefix
+    pub fn join(&self, name: &str) -> Self {
+        match self {
+            Self::Gcs(prefix) => Self::Gcs(GcsObject {
+                bucket: prefix.bucket.clone(),
+                object: format!("{}/{}", prefix.object.trim_end_matches('/'), name),
+            }),
+            Self::File(dir) => Self::File(dir.join(name)),
+        }
+    }
+
+    /// Read the file or object whole, refusing one over `max_bytes`
+    pub async fn read(
+        &self,
+        store: Option<&dyn ObjectStore>,
+        max_bytes: u64,
+    ) -> Result<Vec<u8>, ServiceError> {
+        let size = match self {
+            Self::Gcs(object) => gcs(store)?.size(object).await?,
+            Self::File(path) => tokio::fs::metadata(path)
+                .await
+                .map_err(|e| {
+                    ServiceError::Storage(format!("Failed to read {}: {}", path.display(), e))
+                })?
+                .len(),
+        };
+        if size > max_bytes {
+            return Err(ServiceError::MalformedRequest(format!(
+                "{} is {} bytes, over BULK_MAX_INPUT_BYTES ({})",
+                self, size, max_bytes
+            )));
+        }
+        match self {
+            Self::Gcs(object) => gcs(store)?.get(object).await,
+            Self::File(path) => tokio::fs::read(path).await.map_err(|e| {
+                ServiceError::Storage(format!("Failed to read {}: {}", path.display(), e))
+            }),
+        }
+    }
+
+    /// Write `data` whole; a file appears only once written completely
+    pub async fn write(
+        &self,
+        store: Option<&dyn ObjectStore>,
+        data: Vec<u8>,
+    ) -> Result<(), ServiceError> {
+        match self {
+            Self::Gcs(object) => gcs(store)?.put(object, data).await,
+            Self::File(path) => {
+                let failed = |e: std::io::Error| {
+                    ServiceError::Storage(format!("Failed to write {}: {}", path.display(), e))
+                };
+                if let Some(dir) = path.parent() {
+                    tokio::fs::create_dir_all(dir).await.map_err(failed)?;
+                }
+                let partial = path.with_extension("partial");
+                tokio::fs::write(&partial, data).await.map_err(failed)?;
+                tokio::fs::rename(&partial, path).await.map_err(failed)
+            }
+        }
+    }
+}
+
+impl fmt::Display for JobLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Gcs(object) => write!(f, "{}", object),
+            Self::File(path) => write!(f, "{}{}", FILE_SCHEME, path.display()),
+        }
+    }
+}
+
+fn gcs(store: Option<&dyn ObjectStore>) -> Result<&dyn ObjectStore, ServiceError> {
+    store.ok_or_else(|| ServiceError::Config("Cloud Storage is not available".to_string()))
+}
+
+impl BulkJob {
+    /// Whether either location is in Cloud Storage, so a store is needed
+    pub fn uses_gcs(&self) -> bool {
+        [&self.input_uri, &self.output_uri]
+            .iter()
+            .any(|uri| uri.starts_with(GCS_SCHEME))
+    }
+
+    /// The message data to prove line `line` of the input as, for `results`
+    ///
+    /// Its request ID is derived from the job's and the line number, and the job's
+    /// ID is its `parent_request_id`.
+    pub fn line_request(&self, line: usize, results: Value) -> Result<Vec<u8>, ServiceError> {
+        if !results.is_object() {
+            return Err(ServiceError::MalformedRequest(
+                "line is not a VerificationResults object".to_string(),
+            ));
+        }
+        let weights_profile = match &self.weights_profile {
+            Some(id) => id.as_str(),
+            None => WEIGHTS_PROFILES[WEIGHTS_PROFILES.len() - 1].id,
+        };
+        let request = json!({
+            "request_id": derived_request_id(&format!("{}:{}", self.request_id, line)),
+            "parent_request_id": self.request_id,
+            "verification_results": results,
+            "public_inputs": { "w1": 0, "w2": 0, "w3": 0, "w4": 0, "expected_output": 0 },
+            "weights_profile": weights_profile,
+        });
+        Ok(serde_json::to_vec(&request)?)
+    }
+}
+
+/// Prove every line of `job`'s input and write the output files
+///
+/// Lines are read `bulk_chunk_size` at a time, each proved as a request of its own
+/// through the checks a received request goes through. At most `bulk_permits()`
+/// lines are proved at once, the permits the job holds, and each takes a lease of
+/// `global` while proved. A line that does not parse as `VerificationResults` goes
+/// to the errors file; every other line gets a result, whatever its status.
+/// `observe` sees each result as it is made. Fails, writing nothing, when the input
+/// cannot be read, is over `bulk_max_input_bytes` or the job is interrupted; fails
+/// when the output cannot be written.
+#[allow(clippy::too_many_arguments)]
+pub async fn run_job<B: ProofBackend>(
+    job: &BulkJob,
+    config: &Config,
+    backend: Arc<B>,
+    store: Option<&dyn ObjectStore>,
+    audit: Option<Arc<AuditLog>>,
+    global: Option<Arc<GlobalAdmission>>,
+    interrupt: &CancellationToken,
+    observe: impl Fn(&ProverResponse),
+) -> Result<BulkJobSummary, ServiceError> {
+    let started_at = Utc::now();
+    let input = JobLocation::parse(&job.input_uri, config.bulk_allow_file_uris)?;
+    let output = JobLocation::parse(&job.output_uri, config.bulk_allow_file_uris)?;
+    let data = input.read(store, config.bulk_max_input_bytes).await?;
+    let text = String::from_utf8(data).map_err(|_| {
+        ServiceError::MalformedRequest(format!("{} is not UTF-8 text", job.input_uri))
+    })?;
+    let lines: Vec<(usize, &str)> = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .collect();
+    info!(
+        request_id = %job.request_id,
+        lines = lines.len(),
+        "Bulk job started"
+    );
+
+    let mut results = Vec::new();
+    let mut errors = Vec::new();
+    let mut proofs = Vec::new();
+    let (mut succeeded, mut unsuccessful) = (0, 0);
+    let slots = Arc::new(Semaphore::new(config.bulk_permits()));
+    for (chunk_index, chunk) in lines.chunks(config.bulk_chunk_size).enumerate() {
+        if interrupt.is_cancelled() {
+            return Err(ServiceError::ProofGeneration(format!(
+                "Bulk job interrupted after {} of {} lines",
+                chunk_index * config.bulk_chunk_size,
+                lines.len()
+            )));
+        }
+
+        let mut proving = JoinSet::new();
+        for (i, &(line, text)) in chunk.iter().enumerate() {
+            let data = serde_json::from_str::<Value>(text)
+                .map_err(ServiceError::from)
+                .and_then(|results| job.line_request(line, results));
+            let data = match data {
+                Ok(data) => data,
+                Err(e) => {
+                    errors.push((line, e));
+                    continue;
+                }
+            };
+            let (config, backend) = (config.clone(), backend.clone());
+            let (audit, interrupt) = (audit.clone(), interrupt.clone());
+            let (slots, global) = (slots.clone(), global.clone());
+            proving.spawn(async move {
+                let _slot = slots.acquire_owned().await.expect("slots are never closed");
+                let lease = match &global {
+                    Some(global) => tokio::select! {
+                        lease = global.acquire() => Some(lease),
+                        _ = interrupt.cancelled() => {
+                            let interrupted = ServiceError::ProofGeneration(
+                                "Interrupted waiting for a lease".to_string(),
+                            );
+                            return (i, line, Err(interrupted));
+                        }
+                    },
+                    None => None,
+                };
+                // Quarantine is for redelivered messages; a line is only read once
+                let processed = ProverService::<B>::process_message(
+                    &data,
+                    config,
+                    backend,
+                    audit.as_deref(),
+                    None,
+                    &interrupt,
+                    Utc::now(),
+                )
+                .await;
+                if let Some(lease) = lease {
+                    lease.release().await;
+                }
+                (i, line, processed)
+            });
+        }
+
+        let mut proved = Vec::new();
+        while let Some(joined) = proving.join_next().await {
+            let (i, line, processed) = joined
+                .map_err(|e| ServiceError::ProofGeneration(format!("Task join error: {}", e)))?;
+            match processed {
+                Ok(response) => proved.push((i, line, response)),
+                Err(e) => errors.push((line, e)),
+            }
+        }
+        proved.sort_by_key(|(i, _, _)| *i);
+        for (_, line, response) in proved {
+            observe(&response);
+            match response.status {
+                ProofStatus::Success => succeeded += 1,
+                _ => unsuccessful += 1,
+            }
+            proofs.push(ProofReference {
+                line,
+                request_id: response.request_id.clone(),
+                status: response.status.clone(),
+                proof_sha256: response
+                    .proof_data
+                    .as_ref()
+                    .map(|proof_data| hex::encode(Sha256::digest(proof_data.proof.as_bytes()))),
+            });
+            results.extend(response.encode(config.response_schema_version)?);
+            results.push(b'\n');
+        }
+        info!(
+            request_id = %job.request_id,
+            proved = proofs.len(),
+            lines = lines.len(),
+            "Bulk job chunk done"
+        );
+    }
+
+    // In input order, like the results
+    let mut line_errors: Vec<BulkLineError> = errors
+        .into_iter()
+        .map(|(line, e)| BulkLineError {
+            line,
+            error: e.to_string(),
+        })
+        .collect();
+    line_errors.sort_by_key(|error| error.line);
+    let mut errors_file = Vec::new();
+    for error in &line_errors {
+        errors_file.extend(serde_json::to_vec(error)?);
+        errors_file.push(b'\n');
+    }
+
+    let results_location = output.join(RESULTS_FILE);
+    let errors_location = output.join(ERRORS_FILE);
+    let summary = BulkJobSummary {
+        request_id: job.request_id.clone(),
+        input_uri: job.input_uri.clone(),
+        output_uri: job.output_uri.clone(),
+        results_uri: results_location.to_string(),
+        errors_uri: errors_location.to_string(),
+        lines: lines.len(),
+        succeeded,
+        unsuccessful,
+        rejected: line_errors.len(),
+        results_sha256: hex::encode(Sha256::digest(&results)),
+        proofs,
+        started_at: started_at.to_rfc3339(),
+        completed_at: Utc::now().to_rfc3339(),
+    };
+    results_location.write(store, results).await?;
+    errors_location.write(store, errors_file).await?;
+    // Last, so a summary means the job's output is complete
+    output
+        .join(SUMMARY_FILE)
+        .write(store, serde_json::to_vec_pretty(&summary)?)
+        .await?;
+    info!(
+        request_id = %job.request_id,
+        succeeded = summary.succeeded,
+        unsuccessful = summary.unsuccessful,
+        rejected = summary.rejected,
+        "Bulk job completed"
+    );
+    Ok(summary)
+}
+
+/// The completion of `job`, from what `run_job` returned
+pub fn completion(
+    job: &BulkJob,
+    outcome: &Result<BulkJobSummary, ServiceError>,
+) -> BulkJobCompletion {
+    match outcome {
+        Ok(summary) => {
+            // Only named here; `run_job` checked whether the scheme is allowed
+            let summary_uri = JobLocation::parse(&job.output_uri, true)
+                .map(|output| output.join(SUMMARY_FILE).to_string())
+                .ok();
+            BulkJobCompletion::completed(summary, summary_uri)
+        }
+        Err(e) => BulkJobCompletion::failed(job, e),
+    }
+}
+
+/// The message a bulk job's completion is published as, to the result topic
+///
+/// Its `message_type` attribute tells it apart from a `ProverResponse`.
+pub fn completion_message(completion: &BulkJobCompletion) -> Result<PubsubMessage, ServiceError> {
+    let attributes = HashMap::from([(
+        "message_type".to_string(),
+        COMPLETION_MESSAGE_TYPE.to_string(),
+    )]);
+    Ok(PubsubMessage {
+        data: serde_json::to_vec(completion)?,
+        attributes,
+        ..Default::default()
+    })
+}
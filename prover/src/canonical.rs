@@ -0,0 +1,63 @@
+use crate::types::ProverRequest;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Canonical bytes of the inputs of `request`, the one input of every request hash
+///
+/// The proof cache, the poison request count and the audit log's `input_hash` all
+/// hash these, so requests with the same inputs hash alike however their JSON was
+/// laid out. The request ID and routing fields are not included.
+pub fn canonical_bytes(request: &ProverRequest) -> Vec<u8> {
+    let inputs = serde_json::json!({
+        "verification_results": request.verification_results,
+        "public_inputs": request.public_inputs,
+    });
+    let mut out = Vec::new();
+    write_canonical(&inputs, &mut out);
+    out
+}
+
+/// Canonical JSON of `value`: object keys sorted by their bytes, no whitespace
+///
+/// Independent of the field order of the types and of the key order `serde_json`
+/// keeps maps in, which its `preserve_order` feature changes.
+pub fn canonical_json<T: Serialize>(value: &T) -> Result<Vec<u8>, serde_json::Error> {
+    let value = serde_json::to_value(value)?;
+    let mut out = Vec::new();
+    write_canonical(&value, &mut out);
+    Ok(out)
+}
+
+fn write_canonical(value: &Value, out: &mut Vec<u8>) {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+            out.push(b'{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write_scalar(&Value::String(key.clone()), out);
+                out.push(b':');
+                write_canonical(value, out);
+            }
+            out.push(b'}');
+        }
+        Value::Array(items) => {
+            out.push(b'[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(b']');
+        }
+        scalar => write_scalar(scalar, out),
+    }
+}
+
+fn write_scalar(value: &Value, out: &mut Vec<u8>) {
+    serde_json::to_writer(out, value).expect("JSON scalars serialize");
+}
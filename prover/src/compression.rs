@@ -0,0 +1,85 @@
+use crate::error::ServiceError;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::{Read, Write};
+use std::str::FromStr;
+
+/// zstd level balancing speed and ratio for proof payloads
+const ZSTD_LEVEL: i32 = 3;
+
+/// Compression applied to the proof bytes in a `ProofData`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentEncoding {
+    Gzip,
+    Zstd,
+}
+
+impl ContentEncoding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Zstd => "zstd",
+        }
+    }
+
+    /// Compress `data`
+    ///
+    /// zstd frames carry a content checksum, as gzip always does, so a corrupted
+    /// payload fails to decompress instead of decoding to different bytes.
+    pub fn compress(&self, data: &[u8]) -> Result<Vec<u8>, ServiceError> {
+        let compressed = match self {
+            Self::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()?
+            }
+            Self::Zstd => {
+                let mut encoder = zstd::Encoder::new(Vec::new(), ZSTD_LEVEL)?;
+                encoder.include_checksum(true)?;
+                encoder.write_all(data)?;
+                encoder.finish()?
+            }
+        };
+        Ok(compressed)
+    }
+
+    /// Decompress `data`, failing on corrupted or truncated streams
+    pub fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, ServiceError> {
+        let result = match self {
+            Self::Gzip => {
+                let mut decompressed = Vec::new();
+                GzDecoder::new(data)
+                    .read_to_end(&mut decompressed)
+                    .map(|_| decompressed)
+            }
+            Self::Zstd => zstd::decode_all(data),
+        };
+        result.map_err(|e| ServiceError::Compression(format!("Invalid {} stream: {}", self, e)))
+    }
+}
+
+impl fmt::Display for ContentEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ContentEncoding {
+    type Err = ServiceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "gzip" => Ok(Self::Gzip),
+            "zstd" => Ok(Self::Zstd),
+            other => Err(ServiceError::Compression(format!(
+                "Unsupported content encoding '{}', expected 'gzip' or 'zstd'",
+                other
+            ))),
+        }
+    }
+}
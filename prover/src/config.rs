@@ -1,64 +1,504 @@
+use crate::compression::ContentEncoding;
+use crate::docker::DockerPreflight;
+use crate::endpoints::PubsubEndpoint;
 use crate::error::ServiceError;
+use crate::janitor::JanitorPolicy;
+use crate::memory::{auto_concurrency, MemoryInfo};
+use crate::proof_cache::{CacheLimits, NoStore, ProofCache};
+use crate::prover::{
+    ArtifactEncoding, ProofJsonLayout, ProverProfile, StartupRetry, TimeoutPolicy,
+};
+use crate::scheduler::Lanes;
+use crate::types::{RequestIdFormat, SignalAliases, SignalPolicy, RESPONSE_SCHEMA_VERSION};
+use crate::usage::CostFormula;
+use crate::webhook::CallbackPolicy;
+use human_index_lib::MAX_HUMAN_INDEX;
+use serde::{Serialize, Serializer};
 use std::env;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Which proof backend the service runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProverBackendKind {
+    /// Real proofs via the Pico SDK
+    Pico,
+    /// Native computation with fabricated proofs, for testing
+    Mock,
+}
+
+impl FromStr for ProverBackendKind {
+    type Err = ServiceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "pico" => Ok(Self::Pico),
+            "mock" => Ok(Self::Mock),
+            other => Err(ServiceError::Config(format!(
+                "Invalid PROVER_BACKEND '{}', expected 'pico' or 'mock'",
+                other
+            ))),
+        }
+    }
+}
+
+/// Which part of the pipeline a process runs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Receive, prove and publish in one process
+    #[default]
+    All,
+    /// Validate and dedupe requests, then forward them to `WORK_TOPIC`
+    Frontend,
+    /// Prove work items from `WORK_SUBSCRIPTION` and publish the results
+    Worker,
+}
+
+impl FromStr for Role {
+    type Err = ServiceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "all" => Ok(Self::All),
+            "frontend" => Ok(Self::Frontend),
+            "worker" => Ok(Self::Worker),
+            other => Err(ServiceError::Config(format!(
+                "Invalid ROLE '{}', expected 'all', 'frontend' or 'worker'",
+                other
+            ))),
+        }
+    }
+}
 
 /// Configuration for the prover service loaded from environment variables
-#[derive(Debug, Clone)]
+///
+/// Serializes for `GET /config` with project IDs masked. Holds no credentials:
+/// signing, encryption and Redis settings are read by their own configs.
+#[derive(Debug, Clone, Serialize)]
 pub struct Config {
     /// GCP Project ID
+    #[serde(serialize_with = "serialize_masked")]
     pub gcp_project_id: String,
 
-    /// Subscription name to receive proof requests
+    /// Part of the pipeline this process runs
+    pub role: Role,
+
+    /// Subscription name to receive proof requests; unused by a worker
     pub prover_subscription: String,
 
-    /// Topic name to publish results
+    /// Topic name to publish results; unused by a frontend
     pub result_topic: String,
 
+    /// Topic a frontend forwards work items to
+    pub work_topic: Option<String>,
+
+    /// Subscription a worker receives work items from
+    pub work_subscription: Option<String>,
+
+    /// Topics a request may name in `result_topic` instead of the global one
+    pub result_topic_allowlist: Vec<String>,
+
+    /// Domains a request's `callback_url` may point to, subdomains included; empty
+    /// rejects every `callback_url`
+    pub callback_allowed_domains: Vec<String>,
+
+    /// Attempts to deliver a result to its `callback_url`
+    pub callback_max_attempts: u32,
+
+    /// Delay before the first callback retry in milliseconds; doubles with each retry
+    pub callback_backoff_ms: u64,
+
+    /// Allow plain HTTP and private network addresses for callbacks, for development
+    pub callback_allow_insecure: bool,
+
+    /// Endpoints to receive from, in failover order; empty for the single endpoint
+    /// of `gcp_project_id`, `prover_subscription` and `result_topic`
+    #[serde(serialize_with = "serialize_masked_endpoints")]
+    pub pubsub_endpoints: Vec<PubsubEndpoint>,
+
+    /// Receive errors in a row before failing over to the next endpoint
+    pub pubsub_failover_errors: u32,
+
     /// Maximum number of concurrent proof generations
     pub max_concurrent_proofs: usize,
 
+    /// Whether `max_concurrent_proofs` was sized from system memory
+    pub concurrency_auto: bool,
+
+    /// Share permits between tenants instead of first come, first served
+    pub fair_scheduling: bool,
+
+    /// Permits only interactive requests may use
+    pub interactive_reserved_permits: u32,
+
+    /// Batch requests waiting this long are scheduled as normal ones; 0 disables
+    pub batch_promotion_secs: u64,
+
+    /// Requests waiting longer than this for a permit are logged and counted; 0 disables
+    pub queue_wait_warn_secs: u64,
+
+    /// Estimated peak memory of one proof in GiB
+    pub memory_per_proof_gb: u64,
+
+    /// How long a proof waits for `memory_per_proof_gb` to be available; 0 disables
+    pub memory_wait_secs: u64,
+
+    /// Warm-up after startup over which concurrency ramps up to
+    /// `max_concurrent_proofs`; 0 disables
+    pub concurrency_ramp_secs: u64,
+
     /// Timeout for each proof generation in seconds
     pub proof_timeout_secs: u64,
 
-    /// Path to the ELF file
+    /// Extension granted once to a proof still making progress at the timeout;
+    /// 0 disables
+    pub proof_timeout_extension_secs: u64,
+
+    /// A proof that reported progress within this long before the timeout is
+    /// still making progress
+    pub proof_progress_window_secs: u64,
+
+    /// Retries of `prove_evm` after a transient Docker failure
+    pub prove_retry_attempts: u32,
+
+    /// How long shutdown waits for in-flight proofs before interrupting them
+    pub shutdown_grace_secs: u64,
+
+    /// Requests submitted longer ago than this are failed without proving
+    pub max_request_age_secs: Option<u64>,
+
+    /// Attempts allowed before a request is quarantined; unlimited when unset
+    pub max_processing_attempts: Option<u32>,
+
+    /// Directory for attempt counts and quarantined requests
+    pub quarantine_dir: String,
+
+    /// Path to the ELF file, or its `gs://` URL
     pub elf_path: String,
 
+    /// Lowercase hex SHA-256 the ELF must have, if any
+    pub elf_sha256: Option<String>,
+
+    /// Retries of loading the ELF and resolving the setup files at startup
+    pub startup_retry_attempts: u32,
+
+    /// Delay before the first startup retry in seconds; doubles with each retry
+    pub startup_retry_delay_secs: u64,
+
     /// Output directory for proof artifacts
     pub output_dir: String,
 
+    /// SQLite file holding requests that wait for their `not_before` time
+    pub delayed_queue_path: String,
+
+    /// Published results kept in the `DELAYED_QUEUE_PATH` journal; 0 keeps none
+    pub result_journal_size: usize,
+
+    /// Seconds between sweeps of the output directory after the one at startup;
+    /// 0 sweeps only at startup
+    pub janitor_interval_secs: u64,
+
+    /// Request directories left in the output directory are removed after this long
+    pub request_dir_ttl_secs: u64,
+
+    /// Attempt counts and quarantined requests are removed after this long; kept
+    /// when unset
+    pub quarantine_ttl_secs: Option<u64>,
+
+    /// Budget for the output directory, kept by removing the oldest reclaimable entries
+    pub output_dir_max_bytes: Option<u64>,
+
+    /// Largest `inputs.json` a proof is read from, in bytes
+    pub max_proof_artifact_bytes: u64,
+
+    /// Largest stdin a request is written to, in bytes
+    pub max_stdin_bytes: u64,
+
+    /// Whether the artifact fields of `inputs.json` are encoded in parallel
+    pub artifact_encoding: ArtifactEncoding,
+
+    /// Keys the proof artifacts are read from in `inputs.json`
+    pub proof_json_layout: ProofJsonLayout,
+
+    /// Proofs kept in memory for repeated inputs; 0 disables the cache
+    pub proof_cache_max_entries: usize,
+
+    /// Budget for the serialized size of the proofs kept in memory
+    pub proof_cache_max_bytes: u64,
+
     /// Whether to enable JSON logging
     pub json_logging: bool,
 
     /// Log level (trace, debug, info, warn, error)
     pub log_level: String,
+
+    /// Proof backend to use
+    pub prover_backend: ProverBackendKind,
+
+    /// Proof profile for the Pico backend
+    pub prover_profile: ProverProfile,
+
+    /// Simulated proof duration for the mock backend in milliseconds
+    pub mock_proof_delay_ms: u64,
+
+    /// Identifier of this worker, recorded in the audit log
+    pub worker_id: String,
+
+    /// Compression for proofs in published results, if any
+    pub result_compression: Option<ContentEncoding>,
+
+    /// Schema version of published results; 1 during the migration window
+    pub response_schema_version: u32,
+
+    /// Set an ordering key on published results
+    pub result_ordering: bool,
+
+    /// Lines of a bulk job read and reported on at once
+    pub bulk_chunk_size: usize,
+
+    /// Largest bulk job input read, in bytes
+    pub bulk_max_input_bytes: u64,
+
+    /// Accept `file://` URIs in bulk jobs, for development
+    pub bulk_allow_file_uris: bool,
+
+    /// Include the unproven human index in failed and timed-out results
+    pub emit_provisional_index: bool,
+
+    /// Reject requests that send raw weights instead of a `weights_profile`
+    pub require_named_profile: bool,
+
+    /// Request IDs that are proved; others fail with `InvalidRequestId`
+    pub request_id_format: RequestIdFormat,
+
+    /// Whether out-of-range verification results are clamped or fail the request
+    pub signal_policy: SignalPolicy,
+
+    /// Provider names of the verification results, renamed before a request is parsed
+    pub signal_aliases: SignalAliases,
+
+    /// Requests whose human index is below this are answered `below_threshold`
+    /// without proving; every request is proved when unset
+    pub min_provable_index: Option<u32>,
+
+    /// Rates pricing a proof's resource usage as `cost_units`
+    pub cost_formula: CostFormula,
+
+    /// Port for the status endpoints (`/readyz`); disabled when unset
+    pub status_port: Option<u16>,
+
+    /// Responses `/recent` on the status server keeps; 0 keeps none
+    pub recent_responses: usize,
+
+    /// Port for the Prometheus `/metrics` endpoint; disabled when unset
+    pub metrics_port: Option<u16>,
+
+    /// Minutes between metrics snapshots in `<output_dir>/metrics`; 0 disables them
+    pub metrics_snapshot_mins: u64,
+
+    /// How to handle a Docker memory limit below `docker_min_memory_gb`
+    pub docker_preflight: DockerPreflight,
+
+    /// Minimum Docker memory in GiB for the Groth16 wrap
+    pub docker_min_memory_gb: u64,
 }
 
 impl Config {
     /// Load configuration from environment variables
     pub fn from_env() -> Result<Self, ServiceError> {
         dotenvy::dotenv().ok(); // Load .env file if it exists
+        Self::from_process_env()
+    }
 
+    /// Re-read configuration for a live reload
+    /// Values in the .env file take precedence so edits to it are picked up
+    pub fn reload_from_env() -> Result<Self, ServiceError> {
+        dotenvy::dotenv_override().ok();
+        Self::from_process_env()
+    }
+
+    fn from_process_env() -> Result<Self, ServiceError> {
         let gcp_project_id = env::var("GCP_PROJECT_ID")
             .map_err(|_| ServiceError::Config("GCP_PROJECT_ID not set".to_string()))?;
 
-        let prover_subscription = env::var("PROVER_SUBSCRIPTION")
-            .map_err(|_| ServiceError::Config("PROVER_SUBSCRIPTION not set".to_string()))?;
+        let role = env::var("ROLE")
+            .unwrap_or_else(|_| "all".to_string())
+            .parse::<Role>()?;
 
-        let result_topic = env::var("RESULT_TOPIC")
-            .map_err(|_| ServiceError::Config("RESULT_TOPIC not set".to_string()))?;
+        let pubsub_endpoints = env::var("PUBSUB_ENDPOINTS")
+            .map(|endpoints| {
+                endpoints
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|endpoint| !endpoint.is_empty())
+                    .map(str::parse::<PubsubEndpoint>)
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .unwrap_or_else(|_| Ok(Vec::new()))?;
 
-        let max_concurrent_proofs = env::var("MAX_CONCURRENT_PROOFS")
-            .unwrap_or_else(|_| "2".to_string())
-            .parse::<usize>()
-            .map_err(|e| ServiceError::Config(format!("Invalid MAX_CONCURRENT_PROOFS: {}", e)))?;
+        let pubsub_failover_errors = env::var("PUBSUB_FAILOVER_ERRORS")
+            .unwrap_or_else(|_| "3".to_string())
+            .parse::<u32>()
+            .map_err(|e| ServiceError::Config(format!("Invalid PUBSUB_FAILOVER_ERRORS: {}", e)))?;
+
+        // A worker receives work items instead, and a frontend publishes no results;
+        // with PUBSUB_ENDPOINTS they default to the primary's
+        let primary = pubsub_endpoints.first();
+        let prover_subscription = match (env::var("PROVER_SUBSCRIPTION"), primary) {
+            (Ok(subscription), _) => subscription,
+            (Err(_), Some(primary)) => primary.subscription.clone(),
+            (Err(_), None) if role == Role::Worker => String::new(),
+            (Err(_), None) => {
+                return Err(ServiceError::Config(
+                    "PROVER_SUBSCRIPTION not set".to_string(),
+                ))
+            }
+        };
+
+        let result_topic = match (env::var("RESULT_TOPIC"), primary) {
+            (Ok(topic), _) => topic,
+            (Err(_), Some(primary)) => primary.result_topic.clone(),
+            (Err(_), None) if role == Role::Frontend => String::new(),
+            (Err(_), None) => return Err(ServiceError::Config("RESULT_TOPIC not set".to_string())),
+        };
+
+        let work_topic = env::var("WORK_TOPIC")
+            .ok()
+            .filter(|topic| !topic.is_empty());
+        let work_subscription = env::var("WORK_SUBSCRIPTION")
+            .ok()
+            .filter(|subscription| !subscription.is_empty());
+
+        let result_topic_allowlist = env::var("RESULT_TOPIC_ALLOWLIST")
+            .map(|topics| {
+                topics
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|topic| !topic.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let callback_allowed_domains = env::var("CALLBACK_ALLOWED_DOMAINS")
+            .map(|domains| {
+                domains
+                    .split(',')
+                    .map(|domain| domain.trim().trim_end_matches('.').to_lowercase())
+                    .filter(|domain| !domain.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let callback_max_attempts = env::var("CALLBACK_MAX_ATTEMPTS")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse::<u32>()
+            .map_err(|e| ServiceError::Config(format!("Invalid CALLBACK_MAX_ATTEMPTS: {}", e)))?;
+
+        let callback_backoff_ms = env::var("CALLBACK_BACKOFF_MS")
+            .unwrap_or_else(|_| "1000".to_string())
+            .parse::<u64>()
+            .map_err(|e| ServiceError::Config(format!("Invalid CALLBACK_BACKOFF_MS: {}", e)))?;
+
+        let callback_allow_insecure = env::var("CALLBACK_ALLOW_INSECURE")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .map_err(|e| ServiceError::Config(format!("Invalid CALLBACK_ALLOW_INSECURE: {}", e)))?;
+
+        let memory_per_proof_gb = env::var("MEMORY_PER_PROOF_GB")
+            .unwrap_or_else(|_| "16".to_string())
+            .parse::<u64>()
+            .map_err(|e| ServiceError::Config(format!("Invalid MEMORY_PER_PROOF_GB: {}", e)))?;
+
+        let memory_wait_secs = env::var("MEMORY_WAIT_SECS")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<u64>()
+            .map_err(|e| ServiceError::Config(format!("Invalid MEMORY_WAIT_SECS: {}", e)))?;
+
+        let max_concurrent_proofs =
+            env::var("MAX_CONCURRENT_PROOFS").unwrap_or_else(|_| "2".to_string());
+        let concurrency_auto = max_concurrent_proofs == "auto";
+        let max_concurrent_proofs = if concurrency_auto {
+            auto_concurrency(MemoryInfo::read()?.total_bytes, memory_per_proof_gb)
+        } else {
+            max_concurrent_proofs.parse::<usize>().map_err(|e| {
+                ServiceError::Config(format!("Invalid MAX_CONCURRENT_PROOFS: {}", e))
+            })?
+        };
+
+        let concurrency_ramp_secs = env::var("CONCURRENCY_RAMP_SECS")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<u64>()
+            .map_err(|e| ServiceError::Config(format!("Invalid CONCURRENCY_RAMP_SECS: {}", e)))?;
 
         let proof_timeout_secs = env::var("PROOF_TIMEOUT_SECS")
             .unwrap_or_else(|_| "3600".to_string()) // Default 1 hour
             .parse::<u64>()
             .map_err(|e| ServiceError::Config(format!("Invalid PROOF_TIMEOUT_SECS: {}", e)))?;
 
+        let proof_timeout_extension_secs = env::var("PROOF_TIMEOUT_EXTENSION_SECS")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<u64>()
+            .map_err(|e| {
+                ServiceError::Config(format!("Invalid PROOF_TIMEOUT_EXTENSION_SECS: {}", e))
+            })?;
+
+        let proof_progress_window_secs = env::var("PROOF_PROGRESS_WINDOW_SECS")
+            .unwrap_or_else(|_| "60".to_string())
+            .parse::<u64>()
+            .map_err(|e| {
+                ServiceError::Config(format!("Invalid PROOF_PROGRESS_WINDOW_SECS: {}", e))
+            })?;
+
+        let shutdown_grace_secs = env::var("SHUTDOWN_GRACE_SECS")
+            .unwrap_or_else(|_| "8".to_string()) // Within Cloud Run's 10s termination window
+            .parse::<u64>()
+            .map_err(|e| ServiceError::Config(format!("Invalid SHUTDOWN_GRACE_SECS: {}", e)))?;
+
+        let max_request_age_secs = env::var("MAX_REQUEST_AGE_SECS")
+            .ok()
+            .map(|secs| secs.parse::<u64>())
+            .transpose()
+            .map_err(|e| ServiceError::Config(format!("Invalid MAX_REQUEST_AGE_SECS: {}", e)))?;
+
+        let max_processing_attempts = env::var("MAX_PROCESSING_ATTEMPTS")
+            .ok()
+            .map(|attempts| attempts.parse::<u32>())
+            .transpose()
+            .map_err(|e| ServiceError::Config(format!("Invalid MAX_PROCESSING_ATTEMPTS: {}", e)))?;
+
         let elf_path = env::var("ELF_PATH")
             .unwrap_or_else(|_| "../app/elf/riscv32im-pico-zkvm-elf".to_string());
 
+        let elf_sha256 = env::var("ELF_SHA256")
+            .ok()
+            .map(|digest| {
+                let digest = digest.trim().to_lowercase();
+                if digest.len() == 64 && digest.bytes().all(|b| b.is_ascii_hexdigit()) {
+                    Ok(digest)
+                } else {
+                    Err(ServiceError::Config(format!(
+                        "Invalid ELF_SHA256: expected 64 hex digits, got '{}'",
+                        digest
+                    )))
+                }
+            })
+            .transpose()?;
+
+        let startup_retry_attempts = env::var("STARTUP_RETRY_ATTEMPTS")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse::<u32>()
+            .map_err(|e| ServiceError::Config(format!("Invalid STARTUP_RETRY_ATTEMPTS: {}", e)))?;
+
+        let startup_retry_delay_secs = env::var("STARTUP_RETRY_DELAY_SECS")
+            .unwrap_or_else(|_| "2".to_string())
+            .parse::<u64>()
+            .map_err(|e| {
+                ServiceError::Config(format!("Invalid STARTUP_RETRY_DELAY_SECS: {}", e))
+            })?;
+
         // Default to prover/data relative to the cargo manifest directory
         let output_dir = env::var("OUTPUT_DIR")
             .unwrap_or_else(|_| {
@@ -66,6 +506,69 @@ impl Config {
                 format!("{}/data", manifest_dir)
             });
 
+        let delayed_queue_path = env::var("DELAYED_QUEUE_PATH")
+            .unwrap_or_else(|_| format!("{}/delayed.sqlite", output_dir));
+
+        let result_journal_size = env::var("RESULT_JOURNAL_SIZE")
+            .unwrap_or_else(|_| "10000".to_string())
+            .parse::<usize>()
+            .map_err(|e| ServiceError::Config(format!("Invalid RESULT_JOURNAL_SIZE: {}", e)))?;
+
+        let janitor_interval_secs = env::var("JANITOR_INTERVAL_SECS")
+            .unwrap_or_else(|_| "3600".to_string())
+            .parse::<u64>()
+            .map_err(|e| ServiceError::Config(format!("Invalid JANITOR_INTERVAL_SECS: {}", e)))?;
+
+        let request_dir_ttl_secs = env::var("REQUEST_DIR_TTL_SECS")
+            .unwrap_or_else(|_| "86400".to_string())
+            .parse::<u64>()
+            .map_err(|e| ServiceError::Config(format!("Invalid REQUEST_DIR_TTL_SECS: {}", e)))?;
+
+        let quarantine_ttl_secs = env::var("QUARANTINE_TTL_SECS")
+            .ok()
+            .map(|secs| secs.parse::<u64>())
+            .transpose()
+            .map_err(|e| ServiceError::Config(format!("Invalid QUARANTINE_TTL_SECS: {}", e)))?;
+
+        let output_dir_max_bytes = env::var("OUTPUT_DIR_MAX_BYTES")
+            .ok()
+            .map(|bytes| bytes.parse::<u64>())
+            .transpose()
+            .map_err(|e| ServiceError::Config(format!("Invalid OUTPUT_DIR_MAX_BYTES: {}", e)))?;
+
+        let max_proof_artifact_bytes = env::var("MAX_PROOF_ARTIFACT_BYTES")
+            .unwrap_or_else(|_| (64 * 1024 * 1024).to_string())
+            .parse::<u64>()
+            .map_err(|e| {
+                ServiceError::Config(format!("Invalid MAX_PROOF_ARTIFACT_BYTES: {}", e))
+            })?;
+
+        let max_stdin_bytes = env::var("MAX_STDIN_BYTES")
+            .unwrap_or_else(|_| (1024 * 1024).to_string())
+            .parse::<u64>()
+            .map_err(|e| ServiceError::Config(format!("Invalid MAX_STDIN_BYTES: {}", e)))?;
+
+        let artifact_encoding = env::var("ARTIFACT_ENCODING")
+            .unwrap_or_else(|_| "parallel".to_string())
+            .parse::<ArtifactEncoding>()?;
+
+        let proof_json_layout = env::var("PROOF_JSON_KEYS")
+            .unwrap_or_else(|_| "pico_v1".to_string())
+            .parse::<ProofJsonLayout>()?;
+
+        let proof_cache_max_entries = env::var("PROOF_CACHE_MAX_ENTRIES")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<usize>()
+            .map_err(|e| ServiceError::Config(format!("Invalid PROOF_CACHE_MAX_ENTRIES: {}", e)))?;
+
+        let proof_cache_max_bytes = env::var("PROOF_CACHE_MAX_BYTES")
+            .unwrap_or_else(|_| (256 * 1024 * 1024).to_string())
+            .parse::<u64>()
+            .map_err(|e| ServiceError::Config(format!("Invalid PROOF_CACHE_MAX_BYTES: {}", e)))?;
+
+        let quarantine_dir =
+            env::var("QUARANTINE_DIR").unwrap_or_else(|_| format!("{}/quarantine", output_dir));
+
         let json_logging = env::var("JSON_LOGGING")
             .unwrap_or_else(|_| "false".to_string())
             .parse::<bool>()
@@ -74,41 +577,926 @@ impl Config {
         let log_level = env::var("LOG_LEVEL")
             .unwrap_or_else(|_| "info".to_string());
 
+        let prover_backend = env::var("PROVER_BACKEND")
+            .unwrap_or_else(|_| "pico".to_string())
+            .parse::<ProverBackendKind>()?;
+
+        let prover_profile = env::var("PROVER_PROFILE")
+            .unwrap_or_else(|_| "evm".to_string())
+            .parse::<ProverProfile>()?;
+
+        let mock_proof_delay_ms = env::var("MOCK_PROOF_DELAY_MS")
+            .unwrap_or_else(|_| "100".to_string())
+            .parse::<u64>()
+            .map_err(|e| ServiceError::Config(format!("Invalid MOCK_PROOF_DELAY_MS: {}", e)))?;
+
+        let worker_id = env::var("WORKER_ID")
+            .or_else(|_| env::var("HOSTNAME"))
+            .unwrap_or_else(|_| "unknown".to_string());
+
+        let result_compression = match env::var("RESULT_COMPRESSION") {
+            Ok(value) if !value.is_empty() && value != "none" => {
+                Some(value.parse::<ContentEncoding>().map_err(|e| {
+                    ServiceError::Config(format!("Invalid RESULT_COMPRESSION: {}", e))
+                })?)
+            }
+            _ => None,
+        };
+
+        let response_schema_version = env::var("RESPONSE_SCHEMA_VERSION")
+            .unwrap_or_else(|_| RESPONSE_SCHEMA_VERSION.to_string())
+            .parse::<u32>()
+            .map_err(|e| ServiceError::Config(format!("Invalid RESPONSE_SCHEMA_VERSION: {}", e)))?;
+
+        let prove_retry_attempts = env::var("PROVE_RETRY_ATTEMPTS")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<u32>()
+            .map_err(|e| ServiceError::Config(format!("Invalid PROVE_RETRY_ATTEMPTS: {}", e)))?;
+
+        let fair_scheduling = env::var("FAIR_SCHEDULING")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .map_err(|e| ServiceError::Config(format!("Invalid FAIR_SCHEDULING: {}", e)))?;
+
+        let interactive_reserved_permits = env::var("INTERACTIVE_RESERVED_PERMITS")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<u32>()
+            .map_err(|e| {
+                ServiceError::Config(format!("Invalid INTERACTIVE_RESERVED_PERMITS: {}", e))
+            })?;
+
+        let batch_promotion_secs = env::var("BATCH_PROMOTION_SECS")
+            .unwrap_or_else(|_| "3600".to_string())
+            .parse::<u64>()
+            .map_err(|e| ServiceError::Config(format!("Invalid BATCH_PROMOTION_SECS: {}", e)))?;
+
+        let queue_wait_warn_secs = env::var("QUEUE_WAIT_WARN_SECS")
+            .unwrap_or_else(|_| "300".to_string())
+            .parse::<u64>()
+            .map_err(|e| ServiceError::Config(format!("Invalid QUEUE_WAIT_WARN_SECS: {}", e)))?;
+
+        let result_ordering = env::var("RESULT_ORDERING")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .map_err(|e| ServiceError::Config(format!("Invalid RESULT_ORDERING: {}", e)))?;
+
+        let bulk_chunk_size = env::var("BULK_CHUNK_SIZE")
+            .unwrap_or_else(|_| "4".to_string())
+            .parse::<usize>()
+            .map_err(|e| ServiceError::Config(format!("Invalid BULK_CHUNK_SIZE: {}", e)))?;
+
+        let bulk_max_input_bytes = env::var("BULK_MAX_INPUT_BYTES")
+            .unwrap_or_else(|_| "67108864".to_string())
+            .parse::<u64>()
+            .map_err(|e| ServiceError::Config(format!("Invalid BULK_MAX_INPUT_BYTES: {}", e)))?;
+
+        let bulk_allow_file_uris = env::var("BULK_ALLOW_FILE_URIS")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .map_err(|e| ServiceError::Config(format!("Invalid BULK_ALLOW_FILE_URIS: {}", e)))?;
+
+        let emit_provisional_index = env::var("EMIT_PROVISIONAL_INDEX")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .map_err(|e| ServiceError::Config(format!("Invalid EMIT_PROVISIONAL_INDEX: {}", e)))?;
+
+        let require_named_profile = env::var("REQUIRE_NAMED_PROFILE")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .map_err(|e| ServiceError::Config(format!("Invalid REQUIRE_NAMED_PROFILE: {}", e)))?;
+
+        let request_id_format = match env::var("REQUEST_ID_FORMAT") {
+            Ok(format) => format.parse::<RequestIdFormat>()?,
+            Err(_) => RequestIdFormat::default(),
+        };
+
+        let signal_policy = match env::var("SIGNAL_POLICY") {
+            Ok(policy) => policy.parse::<SignalPolicy>()?,
+            Err(_) => SignalPolicy::default(),
+        };
+
+        let signal_aliases = match env::var("SIGNAL_ALIASES") {
+            Ok(aliases) => aliases.parse::<SignalAliases>()?,
+            Err(_) => SignalAliases::default(),
+        };
+
+        let min_provable_index = env::var("MIN_PROVABLE_INDEX")
+            .ok()
+            .map(|index| index.parse::<u32>())
+            .transpose()
+            .map_err(|e| ServiceError::Config(format!("Invalid MIN_PROVABLE_INDEX: {}", e)))?;
+        if let Some(index) = min_provable_index.filter(|&index| index > MAX_HUMAN_INDEX) {
+            return Err(ServiceError::Config(format!(
+                "Invalid MIN_PROVABLE_INDEX: {} is above the maximum human index {}",
+                index, MAX_HUMAN_INDEX
+            )));
+        }
+
+        let cost_formula = match env::var("COST_FORMULA") {
+            Ok(formula) => formula.parse::<CostFormula>()?,
+            Err(_) => CostFormula::default(),
+        };
+
+        let status_port = env::var("STATUS_PORT")
+            .ok()
+            .map(|port| port.parse::<u16>())
+            .transpose()
+            .map_err(|e| ServiceError::Config(format!("Invalid STATUS_PORT: {}", e)))?;
+
+        let recent_responses = env::var("RECENT_RESPONSES")
+            .unwrap_or_else(|_| "100".to_string())
+            .parse::<usize>()
+            .map_err(|e| ServiceError::Config(format!("Invalid RECENT_RESPONSES: {}", e)))?;
+
+        let metrics_port = env::var("METRICS_PORT")
+            .ok()
+            .map(|port| port.parse::<u16>())
+            .transpose()
+            .map_err(|e| ServiceError::Config(format!("Invalid METRICS_PORT: {}", e)))?;
+
+        let metrics_snapshot_mins = env::var("METRICS_SNAPSHOT_MINS")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<u64>()
+            .map_err(|e| ServiceError::Config(format!("Invalid METRICS_SNAPSHOT_MINS: {}", e)))?;
+
+        let docker_preflight = env::var("DOCKER_PREFLIGHT")
+            .unwrap_or_else(|_| "warn".to_string())
+            .parse::<DockerPreflight>()?;
+
+        let docker_min_memory_gb = env::var("DOCKER_MIN_MEMORY_GB")
+            .unwrap_or_else(|_| "32".to_string())
+            .parse::<u64>()
+            .map_err(|e| ServiceError::Config(format!("Invalid DOCKER_MIN_MEMORY_GB: {}", e)))?;
+
         Ok(Self {
             gcp_project_id,
+            role,
             prover_subscription,
             result_topic,
+            work_topic,
+            work_subscription,
+            result_topic_allowlist,
+            callback_allowed_domains,
+            callback_max_attempts,
+            callback_backoff_ms,
+            callback_allow_insecure,
+            pubsub_endpoints,
+            pubsub_failover_errors,
             max_concurrent_proofs,
+            concurrency_auto,
+            fair_scheduling,
+            interactive_reserved_permits,
+            batch_promotion_secs,
+            queue_wait_warn_secs,
+            memory_per_proof_gb,
+            memory_wait_secs,
+            concurrency_ramp_secs,
             proof_timeout_secs,
+            proof_timeout_extension_secs,
+            proof_progress_window_secs,
+            prove_retry_attempts,
+            shutdown_grace_secs,
+            max_request_age_secs,
+            max_processing_attempts,
+            quarantine_dir,
             elf_path,
+            elf_sha256,
+            startup_retry_attempts,
+            startup_retry_delay_secs,
             output_dir,
+            delayed_queue_path,
+            result_journal_size,
+            janitor_interval_secs,
+            request_dir_ttl_secs,
+            quarantine_ttl_secs,
+            output_dir_max_bytes,
+            max_proof_artifact_bytes,
+            max_stdin_bytes,
+            artifact_encoding,
+            proof_json_layout,
+            proof_cache_max_entries,
+            proof_cache_max_bytes,
             json_logging,
             log_level,
+            prover_backend,
+            prover_profile,
+            mock_proof_delay_ms,
+            worker_id,
+            result_compression,
+            response_schema_version,
+            result_ordering,
+            bulk_chunk_size,
+            bulk_max_input_bytes,
+            bulk_allow_file_uris,
+            emit_provisional_index,
+            require_named_profile,
+            request_id_format,
+            signal_policy,
+            signal_aliases,
+            min_provable_index,
+            cost_formula,
+            status_port,
+            recent_responses,
+            metrics_port,
+            metrics_snapshot_mins,
+            docker_preflight,
+            docker_min_memory_gb,
         })
     }
 
     /// Validate configuration
     pub fn validate(&self) -> Result<(), ServiceError> {
+        if self.role == Role::Frontend && self.work_topic.is_none() {
+            return Err(ServiceError::Config(
+                "WORK_TOPIC must be set with ROLE=frontend".to_string(),
+            ));
+        }
+
+        if self.role == Role::Worker && self.work_subscription.is_none() {
+            return Err(ServiceError::Config(
+                "WORK_SUBSCRIPTION must be set with ROLE=worker".to_string(),
+            ));
+        }
+
+        if self.metrics_port.is_some() && self.metrics_port == self.status_port {
+            return Err(ServiceError::Config(
+                "METRICS_PORT must differ from STATUS_PORT".to_string(),
+            ));
+        }
+
+        if !self.pubsub_endpoints.is_empty() && self.role != Role::All {
+            return Err(ServiceError::Config(
+                "PUBSUB_ENDPOINTS is only supported with ROLE=all".to_string(),
+            ));
+        }
+
+        if self.pubsub_failover_errors == 0 {
+            return Err(ServiceError::Config(
+                "PUBSUB_FAILOVER_ERRORS must be greater than 0".to_string(),
+            ));
+        }
+
+        if self.callback_max_attempts == 0 {
+            return Err(ServiceError::Config(
+                "CALLBACK_MAX_ATTEMPTS must be greater than 0".to_string(),
+            ));
+        }
+
         if self.max_concurrent_proofs == 0 {
             return Err(ServiceError::Config(
                 "MAX_CONCURRENT_PROOFS must be greater than 0".to_string(),
             ));
         }
 
+        if self.bulk_chunk_size == 0 {
+            return Err(ServiceError::Config(
+                "BULK_CHUNK_SIZE must be greater than 0".to_string(),
+            ));
+        }
+
+        if self.bulk_max_input_bytes == 0 {
+            return Err(ServiceError::Config(
+                "BULK_MAX_INPUT_BYTES must be greater than 0".to_string(),
+            ));
+        }
+
+        if self.memory_per_proof_gb == 0 {
+            return Err(ServiceError::Config(
+                "MEMORY_PER_PROOF_GB must be greater than 0".to_string(),
+            ));
+        }
+
+        if self.interactive_reserved_permits as usize >= self.max_concurrent_proofs
+            && self.interactive_reserved_permits > 0
+        {
+            return Err(ServiceError::Config(format!(
+                "INTERACTIVE_RESERVED_PERMITS ({}) must be less than MAX_CONCURRENT_PROOFS ({})",
+                self.interactive_reserved_permits, self.max_concurrent_proofs
+            )));
+        }
+
+        if self.max_processing_attempts == Some(0) {
+            return Err(ServiceError::Config(
+                "MAX_PROCESSING_ATTEMPTS must be greater than 0".to_string(),
+            ));
+        }
+
         if self.proof_timeout_secs == 0 {
             return Err(ServiceError::Config(
                 "PROOF_TIMEOUT_SECS must be greater than 0".to_string(),
             ));
         }
 
-        // Validate ELF file exists
-        if !std::path::Path::new(&self.elf_path).exists() {
+        if self.proof_timeout_extension_secs > 0 && self.proof_progress_window_secs == 0 {
+            return Err(ServiceError::Config(
+                "PROOF_PROGRESS_WINDOW_SECS must be greater than 0 with PROOF_TIMEOUT_EXTENSION_SECS set"
+                    .to_string(),
+            ));
+        }
+
+        if self.max_proof_artifact_bytes == 0 {
+            return Err(ServiceError::Config(
+                "MAX_PROOF_ARTIFACT_BYTES must be greater than 0".to_string(),
+            ));
+        }
+
+        if self.max_stdin_bytes == 0 {
+            return Err(ServiceError::Config(
+                "MAX_STDIN_BYTES must be greater than 0".to_string(),
+            ));
+        }
+
+        if !(1..=RESPONSE_SCHEMA_VERSION).contains(&self.response_schema_version) {
             return Err(ServiceError::Config(format!(
-                "ELF file not found at: {}",
-                self.elf_path
+                "RESPONSE_SCHEMA_VERSION must be between 1 and {}",
+                RESPONSE_SCHEMA_VERSION
             )));
         }
 
+        // The ELF is not checked here: it may be on a volume that is not mounted
+        // yet, and `load_and_cache_elf` retries it
+
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Compare against a newly loaded configuration
+    /// Settings that can be applied live are reported separately from those needing a restart
+    pub fn diff(&self, new: &Config) -> ConfigChanges {
+        let mut changes = ConfigChanges::default();
+
+        if self.log_level != new.log_level {
+            changes.live.push(format!(
+                "LOG_LEVEL: {} -> {}",
+                self.log_level, new.log_level
+            ));
+        }
+        if self.max_concurrent_proofs != new.max_concurrent_proofs {
+            changes.live.push(format!(
+                "MAX_CONCURRENT_PROOFS: {} -> {}",
+                self.max_concurrent_proofs, new.max_concurrent_proofs
+            ));
+        }
+        if self.fair_scheduling != new.fair_scheduling {
+            changes.live.push(format!(
+                "FAIR_SCHEDULING: {} -> {}",
+                self.fair_scheduling, new.fair_scheduling
+            ));
+        }
+        if self.interactive_reserved_permits != new.interactive_reserved_permits
+            || self.batch_promotion_secs != new.batch_promotion_secs
+        {
+            changes.live.push(format!(
+                "INTERACTIVE_RESERVED_PERMITS/BATCH_PROMOTION_SECS: {}/{} -> {}/{}",
+                self.interactive_reserved_permits,
+                self.batch_promotion_secs,
+                new.interactive_reserved_permits,
+                new.batch_promotion_secs
+            ));
+        }
+        if self.queue_wait_warn_secs != new.queue_wait_warn_secs {
+            changes.live.push(format!(
+                "QUEUE_WAIT_WARN_SECS: {} -> {}",
+                self.queue_wait_warn_secs, new.queue_wait_warn_secs
+            ));
+        }
+        if self.proof_timeout_secs != new.proof_timeout_secs {
+            changes.live.push(format!(
+                "PROOF_TIMEOUT_SECS: {} -> {}",
+                self.proof_timeout_secs, new.proof_timeout_secs
+            ));
+        }
+        if self.proof_timeout_extension_secs != new.proof_timeout_extension_secs
+            || self.proof_progress_window_secs != new.proof_progress_window_secs
+        {
+            changes.live.push(format!(
+                "PROOF_TIMEOUT_EXTENSION_SECS/PROOF_PROGRESS_WINDOW_SECS: {}/{} -> {}/{}",
+                self.proof_timeout_extension_secs,
+                self.proof_progress_window_secs,
+                new.proof_timeout_extension_secs,
+                new.proof_progress_window_secs
+            ));
+        }
+        if self.pubsub_failover_errors != new.pubsub_failover_errors {
+            changes.live.push(format!(
+                "PUBSUB_FAILOVER_ERRORS: {} -> {}",
+                self.pubsub_failover_errors, new.pubsub_failover_errors
+            ));
+        }
+        if self.prove_retry_attempts != new.prove_retry_attempts {
+            changes.live.push(format!(
+                "PROVE_RETRY_ATTEMPTS: {} -> {}",
+                self.prove_retry_attempts, new.prove_retry_attempts
+            ));
+        }
+        if self.memory_per_proof_gb != new.memory_per_proof_gb
+            || self.memory_wait_secs != new.memory_wait_secs
+        {
+            changes.live.push(format!(
+                "MEMORY_PER_PROOF_GB/MEMORY_WAIT_SECS: {}/{} -> {}/{}",
+                self.memory_per_proof_gb,
+                self.memory_wait_secs,
+                new.memory_per_proof_gb,
+                new.memory_wait_secs
+            ));
+        }
+        if self.max_request_age_secs != new.max_request_age_secs {
+            changes.live.push(format!(
+                "MAX_REQUEST_AGE_SECS: {:?} -> {:?}",
+                self.max_request_age_secs, new.max_request_age_secs
+            ));
+        }
+        if self.result_compression != new.result_compression {
+            changes.live.push(format!(
+                "RESULT_COMPRESSION: {:?} -> {:?}",
+                self.result_compression, new.result_compression
+            ));
+        }
+        if self.result_ordering != new.result_ordering {
+            changes.live.push(format!(
+                "RESULT_ORDERING: {} -> {}",
+                self.result_ordering, new.result_ordering
+            ));
+        }
+        if self.bulk_chunk_size != new.bulk_chunk_size
+            || self.bulk_max_input_bytes != new.bulk_max_input_bytes
+            || self.bulk_allow_file_uris != new.bulk_allow_file_uris
+        {
+            changes.live.push(format!(
+                "BULK_CHUNK_SIZE/BULK_MAX_INPUT_BYTES/BULK_ALLOW_FILE_URIS: {}/{}/{} -> {}/{}/{}",
+                self.bulk_chunk_size,
+                self.bulk_max_input_bytes,
+                self.bulk_allow_file_uris,
+                new.bulk_chunk_size,
+                new.bulk_max_input_bytes,
+                new.bulk_allow_file_uris
+            ));
+        }
+        if self.callback_allowed_domains != new.callback_allowed_domains
+            || self.callback_allow_insecure != new.callback_allow_insecure
+        {
+            changes.live.push(format!(
+                "CALLBACK_ALLOWED_DOMAINS/CALLBACK_ALLOW_INSECURE: {}/{} -> {}/{}",
+                self.callback_allowed_domains.join(","),
+                self.callback_allow_insecure,
+                new.callback_allowed_domains.join(","),
+                new.callback_allow_insecure
+            ));
+        }
+        if self.callback_max_attempts != new.callback_max_attempts
+            || self.callback_backoff_ms != new.callback_backoff_ms
+        {
+            changes.live.push(format!(
+                "CALLBACK_MAX_ATTEMPTS/CALLBACK_BACKOFF_MS: {}/{} -> {}/{}",
+                self.callback_max_attempts,
+                self.callback_backoff_ms,
+                new.callback_max_attempts,
+                new.callback_backoff_ms
+            ));
+        }
+        if self.emit_provisional_index != new.emit_provisional_index {
+            changes.live.push(format!(
+                "EMIT_PROVISIONAL_INDEX: {} -> {}",
+                self.emit_provisional_index, new.emit_provisional_index
+            ));
+        }
+        if self.require_named_profile != new.require_named_profile {
+            changes.live.push(format!(
+                "REQUIRE_NAMED_PROFILE: {} -> {}",
+                self.require_named_profile, new.require_named_profile
+            ));
+        }
+        if self.request_id_format != new.request_id_format {
+            changes.live.push(format!(
+                "REQUEST_ID_FORMAT: {:?} -> {:?}",
+                self.request_id_format, new.request_id_format
+            ));
+        }
+        if self.signal_policy != new.signal_policy {
+            changes.live.push(format!(
+                "SIGNAL_POLICY: {:?} -> {:?}",
+                self.signal_policy, new.signal_policy
+            ));
+        }
+        if self.signal_aliases != new.signal_aliases {
+            changes.live.push(format!(
+                "SIGNAL_ALIASES: {:?} -> {:?}",
+                self.signal_aliases, new.signal_aliases
+            ));
+        }
+        if self.min_provable_index != new.min_provable_index {
+            changes.live.push(format!(
+                "MIN_PROVABLE_INDEX: {:?} -> {:?}",
+                self.min_provable_index, new.min_provable_index
+            ));
+        }
+        if self.cost_formula != new.cost_formula {
+            changes.live.push(format!(
+                "COST_FORMULA: {:?} -> {:?}",
+                self.cost_formula.terms, new.cost_formula.terms
+            ));
+        }
+        if self.response_schema_version != new.response_schema_version {
+            changes.live.push(format!(
+                "RESPONSE_SCHEMA_VERSION: {} -> {}",
+                self.response_schema_version, new.response_schema_version
+            ));
+        }
+        if self.result_journal_size != new.result_journal_size {
+            changes.live.push(format!(
+                "RESULT_JOURNAL_SIZE: {} -> {}",
+                self.result_journal_size, new.result_journal_size
+            ));
+        }
+        if self.janitor_policy() != new.janitor_policy()
+            || self.janitor_interval_secs != new.janitor_interval_secs
+        {
+            changes.live.push(format!(
+                "JANITOR_INTERVAL_SECS/REQUEST_DIR_TTL_SECS/QUARANTINE_TTL_SECS/OUTPUT_DIR_MAX_BYTES: {}/{}/{:?}/{:?} -> {}/{}/{:?}/{:?}",
+                self.janitor_interval_secs,
+                self.request_dir_ttl_secs,
+                self.quarantine_ttl_secs,
+                self.output_dir_max_bytes,
+                new.janitor_interval_secs,
+                new.request_dir_ttl_secs,
+                new.quarantine_ttl_secs,
+                new.output_dir_max_bytes
+            ));
+        }
+
+        let structural = [
+            ("GCP_PROJECT_ID", &self.gcp_project_id, &new.gcp_project_id),
+            (
+                "PROVER_SUBSCRIPTION",
+                &self.prover_subscription,
+                &new.prover_subscription,
+            ),
+            ("RESULT_TOPIC", &self.result_topic, &new.result_topic),
+            ("ELF_PATH", &self.elf_path, &new.elf_path),
+            ("OUTPUT_DIR", &self.output_dir, &new.output_dir),
+            ("WORKER_ID", &self.worker_id, &new.worker_id),
+            ("QUARANTINE_DIR", &self.quarantine_dir, &new.quarantine_dir),
+            (
+                "DELAYED_QUEUE_PATH",
+                &self.delayed_queue_path,
+                &new.delayed_queue_path,
+            ),
+        ];
+        for (name, old, new) in structural {
+            if old != new {
+                changes
+                    .restart_required
+                    .push(format!("{}: {} -> {}", name, old, new));
+            }
+        }
+        let work = [
+            ("WORK_TOPIC", &self.work_topic, &new.work_topic),
+            (
+                "WORK_SUBSCRIPTION",
+                &self.work_subscription,
+                &new.work_subscription,
+            ),
+        ];
+        for (name, old, new) in work {
+            if old != new {
+                changes
+                    .restart_required
+                    .push(format!("{}: {:?} -> {:?}", name, old, new));
+            }
+        }
+        if self.elf_sha256 != new.elf_sha256 {
+            changes.restart_required.push(format!(
+                "ELF_SHA256: {:?} -> {:?}",
+                self.elf_sha256, new.elf_sha256
+            ));
+        }
+        if self.pubsub_endpoints != new.pubsub_endpoints {
+            let list = |endpoints: &[PubsubEndpoint]| {
+                endpoints
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(",")
+            };
+            changes.restart_required.push(format!(
+                "PUBSUB_ENDPOINTS: {} -> {}",
+                list(&self.pubsub_endpoints),
+                list(&new.pubsub_endpoints)
+            ));
+        }
+        if self.role != new.role {
+            changes
+                .restart_required
+                .push(format!("ROLE: {:?} -> {:?}", self.role, new.role));
+        }
+        if self.prover_backend != new.prover_backend {
+            changes.restart_required.push(format!(
+                "PROVER_BACKEND: {:?} -> {:?}",
+                self.prover_backend, new.prover_backend
+            ));
+        }
+        if self.prover_profile != new.prover_profile {
+            changes.restart_required.push(format!(
+                "PROVER_PROFILE: {:?} -> {:?}",
+                self.prover_profile, new.prover_profile
+            ));
+        }
+        if self.max_proof_artifact_bytes != new.max_proof_artifact_bytes {
+            changes.restart_required.push(format!(
+                "MAX_PROOF_ARTIFACT_BYTES: {} -> {}",
+                self.max_proof_artifact_bytes, new.max_proof_artifact_bytes
+            ));
+        }
+        if self.max_stdin_bytes != new.max_stdin_bytes {
+            changes.restart_required.push(format!(
+                "MAX_STDIN_BYTES: {} -> {}",
+                self.max_stdin_bytes, new.max_stdin_bytes
+            ));
+        }
+        if self.artifact_encoding != new.artifact_encoding {
+            changes.restart_required.push(format!(
+                "ARTIFACT_ENCODING: {:?} -> {:?}",
+                self.artifact_encoding, new.artifact_encoding
+            ));
+        }
+        if self.proof_json_layout != new.proof_json_layout {
+            changes.restart_required.push(format!(
+                "PROOF_JSON_KEYS: {} -> {}",
+                self.proof_json_layout.name(),
+                new.proof_json_layout.name()
+            ));
+        }
+        if (self.proof_cache_max_entries, self.proof_cache_max_bytes)
+            != (new.proof_cache_max_entries, new.proof_cache_max_bytes)
+        {
+            changes.restart_required.push(format!(
+                "PROOF_CACHE_MAX_ENTRIES/PROOF_CACHE_MAX_BYTES: {}/{} -> {}/{}",
+                self.proof_cache_max_entries,
+                self.proof_cache_max_bytes,
+                new.proof_cache_max_entries,
+                new.proof_cache_max_bytes
+            ));
+        }
+        if (self.startup_retry_attempts, self.startup_retry_delay_secs)
+            != (new.startup_retry_attempts, new.startup_retry_delay_secs)
+        {
+            changes.restart_required.push(format!(
+                "STARTUP_RETRY_ATTEMPTS/STARTUP_RETRY_DELAY_SECS: {}/{} -> {}/{}",
+                self.startup_retry_attempts,
+                self.startup_retry_delay_secs,
+                new.startup_retry_attempts,
+                new.startup_retry_delay_secs
+            ));
+        }
+        if self.mock_proof_delay_ms != new.mock_proof_delay_ms {
+            changes.restart_required.push(format!(
+                "MOCK_PROOF_DELAY_MS: {} -> {}",
+                self.mock_proof_delay_ms, new.mock_proof_delay_ms
+            ));
+        }
+        if self.status_port != new.status_port {
+            changes.restart_required.push(format!(
+                "STATUS_PORT: {:?} -> {:?}",
+                self.status_port, new.status_port
+            ));
+        }
+        if self.recent_responses != new.recent_responses {
+            changes.restart_required.push(format!(
+                "RECENT_RESPONSES: {} -> {}",
+                self.recent_responses, new.recent_responses
+            ));
+        }
+        if self.metrics_port != new.metrics_port {
+            changes.restart_required.push(format!(
+                "METRICS_PORT: {:?} -> {:?}",
+                self.metrics_port, new.metrics_port
+            ));
+        }
+        if self.metrics_snapshot_mins != new.metrics_snapshot_mins {
+            changes.restart_required.push(format!(
+                "METRICS_SNAPSHOT_MINS: {} -> {}",
+                self.metrics_snapshot_mins, new.metrics_snapshot_mins
+            ));
+        }
+        if self.docker_preflight != new.docker_preflight
+            || self.docker_min_memory_gb != new.docker_min_memory_gb
+        {
+            changes.restart_required.push(format!(
+                "DOCKER_PREFLIGHT/DOCKER_MIN_MEMORY_GB: {:?}/{} -> {:?}/{}",
+                self.docker_preflight,
+                self.docker_min_memory_gb,
+                new.docker_preflight,
+                new.docker_min_memory_gb
+            ));
+        }
+        if self.result_topic_allowlist != new.result_topic_allowlist {
+            changes.restart_required.push(format!(
+                "RESULT_TOPIC_ALLOWLIST: {} -> {}",
+                self.result_topic_allowlist.join(","),
+                new.result_topic_allowlist.join(",")
+            ));
+        }
+        if self.max_processing_attempts != new.max_processing_attempts {
+            changes.restart_required.push(format!(
+                "MAX_PROCESSING_ATTEMPTS: {:?} -> {:?}",
+                self.max_processing_attempts, new.max_processing_attempts
+            ));
+        }
+        if self.concurrency_ramp_secs != new.concurrency_ramp_secs {
+            changes.restart_required.push(format!(
+                "CONCURRENCY_RAMP_SECS: {} -> {}",
+                self.concurrency_ramp_secs, new.concurrency_ramp_secs
+            ));
+        }
+        if self.shutdown_grace_secs != new.shutdown_grace_secs {
+            changes.restart_required.push(format!(
+                "SHUTDOWN_GRACE_SECS: {} -> {}",
+                self.shutdown_grace_secs, new.shutdown_grace_secs
+            ));
+        }
+        if self.json_logging != new.json_logging {
+            changes.restart_required.push(format!(
+                "JSON_LOGGING: {} -> {}",
+                self.json_logging, new.json_logging
+            ));
+        }
+
+        changes
+    }
+
+    /// Lane settings for the scheduler
+    pub fn lanes(&self) -> Lanes {
+        Lanes {
+            capacity: self.max_concurrent_proofs as u32,
+            interactive_reserved: self.interactive_reserved_permits,
+            batch_promotion: (self.batch_promotion_secs > 0)
+                .then(|| Duration::from_secs(self.batch_promotion_secs)),
+            queue_wait_warning: (self.queue_wait_warn_secs > 0)
+                .then(|| Duration::from_secs(self.queue_wait_warn_secs)),
+        }
+    }
+
+    /// Lines of a bulk job proved at once, and the proof permits the job holds
+    pub fn bulk_permits(&self) -> usize {
+        self.bulk_chunk_size.min(self.max_concurrent_proofs)
+    }
+
+    /// Endpoints requests are received from, in failover order
+    ///
+    /// Without `pubsub_endpoints`, the single endpoint of `gcp_project_id`,
+    /// `subscription()` and `result_topic`.
+    pub fn endpoints(&self) -> Vec<PubsubEndpoint> {
+        if !self.pubsub_endpoints.is_empty() {
+            return self.pubsub_endpoints.clone();
+        }
+        vec![PubsubEndpoint {
+            project_id: self.gcp_project_id.clone(),
+            subscription: self.subscription().to_string(),
+            result_topic: self.result_topic.clone(),
+        }]
+    }
+
+    /// Subscription this process receives messages from
+    pub fn subscription(&self) -> &str {
+        match (self.role, &self.work_subscription) {
+            (Role::Worker, Some(work_subscription)) => work_subscription,
+            _ => &self.prover_subscription,
+        }
+    }
+
+    /// Retries for loading startup files
+    pub fn startup_retry(&self) -> StartupRetry {
+        StartupRetry {
+            attempts: self.startup_retry_attempts,
+            backoff: Duration::from_secs(self.startup_retry_delay_secs),
+        }
+    }
+
+    /// How long a proof is waited for
+    pub fn timeout_policy(&self) -> TimeoutPolicy {
+        TimeoutPolicy {
+            timeout: Duration::from_secs(self.proof_timeout_secs),
+            extension: Duration::from_secs(self.proof_timeout_extension_secs),
+            progress_window: Duration::from_secs(self.proof_progress_window_secs),
+        }
+    }
+
+    /// What the output directory janitor removes
+    ///
+    /// A request directory younger than the proof timeout and its extension may
+    /// belong to a running proof, so it is never removed.
+    pub fn janitor_policy(&self) -> JanitorPolicy {
+        JanitorPolicy {
+            request_ttl: Duration::from_secs(self.request_dir_ttl_secs),
+            min_request_age: Duration::from_secs(
+                self.proof_timeout_secs + self.proof_timeout_extension_secs,
+            ),
+            quarantine_ttl: self.quarantine_ttl_secs.map(Duration::from_secs),
+            max_bytes: self.output_dir_max_bytes,
+        }
+    }
+
+    /// Where results may be POSTed and how delivery is retried
+    pub fn callback_policy(&self) -> CallbackPolicy {
+        CallbackPolicy {
+            allowed_domains: self.callback_allowed_domains.clone(),
+            max_attempts: self.callback_max_attempts,
+            backoff: Duration::from_millis(self.callback_backoff_ms),
+            allow_insecure: self.callback_allow_insecure,
+        }
+    }
+
+    /// In-memory proof cache, if enabled
+    pub fn proof_cache(&self) -> Option<ProofCache> {
+        (self.proof_cache_max_entries > 0).then(|| {
+            ProofCache::new(
+                CacheLimits {
+                    max_entries: self.proof_cache_max_entries,
+                    max_bytes: self.proof_cache_max_bytes,
+                },
+                NoStore,
+            )
+        })
+    }
+
+    /// Copy the settings that are safe to change at runtime from `new`
+    pub fn apply_live(&mut self, new: &Config) {
+        self.log_level = new.log_level.clone();
+        self.max_concurrent_proofs = new.max_concurrent_proofs;
+        self.concurrency_auto = new.concurrency_auto;
+        self.fair_scheduling = new.fair_scheduling;
+        self.interactive_reserved_permits = new.interactive_reserved_permits;
+        self.batch_promotion_secs = new.batch_promotion_secs;
+        self.queue_wait_warn_secs = new.queue_wait_warn_secs;
+        self.memory_per_proof_gb = new.memory_per_proof_gb;
+        self.memory_wait_secs = new.memory_wait_secs;
+        self.proof_timeout_secs = new.proof_timeout_secs;
+        self.proof_timeout_extension_secs = new.proof_timeout_extension_secs;
+        self.proof_progress_window_secs = new.proof_progress_window_secs;
+        self.prove_retry_attempts = new.prove_retry_attempts;
+        self.pubsub_failover_errors = new.pubsub_failover_errors;
+        self.max_request_age_secs = new.max_request_age_secs;
+        self.result_compression = new.result_compression;
+        self.response_schema_version = new.response_schema_version;
+        self.result_ordering = new.result_ordering;
+        self.bulk_chunk_size = new.bulk_chunk_size;
+        self.bulk_max_input_bytes = new.bulk_max_input_bytes;
+        self.bulk_allow_file_uris = new.bulk_allow_file_uris;
+        self.callback_allowed_domains = new.callback_allowed_domains.clone();
+        self.callback_max_attempts = new.callback_max_attempts;
+        self.callback_backoff_ms = new.callback_backoff_ms;
+        self.callback_allow_insecure = new.callback_allow_insecure;
+        self.emit_provisional_index = new.emit_provisional_index;
+        self.require_named_profile = new.require_named_profile;
+        self.request_id_format = new.request_id_format;
+        self.signal_policy = new.signal_policy;
+        self.signal_aliases = new.signal_aliases.clone();
+        self.min_provable_index = new.min_provable_index;
+        self.cost_formula = new.cost_formula.clone();
+        self.janitor_interval_secs = new.janitor_interval_secs;
+        self.request_dir_ttl_secs = new.request_dir_ttl_secs;
+        self.quarantine_ttl_secs = new.quarantine_ttl_secs;
+        self.output_dir_max_bytes = new.output_dir_max_bytes;
+    }
+}
+
+/// `project_id` with all but its first few characters replaced by `*`
+///
+/// Enough to tell projects apart when debugging, not to address one.
+pub fn mask_project_id(project_id: &str) -> String {
+    let shown = (project_id.chars().count() / 3).min(4);
+    project_id
+        .chars()
+        .enumerate()
+        .map(|(i, c)| if i < shown { c } else { '*' })
+        .collect()
+}
+
+fn serialize_masked<S: Serializer>(project_id: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&mask_project_id(project_id))
+}
+
+fn serialize_masked_endpoints<S: Serializer>(
+    endpoints: &[PubsubEndpoint],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(endpoints.iter().map(|endpoint| PubsubEndpoint {
+        project_id: mask_project_id(&endpoint.project_id),
+        ..endpoint.clone()
+    }))
+}
+
+/// Result of comparing two configurations on reload
+#[derive(Debug, Default)]
+pub struct ConfigChanges {
+    /// Settings applied without a restart
+    pub live: Vec<String>,
+
+    /// Settings that changed but only take effect after a restart
+    pub restart_required: Vec<String>,
+}
+
+impl ConfigChanges {
+    pub fn is_empty(&self) -> bool {
+        self.live.is_empty() && self.restart_required.is_empty()
+    }
+}
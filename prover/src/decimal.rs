@@ -0,0 +1,303 @@
+use human_index_lib::{
+    from_fixed, score_to_fixed_exact, to_fixed_exact, ConvertError, HumanIndexPublicInputs,
+    VerificationResults, SCALE,
+};
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fmt;
+
+/// Request inputs that may be sent as decimals, as JSON pointers into the request
+pub const DECIMAL_FIELDS: [(&str, DecimalKind); 5] = [
+    ("/verification_results/recaptcha_score", DecimalKind::Score),
+    ("/public_inputs/w1", DecimalKind::Weight),
+    ("/public_inputs/w2", DecimalKind::Weight),
+    ("/public_inputs/w3", DecimalKind::Weight),
+    ("/public_inputs/w4", DecimalKind::Weight),
+];
+
+/// How a decimal input converts to fixed-point
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecimalKind {
+    /// In 0.0..=1.0
+    Score,
+    /// Any non-negative value; weights may exceed 1.0
+    Weight,
+}
+
+impl DecimalKind {
+    /// Exact fixed-point value of `value` with `SCALE`
+    pub fn to_fixed(self, value: f64) -> Result<u32, ConvertError> {
+        match self {
+            DecimalKind::Score => score_to_fixed_exact(value),
+            DecimalKind::Weight => to_fixed_exact(value, SCALE),
+        }
+    }
+}
+
+/// A decimal given to `ProverRequestBuilder`, as a float or as text like `"0.75"`
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecimalInput {
+    Float(f64),
+    Text(String),
+}
+
+impl DecimalInput {
+    /// Exact fixed-point value for `field`; the error names the field
+    pub fn to_fixed(&self, field: &str, kind: DecimalKind) -> Result<u32, String> {
+        let value = match self {
+            DecimalInput::Float(value) => *value,
+            DecimalInput::Text(text) => text
+                .trim()
+                .parse()
+                .map_err(|_| format!("{}: '{}' is not a decimal number", field, text))?,
+        };
+        kind.to_fixed(value)
+            .map_err(|e| format!("{}: {}", field, e))
+    }
+}
+
+impl From<f64> for DecimalInput {
+    fn from(value: f64) -> Self {
+        DecimalInput::Float(value)
+    }
+}
+
+impl From<&str> for DecimalInput {
+    fn from(text: &str) -> Self {
+        DecimalInput::Text(text.to_string())
+    }
+}
+
+impl From<String> for DecimalInput {
+    fn from(text: String) -> Self {
+        DecimalInput::Text(text)
+    }
+}
+
+/// A request number as sent: a JSON integer is already fixed-point, while a
+/// number with a fraction or exponent (`0.75`, `1.0`, `1e-1`) is a decimal
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WireNumber {
+    Fixed(u32),
+    Decimal(f64),
+}
+
+impl WireNumber {
+    fn to_fixed<E: de::Error>(self, field: &str, kind: DecimalKind) -> Result<u32, E> {
+        match self {
+            WireNumber::Fixed(fixed) => Ok(fixed),
+            WireNumber::Decimal(value) => kind
+                .to_fixed(value)
+                .map_err(|e| E::custom(format!("{}: {}", field, e))),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for WireNumber {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct WireNumberVisitor;
+
+        impl Visitor<'_> for WireNumberVisitor {
+            type Value = WireNumber;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a fixed-point integer or a decimal number")
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<WireNumber, E> {
+                u32::try_from(value)
+                    .map(WireNumber::Fixed)
+                    .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(value), &self))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<WireNumber, E> {
+                Err(E::invalid_value(de::Unexpected::Signed(value), &self))
+            }
+
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<WireNumber, E> {
+                Ok(WireNumber::Decimal(value))
+            }
+        }
+
+        deserializer.deserialize_any(WireNumberVisitor)
+    }
+}
+
+impl JsonSchema for WireNumber {
+    fn inline_schema() -> bool {
+        true
+    }
+
+    fn schema_name() -> Cow<'static, str> {
+        "WireNumber".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "description": "Fixed-point integer, or a decimal with at most four places",
+            "type": "number",
+            "minimum": 0
+        })
+    }
+}
+
+/// `VerificationResults` as a request may send them
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct VerificationResultsWire {
+    #[serde(alias = "recaptchaScore")]
+    pub recaptcha_score: WireNumber,
+    #[serde(alias = "smsVerified")]
+    pub sms_verified: u32,
+    #[serde(alias = "bioVerified")]
+    pub bio_verified: u32,
+}
+
+/// `HumanIndexPublicInputs` as a request may send them
+///
+/// The weights may be left out together when the request names a `weights_profile`.
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct PublicInputsWire {
+    #[serde(default)]
+    pub w1: Option<WireNumber>,
+    #[serde(default)]
+    pub w2: Option<WireNumber>,
+    #[serde(default)]
+    pub w3: Option<WireNumber>,
+    #[serde(default)]
+    pub w4: Option<WireNumber>,
+    #[serde(alias = "expectedOutput")]
+    pub expected_output: u32,
+}
+
+/// Deserialize `verification_results`, converting a decimal `recaptcha_score`
+pub fn verification_results<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<VerificationResults, D::Error> {
+    let wire = VerificationResultsWire::deserialize(deserializer)?;
+    Ok(VerificationResults {
+        recaptcha_score: wire
+            .recaptcha_score
+            .to_fixed("recaptcha_score", DecimalKind::Score)?,
+        sms_verified: wire.sms_verified,
+        bio_verified: wire.bio_verified,
+    })
+}
+
+/// Deserialize `public_inputs`, converting decimal weights
+///
+/// Weights left out are zero, for `ProverRequest::resolve_weights_profile` to fill
+/// in; leaving out only some of them is an error.
+pub fn public_inputs<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HumanIndexPublicInputs, D::Error> {
+    let wire = PublicInputsWire::deserialize(deserializer)?;
+    let weights = [
+        ("w1", wire.w1),
+        ("w2", wire.w2),
+        ("w3", wire.w3),
+        ("w4", wire.w4),
+    ];
+    if weights.iter().all(|(_, weight)| weight.is_none()) {
+        return Ok(HumanIndexPublicInputs {
+            expected_output: wire.expected_output,
+            ..Default::default()
+        });
+    }
+    let [w1, w2, w3, w4] = weights.map(|(field, weight)| {
+        weight
+            .ok_or_else(|| de::Error::missing_field(field))
+            .and_then(|weight| weight.to_fixed(field, DecimalKind::Weight))
+    });
+    Ok(HumanIndexPublicInputs {
+        w1: w1?,
+        w2: w2?,
+        w3: w3?,
+        w4: w4?,
+        expected_output: wire.expected_output,
+    })
+}
+
+/// The request's weights in both representations, echoed in responses
+///
+/// Only the public weights: the verification results stay out of responses.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct EchoedWeights {
+    /// Fixed-point with `SCALE`, as proven
+    pub fixed: Weights<u32>,
+    pub decimal: Weights<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Weights<T> {
+    pub w1: T,
+    pub w2: T,
+    pub w3: T,
+    pub w4: T,
+}
+
+impl EchoedWeights {
+    pub fn new(public_inputs: &HumanIndexPublicInputs) -> Self {
+        let fixed = Weights {
+            w1: public_inputs.w1,
+            w2: public_inputs.w2,
+            w3: public_inputs.w3,
+            w4: public_inputs.w4,
+        };
+        let decimal = |w| from_fixed(w, SCALE);
+        Self {
+            fixed,
+            decimal: Weights {
+                w1: decimal(fixed.w1),
+                w2: decimal(fixed.w2),
+                w3: decimal(fixed.w3),
+                w4: decimal(fixed.w4),
+            },
+        }
+    }
+}
+
+/// Committed weights in both representations, with the decimals as exact strings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct DecodedWeights {
+    /// Fixed-point with `SCALE`, as committed
+    pub fixed: Weights<u32>,
+    /// `fixed` over `SCALE`, e.g. `"0.15"`
+    pub decimal: Weights<String>,
+}
+
+impl DecodedWeights {
+    pub fn new(public_inputs: &HumanIndexPublicInputs) -> Self {
+        let fixed = Weights {
+            w1: public_inputs.w1,
+            w2: public_inputs.w2,
+            w3: public_inputs.w3,
+            w4: public_inputs.w4,
+        };
+        Self {
+            fixed,
+            decimal: Weights {
+                w1: decimal_string(fixed.w1),
+                w2: decimal_string(fixed.w2),
+                w3: decimal_string(fixed.w3),
+                w4: decimal_string(fixed.w4),
+            },
+        }
+    }
+}
+
+/// `fixed` over `SCALE` as an exact decimal, without trailing zeros
+pub fn decimal_string(fixed: u32) -> String {
+    let places = SCALE.ilog10() as usize;
+    let fraction = format!("{:0places$}", fixed % SCALE);
+    match fraction.trim_end_matches('0') {
+        "" => (fixed / SCALE).to_string(),
+        fraction => format!("{}.{}", fixed / SCALE, fraction),
+    }
+}
@@ -0,0 +1,453 @@
+use crate::error::ServiceError;
+use crate::types::Priority;
+use crate::webhook::DeliveryStatus;
+use chrono::{DateTime, TimeZone, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// A journaled request that is due
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DelayedItem {
+    pub id: i64,
+    pub priority: Priority,
+    /// The message data as received
+    pub data: Vec<u8>,
+    /// Key of the Pub/Sub endpoint the request was received from, see
+    /// `PubsubEndpoint::key`; unset in a frontend's journal
+    pub endpoint: Option<String>,
+}
+
+/// A result delivery to a `callback_url`, as journaled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallbackRecord {
+    pub id: i64,
+    pub request_id: String,
+    pub url: String,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+}
+
+/// A result as it was published, journaled so it can be served or published again
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournaledResult {
+    pub request_id: String,
+    /// The message data, as published
+    pub data: Vec<u8>,
+    pub attributes: HashMap<String, String>,
+    pub ordering_key: String,
+    /// Full path of the topic the result was published to
+    pub topic_path: String,
+    /// When the result was journaled (RFC 3339)
+    pub completed_at: String,
+}
+
+/// What the journal knows of the result of a request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResultLookup {
+    /// Never received by this worker, or its result is no longer kept
+    Unknown,
+    /// Being proved
+    InProgress,
+    Completed(JournaledResult),
+}
+
+/// Waiting requests, as reported by the status endpoint
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DelayedStats {
+    pub waiting: u64,
+    /// When the next request is due (RFC 3339)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_release_at: Option<String>,
+}
+
+/// SQLite-backed queue of requests held until their `not_before` time
+///
+/// A request is journaled when received and deleted once its result is published.
+/// Released requests still being proved when the process stops are released again
+/// on the next start. The journal also records result deliveries to callbacks, and
+/// the latest published results, up to `RESULT_JOURNAL_SIZE`.
+pub struct DelayedQueue {
+    conn: Mutex<Connection>,
+    inserted: Notify,
+}
+
+impl DelayedQueue {
+    /// Open or create the queue at `path`
+    ///
+    /// Requests an earlier process released, and results it was still proving, are
+    /// taken as unfinished.
+    pub fn open(path: &Path) -> Result<Self, ServiceError> {
+        let queue = Self::open_shared(path)?;
+        queue
+            .lock()
+            .execute_batch(
+                "UPDATE delayed SET released = 0;
+                 DELETE FROM results WHERE data IS NULL;",
+            )
+            .map_err(storage_error)?;
+        Ok(queue)
+    }
+
+    /// Open or create the queue at `path` next to the service using it, as an
+    /// admin command does
+    ///
+    /// Unlike `open`, leaves released requests and results in progress as they are.
+    pub fn open_shared(path: &Path) -> Result<Self, ServiceError> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let conn = Connection::open(path).map_err(storage_error)?;
+        conn.busy_timeout(BUSY_TIMEOUT).map_err(storage_error)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS delayed (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                release_at_ms INTEGER NOT NULL,
+                priority TEXT NOT NULL,
+                data BLOB NOT NULL,
+                released INTEGER NOT NULL DEFAULT 0
+            );
+            CREATE INDEX IF NOT EXISTS delayed_release_at ON delayed (released, release_at_ms);
+            CREATE TABLE IF NOT EXISTS callbacks (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                request_id TEXT NOT NULL,
+                url TEXT NOT NULL,
+                status TEXT NOT NULL,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                last_error TEXT,
+                updated_at_ms INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS callbacks_request_id ON callbacks (request_id);
+            CREATE TABLE IF NOT EXISTS results (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                request_id TEXT NOT NULL UNIQUE,
+                data BLOB,
+                attributes TEXT,
+                ordering_key TEXT,
+                topic_path TEXT,
+                updated_at_ms INTEGER NOT NULL
+            );",
+        )
+        .map_err(storage_error)?;
+        // Journals from before endpoints were recorded
+        let has_endpoint: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('delayed') WHERE name = 'endpoint'",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(storage_error)?;
+        if !has_endpoint {
+            conn.execute("ALTER TABLE delayed ADD COLUMN endpoint TEXT", [])
+                .map_err(storage_error)?;
+        }
+        Ok(Self {
+            conn: Mutex::new(conn),
+            inserted: Notify::new(),
+        })
+    }
+
+    /// Journal a request to be released at `release_at`
+    pub fn insert(
+        &self,
+        release_at: DateTime<Utc>,
+        priority: Priority,
+        data: &[u8],
+    ) -> Result<i64, ServiceError> {
+        self.insert_from(release_at, priority, data, None)
+    }
+
+    /// Journal a request received from `endpoint`, to be released at `release_at`
+    pub fn insert_from(
+        &self,
+        release_at: DateTime<Utc>,
+        priority: Priority,
+        data: &[u8],
+        endpoint: Option<&str>,
+    ) -> Result<i64, ServiceError> {
+        let id = {
+            let conn = self.lock();
+            conn.execute(
+                "INSERT INTO delayed (release_at_ms, priority, data, endpoint) VALUES (?1, ?2, ?3, ?4)",
+                params![release_at.timestamp_millis(), priority.as_str(), data, endpoint],
+            )
+            .map_err(storage_error)?;
+            conn.last_insert_rowid()
+        };
+        self.inserted.notify_one();
+        Ok(id)
+    }
+
+    /// Mark the requests due at `now` as released and return them, earliest first
+    pub fn take_due(&self, now: DateTime<Utc>) -> Result<Vec<DelayedItem>, ServiceError> {
+        let mut conn = self.lock();
+        let tx = conn.transaction().map_err(storage_error)?;
+        let items = {
+            let mut statement = tx
+                .prepare(
+                    "SELECT id, priority, data, endpoint FROM delayed
+                     WHERE released = 0 AND release_at_ms <= ?1 ORDER BY release_at_ms, id",
+                )
+                .map_err(storage_error)?;
+            let rows = statement
+                .query_map(params![now.timestamp_millis()], |row| {
+                    let priority: String = row.get(1)?;
+                    Ok(DelayedItem {
+                        id: row.get(0)?,
+                        priority: priority.parse().unwrap_or_default(),
+                        data: row.get(2)?,
+                        endpoint: row.get(3)?,
+                    })
+                })
+                .map_err(storage_error)?;
+            rows.collect::<Result<Vec<_>, _>>().map_err(storage_error)?
+        };
+        tx.execute(
+            "UPDATE delayed SET released = 1 WHERE released = 0 AND release_at_ms <= ?1",
+            params![now.timestamp_millis()],
+        )
+        .map_err(storage_error)?;
+        tx.commit().map_err(storage_error)?;
+        Ok(items)
+    }
+
+    /// Delete a released request once it is finished
+    pub fn complete(&self, id: i64) -> Result<(), ServiceError> {
+        self.lock()
+            .execute("DELETE FROM delayed WHERE id = ?1", params![id])
+            .map_err(storage_error)?;
+        Ok(())
+    }
+
+    /// Return a released request to the queue, due again at `release_at`
+    pub fn postpone(&self, id: i64, release_at: DateTime<Utc>) -> Result<(), ServiceError> {
+        self.lock()
+            .execute(
+                "UPDATE delayed SET released = 0, release_at_ms = ?2 WHERE id = ?1",
+                params![id, release_at.timestamp_millis()],
+            )
+            .map_err(storage_error)?;
+        self.inserted.notify_one();
+        Ok(())
+    }
+
+    /// When the next waiting request is due
+    pub fn next_release_at(&self) -> Result<Option<DateTime<Utc>>, ServiceError> {
+        let release_at_ms: Option<i64> = self
+            .lock()
+            .query_row(
+                "SELECT MIN(release_at_ms) FROM delayed WHERE released = 0",
+                [],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(storage_error)?
+            .flatten();
+        Ok(release_at_ms.and_then(|ms| Utc.timestamp_millis_opt(ms).single()))
+    }
+
+    /// Number of waiting requests and when the next is due
+    pub fn stats(&self) -> Result<DelayedStats, ServiceError> {
+        let waiting: i64 = self
+            .lock()
+            .query_row(
+                "SELECT COUNT(*) FROM delayed WHERE released = 0",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(storage_error)?;
+        Ok(DelayedStats {
+            waiting: waiting as u64,
+            next_release_at: self.next_release_at()?.map(|at| at.to_rfc3339()),
+        })
+    }
+
+    /// Journal a callback delivery to `url` as pending; returns its ID
+    pub fn begin_callback(&self, request_id: &str, url: &str) -> Result<i64, ServiceError> {
+        let conn = self.lock();
+        conn.execute(
+            "INSERT INTO callbacks (request_id, url, status, updated_at_ms) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                request_id,
+                url,
+                DeliveryStatus::Pending.as_str(),
+                Utc::now().timestamp_millis()
+            ],
+        )
+        .map_err(storage_error)?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Record the outcome of callback delivery `id`
+    pub fn finish_callback(
+        &self,
+        id: i64,
+        status: DeliveryStatus,
+        attempts: u32,
+        last_error: Option<&str>,
+    ) -> Result<(), ServiceError> {
+        self.lock()
+            .execute(
+                "UPDATE callbacks SET status = ?2, attempts = ?3, last_error = ?4, updated_at_ms = ?5
+                 WHERE id = ?1",
+                params![
+                    id,
+                    status.as_str(),
+                    attempts,
+                    last_error,
+                    Utc::now().timestamp_millis()
+                ],
+            )
+            .map_err(storage_error)?;
+        Ok(())
+    }
+
+    /// Callback deliveries of `request_id`, oldest first
+    pub fn callbacks(&self, request_id: &str) -> Result<Vec<CallbackRecord>, ServiceError> {
+        let conn = self.lock();
+        let mut statement = conn
+            .prepare(
+                "SELECT id, request_id, url, status, attempts, last_error FROM callbacks
+                 WHERE request_id = ?1 ORDER BY id",
+            )
+            .map_err(storage_error)?;
+        let rows = statement
+            .query_map(params![request_id], |row| {
+                let status: String = row.get(3)?;
+                Ok(CallbackRecord {
+                    id: row.get(0)?,
+                    request_id: row.get(1)?,
+                    url: row.get(2)?,
+                    status: status.parse().unwrap_or(DeliveryStatus::Pending),
+                    attempts: row.get(4)?,
+                    last_error: row.get(5)?,
+                })
+            })
+            .map_err(storage_error)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(storage_error)
+    }
+
+    /// Journal the result of `request_id` as in progress
+    pub fn begin_result(&self, request_id: &str) -> Result<(), ServiceError> {
+        self.lock()
+            .execute(
+                "INSERT OR REPLACE INTO results (request_id, updated_at_ms) VALUES (?1, ?2)",
+                params![request_id, Utc::now().timestamp_millis()],
+            )
+            .map_err(storage_error)?;
+        Ok(())
+    }
+
+    /// Drop an in-progress result whose request produced none
+    pub fn abandon_result(&self, request_id: &str) -> Result<(), ServiceError> {
+        self.lock()
+            .execute(
+                "DELETE FROM results WHERE request_id = ?1 AND data IS NULL",
+                params![request_id],
+            )
+            .map_err(storage_error)?;
+        Ok(())
+    }
+
+    /// Journal a published result, keeping the latest `keep` results
+    ///
+    /// `completed_at` is set here; the one in `result` is ignored.
+    pub fn record_result(&self, result: &JournaledResult, keep: usize) -> Result<(), ServiceError> {
+        let attributes = serde_json::to_string(&result.attributes)?;
+        let mut conn = self.lock();
+        let tx = conn.transaction().map_err(storage_error)?;
+        tx.execute(
+            "INSERT OR REPLACE INTO results
+             (request_id, data, attributes, ordering_key, topic_path, updated_at_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                result.request_id,
+                result.data,
+                attributes,
+                result.ordering_key,
+                result.topic_path,
+                Utc::now().timestamp_millis()
+            ],
+        )
+        .map_err(storage_error)?;
+        tx.execute(
+            "DELETE FROM results WHERE data IS NOT NULL AND id NOT IN
+             (SELECT id FROM results WHERE data IS NOT NULL ORDER BY id DESC LIMIT ?1)",
+            params![keep as i64],
+        )
+        .map_err(storage_error)?;
+        tx.commit().map_err(storage_error)
+    }
+
+    /// The journaled result of `request_id`
+    pub fn result(&self, request_id: &str) -> Result<ResultLookup, ServiceError> {
+        type Row = (
+            Option<Vec<u8>>,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+            i64,
+        );
+        let row: Option<Row> = self
+            .lock()
+            .query_row(
+                "SELECT data, attributes, ordering_key, topic_path, updated_at_ms FROM results
+                 WHERE request_id = ?1",
+                params![request_id],
+                |row| {
+                    Ok((
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get(2)?,
+                        row.get(3)?,
+                        row.get(4)?,
+                    ))
+                },
+            )
+            .optional()
+            .map_err(storage_error)?;
+        let Some((data, attributes, ordering_key, topic_path, updated_at_ms)) = row else {
+            return Ok(ResultLookup::Unknown);
+        };
+        let Some(data) = data else {
+            return Ok(ResultLookup::InProgress);
+        };
+        Ok(ResultLookup::Completed(JournaledResult {
+            request_id: request_id.to_string(),
+            data,
+            attributes: attributes
+                .map(|attributes| serde_json::from_str(&attributes))
+                .transpose()?
+                .unwrap_or_default(),
+            ordering_key: ordering_key.unwrap_or_default(),
+            topic_path: topic_path.unwrap_or_default(),
+            completed_at: Utc
+                .timestamp_millis_opt(updated_at_ms)
+                .single()
+                .map(|at| at.to_rfc3339())
+                .unwrap_or_default(),
+        }))
+    }
+
+    /// Resolves after the next `insert`, which may move the next release earlier
+    pub async fn inserted(&self) {
+        self.inserted.notified().await;
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().expect("delayed queue lock poisoned")
+    }
+}
+
+/// How long a write waits for another process holding the journal, e.g. an admin
+/// command
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn storage_error(e: rusqlite::Error) -> ServiceError {
+    ServiceError::Storage(e.to_string())
+}
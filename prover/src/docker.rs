@@ -0,0 +1,259 @@
+use crate::error::ServiceError;
+use serde::Serialize;
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpStream, UnixStream};
+use tokio::time::timeout;
+use tracing::{info, warn};
+
+/// Docker endpoint used when `DOCKER_HOST` is not set
+pub const DEFAULT_DOCKER_HOST: &str = "unix:///var/run/docker.sock";
+
+const GIB: u64 = 1024 * 1024 * 1024;
+const QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What to do when Docker has less memory than proving needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DockerPreflight {
+    /// Skip the check
+    Off,
+    /// Log a warning and report the service as not ready
+    Warn,
+    /// Refuse to start
+    Fail,
+}
+
+impl FromStr for DockerPreflight {
+    type Err = ServiceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "off" => Ok(Self::Off),
+            "warn" => Ok(Self::Warn),
+            "fail" => Ok(Self::Fail),
+            other => Err(ServiceError::Config(format!(
+                "Invalid DOCKER_PREFLIGHT '{}', expected 'off', 'warn' or 'fail'",
+                other
+            ))),
+        }
+    }
+}
+
+/// Outcome of the Docker memory preflight, reported by `/readyz`
+#[derive(Debug, Clone, Serialize)]
+pub struct DockerMemoryCheck {
+    /// Memory available to Docker, if the daemon could be queried
+    pub memory_bytes: Option<u64>,
+
+    /// Threshold the check was run against
+    pub min_memory_bytes: u64,
+
+    /// Whether Docker is known to have enough memory
+    pub ok: bool,
+
+    /// Why the check did not pass
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Answers the Docker API's `GET /info`, which the preflight reads the memory from
+///
+/// Implemented by `DockerDaemon`; tests serve canned responses.
+pub trait DockerApi: Send + Sync {
+    /// Raw HTTP response to `GET /info`
+    fn info<'a>(
+        &'a self,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<u8>, ServiceError>> + Send + 'a>>;
+}
+
+/// The Docker daemon at a `unix://` socket or plain `tcp://` endpoint
+pub struct DockerDaemon {
+    host: String,
+}
+
+impl DockerDaemon {
+    pub fn new(host: impl Into<String>) -> Self {
+        Self { host: host.into() }
+    }
+
+    /// The daemon at `DOCKER_HOST`, or at `DEFAULT_DOCKER_HOST` when it is not set
+    pub fn from_env() -> Self {
+        Self::new(std::env::var("DOCKER_HOST").unwrap_or_else(|_| DEFAULT_DOCKER_HOST.to_string()))
+    }
+}
+
+impl DockerApi for DockerDaemon {
+    fn info<'a>(
+        &'a self,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<u8>, ServiceError>> + Send + 'a>> {
+        Box::pin(query_info(&self.host))
+    }
+}
+
+/// Check that `docker` has at least `min_memory_gb` GiB before any proof is attempted
+///
+/// The Groth16 wrap runs in Docker and fails late, with missing outputs, when the
+/// daemon is short on memory. In `Fail` mode a low or unknown limit is an error.
+pub async fn preflight(
+    docker: &dyn DockerApi,
+    mode: DockerPreflight,
+    min_memory_gb: u64,
+) -> Result<Option<DockerMemoryCheck>, ServiceError> {
+    if mode == DockerPreflight::Off {
+        return Ok(None);
+    }
+
+    let min_memory_bytes = min_memory_gb * GIB;
+    let memory = match docker.info().await {
+        Ok(response) => parse_info_response(&response),
+        Err(e) => Err(e),
+    };
+    let (memory_bytes, result) = match memory {
+        Ok(bytes) => (Some(bytes), check_memory(bytes, min_memory_bytes)),
+        Err(e) => (None, Err(e)),
+    };
+
+    let check = match result {
+        Ok(()) => {
+            info!(
+                "Docker memory: {:.1} GiB (minimum {} GiB)",
+                memory_bytes.unwrap_or_default() as f64 / GIB as f64,
+                min_memory_gb
+            );
+            DockerMemoryCheck {
+                memory_bytes,
+                min_memory_bytes,
+                ok: true,
+                error: None,
+            }
+        }
+        Err(e) if mode == DockerPreflight::Fail => return Err(e),
+        Err(e) => {
+            warn!("Docker preflight failed, proofs may fail: {}", e);
+            DockerMemoryCheck {
+                memory_bytes,
+                min_memory_bytes,
+                ok: false,
+                error: Some(e.to_string()),
+            }
+        }
+    };
+
+    Ok(Some(check))
+}
+
+/// Fail with an actionable message when `memory_bytes` is below `min_memory_bytes`
+pub fn check_memory(memory_bytes: u64, min_memory_bytes: u64) -> Result<(), ServiceError> {
+    if memory_bytes < min_memory_bytes {
+        return Err(ServiceError::Docker(format!(
+            "Docker has {:.1} GiB of memory but proving needs at least {:.1} GiB; \
+             raise the Docker memory limit or lower DOCKER_MIN_MEMORY_GB",
+            memory_bytes as f64 / GIB as f64,
+            min_memory_bytes as f64 / GIB as f64
+        )));
+    }
+    Ok(())
+}
+
+/// Total memory reported by the Docker daemon at `docker_host`
+///
+/// Supports `unix://` sockets and plain `tcp://` endpoints.
+pub async fn query_memory(docker_host: &str) -> Result<u64, ServiceError> {
+    parse_info_response(&query_info(docker_host).await?)
+}
+
+/// Raw HTTP response of the daemon at `docker_host` to `GET /info`
+async fn query_info(docker_host: &str) -> Result<Vec<u8>, ServiceError> {
+    let response = if let Some(path) = docker_host.strip_prefix("unix://") {
+        let stream = UnixStream::connect(path).await.map_err(|e| {
+            ServiceError::Docker(format!(
+                "Cannot connect to Docker at {}: {}",
+                docker_host, e
+            ))
+        })?;
+        timeout(QUERY_TIMEOUT, get_info(stream)).await
+    } else if let Some(addr) = docker_host.strip_prefix("tcp://") {
+        let stream = TcpStream::connect(addr).await.map_err(|e| {
+            ServiceError::Docker(format!(
+                "Cannot connect to Docker at {}: {}",
+                docker_host, e
+            ))
+        })?;
+        timeout(QUERY_TIMEOUT, get_info(stream)).await
+    } else {
+        return Err(ServiceError::Docker(format!(
+            "Unsupported DOCKER_HOST '{}', expected unix:// or tcp://",
+            docker_host
+        )));
+    };
+
+    response
+        .map_err(|_| ServiceError::Docker(format!("Docker at {} did not respond", docker_host)))?
+}
+
+async fn get_info<S>(mut stream: S) -> Result<Vec<u8>, ServiceError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    // HTTP/1.0 so the daemon closes the connection and never chunks the body
+    stream
+        .write_all(b"GET /info HTTP/1.0\r\nHost: docker\r\n\r\n")
+        .await?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).await?;
+    Ok(response)
+}
+
+/// Extract `MemTotal` from a raw HTTP response to `GET /info`
+pub fn parse_info_response(response: &[u8]) -> Result<u64, ServiceError> {
+    let split = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| ServiceError::Docker("Malformed response from Docker".to_string()))?;
+    let (head, body) = (&response[..split], &response[split + 4..]);
+
+    let head = String::from_utf8_lossy(head);
+    let status_line = head.lines().next().unwrap_or_default();
+    if status_line.split_whitespace().nth(1) != Some("200") {
+        return Err(ServiceError::Docker(format!(
+            "Docker /info returned '{}'",
+            status_line
+        )));
+    }
+
+    let info: serde_json::Value = serde_json::from_slice(body)
+        .map_err(|e| ServiceError::Docker(format!("Invalid Docker /info body: {}", e)))?;
+    info.get("MemTotal")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| ServiceError::Docker("Docker /info has no MemTotal".to_string()))
+}
+
+/// Error text of Docker failures that a retry usually fixes: daemon connection
+/// hiccups, registry throttling and image pull or container name races
+const TRANSIENT_FAILURES: &[&str] = &[
+    "cannot connect to the docker daemon",
+    "error during connect",
+    "connection reset by peer",
+    "broken pipe",
+    "i/o timeout",
+    "tls handshake timeout",
+    "context deadline exceeded",
+    "toomanyrequests",
+    "no such image",
+    "failed to register layer",
+    "is already in use by container",
+];
+
+/// Whether a failed Groth16 wrap looks like a Docker hiccup worth retrying
+///
+/// Out-of-memory kills and missing outputs are not transient: they fail again.
+pub fn is_transient_failure(error: &str) -> bool {
+    let error = error.to_lowercase();
+    TRANSIENT_FAILURES
+        .iter()
+        .any(|pattern| error.contains(pattern))
+}
@@ -0,0 +1,288 @@
+use crate::error::ServiceError;
+use crate::kms::KmsSigner;
+use crate::types::ProofData;
+use alloy_primitives::{keccak256, Address, Signature, B256, U256};
+use alloy_signer::SignerSync;
+use alloy_signer_local::PrivateKeySigner;
+use alloy_sol_types::{sol, Eip712Domain, SolStruct};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::borrow::Cow;
+use std::env;
+use std::future::Future;
+use std::pin::Pin;
+use tracing::info;
+
+sol! {
+    /// Attestation that a proof for `bindingId` produced `humanIndex`
+    #[derive(Debug, PartialEq, Eq)]
+    struct HumanIndexAttestation {
+        bytes32 bindingId;
+        uint32 humanIndex;
+        bytes32 vkeyHash;
+        uint64 expiry;
+    }
+}
+
+impl HumanIndexAttestation {
+    /// Build the attestation for a proof
+    ///
+    /// `bindingId` is `keccak256(binding_id)`, the key the on-chain registry uses,
+    /// and `vkeyHash` is the RISC-V program verification key from the proof, so the
+    /// signature is tied to both the binding and the circuit that produced the index.
+    pub fn for_proof(
+        binding_id: &str,
+        proof_data: &ProofData,
+        expiry: u64,
+    ) -> Result<Self, ServiceError> {
+        let vkey_hex = STANDARD
+            .decode(&proof_data.verification_key)
+            .map_err(|e| ServiceError::Attestation(format!("Invalid verification key: {}", e)))?;
+        let vkey_hex = String::from_utf8_lossy(&vkey_hex);
+        let vkey = hex::decode(vkey_hex.trim().trim_start_matches("0x"))
+            .map_err(|e| ServiceError::Attestation(format!("Invalid verification key: {}", e)))?;
+        let vkey_hash = B256::try_from(vkey.as_slice()).map_err(|_| {
+            ServiceError::Attestation(format!(
+                "Verification key must be 32 bytes, got {}",
+                vkey.len()
+            ))
+        })?;
+
+        Ok(Self {
+            bindingId: keccak256(binding_id.as_bytes()),
+            humanIndex: proof_data.human_index,
+            vkeyHash: vkey_hash,
+            expiry,
+        })
+    }
+}
+
+/// EIP-712 domain settings, loaded from environment variables
+#[derive(Debug, Clone)]
+pub struct AttestationConfig {
+    /// Signer backend: `local` or `gcp-kms`
+    pub signing: String,
+
+    /// Hex-encoded private key, for `local`
+    pub signer_key: Option<String>,
+
+    /// Cloud KMS key version, for `gcp-kms`
+    pub kms_key: Option<String>,
+
+    /// Domain name
+    pub domain_name: String,
+
+    /// Domain version
+    pub domain_version: String,
+
+    /// Chain ID of the consuming contract
+    pub chain_id: u64,
+
+    /// Address of the consuming contract
+    pub verifying_contract: Address,
+
+    /// Attestation lifetime in seconds, used to derive `expiry`
+    pub ttl_secs: u64,
+}
+
+impl AttestationConfig {
+    /// Load attestation settings; returns `None` when `ATTESTATION_SIGNING` is not set
+    pub fn from_env() -> Result<Option<Self>, ServiceError> {
+        let signing = match env::var("ATTESTATION_SIGNING") {
+            Ok(signing) => signing,
+            Err(_) => return Ok(None),
+        };
+
+        let signer_key = env::var("ATTESTATION_SIGNER_KEY").ok();
+        let kms_key = env::var("ATTESTATION_KMS_KEY").ok();
+
+        let domain_name =
+            env::var("ATTESTATION_DOMAIN_NAME").unwrap_or_else(|_| "HumanIndex".to_string());
+
+        let domain_version =
+            env::var("ATTESTATION_DOMAIN_VERSION").unwrap_or_else(|_| "1".to_string());
+
+        let chain_id = env::var("ATTESTATION_CHAIN_ID")
+            .map_err(|_| ServiceError::Config("ATTESTATION_CHAIN_ID not set".to_string()))?
+            .parse::<u64>()
+            .map_err(|e| ServiceError::Config(format!("Invalid ATTESTATION_CHAIN_ID: {}", e)))?;
+
+        let verifying_contract = env::var("ATTESTATION_VERIFYING_CONTRACT")
+            .map_err(|_| {
+                ServiceError::Config("ATTESTATION_VERIFYING_CONTRACT not set".to_string())
+            })?
+            .parse::<Address>()
+            .map_err(|e| {
+                ServiceError::Config(format!("Invalid ATTESTATION_VERIFYING_CONTRACT: {}", e))
+            })?;
+
+        let ttl_secs = env::var("ATTESTATION_TTL_SECS")
+            .unwrap_or_else(|_| "86400".to_string()) // Default 1 day
+            .parse::<u64>()
+            .map_err(|e| ServiceError::Config(format!("Invalid ATTESTATION_TTL_SECS: {}", e)))?;
+
+        Ok(Some(Self {
+            signing,
+            signer_key,
+            kms_key,
+            domain_name,
+            domain_version,
+            chain_id,
+            verifying_contract,
+            ttl_secs,
+        }))
+    }
+
+    /// EIP-712 domain for this configuration
+    pub fn domain(&self) -> Eip712Domain {
+        Eip712Domain::new(
+            Some(Cow::Owned(self.domain_name.clone())),
+            Some(Cow::Owned(self.domain_version.clone())),
+            Some(U256::from(self.chain_id)),
+            Some(self.verifying_contract),
+            None,
+        )
+    }
+}
+
+/// Signs EIP-712 digests
+///
+/// Implemented by the local key signer and by `KmsSigner`, which signs remotely
+/// without blocking the runtime.
+pub trait AttestationSigner: Send + Sync {
+    /// Address the signatures recover to
+    fn address(&self) -> Address;
+
+    /// Sign a prehashed EIP-712 digest
+    fn sign_digest<'a>(
+        &'a self,
+        digest: B256,
+    ) -> Pin<Box<dyn Future<Output = Result<Signature, ServiceError>> + Send + 'a>>;
+}
+
+/// Signer backed by a private key held in memory
+pub struct LocalAttestationSigner {
+    signer: PrivateKeySigner,
+}
+
+impl LocalAttestationSigner {
+    pub fn new(key: &str) -> Result<Self, ServiceError> {
+        let signer = key
+            .trim_start_matches("0x")
+            .parse::<PrivateKeySigner>()
+            .map_err(|e| ServiceError::Config(format!("Invalid ATTESTATION_SIGNER_KEY: {}", e)))?;
+        Ok(Self { signer })
+    }
+}
+
+impl AttestationSigner for LocalAttestationSigner {
+    fn address(&self) -> Address {
+        self.signer.address()
+    }
+
+    fn sign_digest<'a>(
+        &'a self,
+        digest: B256,
+    ) -> Pin<Box<dyn Future<Output = Result<Signature, ServiceError>> + Send + 'a>> {
+        Box::pin(async move {
+            self.signer
+                .sign_hash_sync(&digest)
+                .map_err(|e| ServiceError::Attestation(format!("Signing failed: {}", e)))
+        })
+    }
+}
+
+impl AttestationSigner for KmsSigner {
+    fn address(&self) -> Address {
+        KmsSigner::address(self)
+    }
+
+    fn sign_digest<'a>(
+        &'a self,
+        digest: B256,
+    ) -> Pin<Box<dyn Future<Output = Result<Signature, ServiceError>> + Send + 'a>> {
+        Box::pin(KmsSigner::sign_digest(self, digest))
+    }
+}
+
+/// Produces attestation signatures for successful proofs
+pub struct Attestor {
+    domain: Eip712Domain,
+    signer: Box<dyn AttestationSigner>,
+    ttl_secs: u64,
+}
+
+impl Attestor {
+    /// Create an attestor from configuration
+    pub async fn new(config: &AttestationConfig) -> Result<Self, ServiceError> {
+        let signer: Box<dyn AttestationSigner> = match config.signing.as_str() {
+            "local" => {
+                let key = config.signer_key.as_deref().ok_or_else(|| {
+                    ServiceError::Config("ATTESTATION_SIGNER_KEY not set".to_string())
+                })?;
+                Box::new(LocalAttestationSigner::new(key)?)
+            }
+            "gcp-kms" => {
+                let key = config.kms_key.as_deref().ok_or_else(|| {
+                    ServiceError::Config("ATTESTATION_KMS_KEY not set".to_string())
+                })?;
+                Box::new(KmsSigner::connect(key).await?)
+            }
+            other => {
+                return Err(ServiceError::Config(format!(
+                    "Unsupported ATTESTATION_SIGNING backend: {}",
+                    other
+                )))
+            }
+        };
+
+        info!(
+            signer = %signer.address(),
+            chain_id = config.chain_id,
+            verifying_contract = %config.verifying_contract,
+            "Attestation signing enabled"
+        );
+
+        Ok(Self {
+            domain: config.domain(),
+            signer,
+            ttl_secs: config.ttl_secs,
+        })
+    }
+
+    /// Sign an attestation binding the proof to `binding_id` and record it in
+    /// `proof_data`
+    pub async fn attest(
+        &self,
+        binding_id: &str,
+        proof_data: &mut ProofData,
+    ) -> Result<(), ServiceError> {
+        let expiry = chrono::Utc::now().timestamp() as u64 + self.ttl_secs;
+        let attestation = HumanIndexAttestation::for_proof(binding_id, proof_data, expiry)?;
+        let digest = attestation.eip712_signing_hash(&self.domain);
+        let signature = self.signer.sign_digest(digest).await?;
+
+        proof_data.attestation_signature = Some(format!("0x{}", hex::encode(signature.as_bytes())));
+        proof_data.attestation_expiry = Some(expiry);
+        Ok(())
+    }
+}
+
+/// Check that `signature` over `attestation` was produced by `expected_signer`
+pub fn verify_attestation(
+    attestation: &HumanIndexAttestation,
+    domain: &Eip712Domain,
+    signature: &str,
+    expected_signer: Address,
+) -> Result<bool, ServiceError> {
+    let bytes = hex::decode(signature.trim_start_matches("0x"))
+        .map_err(|e| ServiceError::Attestation(format!("Invalid signature hex: {}", e)))?;
+    let signature = Signature::try_from(bytes.as_slice())
+        .map_err(|e| ServiceError::Attestation(format!("Invalid signature: {}", e)))?;
+
+    let digest = attestation.eip712_signing_hash(domain);
+    let recovered = signature
+        .recover_address_from_prehash(&digest)
+        .map_err(|e| ServiceError::Attestation(format!("Signature recovery failed: {}", e)))?;
+
+    Ok(recovered == expected_signer)
+}
@@ -0,0 +1,213 @@
+use crate::error::ServiceError;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::path::Path;
+
+/// First bytes of every sealed artifact
+pub const SEALED_MAGIC: &[u8; 8] = b"HIXSEAL1";
+
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+
+/// Artifact encryption settings, loaded from environment variables
+#[derive(Debug, Clone)]
+pub struct EncryptionConfig {
+    /// Key encrypting the per-artifact data keys, as `<backend>:<key>`
+    pub kms_key: String,
+}
+
+impl EncryptionConfig {
+    /// Load encryption settings; returns `None` when `KMS_KEY` is not set
+    pub fn from_env() -> Result<Option<Self>, ServiceError> {
+        match env::var("KMS_KEY") {
+            Ok(kms_key) if !kms_key.is_empty() => Ok(Some(Self { kms_key })),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Encrypts and decrypts the data keys of sealed artifacts
+///
+/// Implemented by the local key; a KMS client can implement it so the key
+/// encryption key never leaves the KMS.
+pub trait KeyWrapper: Send + Sync {
+    /// Identifier of the key encryption key, for logs
+    fn key_id(&self) -> &str;
+
+    fn wrap(&self, data_key: &[u8; KEY_LEN]) -> Result<Vec<u8>, ServiceError>;
+
+    fn unwrap(&self, wrapped: &[u8]) -> Result<[u8; KEY_LEN], ServiceError>;
+}
+
+/// Key encryption key held in memory, for development and tests
+pub struct LocalKeyWrapper {
+    cipher: Aes256Gcm,
+    key_id: String,
+}
+
+impl LocalKeyWrapper {
+    /// Wrapper for a hex-encoded 32-byte key
+    pub fn new(key_hex: &str) -> Result<Self, ServiceError> {
+        let key = hex::decode(key_hex.trim().trim_start_matches("0x"))
+            .map_err(|e| ServiceError::Config(format!("Invalid KMS_KEY local key: {}", e)))?;
+        if key.len() != KEY_LEN {
+            return Err(ServiceError::Config(format!(
+                "KMS_KEY local key must be {} bytes, got {}",
+                KEY_LEN,
+                key.len()
+            )));
+        }
+        // Names the key in logs without revealing it
+        let key_id = format!("local:{}", &hex::encode(Sha256::digest(&key))[..16]);
+        Ok(Self {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)),
+            key_id,
+        })
+    }
+}
+
+impl KeyWrapper for LocalKeyWrapper {
+    fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    fn wrap(&self, data_key: &[u8; KEY_LEN]) -> Result<Vec<u8>, ServiceError> {
+        seal_with(&self.cipher, data_key)
+    }
+
+    fn unwrap(&self, wrapped: &[u8]) -> Result<[u8; KEY_LEN], ServiceError> {
+        open_with(&self.cipher, wrapped)?.try_into().map_err(|_| {
+            ServiceError::Encryption("Wrapped data key has the wrong size".to_string())
+        })
+    }
+}
+
+/// Envelope encryption of proof artifacts at rest
+///
+/// Each artifact is encrypted with a fresh AES-256-GCM data key, stored wrapped by
+/// the key encryption key next to the ciphertext:
+/// `SEALED_MAGIC | wrapped key length (u16 BE) | wrapped key | nonce | ciphertext`.
+pub struct ArtifactCipher {
+    wrapper: Box<dyn KeyWrapper>,
+}
+
+impl ArtifactCipher {
+    pub fn new(wrapper: Box<dyn KeyWrapper>) -> Self {
+        Self { wrapper }
+    }
+
+    /// Cipher for `KMS_KEY`; `local:<64 hex digits>` is the only built-in backend
+    pub fn from_config(config: &EncryptionConfig) -> Result<Self, ServiceError> {
+        let (backend, key) = config.kms_key.split_once(':').unwrap_or(("", ""));
+        let wrapper: Box<dyn KeyWrapper> = match backend {
+            "local" => Box::new(LocalKeyWrapper::new(key)?),
+            other => {
+                return Err(ServiceError::Config(format!(
+                    "Invalid KMS_KEY backend '{}', expected 'local:<64 hex digits>'",
+                    other
+                )))
+            }
+        };
+        Ok(Self::new(wrapper))
+    }
+
+    /// Identifier of the key encryption key, for logs
+    pub fn key_id(&self) -> &str {
+        self.wrapper.key_id()
+    }
+
+    /// Encrypt `plaintext` into a sealed artifact
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, ServiceError> {
+        let data_key = Aes256Gcm::generate_key(OsRng);
+        let wrapped = self.wrapper.wrap(&data_key.into())?;
+        let wrapped_len = u16::try_from(wrapped.len())
+            .map_err(|_| ServiceError::Encryption("Wrapped data key is too long".to_string()))?;
+        let sealed = seal_with(&Aes256Gcm::new(&data_key), plaintext)?;
+
+        let mut artifact =
+            Vec::with_capacity(SEALED_MAGIC.len() + 2 + wrapped.len() + sealed.len());
+        artifact.extend_from_slice(SEALED_MAGIC);
+        artifact.extend_from_slice(&wrapped_len.to_be_bytes());
+        artifact.extend_from_slice(&wrapped);
+        artifact.extend_from_slice(&sealed);
+        Ok(artifact)
+    }
+
+    /// Decrypt a sealed artifact; fails on anything not sealed under this key
+    pub fn decrypt(&self, artifact: &[u8]) -> Result<Vec<u8>, ServiceError> {
+        let rest = artifact
+            .strip_prefix(SEALED_MAGIC.as_slice())
+            .ok_or_else(|| ServiceError::Encryption("Artifact is not sealed".to_string()))?;
+        let (wrapped_len, rest) = rest
+            .split_first_chunk::<2>()
+            .ok_or_else(|| ServiceError::Encryption("Sealed artifact is truncated".to_string()))?;
+        let wrapped_len = u16::from_be_bytes(*wrapped_len) as usize;
+        if rest.len() < wrapped_len {
+            return Err(ServiceError::Encryption(
+                "Sealed artifact is truncated".to_string(),
+            ));
+        }
+        let (wrapped, sealed) = rest.split_at(wrapped_len);
+
+        let data_key = self.wrapper.unwrap(wrapped)?;
+        open_with(&Aes256Gcm::new(&data_key.into()), sealed)
+    }
+
+    /// Replace every file under `dir` with its sealed form, skipping top-level
+    /// entries named in `skip`
+    pub fn seal_dir(&self, dir: &Path, skip: &[&str]) -> Result<(), ServiceError> {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            if skip.iter().any(|name| entry.file_name() == *name) {
+                continue;
+            }
+            let path = entry.path();
+            if entry.file_type()?.is_dir() {
+                self.seal_dir(&path, &[])?;
+            } else {
+                self.seal_file(&path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Replace the file at `path` with its sealed form
+    pub fn seal_file(&self, path: &Path) -> Result<(), ServiceError> {
+        let sealed = self.encrypt(&std::fs::read(path)?)?;
+        // Written aside and renamed, so a crash leaves no half-sealed file
+        let mut sealing = path.as_os_str().to_owned();
+        sealing.push(".sealing");
+        std::fs::write(&sealing, sealed)?;
+        std::fs::rename(&sealing, path)?;
+        Ok(())
+    }
+}
+
+/// `nonce | ciphertext` of `plaintext` under `cipher` with a random nonce
+fn seal_with(cipher: &Aes256Gcm, plaintext: &[u8]) -> Result<Vec<u8>, ServiceError> {
+    let nonce = Aes256Gcm::generate_nonce(OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| ServiceError::Encryption("Encryption failed".to_string()))?;
+    let mut sealed = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+/// Inverse of `seal_with`; fails if the data was altered or sealed under another key
+fn open_with(cipher: &Aes256Gcm, sealed: &[u8]) -> Result<Vec<u8>, ServiceError> {
+    if sealed.len() < NONCE_LEN {
+        return Err(ServiceError::Encryption(
+            "Sealed artifact is truncated".to_string(),
+        ));
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| {
+            ServiceError::Encryption("Decryption failed: wrong key or altered artifact".to_string())
+        })
+}
@@ -0,0 +1,208 @@
+use crate::error::ServiceError;
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+/// A Pub/Sub project, the subscription requests are received from there and the
+/// topic their results are published to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PubsubEndpoint {
+    pub project_id: String,
+    pub subscription: String,
+    pub result_topic: String,
+}
+
+impl PubsubEndpoint {
+    /// Full path of the subscription
+    pub fn subscription_path(&self) -> String {
+        format!(
+            "projects/{}/subscriptions/{}",
+            self.project_id, self.subscription
+        )
+    }
+
+    /// Full path of `topic` in the endpoint's project
+    pub fn topic_path(&self, topic: &str) -> String {
+        format!("projects/{}/topics/{}", self.project_id, topic)
+    }
+
+    /// Key a journaled request records its endpoint by
+    pub fn key(&self) -> String {
+        format!("{}/{}", self.project_id, self.subscription)
+    }
+}
+
+/// Parses `<project_id>/<subscription>/<result_topic>`, as in `PUBSUB_ENDPOINTS`
+impl FromStr for PubsubEndpoint {
+    type Err = ServiceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split('/').collect::<Vec<_>>()[..] {
+            [project_id, subscription, result_topic]
+                if !project_id.is_empty() && !subscription.is_empty() && !result_topic.is_empty() =>
+            {
+                Ok(Self {
+                    project_id: project_id.to_string(),
+                    subscription: subscription.to_string(),
+                    result_topic: result_topic.to_string(),
+                })
+            }
+            _ => Err(ServiceError::Config(format!(
+                "Invalid Pub/Sub endpoint '{}', expected '<project_id>/<subscription>/<result_topic>'",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for PubsubEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.subscription_path())
+    }
+}
+
+/// What to do after a receive error on the active endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failover {
+    /// Receive from the same endpoint again; `errors` in a row so far
+    Retry { errors: u32 },
+    /// Switch to another endpoint, see `Endpoints::candidates`
+    Switch,
+}
+
+/// Active endpoint, as reported by the status endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EndpointStatus {
+    /// Index of the active endpoint in `PUBSUB_ENDPOINTS`, 0 for the primary
+    pub active: usize,
+    pub endpoint: PubsubEndpoint,
+    pub endpoints: usize,
+    /// Receive errors on the active endpoint since it last delivered a message
+    pub consecutive_errors: u32,
+    /// Switches since startup
+    pub failovers: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+/// Ordered endpoints and the one requests are received from
+///
+/// The service receives from one endpoint at a time. Receive errors are counted
+/// until the endpoint delivers a message again; after `failover_errors` of them in
+/// a row it switches to the next endpoint, wrapping around to the primary after the
+/// last.
+pub struct Endpoints {
+    endpoints: Vec<PubsubEndpoint>,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    active: usize,
+    consecutive_errors: u32,
+    failovers: u64,
+    last_error: Option<String>,
+}
+
+impl Endpoints {
+    /// Endpoints in failover order, receiving from `active`
+    pub fn new(endpoints: Vec<PubsubEndpoint>, active: usize) -> Self {
+        assert!(active < endpoints.len(), "active endpoint out of range");
+        Self {
+            endpoints,
+            state: Mutex::new(State {
+                active,
+                ..State::default()
+            }),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.endpoints.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.endpoints.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> &PubsubEndpoint {
+        &self.endpoints[index]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &PubsubEndpoint> {
+        self.endpoints.iter()
+    }
+
+    /// Index of the endpoint with `key`, see `PubsubEndpoint::key`
+    pub fn find(&self, key: &str) -> Option<usize> {
+        self.endpoints
+            .iter()
+            .position(|endpoint| endpoint.key() == key)
+    }
+
+    /// Index of the endpoint requests are received from
+    pub fn active(&self) -> usize {
+        self.lock().active
+    }
+
+    /// Endpoint `index` delivered a message, so it is healthy
+    pub fn record_message(&self, index: usize) {
+        let mut state = self.lock();
+        if state.active == index {
+            state.consecutive_errors = 0;
+        }
+    }
+
+    /// Receiving from the active endpoint `index` failed with `error`
+    ///
+    /// Returns whether to retry it or switch away after `failover_errors` errors
+    /// in a row. With a single endpoint there is nothing to switch to.
+    pub fn record_error(&self, index: usize, error: &str, failover_errors: u32) -> Failover {
+        let mut state = self.lock();
+        state.last_error = Some(error.to_string());
+        if state.active == index {
+            state.consecutive_errors += 1;
+        }
+        if state.consecutive_errors < failover_errors || self.endpoints.len() < 2 {
+            return Failover::Retry {
+                errors: state.consecutive_errors,
+            };
+        }
+        Failover::Switch
+    }
+
+    /// Endpoints to switch to from `from`, in failover order
+    pub fn candidates(&self, from: usize) -> Vec<usize> {
+        (1..self.endpoints.len())
+            .map(|step| (from + step) % self.endpoints.len())
+            .collect()
+    }
+
+    /// Make `to` the active endpoint instead of `from`
+    pub fn switch(&self, from: usize, to: usize) {
+        let mut state = self.lock();
+        if state.active != from || from == to {
+            return;
+        }
+        state.active = to;
+        state.consecutive_errors = 0;
+        state.failovers += 1;
+    }
+
+    pub fn status(&self) -> EndpointStatus {
+        let state = self.lock();
+        EndpointStatus {
+            active: state.active,
+            endpoint: self.endpoints[state.active].clone(),
+            endpoints: self.endpoints.len(),
+            consecutive_errors: state.consecutive_errors,
+            failovers: state.failovers,
+            last_error: state.last_error.clone(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().expect("endpoints lock poisoned")
+    }
+}
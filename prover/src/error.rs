@@ -1,3 +1,5 @@
+use human_index_lib::PublicValuesError;
+use std::fmt;
 use thiserror::Error;
 
 /// Service-level errors for the prover
@@ -12,11 +14,44 @@ pub enum ServiceError {
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
 
+    #[error("Malformed request: {0}")]
+    MalformedRequest(String),
+
+    #[error("Request too large: {0}")]
+    RequestTooLarge(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
     #[error("Configuration error: {0}")]
     Config(String),
+
+    #[error("On-chain submission failed: {0}")]
+    Onchain(String),
+
+    #[error("Attestation error: {0}")]
+    Attestation(String),
+
+    #[error("KMS error: {0}")]
+    Kms(String),
+
+    #[error("Compression error: {0}")]
+    Compression(String),
+
+    #[error("Docker preflight failed: {0}")]
+    Docker(String),
+
+    #[error("Storage error: {0}")]
+    Storage(String),
+
+    #[error("Encryption error: {0}")]
+    Encryption(String),
+
+    #[error("Invalid public values: {0}")]
+    PublicValues(#[from] PublicValuesError),
+
+    #[error("{0}")]
+    PublicValuesMismatch(#[from] PublicValuesMismatch),
 }
 
 impl ServiceError {
@@ -25,9 +60,85 @@ impl ServiceError {
             ServiceError::PubSub(_) => "PubSubError",
             ServiceError::ProofGeneration(_) => "ProofGenerationError",
             ServiceError::Serialization(_) => "SerializationError",
+            ServiceError::MalformedRequest(_) => "MalformedRequest",
+            ServiceError::RequestTooLarge(_) => "RequestTooLarge",
             ServiceError::Io(_) => "IoError",
             ServiceError::Config(_) => "ConfigError",
+            ServiceError::Onchain(_) => "OnchainError",
+            ServiceError::Attestation(_) => "AttestationError",
+            ServiceError::Kms(_) => "KmsError",
+            ServiceError::Compression(_) => "CompressionError",
+            ServiceError::Docker(_) => "DockerError",
+            ServiceError::Storage(_) => "StorageError",
+            ServiceError::Encryption(_) => "EncryptionError",
+            ServiceError::PublicValues(_) => "PublicValuesError",
+            ServiceError::PublicValuesMismatch(_) => "PublicValuesMismatch",
         }
         .to_string()
     }
 }
+
+/// Why committed public values could not be decoded from a `ProofData`
+#[derive(Debug, Error)]
+pub enum DecodeError {
+    #[error("Invalid base64 public values: {0}")]
+    Base64(#[from] base64::DecodeError),
+
+    #[error("Invalid hex public values: {0}")]
+    Hex(#[from] hex::FromHexError),
+
+    #[error("Invalid public values: {0}")]
+    Layout(#[from] bincode::Error),
+
+    /// Decoded, but values no honest guest commits
+    #[error("Invalid public values: {0}")]
+    PublicValues(#[from] PublicValuesError),
+}
+
+impl From<DecodeError> for ServiceError {
+    fn from(e: DecodeError) -> Self {
+        match e {
+            DecodeError::PublicValues(e) => ServiceError::PublicValues(e),
+            e => ServiceError::ProofGeneration(e.to_string()),
+        }
+    }
+}
+
+/// A committed public value that differs from what the request asked to prove
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldMismatch {
+    /// Name of the field, e.g. `w2` or `valid_until`
+    pub field: &'static str,
+    pub expected: String,
+    pub committed: String,
+}
+
+/// Public values the proof committed that do not encode the request, one entry per
+/// differing field
+///
+/// Raised when the guest and host disagree on what was proved, e.g. because they
+/// read stdin in different orders.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub struct PublicValuesMismatch(pub Vec<FieldMismatch>);
+
+impl PublicValuesMismatch {
+    /// Names of the differing fields
+    pub fn fields(&self) -> Vec<&'static str> {
+        self.0.iter().map(|mismatch| mismatch.field).collect()
+    }
+}
+
+impl fmt::Display for PublicValuesMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Committed public values differ from the request:")?;
+        for (i, mismatch) in self.0.iter().enumerate() {
+            let separator = if i == 0 { " " } else { ", " };
+            write!(
+                f,
+                "{}{} (expected {}, committed {})",
+                separator, mismatch.field, mismatch.expected, mismatch.committed
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,306 @@
+use crate::types::ProofData;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use human_index_lib::{check_human_index, PublicValuesError, Validity};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// Function selector of `verifyPicoProof(bytes32,bytes,uint256[8])` on `PicoVerifier.sol`
+pub const VERIFY_PICO_PROOF_SELECTOR: [u8; 4] = [0xee, 0x87, 0xde, 0x37];
+
+/// Number of field elements in an uncompressed Groth16 proof (A: 2, B: 4, C: 2)
+pub const GROTH16_PROOF_WORDS: usize = 8;
+
+/// Offset of `computed_output` in the bincode-encoded `PublicValues`, a little-endian
+/// u32 after the five of the inputs
+pub const COMPUTED_OUTPUT_OFFSET: usize = 20;
+
+/// Offsets of `issued_at` and `valid_until` in the bincode-encoded `PublicValues`
+///
+/// Both are little-endian u64s after the inputs, output and weights commitment;
+/// `PicoVerifier.sol` reads `valid_until` at the same offset.
+pub const ISSUED_AT_OFFSET: usize = 56;
+pub const VALID_UNTIL_OFFSET: usize = 64;
+
+/// ABI head size: bytes32 + offset of `bytes` + inline uint256[8]
+const HEAD_SIZE: usize = 32 + 32 + GROTH16_PROOF_WORDS * 32;
+
+/// Errors raised while building or decoding verifier calldata
+#[derive(Debug, Error)]
+pub enum EvmError {
+    #[error("Invalid base64 in {field}: {message}")]
+    Base64 {
+        field: &'static str,
+        message: String,
+    },
+
+    #[error("Invalid hex in {field}: {message}")]
+    Hex {
+        field: &'static str,
+        message: String,
+    },
+
+    #[error("Invalid proof array: {0}")]
+    Proof(String),
+
+    #[error("Invalid verification key: {0}")]
+    VerificationKey(String),
+
+    #[error("Malformed calldata: {0}")]
+    Calldata(String),
+
+    #[error("Invalid public values: {0}")]
+    PublicValues(#[from] PublicValuesError),
+}
+
+/// Decoded arguments of a `verifyPicoProof` call
+///
+/// The proof words are kept in the order the generated Groth16Verifier expects:
+/// `[A.x, A.y, B.x1, B.x0, B.y1, B.y0, C.x, C.y]`, which is also the order Pico
+/// writes them to `inputs.json`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyCall {
+    /// RISC-V program verification key (`riscvVKey`)
+    pub riscv_vkey: [u8; 32],
+
+    /// Raw committed public values
+    pub public_values: Vec<u8>,
+
+    /// Uncompressed Groth16 proof as big-endian uint256 words
+    pub proof: [[u8; 32]; GROTH16_PROOF_WORDS],
+}
+
+impl VerifyCall {
+    /// Decode the base64 fields of a `ProofData` into typed call arguments
+    pub fn from_proof_data(proof_data: &ProofData) -> Result<Self, EvmError> {
+        let proof_json = proof_data
+            .decompressed_proof()
+            .map_err(|e| EvmError::Proof(e.to_string()))?;
+        let words: Vec<String> = serde_json::from_slice(&proof_json)
+            .map_err(|e| EvmError::Proof(format!("expected a JSON array of strings: {}", e)))?;
+        if words.len() != GROTH16_PROOF_WORDS {
+            return Err(EvmError::Proof(format!(
+                "expected {} elements, got {}",
+                GROTH16_PROOF_WORDS,
+                words.len()
+            )));
+        }
+
+        let mut proof = [[0u8; 32]; GROTH16_PROOF_WORDS];
+        for (i, word) in words.iter().enumerate() {
+            proof[i] = parse_uint256(word)
+                .map_err(|e| EvmError::Proof(format!("element {}: {}", i, e)))?;
+        }
+
+        let public_values = committed_public_values(proof_data)?;
+
+        let vkey_hex = decode_base64("verification_key", &proof_data.verification_key)?;
+        let vkey = decode_hex("verification_key", &vkey_hex)?;
+        let riscv_vkey: [u8; 32] = vkey.as_slice().try_into().map_err(|_| {
+            EvmError::VerificationKey(format!("expected 32 bytes, got {}", vkey.len()))
+        })?;
+
+        Ok(Self {
+            riscv_vkey,
+            public_values,
+            proof,
+        })
+    }
+
+    /// Public inputs `verifyPicoProof` passes on to `Groth16Verifier.verifyProof`
+    ///
+    /// `[riscvVkey, hashPublicValues(publicValues)]`, as big-endian uint256 words.
+    pub fn public_inputs(&self) -> [[u8; 32]; 2] {
+        [self.riscv_vkey, public_values_digest(&self.public_values)]
+    }
+
+    /// Human index committed in `public_values`, rejected above `MAX_HUMAN_INDEX`
+    pub fn human_index(&self) -> Result<u32, EvmError> {
+        let offset = COMPUTED_OUTPUT_OFFSET;
+        let bytes = self.public_values.get(offset..offset + 4).ok_or_else(|| {
+            EvmError::Calldata(format!(
+                "public values are {} bytes, too short for the human index",
+                self.public_values.len()
+            ))
+        })?;
+        let computed_output = u32::from_le_bytes(bytes.try_into().expect("slice is 4 bytes"));
+        check_human_index(computed_output)?;
+        Ok(computed_output)
+    }
+
+    /// Validity window committed in `public_values`, read as the contract reads it
+    pub fn validity(&self) -> Result<Validity, EvmError> {
+        committed_validity(&self.public_values)
+    }
+
+    /// ABI-encode the full call, including the function selector
+    pub fn encode(&self) -> Vec<u8> {
+        let padded_len = self.public_values.len().div_ceil(32) * 32;
+        let mut out = Vec::with_capacity(4 + HEAD_SIZE + 32 + padded_len);
+
+        out.extend_from_slice(&VERIFY_PICO_PROOF_SELECTOR);
+        out.extend_from_slice(&self.riscv_vkey);
+        out.extend_from_slice(&encode_usize(HEAD_SIZE));
+        for word in &self.proof {
+            out.extend_from_slice(word);
+        }
+
+        out.extend_from_slice(&encode_usize(self.public_values.len()));
+        out.extend_from_slice(&self.public_values);
+        out.resize(out.len() + padded_len - self.public_values.len(), 0);
+
+        out
+    }
+
+    /// Decode calldata produced by [`VerifyCall::encode`] (or any ABI encoder)
+    pub fn decode(calldata: &[u8]) -> Result<Self, EvmError> {
+        if calldata.len() < 4 + HEAD_SIZE {
+            return Err(EvmError::Calldata(format!(
+                "expected at least {} bytes, got {}",
+                4 + HEAD_SIZE,
+                calldata.len()
+            )));
+        }
+        if calldata[..4] != VERIFY_PICO_PROOF_SELECTOR {
+            return Err(EvmError::Calldata(format!(
+                "unexpected selector 0x{}",
+                hex::encode(&calldata[..4])
+            )));
+        }
+
+        let args = &calldata[4..];
+        let riscv_vkey: [u8; 32] = args[..32].try_into().expect("slice is 32 bytes");
+
+        let mut proof = [[0u8; 32]; GROTH16_PROOF_WORDS];
+        for (i, word) in proof.iter_mut().enumerate() {
+            let start = 64 + i * 32;
+            word.copy_from_slice(&args[start..start + 32]);
+        }
+
+        let offset = read_usize(&args[32..64])?;
+        let len_end = offset
+            .checked_add(32)
+            .filter(|end| *end <= args.len())
+            .ok_or_else(|| EvmError::Calldata("public values offset out of bounds".to_string()))?;
+        let len = read_usize(&args[offset..len_end])?;
+        let data_end = len_end
+            .checked_add(len)
+            .filter(|end| *end <= args.len())
+            .ok_or_else(|| EvmError::Calldata("public values length out of bounds".to_string()))?;
+
+        Ok(Self {
+            riscv_vkey,
+            public_values: args[len_end..data_end].to_vec(),
+            proof,
+        })
+    }
+}
+
+/// Build the `verifyPicoProof(bytes32, bytes, uint256[8])` calldata for a proof
+pub fn build_verify_calldata(proof_data: &ProofData) -> Result<Vec<u8>, EvmError> {
+    Ok(VerifyCall::from_proof_data(proof_data)?.encode())
+}
+
+/// Decode `verifyPicoProof` calldata back into its arguments
+pub fn decode_calldata(calldata: &[u8]) -> Result<VerifyCall, EvmError> {
+    VerifyCall::decode(calldata)
+}
+
+/// Validity window committed in `public_values`, read as the contract reads it
+pub fn committed_validity(public_values: &[u8]) -> Result<Validity, EvmError> {
+    let read = |offset: usize| -> Result<u64, EvmError> {
+        let bytes = public_values.get(offset..offset + 8).ok_or_else(|| {
+            EvmError::Calldata(format!(
+                "public values are {} bytes, too short for the validity window",
+                public_values.len()
+            ))
+        })?;
+        Ok(u64::from_le_bytes(
+            bytes.try_into().expect("slice is 8 bytes"),
+        ))
+    };
+    Ok(Validity {
+        issued_at: read(ISSUED_AT_OFFSET)?,
+        valid_until: read(VALID_UNTIL_OFFSET)?,
+    })
+}
+
+/// Public values committed in a proof, decoded from `public_inputs`
+pub fn committed_public_values(proof_data: &ProofData) -> Result<Vec<u8>, EvmError> {
+    let public_values_hex = decode_base64("public_inputs", &proof_data.public_inputs)?;
+    decode_hex("public_inputs", &public_values_hex)
+}
+
+/// `PicoVerifier.hashPublicValues`: SHA-256 truncated to 253 bits to fit the BN254 scalar field
+pub fn public_values_digest(public_values: &[u8]) -> [u8; 32] {
+    let mut digest: [u8; 32] = Sha256::digest(public_values).into();
+    digest[0] &= 0x1f;
+    digest
+}
+
+fn decode_base64(field: &'static str, value: &str) -> Result<Vec<u8>, EvmError> {
+    STANDARD.decode(value).map_err(|e| EvmError::Base64 {
+        field,
+        message: e.to_string(),
+    })
+}
+
+fn decode_hex(field: &'static str, value: &[u8]) -> Result<Vec<u8>, EvmError> {
+    let value = std::str::from_utf8(value).map_err(|e| EvmError::Hex {
+        field,
+        message: e.to_string(),
+    })?;
+    let digits = value.trim().trim_start_matches("0x");
+    hex::decode(digits).map_err(|e| EvmError::Hex {
+        field,
+        message: e.to_string(),
+    })
+}
+
+/// Parse a uint256 given either as `0x`-prefixed hex or as a decimal string
+fn parse_uint256(value: &str) -> Result<[u8; 32], String> {
+    let value = value.trim();
+    let mut out = [0u8; 32];
+
+    if let Some(digits) = value.strip_prefix("0x") {
+        if digits.is_empty() || digits.len() > 64 {
+            return Err(format!("hex value has {} digits", digits.len()));
+        }
+        let padded = format!("{:0>64}", digits);
+        hex::decode_to_slice(&padded, &mut out).map_err(|e| e.to_string())?;
+        return Ok(out);
+    }
+
+    if value.is_empty() {
+        return Err("empty value".to_string());
+    }
+    for c in value.chars() {
+        let digit = c
+            .to_digit(10)
+            .ok_or_else(|| format!("invalid decimal digit '{}'", c))?;
+        // out = out * 10 + digit, big-endian
+        let mut carry = digit;
+        for byte in out.iter_mut().rev() {
+            let v = (*byte as u32) * 10 + carry;
+            *byte = (v & 0xff) as u8;
+            carry = v >> 8;
+        }
+        if carry != 0 {
+            return Err("value exceeds 256 bits".to_string());
+        }
+    }
+    Ok(out)
+}
+
+fn encode_usize(value: usize) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out[24..].copy_from_slice(&(value as u64).to_be_bytes());
+    out
+}
+
+fn read_usize(word: &[u8]) -> Result<usize, EvmError> {
+    if word[..24].iter().any(|b| *b != 0) {
+        return Err(EvmError::Calldata("uint256 value too large".to_string()));
+    }
+    let value = u64::from_be_bytes(word[24..32].try_into().expect("slice is 8 bytes"));
+    usize::try_from(value).map_err(|_| EvmError::Calldata("uint256 value too large".to_string()))
+}
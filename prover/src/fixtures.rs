@@ -0,0 +1,276 @@
+use crate::types::{ProofData, ProverRequest};
+use human_index_lib::{
+    HumanIndexPublicInputs, PublicValues, Validity, VerificationResults, GUEST_VERSION,
+    MAX_VALIDITY_SECS,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Name of the manifest in a fixtures directory
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Validity window fixtures are proven with, fixed so they are reproducible
+pub const FIXTURE_VALIDITY: Validity = Validity {
+    issued_at: 1_700_000_000,
+    valid_until: 1_700_000_000 + MAX_VALIDITY_SECS,
+};
+
+/// Errors raised while loading fixtures
+#[derive(Debug, Error)]
+pub enum FixtureError {
+    #[error("Failed to read {path}: {message}")]
+    Read { path: PathBuf, message: String },
+
+    #[error(
+        "Fixtures in {dir} were recorded for guest version {recorded}, but GUEST_VERSION is \
+         {current}; regenerate them with `cargo run --bin gen-fixtures -- --mode all`"
+    )]
+    StaleGuest {
+        dir: PathBuf,
+        recorded: u32,
+        current: u32,
+    },
+
+    #[error(
+        "Fixtures in {dir} were recorded from other golden vectors; regenerate them with \
+         `cargo run --bin gen-fixtures -- --mode all`"
+    )]
+    StaleVectors { dir: PathBuf },
+
+    #[error(
+        "No {kind} fixture for vector '{vector}'; generate it with \
+         `cargo run --bin gen-fixtures -- --mode {mode}`",
+        mode = kind.mode()
+    )]
+    Missing { kind: FixtureKind, vector: String },
+
+    #[error("{path} does not match the hash in the manifest; it was modified after generation")]
+    Modified { path: PathBuf },
+
+    #[error("Invalid {what}: {message}")]
+    Invalid { what: String, message: String },
+}
+
+/// One test case from `testdata/golden_vectors.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoldenVector {
+    pub name: String,
+    pub verification_results: VerificationResults,
+    pub public_inputs: HumanIndexPublicInputs,
+}
+
+impl GoldenVector {
+    /// The request fixtures for this vector are generated from
+    pub fn request(&self) -> ProverRequest {
+        // Only the required fields and a fixed window, so later optional fields
+        // leave the fixtures unchanged
+        serde_json::from_value(serde_json::json!({
+            "request_id": format!("fixture:{}", self.name),
+            "verification_results": self.verification_results,
+            "public_inputs": self.public_inputs,
+            "issued_at": FIXTURE_VALIDITY.issued_at,
+            "valid_until": FIXTURE_VALIDITY.valid_until,
+        }))
+        .expect("golden vector forms a request")
+    }
+}
+
+/// Kinds of recorded artifact
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FixtureKind {
+    /// Public values committed by the guest under emulation
+    PvStream,
+    /// `ProofData` from the fast profile
+    FastProof,
+    /// `ProofData` from the mock backend
+    MockProof,
+}
+
+impl FixtureKind {
+    /// `gen-fixtures` mode that records this kind
+    pub fn mode(self) -> &'static str {
+        match self {
+            Self::PvStream => "execute",
+            Self::FastProof => "fast",
+            Self::MockProof => "mock",
+        }
+    }
+
+    /// Path of the fixture for `vector`, relative to the fixtures directory
+    pub fn path(self, vector: &str) -> String {
+        match self {
+            Self::PvStream => format!("pv/{}.bin", vector),
+            Self::FastProof => format!("fast/{}.json", vector),
+            Self::MockProof => format!("mock/{}.json", vector),
+        }
+    }
+}
+
+impl fmt::Display for FixtureKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::PvStream => "pv stream",
+            Self::FastProof => "fast proof",
+            Self::MockProof => "mock proof",
+        })
+    }
+}
+
+/// Index of a fixtures directory, written by `gen-fixtures`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// `GUEST_VERSION` the fixtures were recorded for
+    pub guest_version: u32,
+    /// Hex-encoded SHA-256 of the golden vectors file
+    pub vectors_sha256: String,
+    pub fixtures: Vec<FixtureEntry>,
+}
+
+/// A recorded fixture
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FixtureEntry {
+    pub vector: String,
+    pub kind: FixtureKind,
+    /// Relative to the fixtures directory
+    pub path: String,
+    /// Hex-encoded SHA-256 of the file
+    pub sha256: String,
+}
+
+impl Manifest {
+    /// Record `contents` as the `kind` fixture of `vector`, replacing any earlier one
+    pub fn record(&mut self, vector: &str, kind: FixtureKind, contents: &[u8]) {
+        self.fixtures
+            .retain(|entry| !(entry.vector == vector && entry.kind == kind));
+        self.fixtures.push(FixtureEntry {
+            vector: vector.to_string(),
+            kind,
+            path: kind.path(vector),
+            sha256: hex::encode(Sha256::digest(contents)),
+        });
+        self.fixtures
+            .sort_by(|a, b| (a.kind, &a.vector).cmp(&(b.kind, &b.vector)));
+    }
+}
+
+/// Typed access to a fixtures directory
+///
+/// Opening fails if the fixtures were recorded for another `GUEST_VERSION`, and
+/// each load checks the file against the hash in the manifest.
+pub struct Fixtures {
+    dir: PathBuf,
+    manifest: Manifest,
+}
+
+impl Fixtures {
+    /// The fixtures checked in at `prover/fixtures`, checked against the golden vectors
+    pub fn workspace() -> Result<Self, FixtureError> {
+        let fixtures = Self::open(&workspace_path("fixtures"))?;
+        let vectors = read(&golden_vectors_path())?;
+        if hex::encode(Sha256::digest(vectors)) != fixtures.manifest.vectors_sha256 {
+            return Err(FixtureError::StaleVectors { dir: fixtures.dir });
+        }
+        Ok(fixtures)
+    }
+
+    /// Open the fixtures at `dir`
+    pub fn open(dir: &Path) -> Result<Self, FixtureError> {
+        let manifest = read(&dir.join(MANIFEST_FILE))?;
+        let manifest: Manifest = parse(MANIFEST_FILE, serde_json::from_slice(&manifest))?;
+        if manifest.guest_version != GUEST_VERSION {
+            return Err(FixtureError::StaleGuest {
+                dir: dir.to_path_buf(),
+                recorded: manifest.guest_version,
+                current: GUEST_VERSION,
+            });
+        }
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            manifest,
+        })
+    }
+
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+
+    /// Public values the guest committed for `vector`
+    pub fn pv_stream(&self, vector: &str) -> Result<Vec<u8>, FixtureError> {
+        self.load(vector, FixtureKind::PvStream)
+    }
+
+    /// Fast-profile proof of `vector`
+    pub fn fast_proof(&self, vector: &str) -> Result<ProofData, FixtureError> {
+        self.load_proof(vector, FixtureKind::FastProof)
+    }
+
+    /// Mock-backend proof of `vector`
+    pub fn mock_proof(&self, vector: &str) -> Result<ProofData, FixtureError> {
+        self.load_proof(vector, FixtureKind::MockProof)
+    }
+
+    fn load_proof(&self, vector: &str, kind: FixtureKind) -> Result<ProofData, FixtureError> {
+        let bytes = self.load(vector, kind)?;
+        parse(&kind.path(vector), serde_json::from_slice(&bytes))
+    }
+
+    fn load(&self, vector: &str, kind: FixtureKind) -> Result<Vec<u8>, FixtureError> {
+        let entry = self
+            .manifest
+            .fixtures
+            .iter()
+            .find(|entry| entry.vector == vector && entry.kind == kind)
+            .ok_or_else(|| FixtureError::Missing {
+                kind,
+                vector: vector.to_string(),
+            })?;
+        let path = self.dir.join(&entry.path);
+        let bytes = read(&path)?;
+        if hex::encode(Sha256::digest(&bytes)) != entry.sha256 {
+            return Err(FixtureError::Modified { path });
+        }
+        Ok(bytes)
+    }
+}
+
+/// The golden vectors at `prover/testdata/golden_vectors.json`
+pub fn golden_vectors() -> Result<Vec<GoldenVector>, FixtureError> {
+    read_golden_vectors(&golden_vectors_path())
+}
+
+/// Read golden vectors from `path`
+pub fn read_golden_vectors(path: &Path) -> Result<Vec<GoldenVector>, FixtureError> {
+    let bytes = read(path)?;
+    parse("golden vectors", serde_json::from_slice(&bytes))
+}
+
+/// The public values encoded in a proof's `public_inputs`
+pub fn public_values(proof: &ProofData) -> Result<PublicValues, FixtureError> {
+    parse("public values", proof.public_values())
+}
+
+fn workspace_path(relative: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join(relative)
+}
+
+fn golden_vectors_path() -> PathBuf {
+    workspace_path("testdata/golden_vectors.json")
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, FixtureError> {
+    std::fs::read(path).map_err(|e| FixtureError::Read {
+        path: path.to_path_buf(),
+        message: e.to_string(),
+    })
+}
+
+fn parse<T, E: fmt::Display>(what: &str, result: Result<T, E>) -> Result<T, FixtureError> {
+    result.map_err(|e| FixtureError::Invalid {
+        what: what.to_string(),
+        message: e.to_string(),
+    })
+}
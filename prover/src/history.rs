@@ -0,0 +1,90 @@
+use crate::types::{ProofStatus, ProverResponse};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// The last responses a worker published, for `GET /recent`
+///
+/// Only what identifies a response and how it went is kept: a proof, its public
+/// values and anything derived from the verification results are left out.
+pub struct ResponseHistory {
+    capacity: usize,
+    responses: Mutex<VecDeque<RecentResponse>>,
+}
+
+/// A published response, without its proof
+#[derive(Debug, Clone, Serialize)]
+pub struct RecentResponse {
+    pub request_id: String,
+    pub status: ProofStatus,
+    /// Bulk job the request was a line of
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_request_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub received_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queue_wait_ms: Option<u64>,
+    /// Type of the error, never its message: some messages carry the human index
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_type: Option<String>,
+}
+
+impl RecentResponse {
+    pub fn new(response: &ProverResponse) -> Self {
+        let metrics = response.metrics.as_ref();
+        Self {
+            request_id: response.request_id.clone(),
+            status: response.status.clone(),
+            parent_request_id: response.parent_request_id.clone(),
+            received_at: metrics.map(|metrics| metrics.received_at.clone()),
+            completed_at: metrics.map(|metrics| metrics.completed_at.clone()),
+            duration_ms: metrics.map(|metrics| metrics.duration_ms),
+            queue_wait_ms: metrics.and_then(|metrics| metrics.queue_wait_ms),
+            error_type: response
+                .error
+                .as_ref()
+                .map(|error| error.error_type.clone()),
+        }
+    }
+}
+
+impl ResponseHistory {
+    /// Keep up to `capacity` responses, forgetting the oldest beyond it
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            responses: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Remember `response` as the most recent one
+    pub fn record(&self, response: &ProverResponse) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut responses = self.lock();
+        if responses.len() == self.capacity {
+            responses.pop_front();
+        }
+        responses.push_back(RecentResponse::new(response));
+    }
+
+    /// The remembered responses, oldest first
+    pub fn recent(&self) -> Vec<RecentResponse> {
+        self.lock().iter().cloned().collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<RecentResponse>> {
+        self.responses
+            .lock()
+            .expect("response history lock poisoned")
+    }
+}
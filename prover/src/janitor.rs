@@ -0,0 +1,295 @@
+use crate::prover::SETUP_FILES;
+use crate::setup_store::SETUP_STORE_DIR;
+use crate::usage::dir_bytes;
+use human_index_lib::CircuitId;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use tracing::warn;
+
+/// What a reclaimable entry in the output directory is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Category {
+    /// `<output_dir>/<request_id>`, left behind by a crashed or abandoned proof
+    Request,
+    /// Attempt count of a request, in `<quarantine_dir>/attempts`
+    Attempts,
+    /// Quarantined request, in `<quarantine_dir>`
+    Quarantined,
+}
+
+/// Why an entry was removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reason {
+    /// Older than the TTL of its category
+    Expired,
+    /// Oldest while the output directory was over `max_bytes`
+    Budget,
+}
+
+/// Age limits and size budget the janitor enforces
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JanitorPolicy {
+    /// Request directories older than this are removed
+    pub request_ttl: Duration,
+    /// Request directories younger than this may belong to a running proof and are
+    /// never removed, whatever the TTL or budget
+    pub min_request_age: Duration,
+    /// Attempt counts and quarantined requests older than this are removed; they
+    /// are kept when unset
+    pub quarantine_ttl: Option<Duration>,
+    /// Budget for the output directory; the oldest reclaimable entries are removed
+    /// while it is exceeded
+    pub max_bytes: Option<u64>,
+}
+
+/// An entry the janitor may remove
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub path: PathBuf,
+    pub category: Category,
+    pub modified: SystemTime,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Removal {
+    pub path: PathBuf,
+    pub category: Category,
+    pub reason: Reason,
+    pub bytes: u64,
+}
+
+/// Outcome of one sweep
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Sweep {
+    pub removed: Vec<Removal>,
+    /// Size of the output directory after the sweep
+    pub usage_bytes: u64,
+    /// Still above `max_bytes` with nothing left to remove
+    pub over_budget: bool,
+}
+
+impl Sweep {
+    pub fn reclaimed_bytes(&self) -> u64 {
+        self.removed.iter().map(|removal| removal.bytes).sum()
+    }
+
+    /// Number of entries removed from `category`
+    pub fn removed_count(&self, category: Category) -> usize {
+        self.removed
+            .iter()
+            .filter(|removal| removal.category == category)
+            .count()
+    }
+}
+
+/// Output directory usage, as of the last sweep
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct OutputDirUsage {
+    pub usage_bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_bytes: Option<u64>,
+    pub over_budget: bool,
+    /// RFC 3339 time of the last sweep; absent before the first
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_sweep_at: Option<String>,
+    pub last_reclaimed_bytes: u64,
+}
+
+/// Removes what failed and abandoned proofs leave in the output directory
+///
+/// Only request directories and the quarantine store are touched. A request
+/// directory is recognized by the setup files linked into it; anything else in the
+/// output directory, such as `vm_pk`, `vm_vk`, the setup store, the setup files of
+/// other circuits or the delayed queue, counts towards the budget but is never
+/// removed.
+pub struct Janitor {
+    output_dir: PathBuf,
+    quarantine_dir: PathBuf,
+    usage: Mutex<OutputDirUsage>,
+}
+
+impl Janitor {
+    pub fn new(output_dir: &Path, quarantine_dir: &Path) -> Self {
+        Self {
+            output_dir: output_dir.to_path_buf(),
+            quarantine_dir: quarantine_dir.to_path_buf(),
+            usage: Mutex::new(OutputDirUsage::default()),
+        }
+    }
+
+    /// Usage as of the last sweep
+    pub fn usage(&self) -> OutputDirUsage {
+        self.usage
+            .lock()
+            .expect("janitor usage lock poisoned")
+            .clone()
+    }
+
+    /// Reclaimable entries, oldest first
+    pub fn scan(&self) -> Vec<Entry> {
+        let mut entries = Vec::new();
+        let quarantine_dir = self.quarantine_dir.canonicalize().ok();
+        for path in list(&self.output_dir) {
+            let is_quarantine =
+                quarantine_dir.is_some() && path.canonicalize().ok() == quarantine_dir;
+            if !is_quarantine && !is_setup_dir(&path) && is_request_dir(&path) {
+                entries.extend(entry(path, Category::Request));
+            }
+        }
+        for path in list(&self.quarantine_dir.join("attempts")) {
+            entries.extend(entry(path, Category::Attempts));
+        }
+        for path in list(&self.quarantine_dir) {
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "json")
+            {
+                entries.extend(entry(path, Category::Quarantined));
+            }
+        }
+        entries.sort_by_key(|entry| entry.modified);
+        entries
+    }
+
+    /// Size of the output directory, not counting setup files linked into
+    /// request directories
+    pub fn usage_bytes(&self) -> u64 {
+        list(&self.output_dir)
+            .iter()
+            // Links into the setup store count as links; the blobs count in the store
+            .map(|path| match path.symlink_metadata() {
+                Ok(metadata) if metadata.is_dir() => dir_bytes(path, &SETUP_FILES),
+                Ok(metadata) => metadata.len(),
+                Err(_) => 0,
+            })
+            .sum()
+    }
+
+    /// Remove the entries `policy` expires at `now`, then the oldest ones while
+    /// over budget
+    ///
+    /// Entries that fail to be removed are logged and skipped.
+    pub fn sweep(&self, policy: &JanitorPolicy, now: SystemTime) -> Sweep {
+        let mut sweep = Sweep::default();
+        let mut kept = Vec::new();
+        for entry in self.scan() {
+            let age = now.duration_since(entry.modified).unwrap_or_default();
+            if !policy.manages(&entry, age) {
+                continue;
+            }
+            match policy.ttl(entry.category) {
+                Some(ttl) if age >= ttl => {
+                    remove(entry, Reason::Expired, &mut sweep);
+                }
+                _ => kept.push(entry),
+            }
+        }
+
+        sweep.usage_bytes = self.usage_bytes();
+        if let Some(max_bytes) = policy.max_bytes {
+            // A quarantine directory elsewhere does not count towards the budget;
+            // `kept` is oldest first
+            let mut kept = kept
+                .into_iter()
+                .filter(|entry| entry.path.starts_with(&self.output_dir));
+            while sweep.usage_bytes > max_bytes {
+                let Some(entry) = kept.next() else {
+                    sweep.over_budget = true;
+                    break;
+                };
+                let bytes = entry.bytes;
+                if remove(entry, Reason::Budget, &mut sweep) {
+                    sweep.usage_bytes = sweep.usage_bytes.saturating_sub(bytes);
+                }
+            }
+        }
+
+        let mut usage = self.usage.lock().expect("janitor usage lock poisoned");
+        *usage = OutputDirUsage {
+            usage_bytes: sweep.usage_bytes,
+            max_bytes: policy.max_bytes,
+            over_budget: sweep.over_budget,
+            last_sweep_at: Some(chrono::DateTime::<chrono::Utc>::from(now).to_rfc3339()),
+            last_reclaimed_bytes: sweep.reclaimed_bytes(),
+        };
+        sweep
+    }
+}
+
+impl JanitorPolicy {
+    /// Whether the janitor may remove `entry` at all, at `age`
+    ///
+    /// Quarantine entries are only managed with a `quarantine_ttl`, so the budget
+    /// never releases a quarantined request that was meant to be kept.
+    fn manages(&self, entry: &Entry, age: Duration) -> bool {
+        match entry.category {
+            Category::Request => age >= self.min_request_age,
+            Category::Attempts | Category::Quarantined => self.quarantine_ttl.is_some(),
+        }
+    }
+
+    fn ttl(&self, category: Category) -> Option<Duration> {
+        match category {
+            Category::Request => Some(self.request_ttl),
+            Category::Attempts | Category::Quarantined => self.quarantine_ttl,
+        }
+    }
+}
+
+/// Paths of the entries of `dir`; empty if it cannot be read
+fn list(dir: &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default()
+}
+
+/// A directory `prove_evm` ran in: it has the setup files linked into it
+fn is_request_dir(path: &Path) -> bool {
+    path.is_dir() && SETUP_FILES.iter().any(|name| path.join(name).exists())
+}
+
+/// The setup store or the setup files of a circuit, which are never removed
+fn is_setup_dir(path: &Path) -> bool {
+    let Some(name) = path.file_name() else {
+        return false;
+    };
+    name == SETUP_STORE_DIR || CircuitId::ALL.iter().any(|circuit| name == circuit.name())
+}
+
+fn entry(path: PathBuf, category: Category) -> Option<Entry> {
+    let metadata = path.metadata().ok()?;
+    let bytes = match metadata.is_dir() {
+        true => dir_bytes(&path, &SETUP_FILES),
+        false => metadata.len(),
+    };
+    Some(Entry {
+        modified: metadata.modified().ok()?,
+        path,
+        category,
+        bytes,
+    })
+}
+
+/// Remove `entry` and record it in `sweep`; returns whether it was removed
+fn remove(entry: Entry, reason: Reason, sweep: &mut Sweep) -> bool {
+    let result = match entry.category {
+        Category::Request => std::fs::remove_dir_all(&entry.path),
+        Category::Attempts | Category::Quarantined => std::fs::remove_file(&entry.path),
+    };
+    if let Err(e) = result {
+        warn!("Failed to remove {}: {}", entry.path.display(), e);
+        return false;
+    }
+    sweep.removed.push(Removal {
+        path: entry.path,
+        category: entry.category,
+        reason,
+        bytes: entry.bytes,
+    });
+    true
+}
@@ -0,0 +1,198 @@
+use crate::error::ServiceError;
+use alloy_primitives::{Address, Signature, B256, U256};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use google_cloud_storage::client::google_cloud_auth::project::Config as AuthConfig;
+use google_cloud_storage::client::google_cloud_auth::token::DefaultTokenSourceProvider;
+use google_cloud_token::{TokenSource, TokenSourceProvider};
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use k256::pkcs8::DecodePublicKey;
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::info;
+
+/// Cloud KMS REST endpoint
+pub const KMS_ENDPOINT: &str = "https://cloudkms.googleapis.com";
+
+const KMS_SCOPES: [&str; 1] = ["https://www.googleapis.com/auth/cloudkms"];
+
+/// Timeout of a single KMS call
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Signs with an `EC_SIGN_SECP256K1_SHA256` key version in Google Cloud KMS
+///
+/// The private key never leaves KMS. The 32-byte digest is sent as the `sha256`
+/// digest of an asymmetricSign call; the DER signature that comes back is made
+/// low-s and given the recovery bit that recovers to the key's address.
+pub struct KmsSigner {
+    http: reqwest::Client,
+    endpoint: String,
+    key_version: String,
+    token_source: Option<Arc<dyn TokenSource>>,
+    address: Address,
+}
+
+#[derive(Deserialize)]
+struct PublicKeyResponse {
+    pem: String,
+}
+
+#[derive(Deserialize)]
+struct SignResponse {
+    signature: String,
+}
+
+impl KmsSigner {
+    /// Connect to `key_version`, authenticating with Application Default Credentials
+    ///
+    /// `key_version` is the full resource name, `projects/*/locations/*/keyRings/*/
+    /// cryptoKeys/*/cryptoKeyVersions/*`.
+    pub async fn connect(key_version: &str) -> Result<Self, ServiceError> {
+        let provider =
+            DefaultTokenSourceProvider::new(AuthConfig::default().with_scopes(&KMS_SCOPES))
+                .await
+                .map_err(|e| ServiceError::Kms(format!("Failed to load credentials: {}", e)))?;
+        Self::with_endpoint(key_version, KMS_ENDPOINT, Some(provider.token_source())).await
+    }
+
+    /// Connect to `key_version` on another endpoint, e.g. a local fake, without
+    /// authenticating when `token_source` is `None`
+    pub async fn with_endpoint(
+        key_version: &str,
+        endpoint: &str,
+        token_source: Option<Arc<dyn TokenSource>>,
+    ) -> Result<Self, ServiceError> {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| ServiceError::Kms(e.to_string()))?;
+        let mut signer = Self {
+            http,
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            key_version: key_version.to_string(),
+            token_source,
+            address: Address::ZERO,
+        };
+
+        let response: PublicKeyResponse = signer
+            .call(signer.http.get(signer.url("/publicKey")))
+            .await?;
+        signer.address = address_from_pem(&response.pem)?;
+        info!(key_version = key_version, address = %signer.address, "KMS signer ready");
+        Ok(signer)
+    }
+
+    /// Address the signatures recover to
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    /// Sign a prehashed 32-byte digest
+    pub async fn sign_digest(&self, digest: B256) -> Result<Signature, ServiceError> {
+        let body = serde_json::json!({ "digest": { "sha256": STANDARD.encode(digest) } });
+        let request = self
+            .http
+            .post(self.url(":asymmetricSign"))
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.to_string());
+        let response: SignResponse = self.call(request).await?;
+        let der = STANDARD
+            .decode(&response.signature)
+            .map_err(|e| ServiceError::Kms(format!("Invalid signature encoding: {}", e)))?;
+        self.recoverable(digest, &der)
+    }
+
+    /// Turn a DER signature over `digest` into a low-s signature with its recovery bit
+    fn recoverable(&self, digest: B256, der: &[u8]) -> Result<Signature, ServiceError> {
+        let signature = k256::ecdsa::Signature::from_der(der)
+            .map_err(|e| ServiceError::Kms(format!("Invalid DER signature: {}", e)))?;
+        // Ethereum only accepts the low-s form; KMS may return either
+        let signature = signature.normalize_s().unwrap_or(signature);
+        let (r, s) = signature.split_bytes();
+        let (r, s) = (U256::from_be_slice(&r), U256::from_be_slice(&s));
+
+        for y_parity in [false, true] {
+            let candidate = Signature::new(r, s, y_parity);
+            if candidate.recover_address_from_prehash(&digest).ok() == Some(self.address) {
+                return Ok(candidate);
+            }
+        }
+        Err(ServiceError::Kms(format!(
+            "Signature does not recover to {}",
+            self.address
+        )))
+    }
+
+    fn url(&self, suffix: &str) -> String {
+        format!("{}/v1/{}{}", self.endpoint, self.key_version, suffix)
+    }
+
+    /// Send an authenticated request and parse its JSON response
+    async fn call<T: for<'de> Deserialize<'de>>(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<T, ServiceError> {
+        let request = match &self.token_source {
+            Some(token_source) => {
+                let token = token_source
+                    .token()
+                    .await
+                    .map_err(|e| ServiceError::Kms(format!("Failed to get a token: {}", e)))?;
+                request.header(reqwest::header::AUTHORIZATION, token)
+            }
+            None => request,
+        };
+        let response = request
+            .send()
+            .await
+            .map_err(|e| ServiceError::Kms(e.without_url().to_string()))?;
+        let status = response.status();
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| ServiceError::Kms(e.without_url().to_string()))?;
+        if !status.is_success() {
+            return Err(ServiceError::Kms(format!(
+                "{} returned {}: {}",
+                self.key_version,
+                status.as_u16(),
+                String::from_utf8_lossy(&body)
+            )));
+        }
+        serde_json::from_slice(&body)
+            .map_err(|e| ServiceError::Kms(format!("Unexpected KMS response: {}", e)))
+    }
+}
+
+/// Ethereum address of the secp256k1 public key in a PEM `SubjectPublicKeyInfo`
+fn address_from_pem(pem: &str) -> Result<Address, ServiceError> {
+    let base64: String = pem
+        .lines()
+        .filter(|line| !line.starts_with("-----"))
+        .collect();
+    let der = STANDARD
+        .decode(base64.trim())
+        .map_err(|e| ServiceError::Kms(format!("Invalid public key PEM: {}", e)))?;
+    let key = k256::PublicKey::from_public_key_der(&der)
+        .map_err(|e| ServiceError::Kms(format!("Public key is not a secp256k1 key: {}", e)))?;
+    let point = key.to_encoded_point(false);
+    // Uncompressed point: 0x04 || x || y
+    Ok(Address::from_raw_public_key(&point.as_bytes()[1..]))
+}
+
+#[cfg(feature = "onchain")]
+#[async_trait::async_trait]
+impl alloy_network::TxSigner<Signature> for KmsSigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    async fn sign_transaction(
+        &self,
+        tx: &mut dyn alloy_consensus::SignableTransaction<Signature>,
+    ) -> alloy_signer::Result<Signature> {
+        self.sign_digest(tx.signature_hash())
+            .await
+            .map_err(alloy_signer::Error::other)
+    }
+}
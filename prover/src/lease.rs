@@ -0,0 +1,310 @@
+use crate::error::ServiceError;
+use chrono::Utc;
+use std::collections::HashMap;
+use std::env;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, warn};
+
+/// How often a worker waiting for a lease asks the backend again
+pub const LEASE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Configuration for cluster-wide admission, loaded from environment variables
+#[derive(Debug, Clone)]
+pub struct LeaseConfig {
+    /// Proofs allowed in flight across all workers sharing `key`
+    pub global_max_concurrent: u32,
+
+    /// Lease backend, e.g. `redis://leases:6379`
+    pub url: String,
+
+    /// Name of the lease set in the backend; workers with the same key share the cap
+    pub key: String,
+
+    /// How long a lease outlives a worker that stopped renewing it
+    pub ttl: Duration,
+}
+
+impl LeaseConfig {
+    /// Load lease settings; returns `None` when `GLOBAL_MAX_CONCURRENT` is not set
+    pub fn from_env() -> Result<Option<Self>, ServiceError> {
+        let global_max_concurrent = match env::var("GLOBAL_MAX_CONCURRENT") {
+            Ok(value) => value.parse::<u32>().map_err(|e| {
+                ServiceError::Config(format!("Invalid GLOBAL_MAX_CONCURRENT: {}", e))
+            })?,
+            Err(_) => return Ok(None),
+        };
+        if global_max_concurrent == 0 {
+            return Err(ServiceError::Config(
+                "GLOBAL_MAX_CONCURRENT must be at least 1".to_string(),
+            ));
+        }
+
+        let url = env::var("GLOBAL_LEASE_URL").map_err(|_| {
+            ServiceError::Config(
+                "GLOBAL_LEASE_URL must be set with GLOBAL_MAX_CONCURRENT".to_string(),
+            )
+        })?;
+
+        let key = env::var("GLOBAL_LEASE_KEY").unwrap_or_else(|_| "prover:leases".to_string());
+
+        let ttl_secs = env::var("GLOBAL_LEASE_TTL_SECS")
+            .unwrap_or_else(|_| "60".to_string())
+            .parse::<u64>()
+            .map_err(|e| ServiceError::Config(format!("Invalid GLOBAL_LEASE_TTL_SECS: {}", e)))?;
+        if ttl_secs < 3 {
+            return Err(ServiceError::Config(
+                "GLOBAL_LEASE_TTL_SECS must be at least 3".to_string(),
+            ));
+        }
+
+        Ok(Some(Self {
+            global_max_concurrent,
+            url,
+            key,
+            ttl: Duration::from_secs(ttl_secs),
+        }))
+    }
+
+    /// Connect to the backend named by `url`
+    pub fn backend(&self) -> Result<Arc<dyn LeaseBackend>, ServiceError> {
+        match self.url.split_once("://").map(|(scheme, _)| scheme) {
+            #[cfg(feature = "redis-lease")]
+            Some("redis" | "rediss") => Ok(Arc::new(crate::redis_lease::RedisLeases::open(
+                &self.url, &self.key,
+            )?)),
+            #[cfg(not(feature = "redis-lease"))]
+            Some("redis" | "rediss") => Err(ServiceError::Config(
+                "GLOBAL_LEASE_URL is a Redis URL, but the prover was built without the \
+                 redis-lease feature"
+                    .to_string(),
+            )),
+            _ => Err(ServiceError::Config(format!(
+                "Unsupported GLOBAL_LEASE_URL '{}', expected redis://",
+                self.url
+            ))),
+        }
+    }
+}
+
+/// Shared store of the leases held across the cluster
+///
+/// Calls may block on the network, so `GlobalAdmission` runs them off the async
+/// runtime. A lease not renewed within its `ttl` no longer counts towards the limit.
+pub trait LeaseBackend: Send + Sync + 'static {
+    /// Take a lease for `holder` if fewer than `limit` unexpired leases are held
+    fn try_acquire(&self, holder: &str, limit: u32, ttl: Duration) -> Result<bool, ServiceError>;
+
+    /// Extend `holder`'s lease by `ttl`; `false` if it had already expired
+    fn renew(&self, holder: &str, ttl: Duration) -> Result<bool, ServiceError>;
+
+    fn release(&self, holder: &str) -> Result<(), ServiceError>;
+
+    fn name(&self) -> &'static str;
+}
+
+/// Leases held in this process, for tests and single-process setups
+#[derive(Default)]
+pub struct MemoryLeases {
+    /// Expiry of each holder's lease
+    leases: Mutex<HashMap<String, Instant>>,
+}
+
+impl MemoryLeases {
+    /// Unexpired leases
+    pub fn held(&self) -> usize {
+        let now = Instant::now();
+        let leases = self.leases.lock().unwrap();
+        leases.values().filter(|expiry| **expiry > now).count()
+    }
+}
+
+impl LeaseBackend for MemoryLeases {
+    fn try_acquire(&self, holder: &str, limit: u32, ttl: Duration) -> Result<bool, ServiceError> {
+        let now = Instant::now();
+        let mut leases = self.leases.lock().unwrap();
+        leases.retain(|_, expiry| *expiry > now);
+        if !leases.contains_key(holder) && leases.len() >= limit as usize {
+            return Ok(false);
+        }
+        leases.insert(holder.to_string(), now + ttl);
+        Ok(true)
+    }
+
+    fn renew(&self, holder: &str, ttl: Duration) -> Result<bool, ServiceError> {
+        let now = Instant::now();
+        let mut leases = self.leases.lock().unwrap();
+        match leases.get_mut(holder) {
+            Some(expiry) if *expiry > now => {
+                *expiry = now + ttl;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn release(&self, holder: &str) -> Result<(), ServiceError> {
+        self.leases.lock().unwrap().remove(holder);
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+}
+
+/// Caps proofs in flight across workers by taking a lease for each one
+pub struct GlobalAdmission {
+    backend: Arc<dyn LeaseBackend>,
+    limit: u32,
+    ttl: Duration,
+    poll_interval: Duration,
+    /// Prefix of this process's lease holders
+    instance: String,
+    next_lease: AtomicU64,
+}
+
+impl GlobalAdmission {
+    pub fn new(backend: Arc<dyn LeaseBackend>, limit: u32, ttl: Duration, worker_id: &str) -> Self {
+        Self {
+            backend,
+            limit,
+            ttl,
+            poll_interval: LEASE_POLL_INTERVAL,
+            // Distinct per start, so a restarted worker never adopts a lease it held before
+            instance: format!("{}:{}", worker_id, Utc::now().timestamp_millis()),
+            next_lease: AtomicU64::new(0),
+        }
+    }
+
+    /// Admission as configured, connecting to its backend
+    pub fn from_config(config: &LeaseConfig, worker_id: &str) -> Result<Self, ServiceError> {
+        Ok(Self::new(
+            config.backend()?,
+            config.global_max_concurrent,
+            config.ttl,
+            worker_id,
+        ))
+    }
+
+    /// Ask the backend every `poll_interval` while waiting
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    pub fn limit(&self) -> u32 {
+        self.limit
+    }
+
+    pub fn backend_name(&self) -> &'static str {
+        self.backend.name()
+    }
+
+    /// Wait for a lease, renewing it until it is released
+    ///
+    /// Backend errors are retried: proving without a lease could oversubscribe
+    /// the cluster.
+    pub async fn acquire(&self) -> Lease {
+        let holder = format!(
+            "{}:{}",
+            self.instance,
+            self.next_lease.fetch_add(1, Ordering::Relaxed)
+        );
+        loop {
+            let backend = self.backend.clone();
+            let (limit, ttl) = (self.limit, self.ttl);
+            let attempt_holder = holder.clone();
+            let acquired = tokio::task::spawn_blocking(move || {
+                backend.try_acquire(&attempt_holder, limit, ttl)
+            })
+            .await
+            .unwrap_or_else(|e| Err(ServiceError::Storage(format!("Task join error: {}", e))));
+            match acquired {
+                Ok(true) => break,
+                Ok(false) => {}
+                Err(e) => warn!(holder = %holder, "Failed to acquire proof lease: {}", e),
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+
+        debug!(holder = %holder, "Proof lease acquired");
+        let renewal = CancellationToken::new();
+        tokio::spawn(renew(
+            self.backend.clone(),
+            holder.clone(),
+            self.ttl,
+            renewal.clone(),
+        ));
+        Lease {
+            backend: self.backend.clone(),
+            holder,
+            renewal,
+        }
+    }
+}
+
+/// Extend a lease every third of its TTL until `stop` is cancelled
+async fn renew(
+    backend: Arc<dyn LeaseBackend>,
+    holder: String,
+    ttl: Duration,
+    stop: CancellationToken,
+) {
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(ttl / 3) => {}
+            _ = stop.cancelled() => return,
+        }
+        let renew_backend = backend.clone();
+        let renew_holder = holder.clone();
+        let renewed =
+            tokio::task::spawn_blocking(move || renew_backend.renew(&renew_holder, ttl)).await;
+        match renewed {
+            Ok(Ok(true)) => {}
+            // Another worker may take the slot, so the cluster can briefly exceed the limit
+            Ok(Ok(false)) => {
+                warn!(holder = %holder, "Proof lease expired while proving");
+                return;
+            }
+            Ok(Err(e)) => warn!(holder = %holder, "Failed to renew proof lease: {}", e),
+            Err(e) => warn!(holder = %holder, "Lease renewal task failed: {}", e),
+        }
+    }
+}
+
+/// A held lease; release it when the proof completes
+///
+/// Dropping it without `release` stops renewal, so the lease expires after its TTL.
+pub struct Lease {
+    backend: Arc<dyn LeaseBackend>,
+    holder: String,
+    renewal: CancellationToken,
+}
+
+impl Lease {
+    pub fn holder(&self) -> &str {
+        &self.holder
+    }
+
+    /// Stop renewing and give the lease back
+    pub async fn release(self) {
+        self.renewal.cancel();
+        let backend = self.backend.clone();
+        let holder = self.holder.clone();
+        match tokio::task::spawn_blocking(move || backend.release(&holder)).await {
+            Ok(Ok(())) => debug!(holder = %self.holder, "Proof lease released"),
+            // It expires after its TTL instead
+            Ok(Err(e)) => error!(holder = %self.holder, "Failed to release proof lease: {}", e),
+            Err(e) => error!(holder = %self.holder, "Lease release task failed: {}", e),
+        }
+    }
+}
+
+impl Drop for Lease {
+    fn drop(&mut self) {
+        self.renewal.cancel();
+    }
+}
@@ -0,0 +1,51 @@
+pub mod audit;
+pub mod bulk;
+pub mod canonical;
+pub mod compression;
+pub mod config;
+pub mod decimal;
+pub mod delayed;
+pub mod docker;
+#[cfg(feature = "attestation")]
+pub mod eip712;
+pub mod encryption;
+pub mod endpoints;
+pub mod error;
+pub mod evm;
+pub mod fixtures;
+pub mod history;
+pub mod janitor;
+#[cfg(any(feature = "onchain", feature = "attestation"))]
+pub mod kms;
+pub mod lease;
+pub mod logging;
+pub mod memory;
+pub mod metrics_log;
+pub mod mock;
+pub mod object_store;
+#[cfg(feature = "onchain")]
+pub mod onchain;
+pub mod prometheus;
+pub mod proof_cache;
+pub mod provenance;
+pub mod prover;
+pub mod quarantine;
+pub mod ramp;
+pub mod redact;
+#[cfg(feature = "redis-lease")]
+pub mod redis_lease;
+pub mod reproduce;
+pub mod request_builder;
+pub mod response_check;
+pub mod scheduler;
+pub mod schema;
+pub mod service;
+pub mod session;
+pub mod setup_store;
+pub mod startup;
+pub mod status;
+pub mod telemetry;
+pub mod types;
+pub mod usage;
+pub mod verifier;
+pub mod webhook;
@@ -0,0 +1,16 @@
+use crate::error::ServiceError;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+/// Handle used to swap the log filter at runtime
+pub type LogReloadHandle = reload::Handle<EnvFilter, Registry>;
+
+/// Filter logs with `level` from now on, e.g. after a configuration reload
+///
+/// An invalid level leaves the current filter in place.
+pub fn reload_log_level(handle: &LogReloadHandle, level: &str) -> Result<(), ServiceError> {
+    let filter = EnvFilter::try_new(level)
+        .map_err(|e| ServiceError::Config(format!("Invalid LOG_LEVEL '{}': {}", level, e)))?;
+    handle
+        .reload(filter)
+        .map_err(|e| ServiceError::Config(format!("Failed to apply LOG_LEVEL: {}", e)))
+}
@@ -1,72 +1,240 @@
-mod config;
-mod error;
-mod prover;
-mod service;
-mod types;
-
-use config::Config;
-use error::ServiceError;
-use prover::load_and_cache_elf;
-use service::ProverService;
+use prover::config::{Config, ProverBackendKind, Role};
+use prover::docker::DockerMemoryCheck;
+use prover::encryption::{ArtifactCipher, EncryptionConfig};
+use prover::error::ServiceError;
+use prover::logging::{reload_log_level, LogReloadHandle};
+use prover::mock::MockProofGenerator;
+use prover::prometheus::{self, MetricsState};
+use prover::proof_cache::CachingBackend;
+use prover::provenance::Provenance;
+use prover::prover::{NoBackend, ProofBackend, ProofGenerator, ProverProfile};
+use prover::service::{drain_within, ProverService};
+use prover::startup::{load_artifacts, load_config, self_test, StartupCategory, StartupFailure};
+use prover::status::{self, StatusState};
+use prover::webhook::WebhookConfig;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::signal;
+use tokio::signal::unix::{signal as unix_signal, Signal, SignalKind};
 use tokio_util::sync::CancellationToken;
-use tracing::{error, info};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use tracing::{error, info, warn};
+use tracing_subscriber::{layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter};
+
+/// Exit code when a second shutdown signal skips the drain
+const FORCED_EXIT_CODE: i32 = 130;
 
 #[tokio::main]
 async fn main() -> Result<(), ServiceError> {
-    // Load configuration
-    let config = Config::from_env()?;
-    config.validate()?;
+    // Load configuration; a failure to start exits with its category's code
+    let config = load_config().unwrap_or_else(|failure| failure.exit());
 
     // Initialize logging
-    init_logging(&config);
+    let log_handle = init_logging(&config);
 
     info!("Starting Pico ZK Prover Service");
     info!("Configuration loaded successfully");
     info!("  GCP Project: {}", config.gcp_project_id);
-    info!("  Subscription: {}", config.prover_subscription);
+    info!("  Role: {:?}", config.role);
+    info!("  Subscription: {}", config.subscription());
+    if let Some(work_topic) = &config.work_topic {
+        info!("  Work Topic: {}", work_topic);
+    }
     info!("  Result Topic: {}", config.result_topic);
-    info!("  Max Concurrent Proofs: {}", config.max_concurrent_proofs);
+    for (index, endpoint) in config.pubsub_endpoints.iter().enumerate().skip(1) {
+        info!(
+            "  Failover Endpoint {}: {} -> {}",
+            index, endpoint, endpoint.result_topic
+        );
+    }
+    if config.concurrency_auto {
+        info!(
+            "  Max Concurrent Proofs: {} (auto, {} GiB per proof)",
+            config.max_concurrent_proofs, config.memory_per_proof_gb
+        );
+    } else {
+        info!("  Max Concurrent Proofs: {}", config.max_concurrent_proofs);
+    }
     info!("  Proof Timeout: {}s", config.proof_timeout_secs);
     info!("  ELF Path: {}", config.elf_path);
     info!("  Output Dir: {}", config.output_dir);
+    info!("  Prover Backend: {:?}", config.prover_backend);
+    info!("  Prover Profile: {:?}", config.prover_profile);
+    info!("  Worker ID: {}", config.worker_id);
 
-    // Load and cache ELF file
-    info!("Loading ELF file: {}", config.elf_path);
-    let cached_elf = load_and_cache_elf(&config.elf_path).await?;
-    info!("ELF file loaded and cached successfully");
-
-    // Create output directory if it doesn't exist
-    std::fs::create_dir_all(&config.output_dir)?;
-
-    // Validate output directory is writable
-    let test_file = format!("{}/.write_test", config.output_dir);
-    std::fs::write(&test_file, "test").map_err(|e| {
-        error!("Output directory not writable: {}", e);
-        ServiceError::Config(format!(
-            "Output directory '{}' is not writable: {}",
-            config.output_dir, e
-        ))
-    })?;
-    std::fs::remove_file(&test_file).ok();
     info!("Output directory ready: {}", config.output_dir);
 
+    // A frontend never proves, so it needs neither the ELF nor Docker
+    if config.role == Role::Frontend {
+        let provenance = Provenance::collect(&config, None);
+        return run_service(config, NoBackend, provenance, None, log_handle).await;
+    }
+
+    match config.prover_backend {
+        ProverBackendKind::Pico => {
+            // Check Docker memory before hours of proving end in a missing inputs.json
+            let docker_check = self_test(&config)
+                .await
+                .unwrap_or_else(|failure| failure.exit());
+
+            let cached_elf = load_artifacts(&config)
+                .await
+                .unwrap_or_else(|failure| failure.exit());
+            let provenance = Provenance::collect(&config, Some(&cached_elf));
+
+            if config.prover_profile == ProverProfile::Fast {
+                warn!("Using the fast prover profile: proofs skip the Groth16 wrap and cannot be verified on-chain");
+            }
+            let artifact_cipher = load_artifact_cipher()
+                .unwrap_or_else(|failure| failure.exit())
+                .map(Arc::new);
+            let backend = ProofGenerator::new(
+                cached_elf,
+                PathBuf::from(&config.output_dir),
+                config.prover_profile,
+                config.max_proof_artifact_bytes,
+                config.max_stdin_bytes,
+                artifact_cipher,
+            )
+            .with_artifact_encoding(config.artifact_encoding)
+            .with_proof_json_layout(config.proof_json_layout);
+            run_service(config, backend, provenance, docker_check, log_handle).await
+        }
+        ProverBackendKind::Mock => {
+            warn!("Using the mock prover backend: proofs are fabricated and will NOT verify");
+            let backend = MockProofGenerator::new(
+                Duration::from_millis(config.mock_proof_delay_ms),
+                Duration::from_secs(config.proof_timeout_secs),
+            );
+            let provenance = Provenance::collect(&config, None);
+            run_service(config, backend, provenance, None, log_handle).await
+        }
+    }
+}
+
+/// Run the prover service with the given backend until shutdown
+async fn run_service<B: ProofBackend>(
+    config: Config,
+    backend: B,
+    provenance: Provenance,
+    docker_check: Option<DockerMemoryCheck>,
+    log_handle: LogReloadHandle,
+) -> Result<(), ServiceError> {
+    info!(
+        "  Provenance: prover {}, pico-sdk {}, ELF {}, setup {}",
+        provenance.prover_version,
+        provenance.pico_sdk_version,
+        provenance.elf_sha256.as_deref().unwrap_or("-"),
+        provenance.setup_sha256.as_deref().unwrap_or("-")
+    );
+
+    let status_port = config.status_port;
+    let metrics_port = config.metrics_port;
+    // Installed before the service starts, so no result goes unrecorded
+    let metrics_handle = metrics_port.map(|_| prometheus::install()).transpose()?;
+    let shutdown_grace = Duration::from_secs(config.shutdown_grace_secs);
+    let backend_name = backend.name();
+
+    // Build the prover client before the first request rather than for it
+    let warm_up = match tokio::task::block_in_place(|| backend.warm_up()) {
+        Ok(warm_up) => {
+            if let Some(warm_up) = &warm_up {
+                info!("Backend warmed up in {} ms", warm_up.duration_ms);
+            }
+            warm_up
+        }
+        Err(e) => {
+            warn!("Backend warm-up failed: {}", e);
+            None
+        }
+    };
+    let backend = CachingBackend::new(backend, config.proof_cache());
+
     // Initialize prover service
     info!("Initializing Prover Service");
-    let service = ProverService::new(config, cached_elf).await?;
+    let service = match ProverService::new(config, backend, provenance.clone()).await {
+        Ok(service) => Arc::new(service),
+        Err(e) => StartupFailure::new(StartupCategory::PubSub, e).exit(),
+    };
+    let mut status_state = StatusState::new(
+        backend_name,
+        docker_check,
+        provenance,
+        service.scheduler(),
+        service.delayed_queue(),
+        service.janitor(),
+        service.endpoints(),
+    )
+    .with_warm_up(warm_up)
+    .with_config(service.config())
+    .with_history(service.history());
+    // Journaled results are served to requests signed like callbacks
+    if let Some(webhook_config) = WebhookConfig::from_env()? {
+        status_state = status_state.with_signing_key(webhook_config.signing_key);
+    }
+    let status_state = Arc::new(status_state);
 
     // Create cancellation token for graceful shutdown
     let cancellation_token = CancellationToken::new();
     let shutdown_token = cancellation_token.clone();
 
-    // Spawn shutdown handler
+    // Spawn status server
+    if let Some(port) = status_port {
+        let status_shutdown = cancellation_token.clone();
+        tokio::spawn(async move {
+            if let Err(e) = status::serve(port, status_state, status_shutdown).await {
+                error!("Status server failed: {}", e);
+            }
+        });
+    }
+
+    // Spawn metrics server
+    if let (Some(port), Some(handle)) = (metrics_port, metrics_handle) {
+        let metrics_state = Arc::new(MetricsState::new(handle, service.scheduler()));
+        let metrics_shutdown = cancellation_token.clone();
+        tokio::spawn(async move {
+            if let Err(e) = prometheus::serve(port, metrics_state, metrics_shutdown).await {
+                error!("Metrics server failed: {}", e);
+            }
+        });
+    }
+
+    // Spawn shutdown handler; the grace period runs from the signal until `drain`
+    // has returned
+    let mut terminate = unix_signal(SignalKind::terminate())?;
+    let shutdown_service = service.clone();
+    let drained = CancellationToken::new();
+    let drain_done = drained.clone();
     tokio::spawn(async move {
-        if let Err(e) = signal::ctrl_c().await {
-            error!("Failed to listen for shutdown signal: {}", e);
-        }
-        info!("Shutdown signal received, stopping service...");
+        shutdown_signal(&mut terminate).await;
+        info!(
+            "Shutdown signal received, stopping intake and draining in-flight proofs for up to {}s",
+            shutdown_grace.as_secs()
+        );
         shutdown_token.cancel();
+
+        tokio::select! {
+            _ = drain_within(shutdown_grace, drained.cancelled(), || shutdown_service.interrupt()) => {}
+            _ = shutdown_signal(&mut terminate) => force_exit(),
+        }
+        shutdown_signal(&mut terminate).await;
+        force_exit();
+    });
+
+    // Spawn config reload handler
+    let reload_service = service.clone();
+    tokio::spawn(async move {
+        let mut hangup = match unix_signal(SignalKind::hangup()) {
+            Ok(hangup) => hangup,
+            Err(e) => {
+                error!("Failed to listen for SIGHUP, config reload disabled: {}", e);
+                return;
+            }
+        };
+        while hangup.recv().await.is_some() {
+            info!("SIGHUP received, reloading configuration");
+            reload_config(&reload_service, &log_handle);
+        }
     });
 
     // Run service with cancellation token
@@ -78,25 +246,97 @@ async fn main() -> Result<(), ServiceError> {
         }
     }
 
-    Ok(())
+    service.drain().await;
+    drain_done.cancel();
+    info!("Shutdown complete");
+
+    // Exit without waiting for the blocking threads of interrupted proofs
+    std::process::exit(0);
+}
+
+/// The cipher proof artifacts are sealed with, if encryption is configured
+fn load_artifact_cipher() -> Result<Option<ArtifactCipher>, StartupFailure> {
+    let fail = StartupFailure::during(StartupCategory::Config);
+    let Some(encryption_config) = EncryptionConfig::from_env().map_err(&fail)? else {
+        return Ok(None);
+    };
+    let cipher = ArtifactCipher::from_config(&encryption_config).map_err(&fail)?;
+    info!(
+        "Proof artifacts are encrypted at rest with key {}",
+        cipher.key_id()
+    );
+    Ok(Some(cipher))
+}
+
+/// Wait for SIGTERM or SIGINT
+async fn shutdown_signal(terminate: &mut Signal) {
+    // A failed SIGINT listener disables its branch; SIGTERM still works
+    tokio::select! {
+        Ok(()) = signal::ctrl_c() => {}
+        _ = terminate.recv() => {}
+    }
+}
+
+/// Exit immediately on a second shutdown signal
+fn force_exit() -> ! {
+    warn!("Second shutdown signal received, exiting without draining");
+    std::process::exit(FORCED_EXIT_CODE);
 }
 
 /// Initialize logging based on configuration
-fn init_logging(config: &Config) {
-    let env_filter = tracing_subscriber::EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(&config.log_level));
+/// Returns a handle that allows the log level to be changed at runtime
+fn init_logging(config: &Config) -> LogReloadHandle {
+    let env_filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(&config.log_level));
+    let (filter_layer, handle) = reload::Layer::new(env_filter);
 
     if config.json_logging {
         // JSON logging for production
         tracing_subscriber::registry()
-            .with(env_filter)
+            .with(filter_layer)
             .with(tracing_subscriber::fmt::layer().json())
             .init();
     } else {
         // Human-readable logging for development
         tracing_subscriber::registry()
-            .with(env_filter)
+            .with(filter_layer)
             .with(tracing_subscriber::fmt::layer())
             .init();
     }
+
+    handle
+}
+
+/// Re-read configuration and apply the settings that can change without a restart
+fn reload_config<B: ProofBackend>(service: &ProverService<B>, log_handle: &LogReloadHandle) {
+    let new_config = match Config::reload_from_env().and_then(|c| c.validate().map(|_| c)) {
+        Ok(config) => config,
+        Err(e) => {
+            error!("Config reload rejected, keeping current settings: {}", e);
+            return;
+        }
+    };
+
+    let old_log_level = service.config_snapshot().log_level;
+    if new_config.log_level != old_log_level {
+        if let Err(e) = reload_log_level(log_handle, &new_config.log_level) {
+            error!("Config reload rejected, keeping current settings: {}", e);
+            return;
+        }
+    }
+
+    let changes = service.apply_config(&new_config);
+    if changes.is_empty() {
+        info!("Config reload: no changes");
+        return;
+    }
+    for change in &changes.live {
+        info!("Config reload applied: {}", change);
+    }
+    for change in &changes.restart_required {
+        warn!(
+            "Config reload: {} requires a restart to take effect",
+            change
+        );
+    }
 }
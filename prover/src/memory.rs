@@ -0,0 +1,131 @@
+use crate::error::ServiceError;
+use std::time::Duration;
+use tokio::time::{sleep, Instant};
+use tracing::{info, warn};
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+/// Upper bound for `MAX_CONCURRENT_PROOFS=auto`
+pub const MAX_AUTO_CONCURRENT_PROOFS: usize = 16;
+
+/// How often `wait_for_memory` re-reads available memory
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Cgroup v2 memory limit, set when running in a container
+const CGROUP_MEMORY_MAX: &str = "/sys/fs/cgroup/memory.max";
+
+/// System memory figures in bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryInfo {
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+}
+
+impl MemoryInfo {
+    /// Read `/proc/meminfo`, capping the total at the container's cgroup limit
+    pub fn read() -> Result<Self, ServiceError> {
+        let meminfo = std::fs::read_to_string("/proc/meminfo")
+            .map_err(|e| ServiceError::Config(format!("Cannot read /proc/meminfo: {}", e)))?;
+        let mut info = parse_meminfo(&meminfo)?;
+
+        if let Some(limit) = std::fs::read_to_string(CGROUP_MEMORY_MAX)
+            .ok()
+            .and_then(|limit| limit.trim().parse::<u64>().ok())
+        {
+            info.total_bytes = info.total_bytes.min(limit);
+            info.available_bytes = info.available_bytes.min(limit);
+        }
+        Ok(info)
+    }
+}
+
+/// Extract `MemTotal` and `MemAvailable` from the contents of `/proc/meminfo`
+pub fn parse_meminfo(meminfo: &str) -> Result<MemoryInfo, ServiceError> {
+    let field = |name: &str| {
+        meminfo
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+            .and_then(|value| value.trim().strip_suffix("kB"))
+            .and_then(|kb| kb.trim().parse::<u64>().ok())
+            .map(|kb| kb * 1024)
+            .ok_or_else(|| ServiceError::Config(format!("/proc/meminfo has no {}", name)))
+    };
+
+    Ok(MemoryInfo {
+        total_bytes: field("MemTotal")?,
+        available_bytes: field("MemAvailable")?,
+    })
+}
+
+/// Proofs that fit in `total_bytes` at `memory_per_proof_gb` each
+///
+/// Always at least 1, so an undersized host still proves, one at a time, and at
+/// most `MAX_AUTO_CONCURRENT_PROOFS`.
+pub fn auto_concurrency(total_bytes: u64, memory_per_proof_gb: u64) -> usize {
+    let per_proof = memory_per_proof_gb.max(1) * GIB;
+    let fit = (total_bytes / per_proof) as usize;
+    fit.clamp(1, MAX_AUTO_CONCURRENT_PROOFS)
+}
+
+/// Wait up to `max_wait` for `min_available_gb` GiB of available memory
+///
+/// Returns whether the memory became available; the caller proceeds either way.
+pub async fn wait_for_memory(request_id: &str, min_available_gb: u64, max_wait: Duration) -> bool {
+    wait_for_available(
+        request_id,
+        min_available_gb * GIB,
+        max_wait,
+        POLL_INTERVAL,
+        || MemoryInfo::read().map(|info| info.available_bytes),
+    )
+    .await
+}
+
+/// `wait_for_memory` with an injectable memory reading
+pub async fn wait_for_available(
+    request_id: &str,
+    min_available_bytes: u64,
+    max_wait: Duration,
+    poll_interval: Duration,
+    mut read_available: impl FnMut() -> Result<u64, ServiceError>,
+) -> bool {
+    let deadline = Instant::now() + max_wait;
+    let mut waited = false;
+
+    loop {
+        let available = match read_available() {
+            Ok(available) => available,
+            Err(e) => {
+                warn!(request_id = %request_id, "Skipping memory check: {}", e);
+                return true;
+            }
+        };
+        if available >= min_available_bytes {
+            if waited {
+                info!(request_id = %request_id, "Memory available, starting proof");
+            }
+            return true;
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            warn!(
+                request_id = %request_id,
+                available_gib = available / GIB,
+                "Still low on memory after {}s, proving anyway",
+                max_wait.as_secs()
+            );
+            return false;
+        }
+        if !waited {
+            info!(
+                request_id = %request_id,
+                available_gib = available / GIB,
+                needed_gib = min_available_bytes / GIB,
+                "Low on memory, delaying proof"
+            );
+            waited = true;
+        }
+        sleep(poll_interval.min(deadline - now)).await;
+    }
+}
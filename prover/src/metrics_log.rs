@@ -0,0 +1,437 @@
+use crate::error::ServiceError;
+use crate::prometheus::DURATION_BUCKETS;
+use crate::types::{ProofStatus, ProverResponse};
+use chrono::{DateTime, DurationRound, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Directory under `OUTPUT_DIR` the snapshots are written to
+pub const METRICS_DIR: &str = "metrics";
+
+/// `client_id` of requests without one, and of requests that failed unanswered
+pub const NO_CLIENT: &str = "-";
+
+/// What a series counts completions by
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct SeriesKey {
+    /// Status of the response, e.g. `success` or `timeout`
+    pub outcome: String,
+    /// Profile the proof was made with, e.g. `evm`, or `mock`
+    pub proof_type: String,
+    pub client_id: String,
+}
+
+/// Completions of one series
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeriesCounts {
+    pub count: u64,
+    /// Sum of `duration_ms` of the completions that report it
+    pub duration_ms_sum: u64,
+    /// Completions with a `duration_ms` up to each bound of the snapshot's
+    /// `duration_buckets_secs` and above the one before, then above the last
+    pub duration_buckets: Vec<u64>,
+}
+
+impl SeriesCounts {
+    /// Completions that report a duration
+    pub fn timed(&self) -> u64 {
+        self.duration_buckets.iter().sum()
+    }
+
+    fn record(&mut self, duration_ms: Option<u64>) {
+        self.count += 1;
+        let Some(duration_ms) = duration_ms else {
+            return;
+        };
+        if self.duration_buckets.is_empty() {
+            self.duration_buckets = vec![0; DURATION_BUCKETS.len() + 1];
+        }
+        let secs = duration_ms as f64 / 1000.0;
+        let bucket = DURATION_BUCKETS
+            .iter()
+            .position(|bound| secs <= *bound)
+            .unwrap_or(DURATION_BUCKETS.len());
+        self.duration_buckets[bucket] += 1;
+        self.duration_ms_sum += duration_ms;
+    }
+
+    fn add(&mut self, other: &SeriesCounts) {
+        self.count += other.count;
+        self.duration_ms_sum += other.duration_ms_sum;
+        if self.duration_buckets.len() < other.duration_buckets.len() {
+            self.duration_buckets
+                .resize(other.duration_buckets.len(), 0);
+        }
+        for (total, count) in self
+            .duration_buckets
+            .iter_mut()
+            .zip(&other.duration_buckets)
+        {
+            *total += count;
+        }
+    }
+}
+
+/// One series of a snapshot, as written
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Series {
+    #[serde(flatten)]
+    pub key: SeriesKey,
+    #[serde(flatten)]
+    pub counts: SeriesCounts,
+}
+
+/// Completions between two snapshots, as written to `<output_dir>/metrics`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricsSnapshot {
+    /// Start of the period (RFC 3339)
+    pub from: String,
+    /// End of the period (RFC 3339)
+    pub to: String,
+    /// Upper bounds of the duration buckets, in seconds
+    pub duration_buckets_secs: Vec<f64>,
+    pub series: Vec<Series>,
+}
+
+impl MetricsSnapshot {
+    /// Start and end of the period
+    pub fn period(&self) -> Result<(DateTime<Utc>, DateTime<Utc>), ServiceError> {
+        let parse = |time: &str| {
+            DateTime::parse_from_rfc3339(time)
+                .map(|time| time.with_timezone(&Utc))
+                .map_err(|e| {
+                    ServiceError::Storage(format!("Invalid snapshot time '{}': {}", time, e))
+                })
+        };
+        Ok((parse(&self.from)?, parse(&self.to)?))
+    }
+}
+
+/// Counts completions by outcome, proof type and client, and snapshots them to
+/// JSON files
+///
+/// Each snapshot holds the completions since the one before, so snapshots add up
+/// over any range of periods.
+pub struct MetricsLog {
+    dir: PathBuf,
+    proof_type: String,
+    period: Mutex<Period>,
+}
+
+struct Period {
+    from: DateTime<Utc>,
+    series: BTreeMap<SeriesKey, SeriesCounts>,
+}
+
+impl MetricsLog {
+    /// Snapshot to `dir`; completions count as `proof_type` unless their proof
+    /// names its profile
+    pub fn new(dir: &Path, proof_type: &str, now: DateTime<Utc>) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            proof_type: proof_type.to_string(),
+            period: Mutex::new(Period {
+                from: now,
+                series: BTreeMap::new(),
+            }),
+        }
+    }
+
+    /// Count a request answered with `response`
+    pub fn record(&self, response: &ProverResponse, client_id: Option<&str>) {
+        let proof_type = response
+            .proof_data
+            .as_ref()
+            .and_then(|proof_data| proof_data.profile)
+            .map_or(self.proof_type.as_str(), |profile| profile.as_str());
+        self.count(
+            response.status.as_str(),
+            proof_type,
+            client_id,
+            response.metrics.as_ref().map(|metrics| metrics.duration_ms),
+        );
+    }
+
+    /// Count a request that failed without a response, e.g. one that did not parse
+    pub fn record_error(&self) {
+        self.count(ProofStatus::Failed.as_str(), &self.proof_type, None, None);
+    }
+
+    fn count(
+        &self,
+        outcome: &str,
+        proof_type: &str,
+        client_id: Option<&str>,
+        duration_ms: Option<u64>,
+    ) {
+        let key = SeriesKey {
+            outcome: outcome.to_string(),
+            proof_type: proof_type.to_string(),
+            client_id: client_id.unwrap_or(NO_CLIENT).to_string(),
+        };
+        let mut period = self.period.lock().unwrap();
+        period.series.entry(key).or_default().record(duration_ms);
+    }
+
+    /// The completions since the last snapshot, starting a new period at `now`
+    pub fn take(&self, now: DateTime<Utc>) -> MetricsSnapshot {
+        let mut period = self.period.lock().unwrap();
+        let from = std::mem::replace(&mut period.from, now);
+        let series = std::mem::take(&mut period.series);
+        MetricsSnapshot {
+            from: from.to_rfc3339(),
+            to: now.to_rfc3339(),
+            duration_buckets_secs: DURATION_BUCKETS.to_vec(),
+            series: series
+                .into_iter()
+                .map(|(key, counts)| Series { key, counts })
+                .collect(),
+        }
+    }
+
+    /// Write the completions since the last snapshot, returning the file written
+    ///
+    /// A period without completions is written too, so the snapshots show how long
+    /// the service ran.
+    pub fn snapshot(&self, now: DateTime<Utc>) -> Result<PathBuf, ServiceError> {
+        let snapshot = self.take(now);
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self
+            .dir
+            .join(format!("{}.json", now.format("%Y%m%dT%H%M%S%.3fZ")));
+        let partial = path.with_extension("json.partial");
+        std::fs::write(&partial, serde_json::to_vec_pretty(&snapshot)?)?;
+        std::fs::rename(&partial, &path)?;
+        Ok(path)
+    }
+}
+
+/// When the period started at `now` ends, for snapshots every `interval`
+///
+/// Periods end on multiples of the interval since the Unix epoch, so snapshots of an
+/// interval that divides an hour never straddle an hour.
+pub fn next_snapshot_at(now: DateTime<Utc>, interval: TimeDelta) -> DateTime<Utc> {
+    match now.duration_trunc(interval) {
+        Ok(start) => start + interval,
+        Err(_) => now + interval,
+    }
+}
+
+/// Read the snapshots in `dir` whose period ends after `from` and by `to`, oldest
+/// first
+///
+/// Files other than snapshots are skipped; a snapshot that does not parse fails.
+pub fn read_snapshots(
+    dir: &Path,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Vec<MetricsSnapshot>, ServiceError> {
+    let mut snapshots = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|extension| extension != "json") {
+            continue;
+        }
+        let snapshot: MetricsSnapshot =
+            serde_json::from_slice(&std::fs::read(&path)?).map_err(|e| {
+                ServiceError::Storage(format!("Invalid snapshot {}: {}", path.display(), e))
+            })?;
+        let (_, end) = snapshot.period()?;
+        if end > from && end <= to {
+            snapshots.push((end, snapshot));
+        }
+    }
+    snapshots.sort_by_key(|(end, _)| *end);
+    Ok(snapshots
+        .into_iter()
+        .map(|(_, snapshot)| snapshot)
+        .collect())
+}
+
+/// Completions of one series over a report's snapshots
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SeriesSummary {
+    #[serde(flatten)]
+    pub key: SeriesKey,
+    pub count: u64,
+    /// Completions per hour the snapshots cover
+    pub per_hour: f64,
+    /// Mean `duration_ms`; `None` when no completion reports one
+    pub mean_ms: Option<u64>,
+    /// Upper bounds of the buckets holding the 50th, 90th and 99th percentile
+    /// duration, in seconds; infinite above the last bucket
+    pub p50_secs: Option<f64>,
+    pub p90_secs: Option<f64>,
+    pub p99_secs: Option<f64>,
+}
+
+/// Completions in one UTC hour, by the hour their snapshot's period started in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HourSummary {
+    /// Start of the hour (RFC 3339)
+    pub hour: String,
+    pub count: u64,
+    pub succeeded: u64,
+    /// Mean `duration_ms`; `None` when no completion reports one
+    pub mean_ms: Option<u64>,
+}
+
+/// Snapshots added up, by series and by hour
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricsReport {
+    pub snapshots: usize,
+    /// Time the snapshots cover, in seconds
+    pub covered_secs: i64,
+    /// Upper bounds of the duration buckets the percentiles are given by, in seconds
+    pub duration_buckets_secs: Vec<f64>,
+    pub series: Vec<SeriesSummary>,
+    pub hours: Vec<HourSummary>,
+}
+
+impl MetricsReport {
+    /// Add up `snapshots`, which must share their duration buckets
+    pub fn of(snapshots: &[MetricsSnapshot]) -> Result<Self, ServiceError> {
+        let mut series: BTreeMap<SeriesKey, SeriesCounts> = BTreeMap::new();
+        let mut hours: BTreeMap<DateTime<Utc>, SeriesCounts> = BTreeMap::new();
+        let mut succeeded: BTreeMap<DateTime<Utc>, u64> = BTreeMap::new();
+        let mut covered_secs = 0;
+        let buckets = snapshots
+            .first()
+            .map(|snapshot| snapshot.duration_buckets_secs.clone())
+            .unwrap_or_default();
+
+        for snapshot in snapshots {
+            if snapshot.duration_buckets_secs != buckets {
+                return Err(ServiceError::Storage(format!(
+                    "Snapshot from {} has duration buckets {:?}, others have {:?}",
+                    snapshot.from, snapshot.duration_buckets_secs, buckets
+                )));
+            }
+            let (start, end) = snapshot.period()?;
+            covered_secs += (end - start).num_seconds().max(0);
+            let hour = start
+                .duration_trunc(TimeDelta::hours(1))
+                .expect("an hour fits any timestamp");
+            let hour_counts = hours.entry(hour).or_default();
+            for Series { key, counts } in &snapshot.series {
+                series.entry(key.clone()).or_default().add(counts);
+                hour_counts.add(counts);
+                if key.outcome == ProofStatus::Success.as_str() {
+                    *succeeded.entry(hour).or_default() += counts.count;
+                }
+            }
+        }
+
+        let covered_hours = covered_secs as f64 / 3600.0;
+        Ok(Self {
+            snapshots: snapshots.len(),
+            covered_secs,
+            series: series
+                .into_iter()
+                .map(|(key, counts)| SeriesSummary {
+                    per_hour: if covered_hours > 0.0 {
+                        counts.count as f64 / covered_hours
+                    } else {
+                        0.0
+                    },
+                    mean_ms: mean_ms(&counts),
+                    p50_secs: percentile_bound(&counts, &buckets, 50),
+                    p90_secs: percentile_bound(&counts, &buckets, 90),
+                    p99_secs: percentile_bound(&counts, &buckets, 99),
+                    count: counts.count,
+                    key,
+                })
+                .collect(),
+            hours: hours
+                .into_iter()
+                .map(|(hour, counts)| HourSummary {
+                    hour: hour.to_rfc3339(),
+                    count: counts.count,
+                    succeeded: succeeded.get(&hour).copied().unwrap_or(0),
+                    mean_ms: mean_ms(&counts),
+                })
+                .collect(),
+            duration_buckets_secs: buckets,
+        })
+    }
+}
+
+fn mean_ms(counts: &SeriesCounts) -> Option<u64> {
+    let timed = counts.timed();
+    (timed > 0).then(|| counts.duration_ms_sum / timed)
+}
+
+/// Upper bound of the bucket holding the nearest-rank `p`th percentile duration
+fn percentile_bound(counts: &SeriesCounts, buckets: &[f64], p: u64) -> Option<f64> {
+    let rank = (counts.timed() * p).div_ceil(100).max(1);
+    let mut seen = 0;
+    for (index, count) in counts.duration_buckets.iter().enumerate() {
+        seen += count;
+        if seen >= rank {
+            return Some(buckets.get(index).copied().unwrap_or(f64::INFINITY));
+        }
+    }
+    None
+}
+
+impl fmt::Display for MetricsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bound = |secs: Option<f64>| match secs {
+            Some(secs) if secs.is_infinite() => {
+                let last = self.duration_buckets_secs.last().copied().unwrap_or(0.0);
+                format!(">{}s", last)
+            }
+            Some(secs) => format!("<={}s", secs),
+            None => "-".to_string(),
+        };
+        let mean = |mean_ms: Option<u64>| mean_ms.map_or("-".to_string(), |ms| format!("{}ms", ms));
+
+        writeln!(
+            f,
+            "{} snapshots covering {:.2}h",
+            self.snapshots,
+            self.covered_secs as f64 / 3600.0
+        )?;
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:<16} {:<10} {:<20} {:>8} {:>9} {:>10} {:>8} {:>8} {:>8}",
+            "outcome", "proof_type", "client_id", "count", "per_hour", "mean", "p50", "p90", "p99"
+        )?;
+        for series in &self.series {
+            writeln!(
+                f,
+                "{:<16} {:<10} {:<20} {:>8} {:>9.2} {:>10} {:>8} {:>8} {:>8}",
+                series.key.outcome,
+                series.key.proof_type,
+                series.key.client_id,
+                series.count,
+                series.per_hour,
+                mean(series.mean_ms),
+                bound(series.p50_secs),
+                bound(series.p90_secs),
+                bound(series.p99_secs)
+            )?;
+        }
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:<25} {:>8} {:>10} {:>10}",
+            "hour", "count", "succeeded", "mean"
+        )?;
+        for hour in &self.hours {
+            writeln!(
+                f,
+                "{:<25} {:>8} {:>10} {:>10}",
+                hour.hour,
+                hour.count,
+                hour.succeeded,
+                mean(hour.mean_ms)
+            )?;
+        }
+        Ok(())
+    }
+}
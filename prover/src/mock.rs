@@ -0,0 +1,148 @@
+use crate::error::ServiceError;
+use crate::prover::{encode_proof_data, ClientPool, ProofBackend, ProofContext, WarmUp};
+use crate::types::{DecodedPublicValues, ProofData, ProverRequest};
+use chrono::Utc;
+use human_index_lib::{calculate_human_index, commit_weights, PublicValues};
+use sha2::{Digest, Sha256};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Request ID prefixes that make the mock backend fail on purpose
+pub const FAIL_PROOF_PREFIX: &str = "fail-proof-";
+pub const FAIL_IO_PREFIX: &str = "fail-io-";
+pub const FAIL_TIMEOUT_PREFIX: &str = "fail-timeout-";
+
+/// Request ID prefix of a proof that outlives the timeout while making progress
+pub const SLOW_PROOF_PREFIX: &str = "slow-proof-";
+
+/// Interval between the heartbeats of a slow proof
+const SLOW_PROOF_HEARTBEAT: Duration = Duration::from_millis(100);
+
+/// Proof backend that skips the zkVM entirely, for CI and integration testing
+///
+/// The human index is computed natively and the proof words are derived from a
+/// hash of the request, so the same request always yields the same `ProofData`.
+/// Request IDs starting with one of the `FAIL_*_PREFIX` constants simulate
+/// failures: a proving error, an IO error, or a proof that outlives the timeout.
+/// Such a proof is stuck and reports no progress, while one starting with
+/// `SLOW_PROOF_PREFIX` keeps reporting it and finishes a second after the timeout.
+/// Proofs check clients out of a `ClientPool` as the real backend does.
+pub struct MockProofGenerator {
+    simulated_duration: Duration,
+    proof_timeout: Duration,
+    clients: ClientPool<MockClient>,
+}
+
+/// Stands in for the prover client, which the mock never needs
+pub struct MockClient;
+
+impl MockProofGenerator {
+    /// Create a mock backend that takes `simulated_duration` per proof
+    pub fn new(simulated_duration: Duration, proof_timeout: Duration) -> Self {
+        Self {
+            simulated_duration,
+            proof_timeout,
+            clients: ClientPool::default(),
+        }
+    }
+
+    /// Clients kept between proofs
+    pub fn clients(&self) -> &ClientPool<MockClient> {
+        &self.clients
+    }
+}
+
+impl ProofBackend for MockProofGenerator {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn generate_proof(
+        &self,
+        request: ProverRequest,
+        context: &ProofContext,
+    ) -> Result<ProofData, ServiceError> {
+        let request_id = request.request_id.as_str();
+        let _client = self.clients.checkout(|| MockClient);
+
+        if request_id.starts_with(FAIL_TIMEOUT_PREFIX) {
+            // Outlive the service timeout so the timeout path is exercised
+            std::thread::sleep(self.proof_timeout + Duration::from_secs(1));
+        } else if request_id.starts_with(SLOW_PROOF_PREFIX) {
+            // Outlive it too, but slow rather than stuck
+            let finish = Instant::now() + self.proof_timeout + Duration::from_secs(1);
+            while Instant::now() < finish {
+                context.heartbeat("prove");
+                std::thread::sleep(SLOW_PROOF_HEARTBEAT);
+            }
+        } else {
+            context.heartbeat("prove");
+            std::thread::sleep(self.simulated_duration);
+        }
+
+        if request_id.starts_with(FAIL_PROOF_PREFIX) {
+            return Err(ServiceError::ProofGeneration(
+                "mock prove_evm failed".to_string(),
+            ));
+        }
+        if request_id.starts_with(FAIL_IO_PREFIX) {
+            return Err(ServiceError::Io(std::io::Error::other("mock IO failure")));
+        }
+
+        // Checked as the guest checks it
+        let validity = request.validity(Utc::now().timestamp() as u64);
+        validity.check().map_err(|e| {
+            ServiceError::ProofGeneration(format!("Invalid validity window: {}", e))
+        })?;
+
+        let human_index =
+            calculate_human_index(&request.verification_results, &request.public_inputs);
+        let weights_commitment = commit_weights(&request.public_inputs);
+
+        let request_hash: [u8; 32] = Sha256::digest(serde_json::to_vec(&request)?).into();
+        let proof_words: Vec<String> = (0u8..8)
+            .map(|i| {
+                let word = Sha256::new()
+                    .chain_update(request_hash)
+                    .chain_update([i])
+                    .finalize();
+                format!("0x{}", hex::encode(word))
+            })
+            .collect();
+
+        let public_values = PublicValues {
+            inputs: request.public_inputs.clone(),
+            computed_output: human_index,
+            weights_commitment,
+            validity,
+        };
+        let public_values_bytes = bincode::serialize(&public_values).map_err(|e| {
+            ServiceError::ProofGeneration(format!("Failed to encode public values: {}", e))
+        })?;
+        let public_values_hex = format!("0x{}", hex::encode(public_values_bytes));
+
+        let riscv_vkey = format!("0x{}", hex::encode(Sha256::digest(b"mock-riscv-vkey")));
+
+        debug!(request_id = request_id, "Mock proof generated");
+
+        let mut proof_data = encode_proof_data(
+            &proof_words,
+            &public_values_hex,
+            &riscv_vkey,
+            human_index,
+            &weights_commitment,
+        )?;
+        proof_data.decoded_public_values = Some(DecodedPublicValues::new(&public_values));
+        Ok(proof_data)
+    }
+
+    /// Build the first client; there is no guest to run
+    fn warm_up(&self) -> Result<Option<WarmUp>, ServiceError> {
+        let started = Instant::now();
+        drop(self.clients.checkout(|| MockClient));
+        Ok(Some(WarmUp {
+            duration_ms: started.elapsed().as_millis() as u64,
+            guest_cycles: None,
+        }))
+    }
+}
@@ -0,0 +1,210 @@
+use crate::error::ServiceError;
+use google_cloud_storage::client::{Client, ClientConfig};
+use google_cloud_storage::http::objects::download::Range;
+use google_cloud_storage::http::objects::get::GetObjectRequest;
+use google_cloud_storage::http::objects::upload::{Media, UploadObjectRequest, UploadType};
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use tracing::info;
+
+/// Scheme of Cloud Storage URLs
+pub const GCS_SCHEME: &str = "gs://";
+
+/// An object in Cloud Storage, as named by a `gs://<bucket>/<object>` URL
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GcsObject {
+    pub bucket: String,
+    pub object: String,
+}
+
+impl GcsObject {
+    /// Parse `url`; `None` when it is not a `gs://` URL, e.g. a local path
+    pub fn parse(url: &str) -> Option<Result<Self, ServiceError>> {
+        let path = url.strip_prefix(GCS_SCHEME)?;
+        Some(match path.split_once('/') {
+            Some((bucket, object)) if !bucket.is_empty() && !object.is_empty() => Ok(Self {
+                bucket: bucket.to_string(),
+                object: object.to_string(),
+            }),
+            _ => Err(ServiceError::Config(format!(
+                "Invalid Cloud Storage URL '{}', expected 'gs://<bucket>/<object>'",
+                url
+            ))),
+        })
+    }
+}
+
+impl fmt::Display for GcsObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}/{}", GCS_SCHEME, self.bucket, self.object)
+    }
+}
+
+/// Reads and writes whole objects in Cloud Storage
+///
+/// Implemented by the Cloud Storage client; tests use `MemoryObjects`.
+pub trait ObjectStore: Send + Sync {
+    fn get<'a>(
+        &'a self,
+        object: &'a GcsObject,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<u8>, ServiceError>> + Send + 'a>>;
+
+    /// Size of `object` in bytes, without downloading it
+    fn size<'a>(
+        &'a self,
+        object: &'a GcsObject,
+    ) -> Pin<Box<dyn Future<Output = Result<u64, ServiceError>> + Send + 'a>>;
+
+    /// Store `data` at `object`, replacing any object there
+    fn put<'a>(
+        &'a self,
+        object: &'a GcsObject,
+        data: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), ServiceError>> + Send + 'a>>;
+}
+
+/// Cloud Storage through its JSON API
+///
+/// Authenticates with the application default credentials, or not at all against
+/// the emulator at `STORAGE_EMULATOR_HOST`.
+pub struct GcsObjects {
+    client: Client,
+}
+
+impl GcsObjects {
+    pub async fn new() -> Result<Self, ServiceError> {
+        let client_config = match std::env::var("STORAGE_EMULATOR_HOST") {
+            Ok(host) => {
+                info!("Storage emulator detected, skipping authentication");
+                ClientConfig {
+                    storage_endpoint: host,
+                    ..ClientConfig::default()
+                }
+                .anonymous()
+            }
+            Err(_) => ClientConfig::default().with_auth().await.map_err(|e| {
+                ServiceError::Config(format!("Failed to set up Cloud Storage auth: {}", e))
+            })?,
+        };
+        Ok(Self {
+            client: Client::new(client_config),
+        })
+    }
+}
+
+impl ObjectStore for GcsObjects {
+    fn get<'a>(
+        &'a self,
+        object: &'a GcsObject,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<u8>, ServiceError>> + Send + 'a>> {
+        Box::pin(async move {
+            let request = GetObjectRequest {
+                bucket: object.bucket.clone(),
+                object: object.object.clone(),
+                ..Default::default()
+            };
+            self.client
+                .download_object(&request, &Range::default())
+                .await
+                .map_err(|e| ServiceError::Storage(format!("Failed to download {}: {}", object, e)))
+        })
+    }
+
+    fn size<'a>(
+        &'a self,
+        object: &'a GcsObject,
+    ) -> Pin<Box<dyn Future<Output = Result<u64, ServiceError>> + Send + 'a>> {
+        Box::pin(async move {
+            let request = GetObjectRequest {
+                bucket: object.bucket.clone(),
+                object: object.object.clone(),
+                ..Default::default()
+            };
+            self.client
+                .get_object(&request)
+                .await
+                .map(|metadata| metadata.size.max(0) as u64)
+                .map_err(|e| ServiceError::Storage(format!("Failed to stat {}: {}", object, e)))
+        })
+    }
+
+    fn put<'a>(
+        &'a self,
+        object: &'a GcsObject,
+        data: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), ServiceError>> + Send + 'a>> {
+        Box::pin(async move {
+            let request = UploadObjectRequest {
+                bucket: object.bucket.clone(),
+                ..Default::default()
+            };
+            let upload_type = UploadType::Simple(Media::new(object.object.clone()));
+            self.client
+                .upload_object(&request, data, &upload_type)
+                .await
+                .map(|_| ())
+                .map_err(|e| ServiceError::Storage(format!("Failed to upload {}: {}", object, e)))
+        })
+    }
+}
+
+/// Objects held in memory, for tests
+#[derive(Default)]
+pub struct MemoryObjects {
+    objects: Mutex<HashMap<GcsObject, Vec<u8>>>,
+}
+
+impl MemoryObjects {
+    /// Store `data` at `url`, replacing any object there
+    pub fn insert(&self, url: &str, data: &[u8]) {
+        let object = GcsObject::parse(url)
+            .expect("a gs:// URL")
+            .expect("a valid gs:// URL");
+        self.objects.lock().unwrap().insert(object, data.to_vec());
+    }
+
+    /// The object at `url`, if there is one
+    pub fn object(&self, url: &str) -> Option<Vec<u8>> {
+        let object = GcsObject::parse(url)?.ok()?;
+        self.objects.lock().unwrap().get(&object).cloned()
+    }
+}
+
+impl ObjectStore for MemoryObjects {
+    fn get<'a>(
+        &'a self,
+        object: &'a GcsObject,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<u8>, ServiceError>> + Send + 'a>> {
+        let found = self.objects.lock().unwrap().get(object).cloned();
+        Box::pin(async move {
+            found.ok_or_else(|| ServiceError::Storage(format!("No such object: {}", object)))
+        })
+    }
+
+    fn size<'a>(
+        &'a self,
+        object: &'a GcsObject,
+    ) -> Pin<Box<dyn Future<Output = Result<u64, ServiceError>> + Send + 'a>> {
+        let size = self
+            .objects
+            .lock()
+            .unwrap()
+            .get(object)
+            .map(|data| data.len() as u64);
+        Box::pin(async move {
+            size.ok_or_else(|| ServiceError::Storage(format!("No such object: {}", object)))
+        })
+    }
+
+    fn put<'a>(
+        &'a self,
+        object: &'a GcsObject,
+        data: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), ServiceError>> + Send + 'a>> {
+        self.objects.lock().unwrap().insert(object.clone(), data);
+        Box::pin(std::future::ready(Ok(())))
+    }
+}
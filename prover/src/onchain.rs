@@ -0,0 +1,297 @@
+use crate::error::ServiceError;
+use crate::evm::{EvmError, VerifyCall};
+use crate::kms::KmsSigner;
+use crate::types::ProofData;
+use alloy_network::{Ethereum, EthereumWallet, NetworkWallet, TransactionBuilder};
+use alloy_primitives::{keccak256, Address, Bytes, B256, U256};
+use alloy_provider::{DynProvider, Provider, ProviderBuilder};
+use alloy_rpc_types_eth::TransactionRequest;
+use alloy_signer_local::PrivateKeySigner;
+use alloy_sol_types::{sol, SolCall};
+use alloy_transport::TransportError;
+use std::env;
+use tracing::info;
+
+sol! {
+    /// The `HumanIndexRegistry.sol` functions the service uses
+    interface HumanIndexRegistry {
+        function recordHumanIndex(
+            bytes32 bindingId,
+            bytes32 programVkey,
+            bytes publicValues,
+            uint256[8] proof
+        ) external;
+
+        function records(bytes32 bindingId)
+            external
+            view
+            returns (uint32 humanIndex, uint64 validUntil, uint64 recordedAt);
+    }
+}
+
+/// Configuration for submitting proofs on-chain, loaded from environment variables
+#[derive(Debug, Clone)]
+pub struct OnchainConfig {
+    /// JSON-RPC endpoint of the target chain
+    pub rpc_url: String,
+
+    /// Chain ID, used to sign transactions without querying the node
+    pub chain_id: u64,
+
+    /// Address of the `HumanIndexRegistry` contract
+    pub contract_address: Address,
+
+    /// Signer backend of the submitting account: `local` or `gcp-kms`
+    pub signing: String,
+
+    /// Hex-encoded private key of the submitting account, for `local`
+    pub signer_key: Option<String>,
+
+    /// Cloud KMS key version of the submitting account, for `gcp-kms`
+    pub kms_key: Option<String>,
+
+    /// Number of confirmations to wait for before reporting success
+    pub confirmations: u64,
+}
+
+impl OnchainConfig {
+    /// Load on-chain settings; returns `None` when `ONCHAIN_RPC_URL` is not set
+    pub fn from_env() -> Result<Option<Self>, ServiceError> {
+        let rpc_url = match env::var("ONCHAIN_RPC_URL") {
+            Ok(url) => url,
+            Err(_) => return Ok(None),
+        };
+
+        let chain_id = env::var("ONCHAIN_CHAIN_ID")
+            .map_err(|_| ServiceError::Config("ONCHAIN_CHAIN_ID not set".to_string()))?
+            .parse::<u64>()
+            .map_err(|e| ServiceError::Config(format!("Invalid ONCHAIN_CHAIN_ID: {}", e)))?;
+
+        let contract_address = env::var("ONCHAIN_CONTRACT_ADDRESS")
+            .map_err(|_| ServiceError::Config("ONCHAIN_CONTRACT_ADDRESS not set".to_string()))?
+            .parse::<Address>()
+            .map_err(|e| {
+                ServiceError::Config(format!("Invalid ONCHAIN_CONTRACT_ADDRESS: {}", e))
+            })?;
+
+        let signing = env::var("ONCHAIN_SIGNING").unwrap_or_else(|_| "local".to_string());
+        let signer_key = env::var("ONCHAIN_SIGNER_KEY").ok();
+        let kms_key = env::var("ONCHAIN_KMS_KEY").ok();
+
+        let confirmations = env::var("ONCHAIN_CONFIRMATIONS")
+            .unwrap_or_else(|_| "1".to_string())
+            .parse::<u64>()
+            .map_err(|e| ServiceError::Config(format!("Invalid ONCHAIN_CONFIRMATIONS: {}", e)))?;
+
+        Ok(Some(Self {
+            rpc_url,
+            chain_id,
+            contract_address,
+            signing,
+            signer_key,
+            kms_key,
+            confirmations,
+        }))
+    }
+}
+
+/// Registry key of a binding: keccak256 of its id, as in `HumanIndexAttestation`
+pub fn binding_key(binding_id: &str) -> B256 {
+    keccak256(binding_id.as_bytes())
+}
+
+/// Build the `recordHumanIndex` calldata recording a proof under `binding_id`
+pub fn build_record_calldata(
+    proof_data: &ProofData,
+    binding_id: &str,
+) -> Result<Vec<u8>, EvmError> {
+    let call = VerifyCall::from_proof_data(proof_data)?;
+    Ok(HumanIndexRegistry::recordHumanIndexCall {
+        bindingId: binding_key(binding_id),
+        programVkey: call.riscv_vkey.into(),
+        publicValues: call.public_values.into(),
+        proof: call.proof.map(U256::from_be_bytes),
+    }
+    .abi_encode())
+}
+
+/// Records proofs in the registry contract from a single signing account
+///
+/// One submitter is shared by all proof tasks; its provider caches the account
+/// nonce so concurrent submissions get consecutive nonces instead of colliding.
+pub struct OnchainSubmitter {
+    provider: DynProvider,
+    contract_address: Address,
+    confirmations: u64,
+}
+
+impl OnchainSubmitter {
+    /// Create a submitter from configuration
+    pub async fn new(config: &OnchainConfig) -> Result<Self, ServiceError> {
+        let wallet = match config.signing.as_str() {
+            "local" => {
+                let signer = config
+                    .signer_key
+                    .as_deref()
+                    .ok_or_else(|| ServiceError::Config("ONCHAIN_SIGNER_KEY not set".to_string()))?
+                    .trim_start_matches("0x")
+                    .parse::<PrivateKeySigner>()
+                    .map_err(|e| {
+                        ServiceError::Config(format!("Invalid ONCHAIN_SIGNER_KEY: {}", e))
+                    })?;
+                EthereumWallet::from(signer)
+            }
+            "gcp-kms" => {
+                let key = config
+                    .kms_key
+                    .as_deref()
+                    .ok_or_else(|| ServiceError::Config("ONCHAIN_KMS_KEY not set".to_string()))?;
+                EthereumWallet::from(KmsSigner::connect(key).await?)
+            }
+            other => {
+                return Err(ServiceError::Config(format!(
+                    "Unsupported ONCHAIN_SIGNING backend: {}",
+                    other
+                )))
+            }
+        };
+        let rpc_url = config
+            .rpc_url
+            .parse()
+            .map_err(|e| ServiceError::Config(format!("Invalid ONCHAIN_RPC_URL: {}", e)))?;
+
+        info!(
+            signer = %NetworkWallet::<Ethereum>::default_signer_address(&wallet),
+            contract = %config.contract_address,
+            chain_id = config.chain_id,
+            "On-chain submission enabled"
+        );
+
+        let provider = ProviderBuilder::default()
+            .with_gas_estimation()
+            .with_cached_nonce_management()
+            .with_chain_id(config.chain_id)
+            .wallet(wallet)
+            .connect_http(rpc_url)
+            .erased();
+
+        Ok(Self {
+            provider,
+            contract_address: config.contract_address,
+            confirmations: config.confirmations,
+        })
+    }
+
+    /// Record a proof under the request's `binding_id` and wait for the configured
+    /// number of confirmations
+    ///
+    /// The registry verifies the proof before storing its index under
+    /// `binding_key(binding_id)`. Returns the transaction hash as a `0x`-prefixed
+    /// hex string.
+    pub async fn submit_proof(
+        &self,
+        proof_data: &ProofData,
+        binding_id: &str,
+    ) -> Result<String, ServiceError> {
+        let calldata = build_record_calldata(proof_data, binding_id).map_err(|e| {
+            ServiceError::Onchain(format!("[{}] Failed to build calldata: {}", binding_id, e))
+        })?;
+
+        let tx = TransactionRequest::default()
+            .with_to(self.contract_address)
+            .with_input(calldata);
+
+        // Estimate first so a rejected proof surfaces as a decoded revert, not a failed tx
+        let gas = self.provider.estimate_gas(tx.clone()).await.map_err(|e| {
+            ServiceError::Onchain(format!(
+                "[{}] Gas estimation failed: {}",
+                binding_id,
+                describe_rpc_error(&e)
+            ))
+        })?;
+
+        let pending = self
+            .provider
+            .send_transaction(tx.with_gas_limit(gas))
+            .await
+            .map_err(|e| {
+                ServiceError::Onchain(format!(
+                    "[{}] Failed to send transaction: {}",
+                    binding_id,
+                    describe_rpc_error(&e)
+                ))
+            })?;
+        let tx_hash = format!("{:#x}", pending.tx_hash());
+        info!(binding_id = binding_id, tx_hash = %tx_hash, "Proof submitted on-chain");
+
+        let receipt = pending
+            .with_required_confirmations(self.confirmations)
+            .get_receipt()
+            .await
+            .map_err(|e| {
+                ServiceError::Onchain(format!(
+                    "[{}] Failed waiting for {}: {}",
+                    binding_id, tx_hash, e
+                ))
+            })?;
+
+        if !receipt.status() {
+            return Err(ServiceError::Onchain(format!(
+                "[{}] Transaction {} reverted",
+                binding_id, tx_hash
+            )));
+        }
+
+        info!(
+            binding_id = binding_id,
+            tx_hash = %tx_hash,
+            confirmations = self.confirmations,
+            "On-chain submission confirmed"
+        );
+        Ok(tx_hash)
+    }
+}
+
+/// Render an RPC error, decoding revert data from our contracts when present
+fn describe_rpc_error(error: &TransportError) -> String {
+    match error
+        .as_error_resp()
+        .and_then(|payload| payload.as_revert_data())
+    {
+        Some(data) => format!("reverted with {}", decode_revert(&data)),
+        None => error.to_string(),
+    }
+}
+
+/// Map revert data to a readable reason for the errors our contracts can raise
+fn decode_revert(data: &Bytes) -> String {
+    if data.len() < 4 {
+        return "empty revert data".to_string();
+    }
+    let selector = &data[..4];
+
+    // Error(string) from require/revert with a message
+    if selector == &keccak256("Error(string)")[..4] && data.len() >= 4 + 64 {
+        let len = u64::from_be_bytes(data[4 + 56..4 + 64].try_into().expect("8 bytes")) as usize;
+        if let Some(message) = data.get(4 + 64..4 + 64 + len) {
+            return format!("Error(\"{}\")", String::from_utf8_lossy(message));
+        }
+    }
+
+    const KNOWN_ERRORS: [&str; 7] = [
+        "InvalidProof()",
+        "ProofInvalid()",
+        "PublicInputNotInField()",
+        "IndexExpired(uint64)",
+        "Unauthorized(address)",
+        "UnknownProgram(bytes32)",
+        "MalformedPublicValues(uint256)",
+    ];
+    for signature in KNOWN_ERRORS {
+        if selector == &keccak256(signature)[..4] {
+            return signature.to_string();
+        }
+    }
+
+    format!("unknown error 0x{}", hex::encode(data))
+}
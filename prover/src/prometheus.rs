@@ -0,0 +1,136 @@
+use crate::error::ServiceError;
+use crate::scheduler::Scheduler;
+use crate::types::{Priority, ProverResponse};
+use axum::extract::State;
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use metrics::{counter, gauge, histogram};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use std::net::SocketAddr;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+/// Proof generation time, `duration_ms` of the result metrics, by status
+pub const PROOF_DURATION: &str = "prover_proof_duration_seconds";
+/// Results by status and `error_type`, `none` for results without an error
+pub const PROOFS: &str = "prover_proofs_total";
+/// Result publishes by outcome, `ok` or `error`
+pub const PUBLISHES: &str = "prover_result_publishes_total";
+/// Requests being processed
+pub const IN_FLIGHT: &str = "prover_in_flight";
+/// Messages waiting for a proof permit, by lane
+pub const QUEUE_DEPTH: &str = "prover_queue_depth";
+
+/// Histogram buckets of `PROOF_DURATION`, in seconds; Groth16 proofs take minutes
+pub const DURATION_BUCKETS: &[f64] = &[
+    1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 180.0, 300.0, 450.0, 600.0, 900.0,
+];
+
+static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// Install the Prometheus recorder, once per process; returns its handle
+///
+/// Until it is installed, recorded metrics go nowhere.
+pub fn install() -> Result<PrometheusHandle, ServiceError> {
+    if let Some(handle) = HANDLE.get() {
+        return Ok(handle.clone());
+    }
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Full(PROOF_DURATION.to_string()), DURATION_BUCKETS)
+        .and_then(PrometheusBuilder::install_recorder)
+        .map_err(|e| ServiceError::Config(format!("Failed to install metrics recorder: {}", e)))?;
+    Ok(HANDLE.get_or_init(|| handle).clone())
+}
+
+/// Record the result of a request
+pub fn record_proof(response: &ProverResponse) {
+    let status = response.status.as_str();
+    let error_type = response
+        .error
+        .as_ref()
+        .map_or("none", |error| error.error_type.as_str())
+        .to_string();
+    counter!(PROOFS, "status" => status, "error_type" => error_type).increment(1);
+    if let Some(metrics) = &response.metrics {
+        histogram!(PROOF_DURATION, "status" => status)
+            .record(Duration::from_millis(metrics.duration_ms).as_secs_f64());
+    }
+}
+
+/// Record a result publish
+pub fn record_publish(result: &Result<(), ServiceError>) {
+    let outcome = if result.is_ok() { "ok" } else { "error" };
+    counter!(PUBLISHES, "outcome" => outcome).increment(1);
+}
+
+/// Counts a request in `IN_FLIGHT` until dropped
+pub struct InFlight(());
+
+impl InFlight {
+    pub fn start() -> Self {
+        gauge!(IN_FLIGHT).increment(1.0);
+        Self(())
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        gauge!(IN_FLIGHT).decrement(1.0);
+    }
+}
+
+/// State of the metrics endpoint
+pub struct MetricsState {
+    handle: PrometheusHandle,
+    scheduler: Arc<Scheduler>,
+}
+
+impl MetricsState {
+    pub fn new(handle: PrometheusHandle, scheduler: Arc<Scheduler>) -> Self {
+        Self { handle, scheduler }
+    }
+}
+
+/// Build the metrics router
+pub fn router(state: Arc<MetricsState>) -> Router {
+    Router::new()
+        .route("/metrics", get(scrape))
+        .with_state(state)
+}
+
+/// Serve `/metrics` on `port` until `shutdown` is cancelled
+pub async fn serve(
+    port: u16,
+    state: Arc<MetricsState>,
+    shutdown: CancellationToken,
+) -> Result<(), ServiceError> {
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    info!("Metrics server listening on {}", addr);
+
+    axum::serve(listener, router(state))
+        .with_graceful_shutdown(async move { shutdown.cancelled().await })
+        .await?;
+    Ok(())
+}
+
+async fn scrape(State(state): State<Arc<MetricsState>>) -> impl IntoResponse {
+    // Queue depths are sampled when scraped rather than tracked on every change
+    let depths = state.scheduler.depths();
+    for (lane, depth) in
+        Priority::ALL
+            .into_iter()
+            .zip([depths.interactive, depths.normal, depths.batch])
+    {
+        gauge!(QUEUE_DEPTH, "lane" => lane.as_str()).set(depth as f64);
+    }
+    state.handle.run_upkeep();
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.handle.render(),
+    )
+}
@@ -0,0 +1,238 @@
+use crate::error::ServiceError;
+use crate::prover::{ProofBackend, ProofContext, WarmUp};
+use crate::quarantine::request_hash;
+use crate::types::{ProofData, ProverRequest};
+use chrono::Utc;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, MutexGuard};
+use tracing::{debug, warn};
+
+/// Slower storage behind the in-memory cache, such as a directory of proofs
+pub trait ProofStore: Send + Sync + 'static {
+    fn load(&self, key: &str) -> Result<Option<ProofData>, ServiceError>;
+    fn save(&self, key: &str, proof: &ProofData) -> Result<(), ServiceError>;
+}
+
+/// No storage behind the cache; proofs live only in memory
+pub struct NoStore;
+
+impl ProofStore for NoStore {
+    fn load(&self, _key: &str) -> Result<Option<ProofData>, ServiceError> {
+        Ok(None)
+    }
+
+    fn save(&self, _key: &str, _proof: &ProofData) -> Result<(), ServiceError> {
+        Ok(())
+    }
+}
+
+/// Limits of the in-memory cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheLimits {
+    pub max_entries: usize,
+    /// Budget for the serialized size of the cached proofs
+    pub max_bytes: u64,
+}
+
+/// Usage of the in-memory cache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheUsage {
+    pub entries: usize,
+    pub bytes: u64,
+}
+
+/// Least-recently-used in-memory cache of proofs in front of a `ProofStore`
+///
+/// Each proof is accounted at its serialized JSON size. Inserting evicts the least
+/// recently used proofs until both limits hold; a proof larger than the byte budget
+/// is only saved to the store. Lookups that miss memory fall through to the store
+/// and bring the proof back into memory.
+pub struct ProofCache<S: ProofStore = NoStore> {
+    limits: CacheLimits,
+    store: S,
+    lru: Mutex<Lru>,
+}
+
+#[derive(Default)]
+struct Lru {
+    entries: HashMap<String, Entry>,
+    /// Keys by last use, oldest first
+    recency: BTreeMap<u64, String>,
+    bytes: u64,
+    clock: u64,
+}
+
+struct Entry {
+    proof: ProofData,
+    size: u64,
+    last_used: u64,
+}
+
+impl<S: ProofStore> ProofCache<S> {
+    pub fn new(limits: CacheLimits, store: S) -> Self {
+        Self {
+            limits,
+            store,
+            lru: Mutex::new(Lru::default()),
+        }
+    }
+
+    /// Proof for `key`, from memory if present, else from the store
+    pub fn get(&self, key: &str) -> Result<Option<ProofData>, ServiceError> {
+        if let Some(proof) = self.lock().touch(key) {
+            return Ok(Some(proof));
+        }
+        let Some(proof) = self.store.load(key)? else {
+            return Ok(None);
+        };
+        self.remember(key, &proof)?;
+        Ok(Some(proof))
+    }
+
+    /// Cache `proof` under `key` in memory and in the store
+    pub fn insert(&self, key: &str, proof: &ProofData) -> Result<(), ServiceError> {
+        self.store.save(key, proof)?;
+        self.remember(key, proof)
+    }
+
+    /// Current number and size of the proofs held in memory
+    pub fn usage(&self) -> CacheUsage {
+        let lru = self.lock();
+        CacheUsage {
+            entries: lru.entries.len(),
+            bytes: lru.bytes,
+        }
+    }
+
+    fn remember(&self, key: &str, proof: &ProofData) -> Result<(), ServiceError> {
+        let size = serde_json::to_vec(proof)?.len() as u64;
+        let mut lru = self.lock();
+        lru.remove(key);
+        if self.limits.max_entries == 0 || size > self.limits.max_bytes {
+            return Ok(());
+        }
+        while lru.entries.len() >= self.limits.max_entries
+            || lru.bytes + size > self.limits.max_bytes
+        {
+            lru.evict_oldest();
+        }
+        lru.insert(key, proof.clone(), size);
+        Ok(())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Lru> {
+        self.lru.lock().expect("proof cache lock poisoned")
+    }
+}
+
+impl Lru {
+    fn touch(&mut self, key: &str) -> Option<ProofData> {
+        self.clock += 1;
+        let entry = self.entries.get_mut(key)?;
+        self.recency.remove(&entry.last_used);
+        entry.last_used = self.clock;
+        self.recency.insert(self.clock, key.to_string());
+        Some(entry.proof.clone())
+    }
+
+    fn insert(&mut self, key: &str, proof: ProofData, size: u64) {
+        self.clock += 1;
+        self.recency.insert(self.clock, key.to_string());
+        self.bytes += size;
+        self.entries.insert(
+            key.to_string(),
+            Entry {
+                proof,
+                size,
+                last_used: self.clock,
+            },
+        );
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.recency.remove(&entry.last_used);
+            self.bytes -= entry.size;
+        }
+    }
+
+    fn evict_oldest(&mut self) {
+        if let Some((_, key)) = self.recency.pop_first() {
+            if let Some(entry) = self.entries.remove(&key) {
+                self.bytes -= entry.size;
+            }
+        }
+    }
+}
+
+/// Backend that answers repeated inputs from a `ProofCache`
+///
+/// Requests are keyed by the hash of their inputs, so a replay of the same inputs
+/// under a new ID gets the earlier proof, as long as its committed validity window
+/// has not ended and ends no later than the request's. Cache errors are logged and
+/// the request is proved as if it had missed.
+pub struct CachingBackend<B: ProofBackend, S: ProofStore = NoStore> {
+    inner: B,
+    cache: Option<ProofCache<S>>,
+}
+
+impl<B: ProofBackend, S: ProofStore> CachingBackend<B, S> {
+    /// Wrap `inner`; without a cache every request is proved
+    pub fn new(inner: B, cache: Option<ProofCache<S>>) -> Self {
+        Self { inner, cache }
+    }
+}
+
+impl<B: ProofBackend, S: ProofStore> ProofBackend for CachingBackend<B, S> {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn generate_proof(
+        &self,
+        request: ProverRequest,
+        context: &ProofContext,
+    ) -> Result<ProofData, ServiceError> {
+        let Some(cache) = &self.cache else {
+            return self.inner.generate_proof(request, context);
+        };
+
+        let key = request_hash(&request);
+        match cache.get(&key) {
+            Ok(Some(proof)) if serves(&proof, &request) => {
+                debug!(request_id = %request.request_id, key = %key, "Proof served from cache");
+                return Ok(proof);
+            }
+            Ok(Some(_)) => debug!(
+                request_id = %request.request_id,
+                key = %key,
+                "Cached proof is outside the validity window"
+            ),
+            Ok(None) => {}
+            Err(e) => warn!(request_id = %request.request_id, "Proof cache lookup failed: {}", e),
+        }
+
+        let request_id = request.request_id.clone();
+        let proof = self.inner.generate_proof(request, context)?;
+        if let Err(e) = cache.insert(&key, &proof) {
+            warn!(request_id = %request_id, "Failed to cache proof: {}", e);
+        }
+        Ok(proof)
+    }
+
+    fn warm_up(&self) -> Result<Option<WarmUp>, ServiceError> {
+        self.inner.warm_up()
+    }
+}
+
+/// Whether a cached proof is valid now and expires no later than `request` allows
+fn serves(proof: &ProofData, request: &ProverRequest) -> bool {
+    let now = Utc::now().timestamp() as u64;
+    match proof.public_values() {
+        Ok(public_values) => {
+            public_values.validity.is_valid_at(now)
+                && public_values.validity.valid_until <= request.validity(now).valid_until
+        }
+        Err(_) => false,
+    }
+}
@@ -0,0 +1,83 @@
+use crate::config::{Config, ProverBackendKind};
+use crate::prover::{CachedElf, ProverProfile};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+/// Pico SDK tag the prover is built against; keep in sync with the workspace `Cargo.toml`
+pub const PICO_SDK_VERSION: &str = "v1.1.6";
+
+/// Identifies the worker and build that produced a response
+///
+/// Assembled once at startup, attached to every response and served by the
+/// status endpoint, so a bad proof can be traced to the replica that made it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Provenance {
+    /// `WORKER_ID`, defaulting to `HOSTNAME`
+    #[serde(alias = "instanceId")]
+    pub instance_id: String,
+
+    /// Kernel hostname of the machine or container
+    pub hostname: String,
+
+    /// Version of the prover crate
+    #[serde(alias = "proverVersion")]
+    pub prover_version: String,
+
+    /// Pico SDK tag
+    #[serde(alias = "picoSdkVersion")]
+    pub pico_sdk_version: String,
+
+    /// Hex-encoded SHA-256 of the guest ELF; absent for the mock backend, which never loads it
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "elfSha256")]
+    pub elf_sha256: Option<String>,
+
+    /// Hex-encoded SHA-256 of the Groth16 verifying key (`vm_vk`); absent when
+    /// proofs skip the Groth16 wrap
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        alias = "setupSha256"
+    )]
+    pub setup_sha256: Option<String>,
+
+    /// Backend and profile, e.g. `pico/evm` or `mock`
+    pub backend: String,
+}
+
+impl Provenance {
+    /// Collect provenance for a service running with `config`
+    ///
+    /// `elf` is the cached guest ELF, if the backend loaded one.
+    pub fn collect(config: &Config, elf: Option<&CachedElf>) -> Self {
+        let (backend, setup_sha256) = match (config.prover_backend, config.prover_profile) {
+            (ProverBackendKind::Pico, ProverProfile::Evm) => (
+                "pico/evm",
+                file_sha256(&Path::new(&config.output_dir).join("vm_vk")),
+            ),
+            (ProverBackendKind::Pico, ProverProfile::Fast) => ("pico/fast", None),
+            (ProverBackendKind::Mock, _) => ("mock", None),
+        };
+
+        Self {
+            instance_id: config.worker_id.clone(),
+            hostname: std::fs::read_to_string("/proc/sys/kernel/hostname")
+                .map(|hostname| hostname.trim().to_string())
+                .unwrap_or_else(|_| "unknown".to_string()),
+            prover_version: env!("CARGO_PKG_VERSION").to_string(),
+            pico_sdk_version: PICO_SDK_VERSION.to_string(),
+            elf_sha256: elf.map(CachedElf::sha256),
+            setup_sha256,
+            backend: backend.to_string(),
+        }
+    }
+}
+
+/// Missing setup files are reported when a proof needs them, not here
+fn file_sha256(path: &Path) -> Option<String> {
+    std::fs::read(path)
+        .ok()
+        .map(|bytes| hex::encode(Sha256::digest(bytes)))
+}
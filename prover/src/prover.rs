@@ -1,10 +1,41 @@
-use crate::error::ServiceError;
-use crate::types::{ProofData, ProverRequest};
-use human_index_lib::{calculate_human_index, load_elf};
+use crate::docker::is_transient_failure;
+use crate::encryption::ArtifactCipher;
+use crate::error::{FieldMismatch, PublicValuesMismatch, ServiceError};
+use crate::object_store::{GcsObject, GcsObjects, ObjectStore};
+use crate::setup_store::{link_setup_files, SetupStore};
+use crate::types::{ProofData, ProofProgress, ProverRequest, ResourceUsage};
+use crate::usage::{dir_bytes, UsageSampler};
+use crate::verifier::{verify_with_fallback, VerifyingKey, PUBLIC_INPUTS};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::Utc;
+use human_index_lib::{
+    commit_weights, CircuitId, HumanIndexPublicInputs, ProofInputs, PublicValues, Validity,
+    VerificationResults, MAX_VALIDITY_SECS, STDIN_WORDS,
+};
 use pico_sdk::client::DefaultProverClient;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::borrow::Borrow;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
-use tracing::info;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use zeroize::Zeroizing;
+
+/// Delay before the first retry of a transient failure; doubles with each retry
+pub const RETRY_BACKOFF: Duration = Duration::from_secs(5);
+
+/// How often a running `prove_evm` is checked for progress
+pub const PROGRESS_POLL: Duration = Duration::from_secs(5);
+
+/// Setup files `prove_evm` needs in the output directory
+pub const SETUP_FILES: [&str; 2] = ["vm_pk", "vm_vk"];
 
 /// Cached ELF data to avoid reloading for each proof
 pub struct CachedElf {
@@ -14,37 +45,774 @@ pub struct CachedElf {
 impl CachedElf {
     /// Load and cache the ELF file once
     pub fn load(elf_path: &str) -> Result<Self, ServiceError> {
-        let elf_data = load_elf(elf_path);
+        let elf_data = std::fs::read(elf_path).map_err(|e| {
+            ServiceError::Io(std::io::Error::new(
+                e.kind(),
+                format!("{}: {}", elf_path, e),
+            ))
+        })?;
         Ok(Self { data: elf_data })
     }
+
+    /// Hex-encoded SHA-256 of the ELF
+    pub fn sha256(&self) -> String {
+        hex::encode(Sha256::digest(&self.data))
+    }
+}
+
+/// How far the Pico backend takes a proof
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ProverProfile {
+    /// Full pipeline with the Groth16 wrap, verifiable on-chain
+    Evm,
+    /// RISC-V proof only; much cheaper but not verifiable on-chain
+    Fast,
+}
+
+impl ProverProfile {
+    /// Whether proofs need the Groth16 setup files, `vm_pk`/`vm_vk`
+    pub fn needs_setup_files(self) -> bool {
+        self == Self::Evm
+    }
+
+    /// The profile as serialized, e.g. for metric labels
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Evm => "evm",
+            Self::Fast => "fast",
+        }
+    }
+}
+
+impl FromStr for ProverProfile {
+    type Err = ServiceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "evm" => Ok(Self::Evm),
+            "fast" => Ok(Self::Fast),
+            other => Err(ServiceError::Config(format!(
+                "Invalid PROVER_PROFILE '{}', expected 'evm' or 'fast'",
+                other
+            ))),
+        }
+    }
+}
+
+/// How the artifact fields of `inputs.json` are base64-encoded into `ProofData`
+///
+/// The proof, public values and verifying key are independent, so `Parallel`
+/// encodes them at once on the rayon pool; both give byte-identical `ProofData`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArtifactEncoding {
+    Sequential,
+    #[default]
+    Parallel,
+}
+
+impl FromStr for ArtifactEncoding {
+    type Err = ServiceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sequential" => Ok(Self::Sequential),
+            "parallel" => Ok(Self::Parallel),
+            other => Err(ServiceError::Config(format!(
+                "Invalid ARTIFACT_ENCODING '{}', expected 'parallel' or 'sequential'",
+                other
+            ))),
+        }
+    }
+}
+
+/// Keys the proof artifacts are found under in the `inputs.json` the SDK writes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProofJsonLayout {
+    /// The Pico SDK: `proof`, `publicValues` and `riscvVKey`
+    #[default]
+    PicoV1,
+    /// SP1 proof fixtures: `proof`, `publicValues` and `vkey`
+    Sp1,
+}
+
+impl ProofJsonLayout {
+    /// Name as `PROOF_JSON_KEYS` takes it
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::PicoV1 => "pico_v1",
+            Self::Sp1 => "sp1",
+        }
+    }
+
+    /// Keys of the proof, the public values and the verifying key
+    pub fn keys(self) -> [&'static str; 3] {
+        match self {
+            Self::PicoV1 => ["proof", "publicValues", "riscvVKey"],
+            Self::Sp1 => ["proof", "publicValues", "vkey"],
+        }
+    }
+}
+
+impl FromStr for ProofJsonLayout {
+    type Err = ServiceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "pico_v1" => Ok(Self::PicoV1),
+            "sp1" => Ok(Self::Sp1),
+            other => Err(ServiceError::Config(format!(
+                "Invalid PROOF_JSON_KEYS '{}', expected 'pico_v1' or 'sp1'",
+                other
+            ))),
+        }
+    }
+}
+
+/// Per-proof settings passed to a backend, and the attempts it reports back
+pub struct ProofContext {
+    /// When the service stops waiting for the proof
+    pub deadline: Instant,
+
+    /// Retries allowed after a transient failure
+    pub retry_attempts: u32,
+
+    attempts: AtomicU32,
+
+    usage: Mutex<ResourceUsage>,
+
+    progress: Mutex<Option<Progress>>,
+}
+
+/// Progress a backend last reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// Phase the proof was in, e.g. `emulate` or `prove`
+    pub phase: &'static str,
+
+    /// When it was reported
+    pub at: Instant,
+
+    /// Reports over the whole proof, this one included
+    pub heartbeats: u32,
+}
+
+impl Progress {
+    /// Wire form as of `now`, for a proof whose timeout was extended by `extended`
+    pub fn report(&self, now: Instant, extended: Option<Duration>) -> ProofProgress {
+        ProofProgress {
+            phase: self.phase.to_string(),
+            heartbeats: self.heartbeats,
+            idle_ms: now.saturating_duration_since(self.at).as_millis() as u64,
+            extended_secs: extended.map(|extension| extension.as_secs()),
+        }
+    }
+}
+
+impl ProofContext {
+    pub fn new(deadline: Instant, retry_attempts: u32) -> Self {
+        Self {
+            deadline,
+            retry_attempts,
+            attempts: AtomicU32::new(1),
+            usage: Mutex::new(ResourceUsage::default()),
+            progress: Mutex::new(None),
+        }
+    }
+
+    /// Attempts made so far, including the one running
+    pub fn attempts(&self) -> u32 {
+        self.attempts.load(Ordering::Relaxed)
+    }
+
+    /// Resources the backend reported; all unset if it reports none
+    pub fn usage(&self) -> ResourceUsage {
+        *self.usage.lock().expect("usage lock poisoned")
+    }
+
+    /// Update the reported resources
+    pub fn record_usage(&self, update: impl FnOnce(&mut ResourceUsage)) {
+        update(&mut self.usage.lock().expect("usage lock poisoned"));
+    }
+
+    /// Report that the proof is making progress in `phase`
+    pub fn heartbeat(&self, phase: &'static str) {
+        let mut progress = self.progress.lock().expect("progress lock poisoned");
+        let heartbeats = progress.map_or(0, |progress| progress.heartbeats) + 1;
+        *progress = Some(Progress {
+            phase,
+            at: Instant::now(),
+            heartbeats,
+        });
+    }
+
+    /// Progress last reported; unset before the first heartbeat
+    pub fn progress(&self) -> Option<Progress> {
+        *self.progress.lock().expect("progress lock poisoned")
+    }
+}
+
+/// How long the service waits for a proof
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeoutPolicy {
+    /// Time allowed before the proof counts as timed out
+    pub timeout: Duration,
+
+    /// Extra time granted once to a proof still making progress at the timeout;
+    /// zero disables the extension
+    pub extension: Duration,
+
+    /// A heartbeat at most this long before the timeout counts as progress
+    pub progress_window: Duration,
+}
+
+/// A proof that did not finish within its `TimeoutPolicy`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimedOut {
+    /// Extension granted before giving up, if any
+    pub extended: Option<Duration>,
+
+    /// Progress last reported, as of the timeout
+    pub progress: Option<ProofProgress>,
+}
+
+impl TimedOut {
+    /// Total time waited for the proof
+    pub fn waited(&self, policy: &TimeoutPolicy) -> Duration {
+        policy.timeout + self.extended.unwrap_or_default()
+    }
+}
+
+/// Wait for `proof` under `policy`
+///
+/// When the timeout fires, a proof whose backend reported progress in `context`
+/// within `progress_window` is slow rather than stuck, and gets one extension. A
+/// proof that does not finish in that either times out with the progress it last
+/// reported.
+pub async fn await_proof<F: Future>(
+    request_id: &str,
+    proof: F,
+    policy: &TimeoutPolicy,
+    context: &ProofContext,
+) -> Result<F::Output, TimedOut> {
+    let mut proof = std::pin::pin!(proof);
+    if let Ok(output) = tokio::time::timeout(policy.timeout, &mut proof).await {
+        return Ok(output);
+    }
+
+    let recent = context
+        .progress()
+        .filter(|progress| progress.at.elapsed() <= policy.progress_window);
+    let extended = match recent {
+        Some(progress) if !policy.extension.is_zero() => {
+            warn!(
+                request_id = %request_id,
+                phase = progress.phase,
+                extension_secs = policy.extension.as_secs(),
+                "Proof still making progress at the timeout, extending it once"
+            );
+            if let Ok(output) = tokio::time::timeout(policy.extension, &mut proof).await {
+                return Ok(output);
+            }
+            Some(policy.extension)
+        }
+        _ => None,
+    };
+
+    let now = Instant::now();
+    Err(TimedOut {
+        extended,
+        progress: context
+            .progress()
+            .map(|progress| progress.report(now, extended)),
+    })
+}
+
+/// A backend that turns a request into proof data
+///
+/// `generate_proof` is blocking and is called via `spawn_blocking`.
+pub trait ProofBackend: Send + Sync + 'static {
+    /// Short backend name for logs
+    fn name(&self) -> &'static str;
+
+    /// Generate a proof for the given request
+    fn generate_proof(
+        &self,
+        request: ProverRequest,
+        context: &ProofContext,
+    ) -> Result<ProofData, ServiceError>;
+
+    /// Prepare for the first proof, e.g. by building the prover client and running
+    /// the guest once; `None` when there is nothing to warm up
+    ///
+    /// Blocking; called once at startup, before the worker reports ready.
+    fn warm_up(&self) -> Result<Option<WarmUp>, ServiceError> {
+        Ok(None)
+    }
+}
+
+/// How a backend warmed up at startup, as reported by the status endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WarmUp {
+    pub duration_ms: u64,
+    /// Cycles of the execute-only run, when the guest was run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guest_cycles: Option<u64>,
+}
+
+/// Prover clients kept between proofs, so each proof does not build its own
+///
+/// A proof checks a client out and returns it when done, so concurrent proofs
+/// never share one and per-proof state (stdin, output directory) stays with the
+/// proof. Clients are created on demand, so there are at most as many as proofs
+/// ever ran at once. A client whose proof panicked is dropped rather than returned.
+pub struct ClientPool<C> {
+    idle: Mutex<Vec<C>>,
+    created: AtomicU32,
+}
+
+impl<C> Default for ClientPool<C> {
+    fn default() -> Self {
+        Self {
+            idle: Mutex::new(Vec::new()),
+            created: AtomicU32::new(0),
+        }
+    }
+}
+
+impl<C> ClientPool<C> {
+    /// Take an idle client, or build one with `create`
+    pub fn checkout(&self, create: impl FnOnce() -> C) -> PooledClient<'_, C> {
+        let idle = self.lock().pop();
+        let client = idle.unwrap_or_else(|| {
+            self.created.fetch_add(1, Ordering::Relaxed);
+            create()
+        });
+        PooledClient {
+            pool: self,
+            client: Some(client),
+        }
+    }
+
+    /// Clients built so far
+    pub fn created(&self) -> u32 {
+        self.created.load(Ordering::Relaxed)
+    }
+
+    /// Clients waiting for a proof
+    pub fn idle(&self) -> usize {
+        self.lock().len()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<C>> {
+        self.idle.lock().expect("client pool lock poisoned")
+    }
+}
+
+/// A client checked out of a `ClientPool`, returned to it on drop
+pub struct PooledClient<'a, C> {
+    pool: &'a ClientPool<C>,
+    client: Option<C>,
+}
+
+impl<C> std::ops::Deref for PooledClient<'_, C> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        self.client
+            .as_ref()
+            .expect("client is present until dropped")
+    }
+}
+
+impl<C> Drop for PooledClient<'_, C> {
+    fn drop(&mut self) {
+        // A panic may have left the client mid-proof
+        if std::thread::panicking() {
+            return;
+        }
+        if let Some(client) = self.client.take() {
+            self.pool.lock().push(client);
+        }
+    }
+}
+
+/// Backend of a frontend, which forwards requests instead of proving them
+pub struct NoBackend;
+
+impl ProofBackend for NoBackend {
+    fn name(&self) -> &'static str {
+        "none"
+    }
+
+    fn generate_proof(
+        &self,
+        request: ProverRequest,
+        _context: &ProofContext,
+    ) -> Result<ProofData, ServiceError> {
+        Err(ServiceError::ProofGeneration(format!(
+            "Request {} reached a frontend, which does not prove",
+            request.request_id
+        )))
+    }
+}
+
+/// Run `attempt` until it succeeds or fails for good
+///
+/// Errors accepted by `is_transient` are retried up to `context.retry_attempts`
+/// times, waiting `backoff` and doubling it after each retry. A retry that would
+/// start past `context.deadline` is not made. `attempt` receives the attempt
+/// number, starting at 1, which is also recorded in `context`.
+pub fn retry_transient<T>(
+    request_id: &str,
+    context: &ProofContext,
+    backoff: Duration,
+    is_transient: impl Fn(&ServiceError) -> bool,
+    mut attempt: impl FnMut(u32) -> Result<T, ServiceError>,
+) -> Result<T, ServiceError> {
+    let mut number = 1;
+    let mut delay = backoff;
+    loop {
+        context.attempts.store(number, Ordering::Relaxed);
+        let error = match attempt(number) {
+            Ok(value) => return Ok(value),
+            Err(error) => error,
+        };
+
+        if number > context.retry_attempts
+            || !is_transient(&error)
+            || Instant::now() + delay >= context.deadline
+        {
+            return Err(error);
+        }
+        warn!(
+            request_id = %request_id,
+            attempt = number,
+            "Transient failure, retrying in {}s: {}",
+            delay.as_secs(),
+            error
+        );
+        std::thread::sleep(delay);
+        number += 1;
+        delay *= 2;
+    }
+}
+
+/// Whether a failed `prove_evm` is a transient Docker failure, retried in place
+pub fn is_transient_prove_error(error: &ServiceError) -> bool {
+    matches!(error, ServiceError::ProofGeneration(message) if is_transient_failure(message))
+}
+
+/// Bounded retries of a startup step, e.g. reading from a volume mounted after the
+/// container starts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StartupRetry {
+    /// Retries after the first attempt
+    pub attempts: u32,
+
+    /// Delay before the first retry; doubles with each retry
+    pub backoff: Duration,
+}
+
+impl StartupRetry {
+    /// Run `attempt` until it succeeds or the retries are used up
+    ///
+    /// Every error is retried, and the last one is returned.
+    pub async fn run<T, F>(
+        &self,
+        what: &str,
+        mut attempt: impl FnMut() -> F,
+    ) -> Result<T, ServiceError>
+    where
+        F: Future<Output = Result<T, ServiceError>>,
+    {
+        let mut number = 0;
+        let mut delay = self.backoff;
+        loop {
+            let error = match attempt().await {
+                Ok(value) => return Ok(value),
+                Err(error) => error,
+            };
+            if number >= self.attempts {
+                return Err(error);
+            }
+            number += 1;
+            warn!(
+                "{} failed, retry {} of {} in {}ms: {}",
+                what,
+                number,
+                self.attempts,
+                delay.as_millis(),
+                error
+            );
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+}
+
+/// Name of the directory a proof of `request_id` runs in, under the output directory
+///
+/// Request IDs are validated before proving, but the name does not rely on it: it
+/// keeps at most 64 of the ID's letters, digits, `_` and `-`, and ends in a hash of
+/// the whole ID, so no ID leaves the output directory or shares another's.
+pub fn request_dir_name(request_id: &str) -> String {
+    let readable: String = request_id
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+        .take(64)
+        .collect();
+    let hash = hex::encode(&Sha256::digest(request_id.as_bytes())[..8]);
+    match readable.is_empty() {
+        true => format!("request-{}", hash),
+        false => format!("{}-{}", readable, hash),
+    }
 }
 
 /// Proof generator handles the core proving logic
 pub struct ProofGenerator {
     cached_elf: Arc<CachedElf>,
+    clients: ClientPool<DefaultProverClient>,
     output_base_dir: PathBuf,
+    setup_store: SetupStore,
+    verifying_key: OnceLock<VerifyingKey>,
+    profile: ProverProfile,
+    max_artifact_bytes: u64,
+    max_stdin_bytes: u64,
+    artifact_cipher: Option<Arc<ArtifactCipher>>,
+    artifact_encoding: ArtifactEncoding,
+    proof_json_layout: ProofJsonLayout,
 }
 
 impl ProofGenerator {
+    /// Circuit the generator proves: its stdin is a `ProofInputs`
+    pub const CIRCUIT: CircuitId = CircuitId::HumanIndex;
+
     /// Create a new proof generator with cached ELF
-    pub fn new(cached_elf: Arc<CachedElf>, output_base_dir: PathBuf) -> Self {
+    ///
+    /// `max_artifact_bytes` caps the size of the `inputs.json` a proof is read from,
+    /// `max_stdin_bytes` the size of the stdin a request is written to. With an
+    /// `artifact_cipher`, the artifacts written to the output directory are
+    /// sealed as soon as `prove_evm` returns.
+    pub fn new(
+        cached_elf: Arc<CachedElf>,
+        output_base_dir: PathBuf,
+        profile: ProverProfile,
+        max_artifact_bytes: u64,
+        max_stdin_bytes: u64,
+        artifact_cipher: Option<Arc<ArtifactCipher>>,
+    ) -> Self {
         Self {
             cached_elf,
+            clients: ClientPool::default(),
+            setup_store: SetupStore::new(&output_base_dir),
+            verifying_key: OnceLock::new(),
             output_base_dir,
+            profile,
+            max_artifact_bytes,
+            max_stdin_bytes,
+            artifact_cipher,
+            artifact_encoding: ArtifactEncoding::default(),
+            proof_json_layout: ProofJsonLayout::default(),
+        }
+    }
+
+    /// Encode proof artifacts as `encoding` says (default `Parallel`)
+    pub fn with_artifact_encoding(mut self, encoding: ArtifactEncoding) -> Self {
+        self.artifact_encoding = encoding;
+        self
+    }
+
+    /// Read proof artifacts from the keys of `layout` (default `PicoV1`)
+    pub fn with_proof_json_layout(mut self, layout: ProofJsonLayout) -> Self {
+        self.proof_json_layout = layout;
+        self
+    }
+
+    /// Prover clients kept between proofs
+    pub fn clients(&self) -> &ClientPool<DefaultProverClient> {
+        &self.clients
+    }
+
+    fn client(&self) -> PooledClient<'_, DefaultProverClient> {
+        self.clients
+            .checkout(|| DefaultProverClient::new(&self.cached_elf.data))
+    }
+
+    /// The `vm_vk` proofs are verified under before they are published
+    ///
+    /// Read from the setup store on first use. `None` while the key is missing,
+    /// unreadable or not a `PicoVerifier` key; proofs are then only checked for
+    /// structure, and the key is read again for the next proof.
+    fn verifying_key(&self) -> Option<&VerifyingKey> {
+        if let Some(vk) = self.verifying_key.get() {
+            return Some(vk);
+        }
+        let loaded = self
+            .setup_store
+            .resolve(Self::CIRCUIT, "vm_vk")
+            .map_err(|e| e.to_string())
+            .and_then(|path| VerifyingKey::from_file(&path).map_err(|e| e.to_string()))
+            .and_then(|vk| match vk.public_inputs() {
+                PUBLIC_INPUTS => Ok(vk),
+                n => Err(format!(
+                    "key has {} public inputs, PicoVerifier takes {}",
+                    n, PUBLIC_INPUTS
+                )),
+            });
+        match loaded {
+            Ok(vk) => Some(self.verifying_key.get_or_init(|| vk)),
+            Err(e) => {
+                warn!(
+                    "Verifying key unavailable, checking proofs for structure only: {}",
+                    e
+                );
+                None
+            }
         }
     }
 
     /// Generate a proof for the given request
     /// This is a blocking operation and should be called via spawn_blocking
-    pub fn generate_proof(&self, request: ProverRequest) -> Result<ProofData, ServiceError> {
+    fn prove(
+        &self,
+        request: ProverRequest,
+        context: &ProofContext,
+    ) -> Result<ProofData, ServiceError> {
+        // Calculate expected output
+        let validity = request.validity(Utc::now().timestamp() as u64);
+        let proof_inputs = ProofInputs::new(
+            request.verification_results,
+            request.public_inputs,
+            validity,
+        );
+        let expected_output = proof_inputs.expected_output;
+        let weights_commitment = commit_weights(&proof_inputs.inputs);
+        let expected = expected_public_values(&proof_inputs);
+
+        // The guest would refuse the window; fail before starting the zkVM
+        validity.check().map_err(|e| {
+            ServiceError::ProofGeneration(format!("Invalid validity window: {}", e))
+        })?;
+
+        // The private inputs are zeroized here; only the stdin words outlive them.
+        // A request over the stdin budget fails before a client is taken.
+        let (stdin, prepare_time) = prepare_stdin(proof_inputs, self.max_stdin_bytes)?;
+        context
+            .record_usage(|usage| usage.stdin_prepare_ms = Some(prepare_time.as_millis() as u64));
+
+        // A client from an earlier proof or the warm-up, or a new one with the cached ELF
+        let client = self.client();
+
+        // Proving consumes the stdin, so each attempt builds its own
+        let new_stdin = || {
+            let mut stdin_builder = client.new_stdin_builder();
+            stdin.write_to(|value| stdin_builder.write(value));
+            stdin_builder
+        };
+
+        // A guest panic in emulation leaves the cycles unknown; proving reports it
+        context.heartbeat("emulate");
+        let guest_cycles =
+            std::panic::catch_unwind(AssertUnwindSafe(|| client.emulate(new_stdin()).0)).ok();
+        context.record_usage(|usage| usage.guest_cycles = guest_cycles);
+
+        context.heartbeat("prove");
+        let mut proof_data = match self.profile {
+            ProverProfile::Evm => self.prove_evm(
+                &request.request_id,
+                expected_output,
+                &weights_commitment,
+                context,
+                // Generate EVM proof (never run trusted setup)
+                |output_dir| client.prove_evm(new_stdin(), false, output_dir, "kb"),
+            ),
+            ProverProfile::Fast => {
+                // No Groth16 wrap, so neither vm_pk/vm_vk nor Docker are needed
+                let meta_proof = client.prove_fast(new_stdin()).map_err(|e| {
+                    ServiceError::ProofGeneration(format!("prove_fast failed: {}", e))
+                })?;
+                let public_values = meta_proof.pv_stream.unwrap_or_default();
+
+                let mut proof_data = encode_proof_data(
+                    &[] as &[String],
+                    &format!("0x{}", hex::encode(public_values)),
+                    "",
+                    expected_output,
+                    &weights_commitment,
+                )?;
+                proof_data.profile = Some(ProverProfile::Fast);
+                proof_data.decoded_public_values = proof_data.decode_public_values().ok();
+                context.record_usage(|usage| usage.artifact_bytes_written = Some(0));
+                Ok(proof_data)
+            }
+        }?;
+
+        // `human_index` is what the host expected; only report it if the proof commits
+        // it, over the request's weights and window. Also catches a guest reading
+        // stdin in another order than the host wrote it.
+        check_public_values(&proof_data, &expected)?;
+        check_decoded_public_values(&proof_data)?;
+
+        // Verify before publishing; without the key, at least the structure is checked
+        if self.profile == ProverProfile::Evm {
+            let level = verify_with_fallback(self.verifying_key(), &proof_data).map_err(|e| {
+                ServiceError::ProofGeneration(format!("Proof failed verification: {}", e))
+            })?;
+            proof_data.verification = Some(level);
+        }
+        Ok(proof_data)
+    }
+
+    /// Run the Groth16 wrap in a request-specific directory and read its outputs
+    /// `prove_evm` receives the directory holding `vm_pk`/`vm_vk`
+    ///
+    /// A transient Docker failure is retried from a fresh directory, as allowed by `context`.
+    fn prove_evm(
+        &self,
+        request_id: &str,
+        human_index: u32,
+        weights_commitment: &[u8; 32],
+        context: &ProofContext,
+        mut prove_evm: impl FnMut(PathBuf) -> Result<(), anyhow::Error>,
+    ) -> Result<ProofData, ServiceError> {
+        retry_transient(
+            request_id,
+            context,
+            RETRY_BACKOFF,
+            is_transient_prove_error,
+            |_| {
+                self.prove_evm_once(
+                    request_id,
+                    human_index,
+                    weights_commitment,
+                    context,
+                    &mut prove_evm,
+                )
+            },
+        )
+    }
+
+    /// One `prove_evm` attempt; the request directory is removed afterwards
+    fn prove_evm_once(
+        &self,
+        request_id: &str,
+        human_index: u32,
+        weights_commitment: &[u8; 32],
+        context: &ProofContext,
+        prove_evm: &mut impl FnMut(PathBuf) -> Result<(), anyhow::Error>,
+    ) -> Result<ProofData, ServiceError> {
         // Create request-specific output directory (must be absolute path for prove_evm)
+        let dir_name = request_dir_name(request_id);
         let output_dir = self
             .output_base_dir
-            .join(&request.request_id)
+            .join(&dir_name)
             .canonicalize()
             .or_else(|_| {
                 // If canonicalize fails (dir doesn't exist yet), create it first
-                let dir = self.output_base_dir.join(&request.request_id);
+                let dir = self.output_base_dir.join(&dir_name);
                 std::fs::create_dir_all(&dir)?;
                 dir.canonicalize()
             })
@@ -52,70 +820,49 @@ impl ProofGenerator {
                 ServiceError::ProofGeneration(format!("Failed to resolve output directory: {}", e))
             })?;
 
-        // Initialize the prover client with cached ELF
-        let client = DefaultProverClient::new(&self.cached_elf.data);
-        let mut stdin_builder = client.new_stdin_builder();
-
-        // Write private inputs to stdin
-        let verification_results = &request.verification_results;
-        stdin_builder.write(&verification_results.recaptcha_score);
-        stdin_builder.write(&verification_results.sms_verified);
-        stdin_builder.write(&verification_results.bio_verified);
-
-        // Calculate expected output
-        let public_inputs = &request.public_inputs;
-        let expected_output = calculate_human_index(verification_results, public_inputs);
-
-        // Write public inputs to stdin
-        stdin_builder.write(&public_inputs.w1);
-        stdin_builder.write(&public_inputs.w2);
-        stdin_builder.write(&public_inputs.w3);
-        stdin_builder.write(&public_inputs.w4);
-        stdin_builder.write(&expected_output);
-
-        // Hard link setup files from base data directory to proof directory
-        // Note: We use hard links instead of symlinks because Docker mounts the subdirectory,
-        // and symlinks pointing to files outside the mounted directory won't resolve in the container.
-        let vm_pk_path = self.output_base_dir.join("vm_pk").canonicalize().map_err(|e| {
-            ServiceError::ProofGeneration(format!(
-                "Groth16 setup file vm_pk not found at {}. Run the setup command first. Error: {}",
-                self.output_base_dir.join("vm_pk").display(),
-                e
-            ))
-        })?;
-        let vm_vk_path = self.output_base_dir.join("vm_vk").canonicalize().map_err(|e| {
-            ServiceError::ProofGeneration(format!(
-                "Groth16 setup file vm_vk not found at {}. Run the setup command first. Error: {}",
-                self.output_base_dir.join("vm_vk").display(),
-                e
-            ))
-        })?;
-
-        let dest_vm_pk = output_dir.join("vm_pk");
-        let dest_vm_vk = output_dir.join("vm_vk");
-        // Try hard link first, fall back to copy if cross-filesystem or Docker overlay
-        if std::fs::hard_link(&vm_pk_path, &dest_vm_pk).is_err() {
-            std::fs::copy(&vm_pk_path, &dest_vm_pk).map_err(|e| {
-                ServiceError::ProofGeneration(format!("Failed to copy vm_pk: {}", e))
-            })?;
-        }
-        if std::fs::hard_link(&vm_vk_path, &dest_vm_vk).is_err() {
-            std::fs::copy(&vm_vk_path, &dest_vm_vk).map_err(|e| {
-                ServiceError::ProofGeneration(format!("Failed to copy vm_vk: {}", e))
-            })?;
+        // The circuit's keys, through the setup store, linked into the proof directory;
+        // without them there is nothing to prove, so the directory goes too
+        if let Err(e) = link_setup_files(&self.setup_store, Self::CIRCUIT, &output_dir) {
+            if let Err(e) = std::fs::remove_dir_all(&output_dir) {
+                info!(
+                    "Failed to cleanup output directory {}: {} (non-fatal)",
+                    output_dir.display(),
+                    e
+                );
+            }
+            return Err(e);
         }
 
-        // Generate EVM proof (never run trusted setup)
-        let prove_result = client
-            .prove_evm(stdin_builder, false, output_dir.clone(), "kb")
-            .map_err(|e| ServiceError::ProofGeneration(format!("prove_evm failed: {}", e)));
+        // `{:#}` keeps the cause chain, which carries the Docker error text
+        let prove_result = std::thread::scope(|scope| {
+            let (stop, stopped) = channel();
+            let dir = &output_dir;
+            scope.spawn(move || watch_progress(dir, context, stopped));
+            let result = prove_evm(output_dir.clone());
+            drop(stop);
+            result
+        })
+        .map_err(|e| ServiceError::ProofGeneration(format!("prove_evm failed: {:#}", e)))
+        .and_then(|()| match &self.artifact_cipher {
+            Some(cipher) => cipher.seal_dir(&output_dir, &SETUP_FILES),
+            None => Ok(()),
+        });
 
         // Read the generated proof files before cleanup
         let result = match prove_result {
-            Ok(()) => self.read_proof_files(&output_dir, expected_output),
+            Ok(()) => {
+                context.heartbeat("read_artifacts");
+                self.read_proof_files(&output_dir, human_index, weights_commitment)
+            }
             Err(e) => Err(e),
         };
 
+        // Failed attempts wrote artifacts too
+        let written = dir_bytes(&output_dir, &SETUP_FILES);
+        context.record_usage(|usage| {
+            usage.artifact_bytes_written = Some(usage.artifact_bytes_written.unwrap_or(0) + written)
+        });
+
         // Always cleanup the request-specific output directory
         if let Err(e) = std::fs::remove_dir_all(&output_dir) {
             info!(
@@ -128,79 +875,696 @@ impl ProofGenerator {
         result
     }
 
-    /// Read and encode proof files to base64
+    /// Read and encode proof files to base64, decrypting them if sealed
     fn read_proof_files(
         &self,
         output_dir: &Path,
         human_index: u32,
+        weights_commitment: &[u8; 32],
     ) -> Result<ProofData, ServiceError> {
-        use base64::{Engine, engine::general_purpose::STANDARD};
-        use serde_json::Value;
-
-        // Read inputs.json file generated by Pico SDK
         let inputs_path = output_dir.join("inputs.json");
-        let inputs_content = std::fs::read_to_string(&inputs_path).map_err(|e| {
+        match &self.artifact_cipher {
+            Some(cipher) => read_sealed_proof_artifacts(
+                &inputs_path,
+                self.max_artifact_bytes,
+                self.proof_json_layout,
+                self.artifact_encoding,
+                cipher,
+                human_index,
+                weights_commitment,
+            ),
+            None => read_proof_artifacts(
+                &inputs_path,
+                self.max_artifact_bytes,
+                self.proof_json_layout,
+                self.artifact_encoding,
+                human_index,
+                weights_commitment,
+            ),
+        }
+    }
+}
+
+impl ProofBackend for ProofGenerator {
+    fn name(&self) -> &'static str {
+        "pico"
+    }
+
+    fn generate_proof(
+        &self,
+        request: ProverRequest,
+        context: &ProofContext,
+    ) -> Result<ProofData, ServiceError> {
+        let sampler = UsageSampler::start();
+        let result = self.prove(request, context);
+        let sample = sampler.finish();
+        context.record_usage(|usage| {
+            usage.peak_rss_bytes = sample.peak_rss_bytes;
+            usage.cpu_time_ms = sample.cpu_time_ms;
+        });
+        result
+    }
+
+    /// Build the first client and run the guest once over the setup inputs
+    ///
+    /// Execute-only: nothing is proved and Docker is not started, but the ELF is
+    /// loaded into the client and the emulator's code paths and caches are hot.
+    fn warm_up(&self) -> Result<Option<WarmUp>, ServiceError> {
+        let started = Instant::now();
+        let client = self.client();
+        let stdin = StdinWords::take(setup_inputs());
+        let mut stdin_builder = client.new_stdin_builder();
+        stdin.write_to(|value| stdin_builder.write(value));
+        let guest_cycles =
+            std::panic::catch_unwind(AssertUnwindSafe(|| client.emulate(stdin_builder).0))
+                .map_err(|_| {
+                    ServiceError::ProofGeneration("Warm-up emulation panicked".to_string())
+                })?;
+        Ok(Some(WarmUp {
+            duration_ms: started.elapsed().as_millis() as u64,
+            guest_cycles: Some(guest_cycles),
+        }))
+    }
+}
+
+/// Report progress while `prove_evm` writes to `output_dir`, until `stop` is dropped
+///
+/// The RISC-V proof and the Groth16 wrap do not report back, so a request directory
+/// that keeps growing is the heartbeat; a stuck proof writes nothing.
+fn watch_progress(output_dir: &Path, context: &ProofContext, stop: Receiver<()>) {
+    let mut written = dir_bytes(output_dir, &SETUP_FILES);
+    while let Err(RecvTimeoutError::Timeout) = stop.recv_timeout(PROGRESS_POLL) {
+        let now = dir_bytes(output_dir, &SETUP_FILES);
+        if now != written {
+            context.heartbeat("prove");
+            written = now;
+        }
+    }
+}
+
+/// Types of the stdin values a host writes, in order
+///
+/// The Groth16 setup is specific to the circuit, and so to the number and types of
+/// the stdin values. A setup run over another layout than proving uses produces keys
+/// the runtime proofs do not match, so `setup` checks its layout against `runtime`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputLayout(Vec<&'static str>);
+
+impl InputLayout {
+    /// Layout `ProofGenerator` writes; it is the same for every request
+    pub fn runtime() -> Self {
+        let mut layout = Self::default();
+        ProofInputs::default().write_to(|value| layout.record(value));
+        layout
+    }
+
+    /// Record a value written to stdin
+    pub fn record<T>(&mut self, _value: &T) {
+        self.0.push(std::any::type_name::<T>());
+    }
+
+    /// Number of values written
+    pub fn arity(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Fail unless this layout is the one proofs are generated with
+    pub fn check_runtime(&self) -> Result<(), ServiceError> {
+        let runtime = Self::runtime();
+        if *self != runtime {
+            return Err(ServiceError::Config(format!(
+                "stdin layout {:?} differs from the runtime layout {:?}",
+                self.0, runtime.0
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Stdin words of a proof, held in place of the `ProofInputs` they were written from
+///
+/// Proving consumes the stdin, so every attempt writes the words to a fresh builder.
+/// Holding the words lets the private inputs be zeroized before proving starts;
+/// the words are zeroized on drop too.
+pub struct StdinWords(Zeroizing<Vec<u32>>);
+
+impl StdinWords {
+    /// Write `inputs` to words, then drop them
+    pub fn take<I: Borrow<ProofInputs>>(inputs: I) -> Self {
+        let mut words = Zeroizing::new(Vec::with_capacity(STDIN_WORDS));
+        inputs.borrow().write_to(|value| words.push(*value));
+        drop(inputs);
+        Self(words)
+    }
+
+    /// Size of the words, in bytes
+    pub fn byte_len(&self) -> u64 {
+        (self.0.len() * std::mem::size_of::<u32>()) as u64
+    }
+
+    /// Write the words, in the order `ProofInputs::write_to` wrote them
+    pub fn write_to(&self, mut write: impl FnMut(&u32)) {
+        for value in self.0.iter() {
+            write(value);
+        }
+    }
+}
+
+/// Longest `prepare_stdin` may take before the request is refused as too large
+pub const STDIN_PREPARE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Write `inputs` to stdin words, refusing them over `max_bytes`
+///
+/// Returns the words and how long writing them took. Words over `max_bytes`, or
+/// that took longer than `STDIN_PREPARE_TIMEOUT`, fail with `RequestTooLarge`
+/// before anything is written to the zkVM; the words are zeroized either way.
+pub fn prepare_stdin<I: Borrow<ProofInputs>>(
+    inputs: I,
+    max_bytes: u64,
+) -> Result<(StdinWords, Duration), ServiceError> {
+    let started = Instant::now();
+    let stdin = StdinWords::take(inputs);
+    let elapsed = started.elapsed();
+
+    if stdin.byte_len() > max_bytes {
+        return Err(ServiceError::RequestTooLarge(format!(
+            "stdin is {} bytes, over MAX_STDIN_BYTES ({})",
+            stdin.byte_len(),
+            max_bytes
+        )));
+    }
+    if elapsed > STDIN_PREPARE_TIMEOUT {
+        return Err(ServiceError::RequestTooLarge(format!(
+            "preparing stdin took {}ms, over {}ms",
+            elapsed.as_millis(),
+            STDIN_PREPARE_TIMEOUT.as_millis()
+        )));
+    }
+    Ok((stdin, elapsed))
+}
+
+/// Inputs the Groth16 setup proves over
+///
+/// The values do not affect the keys, but are kept on the runtime scale (`SCALE`
+/// fixed-point, production weights) so the setup proof is a realistic one.
+pub fn setup_inputs() -> ProofInputs {
+    let verification_results = VerificationResults {
+        recaptcha_score: 7500,
+        sms_verified: 1,
+        bio_verified: 1,
+    };
+    let public_inputs = HumanIndexPublicInputs {
+        w1: 1500,
+        w2: 2000,
+        w3: 2500,
+        w4: 4000,
+        expected_output: 0,
+    };
+    let validity = Validity {
+        issued_at: 1_700_000_000,
+        valid_until: 1_700_000_000 + MAX_VALIDITY_SECS,
+    };
+    ProofInputs::new(verification_results, public_inputs, validity)
+}
+
+/// Public values the guest commits when proving `proof_inputs`
+///
+/// The guest commits the weights and window it read, the expected output the host
+/// wrote in place of `inputs.expected_output`, and the index it computed, which
+/// must be that same expected output.
+pub fn expected_public_values(proof_inputs: &ProofInputs) -> PublicValues {
+    let inputs = HumanIndexPublicInputs {
+        expected_output: proof_inputs.expected_output,
+        ..proof_inputs.inputs.clone()
+    };
+    PublicValues {
+        weights_commitment: commit_weights(&inputs),
+        inputs,
+        computed_output: proof_inputs.expected_output,
+        validity: proof_inputs.validity,
+    }
+}
+
+/// Check that `proof_data` commits `expected`, field by field
+///
+/// The committed values are decoded from `public_inputs` with the layout the guest
+/// commits. Every differing field is listed in the `PublicValuesMismatch`.
+pub fn check_public_values(
+    proof_data: &ProofData,
+    expected: &PublicValues,
+) -> Result<(), ServiceError> {
+    let committed = proof_data.public_values()?;
+    let fields: [(&'static str, u64, u64); 8] = [
+        ("w1", expected.inputs.w1.into(), committed.inputs.w1.into()),
+        ("w2", expected.inputs.w2.into(), committed.inputs.w2.into()),
+        ("w3", expected.inputs.w3.into(), committed.inputs.w3.into()),
+        ("w4", expected.inputs.w4.into(), committed.inputs.w4.into()),
+        (
+            "expected_output",
+            expected.inputs.expected_output.into(),
+            committed.inputs.expected_output.into(),
+        ),
+        (
+            "computed_output",
+            expected.computed_output.into(),
+            committed.computed_output.into(),
+        ),
+        (
+            "issued_at",
+            expected.validity.issued_at,
+            committed.validity.issued_at,
+        ),
+        (
+            "valid_until",
+            expected.validity.valid_until,
+            committed.validity.valid_until,
+        ),
+    ];
+    let mut mismatches: Vec<FieldMismatch> = fields
+        .into_iter()
+        .filter(|(_, expected, committed)| expected != committed)
+        .map(|(field, expected, committed)| FieldMismatch {
+            field,
+            expected: expected.to_string(),
+            committed: committed.to_string(),
+        })
+        .collect();
+    if committed.weights_commitment != expected.weights_commitment {
+        mismatches.push(FieldMismatch {
+            field: "weights_commitment",
+            expected: format!("0x{}", hex::encode(expected.weights_commitment)),
+            committed: format!("0x{}", hex::encode(committed.weights_commitment)),
+        });
+    }
+
+    if mismatches.is_empty() {
+        return Ok(());
+    }
+    Err(PublicValuesMismatch(mismatches).into())
+}
+
+/// Check that `proof_data` carries `decoded_public_values`, as `public_inputs` decode
+///
+/// Fails when the public values do not decode, so a proof is never published
+/// without the block.
+pub fn check_decoded_public_values(proof_data: &ProofData) -> Result<(), ServiceError> {
+    let decoded = proof_data.decode_public_values()?;
+    match &proof_data.decoded_public_values {
+        Some(published) if *published == decoded => Ok(()),
+        Some(_) => Err(ServiceError::ProofGeneration(
+            "decoded_public_values differ from public_inputs".to_string(),
+        )),
+        None => Err(ServiceError::ProofGeneration(
+            "decoded_public_values missing".to_string(),
+        )),
+    }
+}
+
+/// Encode proof artifacts, as found in inputs.json, into `ProofData`
+///
+/// `proof` is the JSON array of proof words, `public_values` and `riscv_vkey`
+/// the hex strings written by the SDK; each is base64-encoded as-is.
+pub fn encode_proof_data<P: serde::Serialize + ?Sized>(
+    proof: &P,
+    public_values: &str,
+    riscv_vkey: &str,
+    human_index: u32,
+    weights_commitment: &[u8; 32],
+) -> Result<ProofData, ServiceError> {
+    Ok(proof_data(
+        encode_json_base64(proof)?,
+        STANDARD.encode(public_values.as_bytes()),
+        STANDARD.encode(riscv_vkey.as_bytes()),
+        human_index,
+        weights_commitment,
+    ))
+}
+
+/// `encode_proof_data`, with the three encodings run at once on the rayon pool
+pub fn encode_proof_data_parallel<P: serde::Serialize + Sync + ?Sized>(
+    proof: &P,
+    public_values: &str,
+    riscv_vkey: &str,
+    human_index: u32,
+    weights_commitment: &[u8; 32],
+) -> Result<ProofData, ServiceError> {
+    let (proof, (public_inputs, verification_key)) = rayon::join(
+        || encode_json_base64(proof),
+        || {
+            rayon::join(
+                || STANDARD.encode(public_values.as_bytes()),
+                || STANDARD.encode(riscv_vkey.as_bytes()),
+            )
+        },
+    );
+    Ok(proof_data(
+        proof?,
+        public_inputs,
+        verification_key,
+        human_index,
+        weights_commitment,
+    ))
+}
+
+/// `ProofData` of encoded artifacts, before the service fills in the optional fields
+fn proof_data(
+    proof: String,
+    public_inputs: String,
+    verification_key: String,
+    human_index: u32,
+    weights_commitment: &[u8; 32],
+) -> ProofData {
+    ProofData {
+        proof,
+        public_inputs,
+        verification_key,
+        human_index,
+        weights_commitment: format!("0x{}", hex::encode(weights_commitment)),
+        attestation_signature: None,
+        attestation_expiry: None,
+        onchain_tx: None,
+        content_encoding: None,
+        profile: None,
+        weights_profile: None,
+        verification: None,
+        decoded_public_values: None,
+    }
+}
+
+/// Locate the Groth16 setup files, `vm_pk` and `vm_vk`, in `base_dir`
+///
+/// Only the `evm` profile needs them; see `ProverProfile::needs_setup_files`.
+pub fn find_setup_files(base_dir: &Path) -> Result<(PathBuf, PathBuf), ServiceError> {
+    let store = SetupStore::new(base_dir);
+    Ok((
+        store.resolve(ProofGenerator::CIRCUIT, "vm_pk")?,
+        store.resolve(ProofGenerator::CIRCUIT, "vm_vk")?,
+    ))
+}
+
+/// The fields of an `inputs.json` that go into `ProofData`
+struct ProofJson {
+    proof: ProofField,
+    public_values: String,
+    verifying_key: String,
+}
+
+/// A proof as the SDK writes it: Pico's list of words, or a single hex string
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum ProofField {
+    Words(Vec<String>),
+    Hex(String),
+}
+
+/// Deserializes a `ProofJson` from the keys of a layout, skipping any other key
+struct ProofJsonSeed(ProofJsonLayout);
+
+impl<'de> serde::de::DeserializeSeed<'de> for ProofJsonSeed {
+    type Value = ProofJson;
+
+    fn deserialize<D: serde::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<ProofJson, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> serde::de::Visitor<'de> for ProofJsonSeed {
+    type Value = ProofJson;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "an object with the {} keys {:?}",
+            self.0.name(),
+            self.0.keys()
+        )
+    }
+
+    fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<ProofJson, A::Error> {
+        use serde::de::Error;
+
+        let [proof_key, public_values_key, verifying_key_key] = self.0.keys();
+        let (mut proof, mut public_values, mut verifying_key) = (None, None, None);
+        while let Some(key) = map.next_key::<String>()? {
+            if key == proof_key {
+                proof = Some(map.next_value()?);
+            } else if key == public_values_key {
+                public_values = Some(map.next_value()?);
+            } else if key == verifying_key_key {
+                verifying_key = Some(map.next_value()?);
+            } else {
+                map.next_value::<serde::de::IgnoredAny>()?;
+            }
+        }
+        Ok(ProofJson {
+            proof: proof.ok_or_else(|| A::Error::missing_field(proof_key))?,
+            public_values: public_values
+                .ok_or_else(|| A::Error::missing_field(public_values_key))?,
+            verifying_key: verifying_key
+                .ok_or_else(|| A::Error::missing_field(verifying_key_key))?,
+        })
+    }
+}
+
+/// Read the `inputs.json` at `inputs_path` into a `ProofData`
+///
+/// The file is deserialized straight from a reader, with the artifacts taken from
+/// the keys of `layout`, and refused without being read if it is larger than
+/// `max_bytes`.
+pub fn read_proof_artifacts(
+    inputs_path: &Path,
+    max_bytes: u64,
+    layout: ProofJsonLayout,
+    encoding: ArtifactEncoding,
+    human_index: u32,
+    weights_commitment: &[u8; 32],
+) -> Result<ProofData, ServiceError> {
+    let file = open_proof_artifact(inputs_path, max_bytes)?;
+    decode_proof_artifact(
+        std::io::BufReader::new(file),
+        layout,
+        encoding,
+        human_index,
+        weights_commitment,
+    )
+}
+
+/// Read the `inputs.json` at `inputs_path`, sealed by `cipher`, into a `ProofData`
+///
+/// `max_bytes` caps the sealed file; a plaintext file is refused.
+pub fn read_sealed_proof_artifacts(
+    inputs_path: &Path,
+    max_bytes: u64,
+    layout: ProofJsonLayout,
+    encoding: ArtifactEncoding,
+    cipher: &ArtifactCipher,
+    human_index: u32,
+    weights_commitment: &[u8; 32],
+) -> Result<ProofData, ServiceError> {
+    let mut sealed = Vec::new();
+    std::io::Read::read_to_end(
+        &mut open_proof_artifact(inputs_path, max_bytes)?,
+        &mut sealed,
+    )?;
+    let inputs = Zeroizing::new(cipher.decrypt(&sealed).map_err(|e| {
+        ServiceError::ProofGeneration(format!(
+            "Failed to decrypt {}: {}",
+            inputs_path.display(),
+            e
+        ))
+    })?);
+    decode_proof_artifact(
+        inputs.as_slice(),
+        layout,
+        encoding,
+        human_index,
+        weights_commitment,
+    )
+}
+
+/// Open the `inputs.json` at `inputs_path`, refusing it if larger than `max_bytes`
+fn open_proof_artifact(inputs_path: &Path, max_bytes: u64) -> Result<std::fs::File, ServiceError> {
+    let file = std::fs::File::open(inputs_path).map_err(|e| {
+        ServiceError::ProofGeneration(format!(
+            "Failed to read inputs file {}: {}. The preceding Docker step likely failed to write outputs (commonly due to insufficient Docker memory). Check the `docker` logs or increase Docker's memory limit.",
+            inputs_path.display(),
+            e
+        ))
+    })?;
+
+    let size = file.metadata()?.len();
+    if size > max_bytes {
+        return Err(ServiceError::ProofGeneration(format!(
+            "Inputs file {} is {} bytes, over MAX_PROOF_ARTIFACT_BYTES ({})",
+            inputs_path.display(),
+            size,
+            max_bytes
+        )));
+    }
+    Ok(file)
+}
+
+/// Decode an `inputs.json` from `reader` into a `ProofData`
+fn decode_proof_artifact(
+    reader: impl std::io::Read,
+    layout: ProofJsonLayout,
+    encoding: ArtifactEncoding,
+    human_index: u32,
+    weights_commitment: &[u8; 32],
+) -> Result<ProofData, ServiceError> {
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let inputs = serde::de::DeserializeSeed::deserialize(ProofJsonSeed(layout), &mut deserializer)
+        .and_then(|inputs| deserializer.end().map(|()| inputs))
+        .map_err(|e| {
             ServiceError::ProofGeneration(format!(
-                "Failed to read inputs file {}: {}. The preceding Docker step likely failed to write outputs (commonly due to insufficient Docker memory). Check the `docker` logs or increase Docker's memory limit.",
-                inputs_path.display(),
+                "Failed to parse inputs.json with PROOF_JSON_KEYS={}: {}",
+                layout.name(),
                 e
             ))
         })?;
 
-        let inputs: Value = serde_json::from_str(&inputs_content).map_err(|e| {
-            ServiceError::ProofGeneration(format!("Failed to parse inputs.json: {}", e))
-        })?;
+    let encode = match encoding {
+        ArtifactEncoding::Sequential => encode_proof_data::<ProofField>,
+        ArtifactEncoding::Parallel => encode_proof_data_parallel::<ProofField>,
+    };
+    let mut proof_data = encode(
+        &inputs.proof,
+        &inputs.public_values,
+        &inputs.verifying_key,
+        human_index,
+        weights_commitment,
+    )?;
+    // Left out if the values do not decode; `check_decoded_public_values` fails then
+    proof_data.decoded_public_values = proof_data.decode_public_values().ok();
+    Ok(proof_data)
+}
 
-        // Extract proof array and encode to base64
-        let proof_array = inputs
-            .get("proof")
-            .and_then(|v| v.as_array())
-            .ok_or_else(|| {
-                ServiceError::ProofGeneration("Missing proof array in inputs.json".to_string())
-            })?;
+/// Serialize `value` to JSON and base64-encode it without an intermediate JSON string
+///
+/// A first pass counts the JSON bytes so the output is allocated once.
+fn encode_json_base64<T: serde::Serialize + ?Sized>(value: &T) -> Result<String, ServiceError> {
+    let to_error = |e: serde_json::Error| {
+        ServiceError::ProofGeneration(format!("Failed to serialize proof: {}", e))
+    };
 
-        let proof_json = serde_json::to_string(&proof_array).map_err(|e| {
-            ServiceError::ProofGeneration(format!("Failed to serialize proof: {}", e))
-        })?;
-        let proof = STANDARD.encode(proof_json.as_bytes());
-
-        // Extract publicValues and encode to base64
-        let public_values = inputs
-            .get("publicValues")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| {
-                ServiceError::ProofGeneration("Missing publicValues in inputs.json".to_string())
-            })?;
-        let public_inputs = STANDARD.encode(public_values.as_bytes());
-
-        // Extract riscvVKey and encode to base64
-        let riscv_vkey = inputs
-            .get("riscvVKey")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| {
-                ServiceError::ProofGeneration("Missing riscvVKey in inputs.json".to_string())
-            })?;
-        let verification_key = STANDARD.encode(riscv_vkey.as_bytes());
+    let mut counter = ByteCounter(0);
+    serde_json::to_writer(&mut counter, value).map_err(to_error)?;
+    let capacity = base64::encoded_len(counter.0, true).unwrap_or(0);
 
-        Ok(ProofData {
-            proof,
-            public_inputs,
-            verification_key,
-            human_index,
-        })
+    let mut encoder = base64::write::EncoderStringWriter::from_consumer(
+        String::with_capacity(capacity),
+        &STANDARD,
+    );
+    serde_json::to_writer(&mut encoder, value).map_err(to_error)?;
+    Ok(encoder.into_inner())
+}
+
+/// Writer that only counts the bytes written to it
+struct ByteCounter(usize);
+
+impl std::io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
     }
 }
 
 /// Helper to load and cache ELF at service startup
-pub async fn load_and_cache_elf(elf_path: &str) -> Result<Arc<CachedElf>, ServiceError> {
-    // Load ELF in a blocking task since it's an IO operation
-    let elf_path = elf_path.to_string();
-    let cached_elf = tokio::task::spawn_blocking(move || CachedElf::load(&elf_path))
+///
+/// `elf_path` is a local path or a `gs://<bucket>/<object>` URL. A failed load is
+/// retried as `retry` allows; an ELF whose SHA-256 is not `expected_sha256` fails.
+pub async fn load_and_cache_elf(
+    elf_path: &str,
+    expected_sha256: Option<&str>,
+    retry: StartupRetry,
+) -> Result<Arc<CachedElf>, ServiceError> {
+    if let Some(object) = GcsObject::parse(elf_path) {
+        // A malformed URL fails before any credentials are looked up
+        object?;
+        let store = GcsObjects::new().await?;
+        return load_gcs_elf(&store, elf_path, expected_sha256, retry).await;
+    }
+
+    let cached_elf = retry
+        .run("Loading the ELF", || {
+            // Load ELF in a blocking task since it's an IO operation
+            let elf_path = elf_path.to_string();
+            async move {
+                tokio::task::spawn_blocking(move || CachedElf::load(&elf_path))
+                    .await
+                    .map_err(|e| {
+                        ServiceError::ProofGeneration(format!(
+                            "Failed to spawn ELF loading task: {}",
+                            e
+                        ))
+                    })?
+            }
+        })
+        .await
+        .map_err(|e| ServiceError::ProofGeneration(format!("Failed to load ELF: {}", e)))?;
+
+    verify_elf(cached_elf, expected_sha256)
+}
+
+/// Download the ELF at the `gs://` URL `url` from `store` into memory
+///
+/// Retried and verified like `load_and_cache_elf`.
+pub async fn load_gcs_elf(
+    store: &dyn ObjectStore,
+    url: &str,
+    expected_sha256: Option<&str>,
+    retry: StartupRetry,
+) -> Result<Arc<CachedElf>, ServiceError> {
+    let object = GcsObject::parse(url)
+        .ok_or_else(|| ServiceError::Config(format!("Not a Cloud Storage URL: {}", url)))??;
+    let data = retry
+        .run("Downloading the ELF", || store.get(&object))
         .await
-        .map_err(|e| {
-            ServiceError::ProofGeneration(format!("Failed to spawn ELF loading task: {}", e))
-        })?
         .map_err(|e| ServiceError::ProofGeneration(format!("Failed to load ELF: {}", e)))?;
 
+    verify_elf(CachedElf { data }, expected_sha256)
+}
+
+fn verify_elf(
+    cached_elf: CachedElf,
+    expected_sha256: Option<&str>,
+) -> Result<Arc<CachedElf>, ServiceError> {
+    if let Some(expected) = expected_sha256 {
+        let actual = cached_elf.sha256();
+        if !actual.eq_ignore_ascii_case(expected) {
+            return Err(ServiceError::ProofGeneration(format!(
+                "ELF SHA-256 mismatch: expected {}, got {}",
+                expected, actual
+            )));
+        }
+    }
     Ok(Arc::new(cached_elf))
 }
+
+/// Wait for the setup files of `ProofGenerator::CIRCUIT` in `output_dir`, as
+/// `retry` allows
+///
+/// Returns their resolved paths, in `SETUP_FILES` order: the blobs when the files
+/// are in the setup store.
+pub async fn resolve_setup_files(
+    output_dir: &Path,
+    retry: StartupRetry,
+) -> Result<Vec<PathBuf>, ServiceError> {
+    retry
+        .run("Resolving the setup files", || {
+            let store = SetupStore::new(output_dir);
+            let resolved = SETUP_FILES
+                .iter()
+                .map(|name| store.resolve(ProofGenerator::CIRCUIT, name))
+                .collect();
+            std::future::ready(resolved)
+        })
+        .await
+}
@@ -0,0 +1,131 @@
+use crate::canonical::canonical_bytes;
+use crate::error::ServiceError;
+use crate::types::ProverRequest;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Outcome of `Quarantine::begin`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Admission {
+    /// Prove the request; this is attempt `attempt`
+    Proceed { attempt: u32 },
+    /// The request is quarantined and must not be proved
+    Quarantined,
+}
+
+/// Tracks processing attempts per request and quarantines requests that keep failing
+///
+/// A request is identified by the hash of its inputs, so a replay under a new ID
+/// counts against the same request. Counts live in `<dir>/attempts/<hash>` and
+/// quarantined requests in `<dir>/<hash>.json`.
+pub struct Quarantine {
+    dir: PathBuf,
+    max_attempts: u32,
+    lock: Mutex<()>,
+}
+
+impl Quarantine {
+    /// Open the store in `dir`, allowing `max_attempts` attempts per request
+    pub fn open(dir: &Path, max_attempts: u32) -> Result<Self, ServiceError> {
+        fs::create_dir_all(dir.join("attempts"))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            max_attempts,
+            lock: Mutex::new(()),
+        })
+    }
+
+    /// Count an attempt before proving starts, so a crash mid-proof still counts
+    ///
+    /// Once `max_attempts` attempts have not succeeded, the request is written to
+    /// the quarantine directory instead and never attempted again.
+    pub fn begin(&self, request: &ProverRequest) -> Result<Admission, ServiceError> {
+        let hash = request_hash(request);
+        let _guard = self.lock.lock().expect("quarantine lock poisoned");
+
+        if self.quarantined_path(&hash).exists() {
+            return Ok(Admission::Quarantined);
+        }
+
+        let attempts_path = self.attempts_path(&hash);
+        let attempts = match fs::read_to_string(&attempts_path) {
+            Ok(count) => count.trim().parse::<u32>().unwrap_or(0),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => 0,
+            Err(e) => return Err(e.into()),
+        };
+
+        if attempts >= self.max_attempts {
+            write_synced(
+                &self.quarantined_path(&hash),
+                &serde_json::to_vec_pretty(request)?,
+            )?;
+            fs::remove_file(&attempts_path).ok();
+            return Ok(Admission::Quarantined);
+        }
+
+        let attempt = attempts + 1;
+        write_synced(&attempts_path, attempt.to_string().as_bytes())?;
+        Ok(Admission::Proceed { attempt })
+    }
+
+    /// Forget the attempts of a request that finished without a fault of its own
+    pub fn clear(&self, request: &ProverRequest) {
+        let _guard = self.lock.lock().expect("quarantine lock poisoned");
+        fs::remove_file(self.attempts_path(&request_hash(request))).ok();
+    }
+
+    /// Hashes of the quarantined requests
+    pub fn list(&self) -> Result<Vec<String>, ServiceError> {
+        let mut hashes: Vec<String> = fs::read_dir(&self.dir)?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                name.strip_suffix(".json").map(str::to_string)
+            })
+            .collect();
+        hashes.sort();
+        Ok(hashes)
+    }
+
+    /// Release a quarantined request so it is attempted again
+    ///
+    /// Returns false when `hash` was not quarantined.
+    pub fn release(&self, hash: &str) -> Result<bool, ServiceError> {
+        if hash.len() != 64 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(ServiceError::Config(format!(
+                "Invalid request hash '{}'",
+                hash
+            )));
+        }
+        let _guard = self.lock.lock().expect("quarantine lock poisoned");
+        fs::remove_file(self.attempts_path(hash)).ok();
+        match fs::remove_file(self.quarantined_path(hash)) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn attempts_path(&self, hash: &str) -> PathBuf {
+        self.dir.join("attempts").join(hash)
+    }
+
+    fn quarantined_path(&self, hash: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", hash))
+    }
+}
+
+/// SHA-256 of the request inputs, hex-encoded; see `canonical_bytes`
+pub fn request_hash(request: &ProverRequest) -> String {
+    hex::encode(Sha256::digest(canonical_bytes(request)))
+}
+
+fn write_synced(path: &Path, contents: &[u8]) -> Result<(), ServiceError> {
+    let mut file = fs::File::create(path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    Ok(())
+}
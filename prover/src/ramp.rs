@@ -0,0 +1,76 @@
+use std::time::{Duration, Instant};
+
+/// Warm-up of proof concurrency after startup
+///
+/// Starts at one permit and grows in even steps toward the target over the
+/// warm-up window. Each step also has to be earned: the limit never exceeds one
+/// more than the number of proofs that succeeded so far, so a worker whose first
+/// proofs fail, e.g. for lack of memory, stays where it is.
+#[derive(Debug, Clone)]
+pub struct ConcurrencyRamp {
+    target: usize,
+    warmup: Duration,
+    started: Instant,
+    successes: usize,
+    /// Permits handed out so far
+    granted: usize,
+}
+
+impl ConcurrencyRamp {
+    /// Ramp toward `target` permits over `warmup`, starting at `started` with
+    /// one permit granted
+    pub fn new(target: usize, warmup: Duration, started: Instant) -> Self {
+        Self {
+            target: target.max(1),
+            warmup,
+            started,
+            successes: 0,
+            granted: 1,
+        }
+    }
+
+    /// Permits allowed at `now`
+    pub fn limit(&self, now: Instant) -> usize {
+        let elapsed = now.saturating_duration_since(self.started);
+        let by_time = if elapsed >= self.warmup {
+            self.target
+        } else {
+            let steps = (self.target - 1) as u128 * elapsed.as_nanos() / self.warmup.as_nanos();
+            1 + steps as usize
+        };
+        by_time.min(self.successes + 1)
+    }
+
+    /// Permits handed out so far
+    pub fn granted(&self) -> usize {
+        self.granted
+    }
+
+    /// Whether the target has been granted, ending the warm-up
+    pub fn is_complete(&self) -> bool {
+        self.granted >= self.target
+    }
+
+    /// Count a successful proof toward the next step
+    pub fn record_success(&mut self) {
+        self.successes += 1;
+    }
+
+    /// Grant the permits the limit allows at `now`; returns how many are new
+    pub fn advance(&mut self, now: Instant) -> usize {
+        let limit = self.limit(now);
+        let new = limit.saturating_sub(self.granted);
+        self.granted += new;
+        new
+    }
+
+    /// Ramp toward a new target, e.g. after a configuration reload
+    ///
+    /// Permits granted beyond it are taken back; returns how many.
+    pub fn set_target(&mut self, target: usize) -> usize {
+        self.target = target.max(1);
+        let excess = self.granted.saturating_sub(self.target);
+        self.granted -= excess;
+        excess
+    }
+}
@@ -0,0 +1,164 @@
+use crate::error::ServiceError;
+use crate::types::{ProverRequest, Signal};
+use serde::de::DeserializeOwned;
+use std::fmt;
+
+/// Stands in for a private value in logs and errors
+pub const REDACTED: &str = "<redacted>";
+
+/// Signals carrying verification results, which never appear in logs
+pub const PRIVATE_SIGNALS: [Signal; 3] = [Signal::Recaptcha, Signal::Sms, Signal::Bio];
+
+/// A request formatted for logs, with its private inputs redacted
+///
+/// `Display` is a one-line summary; `Debug` shows every field, with the
+/// verification results and the values of private signals as `<redacted>`.
+pub struct LoggableRequest<'a>(pub &'a ProverRequest);
+
+impl fmt::Display for LoggableRequest<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let request = self.0;
+        let inputs = &request.public_inputs;
+        write!(
+            f,
+            "request {} (verification_results {}, weights {}/{}/{}/{}",
+            request.request_id, REDACTED, inputs.w1, inputs.w2, inputs.w3, inputs.w4
+        )?;
+        if let Some(profile) = &request.weights_profile {
+            write!(f, ", weights_profile {}", profile)?;
+        }
+        if let Some(client_id) = &request.client_id {
+            write!(f, ", client {}", client_id)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl fmt::Debug for LoggableRequest<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let request = self.0;
+        let signals = request.signals.as_ref().map(|signals| {
+            signals
+                .iter()
+                .map(|(name, value)| {
+                    let private = PRIVATE_SIGNALS.iter().any(|s| s.name() == name);
+                    let value = match private {
+                        true => REDACTED.to_string(),
+                        false => value.to_string(),
+                    };
+                    (name.as_str(), value)
+                })
+                .collect::<Vec<_>>()
+        });
+        f.debug_struct("ProverRequest")
+            .field("request_id", &request.request_id)
+            .field("verification_results", &format_args!("{}", REDACTED))
+            .field("public_inputs", &request.public_inputs)
+            .field("weights_profile", &request.weights_profile)
+            .field("signals", &signals)
+            .field("client_id", &request.client_id)
+            .field("tenant_id", &request.tenant_id)
+            .field("binding_id", &request.binding_id)
+            .field("user_id", &request.user_id)
+            .field("labels", &request.labels)
+            .field("weight_class", &request.weight_class)
+            .field("priority", &request.priority)
+            .field("result_topic", &request.result_topic)
+            .field(
+                "callback_url",
+                &request.callback_url.as_deref().map(url_origin),
+            )
+            .field("replay_of", &request.replay_of)
+            .field("trace_id", &request.trace_id)
+            .field("parent_request_id", &request.parent_request_id)
+            .field("submitted_at", &request.submitted_at)
+            .field("not_before", &request.not_before)
+            .field("issued_at", &request.issued_at)
+            .field("valid_until", &request.valid_until)
+            .finish()
+    }
+}
+
+/// Deserialize message data whose errors may be logged
+///
+/// The error names the failing field as a JSON pointer and never quotes the
+/// payload: values are dropped from serde's message, and an error at a private
+/// input says only that the value is invalid.
+pub fn from_json<T: DeserializeOwned>(data: &[u8]) -> Result<T, ServiceError> {
+    let deserializer = &mut serde_json::Deserializer::from_slice(data);
+    serde_path_to_error::deserialize(deserializer).map_err(|e| {
+        let pointer = json_pointer(e.path());
+        let inner = e.inner();
+        let reason = if is_private(&pointer) {
+            "invalid value".to_string()
+        } else {
+            without_values(&inner.to_string())
+        };
+        let at = match pointer.is_empty() {
+            true => String::new(),
+            false => format!(" at {}", pointer),
+        };
+        ServiceError::MalformedRequest(format!(
+            "{}{} (line {} column {})",
+            reason,
+            at,
+            inner.line(),
+            inner.column()
+        ))
+    })
+}
+
+/// Scheme, host and port of `url`; its path and query may carry tokens
+fn url_origin(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .map(|url| url.origin().ascii_serialization())
+        .unwrap_or_else(|_| REDACTED.to_string())
+}
+
+/// The path as a JSON pointer, e.g. `/verification_results/recaptcha_score`
+fn json_pointer(path: &serde_path_to_error::Path) -> String {
+    use serde_path_to_error::Segment;
+
+    path.iter()
+        .filter_map(|segment| match segment {
+            Segment::Seq { index } => Some(index.to_string()),
+            Segment::Map { key } => Some(key.replace('~', "~0").replace('/', "~1")),
+            Segment::Enum { variant } => Some(variant.clone()),
+            Segment::Unknown => None,
+        })
+        .map(|segment| format!("/{}", segment))
+        .collect()
+}
+
+/// Whether the value at `pointer` holds, or is inside, a private input
+fn is_private(pointer: &str) -> bool {
+    let segments: Vec<&str> = pointer.split('/').collect();
+    segments.contains(&"verification_results")
+        || segments.contains(&"verificationResults")
+        || segments
+            .windows(2)
+            .any(|pair| pair[0] == "signals" && PRIVATE_SIGNALS.iter().any(|s| s.name() == pair[1]))
+}
+
+/// serde's message with the offending value and the position removed
+///
+/// `invalid type: string "0.8", expected u32 at line 1 column 9` becomes
+/// `invalid type, expected u32`.
+fn without_values(message: &str) -> String {
+    let message = match message.rsplit_once(" at line ") {
+        Some((message, _)) => message,
+        None => message,
+    };
+    for kind in ["invalid type", "invalid value", "invalid length"] {
+        if let Some(rest) = message
+            .strip_prefix(kind)
+            .and_then(|r| r.strip_prefix(": "))
+        {
+            return match rest.rsplit_once(", expected ") {
+                Some((_, expected)) => format!("{}, expected {}", kind, expected),
+                None => kind.to_string(),
+            };
+        }
+    }
+    message.to_string()
+}
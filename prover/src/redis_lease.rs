@@ -0,0 +1,110 @@
+use crate::error::ServiceError;
+use crate::lease::LeaseBackend;
+use redis::{Client, Connection, Script};
+use std::sync::Mutex;
+use std::time::Duration;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Leases are members of a sorted set scored by their expiry in milliseconds of
+// the Redis server's clock, so worker clock skew cannot stretch a lease.
+const NOW: &str = "local t = redis.call('TIME')
+local now = tonumber(t[1]) * 1000 + math.floor(tonumber(t[2]) / 1000)
+";
+
+const ACQUIRE: &str = "redis.call('ZREMRANGEBYSCORE', KEYS[1], '-inf', now)
+if redis.call('ZSCORE', KEYS[1], ARGV[1]) or redis.call('ZCARD', KEYS[1]) < tonumber(ARGV[2]) then
+  redis.call('ZADD', KEYS[1], now + tonumber(ARGV[3]), ARGV[1])
+  return 1
+end
+return 0
+";
+
+const RENEW: &str = "local expiry = redis.call('ZSCORE', KEYS[1], ARGV[1])
+if expiry and tonumber(expiry) > now then
+  redis.call('ZADD', KEYS[1], now + tonumber(ARGV[2]), ARGV[1])
+  return 1
+end
+return 0
+";
+
+/// Leases in a Redis sorted set, shared by every worker using the same key
+pub struct RedisLeases {
+    client: Client,
+    key: String,
+    acquire: Script,
+    renew: Script,
+    /// Reopened after an error
+    connection: Mutex<Option<Connection>>,
+}
+
+impl RedisLeases {
+    pub fn open(url: &str, key: &str) -> Result<Self, ServiceError> {
+        let client = Client::open(url)
+            .map_err(|e| ServiceError::Config(format!("Invalid GLOBAL_LEASE_URL: {}", e)))?;
+        Ok(Self {
+            client,
+            key: key.to_string(),
+            acquire: Script::new(&format!("{}{}", NOW, ACQUIRE)),
+            renew: Script::new(&format!("{}{}", NOW, RENEW)),
+            connection: Mutex::new(None),
+        })
+    }
+
+    fn with_connection<T>(
+        &self,
+        f: impl FnOnce(&mut Connection) -> redis::RedisResult<T>,
+    ) -> Result<T, ServiceError> {
+        let mut connection = self.connection.lock().unwrap();
+        if connection.is_none() {
+            let opened = self
+                .client
+                .get_connection_with_timeout(CONNECT_TIMEOUT)
+                .map_err(|e| ServiceError::Storage(format!("Redis connection failed: {}", e)))?;
+            *connection = Some(opened);
+        }
+        let result = f(connection.as_mut().expect("connection opened above"));
+        if result.is_err() {
+            *connection = None;
+        }
+        result.map_err(|e| ServiceError::Storage(format!("Redis lease command failed: {}", e)))
+    }
+}
+
+impl LeaseBackend for RedisLeases {
+    fn try_acquire(&self, holder: &str, limit: u32, ttl: Duration) -> Result<bool, ServiceError> {
+        let acquired: i32 = self.with_connection(|connection| {
+            self.acquire
+                .key(&self.key)
+                .arg(holder)
+                .arg(limit)
+                .arg(ttl.as_millis() as u64)
+                .invoke(connection)
+        })?;
+        Ok(acquired == 1)
+    }
+
+    fn renew(&self, holder: &str, ttl: Duration) -> Result<bool, ServiceError> {
+        let renewed: i32 = self.with_connection(|connection| {
+            self.renew
+                .key(&self.key)
+                .arg(holder)
+                .arg(ttl.as_millis() as u64)
+                .invoke(connection)
+        })?;
+        Ok(renewed == 1)
+    }
+
+    fn release(&self, holder: &str) -> Result<(), ServiceError> {
+        self.with_connection(|connection| {
+            redis::cmd("ZREM")
+                .arg(&self.key)
+                .arg(holder)
+                .query::<()>(connection)
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "redis"
+    }
+}
@@ -0,0 +1,156 @@
+use crate::error::ServiceError;
+use crate::prover::{ProofBackend, ProofContext};
+use crate::response_check::{check_response, CheckOptions, Outcome};
+use crate::types::{ProofData, ProofMetrics, ProverRequest, ProverResponse, ResourceUsage};
+use crate::verifier::VerifyingKey;
+use chrono::Utc;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// Fields of `ProofData` that proving the same pinned request must reproduce
+pub const REPRODUCIBLE: [&str; 4] = [
+    "public_inputs",
+    "human_index",
+    "weights_commitment",
+    "verification_key",
+];
+
+/// Proving a request more than once with nothing left to the clock
+///
+/// The host's only source of variation is the validity window, which falls back to
+/// the current time without an `issued_at`; it is pinned to `now` before the first
+/// run. The SDK exposes no randomness seed, so the Groth16 proof bytes of the pico
+/// backend may still differ between runs; everything the proof commits does not.
+pub struct Deterministic<'a> {
+    pub vk: Option<&'a VerifyingKey>,
+    /// Unix time standing in for a missing `issued_at`, and checked against the window
+    pub now: u64,
+    /// Deadline of each run
+    pub timeout: Duration,
+}
+
+/// One proof of the request
+#[derive(Debug, Clone, Serialize)]
+pub struct Run {
+    pub duration_ms: u64,
+    pub proof_data: ProofData,
+    /// No check failed
+    pub passed: bool,
+    /// Outcome of each check `verify-response` runs, in its order
+    pub checks: Vec<(&'static str, Outcome)>,
+}
+
+/// The runs of a request and how they compare
+#[derive(Debug, Clone, Serialize)]
+pub struct Reproduction {
+    pub request_id: String,
+    pub runs: Vec<Run>,
+}
+
+impl Reproduction {
+    /// Names of the `REPRODUCIBLE` fields, and `checks`, that differ between runs;
+    /// empty when the request reproduced
+    pub fn differences(&self) -> Vec<&'static str> {
+        let Some((first, rest)) = self.runs.split_first() else {
+            return Vec::new();
+        };
+        let committed = reproducible(&first.proof_data);
+        let mut differences: Vec<&'static str> = REPRODUCIBLE
+            .into_iter()
+            .enumerate()
+            .filter(|&(i, _)| {
+                rest.iter()
+                    .any(|run| reproducible(&run.proof_data)[i] != committed[i])
+            })
+            .map(|(_, name)| name)
+            .collect();
+        if rest.iter().any(|run| run.checks != first.checks) {
+            differences.push("checks");
+        }
+        differences
+    }
+
+    /// Whether every run produced the same proof bytes too
+    pub fn proofs_identical(&self) -> bool {
+        self.runs
+            .windows(2)
+            .all(|pair| pair[0].proof_data.proof == pair[1].proof_data.proof)
+    }
+}
+
+impl Deterministic<'_> {
+    /// Prove `request` `runs` times and check each proof as an integrator would
+    ///
+    /// Blocking, like `generate_proof`. Fails on the first run that fails to prove.
+    pub fn reproduce<B: ProofBackend + ?Sized>(
+        &self,
+        backend: &B,
+        request: ProverRequest,
+        runs: u32,
+    ) -> Result<Reproduction, ServiceError> {
+        if runs < 2 {
+            return Err(ServiceError::Config(format!(
+                "Invalid run count: {}, expected at least 2",
+                runs
+            )));
+        }
+        let request = request.resolve_validity(self.now);
+        let options = CheckOptions {
+            vk: self.vk,
+            request: Some(&request),
+            now: Some(self.now),
+            #[cfg(feature = "attestation")]
+            attestation: None,
+        };
+
+        let mut reproduction = Reproduction {
+            request_id: request.request_id.clone(),
+            runs: Vec::new(),
+        };
+        for _ in 0..runs {
+            let started = Instant::now();
+            let context = ProofContext::new(started + self.timeout, 0);
+            let proof_data = backend.generate_proof(request.clone(), &context)?;
+            let duration_ms = started.elapsed().as_millis() as u64;
+
+            let completed_at = Utc::now().to_rfc3339();
+            let response = ProverResponse::success(
+                request.request_id.clone(),
+                proof_data,
+                ProofMetrics {
+                    received_at: completed_at.clone(),
+                    started_at: completed_at.clone(),
+                    completed_at,
+                    duration_ms,
+                    attempts: None,
+                    queue_wait_ms: None,
+                    usage: ResourceUsage::default(),
+                    cost_units: None,
+                    progress: None,
+                },
+            );
+            let report = check_response(&response, &options);
+            reproduction.runs.push(Run {
+                duration_ms,
+                proof_data: response.proof_data.expect("success carries proof data"),
+                passed: report.passed,
+                checks: report
+                    .checks
+                    .iter()
+                    .map(|check| (check.name, check.outcome))
+                    .collect(),
+            });
+        }
+        Ok(reproduction)
+    }
+}
+
+/// The `REPRODUCIBLE` fields of `proof_data`, in order
+fn reproducible(proof_data: &ProofData) -> [String; 4] {
+    [
+        proof_data.public_inputs.clone(),
+        proof_data.human_index.to_string(),
+        proof_data.weights_commitment.clone(),
+        proof_data.verification_key.clone(),
+    ]
+}
@@ -0,0 +1,238 @@
+use crate::canonical::canonical_json;
+use crate::decimal::{DecimalInput, DecimalKind};
+use crate::error::ServiceError;
+use crate::quarantine::request_hash;
+use crate::schema::RequestValidator;
+use crate::types::{check_labels, derived_request_id, Priority, ProverRequest, RequestIdFormat};
+use human_index_lib::{HumanIndexPublicInputs, VerificationResults};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Builds a `ProverRequest` from decimal inputs, checked as the service checks it
+///
+/// The score and weights are given as decimals, as floats or as text, and scaled to
+/// fixed-point exactly as the service scales decimals it receives. `build` fails with
+/// the message the service would fail the request with.
+#[derive(Debug, Clone, Default)]
+pub struct ProverRequestBuilder {
+    request_id: Option<String>,
+    request_id_format: RequestIdFormat,
+    recaptcha_score: Option<DecimalInput>,
+    sms_verified: bool,
+    bio_verified: bool,
+    weights: Option<[DecimalInput; 4]>,
+    weights_profile: Option<String>,
+    expected_output: u32,
+    client_id: Option<String>,
+    binding_id: Option<String>,
+    tenant_id: Option<String>,
+    trace_id: Option<String>,
+    priority: Option<Priority>,
+    labels: BTreeMap<String, String>,
+    issued_at: Option<u64>,
+    valid_until: Option<u64>,
+}
+
+/// A request as `ProverRequestBuilder` built it, with what a publisher sends and keys
+#[derive(Debug, Clone)]
+pub struct BuiltRequest {
+    pub request: ProverRequest,
+    /// Canonical JSON of the request, as published
+    pub json: Vec<u8>,
+    /// Hash of the request inputs the service recognizes repeated requests by; see
+    /// `request_hash`
+    pub hash: String,
+}
+
+impl ProverRequestBuilder {
+    /// Request ID to send; a UUID is generated when unset
+    pub fn request_id(mut self, request_id: impl Into<String>) -> Self {
+        self.request_id = Some(request_id.into());
+        self
+    }
+
+    /// `REQUEST_ID_FORMAT` of the service the request is for (default `uuid`)
+    pub fn request_id_format(mut self, format: RequestIdFormat) -> Self {
+        self.request_id_format = format;
+        self
+    }
+
+    /// reCAPTCHA score in 0.0..=1.0, e.g. `0.8` or `"0.8"`
+    pub fn score(mut self, score: impl Into<DecimalInput>) -> Self {
+        self.recaptcha_score = Some(score.into());
+        self
+    }
+
+    pub fn sms_verified(mut self, verified: bool) -> Self {
+        self.sms_verified = verified;
+        self
+    }
+
+    pub fn bio_verified(mut self, verified: bool) -> Self {
+        self.bio_verified = verified;
+        self
+    }
+
+    /// Weights `w1` to `w4` as decimals, e.g. `[0.15, 0.2, 0.25, 0.4]`
+    pub fn weights<D: Into<DecimalInput>>(mut self, weights: [D; 4]) -> Self {
+        self.weights = Some(weights.map(Into::into));
+        self
+    }
+
+    /// Named weight set to prove with instead of `weights`, e.g. `"v1"`
+    pub fn weights_profile(mut self, id: impl Into<String>) -> Self {
+        self.weights_profile = Some(id.into());
+        self
+    }
+
+    /// Human index the publisher expects (default 0); the service proves the index
+    /// it computes either way
+    pub fn expected_output(mut self, expected_output: u32) -> Self {
+        self.expected_output = expected_output;
+        self
+    }
+
+    pub fn client_id(mut self, client_id: impl Into<String>) -> Self {
+        self.client_id = Some(client_id.into());
+        self
+    }
+
+    pub fn binding_id(mut self, binding_id: impl Into<String>) -> Self {
+        self.binding_id = Some(binding_id.into());
+        self
+    }
+
+    pub fn tenant_id(mut self, tenant_id: impl Into<String>) -> Self {
+        self.tenant_id = Some(tenant_id.into());
+        self
+    }
+
+    pub fn trace_id(mut self, trace_id: impl Into<String>) -> Self {
+        self.trace_id = Some(trace_id.into());
+        self
+    }
+
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Cost attribution label, e.g. `("team", "risk")`; may be called repeatedly
+    pub fn label(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.labels.insert(key.into(), value.into());
+        self
+    }
+
+    /// Validity window of the proven index (unix seconds)
+    pub fn validity(mut self, issued_at: u64, valid_until: u64) -> Self {
+        self.issued_at = Some(issued_at);
+        self.valid_until = Some(valid_until);
+        self
+    }
+
+    /// Scale the inputs, fill in a request ID and check the request
+    ///
+    /// Runs the checks of `POST /v1/validate`, the request ID format, the labels and the
+    /// parsing and input resolution the service applies to a received message.
+    pub fn build(self) -> Result<BuiltRequest, ServiceError> {
+        let malformed = ServiceError::MalformedRequest;
+        let recaptcha_score = match &self.recaptcha_score {
+            Some(score) => score
+                .to_fixed("recaptcha_score", DecimalKind::Score)
+                .map_err(malformed)?,
+            None => return Err(malformed("recaptcha_score is not set".to_string())),
+        };
+        let [w1, w2, w3, w4] = match &self.weights {
+            Some(weights) => {
+                let mut fixed = [0; 4];
+                for (i, weight) in weights.iter().enumerate() {
+                    fixed[i] = weight
+                        .to_fixed(&format!("w{}", i + 1), DecimalKind::Weight)
+                        .map_err(malformed)?;
+                }
+                fixed
+            }
+            // Filled in from `weights_profile` by the service
+            None => [0; 4],
+        };
+
+        let request = ProverRequest {
+            request_id: self.request_id.unwrap_or_else(generated_request_id),
+            verification_results: VerificationResults {
+                recaptcha_score,
+                sms_verified: self.sms_verified as u32,
+                bio_verified: self.bio_verified as u32,
+            },
+            public_inputs: HumanIndexPublicInputs {
+                w1,
+                w2,
+                w3,
+                w4,
+                expected_output: self.expected_output,
+            },
+            client_id: self.client_id,
+            weight_class: None,
+            replay_of: None,
+            trace_id: self.trace_id,
+            parent_request_id: None,
+            submitted_at: None,
+            binding_id: self.binding_id,
+            user_id: None,
+            tenant_id: self.tenant_id,
+            labels: (!self.labels.is_empty()).then_some(self.labels),
+            result_topic: None,
+            callback_url: None,
+            priority: self.priority,
+            not_before: None,
+            signals: None,
+            weights_profile: self.weights_profile,
+            issued_at: self.issued_at,
+            valid_until: self.valid_until,
+        };
+
+        let violations = validator().validate(&serde_json::to_value(&request)?);
+        if !violations.is_empty() {
+            let messages: Vec<String> = violations
+                .iter()
+                .map(|violation| format!("{}: {}", violation.path, violation.message))
+                .collect();
+            return Err(malformed(messages.join("; ")));
+        }
+        self.request_id_format
+            .check(&request.request_id)
+            .map_err(malformed)?;
+        if let Some(labels) = &request.labels {
+            check_labels(labels).map_err(malformed)?;
+        }
+        let json = canonical_json(&request)?;
+        ProverRequest::from_json(&json)?.resolve_inputs()?;
+
+        Ok(BuiltRequest {
+            hash: request_hash(&request),
+            request,
+            json,
+        })
+    }
+}
+
+/// The validator `POST /v1/validate` checks requests with, compiled once
+fn validator() -> &'static RequestValidator {
+    static VALIDATOR: OnceLock<RequestValidator> = OnceLock::new();
+    VALIDATOR.get_or_init(RequestValidator::new)
+}
+
+/// A UUID unique to this process and call, for a request built without an ID
+fn generated_request_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos());
+    derived_request_id(&format!(
+        "{}:{}:{}",
+        std::process::id(),
+        nanos,
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ))
+}
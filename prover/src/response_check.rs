@@ -0,0 +1,383 @@
+use crate::evm::{committed_public_values, committed_validity, VerifyCall, ISSUED_AT_OFFSET};
+use crate::prover::ProverProfile;
+use crate::types::{ProofData, ProofStatus, ProverRequest, ProverResponse};
+use crate::verifier::VerifyingKey;
+use human_index_lib::{calculate_human_index, commit_weights, HumanIndexPublicInputs};
+use serde::Serialize;
+#[cfg(feature = "attestation")]
+use {alloy_primitives::Address, alloy_sol_types::Eip712Domain};
+
+/// Names of the checks, in the order they appear in a report
+pub const CHECKS: [&str; 10] = [
+    "status",
+    "proof_data",
+    "groth16",
+    "public_values",
+    "human_index",
+    "weights_commitment",
+    "echoed_weights",
+    "validity",
+    "cleartext_inputs",
+    "attestation",
+];
+
+/// Outcome of one check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Pass,
+    Fail,
+    /// Not checked, e.g. because an optional field or input is absent
+    Skip,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub outcome: Outcome,
+    pub detail: String,
+}
+
+/// Result of checking a response, one entry per name in `CHECKS`
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub request_id: String,
+    /// No check failed; skipped checks do not count against it
+    pub passed: bool,
+    pub checks: Vec<Check>,
+}
+
+impl Report {
+    /// The check named `name`
+    pub fn check(&self, name: &str) -> Option<&Check> {
+        self.checks.iter().find(|check| check.name == name)
+    }
+
+    /// Outcome of the check named `name`
+    pub fn outcome(&self, name: &str) -> Option<Outcome> {
+        self.check(name).map(|check| check.outcome)
+    }
+}
+
+/// Signer and EIP-712 domain an attestation must verify against
+#[cfg(feature = "attestation")]
+#[derive(Debug, Clone)]
+pub struct AttestationKey {
+    pub domain: Eip712Domain,
+    pub signer: Address,
+}
+
+/// What to check a response against; checks needing an absent input are skipped
+#[derive(Default)]
+pub struct CheckOptions<'a> {
+    /// Groth16 verifying key (`vm_vk`) the proof must verify under
+    pub vk: Option<&'a VerifyingKey>,
+    /// The original request, to recompute the human index from its cleartext inputs
+    pub request: Option<&'a ProverRequest>,
+    /// Unix time the validity window is checked at
+    pub now: Option<u64>,
+    #[cfg(feature = "attestation")]
+    pub attestation: Option<AttestationKey>,
+}
+
+/// The committed public values before the validity window
+struct Committed {
+    inputs: HumanIndexPublicInputs,
+    computed_output: u32,
+    weights_commitment: [u8; 32],
+}
+
+/// Check a response the way an integrator would before trusting its human index
+///
+/// Decodes the proof, verifies it as `PicoVerifier.sol` would, decodes the public
+/// values and checks that the reported fields match them. Never fails itself: every
+/// problem is reported as a failed check.
+pub fn check_response(response: &ProverResponse, options: &CheckOptions) -> Report {
+    let mut checks = Vec::with_capacity(CHECKS.len());
+    run_checks(response, options, &mut checks);
+
+    // Checks run in order and stop at a failure that leaves nothing to check;
+    // whatever was not reached is skipped
+    let reason = match checks.iter().any(|check| check.outcome == Outcome::Fail) {
+        true => "an earlier check failed",
+        false => "not applicable",
+    };
+    for name in &CHECKS[checks.len()..] {
+        checks.push(skip(name, reason));
+    }
+
+    Report {
+        request_id: response.request_id.clone(),
+        passed: checks.iter().all(|check| check.outcome != Outcome::Fail),
+        checks,
+    }
+}
+
+fn run_checks(response: &ProverResponse, options: &CheckOptions, checks: &mut Vec<Check>) {
+    let proof_data = match (&response.status, &response.proof_data) {
+        (ProofStatus::Success, Some(proof_data)) => {
+            checks.push(pass("status", "success"));
+            proof_data
+        }
+        (ProofStatus::Success, None) => {
+            return checks.push(fail("status", "success without proof_data"));
+        }
+        (status, _) => {
+            let reason = response.error.as_ref().map_or("", |e| e.message.as_str());
+            return checks.push(fail("status", format!("{:?}: {}", status, reason)));
+        }
+    };
+
+    let fast = proof_data.profile == Some(ProverProfile::Fast);
+    let call = if fast {
+        match proof_data.decompressed_proof() {
+            Ok(proof) => checks.push(pass(
+                "proof_data",
+                format!("{}-byte fast proof{}", proof.len(), encoding(proof_data)),
+            )),
+            Err(e) => return checks.push(fail("proof_data", e.to_string())),
+        }
+        checks.push(skip("groth16", "fast proofs have no Groth16 wrap"));
+        None
+    } else {
+        match VerifyCall::from_proof_data(proof_data) {
+            Ok(call) => {
+                checks.push(pass(
+                    "proof_data",
+                    format!("Groth16 proof{}", encoding(proof_data)),
+                ));
+                Some(call)
+            }
+            Err(e) => return checks.push(fail("proof_data", e.to_string())),
+        }
+    };
+
+    if let Some(call) = &call {
+        checks.push(match options.vk {
+            Some(vk) => match vk.verify_call(call) {
+                Ok(()) => pass("groth16", "pairing check passed"),
+                Err(e) => fail("groth16", e.to_string()),
+            },
+            None => skip("groth16", "no verifying key given"),
+        });
+    }
+
+    let public_values = match committed_public_values(proof_data) {
+        Ok(public_values) => public_values,
+        Err(e) => return checks.push(fail("public_values", e.to_string())),
+    };
+    let committed =
+        match bincode::deserialize::<(HumanIndexPublicInputs, u32, [u8; 32])>(&public_values) {
+            Ok((inputs, computed_output, weights_commitment)) => Committed {
+                inputs,
+                computed_output,
+                weights_commitment,
+            },
+            Err(e) => return checks.push(fail("public_values", e.to_string())),
+        };
+    checks.push(pass(
+        "public_values",
+        format!("{} bytes", public_values.len()),
+    ));
+
+    checks.push(check_human_index(proof_data, &committed));
+    checks.push(check_weights_commitment(proof_data, &committed));
+    checks.push(match &response.weights {
+        Some(echoed) => {
+            let fixed = &echoed.fixed;
+            let echoed = [fixed.w1, fixed.w2, fixed.w3, fixed.w4];
+            let committed = weights(&committed.inputs);
+            match echoed == committed {
+                true => pass("echoed_weights", "match the committed weights"),
+                false => fail(
+                    "echoed_weights",
+                    format!("{:?} differ from the committed {:?}", echoed, committed),
+                ),
+            }
+        }
+        None => skip("echoed_weights", "response has no weights"),
+    });
+
+    // Guests before the validity window committed nothing after the weights commitment
+    checks.push(if public_values.len() == ISSUED_AT_OFFSET {
+        skip("validity", "public values commit no validity window")
+    } else {
+        match committed_validity(&public_values).map(|validity| (validity.check(), validity)) {
+            Err(e) => fail("validity", e.to_string()),
+            Ok((Err(e), _)) => fail("validity", e.to_string()),
+            Ok((Ok(()), validity)) => {
+                let window = format!(
+                    "valid from {} until {}",
+                    validity.issued_at, validity.valid_until
+                );
+                match options.now {
+                    Some(now) if !validity.is_valid_at(now) => {
+                        fail("validity", format!("{}, not at {}", window, now))
+                    }
+                    _ => pass("validity", window),
+                }
+            }
+        }
+    });
+
+    checks.push(match options.request {
+        Some(request) => check_cleartext_inputs(response, request, &committed),
+        None => skip("cleartext_inputs", "no request given"),
+    });
+
+    checks.push(check_attestation(response, proof_data, options));
+}
+
+fn check_human_index(proof_data: &ProofData, committed: &Committed) -> Check {
+    if let Err(e) = human_index_lib::check_human_index(committed.computed_output) {
+        return fail("human_index", e.to_string());
+    }
+    match committed.computed_output == proof_data.human_index {
+        true => pass(
+            "human_index",
+            format!("{} as committed", proof_data.human_index),
+        ),
+        false => fail(
+            "human_index",
+            format!(
+                "reported {}, committed {}",
+                proof_data.human_index, committed.computed_output
+            ),
+        ),
+    }
+}
+
+fn check_weights_commitment(proof_data: &ProofData, committed: &Committed) -> Check {
+    let expected = commit_weights(&committed.inputs);
+    if committed.weights_commitment != expected {
+        return fail(
+            "weights_commitment",
+            "committed commitment does not match the committed weights",
+        );
+    }
+    let committed_hex = format!("0x{}", hex::encode(committed.weights_commitment));
+    match proof_data.weights_commitment.as_str() {
+        "" => pass("weights_commitment", committed_hex),
+        reported if reported.eq_ignore_ascii_case(&committed_hex) => {
+            pass("weights_commitment", committed_hex)
+        }
+        reported => fail(
+            "weights_commitment",
+            format!("reported {}, committed {}", reported, committed_hex),
+        ),
+    }
+}
+
+fn check_cleartext_inputs(
+    response: &ProverResponse,
+    request: &ProverRequest,
+    committed: &Committed,
+) -> Check {
+    if request.request_id != response.request_id {
+        return fail(
+            "cleartext_inputs",
+            format!(
+                "request is {}, response is for {}",
+                request.request_id, response.request_id
+            ),
+        );
+    }
+    if weights(&request.public_inputs) != weights(&committed.inputs) {
+        return fail(
+            "cleartext_inputs",
+            "request weights differ from the committed weights",
+        );
+    }
+    let recomputed = calculate_human_index(&request.verification_results, &committed.inputs);
+    match recomputed == committed.computed_output {
+        true => pass(
+            "cleartext_inputs",
+            format!("recomputed human index {}", recomputed),
+        ),
+        false => fail(
+            "cleartext_inputs",
+            format!(
+                "recomputed human index {}, committed {}",
+                recomputed, committed.computed_output
+            ),
+        ),
+    }
+}
+
+#[cfg(feature = "attestation")]
+fn check_attestation(
+    response: &ProverResponse,
+    proof_data: &ProofData,
+    options: &CheckOptions,
+) -> Check {
+    use crate::eip712::{verify_attestation, HumanIndexAttestation};
+
+    let Some(signature) = &proof_data.attestation_signature else {
+        return skip("attestation", "response has no attestation_signature");
+    };
+    let Some(key) = &options.attestation else {
+        return skip("attestation", "no signer given");
+    };
+    let Some(expiry) = proof_data.attestation_expiry else {
+        return fail("attestation", "attestation_expiry is missing");
+    };
+    let Some(binding_id) = &response.binding_id else {
+        return fail("attestation", "binding_id is missing");
+    };
+    let attestation = match HumanIndexAttestation::for_proof(binding_id, proof_data, expiry) {
+        Ok(attestation) => attestation,
+        Err(e) => return fail("attestation", e.to_string()),
+    };
+    match verify_attestation(&attestation, &key.domain, signature, key.signer) {
+        Ok(true) => pass("attestation", format!("signed by {}", key.signer)),
+        Ok(false) => fail("attestation", format!("not signed by {}", key.signer)),
+        Err(e) => fail("attestation", e.to_string()),
+    }
+}
+
+#[cfg(not(feature = "attestation"))]
+fn check_attestation(
+    _response: &ProverResponse,
+    proof_data: &ProofData,
+    _options: &CheckOptions,
+) -> Check {
+    match proof_data.attestation_signature {
+        Some(_) => skip("attestation", "built without the `attestation` feature"),
+        None => skip("attestation", "response has no attestation_signature"),
+    }
+}
+
+fn weights(inputs: &HumanIndexPublicInputs) -> [u32; 4] {
+    [inputs.w1, inputs.w2, inputs.w3, inputs.w4]
+}
+
+fn encoding(proof_data: &ProofData) -> String {
+    match proof_data.content_encoding {
+        Some(encoding) => format!(", {}-compressed", encoding),
+        None => String::new(),
+    }
+}
+
+fn pass(name: &'static str, detail: impl Into<String>) -> Check {
+    Check {
+        name,
+        outcome: Outcome::Pass,
+        detail: detail.into(),
+    }
+}
+
+fn fail(name: &'static str, detail: impl Into<String>) -> Check {
+    Check {
+        name,
+        outcome: Outcome::Fail,
+        detail: detail.into(),
+    }
+}
+
+fn skip(name: &'static str, detail: impl Into<String>) -> Check {
+    Check {
+        name,
+        outcome: Outcome::Skip,
+        detail: detail.into(),
+    }
+}
@@ -0,0 +1,428 @@
+use crate::types::Priority;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tokio::sync::{oneshot, Notify, OwnedSemaphorePermit, Semaphore};
+use tokio::task::AbortHandle;
+use tracing::error;
+
+/// Hands out proof permits from the shared semaphore to waiting messages
+///
+/// Each time permits free up, they go to the most urgent lane with waiters, and
+/// within it to the tenant that holds the fewest, ties broken by arrival. A tenant
+/// with a backlog therefore cannot keep another tenant's request waiting for more
+/// than a fair share of completions. With a single tenant and lane this is first
+/// come, first served.
+///
+/// `Lanes` can hold permits back for interactive requests and promote batch
+/// requests that have waited too long.
+///
+/// Arrival is when a message took its `Ticket`, not when it started waiting, so
+/// messages received concurrently are still admitted in the order they came in.
+pub struct Scheduler {
+    semaphore: Arc<Semaphore>,
+    state: Mutex<State>,
+    wake: Notify,
+    next_seq: AtomicU64,
+    long_waits: AtomicU64,
+}
+
+/// Lane settings of a `Scheduler`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lanes {
+    /// Permits in the semaphore; no request takes more
+    pub capacity: u32,
+    /// Permits only interactive requests may use
+    pub interactive_reserved: u32,
+    /// Batch requests that waited this long are admitted as normal ones
+    pub batch_promotion: Option<Duration>,
+    /// Waits longer than this are counted and flagged on the permit
+    pub queue_wait_warning: Option<Duration>,
+}
+
+impl Lanes {
+    /// Permits the normal and batch lanes may hold together
+    fn shared_capacity(&self) -> u32 {
+        self.capacity
+            .saturating_sub(self.interactive_reserved)
+            .max(1)
+    }
+}
+
+/// Waiting messages per lane, as reported by the status endpoint
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct LaneDepths {
+    pub interactive: usize,
+    pub normal: usize,
+    pub batch: usize,
+}
+
+struct State {
+    /// Waiting messages, in the order they started waiting
+    waiters: Vec<Waiter>,
+    /// Permits currently held per tenant
+    held: HashMap<String, u32>,
+    /// Permits currently held by normal and batch requests
+    shared_held: u32,
+    lanes: Lanes,
+}
+
+/// A message's place in the queue, taken when it is received
+#[derive(Debug, Clone, Copy)]
+pub struct Ticket {
+    seq: u64,
+    received_at: Instant,
+}
+
+struct Waiter {
+    ticket: Ticket,
+    tenant: String,
+    priority: Priority,
+    cost: u32,
+    grant: oneshot::Sender<TenantPermit>,
+}
+
+impl Waiter {
+    /// Lane the waiter competes in, after any promotion
+    fn lane(&self, lanes: &Lanes, now: Instant) -> Priority {
+        match (self.priority, lanes.batch_promotion) {
+            (Priority::Batch, Some(after))
+                if now.duration_since(self.ticket.received_at) >= after =>
+            {
+                Priority::Normal
+            }
+            (priority, _) => priority,
+        }
+    }
+}
+
+/// Permits held by one message, returned when dropped
+pub struct TenantPermit {
+    scheduler: Arc<Scheduler>,
+    tenant: String,
+    cost: u32,
+    shared: bool,
+    queue_wait: Duration,
+    long_wait: bool,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl TenantPermit {
+    /// Time from the message's `Ticket` to the grant
+    pub fn queue_wait(&self) -> Duration {
+        self.queue_wait
+    }
+
+    /// Whether the wait exceeded `Lanes::queue_wait_warning`
+    pub fn long_wait(&self) -> bool {
+        self.long_wait
+    }
+}
+
+impl Scheduler {
+    /// Start granting permits from `semaphore`
+    pub fn start(semaphore: Arc<Semaphore>, lanes: Lanes) -> Arc<Self> {
+        let scheduler = Arc::new(Self {
+            semaphore,
+            state: Mutex::new(State {
+                waiters: Vec::new(),
+                held: HashMap::new(),
+                shared_held: 0,
+                lanes,
+            }),
+            wake: Notify::new(),
+            next_seq: AtomicU64::new(0),
+            long_waits: AtomicU64::new(0),
+        });
+        tokio::spawn(scheduler.clone().dispatch());
+        scheduler
+    }
+
+    /// Take a place in the queue for a message received now
+    pub fn ticket(&self) -> Ticket {
+        Ticket {
+            seq: self.next_seq.fetch_add(1, Ordering::Relaxed),
+            received_at: Instant::now(),
+        }
+    }
+
+    /// Wait for `cost` permits on behalf of `tenant`, queued from now
+    ///
+    /// Returns `None` if the semaphore was closed. Dropping the future gives up
+    /// the place in the queue.
+    pub async fn acquire(
+        &self,
+        tenant: &str,
+        priority: Priority,
+        cost: u32,
+    ) -> Option<TenantPermit> {
+        self.acquire_in_turn(self.ticket(), tenant, priority, cost)
+            .await
+    }
+
+    /// `acquire`, queued from when `ticket` was taken
+    pub async fn acquire_in_turn(
+        &self,
+        ticket: Ticket,
+        tenant: &str,
+        priority: Priority,
+        cost: u32,
+    ) -> Option<TenantPermit> {
+        if self.semaphore.is_closed() {
+            return None;
+        }
+        let (grant, granted) = oneshot::channel();
+        self.lock().waiters.push(Waiter {
+            ticket,
+            tenant: tenant.to_string(),
+            priority,
+            cost,
+            grant,
+        });
+        self.wake.notify_one();
+        granted.await.ok()
+    }
+
+    /// Grants whose wait exceeded `Lanes::queue_wait_warning` since the start
+    pub fn long_waits(&self) -> u64 {
+        self.long_waits.load(Ordering::Relaxed)
+    }
+
+    /// Apply new lane settings, e.g. after a configuration reload
+    pub fn set_lanes(&self, lanes: Lanes) {
+        self.lock().lanes = lanes;
+        self.wake.notify_one();
+    }
+
+    /// Number of messages waiting in each lane, after promotion
+    pub fn depths(&self) -> LaneDepths {
+        let state = self.lock();
+        let now = Instant::now();
+        let mut depths = LaneDepths::default();
+        for waiter in state
+            .waiters
+            .iter()
+            .filter(|waiter| !waiter.grant.is_closed())
+        {
+            match waiter.lane(&state.lanes, now) {
+                Priority::Interactive => depths.interactive += 1,
+                Priority::Normal => depths.normal += 1,
+                Priority::Batch => depths.batch += 1,
+            }
+        }
+        depths
+    }
+
+    async fn dispatch(self: Arc<Self>) {
+        loop {
+            while self.lock().waiters.is_empty() {
+                self.wake.notified().await;
+            }
+
+            // Choose the recipient only once capacity is free, so messages that
+            // arrived in the meantime are considered
+            let mut permit = match self.semaphore.clone().acquire_owned().await {
+                Ok(permit) => permit,
+                Err(e) => return self.stop(e),
+            };
+            let Some((waiter, cost, shared)) = self.next_waiter() else {
+                // Nobody may use the free capacity yet; wait for an arrival or a release
+                drop(permit);
+                self.wake.notified().await;
+                continue;
+            };
+            if cost > 1 {
+                match self.semaphore.clone().acquire_many_owned(cost - 1).await {
+                    Ok(rest) => permit.merge(rest),
+                    Err(e) => return self.stop(e),
+                }
+            }
+
+            let queue_wait = waiter.ticket.received_at.elapsed();
+            let long_wait = {
+                let mut state = self.lock();
+                *state.held.entry(waiter.tenant.clone()).or_default() += cost;
+                if shared {
+                    state.shared_held += cost;
+                }
+                state
+                    .lanes
+                    .queue_wait_warning
+                    .is_some_and(|warning| queue_wait > warning)
+            };
+            if long_wait {
+                self.long_waits.fetch_add(1, Ordering::Relaxed);
+            }
+            // A waiter that gave up in the meantime returns the permits on drop
+            let _ = waiter.grant.send(TenantPermit {
+                scheduler: self.clone(),
+                tenant: waiter.tenant,
+                cost,
+                shared,
+                queue_wait,
+                long_wait,
+                _permit: permit,
+            });
+        }
+    }
+
+    /// Take the next waiter to admit, with the permits it gets and whether they
+    /// count against the shared lanes
+    ///
+    /// Picks the most urgent lane, then the tenant in it holding the fewest
+    /// permits. Returns `None` when that waiter would eat into the interactive
+    /// reservation; less urgent lanes would too.
+    fn next_waiter(&self) -> Option<(Waiter, u32, bool)> {
+        let mut state = self.lock();
+        let State {
+            waiters,
+            held,
+            shared_held,
+            lanes,
+            ..
+        } = &mut *state;
+
+        // Forget waiters that gave up, e.g. at shutdown
+        waiters.retain(|waiter| !waiter.grant.is_closed());
+
+        let now = Instant::now();
+        let lane = waiters.iter().map(|waiter| waiter.lane(lanes, now)).min()?;
+        let (index, waiter) = waiters
+            .iter()
+            .enumerate()
+            .filter(|(_, waiter)| waiter.lane(lanes, now) == lane)
+            .min_by_key(|(_, waiter)| {
+                (
+                    held.get(&waiter.tenant).copied().unwrap_or(0),
+                    waiter.ticket.seq,
+                )
+            })?;
+
+        let shared = lane != Priority::Interactive;
+        let cost = if shared {
+            // Capped so an oversized request can still run, alone
+            let cost = waiter.cost.min(lanes.shared_capacity());
+            if *shared_held + cost > lanes.shared_capacity() {
+                return None;
+            }
+            cost
+        } else {
+            waiter.cost.min(lanes.capacity.max(1))
+        };
+        Some((waiters.remove(index), cost, shared))
+    }
+
+    fn stop(&self, e: tokio::sync::AcquireError) {
+        error!("Semaphore closed, cannot acquire permit: {}", e);
+        // Dropping the senders fails every pending `acquire`
+        self.lock().waiters.clear();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect("scheduler lock poisoned")
+    }
+}
+
+impl Drop for TenantPermit {
+    fn drop(&mut self) {
+        {
+            let mut state = self.scheduler.lock();
+            if let Some(held) = state.held.get_mut(&self.tenant) {
+                *held -= self.cost;
+                if *held == 0 {
+                    state.held.remove(&self.tenant);
+                }
+            }
+            if self.shared {
+                state.shared_held -= self.cost;
+            }
+        }
+        // Capacity held back for the reservation may now be usable
+        self.scheduler.wake.notify_one();
+    }
+}
+
+/// Resizes the semaphore a `Scheduler` grants from
+///
+/// Permits held by running proofs cannot be taken back, so a shrink is a debt
+/// paid as they are released. A grow pays off any unpaid debt first, so a grow
+/// during a pending shrink leaves exactly the configured number of permits.
+pub struct Capacity {
+    semaphore: Arc<Semaphore>,
+    shrink: Arc<Mutex<Shrink>>,
+}
+
+#[derive(Default)]
+struct Shrink {
+    /// Permits still to be retired
+    debt: u32,
+    /// Bumped whenever the debt changes, so an outdated retirement gives its
+    /// permits back
+    generation: u64,
+    task: Option<AbortHandle>,
+}
+
+impl Capacity {
+    pub fn new(semaphore: Arc<Semaphore>) -> Self {
+        Self {
+            semaphore,
+            shrink: Arc::default(),
+        }
+    }
+
+    /// Add `permits`, first cancelling as much of a pending shrink
+    pub fn grow(&self, permits: usize) {
+        let mut shrink = self.lock();
+        let cancelled = (shrink.debt as usize).min(permits);
+        let debt = shrink.debt - cancelled as u32;
+        self.retire(&mut shrink, debt);
+        self.semaphore.add_permits(permits - cancelled);
+    }
+
+    /// Retire `permits` as running proofs release them
+    pub fn shrink(&self, permits: usize) {
+        let mut shrink = self.lock();
+        let debt = shrink.debt + permits as u32;
+        self.retire(&mut shrink, debt);
+    }
+
+    /// Permits a shrink is still waiting to retire
+    pub fn pending_shrink(&self) -> usize {
+        self.lock().debt as usize
+    }
+
+    /// Replace any pending retirement with one of `debt` permits
+    fn retire(&self, shrink: &mut Shrink, debt: u32) {
+        // Permits the old retirement was assigned go back when it is dropped
+        if let Some(task) = shrink.task.take() {
+            task.abort();
+        }
+        shrink.generation += 1;
+        shrink.debt = debt;
+        if debt == 0 {
+            return;
+        }
+        let generation = shrink.generation;
+        let semaphore = self.semaphore.clone();
+        let state = self.shrink.clone();
+        let task = tokio::spawn(async move {
+            let permits = match semaphore.acquire_many_owned(debt).await {
+                Ok(permits) => permits,
+                Err(e) => return error!("Failed to shrink proof concurrency: {}", e),
+            };
+            let mut shrink = state.lock().expect("capacity lock poisoned");
+            if shrink.generation == generation {
+                permits.forget();
+                shrink.debt = 0;
+                shrink.task = None;
+            }
+        });
+        shrink.task = Some(task.abort_handle());
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Shrink> {
+        self.shrink.lock().expect("capacity lock poisoned")
+    }
+}
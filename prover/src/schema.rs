@@ -0,0 +1,237 @@
+use crate::decimal::DECIMAL_FIELDS;
+use crate::types::{ProverRequest, ProverResponse, Signal};
+use chrono::DateTime;
+use human_index_lib::{HumanIndexPublicInputs, VerificationResults, MAX_HUMAN_INDEX, SCALE};
+use jsonschema::error::ValidationErrorKind;
+use jsonschema::Validator;
+use schemars::{schema_for, Schema};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Schemas of the message types, keyed by the file name the `schema` binary writes
+///
+/// `ProverResponse` is described at the current `RESPONSE_SCHEMA_VERSION`.
+pub fn schemas() -> Vec<(&'static str, Schema)> {
+    vec![
+        ("prover_request.schema.json", schema_for!(ProverRequest)),
+        ("prover_response.schema.json", schema_for!(ProverResponse)),
+        (
+            "human_index_public_inputs.schema.json",
+            schema_for!(HumanIndexPublicInputs),
+        ),
+        (
+            "verification_results.schema.json",
+            schema_for!(VerificationResults),
+        ),
+    ]
+}
+
+/// Which check a violation comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ViolationSource {
+    /// The JSON Schema of `ProverRequest`
+    Schema,
+    /// A check the schema cannot express, such as a range or the weight sum
+    Semantic,
+}
+
+/// A problem with a candidate request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Violation {
+    /// JSON pointer to the offending value; empty for the whole document
+    pub path: String,
+    pub source: ViolationSource,
+    pub message: String,
+}
+
+impl Violation {
+    /// The body is not JSON at all
+    pub fn invalid_json(error: &serde_json::Error) -> Self {
+        Self {
+            path: String::new(),
+            source: ViolationSource::Schema,
+            message: format!("invalid JSON: {}", error),
+        }
+    }
+
+    /// A problem the schema cannot express, at `path`
+    pub fn semantic(path: &str, message: impl Into<String>) -> Self {
+        Self {
+            path: path.to_string(),
+            source: ViolationSource::Semantic,
+            message: message.into(),
+        }
+    }
+}
+
+/// Checks candidate requests against the `ProverRequest` schema and the semantic rules
+pub struct RequestValidator {
+    validator: Validator,
+}
+
+impl RequestValidator {
+    pub fn new() -> Self {
+        let schema = schema_for!(ProverRequest);
+        let validator =
+            jsonschema::validator_for(schema.as_value()).expect("ProverRequest schema compiles");
+        Self { validator }
+    }
+
+    /// Violations of `request`, empty when the service would accept it
+    ///
+    /// Semantic checks only run once the request matches the schema.
+    pub fn validate(&self, request: &Value) -> Vec<Violation> {
+        let violations: Vec<Violation> = self
+            .validator
+            .iter_errors(request)
+            .map(|error| {
+                let mut path = error.instance_path.as_str().to_string();
+                // Report a missing field at its own path rather than its parent's
+                if let ValidationErrorKind::Required { property } = &error.kind {
+                    if let Some(property) = property.as_str() {
+                        path = format!("{}/{}", path, property);
+                    }
+                }
+                Violation {
+                    path,
+                    source: ViolationSource::Schema,
+                    message: error.to_string(),
+                }
+            })
+            .collect();
+        if !violations.is_empty() {
+            return violations;
+        }
+
+        // Checked here too, so a bad decimal is reported at its own path
+        let decimals: Vec<Violation> = DECIMAL_FIELDS
+            .iter()
+            .filter_map(|&(path, kind)| {
+                let value = request.pointer(path)?;
+                let decimal = value.as_f64().filter(|_| value.is_f64())?;
+                let e = kind.to_fixed(decimal).err()?;
+                Some(Violation::semantic(path, e.to_string()))
+            })
+            .collect();
+        if !decimals.is_empty() {
+            return decimals;
+        }
+
+        let request = match serde_json::from_value::<ProverRequest>(request.clone()) {
+            Ok(request) => request,
+            Err(e) => return vec![Violation::semantic("", e.to_string())],
+        };
+        let unknown: Vec<Violation> = request
+            .signals
+            .iter()
+            .flatten()
+            .filter(|(name, _)| Signal::from_name(name).is_none())
+            .map(|(name, _)| {
+                Violation::semantic(
+                    &format!("/signals/{}", name),
+                    format!("unknown signal, expected one of {}", Signal::names()),
+                )
+            })
+            .collect();
+        if !unknown.is_empty() {
+            return unknown;
+        }
+        let signals = request.signals.clone().unwrap_or_default();
+        let request = match request.resolve_signals() {
+            Ok(request) => request,
+            Err(e) => return vec![Violation::semantic("/signals", e.to_string())],
+        };
+        // Without a profile, missing weights are reported as summing to zero below
+        if request.weights_profile.is_none() {
+            return semantic_violations(&request, &signals);
+        }
+        match request.resolve_weights_profile() {
+            Ok(request) => semantic_violations(&request, &signals),
+            Err(e) => vec![Violation::semantic("/weights_profile", e.to_string())],
+        }
+    }
+}
+
+impl Default for RequestValidator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `signals` are those the request named; a value from there is reported at its key
+fn semantic_violations(request: &ProverRequest, signals: &BTreeMap<String, u32>) -> Vec<Violation> {
+    let at = |signal: Signal, path: &str| {
+        if signals.contains_key(signal.name()) {
+            format!("/signals/{}", signal.name())
+        } else {
+            path.to_string()
+        }
+    };
+    let mut violations = Vec::new();
+    if request.request_id.is_empty() {
+        violations.push(Violation::semantic("/request_id", "must not be empty"));
+    }
+
+    let results = &request.verification_results;
+    if results.recaptcha_score > SCALE {
+        violations.push(Violation::semantic(
+            &at(Signal::Recaptcha, "/verification_results/recaptcha_score"),
+            format!("{} is above SCALE ({})", results.recaptcha_score, SCALE),
+        ));
+    }
+    for (signal, field, value) in [
+        (Signal::Sms, "sms_verified", results.sms_verified),
+        (Signal::Bio, "bio_verified", results.bio_verified),
+    ] {
+        if value > 1 {
+            violations.push(Violation::semantic(
+                &at(signal, &format!("/verification_results/{}", field)),
+                format!("{} is not 0 or 1", value),
+            ));
+        }
+    }
+
+    // Weights may sum to anything but zero, which gives an index of 0 whatever the results
+    let inputs = &request.public_inputs;
+    let weight_sum = [inputs.w1, inputs.w2, inputs.w3, inputs.w4]
+        .iter()
+        .map(|&w| w as u64)
+        .sum::<u64>();
+    if weight_sum == 0 {
+        violations.push(Violation::semantic("/public_inputs", "weights sum to zero"));
+    }
+    if inputs.expected_output > MAX_HUMAN_INDEX {
+        violations.push(Violation::semantic(
+            &at(Signal::ExpectedOutput, "/public_inputs/expected_output"),
+            format!(
+                "{} is above the maximum index ({})",
+                inputs.expected_output, MAX_HUMAN_INDEX
+            ),
+        ));
+    }
+
+    for (field, value) in [
+        ("submitted_at", &request.submitted_at),
+        ("not_before", &request.not_before),
+    ] {
+        if let Some(Err(e)) = value.as_deref().map(DateTime::parse_from_rfc3339) {
+            violations.push(Violation::semantic(
+                &format!("/{}", field),
+                format!("not an RFC 3339 timestamp: {}", e),
+            ));
+        }
+    }
+
+    // A longer window is clamped, but an empty one cannot be proven
+    if let (Some(issued_at), Some(valid_until)) = (request.issued_at, request.valid_until) {
+        if valid_until <= issued_at {
+            violations.push(Violation::semantic(
+                "/valid_until",
+                format!("{} is not after issued_at ({})", valid_until, issued_at),
+            ));
+        }
+    }
+    violations
+}
@@ -1,232 +1,1450 @@
-use crate::config::Config;
+use crate::audit::{AuditConfig, AuditLog};
+use crate::bulk::{self, completion_message};
+use crate::compression::ContentEncoding;
+use crate::config::{Config, ConfigChanges, ProverBackendKind, Role};
+use crate::decimal::EchoedWeights;
+use crate::delayed::{DelayedItem, DelayedQueue, JournaledResult};
+#[cfg(feature = "attestation")]
+use crate::eip712::{AttestationConfig, Attestor};
+use crate::endpoints::{Endpoints, Failover, PubsubEndpoint};
 use crate::error::ServiceError;
-use crate::prover::{CachedElf, ProofGenerator};
-use crate::types::{ProofError, ProofMetrics, ProverRequest, ProverResponse};
-use chrono::Utc;
+use crate::history::ResponseHistory;
+use crate::janitor::{Category, Janitor, Sweep};
+use crate::lease::{GlobalAdmission, Lease, LeaseConfig};
+use crate::memory::wait_for_memory;
+use crate::metrics_log::{next_snapshot_at, MetricsLog, METRICS_DIR};
+use crate::object_store::{GcsObjects, ObjectStore};
+#[cfg(feature = "onchain")]
+use crate::onchain::{OnchainConfig, OnchainSubmitter};
+use crate::prometheus::{self, InFlight};
+use crate::provenance::Provenance;
+use crate::prover::{await_proof, ProofBackend, ProofContext};
+use crate::quarantine::{request_hash, Admission, Quarantine};
+use crate::ramp::ConcurrencyRamp;
+use crate::redact;
+use crate::scheduler::{Capacity, Lanes, Scheduler, TenantPermit, Ticket};
+use crate::session::{SessionReport, SessionStats};
+use crate::telemetry::{Telemetry, TelemetryConfig};
+use crate::types::{
+    check_labels, BulkJob, Priority, ProofError, ProofMetrics, ProofStatus, ProverRequest,
+    ProverResponse, WorkItem, LABEL_ATTRIBUTE_PREFIX,
+};
+use crate::webhook::{check_callback_url, CallbackPolicy, WebhookConfig, Webhooks};
+use chrono::{DateTime, Utc};
 use google_cloud_googleapis::pubsub::v1::PubsubMessage;
 use google_cloud_pubsub::client::{Client, ClientConfig};
+use google_cloud_pubsub::publisher::Publisher;
+use google_cloud_pubsub::subscriber::ReceivedMessage;
 use google_cloud_pubsub::subscription::Subscription;
-use std::path::PathBuf;
-use std::sync::Arc;
-use std::time::Duration;
+use human_index_lib::{calculate_human_index, WeightsProfile};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::future::Future;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::Semaphore;
-use tokio::time::timeout;
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, error, info, warn};
+use tokio_util::task::TaskTracker;
+use tracing::{debug, error, info, warn, Instrument};
 
 /// Prover service that subscribes to Pub/Sub and processes proof requests
-pub struct ProverService {
-    config: Config,
-    cached_elf: Arc<CachedElf>,
-    client: Client,
-    subscription: Subscription,
-    result_topic_path: String,
-    semaphore: Arc<Semaphore>,
+pub struct ProverService<B: ProofBackend> {
+    config: Arc<RwLock<Config>>,
+    backend: Arc<B>,
+    /// Endpoints requests are received from, and the active one
+    endpoints: Arc<Endpoints>,
+    /// Subscription of each endpoint
+    subscriptions: Vec<Subscription>,
+    /// Result publishers of each endpoint, long-lived so results sharing an
+    /// ordering key are published in order
+    publishers: Arc<Vec<ResultPublishers>>,
+    publish_sequence: Arc<PublishSequence>,
+    /// Work topic a frontend forwards accepted requests to
+    work: Option<Publisher>,
+    /// IDs of messages a frontend accepted, to drop their redeliveries
+    recent: Mutex<RecentMessages>,
+    capacity: Capacity,
+    scheduler: Arc<Scheduler>,
+    /// Startup warm-up of the proof permits, with `CONCURRENCY_RAMP_SECS`
+    ramp: Mutex<Option<ConcurrencyRamp>>,
+    audit: Option<Arc<AuditLog>>,
+    /// Sampled, private-input-free stats, with `TELEMETRY_TOPIC`
+    telemetry: Option<Arc<Telemetry>>,
+    /// Delivers results to the `callback_url` of their requests, with
+    /// `CALLBACK_SIGNING_KEY`
+    webhooks: Option<Arc<Webhooks>>,
+    quarantine: Option<Arc<Quarantine>>,
+    provenance: Arc<Provenance>,
+    delayed: Arc<DelayedQueue>,
+    janitor: Arc<Janitor>,
+    /// Cluster-wide cap on proofs in flight, with `GLOBAL_MAX_CONCURRENT`
+    global: Option<Arc<GlobalAdmission>>,
+    /// Messages being handled, waited on by `drain`
+    in_flight: TaskTracker,
+    /// Counts for the shutdown report
+    session: Arc<SessionStats>,
+    /// Last responses, for `GET /recent`
+    history: Arc<ResponseHistory>,
+    /// Counts snapshotted to `<output_dir>/metrics`, with `METRICS_SNAPSHOT_MINS`
+    metrics_log: Option<Arc<MetricsLog>>,
+    /// Cancelled when the shutdown grace period runs out
+    interrupt: CancellationToken,
+    #[cfg(feature = "attestation")]
+    attestor: Option<Arc<Attestor>>,
+    #[cfg(feature = "onchain")]
+    onchain: Option<Arc<OnchainSubmitter>>,
 }
 
-impl ProverService {
+impl<B: ProofBackend> ProverService<B> {
     /// Create a new prover service
-    pub async fn new(config: Config, cached_elf: Arc<CachedElf>) -> Result<Self, ServiceError> {
-        info!("Initializing Google Cloud Pub/Sub client");
+    pub async fn new(
+        config: Config,
+        backend: B,
+        provenance: Provenance,
+    ) -> Result<Self, ServiceError> {
+        let client = pubsub_client().await?;
+
+        // Subscriptions and topics with full paths (required for emulator), in the
+        // project of each endpoint
+        let endpoints = config.endpoints();
+        let subscriptions: Vec<Subscription> = endpoints
+            .iter()
+            .map(|endpoint| client.subscription(&endpoint.subscription_path()))
+            .collect();
+        let publishers = Arc::new(
+            endpoints
+                .iter()
+                .map(|endpoint| {
+                    let new_publisher = |topic: &str| {
+                        client
+                            .topic(&endpoint.topic_path(topic))
+                            .new_publisher(None)
+                    };
+                    ResultPublishers {
+                        global: new_publisher(&endpoint.result_topic),
+                        allowed: config
+                            .result_topic_allowlist
+                            .iter()
+                            .map(|topic| (topic.clone(), new_publisher(topic)))
+                            .collect(),
+                    }
+                })
+                .collect::<Vec<_>>(),
+        );
 
-        // Check if using emulator (skip auth in emulator mode)
-        let is_emulator = std::env::var("PUBSUB_EMULATOR_HOST").is_ok();
-        if is_emulator {
-            info!("Emulator mode detected, skipping authentication");
+        // Start on the first healthy endpoint; a single one is used as it is
+        let mut active = 0;
+        if endpoints.len() > 1 {
+            let mut healthy = None;
+            for (index, (endpoint, subscription)) in
+                endpoints.iter().zip(&subscriptions).enumerate()
+            {
+                if is_healthy(endpoint, subscription).await {
+                    healthy = Some(index);
+                    break;
+                }
+            }
+            active = healthy.unwrap_or_else(|| {
+                warn!("No Pub/Sub endpoint is healthy, starting on the primary");
+                0
+            });
         }
+        let endpoints = Arc::new(Endpoints::new(endpoints, active));
 
-        // Create Pub/Sub client (skip auth for emulator)
-        let client_config = if is_emulator {
-            ClientConfig::default()
-        } else {
-            ClientConfig::default()
-                .with_auth()
-                .await
-                .map_err(|e| ServiceError::PubSub(format!("Failed to setup auth: {}", e)))?
+        let new_publisher = |topic: &str| {
+            let topic_path = format!("projects/{}/topics/{}", config.gcp_project_id, topic);
+            client.topic(&topic_path).new_publisher(None)
+        };
+        let work = match (config.role, &config.work_topic) {
+            (Role::Frontend, Some(work_topic)) => Some(new_publisher(work_topic)),
+            _ => None,
         };
 
-        let client = Client::new(client_config)
-            .await
-            .map_err(|e| ServiceError::PubSub(format!("Failed to create Pub/Sub client: {}", e)))?;
+        // Create semaphore for concurrency control, holding back permits for the warm-up
+        let ramp =
+            (config.concurrency_ramp_secs > 0 && config.max_concurrent_proofs > 1).then(|| {
+                ConcurrencyRamp::new(
+                    config.max_concurrent_proofs,
+                    Duration::from_secs(config.concurrency_ramp_secs),
+                    Instant::now(),
+                )
+            });
+        let permits = ramp
+            .as_ref()
+            .map_or(config.max_concurrent_proofs, ConcurrencyRamp::granted);
+        let semaphore = Arc::new(Semaphore::new(permits));
+        let scheduler = Scheduler::start(semaphore.clone(), ramped_lanes(&config, ramp.as_ref()));
 
-        // Get subscription with full path (required for emulator)
-        let subscription_path = format!(
-            "projects/{}/subscriptions/{}",
-            config.gcp_project_id, config.prover_subscription
+        info!(
+            "Prover service initialized with max_concurrent_proofs={}, backend={}",
+            config.max_concurrent_proofs,
+            backend.name()
         );
-        let subscription = client.subscription(&subscription_path);
+        if ramp.is_some() {
+            info!(
+                "Concurrency ramp enabled: starting with 1 proof permit, up to {} over CONCURRENCY_RAMP_SECS={}",
+                config.max_concurrent_proofs, config.concurrency_ramp_secs
+            );
+        }
 
-        // Store result topic path for publishing
-        let result_topic_path = format!(
-            "projects/{}/topics/{}",
-            config.gcp_project_id, config.result_topic
-        );
+        let audit = AuditConfig::from_env()?
+            .map(|audit_config| {
+                AuditLog::open(&audit_config, &config.worker_id, &client).map(Arc::new)
+            })
+            .transpose()?;
 
-        // Create semaphore for concurrency control
-        let semaphore = Arc::new(Semaphore::new(config.max_concurrent_proofs));
+        let telemetry = TelemetryConfig::from_env()?.map(|telemetry_config| {
+            info!(
+                "Telemetry enabled with TELEMETRY_SAMPLE_RATE={}",
+                telemetry_config.sample_rate
+            );
+            Arc::new(Telemetry::new(&telemetry_config, &client))
+        });
 
-        info!(
-            "Prover service initialized with max_concurrent_proofs={}",
-            config.max_concurrent_proofs
-        );
+        let webhooks = WebhookConfig::from_env()?
+            .map(|webhook_config| Arc::new(Webhooks::new(&webhook_config)));
+        if webhooks.is_none() && !config.callback_allowed_domains.is_empty() {
+            return Err(ServiceError::Config(
+                "CALLBACK_SIGNING_KEY must be set with CALLBACK_ALLOWED_DOMAINS".to_string(),
+            ));
+        }
+
+        let quarantine = config
+            .max_processing_attempts
+            .map(|max_attempts| {
+                Quarantine::open(Path::new(&config.quarantine_dir), max_attempts).map(Arc::new)
+            })
+            .transpose()?;
+
+        let delayed = Arc::new(DelayedQueue::open(Path::new(&config.delayed_queue_path))?);
+        let janitor = Arc::new(Janitor::new(
+            Path::new(&config.output_dir),
+            Path::new(&config.quarantine_dir),
+        ));
+
+        let metrics_log = (config.metrics_snapshot_mins > 0).then(|| {
+            info!(
+                "Metrics snapshots enabled every {} minutes",
+                config.metrics_snapshot_mins
+            );
+            let proof_type = match config.prover_backend {
+                ProverBackendKind::Pico => config.prover_profile.as_str(),
+                ProverBackendKind::Mock => "mock",
+            };
+            Arc::new(MetricsLog::new(
+                &Path::new(&config.output_dir).join(METRICS_DIR),
+                proof_type,
+                Utc::now(),
+            ))
+        });
+
+        let global = LeaseConfig::from_env()?
+            .map(|lease_config| {
+                GlobalAdmission::from_config(&lease_config, &config.worker_id).map(Arc::new)
+            })
+            .transpose()?;
+        if let Some(global) = &global {
+            info!(
+                "Global admission enabled with GLOBAL_MAX_CONCURRENT={}, backend={}",
+                global.limit(),
+                global.backend_name()
+            );
+        }
+
+        #[cfg(feature = "attestation")]
+        let attestor = match AttestationConfig::from_env()? {
+            Some(attestation_config) => Some(Arc::new(Attestor::new(&attestation_config).await?)),
+            None => None,
+        };
+
+        #[cfg(feature = "onchain")]
+        let onchain = match OnchainConfig::from_env()? {
+            Some(onchain_config) => Some(Arc::new(OnchainSubmitter::new(&onchain_config).await?)),
+            None => None,
+        };
+        let history = Arc::new(ResponseHistory::new(config.recent_responses));
 
         Ok(Self {
-            config,
-            cached_elf,
-            client,
-            subscription,
-            result_topic_path,
-            semaphore,
+            config: Arc::new(RwLock::new(config)),
+            backend: Arc::new(backend),
+            endpoints,
+            subscriptions,
+            publishers,
+            publish_sequence: Arc::new(PublishSequence::default()),
+            work,
+            recent: Mutex::new(RecentMessages::new(RECENT_MESSAGES)),
+            scheduler,
+            capacity: Capacity::new(semaphore),
+            ramp: Mutex::new(ramp),
+            audit,
+            telemetry,
+            webhooks,
+            quarantine,
+            provenance: Arc::new(provenance),
+            delayed,
+            janitor,
+            global,
+            in_flight: TaskTracker::new(),
+            session: Arc::default(),
+            history,
+            metrics_log,
+            interrupt: CancellationToken::new(),
+            #[cfg(feature = "attestation")]
+            attestor,
+            #[cfg(feature = "onchain")]
+            onchain,
         })
     }
 
     /// Start the service and process messages
-    pub async fn run(&self, cancellation_token: CancellationToken) -> Result<(), ServiceError> {
+    pub async fn run(
+        self: &Arc<Self>,
+        cancellation_token: CancellationToken,
+    ) -> Result<(), ServiceError> {
+        let config = self.config_snapshot();
         info!(
-            "Starting prover service, subscribing to '{}'",
-            self.config.prover_subscription
+            "Starting prover service as {:?}, subscribing to '{}'",
+            config.role,
+            self.endpoints.get(self.endpoints.active()).subscription
         );
 
-        let config = self.config.clone();
-        let cached_elf = self.cached_elf.clone();
-        let client = self.client.clone();
-        let result_topic_path = self.result_topic_path.clone();
-        let semaphore = self.semaphore.clone();
-
-        // Subscribe to messages with handler function
-        self.subscription
-            .receive(
-                move |message, _cancel| {
-                    let config = config.clone();
-                    let cached_elf = cached_elf.clone();
-                    let client = client.clone();
-                    let result_topic_path = result_topic_path.clone();
-                    let semaphore = semaphore.clone();
-
-                    async move {
-                        // Wait for permit (blocks until capacity available)
-                        let permit = match semaphore.clone().acquire_owned().await {
-                            Ok(permit) => permit,
-                            Err(e) => {
-                                error!("Semaphore closed, cannot acquire permit: {}", e);
-                                return;
-                            }
-                        };
-
-                        let received_at = Utc::now();
-                        let ack_id = message.ack_id().to_string();
-
-                        // Immediately ACK to prevent redelivery (proof generation takes hours)
-                        if let Err(e) = message.ack().await {
-                            error!(ack_id = ack_id, "Failed to ACK message: {}", e);
-                            drop(permit);
-                            return;
+        tokio::spawn(self.clone().release_delayed(cancellation_token.clone()));
+        tokio::spawn(self.clone().ramp_up(cancellation_token.clone()));
+        tokio::spawn(self.clone().sweep_output_dir(cancellation_token.clone()));
+        if let Some(metrics_log) = &self.metrics_log {
+            let interval = Duration::from_secs(config.metrics_snapshot_mins * 60);
+            tokio::spawn(snapshot_metrics(
+                metrics_log.clone(),
+                interval,
+                cancellation_token.clone(),
+            ));
+        }
+
+        // Receive from the active endpoint, failing over when it keeps failing
+        loop {
+            let index = self.endpoints.active();
+            let endpoint = self.endpoints.get(index);
+            if self.endpoints.len() > 1 {
+                info!(
+                    endpoint = %endpoint,
+                    "Receiving from Pub/Sub endpoint {} of {}",
+                    index + 1,
+                    self.endpoints.len()
+                );
+            }
+
+            // Subscribe to messages with handler function
+            let service = self.clone();
+            let result = self.subscriptions[index]
+                .receive(
+                    move |message, cancel| {
+                        let service = service.clone();
+                        let in_flight = service.in_flight.token();
+                        async move {
+                            service.handle_message(message, cancel, index).await;
+                            drop(in_flight);
                         }
+                    },
+                    cancellation_token.clone(),
+                    None,
+                )
+                .await;
+
+            // A single endpoint has nothing to fail over to, so its errors stop the service
+            if cancellation_token.is_cancelled() || self.endpoints.len() == 1 {
+                result.map_err(|e| {
+                    ServiceError::PubSub(format!("Subscription receive error: {}", e))
+                })?;
+                return Ok(());
+            }
+
+            // Receiving only stops on its own when the subscription failed
+            let error = match result {
+                Ok(()) => "receive stopped".to_string(),
+                Err(e) => e.to_string(),
+            };
+            let failover_errors = self.config_snapshot().pubsub_failover_errors;
+            match self.endpoints.record_error(index, &error, failover_errors) {
+                Failover::Retry { errors } => {
+                    warn!(
+                        endpoint = %endpoint,
+                        errors = errors,
+                        "Receive from Pub/Sub endpoint failed, retrying in {}s: {}",
+                        RECEIVE_RETRY_DELAY.as_secs(),
+                        error
+                    );
+                    tokio::select! {
+                        _ = tokio::time::sleep(RECEIVE_RETRY_DELAY) => {}
+                        _ = cancellation_token.cancelled() => return Ok(()),
+                    }
+                }
+                Failover::Switch => {
+                    let to = self.next_healthy_endpoint(index).await;
+                    self.endpoints.switch(index, to);
+                    warn!(
+                        from = %endpoint,
+                        to = %self.endpoints.get(to),
+                        "Pub/Sub endpoint failed {} times in a row, failing over: {}",
+                        failover_errors,
+                        error
+                    );
+                }
+            }
+        }
+    }
+
+    /// First healthy endpoint after `from`, in failover order; the next one if none is
+    async fn next_healthy_endpoint(&self, from: usize) -> usize {
+        let candidates = self.endpoints.candidates(from);
+        for &candidate in &candidates {
+            if is_healthy(
+                self.endpoints.get(candidate),
+                &self.subscriptions[candidate],
+            )
+            .await
+            {
+                return candidate;
+            }
+        }
+        candidates[0]
+    }
+
+    /// Admit, prove and publish one message, or journal it if it is not due yet
+    ///
+    /// A frontend journals every request for the workers instead.
+    async fn handle_message(
+        &self,
+        message: ReceivedMessage,
+        cancel: CancellationToken,
+        endpoint: usize,
+    ) {
+        self.endpoints.record_message(endpoint);
+        // Queued from now, however long parsing below takes
+        let ticket = self.scheduler.ticket();
+        // Snapshot per message so reloaded settings apply to new work only
+        let config = self.config_snapshot();
+        let (data, request, priority) = match config.role {
+            Role::Frontend => return self.accept(message, &config).await,
+            Role::Worker => match decode_work_item(&message.message.data) {
+                Ok((data, item)) => {
+                    if let Some(queued) = item.queued_for(Utc::now()) {
                         debug!(
-                            ack_id = ack_id,
-                            "Message ACKed immediately to prevent redelivery"
+                            request_id = %item.request.request_id,
+                            queued_ms = queued.num_milliseconds(),
+                            "Work item received"
                         );
+                    }
+                    (data, Some(item.request), item.priority)
+                }
+                Err(e) => {
+                    error!(
+                        message_id = %message.message.message_id,
+                        "Dropping malformed work item: {}", e
+                    );
+                    if let Err(e) = message.ack().await {
+                        error!(ack_id = message.ack_id(), "Failed to ACK message: {}", e);
+                    }
+                    return;
+                }
+            },
+            Role::All => {
+                if let Ok(job) = serde_json::from_slice::<BulkJob>(&message.message.data) {
+                    return self
+                        .run_bulk_job(message, job, &config, ticket, cancel, endpoint)
+                        .await;
+                }
+                // Renamed once here, so scheduling and publishing see the request
+                // that is proved
+                let data = normalize_aliases(&config, &message.message.data);
+                let request = serde_json::from_slice::<ProverRequest>(&data).ok();
+                let priority = request
+                    .as_ref()
+                    .and_then(|request| request.priority)
+                    .or_else(|| message_priority(&message.message))
+                    .unwrap_or_default();
 
-                        // Process the message (no retry on failure)
-                        match Self::process_message(
-                            &message.message.data,
-                            config,
-                            cached_elf,
-                            received_at,
-                        )
-                        .await
-                        {
-                            Ok(response) => {
-                                // Publish result
-                                if let Err(e) =
-                                    Self::publish_result(&client, &result_topic_path, &response)
-                                        .await
-                                {
-                                    error!(
-                                        request_id = response.request_id,
-                                        "Failed to publish result: {}", e
-                                    );
-                                }
-                            }
-                            Err(e) => {
-                                error!("Failed to process message: {}", e);
-                                // Message already ACKed, no retry will happen
-                            }
-                        }
+                if let Some(request) = &request {
+                    if let Some(release_at) =
+                        request.release_at(config.max_request_age_secs, Utc::now())
+                    {
+                        return self
+                            .delay(message, &data, request, release_at, priority, endpoint)
+                            .await;
+                    }
+                }
+                (data, request, priority)
+            }
+        };
+
+        // Wait for permits (blocks until capacity available)
+        let permit = tokio::select! {
+            permit = self.acquire_permit(ticket, &config, request.as_ref(), priority) => match permit {
+                Some(permit) => permit,
+                None => return,
+            },
+            // Shutting down: leave queued work for another worker
+            _ = cancel.cancelled() => {
+                if let Err(e) = message.nack().await {
+                    error!(ack_id = message.ack_id(), "Failed to NACK message: {}", e);
+                }
+                return;
+            }
+        };
 
-                        drop(permit);
+        let lease = tokio::select! {
+            lease = self.acquire_lease() => lease,
+            _ = cancel.cancelled() => {
+                drop(permit);
+                if let Err(e) = message.nack().await {
+                    error!(ack_id = message.ack_id(), "Failed to NACK message: {}", e);
+                }
+                return;
+            }
+        };
+
+        let received_at = Utc::now();
+        let ack_id = message.ack_id().to_string();
+
+        // Immediately ACK to prevent redelivery (proof generation takes hours)
+        if let Err(e) = message.ack().await {
+            error!(ack_id = ack_id, "Failed to ACK message: {}", e);
+            release_lease(lease).await;
+            drop(permit);
+            return;
+        }
+        debug!(
+            ack_id = ack_id,
+            "Message ACKed immediately to prevent redelivery"
+        );
+
+        let queue_wait = permit.queue_wait();
+        self.prove_and_publish(
+            config,
+            &data,
+            request.as_ref(),
+            received_at,
+            queue_wait,
+            endpoint,
+        )
+        .instrument(request_span(request.as_ref()))
+        .await;
+        release_lease(lease).await;
+        drop(permit);
+    }
+
+    /// Prove the lines of a bulk job and publish its completion
+    ///
+    /// The job holds a permit per line it proves at once, in the `batch` lane unless
+    /// the `priority` attribute says otherwise, and is ACKed once admitted. Each
+    /// line takes a cluster-wide lease while proved.
+    async fn run_bulk_job(
+        &self,
+        message: ReceivedMessage,
+        job: BulkJob,
+        config: &Config,
+        ticket: Ticket,
+        cancel: CancellationToken,
+        endpoint: usize,
+    ) {
+        let priority = message_priority(&message.message).unwrap_or(Priority::Batch);
+        let cost = config.bulk_permits() as u32;
+        let permit = tokio::select! {
+            permit = self.scheduler.acquire_in_turn(ticket, "", priority, cost) => match permit {
+                Some(permit) => permit,
+                None => return,
+            },
+            _ = cancel.cancelled() => {
+                if let Err(e) = message.nack().await {
+                    error!(ack_id = message.ack_id(), "Failed to NACK message: {}", e);
+                }
+                return;
+            }
+        };
+        if let Err(e) = message.ack().await {
+            error!(ack_id = message.ack_id(), "Failed to ACK message: {}", e);
+            return;
+        }
+
+        let outcome = async {
+            let store = match job.uses_gcs() {
+                true => Some(GcsObjects::new().await?),
+                false => None,
+            };
+            bulk::run_job(
+                &job,
+                config,
+                self.backend.clone(),
+                store.as_ref().map(|store| store as &dyn ObjectStore),
+                self.audit.clone(),
+                self.global.clone(),
+                &self.interrupt,
+                |response| {
+                    prometheus::record_proof(response);
+                    self.session.record(response);
+                    self.history.record(response);
+                    if let Some(metrics_log) = &self.metrics_log {
+                        metrics_log.record(response, None);
                     }
                 },
-                cancellation_token,
-                None,
             )
             .await
-            .map_err(|e| ServiceError::PubSub(format!("Subscription receive error: {}", e)))?;
+        }
+        .instrument(tracing::info_span!("bulk_job", request_id = %job.request_id))
+        .await;
+        drop(permit);
+        if let Err(e) = &outcome {
+            error!(request_id = %job.request_id, "Bulk job failed: {}", e);
+        }
 
-        Ok(())
+        let published = match completion_message(&bulk::completion(&job, &outcome)) {
+            Ok(completion) => {
+                let awaiter = self.publishers[endpoint]
+                    .get(None)
+                    .publish(completion)
+                    .await;
+                awaiter
+                    .get()
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| ServiceError::PubSub(format!("Failed to publish: {}", e)))
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = published {
+            error!(
+                request_id = %job.request_id,
+                "Failed to publish bulk job completion: {}", e
+            );
+        }
+    }
+
+    /// Validate and journal a request for the workers, then ACK it
+    ///
+    /// Redeliveries of a message accepted before are ACKed and dropped, and so are
+    /// messages that are not valid requests.
+    async fn accept(&self, message: ReceivedMessage, config: &Config) {
+        let message_id = message.message.message_id.clone();
+        if self.recent_messages().contains(&message_id) {
+            debug!(message_id = %message_id, "Dropping redelivered message");
+            if let Err(e) = message.ack().await {
+                error!(ack_id = message.ack_id(), "Failed to ACK message: {}", e);
+            }
+            return;
+        }
+
+        // Journaled with its signal aliases renamed, so the workers parse it as is
+        let parsed = config
+            .signal_aliases
+            .normalize(&message.message.data)
+            .and_then(|data| {
+                let request = ProverRequest::from_json(&data)
+                    .and_then(|request| request.clone().resolve_inputs().map(|_| request))?;
+                Ok((data, request))
+            });
+        let (data, request) = match parsed {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                error!(message_id = %message_id, "Rejecting malformed request: {}", e);
+                if let Some(audit) = &self.audit {
+                    audit.record_rejected(&e);
+                }
+                if let Err(e) = message.ack().await {
+                    error!(ack_id = message.ack_id(), "Failed to ACK message: {}", e);
+                }
+                return;
+            }
+        };
+        let priority = request
+            .priority
+            .or_else(|| message_priority(&message.message))
+            .unwrap_or_default();
+
+        // Due requests are forwarded at once, the others at their release time
+        let now = Utc::now();
+        let release_at = request
+            .release_at(config.max_request_age_secs, now)
+            .unwrap_or(now);
+        if let Err(e) = self.delayed.insert(release_at, priority, &data) {
+            // Not journaled: let Pub/Sub redeliver it
+            error!(request_id = %request.request_id, "Failed to journal request: {}", e);
+            if let Err(e) = message.nack().await {
+                error!(ack_id = message.ack_id(), "Failed to NACK message: {}", e);
+            }
+            return;
+        }
+        self.recent_messages().insert(message_id);
+        info!(
+            request_id = %request.request_id,
+            trace_id = request.trace_id.as_deref(),
+            parent_request_id = request.parent_request_id.as_deref(),
+            release_at = %release_at.to_rfc3339(),
+            "Request accepted"
+        );
+
+        if let Err(e) = message.ack().await {
+            // Redelivered, but dropped as already accepted
+            error!(
+                ack_id = message.ack_id(),
+                "Failed to ACK accepted message: {}", e
+            );
+        }
+    }
+
+    /// Publish a journaled request to the work topic, then drop it from the journal
+    ///
+    /// A request that fails to publish is retried after `FORWARD_RETRY_DELAY`.
+    async fn forward(&self, item: DelayedItem) {
+        let Some(work) = &self.work else {
+            return;
+        };
+        match publish_work(work, &item).await {
+            Ok(request_id) => {
+                info!(request_id = %request_id, "Request forwarded to the workers");
+                if let Err(e) = self.delayed.complete(item.id) {
+                    error!(
+                        delayed_id = item.id,
+                        "Failed to remove forwarded request: {}", e
+                    );
+                }
+            }
+            Err(e @ ServiceError::MalformedRequest(_)) => {
+                // Validated when accepted, so only a journal from another role gets here
+                error!(
+                    delayed_id = item.id,
+                    "Dropping journaled request that is not valid: {}", e
+                );
+                if let Err(e) = self.delayed.complete(item.id) {
+                    error!(
+                        delayed_id = item.id,
+                        "Failed to remove journaled request: {}", e
+                    );
+                }
+            }
+            Err(e) => {
+                warn!(
+                    delayed_id = item.id,
+                    "Failed to forward request, retrying: {}", e
+                );
+                let retry_at = Utc::now() + FORWARD_RETRY_DELAY;
+                if let Err(e) = self.delayed.postpone(item.id, retry_at) {
+                    error!(delayed_id = item.id, "Failed to requeue request: {}", e);
+                }
+            }
+        }
+    }
+
+    fn recent_messages(&self) -> std::sync::MutexGuard<'_, RecentMessages> {
+        self.recent.lock().expect("recent messages lock poisoned")
+    }
+
+    /// Journal a request until `release_at`, then ACK it
+    async fn delay(
+        &self,
+        message: ReceivedMessage,
+        data: &[u8],
+        request: &ProverRequest,
+        release_at: chrono::DateTime<Utc>,
+        priority: Priority,
+        endpoint: usize,
+    ) {
+        // Journaled with its endpoint, so the result goes back there on release
+        let key = self.endpoints.get(endpoint).key();
+        if let Err(e) = self
+            .delayed
+            .insert_from(release_at, priority, data, Some(&key))
+        {
+            // Not journaled: let Pub/Sub redeliver it
+            error!(request_id = %request.request_id, "Failed to delay request: {}", e);
+            if let Err(e) = message.nack().await {
+                error!(ack_id = message.ack_id(), "Failed to NACK message: {}", e);
+            }
+            return;
+        }
+        info!(
+            request_id = %request.request_id,
+            trace_id = request.trace_id.as_deref(),
+            parent_request_id = request.parent_request_id.as_deref(),
+            release_at = %release_at.to_rfc3339(),
+            "Request delayed until not_before"
+        );
+        if let Err(e) = message.ack().await {
+            // Redelivered and journaled again; the copies are proved independently
+            error!(
+                ack_id = message.ack_id(),
+                "Failed to ACK delayed message: {}", e
+            );
+        }
+    }
+
+    /// Move delayed requests into the worker queue as they fall due
+    async fn release_delayed(self: Arc<Self>, cancel: CancellationToken) {
+        loop {
+            match self.delayed.take_due(Utc::now()) {
+                Ok(items) => {
+                    for item in items {
+                        let in_flight = self.in_flight.token();
+                        let service = self.clone();
+                        let cancel = cancel.clone();
+                        tokio::spawn(async move {
+                            service.run_released(item, cancel).await;
+                            drop(in_flight);
+                        });
+                    }
+                }
+                Err(e) => error!("Failed to release delayed requests: {}", e),
+            }
+
+            // Sleep until the next release, or an insert that may come sooner
+            let next = match self.delayed.next_release_at() {
+                Ok(next) => next,
+                Err(e) => {
+                    error!("Failed to read delayed queue: {}", e);
+                    None
+                }
+            };
+            let wait = next
+                .and_then(|next| (next - Utc::now()).to_std().ok())
+                .unwrap_or(DELAYED_POLL_INTERVAL)
+                .min(DELAYED_POLL_INTERVAL);
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                _ = self.delayed.inserted() => {}
+                _ = cancel.cancelled() => return,
+            }
+        }
+    }
+
+    /// Sweep the output directory now and then every `JANITOR_INTERVAL_SECS`
+    ///
+    /// With an interval of 0 only the startup sweep runs.
+    async fn sweep_output_dir(self: Arc<Self>, cancel: CancellationToken) {
+        loop {
+            let config = self.config_snapshot();
+            let policy = config.janitor_policy();
+            let janitor = self.janitor.clone();
+            match tokio::task::spawn_blocking(move || janitor.sweep(&policy, SystemTime::now()))
+                .await
+            {
+                Ok(sweep) => log_sweep(&sweep, config.output_dir_max_bytes),
+                Err(e) => error!("Output directory sweep failed: {}", e),
+            }
+            if config.janitor_interval_secs == 0 {
+                return;
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(config.janitor_interval_secs)) => {}
+                _ = cancel.cancelled() => return,
+            }
+        }
+    }
+
+    /// Grant warm-up permits as they fall due, until the ramp is complete
+    async fn ramp_up(self: Arc<Self>, cancel: CancellationToken) {
+        let mut ticks = tokio::time::interval(RAMP_TICK);
+        while self.advance_ramp() {
+            tokio::select! {
+                _ = ticks.tick() => {}
+                _ = cancel.cancelled() => return,
+            }
+        }
+    }
+
+    /// Grant the permits the warm-up allows by now; returns whether it is still running
+    fn advance_ramp(&self) -> bool {
+        let config = self.config.read().expect("config lock poisoned");
+        let mut ramp = self.ramp();
+        let Some(warmup) = ramp.as_mut() else {
+            return false;
+        };
+        let new = warmup.advance(Instant::now());
+        if new > 0 {
+            self.capacity.grow(new);
+            self.scheduler
+                .set_lanes(ramped_lanes(&config, Some(&*warmup)));
+            info!(
+                "Concurrency ramp at {} of {} proof permits",
+                warmup.granted(),
+                config.max_concurrent_proofs
+            );
+        }
+        if warmup.is_complete() {
+            *ramp = None;
+            return false;
+        }
+        true
+    }
+
+    /// Count a successful proof toward the next warm-up step
+    fn record_ramp_success(&self) {
+        if let Some(warmup) = self.ramp().as_mut() {
+            warmup.record_success();
+        }
+        self.advance_ramp();
+    }
+
+    fn ramp(&self) -> std::sync::MutexGuard<'_, Option<ConcurrencyRamp>> {
+        self.ramp.lock().expect("ramp lock poisoned")
+    }
+
+    /// Prove a released request like a received one, then drop it from the queue
+    async fn run_released(&self, item: DelayedItem, cancel: CancellationToken) {
+        let ticket = self.scheduler.ticket();
+        let config = self.config_snapshot();
+        if config.role == Role::Frontend {
+            return self.forward(item).await;
+        }
+        // Items delayed by an older version were journaled as received
+        let data = normalize_aliases(&config, &item.data);
+        let request = serde_json::from_slice::<ProverRequest>(&data).ok();
+
+        let permit = tokio::select! {
+            permit = self.acquire_permit(ticket, &config, request.as_ref(), item.priority) => match permit {
+                Some(permit) => permit,
+                None => return,
+            },
+            // Shutting down: released again on the next start
+            _ = cancel.cancelled() => return,
+        };
+        let lease = tokio::select! {
+            lease = self.acquire_lease() => lease,
+            _ = cancel.cancelled() => return,
+        };
+
+        let endpoint = self.released_endpoint(&item);
+        let queue_wait = permit.queue_wait();
+        self.prove_and_publish(
+            config,
+            &data,
+            request.as_ref(),
+            Utc::now(),
+            queue_wait,
+            endpoint,
+        )
+        .instrument(request_span(request.as_ref()))
+        .await;
+        release_lease(lease).await;
+        drop(permit);
+
+        if let Err(e) = self.delayed.complete(item.id) {
+            error!(
+                delayed_id = item.id,
+                "Failed to remove delayed request: {}", e
+            );
+        }
+    }
+
+    /// Wait for the permits a request needs, in the turn of its `ticket`
+    ///
+    /// Waits over `QUEUE_WAIT_WARN_SECS` are logged, to tell a starved request
+    /// from a slow proof.
+    async fn acquire_permit(
+        &self,
+        ticket: Ticket,
+        config: &Config,
+        request: Option<&ProverRequest>,
+        priority: Priority,
+    ) -> Option<TenantPermit> {
+        let cost = admission_cost(request, config.max_concurrent_proofs);
+        // Without fair scheduling all messages queue as one tenant
+        let tenant = match request {
+            Some(request) if config.fair_scheduling => request.tenant(),
+            _ => "",
+        };
+        let permit = self
+            .scheduler
+            .acquire_in_turn(ticket, tenant, priority, cost)
+            .await?;
+        if permit.long_wait() {
+            warn!(
+                request_id = request.map_or("", |r| r.request_id.as_str()),
+                queue_wait_ms = permit.queue_wait().as_millis() as u64,
+                priority = ?priority,
+                "Request waited longer than QUEUE_WAIT_WARN_SECS for a permit"
+            );
+        }
+        Some(permit)
+    }
+
+    /// Wait for a cluster-wide lease, when `GLOBAL_MAX_CONCURRENT` is set
+    ///
+    /// Taken after the local permits, so a worker only holds leases it can use.
+    async fn acquire_lease(&self) -> Option<Lease> {
+        match &self.global {
+            Some(global) => Some(global.acquire().await),
+            None => None,
+        }
+    }
+
+    /// Endpoint a released request was received from, so its result goes back there
+    ///
+    /// Requests journaled without one, or from an endpoint no longer configured, are
+    /// answered through the primary.
+    fn released_endpoint(&self, item: &DelayedItem) -> usize {
+        let Some(key) = &item.endpoint else {
+            return 0;
+        };
+        self.endpoints.find(key).unwrap_or_else(|| {
+            warn!(
+                delayed_id = item.id,
+                endpoint = %key,
+                "Endpoint of a delayed request is no longer configured, publishing to the primary"
+            );
+            0
+        })
+    }
+
+    /// Process admitted message data and publish the result (no retry on failure)
+    ///
+    /// The result goes to the result topic of `endpoint`, the one the request came from.
+    async fn prove_and_publish(
+        &self,
+        config: Config,
+        data: &[u8],
+        request: Option<&ProverRequest>,
+        received_at: chrono::DateTime<Utc>,
+        queue_wait: Duration,
+        endpoint: usize,
+    ) {
+        let response_schema_version = config.response_schema_version;
+        let result_ordering = config.result_ordering;
+        let callback_policy = config.callback_policy();
+        // v1 consumers cannot undo compression
+        let result_compression = config
+            .result_compression
+            .filter(|_| response_schema_version >= 2);
+        let journal_size = config.result_journal_size;
+        if let (Some(request), true) = (request, journal_size > 0) {
+            if let Err(e) = self.delayed.begin_result(&request.request_id) {
+                error!(request_id = %request.request_id, "Failed to journal result: {}", e);
+            }
+        }
+        let in_flight = (InFlight::start(), self.session.start());
+        let processed = Self::process_message(
+            data,
+            config,
+            self.backend.clone(),
+            self.audit.as_deref(),
+            self.quarantine.as_deref(),
+            &self.interrupt,
+            received_at,
+        )
+        .await;
+        drop(in_flight);
+        match processed {
+            Ok(mut response) => {
+                if let Some(metrics) = &mut response.metrics {
+                    metrics.queue_wait_ms = Some(queue_wait.as_millis() as u64);
+                }
+                prometheus::record_proof(&response);
+                self.session.record(&response);
+                self.history.record(&response);
+                if let Some(metrics_log) = &self.metrics_log {
+                    metrics_log.record(&response, request.and_then(|r| r.client_id.as_deref()));
+                }
+                let succeeded = response.status == ProofStatus::Success;
+                #[cfg(feature = "attestation")]
+                let response = Self::attach_attestation(self.attestor.as_deref(), response).await;
+                #[cfg(feature = "onchain")]
+                let response = Self::submit_onchain(self.onchain.as_deref(), response).await;
+                let mut response = Self::compress_result(result_compression, response);
+                response.publish_sequence = Some(self.publish_sequence.next());
+                response.provenance = Some(Provenance::clone(&self.provenance));
+
+                // Publish result, to the request's own topic if allowed
+                let result_topic = request.and_then(|r| r.result_topic.as_deref());
+                if let Err(e) = self
+                    .publish(
+                        endpoint,
+                        result_topic,
+                        &response,
+                        response_schema_version,
+                        result_ordering,
+                        journal_size,
+                    )
+                    .await
+                {
+                    error!(
+                        request_id = response.request_id,
+                        "Failed to publish result: {}", e
+                    );
+                }
+
+                // Also POSTed to the request's callback, while the proof permit is released
+                if let Some(url) = request.and_then(|r| r.callback_url.as_deref()) {
+                    self.send_callback(url, &response, response_schema_version, callback_policy);
+                }
+
+                if let (Some(telemetry), Some(request)) = (&self.telemetry, request) {
+                    telemetry.record(request, &response).await;
+                }
+                if succeeded {
+                    self.record_ramp_success();
+                }
+            }
+            Err(e) => {
+                error!("Failed to process message: {}", e);
+                self.session.record_error();
+                if let Some(request) = request {
+                    if let Err(e) = self.delayed.abandon_result(&request.request_id) {
+                        error!(request_id = %request.request_id, "Failed to journal result: {}", e);
+                    }
+                }
+                if let Some(metrics_log) = &self.metrics_log {
+                    metrics_log.record_error();
+                }
+                // Message already ACKed, no retry will happen
+            }
+        }
+    }
+
+    /// Publish a result to the result topic of `endpoint`, or to the request's
+    /// `result_topic` if it is allowlisted
+    ///
+    /// With `journal_size` above 0 the result is journaled first, whether or not it is
+    /// then published, so it can still be fetched or published again.
+    async fn publish(
+        &self,
+        endpoint: usize,
+        result_topic: Option<&str>,
+        response: &ProverResponse,
+        schema_version: u32,
+        ordering: bool,
+        journal_size: usize,
+    ) -> Result<(), ServiceError> {
+        let publishers = &self.publishers[endpoint];
+        let result_topic = publishers.allowed(result_topic);
+        let message = result_message(response, schema_version, ordering)?;
+        if journal_size > 0 {
+            let endpoint = self.endpoints.get(endpoint);
+            let topic_path = endpoint.topic_path(result_topic.unwrap_or(&endpoint.result_topic));
+            self.journal_result(response, &message, topic_path, journal_size);
+        }
+        Self::publish_result(publishers.get(result_topic), message, &response.request_id).await
+    }
+
+    /// Keep a published result in the journal, with the latest `keep` others
+    fn journal_result(
+        &self,
+        response: &ProverResponse,
+        message: &PubsubMessage,
+        topic_path: String,
+        keep: usize,
+    ) {
+        let result = JournaledResult {
+            request_id: response.request_id.clone(),
+            data: message.data.clone(),
+            attributes: message.attributes.clone(),
+            ordering_key: message.ordering_key.clone(),
+            topic_path,
+            completed_at: String::new(),
+        };
+        if let Err(e) = self.delayed.record_result(&result, keep) {
+            error!(
+                request_id = response.request_id,
+                "Failed to journal result: {}", e
+            );
+        }
+    }
+
+    /// Deliver a result to its request's `callback_url` in the background
+    ///
+    /// Deliveries still retrying when the shutdown grace period runs out are given
+    /// up, and stay pending in the journal.
+    fn send_callback(
+        &self,
+        url: &str,
+        response: &ProverResponse,
+        schema_version: u32,
+        policy: CallbackPolicy,
+    ) {
+        // A refused URL failed the request with `CallbackNotAllowed`
+        let Ok(url) = check_callback_url(url, &policy) else {
+            return;
+        };
+        let Some(webhooks) = self.webhooks.clone() else {
+            warn!(
+                request_id = response.request_id,
+                "Result not delivered to its callback: CALLBACK_SIGNING_KEY is not set"
+            );
+            return;
+        };
+        let body = match response.encode(schema_version) {
+            Ok(body) => body,
+            Err(e) => {
+                error!(
+                    request_id = response.request_id,
+                    "Failed to encode callback: {}", e
+                );
+                return;
+            }
+        };
+        let journal = self.delayed.clone();
+        let interrupt = self.interrupt.clone();
+        let request_id = response.request_id.clone();
+        self.in_flight.spawn(async move {
+            tokio::select! {
+                _ = webhooks.deliver(&journal, &request_id, &url, &body, schema_version, &policy) => {}
+                _ = interrupt.cancelled() => {}
+            }
+        });
+    }
+
+    /// Interrupt in-flight proofs; each publishes an `interrupted` response
+    pub fn interrupt(&self) {
+        self.interrupt.cancel();
+    }
+
+    /// Wait for in-flight messages to finish, then flush the publishers and audit log
+    ///
+    /// Call after `run` has returned. Proofs that outlast the grace period must be
+    /// stopped with `interrupt` for this to return. Logs the shutdown report once the
+    /// messages are done.
+    pub async fn drain(&self) {
+        self.in_flight.close();
+        self.in_flight.wait().await;
+        self.session.report().log();
+        if let Some(metrics_log) = &self.metrics_log {
+            write_metrics_snapshot(metrics_log);
+        }
+
+        for publishers in self.publishers.iter() {
+            publishers.shutdown().await;
+        }
+        if let Some(work) = &self.work {
+            work.clone().shutdown().await;
+        }
+        if let Some(telemetry) = &self.telemetry {
+            telemetry.shutdown().await;
+        }
+
+        if let Some(audit) = &self.audit {
+            if let Err(e) = audit.flush().await {
+                error!("Failed to flush audit log: {}", e);
+            }
+        }
+    }
+
+    /// What the service did so far, as the shutdown report gives it
+    pub fn session_report(&self) -> SessionReport {
+        self.session.report()
+    }
+
+    /// Queue of requests waiting for `not_before`, e.g. to report its size
+    pub fn delayed_queue(&self) -> Arc<DelayedQueue> {
+        self.delayed.clone()
+    }
+
+    /// Pub/Sub endpoints and the active one, e.g. to report failovers
+    pub fn endpoints(&self) -> Arc<Endpoints> {
+        self.endpoints.clone()
+    }
+
+    /// Scheduler admitting messages, e.g. to report lane depths
+    pub fn scheduler(&self) -> Arc<Scheduler> {
+        self.scheduler.clone()
+    }
+
+    /// Configuration as `apply_config` keeps it, e.g. to report the effective settings
+    pub fn config(&self) -> Arc<RwLock<Config>> {
+        self.config.clone()
+    }
+
+    /// Last responses the service answered with, e.g. to report them
+    pub fn history(&self) -> Arc<ResponseHistory> {
+        self.history.clone()
+    }
+
+    /// Janitor of the output directory, e.g. to report its usage
+    pub fn janitor(&self) -> Arc<Janitor> {
+        self.janitor.clone()
+    }
+
+    /// Current effective configuration
+    pub fn config_snapshot(&self) -> Config {
+        self.config.read().expect("config lock poisoned").clone()
+    }
+
+    /// Apply a reloaded configuration
+    ///
+    /// Only the settings `Config::apply_live` copies are applied; the returned
+    /// changes list anything else that needs a restart. The log level itself is
+    /// applied by the caller, which owns the tracing reload handle.
+    pub fn apply_config(&self, new_config: &Config) -> ConfigChanges {
+        let mut config = self.config.write().expect("config lock poisoned");
+        let changes = config.diff(new_config);
+
+        // During the warm-up the ramp holds back permits, and heads for the new maximum
+        let mut ramp = self.ramp();
+        let (old_permits, new_permits) = match ramp.as_mut() {
+            Some(warmup) => {
+                let old_permits = warmup.granted();
+                warmup.set_target(new_config.max_concurrent_proofs);
+                (old_permits, warmup.granted())
+            }
+            None => (
+                config.max_concurrent_proofs,
+                new_config.max_concurrent_proofs,
+            ),
+        };
+        if new_permits > old_permits {
+            self.capacity.grow(new_permits - old_permits);
+        } else if new_permits < old_permits {
+            // Retired as in-flight proofs release them
+            self.capacity.shrink(old_permits - new_permits);
+        }
+
+        config.apply_live(new_config);
+        self.scheduler
+            .set_lanes(ramped_lanes(&config, ramp.as_ref()));
+        if ramp.as_ref().is_some_and(ConcurrencyRamp::is_complete) {
+            *ramp = None;
+        }
+        changes
     }
 
     /// Process a single message
-    async fn process_message(
+    ///
+    /// Nothing is published; the response is returned for the caller to route.
+    pub async fn process_message(
         data: &[u8],
         config: Config,
-        cached_elf: Arc<CachedElf>,
+        backend: Arc<B>,
+        audit: Option<&AuditLog>,
+        quarantine: Option<&Quarantine>,
+        interrupt: &CancellationToken,
         received_at: chrono::DateTime<Utc>,
     ) -> Result<ProverResponse, ServiceError> {
-        // Parse request
-        let request: ProverRequest = serde_json::from_slice(data)?;
+        // Parse request, with its signal aliases renamed, any named signals and
+        // weights profile applied and its validity window set
+        let request = config
+            .signal_aliases
+            .normalize(data)
+            .and_then(|data| ProverRequest::from_json(&data))
+            .and_then(ProverRequest::resolve_inputs)
+            .map(|request| request.resolve_validity(received_at.timestamp() as u64));
+        let mut request = match request {
+            Ok(request) => request,
+            Err(e) => {
+                if let Some(audit) = audit {
+                    audit.record_rejected(&e);
+                }
+                return Err(e);
+            }
+        };
         let request_id = request.request_id.clone();
+        // Out-of-range results are clamped before anything sees them, or fail below
+        let out_of_range = config
+            .signal_policy
+            .apply(&mut request.verification_results)
+            .err();
 
-        info!(request_id = %request_id, "Processing proof request");
+        info!(request_id = %request_id, "Processing proof {}", request.loggable());
+        if let Some(audit) = audit {
+            audit.record_accepted(&request);
+        }
 
-        let started_at = Utc::now();
+        // Requests failed without proving; quarantine counts an attempt, so it goes last
+        let rejection = Self::check_request_id(&request, &config, received_at)
+            .or_else(|| Self::check_labels(&request, received_at))
+            .or_else(|| Self::check_signals(&request, out_of_range, received_at))
+            .or_else(|| Self::check_result_topic(&request, &config, received_at))
+            .or_else(|| Self::check_callback(&request, &config, received_at))
+            .or_else(|| Self::check_named_profile(&request, &config, received_at))
+            .or_else(|| Self::check_expiry(&request, &config, received_at))
+            .or_else(|| Self::check_min_index(&request, &config, received_at))
+            .or_else(|| {
+                quarantine.and_then(|quarantine| {
+                    Self::check_quarantine(&request, quarantine, received_at)
+                })
+            });
+        if let Some(mut response) = rejection {
+            response.correlate(&request);
+            response.weights = Some(EchoedWeights::new(&request.public_inputs));
+            if let Some(audit) = audit {
+                audit.record_completed(&request, &response, 0);
+            }
+            return Ok(response);
+        }
 
-        // Create proof generator
-        let output_dir = PathBuf::from(&config.output_dir);
-        let generator = ProofGenerator::new(cached_elf, output_dir);
+        // Give earlier proofs a chance to free memory before starting another
+        if config.memory_wait_secs > 0 {
+            let wait = wait_for_memory(
+                &request_id,
+                config.memory_per_proof_gb,
+                Duration::from_secs(config.memory_wait_secs),
+            );
+            tokio::select! {
+                _ = wait => {}
+                _ = interrupt.cancelled() => {}
+            }
+        }
+
+        let started_at = Utc::now();
 
-        // Generate proof with timeout
-        let proof_timeout = Duration::from_secs(config.proof_timeout_secs);
+        // Generate proof with timeout, extended once for a proof still making progress
+        let policy = config.timeout_policy();
+        let proof_timeout = policy.timeout;
+        // The backend consumes its copy; the private inputs in it are zeroized as soon
+        // as they are written to stdin, not when the proof finishes
         let request_clone = request.clone();
+        // Retries stay within the timeout
+        let context = Arc::new(ProofContext::new(
+            std::time::Instant::now() + proof_timeout,
+            config.prove_retry_attempts,
+        ));
+        let proof_context = context.clone();
 
-        let result = timeout(proof_timeout, async move {
-            tokio::task::spawn_blocking(move || generator.generate_proof(request_clone))
-                .await
-                .map_err(|e| ServiceError::ProofGeneration(format!("Task join error: {}", e)))?
-        })
-        .await;
+        // The backend logs in the request's span too
+        let span = tracing::Span::current();
+        let proving = async move {
+            tokio::task::spawn_blocking(move || {
+                span.in_scope(|| backend.generate_proof(request_clone, &proof_context))
+            })
+            .await
+            .map_err(|e| ServiceError::ProofGeneration(format!("Task join error: {}", e)))?
+        };
+        let proof = await_proof(&request_id, proving, &policy, &context);
+
+        // The proving thread cannot be stopped; on interrupt it is abandoned and the
+        // process exits without it
+        let result = tokio::select! {
+            result = proof => Some(result),
+            _ = interrupt.cancelled() => None,
+        };
 
         let completed_at = Utc::now();
         let duration_ms = (completed_at - received_at).num_milliseconds() as u64;
 
-        match result {
-            Ok(Ok(proof_data)) => {
+        // Partial when the proof timed out or was interrupted while still running
+        let usage = context.usage();
+        let mut metrics = ProofMetrics {
+            received_at: received_at.to_rfc3339(),
+            started_at: started_at.to_rfc3339(),
+            completed_at: completed_at.to_rfc3339(),
+            duration_ms,
+            attempts: Some(context.attempts()),
+            queue_wait_ms: None,
+            usage,
+            cost_units: config.cost_formula.cost_units(&usage),
+            progress: None,
+        };
+
+        let mut response = match result {
+            Some(Ok(Ok(mut proof_data))) => {
+                proof_data.weights_profile = request.weights_profile.clone();
                 info!(
                     request_id = %request_id,
                     duration_ms = duration_ms,
                     "Proof generated successfully"
                 );
 
-                let metrics = ProofMetrics {
-                    received_at: received_at.to_rfc3339(),
-                    started_at: started_at.to_rfc3339(),
-                    completed_at: completed_at.to_rfc3339(),
-                    duration_ms,
-                };
-
-                Ok(ProverResponse::success(request_id, proof_data, metrics))
+                ProverResponse::success(request_id, proof_data, metrics)
             }
-            Ok(Err(e)) => {
+            Some(Ok(Err(e))) => {
                 error!(request_id = %request_id, "Proof generation failed: {}", e);
 
-                let metrics = ProofMetrics {
-                    received_at: received_at.to_rfc3339(),
-                    started_at: started_at.to_rfc3339(),
-                    completed_at: completed_at.to_rfc3339(),
-                    duration_ms,
-                };
-
-                Ok(ProverResponse::failed(
+                ProverResponse::failed(
                     request_id,
                     ProofError {
                         error_type: e.error_type(),
@@ -234,60 +1452,763 @@ impl ProverService {
                         details: None,
                     },
                     Some(metrics),
-                ))
+                )
             }
-            Err(_) => {
+            Some(Err(timed_out)) => {
+                let waited_secs = timed_out.waited(&policy).as_secs();
+                let last_progress = match &timed_out.progress {
+                    Some(progress) => format!(
+                        "last progress in phase {} {}ms before",
+                        progress.phase, progress.idle_ms
+                    ),
+                    None => "no progress reported".to_string(),
+                };
                 warn!(
                     request_id = %request_id,
-                    timeout_secs = config.proof_timeout_secs,
-                    "Proof generation timed out"
+                    timeout_secs = waited_secs,
+                    "Proof generation timed out, {}",
+                    last_progress
                 );
 
-                let metrics = ProofMetrics {
-                    received_at: received_at.to_rfc3339(),
-                    started_at: started_at.to_rfc3339(),
-                    completed_at: completed_at.to_rfc3339(),
-                    duration_ms,
-                };
-
-                Ok(ProverResponse::timeout(
+                metrics.progress = timed_out.progress;
+                ProverResponse::timeout(
                     request_id,
                     format!(
-                        "Proof generation timed out after {} seconds",
-                        config.proof_timeout_secs
+                        "Proof generation timed out after {} seconds, {}",
+                        waited_secs, last_progress
                     ),
                     Some(metrics),
+                )
+            }
+            None => {
+                warn!(request_id = %request_id, "Proof interrupted by shutdown");
+
+                ProverResponse::interrupted(
+                    request_id,
+                    "Worker shut down before the proof finished".to_string(),
+                    Some(metrics),
+                )
+            }
+        };
+        response.correlate(&request);
+        response.weights = Some(EchoedWeights::new(&request.public_inputs));
+        // The proof failed, not the inputs; some clients can use the index provisionally
+        if config.emit_provisional_index
+            && matches!(response.status, ProofStatus::Failed | ProofStatus::Timeout)
+        {
+            response = response.with_provisional_index(&request);
+        }
+
+        // Failures and timeouts keep counting towards quarantine
+        if let Some(quarantine) = quarantine {
+            if matches!(
+                response.status,
+                ProofStatus::Success | ProofStatus::Interrupted
+            ) {
+                quarantine.clear(&request);
+            }
+        }
+
+        if let Some(audit) = audit {
+            audit.record_completed(&request, &response, duration_ms);
+        }
+
+        Ok(response)
+    }
+
+    /// Fail a request whose ID does not have `request_id_format`
+    fn check_request_id(
+        request: &ProverRequest,
+        config: &Config,
+        received_at: chrono::DateTime<Utc>,
+    ) -> Option<ProverResponse> {
+        let message = config.request_id_format.check(&request.request_id).err()?;
+
+        warn!(request_id = %request.request_id, "Invalid request ID: {}", message);
+        Some(ProverResponse::failed(
+            request.request_id.clone(),
+            ProofError {
+                error_type: "InvalidRequestId".to_string(),
+                message,
+                details: None,
+            },
+            Some(ProofMetrics::rejected(received_at)),
+        ))
+    }
+
+    /// Fail a request whose labels are out of bounds; see `types::check_labels`
+    fn check_labels(
+        request: &ProverRequest,
+        received_at: chrono::DateTime<Utc>,
+    ) -> Option<ProverResponse> {
+        let message = check_labels(request.labels.as_ref()?).err()?;
+
+        warn!(request_id = %request.request_id, "Invalid labels: {}", message);
+        Some(ProverResponse::failed(
+            request.request_id.clone(),
+            ProofError {
+                error_type: "InvalidLabels".to_string(),
+                message,
+                details: None,
+            },
+            Some(ProofMetrics::rejected(received_at)),
+        ))
+    }
+
+    /// Fail a request naming a result topic outside `result_topic_allowlist`
+    ///
+    /// The failure is published to the global result topic.
+    fn check_result_topic(
+        request: &ProverRequest,
+        config: &Config,
+        received_at: chrono::DateTime<Utc>,
+    ) -> Option<ProverResponse> {
+        let topic = request.result_topic.as_deref()?;
+        // The result topic of any endpoint is as good as the global one
+        if topic == config.result_topic
+            || config.endpoints().iter().any(|e| e.result_topic == topic)
+            || config.result_topic_allowlist.iter().any(|t| t == topic)
+        {
+            return None;
+        }
+
+        warn!(request_id = %request.request_id, topic = %topic, "Result topic not allowed");
+        Some(ProverResponse::failed(
+            request.request_id.clone(),
+            ProofError {
+                error_type: "ResultTopicNotAllowed".to_string(),
+                message: format!("Result topic '{}' is not in RESULT_TOPIC_ALLOWLIST", topic),
+                details: None,
+            },
+            Some(ProofMetrics::rejected(received_at)),
+        ))
+    }
+
+    /// Fail a request whose `callback_url` is refused by `check_callback_url`
+    ///
+    /// The failure is published to the result topic only.
+    fn check_callback(
+        request: &ProverRequest,
+        config: &Config,
+        received_at: chrono::DateTime<Utc>,
+    ) -> Option<ProverResponse> {
+        let url = request.callback_url.as_deref()?;
+        let e = check_callback_url(url, &config.callback_policy()).err()?;
+
+        warn!(request_id = %request.request_id, "Callback not allowed: {}", e);
+        Some(ProverResponse::failed(
+            request.request_id.clone(),
+            ProofError {
+                error_type: "CallbackNotAllowed".to_string(),
+                message: e.to_string(),
+                details: None,
+            },
+            Some(ProofMetrics::rejected(received_at)),
+        ))
+    }
+
+    /// Fail a request sending raw weights when `require_named_profile` is set
+    fn check_named_profile(
+        request: &ProverRequest,
+        config: &Config,
+        received_at: chrono::DateTime<Utc>,
+    ) -> Option<ProverResponse> {
+        if !config.require_named_profile || request.weights_profile.is_some() {
+            return None;
+        }
+
+        warn!(request_id = %request.request_id, "Raw weights without a weights profile");
+        Some(ProverResponse::failed(
+            request.request_id.clone(),
+            ProofError {
+                error_type: "NamedProfileRequired".to_string(),
+                message: format!(
+                    "Raw weights are not accepted with REQUIRE_NAMED_PROFILE; send a weights_profile ({})",
+                    WeightsProfile::ids()
+                ),
+                details: None,
+            },
+            Some(ProofMetrics::rejected(received_at)),
+        ))
+    }
+
+    /// Fail a request whose verification results `signal_policy` rejected
+    fn check_signals(
+        request: &ProverRequest,
+        out_of_range: Option<String>,
+        received_at: chrono::DateTime<Utc>,
+    ) -> Option<ProverResponse> {
+        let message = out_of_range?;
+
+        warn!(request_id = %request.request_id, "Signals out of range: {}", message);
+        Some(ProverResponse::failed(
+            request.request_id.clone(),
+            ProofError {
+                error_type: "InvalidSignal".to_string(),
+                message,
+                details: None,
+            },
+            Some(ProofMetrics::rejected(received_at)),
+        ))
+    }
+
+    /// Fail a request older than `max_request_age_secs` instead of proving stale work
+    fn check_expiry(
+        request: &ProverRequest,
+        config: &Config,
+        received_at: chrono::DateTime<Utc>,
+    ) -> Option<ProverResponse> {
+        let max_age_secs = config.max_request_age_secs?;
+        let age = match request.age_at(received_at)? {
+            Ok(age) => age,
+            Err(e) => {
+                warn!(request_id = %request.request_id, "Ignoring invalid submitted_at: {}", e);
+                return None;
+            }
+        };
+        if age.num_seconds() <= max_age_secs as i64 {
+            return None;
+        }
+
+        warn!(
+            request_id = %request.request_id,
+            age_secs = age.num_seconds(),
+            max_age_secs = max_age_secs,
+            "Request expired before proving"
+        );
+        Some(ProverResponse::failed(
+            request.request_id.clone(),
+            ProofError {
+                error_type: "Expired".to_string(),
+                message: format!(
+                    "Request was submitted {}s ago, more than MAX_REQUEST_AGE_SECS ({}s)",
+                    age.num_seconds(),
+                    max_age_secs
+                ),
+                details: None,
+            },
+            Some(ProofMetrics::rejected(received_at)),
+        ))
+    }
+
+    /// Answer a request whose human index is below `min_provable_index` without
+    /// proving it
+    fn check_min_index(
+        request: &ProverRequest,
+        config: &Config,
+        received_at: chrono::DateTime<Utc>,
+    ) -> Option<ProverResponse> {
+        let min_provable_index = config.min_provable_index?;
+        let human_index =
+            calculate_human_index(&request.verification_results, &request.public_inputs);
+        if human_index >= min_provable_index {
+            return None;
+        }
+
+        info!(
+            request_id = %request.request_id,
+            human_index = human_index,
+            min_provable_index = min_provable_index,
+            "Human index below threshold, not proving"
+        );
+        Some(ProverResponse::below_threshold(
+            request.request_id.clone(),
+            human_index,
+            min_provable_index,
+            Some(ProofMetrics::rejected(received_at)),
+        ))
+    }
+
+    /// Count a processing attempt, or fail a request that is or becomes quarantined
+    ///
+    /// Errors of the store itself are logged and the request is proved.
+    fn check_quarantine(
+        request: &ProverRequest,
+        quarantine: &Quarantine,
+        received_at: chrono::DateTime<Utc>,
+    ) -> Option<ProverResponse> {
+        match quarantine.begin(request) {
+            Ok(Admission::Proceed { attempt }) => {
+                debug!(request_id = %request.request_id, attempt = attempt, "Processing attempt");
+                None
+            }
+            Ok(Admission::Quarantined) => {
+                error!(
+                    request_id = %request.request_id,
+                    request_hash = %request_hash(request),
+                    "Request is quarantined after repeated failed attempts"
+                );
+                Some(ProverResponse::failed(
+                    request.request_id.clone(),
+                    ProofError {
+                        error_type: "PoisonRequest".to_string(),
+                        message: "Request failed too many processing attempts and is quarantined"
+                            .to_string(),
+                        details: Some(request_hash(request)),
+                    },
+                    Some(ProofMetrics::rejected(received_at)),
                 ))
             }
+            Err(e) => {
+                error!(request_id = %request.request_id, "Quarantine store error: {}", e);
+                None
+            }
         }
     }
 
-    /// Publish result to result topic
-    async fn publish_result(
-        client: &Client,
-        result_topic: &str,
-        response: &ProverResponse,
-    ) -> Result<(), ServiceError> {
-        let topic = client.topic(result_topic);
-        let publisher = topic.new_publisher(None);
+    /// Sign an attestation binding a successful proof to the request's `binding_id`
+    /// A signing failure is logged; the proof itself is still published
+    #[cfg(feature = "attestation")]
+    async fn attach_attestation(
+        attestor: Option<&Attestor>,
+        mut response: ProverResponse,
+    ) -> ProverResponse {
+        let (Some(attestor), Some(proof_data)) = (attestor, response.proof_data.as_mut()) else {
+            return response;
+        };
+        let Some(binding_id) = response.binding_id.as_deref() else {
+            warn!(
+                request_id = %response.request_id,
+                "Request has no binding_id to attest the proof for; not attested"
+            );
+            return response;
+        };
 
-        let data = serde_json::to_vec(response)?;
-        let message = PubsubMessage {
-            data,
-            ..Default::default()
+        if let Err(e) = attestor.attest(binding_id, proof_data).await {
+            error!(request_id = %response.request_id, "{}", e);
+        }
+        response
+    }
+
+    /// Record a successful proof on-chain under the request's `binding_id` and keep
+    /// the transaction hash
+    /// A failed submission is logged; the proof itself is still published
+    #[cfg(feature = "onchain")]
+    async fn submit_onchain(
+        submitter: Option<&OnchainSubmitter>,
+        mut response: ProverResponse,
+    ) -> ProverResponse {
+        let (Some(submitter), Some(proof_data)) = (submitter, response.proof_data.as_mut()) else {
+            return response;
+        };
+        let Some(binding_id) = response.binding_id.as_deref() else {
+            warn!(
+                request_id = %response.request_id,
+                "Request has no binding_id to record the proof under; not submitted on-chain"
+            );
+            return response;
+        };
+
+        match submitter.submit_proof(proof_data, binding_id).await {
+            Ok(tx_hash) => proof_data.onchain_tx = Some(tx_hash),
+            Err(e) => error!(request_id = %response.request_id, "{}", e),
+        }
+        response
+    }
+
+    /// Compress the proof of a successful response
+    /// Runs after on-chain submission, which needs the raw proof; on failure the
+    /// proof is published uncompressed
+    fn compress_result(
+        encoding: Option<ContentEncoding>,
+        mut response: ProverResponse,
+    ) -> ProverResponse {
+        let (Some(encoding), Some(proof_data)) = (encoding, response.proof_data.as_mut()) else {
+            return response;
         };
 
+        if let Err(e) = proof_data.compress_proof(encoding) {
+            error!(request_id = %response.request_id, "{}", e);
+        }
+        response
+    }
+
+    /// Publish a result message to result topic
+    async fn publish_result(
+        publisher: &Publisher,
+        message: PubsubMessage,
+        request_id: &str,
+    ) -> Result<(), ServiceError> {
         let awaiter = publisher.publish(message).await;
-        awaiter
+        let published = awaiter
             .get()
             .await
-            .map_err(|e| ServiceError::PubSub(format!("Failed to publish: {}", e)))?;
+            .map(|_| ())
+            .map_err(|e| ServiceError::PubSub(format!("Failed to publish: {}", e)));
+        prometheus::record_publish(&published);
+        published?;
+
+        info!(request_id = request_id, "Result published successfully");
+
+        Ok(())
+    }
+}
+
+/// Build the Pub/Sub message a result is published as
+///
+/// With `ordering`, results are keyed by `binding_id`, then `user_id`, falling back
+/// to `request_id`, so an ordering-enabled subscription sees a user's results, and
+/// the results of a redelivered request, in publish order. Each of the response's
+/// `labels` becomes a `label.<key>` attribute.
+pub fn result_message(
+    response: &ProverResponse,
+    schema_version: u32,
+    ordering: bool,
+) -> Result<PubsubMessage, ServiceError> {
+    let data = response.encode(schema_version)?;
+    let mut attributes = HashMap::new();
+    attributes.insert("schema_version".to_string(), schema_version.to_string());
+    if let Some(encoding) = response
+        .proof_data
+        .as_ref()
+        .and_then(|proof_data| proof_data.content_encoding)
+    {
+        attributes.insert("content_encoding".to_string(), encoding.to_string());
+    }
+    for (key, value) in response.labels.iter().flatten() {
+        attributes.insert(format!("{}{}", LABEL_ATTRIBUTE_PREFIX, key), value.clone());
+    }
+    let ordering_key = if ordering {
+        response
+            .binding_id
+            .as_ref()
+            .or(response.user_id.as_ref())
+            .unwrap_or(&response.request_id)
+            .clone()
+    } else {
+        String::new()
+    };
+    Ok(PubsubMessage {
+        data,
+        attributes,
+        ordering_key,
+        ..Default::default()
+    })
+}
+
+/// The message a journaled result is published again as
+///
+/// The data, attributes and ordering key are the ones first published, with the
+/// attributes `republished` set to `true` and `republished_at` to `at` (RFC 3339).
+pub fn republished_message(result: &JournaledResult, at: DateTime<Utc>) -> PubsubMessage {
+    let mut attributes = result.attributes.clone();
+    attributes.insert("republished".to_string(), "true".to_string());
+    attributes.insert("republished_at".to_string(), at.to_rfc3339());
+    PubsubMessage {
+        data: result.data.clone(),
+        attributes,
+        ordering_key: result.ordering_key.clone(),
+        ..Default::default()
+    }
+}
+
+/// Publishers for the global result topic and each allowlisted topic
+struct ResultPublishers {
+    global: Publisher,
+    allowed: HashMap<String, Publisher>,
+}
+
+impl ResultPublishers {
+    /// Publisher for a request's `result_topic`, falling back to the global topic
+    fn get(&self, topic: Option<&str>) -> &Publisher {
+        topic
+            .and_then(|topic| self.allowed.get(topic))
+            .unwrap_or(&self.global)
+    }
+
+    /// A request's `result_topic` if it is allowlisted; `None` is the global topic
+    fn allowed<'a>(&self, topic: Option<&'a str>) -> Option<&'a str> {
+        topic.filter(|topic| self.allowed.contains_key(*topic))
+    }
+
+    async fn shutdown(&self) {
+        self.global.clone().shutdown().await;
+        for publisher in self.allowed.values() {
+            publisher.clone().shutdown().await;
+        }
+    }
+}
+
+/// Source of `publish_sequence` values
+///
+/// Microseconds since the epoch at publish time, bumped where needed so the values
+/// from one worker strictly increase. Values from different workers are ordered
+/// only as well as their clocks agree.
+#[derive(Default)]
+pub struct PublishSequence {
+    last: AtomicU64,
+}
+
+impl PublishSequence {
+    pub fn next(&self) -> u64 {
+        let now = Utc::now().timestamp_micros().max(0) as u64;
+        let mut last = self.last.load(Ordering::Relaxed);
+        loop {
+            let next = now.max(last + 1);
+            match self
+                .last
+                .compare_exchange_weak(last, next, Ordering::Relaxed, Ordering::Relaxed)
+            {
+                Ok(_) => return next,
+                Err(actual) => last = actual,
+            }
+        }
+    }
+}
+
+/// Wait up to `grace` for `drained`, then call `interrupt` and wait for it anyway
+///
+/// Returns whether everything finished within the grace period. An interrupted
+/// proof publishes an `interrupted` response and is done, so `drained` completes
+/// soon after `interrupt` too.
+pub async fn drain_within(
+    grace: Duration,
+    drained: impl Future<Output = ()>,
+    interrupt: impl FnOnce(),
+) -> bool {
+    tokio::pin!(drained);
+    tokio::select! {
+        _ = &mut drained => return true,
+        _ = tokio::time::sleep(grace) => {}
+    }
+    warn!("Shutdown grace period elapsed, interrupting remaining proofs");
+    interrupt();
+    drained.await;
+    false
+}
+
+/// IDs of recently accepted messages a frontend remembers
+///
+/// Pub/Sub redelivers a message under the same ID, e.g. when its ACK is lost.
+const RECENT_MESSAGES: usize = 10_000;
+
+/// Message IDs, forgetting the oldest beyond a capacity
+struct RecentMessages {
+    capacity: usize,
+    order: VecDeque<String>,
+    ids: HashSet<String>,
+}
+
+impl RecentMessages {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            order: VecDeque::new(),
+            ids: HashSet::new(),
+        }
+    }
 
+    fn contains(&self, id: &str) -> bool {
+        self.ids.contains(id)
+    }
+
+    fn insert(&mut self, id: String) {
+        if !self.ids.insert(id.clone()) {
+            return;
+        }
+        self.order.push_back(id);
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// How often the concurrency ramp checks for a new step
+const RAMP_TICK: Duration = Duration::from_secs(1);
+
+/// Longest the releaser sleeps between checks of the delayed queue
+const DELAYED_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Wait before receiving from an endpoint again after an error
+const RECEIVE_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Wait before a frontend retries forwarding a request
+const FORWARD_RETRY_DELAY: chrono::TimeDelta = chrono::TimeDelta::seconds(5);
+
+/// Lane settings for the permits granted so far
+///
+/// During the warm-up the capacity is what the ramp has granted, so a heavy request
+/// is capped to it instead of waiting for permits that depend on its own success.
+fn ramped_lanes(config: &Config, ramp: Option<&ConcurrencyRamp>) -> Lanes {
+    let mut lanes = config.lanes();
+    if let Some(ramp) = ramp {
+        lanes.capacity = lanes.capacity.min(ramp.granted() as u32);
+    }
+    lanes
+}
+
+/// Snapshot `metrics_log` at the end of every `interval` until `cancel` is cancelled
+///
+/// The last period is snapshotted by `drain`, once the in-flight messages are done.
+async fn snapshot_metrics(
+    metrics_log: Arc<MetricsLog>,
+    interval: Duration,
+    cancel: CancellationToken,
+) {
+    let Ok(interval) = chrono::TimeDelta::from_std(interval) else {
+        return;
+    };
+    loop {
+        let now = Utc::now();
+        let wait = (next_snapshot_at(now, interval) - now)
+            .to_std()
+            .unwrap_or_default();
+        tokio::select! {
+            _ = tokio::time::sleep(wait) => {}
+            _ = cancel.cancelled() => return,
+        }
+        write_metrics_snapshot(&metrics_log);
+    }
+}
+
+/// Write a snapshot of `metrics_log`, logging a failure
+fn write_metrics_snapshot(metrics_log: &MetricsLog) {
+    match metrics_log.snapshot(Utc::now()) {
+        Ok(path) => debug!("Metrics snapshot written to {}", path.display()),
+        Err(e) => error!("Failed to write metrics snapshot: {}", e),
+    }
+}
+
+/// Log what a sweep of the output directory reclaimed
+fn log_sweep(sweep: &Sweep, max_bytes: Option<u64>) {
+    if sweep.removed.is_empty() {
+        debug!(
+            "Output directory sweep removed nothing, {} bytes in use",
+            sweep.usage_bytes
+        );
+    } else {
         info!(
-            request_id = response.request_id,
-            "Result published successfully"
+            "Reclaimed {} bytes from the output directory: {} request directories, {} attempt counts, {} quarantined requests; {} bytes in use",
+            sweep.reclaimed_bytes(),
+            sweep.removed_count(Category::Request),
+            sweep.removed_count(Category::Attempts),
+            sweep.removed_count(Category::Quarantined),
+            sweep.usage_bytes
+        );
+    }
+    if sweep.over_budget {
+        warn!(
+            "Output directory uses {} bytes, over OUTPUT_DIR_MAX_BYTES={:?}, with nothing left it may remove",
+            sweep.usage_bytes, max_bytes
         );
+    }
+}
 
-        Ok(())
+/// Span of a request's processing; without a parsed request, the message's logs
+/// stay outside any span
+fn request_span(request: Option<&ProverRequest>) -> tracing::Span {
+    request.map_or_else(tracing::Span::none, ProverRequest::span)
+}
+
+/// Permits a message occupies while it is proved
+///
+/// Heavier requests take proportionally more of `max_concurrent_proofs`. The cost is
+/// capped at the pool size so an oversized request still runs, alone. Messages that
+/// fail to parse cost 1; they are rejected right after admission.
+pub fn admission_cost(request: Option<&ProverRequest>, max_concurrent_proofs: usize) -> u32 {
+    let cost = request.map_or(1, ProverRequest::cost);
+    cost.min(max_concurrent_proofs as u32)
+}
+
+async fn release_lease(lease: Option<Lease>) {
+    if let Some(lease) = lease {
+        lease.release().await;
     }
 }
+
+/// Decode a work item into the request data to prove and the item
+fn decode_work_item(data: &[u8]) -> Result<(Vec<u8>, WorkItem), ServiceError> {
+    let item = redact::from_json::<WorkItem>(data)?;
+    let data = serde_json::to_vec(&item.request)?;
+    Ok((data, item))
+}
+
+/// Publish a journaled request as a `WorkItem`; returns its request ID
+async fn publish_work(work: &Publisher, item: &DelayedItem) -> Result<String, ServiceError> {
+    let request = ProverRequest::from_json(&item.data)?;
+    let request_id = request.request_id.clone();
+    let work_item = WorkItem {
+        request,
+        priority: item.priority,
+        forwarded_at: Utc::now().to_rfc3339(),
+    };
+    let message = PubsubMessage {
+        data: serde_json::to_vec(&work_item)?,
+        ..Default::default()
+    };
+    work.publish(message)
+        .await
+        .get()
+        .await
+        .map_err(|e| ServiceError::PubSub(format!("Failed to publish work item: {}", e)))?;
+    Ok(request_id)
+}
+
+/// Request `data` with its signal aliases renamed, for every stage to parse alike
+///
+/// Data that cannot be renamed is kept as received, for `process_message` to
+/// reject as malformed.
+fn normalize_aliases(config: &Config, data: &[u8]) -> Vec<u8> {
+    config
+        .signal_aliases
+        .normalize(data)
+        .map(|data| data.into_owned())
+        .unwrap_or_else(|_| data.to_vec())
+}
+
+/// Priority from the `priority` attribute, for requests that do not set it in the body
+fn message_priority(message: &PubsubMessage) -> Option<Priority> {
+    let priority = message.attributes.get("priority")?;
+    match priority.parse() {
+        Ok(priority) => Some(priority),
+        Err(e) => {
+            warn!(message_id = %message.message_id, "Ignoring priority attribute: {}", e);
+            None
+        }
+    }
+}
+
+/// Whether the subscription of `endpoint` can be received from
+///
+/// Checked with `subscriptions.get`, so an endpoint in a project the service
+/// account has no access to counts as unhealthy.
+async fn is_healthy(endpoint: &PubsubEndpoint, subscription: &Subscription) -> bool {
+    match subscription.exists(None).await {
+        Ok(true) => true,
+        Ok(false) => {
+            warn!(endpoint = %endpoint, "Pub/Sub subscription does not exist");
+            false
+        }
+        Err(e) => {
+            warn!(endpoint = %endpoint, "Pub/Sub endpoint health check failed: {}", e);
+            false
+        }
+    }
+}
+
+/// Create a Pub/Sub client, skipping authentication when `PUBSUB_EMULATOR_HOST` is set
+pub async fn pubsub_client() -> Result<Client, ServiceError> {
+    info!("Initializing Google Cloud Pub/Sub client");
+
+    // Check if using emulator (skip auth in emulator mode)
+    let is_emulator = std::env::var("PUBSUB_EMULATOR_HOST").is_ok();
+    if is_emulator {
+        info!("Emulator mode detected, skipping authentication");
+    }
+
+    // Create Pub/Sub client (skip auth for emulator)
+    let client_config = if is_emulator {
+        ClientConfig::default()
+    } else {
+        ClientConfig::default()
+            .with_auth()
+            .await
+            .map_err(|e| ServiceError::PubSub(format!("Failed to setup auth: {}", e)))?
+    };
+
+    Client::new(client_config)
+        .await
+        .map_err(|e| ServiceError::PubSub(format!("Failed to create Pub/Sub client: {}", e)))
+}
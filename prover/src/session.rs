@@ -0,0 +1,157 @@
+use crate::types::{ProofStatus, ProverResponse};
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::info;
+
+/// What the service did since it started, reported when it shuts down
+pub struct SessionStats {
+    started: Instant,
+    counts: Mutex<Counts>,
+}
+
+#[derive(Default)]
+struct Counts {
+    processed: u64,
+    succeeded: u64,
+    failed: u64,
+    timed_out: u64,
+    interrupted: u64,
+    /// `duration_ms` of every successful proof
+    durations_ms: Vec<u64>,
+    in_flight: usize,
+    peak_in_flight: usize,
+}
+
+impl Default for SessionStats {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            counts: Mutex::default(),
+        }
+    }
+}
+
+impl SessionStats {
+    /// Count a request as in flight until the returned guard is dropped
+    pub fn start(self: &Arc<Self>) -> InFlightRequest {
+        let mut counts = self.counts.lock().unwrap();
+        counts.in_flight += 1;
+        counts.peak_in_flight = counts.peak_in_flight.max(counts.in_flight);
+        InFlightRequest(self.clone())
+    }
+
+    /// Count a request answered with `response`
+    pub fn record(&self, response: &ProverResponse) {
+        let mut counts = self.counts.lock().unwrap();
+        counts.processed += 1;
+        match response.status {
+            ProofStatus::Success => {
+                counts.succeeded += 1;
+                if let Some(metrics) = &response.metrics {
+                    counts.durations_ms.push(metrics.duration_ms);
+                }
+            }
+            ProofStatus::Failed => counts.failed += 1,
+            ProofStatus::Timeout => counts.timed_out += 1,
+            ProofStatus::Interrupted => counts.interrupted += 1,
+            ProofStatus::BelowThreshold => {}
+        }
+    }
+
+    /// Count a request that failed without a response, e.g. one that did not parse
+    pub fn record_error(&self) {
+        let mut counts = self.counts.lock().unwrap();
+        counts.processed += 1;
+        counts.failed += 1;
+    }
+
+    /// The counts so far
+    pub fn report(&self) -> SessionReport {
+        let counts = self.counts.lock().unwrap();
+        SessionReport {
+            uptime_secs: self.started.elapsed().as_secs(),
+            processed: counts.processed,
+            succeeded: counts.succeeded,
+            failed: counts.failed,
+            timed_out: counts.timed_out,
+            interrupted: counts.interrupted,
+            proof_duration_ms: DurationSummary::of(&counts.durations_ms),
+            peak_in_flight: counts.peak_in_flight,
+        }
+    }
+}
+
+/// A request counted as in flight by `SessionStats::start`
+pub struct InFlightRequest(Arc<SessionStats>);
+
+impl Drop for InFlightRequest {
+    fn drop(&mut self) {
+        self.0.counts.lock().unwrap().in_flight -= 1;
+    }
+}
+
+/// Summary of a session, logged at shutdown for capacity planning
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SessionReport {
+    pub uptime_secs: u64,
+    /// Requests answered, whatever their status, and requests that failed unanswered
+    pub processed: u64,
+    pub succeeded: u64,
+    pub failed: u64,
+    pub timed_out: u64,
+    pub interrupted: u64,
+    /// Duration of the successful proofs; `None` without any
+    pub proof_duration_ms: Option<DurationSummary>,
+    /// Most requests processed at once
+    pub peak_in_flight: usize,
+}
+
+impl SessionReport {
+    /// Log the report, one field per figure
+    pub fn log(&self) {
+        let duration = self.proof_duration_ms.as_ref();
+        info!(
+            uptime_secs = self.uptime_secs,
+            processed = self.processed,
+            succeeded = self.succeeded,
+            failed = self.failed,
+            timed_out = self.timed_out,
+            interrupted = self.interrupted,
+            proof_duration_mean_ms = duration.map(|d| d.mean),
+            proof_duration_p50_ms = duration.map(|d| d.p50),
+            proof_duration_p90_ms = duration.map(|d| d.p90),
+            proof_duration_p99_ms = duration.map(|d| d.p99),
+            proof_duration_max_ms = duration.map(|d| d.max),
+            peak_in_flight = self.peak_in_flight,
+            "Shutdown report"
+        );
+    }
+}
+
+/// Mean and nearest-rank percentiles of durations, in milliseconds
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DurationSummary {
+    pub mean: u64,
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+    pub max: u64,
+}
+
+impl DurationSummary {
+    /// Summary of `durations_ms`; `None` when it is empty
+    pub fn of(durations_ms: &[u64]) -> Option<Self> {
+        let mut sorted = durations_ms.to_vec();
+        sorted.sort_unstable();
+        let max = *sorted.last()?;
+        let percentile = |p: usize| sorted[(sorted.len() * p).div_ceil(100).max(1) - 1];
+        Some(Self {
+            mean: sorted.iter().sum::<u64>() / sorted.len() as u64,
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max,
+        })
+    }
+}
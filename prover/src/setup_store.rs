@@ -0,0 +1,197 @@
+use crate::error::ServiceError;
+use crate::prover::SETUP_FILES;
+use human_index_lib::CircuitId;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory of the store, in the output directory
+pub const SETUP_STORE_DIR: &str = "setup";
+
+/// Content-addressed store for Groth16 setup files
+///
+/// Each distinct key file is kept once, as `<output_dir>/setup/<sha256>`. A
+/// circuit's `vm_pk`/`vm_vk` are relative symlinks to these blobs, so circuits
+/// whose setups produced identical keys share them on disk. The setup files of
+/// `CircuitId::HumanIndex` are in the output directory itself, as before the store,
+/// and those of any other circuit in `<output_dir>/<circuit name>`.
+///
+/// Setup files that are still plain files resolve as they are until interned.
+#[derive(Debug, Clone)]
+pub struct SetupStore {
+    output_dir: PathBuf,
+}
+
+impl SetupStore {
+    pub fn new(output_dir: &Path) -> Self {
+        Self {
+            output_dir: output_dir.to_path_buf(),
+        }
+    }
+
+    /// Directory holding the blobs
+    pub fn root(&self) -> PathBuf {
+        self.output_dir.join(SETUP_STORE_DIR)
+    }
+
+    /// Directory holding the setup files of `circuit`
+    pub fn circuit_dir(&self, circuit: CircuitId) -> PathBuf {
+        match circuit {
+            CircuitId::HumanIndex => self.output_dir.clone(),
+            circuit => self.output_dir.join(circuit.name()),
+        }
+    }
+
+    /// Canonical path of the setup file `name` of `circuit`, the blob if interned
+    pub fn resolve(&self, circuit: CircuitId, name: &str) -> Result<PathBuf, ServiceError> {
+        let path = self.circuit_dir(circuit).join(name);
+        path.canonicalize().map_err(|e| {
+            ServiceError::ProofGeneration(format!(
+                "Groth16 setup file {} not found at {}. Run the setup command first. Error: {}",
+                name,
+                path.display(),
+                e
+            ))
+        })
+    }
+
+    /// Move the setup file `name` of `circuit` into the store and link it back
+    ///
+    /// A blob with the same content is reused, so the file then takes no space of
+    /// its own. The file is replaced by its link in one rename, so readers never
+    /// find it missing. Interning a file that already links into the store does
+    /// nothing. Returns the blob path.
+    pub fn intern(&self, circuit: CircuitId, name: &str) -> Result<PathBuf, ServiceError> {
+        let path = self.circuit_dir(circuit).join(name);
+        let root = self.root();
+        fs::create_dir_all(&root)?;
+        let root = root.canonicalize()?;
+
+        if path.symlink_metadata()?.file_type().is_symlink() {
+            let target = path.canonicalize()?;
+            if target.parent() == Some(root.as_path()) {
+                return Ok(target);
+            }
+        }
+
+        let hash = file_sha256(&path)?;
+        let blob = root.join(&hash);
+        if !blob.exists() {
+            // Staged under a temporary name so a blob is never seen half written
+            let staged = root.join(format!(".{}.tmp", hash));
+            if fs::hard_link(&path, &staged).is_err() {
+                fs::copy(&path, &staged)?;
+            }
+            fs::rename(&staged, &blob)?;
+        }
+
+        let link = path.with_file_name(format!(".{}.link", name));
+        let _ = fs::remove_file(&link);
+        std::os::unix::fs::symlink(self.link_target(circuit, &hash), &link)?;
+        fs::rename(&link, &path)?;
+        Ok(blob)
+    }
+
+    /// Intern every setup file of `circuit`; returns the blob paths in
+    /// `SETUP_FILES` order
+    pub fn intern_circuit(&self, circuit: CircuitId) -> Result<Vec<PathBuf>, ServiceError> {
+        SETUP_FILES
+            .iter()
+            .map(|name| self.intern(circuit, name))
+            .collect()
+    }
+
+    /// Remove the links of `circuit` into the store, leaving the blobs
+    ///
+    /// Run before a new setup of the circuit: it would otherwise write its keys
+    /// through the links into blobs other circuits may share.
+    pub fn detach(&self, circuit: CircuitId) -> Result<(), ServiceError> {
+        for name in SETUP_FILES {
+            let path = self.circuit_dir(circuit).join(name);
+            if path
+                .symlink_metadata()
+                .is_ok_and(|metadata| metadata.file_type().is_symlink())
+            {
+                fs::remove_file(&path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Blobs in the store
+    pub fn blobs(&self) -> Vec<PathBuf> {
+        let mut blobs: Vec<PathBuf> = fs::read_dir(self.root())
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|entry| entry.path())
+                    .filter(|path| {
+                        path.file_name()
+                            .and_then(|name| name.to_str())
+                            .is_some_and(|name| !name.starts_with('.'))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        blobs.sort();
+        blobs
+    }
+
+    /// Link target of blob `hash`, relative to the circuit directory, so the links
+    /// still resolve with the output directory mounted elsewhere
+    fn link_target(&self, circuit: CircuitId, hash: &str) -> PathBuf {
+        let up = match circuit {
+            CircuitId::HumanIndex => PathBuf::new(),
+            _ => PathBuf::from(".."),
+        };
+        up.join(SETUP_STORE_DIR).join(hash)
+    }
+}
+
+/// Hex SHA-256 of the file at `path`, read in chunks
+pub fn file_sha256(path: &Path) -> Result<String, ServiceError> {
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut fs::File::open(path)?, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Hard link the setup files of `circuit` into the request directory `output_dir`,
+/// as `prove_evm` expects them
+///
+/// Hard links rather than symlinks, because Docker mounts only the request
+/// directory and a symlink to a file outside it would not resolve in the container.
+/// Files are copied when they cannot be linked, e.g. across filesystems. If any
+/// file fails, the ones already placed are removed again, so the directory is
+/// never left with only some of them.
+pub fn link_setup_files(
+    store: &SetupStore,
+    circuit: CircuitId,
+    output_dir: &Path,
+) -> Result<(), ServiceError> {
+    for (i, name) in SETUP_FILES.iter().enumerate() {
+        if let Err(e) = link_setup_file(store, circuit, output_dir, name) {
+            // Including the failed one, which a failed copy may have left half written
+            for placed in &SETUP_FILES[..=i] {
+                let _ = fs::remove_file(output_dir.join(placed));
+            }
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+fn link_setup_file(
+    store: &SetupStore,
+    circuit: CircuitId,
+    output_dir: &Path,
+    name: &str,
+) -> Result<(), ServiceError> {
+    let source = store.resolve(circuit, name)?;
+    let dest = output_dir.join(name);
+    if fs::hard_link(&source, &dest).is_err() {
+        fs::copy(&source, &dest).map_err(|e| {
+            ServiceError::ProofGeneration(format!("Failed to copy {}: {}", name, e))
+        })?;
+    }
+    Ok(())
+}
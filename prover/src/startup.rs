@@ -0,0 +1,162 @@
+use crate::config::Config;
+use crate::docker::{preflight, DockerDaemon, DockerMemoryCheck};
+use crate::error::ServiceError;
+use crate::prover::{load_and_cache_elf, resolve_setup_files, CachedElf, ProverProfile};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// What kept the service from starting, each with its own process exit code
+///
+/// Lets an orchestrator tell a failure worth retrying (Pub/Sub unreachable) from
+/// one that will fail the same way again (bad configuration).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StartupCategory {
+    /// A setting is missing or invalid, or `OUTPUT_DIR` is not writable
+    Config,
+    /// The guest ELF could not be loaded
+    Artifacts,
+    /// Pub/Sub, or the service's other connections and state, could not be set up
+    #[serde(rename = "pubsub")]
+    PubSub,
+    /// The Docker preflight failed with `DOCKER_PREFLIGHT=fail`
+    SelfTest,
+}
+
+impl StartupCategory {
+    /// Process exit code the service exits with
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Self::Config => 2,
+            Self::Artifacts => 3,
+            Self::PubSub => 4,
+            Self::SelfTest => 5,
+        }
+    }
+
+    /// What an operator should do about it
+    pub fn remediation(self) -> &'static str {
+        match self {
+            Self::Config => {
+                "Fix the setting named in the message; restarting unchanged fails again"
+            }
+            Self::Artifacts => {
+                "Check that ELF_PATH exists and matches ELF_SHA256; rebuild or re-upload the guest"
+            }
+            Self::PubSub => {
+                "Check Pub/Sub credentials, topics and subscriptions; retrying may succeed"
+            }
+            Self::SelfTest => "Give Docker more memory or set DOCKER_PREFLIGHT=warn",
+        }
+    }
+}
+
+/// A startup step that failed, reported on stderr before the process exits
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StartupFailure {
+    pub category: StartupCategory,
+    pub message: String,
+    pub remediation: &'static str,
+}
+
+impl StartupFailure {
+    /// `error` raised by a startup step of `category`
+    ///
+    /// A configuration error is a `Config` failure whichever step raised it, e.g. a
+    /// malformed `gs://` ELF URL.
+    pub fn new(category: StartupCategory, error: ServiceError) -> Self {
+        let category = match error {
+            ServiceError::Config(_) => StartupCategory::Config,
+            _ => category,
+        };
+        Self {
+            category,
+            message: error.to_string(),
+            remediation: category.remediation(),
+        }
+    }
+
+    /// Wrap errors of a startup step of `category`, for `map_err`
+    pub fn during(category: StartupCategory) -> impl Fn(ServiceError) -> Self {
+        move |error| Self::new(category, error)
+    }
+
+    /// Single-line JSON report
+    pub fn report(&self) -> String {
+        serde_json::to_string(self).expect("startup failure serializes")
+    }
+
+    /// Print the report to stderr and exit with the category's code
+    pub fn exit(&self) -> ! {
+        eprintln!("{}", self.report());
+        std::process::exit(self.category.exit_code());
+    }
+}
+
+/// Read the configuration from the environment and check it
+pub fn load_config() -> Result<Config, StartupFailure> {
+    Config::from_env()
+        .map_err(StartupFailure::during(StartupCategory::Config))
+        .and_then(check_config)
+}
+
+/// Validate `config` and make sure its output directory is writable
+pub fn check_config(config: Config) -> Result<Config, StartupFailure> {
+    let fail = StartupFailure::during(StartupCategory::Config);
+    config.validate().map_err(&fail)?;
+
+    // Create output directory if it doesn't exist, and check it is writable
+    let not_writable = |e: std::io::Error| {
+        fail(ServiceError::Config(format!(
+            "Output directory '{}' is not writable: {}",
+            config.output_dir, e
+        )))
+    };
+    std::fs::create_dir_all(&config.output_dir).map_err(not_writable)?;
+    let test_file = Path::new(&config.output_dir).join(".write_test");
+    std::fs::write(&test_file, "test").map_err(not_writable)?;
+    std::fs::remove_file(&test_file).ok();
+    Ok(config)
+}
+
+/// Load the guest ELF, and wait for the setup files of the `evm` profile
+///
+/// Setup files still missing after the retries are only logged, since a volume
+/// that mounts late is reported by the proofs that need it.
+pub async fn load_artifacts(config: &Config) -> Result<Arc<CachedElf>, StartupFailure> {
+    info!("Loading ELF file: {}", config.elf_path);
+    let cached_elf = load_and_cache_elf(
+        &config.elf_path,
+        config.elf_sha256.as_deref(),
+        config.startup_retry(),
+    )
+    .await
+    .map_err(StartupFailure::during(StartupCategory::Artifacts))?;
+    info!("ELF file loaded and cached successfully");
+
+    // Give the setup volume time to mount; proofs report it if it never does
+    if config.prover_profile.needs_setup_files() {
+        let output_dir = Path::new(&config.output_dir);
+        if let Err(e) = resolve_setup_files(output_dir, config.startup_retry()).await {
+            warn!("{}", e);
+        }
+    }
+    Ok(cached_elf)
+}
+
+/// Check that Docker can run the Groth16 wrap, for the `evm` profile
+pub async fn self_test(config: &Config) -> Result<Option<DockerMemoryCheck>, StartupFailure> {
+    match config.prover_profile {
+        ProverProfile::Evm => preflight(
+            &DockerDaemon::from_env(),
+            config.docker_preflight,
+            config.docker_min_memory_gb,
+        )
+        .await
+        .map_err(StartupFailure::during(StartupCategory::SelfTest)),
+        // Fast proofs never start the Groth16 container
+        ProverProfile::Fast => Ok(None),
+    }
+}
@@ -0,0 +1,317 @@
+use crate::config::Config;
+use crate::delayed::{DelayedQueue, DelayedStats, ResultLookup};
+use crate::docker::DockerMemoryCheck;
+use crate::endpoints::{EndpointStatus, Endpoints};
+use crate::error::ServiceError;
+use crate::history::{RecentResponse, ResponseHistory};
+use crate::janitor::{Janitor, OutputDirUsage};
+use crate::provenance::Provenance;
+use crate::prover::WarmUp;
+use crate::scheduler::{LaneDepths, Scheduler};
+use crate::schema::{RequestValidator, Violation};
+use crate::types::ProverRequest;
+use crate::webhook::{verify_signature, SCHEMA_VERSION_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chrono::Utc;
+use serde::Serialize;
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+/// How far the timestamp of a signed request may be from the server's clock
+pub const SIGNATURE_MAX_AGE_SECS: i64 = 300;
+
+/// State shared with the status endpoints
+pub struct StatusState {
+    backend: &'static str,
+    docker: Option<DockerMemoryCheck>,
+    provenance: Provenance,
+    scheduler: Arc<Scheduler>,
+    delayed: Arc<DelayedQueue>,
+    janitor: Arc<Janitor>,
+    endpoints: Arc<Endpoints>,
+    config: Option<Arc<RwLock<Config>>>,
+    history: Option<Arc<ResponseHistory>>,
+    warm_up: Option<WarmUp>,
+    validator: RequestValidator,
+    /// Key requests for results are signed with
+    signing_key: Option<Vec<u8>>,
+}
+
+impl StatusState {
+    pub fn new(
+        backend: &'static str,
+        docker: Option<DockerMemoryCheck>,
+        provenance: Provenance,
+        scheduler: Arc<Scheduler>,
+        delayed: Arc<DelayedQueue>,
+        janitor: Arc<Janitor>,
+        endpoints: Arc<Endpoints>,
+    ) -> Self {
+        Self {
+            backend,
+            docker,
+            provenance,
+            scheduler,
+            delayed,
+            janitor,
+            endpoints,
+            config: None,
+            history: None,
+            warm_up: None,
+            validator: RequestValidator::new(),
+            signing_key: None,
+        }
+    }
+
+    /// Report how the backend warmed up at startup
+    pub fn with_warm_up(mut self, warm_up: Option<WarmUp>) -> Self {
+        self.warm_up = warm_up;
+        self
+    }
+
+    /// Report the service's configuration on `/config`, which is not found without it
+    pub fn with_config(mut self, config: Arc<RwLock<Config>>) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Serve journaled results on `/v1/proofs/{id}/result` to requests signed with
+    /// `key`; the endpoint is not found without it
+    pub fn with_signing_key(mut self, key: Vec<u8>) -> Self {
+        self.signing_key = Some(key);
+        self
+    }
+
+    /// Report the last responses on `/recent`, which is not found without them
+    pub fn with_history(mut self, history: Arc<ResponseHistory>) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Ready unless a preflight found the host unable to prove
+    pub fn is_ready(&self) -> bool {
+        self.docker.as_ref().is_none_or(|docker| docker.ok)
+    }
+}
+
+#[derive(Serialize)]
+struct Readiness<'a> {
+    status: &'static str,
+    backend: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    docker: Option<&'a DockerMemoryCheck>,
+    provenance: &'a Provenance,
+    /// Messages waiting for a proof permit, per lane
+    lanes: LaneDepths,
+    /// Requests that waited longer than `QUEUE_WAIT_WARN_SECS` for a permit
+    long_queue_waits: u64,
+    /// Requests waiting for `not_before`; absent if the queue could not be read
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delayed: Option<DelayedStats>,
+    /// Output directory usage as of the last janitor sweep
+    output_dir: OutputDirUsage,
+    /// Pub/Sub endpoint requests are received from
+    pubsub: EndpointStatus,
+    /// Startup warm-up of the backend; absent if it has none or it failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    warm_up: Option<&'a WarmUp>,
+}
+
+#[derive(Serialize)]
+struct Recent {
+    /// Responses kept, `RECENT_RESPONSES`
+    capacity: usize,
+    /// Oldest first
+    responses: Vec<RecentResponse>,
+}
+
+#[derive(Serialize)]
+struct Validation {
+    valid: bool,
+    violations: Vec<Violation>,
+}
+
+/// Build the status router
+pub fn router(state: Arc<StatusState>) -> Router {
+    Router::new()
+        .route("/readyz", get(readyz))
+        .route("/v1/validate", post(validate).get(example))
+        .route("/config", get(config))
+        .route("/v1/proofs/{id}/result", get(result))
+        .route("/recent", get(recent))
+        .with_state(state)
+}
+
+/// Serve the status endpoints on `port` until `shutdown` is cancelled
+pub async fn serve(
+    port: u16,
+    state: Arc<StatusState>,
+    shutdown: CancellationToken,
+) -> Result<(), ServiceError> {
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    info!("Status server listening on {}", addr);
+
+    axum::serve(listener, router(state))
+        .with_graceful_shutdown(async move { shutdown.cancelled().await })
+        .await?;
+    Ok(())
+}
+
+async fn readyz(State(state): State<Arc<StatusState>>) -> (StatusCode, Json<serde_json::Value>) {
+    let ready = state.is_ready();
+    let body = Readiness {
+        status: if ready { "ready" } else { "degraded" },
+        backend: state.backend,
+        docker: state.docker.as_ref(),
+        provenance: &state.provenance,
+        lanes: state.scheduler.depths(),
+        long_queue_waits: state.scheduler.long_waits(),
+        delayed: state
+            .delayed
+            .stats()
+            .inspect_err(|e| warn!("Failed to read delayed queue: {}", e))
+            .ok(),
+        output_dir: state.janitor.usage(),
+        pubsub: state.endpoints.status(),
+        warm_up: state.warm_up.as_ref(),
+    };
+    let code = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (code, Json(serde_json::to_value(body).unwrap_or_default()))
+}
+
+/// Check a candidate request without enqueuing it
+///
+/// Signal aliases are renamed first, as when the request is received.
+async fn validate(State(state): State<Arc<StatusState>>, body: Bytes) -> Json<serde_json::Value> {
+    let aliases = state
+        .config
+        .as_ref()
+        .map(|config| {
+            config
+                .read()
+                .expect("config lock poisoned")
+                .signal_aliases
+                .clone()
+        })
+        .unwrap_or_default();
+    let violations = match aliases.normalize(&body) {
+        Ok(body) => match serde_json::from_slice(&body) {
+            Ok(request) => state.validator.validate(&request),
+            Err(e) => vec![Violation::invalid_json(&e)],
+        },
+        Err(ServiceError::MalformedRequest(message)) => {
+            vec![Violation::semantic("/verification_results", message)]
+        }
+        Err(e) => vec![Violation::semantic("", e.to_string())],
+    };
+    let body = Validation {
+        valid: violations.is_empty(),
+        violations,
+    };
+    Json(serde_json::to_value(body).unwrap_or_default())
+}
+
+/// A request `POST /v1/validate` accepts, to start a publisher from
+async fn example() -> Json<ProverRequest> {
+    Json(ProverRequest::example())
+}
+
+/// The effective configuration, as last reloaded, with project IDs masked
+async fn config(
+    State(state): State<Arc<StatusState>>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let config = state.config.as_ref().ok_or(StatusCode::NOT_FOUND)?;
+    let config = config.read().expect("config lock poisoned").clone();
+    Ok(Json(serde_json::to_value(config).unwrap_or_default()))
+}
+
+#[derive(Serialize)]
+struct ResultStatus<'a> {
+    request_id: &'a str,
+    status: &'static str,
+}
+
+/// A result from the journal, as it was published
+///
+/// The request is signed like callback deliveries, over `<timestamp>.<path>` with
+/// `CALLBACK_SIGNING_KEY`. `202` while the request is proved, `404` if the journal
+/// does not know it.
+async fn result(
+    State(state): State<Arc<StatusState>>,
+    Path(request_id): Path<String>,
+    uri: Uri,
+    headers: HeaderMap,
+) -> Response {
+    let Some(key) = &state.signing_key else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    if !is_signed(key, &headers, uri.path().as_bytes(), Utc::now().timestamp()) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    let (code, status) = match state.delayed.result(&request_id) {
+        Ok(ResultLookup::Completed(result)) => {
+            let mut response =
+                ([(header::CONTENT_TYPE, "application/json")], result.data).into_response();
+            if let Some(version) = result
+                .attributes
+                .get("schema_version")
+                .and_then(|version| HeaderValue::from_str(version).ok())
+            {
+                response
+                    .headers_mut()
+                    .insert(SCHEMA_VERSION_HEADER, version);
+            }
+            return response;
+        }
+        Ok(ResultLookup::InProgress) => (StatusCode::ACCEPTED, "in_progress"),
+        Ok(ResultLookup::Unknown) => (StatusCode::NOT_FOUND, "unknown"),
+        Err(e) => {
+            error!(request_id = %request_id, "Failed to read result journal: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let body = ResultStatus {
+        request_id: &request_id,
+        status,
+    };
+    (code, Json(body)).into_response()
+}
+
+/// Whether `headers` sign `body` with `key` at a time within `SIGNATURE_MAX_AGE_SECS` of `now`
+fn is_signed(key: &[u8], headers: &HeaderMap, body: &[u8], now: i64) -> bool {
+    let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+    let (Some(timestamp), Some(signature)) = (header(TIMESTAMP_HEADER), header(SIGNATURE_HEADER))
+    else {
+        return false;
+    };
+    let Ok(timestamp) = timestamp.parse::<i64>() else {
+        return false;
+    };
+    (now - timestamp).abs() <= SIGNATURE_MAX_AGE_SECS
+        && verify_signature(key, timestamp, body, signature)
+}
+
+/// The last responses, oldest first, without their proofs
+async fn recent(
+    State(state): State<Arc<StatusState>>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let history = state.history.as_ref().ok_or(StatusCode::NOT_FOUND)?;
+    let body = Recent {
+        capacity: history.capacity(),
+        responses: history.recent(),
+    };
+    Ok(Json(serde_json::to_value(body).unwrap_or_default()))
+}
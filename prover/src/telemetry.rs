@@ -0,0 +1,164 @@
+use crate::error::ServiceError;
+use crate::types::{ProofStatus, ProverRequest, ProverResponse};
+use chrono::{DateTime, DurationRound, TimeDelta, Utc};
+use google_cloud_googleapis::pubsub::v1::PubsubMessage;
+use google_cloud_pubsub::client::Client;
+use google_cloud_pubsub::publisher::Publisher;
+use human_index_lib::MAX_HUMAN_INDEX;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::env;
+use tracing::warn;
+
+/// Number of consecutive index values reported as one bucket
+pub const INDEX_BUCKET_WIDTH: u32 = 32;
+
+/// Configuration for proof telemetry, loaded from environment variables
+#[derive(Debug, Clone)]
+pub struct TelemetryConfig {
+    /// Full path of the Pub/Sub topic receiving telemetry events
+    pub topic: String,
+
+    /// Fraction of requests reported, in 0.0..=1.0
+    pub sample_rate: f64,
+}
+
+impl TelemetryConfig {
+    /// Load telemetry settings; returns `None` when `TELEMETRY_TOPIC` is not set
+    pub fn from_env() -> Result<Option<Self>, ServiceError> {
+        let Ok(topic) = env::var("TELEMETRY_TOPIC") else {
+            return Ok(None);
+        };
+        let project_id = env::var("GCP_PROJECT_ID")
+            .map_err(|_| ServiceError::Config("GCP_PROJECT_ID not set".to_string()))?;
+
+        let sample_rate = env::var("TELEMETRY_SAMPLE_RATE")
+            .unwrap_or_else(|_| "0.1".to_string())
+            .parse::<f64>()
+            .map_err(|e| ServiceError::Config(format!("Invalid TELEMETRY_SAMPLE_RATE: {}", e)))?;
+        if !(0.0..=1.0).contains(&sample_rate) {
+            return Err(ServiceError::Config(
+                "TELEMETRY_SAMPLE_RATE must be between 0 and 1".to_string(),
+            ));
+        }
+
+        Ok(Some(Self {
+            topic: format!("projects/{}/topics/{}", project_id, topic),
+            sample_rate,
+        }))
+    }
+}
+
+/// Which signals a request carried, without their values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignalPresence {
+    /// `recaptcha_score` was above zero
+    pub recaptcha: bool,
+    pub sms: bool,
+    pub bio: bool,
+}
+
+/// Coarse stats of one sampled request
+///
+/// Carries no identifier, score, weight or exact index, and its time is the hour,
+/// so an event cannot be traced back to the request it came from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TelemetryEvent {
+    /// Start of the UTC hour the request completed in (RFC 3339)
+    pub hour: String,
+
+    pub outcome: ProofStatus,
+
+    /// Lowest index of the `INDEX_BUCKET_WIDTH`-wide bucket holding the human
+    /// index, for successful proofs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index_bucket: Option<u32>,
+
+    pub signals: SignalPresence,
+}
+
+impl TelemetryEvent {
+    /// Reduce a completed request to telemetry; `request` has its signals resolved
+    pub fn new(request: &ProverRequest, response: &ProverResponse, now: DateTime<Utc>) -> Self {
+        let results = &request.verification_results;
+        let hour = now
+            .duration_trunc(TimeDelta::hours(1))
+            .expect("an hour fits any timestamp");
+        Self {
+            hour: hour.to_rfc3339(),
+            outcome: response.status.clone(),
+            index_bucket: response
+                .proof_data
+                .as_ref()
+                .map(|proof_data| index_bucket(proof_data.human_index)),
+            signals: SignalPresence {
+                recaptcha: results.recaptcha_score > 0,
+                sms: results.sms_verified != 0,
+                bio: results.bio_verified != 0,
+            },
+        }
+    }
+}
+
+/// Lowest index of the bucket holding `human_index`
+pub fn index_bucket(human_index: u32) -> u32 {
+    human_index.min(MAX_HUMAN_INDEX) / INDEX_BUCKET_WIDTH * INDEX_BUCKET_WIDTH
+}
+
+/// Whether `request_id` falls in a sample of `sample_rate`
+///
+/// Decided by a hash of the ID, so a replayed request is sampled alike.
+pub fn sampled(request_id: &str, sample_rate: f64) -> bool {
+    let digest = Sha256::digest(request_id.as_bytes());
+    let point = u64::from_be_bytes(digest[..8].try_into().expect("slice is 8 bytes"));
+    (point as f64) < sample_rate * u64::MAX as f64
+}
+
+/// Publishes `TelemetryEvent`s for a sample of completed requests
+///
+/// Failures are logged and never fail the request.
+pub struct Telemetry {
+    publisher: Publisher,
+    sample_rate: f64,
+}
+
+impl Telemetry {
+    pub fn new(config: &TelemetryConfig, client: &Client) -> Self {
+        Self {
+            publisher: client.topic(&config.topic).new_publisher(None),
+            sample_rate: config.sample_rate,
+        }
+    }
+
+    /// Publish the event for a completed request, if it is sampled
+    pub async fn record(&self, request: &ProverRequest, response: &ProverResponse) {
+        if !sampled(&request.request_id, self.sample_rate) {
+            return;
+        }
+        // Unresolved signals would report the structured values they override
+        let Ok(request) = request.clone().resolve_inputs() else {
+            return;
+        };
+        let event = TelemetryEvent::new(&request, response, Utc::now());
+        let data = match serde_json::to_vec(&event) {
+            Ok(data) => data,
+            Err(e) => {
+                warn!("Failed to encode telemetry event: {}", e);
+                return;
+            }
+        };
+
+        let message = PubsubMessage {
+            data,
+            ..Default::default()
+        };
+        if let Err(e) = self.publisher.publish(message).await.get().await {
+            warn!("Failed to publish telemetry event: {}", e);
+        }
+    }
+
+    /// Flush pending events
+    pub async fn shutdown(&self) {
+        self.publisher.clone().shutdown().await;
+    }
+}
@@ -1,31 +1,888 @@
-use human_index_lib::{HumanIndexPublicInputs, VerificationResults};
+use crate::compression::ContentEncoding;
+use crate::decimal::{self, DecodedWeights, EchoedWeights};
+use crate::error::{DecodeError, ServiceError};
+use crate::provenance::Provenance;
+use crate::prover::ProverProfile;
+use crate::redact::{self, LoggableRequest};
+use crate::request_builder::ProverRequestBuilder;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, TimeDelta, Utc};
+use human_index_lib::{
+    calculate_human_index, HumanIndexPublicInputs, PublicValues, Validity, VerificationResults,
+    WeightsProfile, SCALE,
+};
+pub use human_index_lib::{Signal, STDIN_ORDER};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt;
+use tracing::warn;
 
 /// Request message received from Pub/Sub
 /// Contains both private inputs (VerificationResults) and public inputs (HumanIndexPublicInputs)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// `Debug` redacts the private inputs; see `LoggableRequest`.
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
 pub struct ProverRequest {
     /// Unique request ID for tracking
+    #[serde(alias = "requestId")]
     pub request_id: String,
 
-    /// Private inputs: verification results that will be proven
+    /// Private inputs: verification results that will be proven; `recaptcha_score`
+    /// may be sent as a decimal in 0.0..=1.0
+    #[serde(deserialize_with = "decimal::verification_results")]
+    #[schemars(with = "decimal::VerificationResultsWire")]
+    #[serde(alias = "verificationResults")]
     pub verification_results: VerificationResults,
 
-    /// Public inputs: weights and expected output
+    /// Public inputs: weights and expected output; weights may be sent as decimals,
+    /// or left out for a `weights_profile`
+    #[serde(deserialize_with = "decimal::public_inputs")]
+    #[schemars(with = "decimal::PublicInputsWire")]
+    #[serde(alias = "publicInputs")]
     pub public_inputs: HumanIndexPublicInputs,
+
+    /// Identifier of the requesting client, recorded in the audit log
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "clientId")]
+    pub client_id: Option<String>,
+
+    /// Number of concurrency permits the request occupies while proving (default 1)
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        alias = "weightClass"
+    )]
+    pub weight_class: Option<u32>,
+
+    /// Original request ID when this request is a replay
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "replayOf")]
+    pub replay_of: Option<String>,
+
+    /// Correlation ID of the upstream system, echoed in logs, audit records and the
+    /// response; unlike `request_id`, never used to recognize a request
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "traceId")]
+    pub trace_id: Option<String>,
+
+    /// ID of the upstream request this one was made for, echoed like `trace_id`
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        alias = "parentRequestId"
+    )]
+    pub parent_request_id: Option<String>,
+
+    /// When the client submitted the request (RFC 3339 timestamp)
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        alias = "submittedAt"
+    )]
+    pub submitted_at: Option<String>,
+
+    /// Subject the human index is computed for, e.g. a user; echoed in the response
+    /// and used as the result ordering key
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "bindingId")]
+    pub binding_id: Option<String>,
+
+    /// User the request is made for, as some clients name it; echoed in the response
+    /// and the result ordering key when there is no `binding_id`
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "userId")]
+    pub user_id: Option<String>,
+
+    /// Tenant the request is scheduled under with `FAIR_SCHEDULING`
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "tenantId")]
+    pub tenant_id: Option<String>,
+
+    /// Cost attribution labels, e.g. `{"team": "risk", "env": "prod"}`, attached to
+    /// the request's log lines and to its result message as `label.<key>` attributes;
+    /// see `check_labels` for the bounds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub labels: Option<BTreeMap<String, String>>,
+
+    /// Topic for this request's result instead of `RESULT_TOPIC`; must be in
+    /// `RESULT_TOPIC_ALLOWLIST`
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        alias = "resultTopic"
+    )]
+    pub result_topic: Option<String>,
+
+    /// URL the result is also POSTed to; its domain must be in
+    /// `CALLBACK_ALLOWED_DOMAINS`
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        alias = "callbackUrl"
+    )]
+    pub callback_url: Option<String>,
+
+    /// Scheduling lane; falls back to the `priority` message attribute, then `normal`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<Priority>,
+
+    /// Earliest time to prove the request (RFC 3339 timestamp); until then it waits
+    /// in the delayed queue
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "notBefore")]
+    pub not_before: Option<String>,
+
+    /// Guest inputs by signal name, e.g. `{"recaptcha": 8000, "sms": 1}`, overriding
+    /// the values in `verification_results` and `public_inputs`; see `Signal`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signals: Option<BTreeMap<String, u32>>,
+
+    /// Named weight set to prove with instead of raw weights, e.g. `"v1"`; see
+    /// `WEIGHTS_PROFILES`
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        alias = "weightsProfile"
+    )]
+    pub weights_profile: Option<String>,
+
+    /// When the proven index was issued (unix seconds); defaults to when the service
+    /// received the request
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "issuedAt")]
+    pub issued_at: Option<u64>,
+
+    /// Until when the proven index is valid (unix seconds); defaults to, and is
+    /// clamped to, `MAX_VALIDITY_SECS` after `issued_at`
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "validUntil")]
+    pub valid_until: Option<u64>,
 }
 
-/// Response message to be published to result topic
+impl fmt::Debug for ProverRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.loggable(), f)
+    }
+}
+
+impl ProverRequest {
+    /// Parse message data; the error names the failing field but never quotes
+    /// the payload, so it is safe to log
+    pub fn from_json(data: &[u8]) -> Result<Self, ServiceError> {
+        redact::from_json(data)
+    }
+
+    /// Build a request from decimal inputs, checked as the service checks it
+    pub fn builder() -> ProverRequestBuilder {
+        ProverRequestBuilder::default()
+    }
+
+    /// A valid request, as shown in the docs and served by `GET /v1/validate`
+    pub fn example() -> Self {
+        Self::builder()
+            .request_id("6f1c2a9e-8d4b-4e3a-9c1f-2b7d5e8a4c10")
+            .score(0.8)
+            .sms_verified(true)
+            .bio_verified(true)
+            .weights([0.15, 0.2, 0.25, 0.4])
+            .build()
+            .expect("example request is valid")
+            .request
+    }
+
+    /// The request for logs, with its private inputs redacted
+    pub fn loggable(&self) -> LoggableRequest<'_> {
+        LoggableRequest(self)
+    }
+
+    /// Apply `signals`, then `weights_profile`, to the structured inputs
+    pub fn resolve_inputs(self) -> Result<Self, ServiceError> {
+        self.resolve_signals()
+            .and_then(Self::resolve_weights_profile)
+            .map_err(|e| ServiceError::MalformedRequest(e.to_string()))
+    }
+
+    /// Apply `signals` to the structured inputs and drop the map
+    ///
+    /// Fails on an unknown signal name, leaving the request unchanged.
+    pub fn resolve_signals(mut self) -> Result<Self, serde_json::Error> {
+        let Some(signals) = self.signals.take() else {
+            return Ok(self);
+        };
+        for (name, &value) in &signals {
+            let signal = Signal::from_name(name).ok_or_else(|| {
+                <serde_json::Error as serde::de::Error>::custom(format!(
+                    "unknown signal '{}', expected one of {}",
+                    name,
+                    Signal::names()
+                ))
+            })?;
+            signal.set(
+                &mut self.verification_results,
+                &mut self.public_inputs,
+                value,
+            );
+        }
+        Ok(self)
+    }
+
+    /// Set the weights from `weights_profile`
+    ///
+    /// Fails on an unknown profile and on raw weights that differ from the profile's.
+    /// Without a profile the raw weights are kept as sent, even all zero. Apply
+    /// `signals` first, since they may set weights.
+    pub fn resolve_weights_profile(mut self) -> Result<Self, serde_json::Error> {
+        let invalid = |message: String| <serde_json::Error as serde::de::Error>::custom(message);
+        let Some(id) = &self.weights_profile else {
+            return Ok(self);
+        };
+        let inputs = &self.public_inputs;
+        let has_weights = [inputs.w1, inputs.w2, inputs.w3, inputs.w4] != [0; 4];
+        let profile = WeightsProfile::from_id(id).ok_or_else(|| {
+            invalid(format!(
+                "unknown weights_profile '{}', expected one of {}",
+                id,
+                WeightsProfile::ids()
+            ))
+        })?;
+        if has_weights && !profile.matches(inputs) {
+            return Err(invalid(format!(
+                "public_inputs weights differ from weights_profile '{}'",
+                id
+            )));
+        }
+        profile.apply(&mut self.public_inputs);
+        Ok(self)
+    }
+
+    /// Validity window to prove, with `now` standing in for a missing `issued_at`
+    ///
+    /// An inverted window is kept as sent, for the guest to reject.
+    pub fn validity(&self, now: u64) -> Validity {
+        let issued_at = self.issued_at.unwrap_or(now);
+        Validity::clamped(issued_at, self.valid_until.unwrap_or(u64::MAX))
+    }
+
+    /// Fill in `issued_at` and `valid_until` as `validity` resolves them
+    pub fn resolve_validity(mut self, now: u64) -> Self {
+        let validity = self.validity(now);
+        self.issued_at = Some(validity.issued_at);
+        self.valid_until = Some(validity.valid_until);
+        self
+    }
+
+    /// Span the request's log lines are recorded in, carrying its identifiers
+    pub fn span(&self) -> tracing::Span {
+        tracing::info_span!(
+            "request",
+            request_id = %self.request_id,
+            trace_id = self.trace_id.as_deref(),
+            parent_request_id = self.parent_request_id.as_deref(),
+            labels = self.labels.as_ref().map(format_labels).as_deref(),
+        )
+    }
+
+    /// Permits needed to prove this request, never less than 1
+    pub fn cost(&self) -> u32 {
+        self.weight_class.unwrap_or(1).max(1)
+    }
+
+    /// `tenant_id`, else the `request_id` prefix before the first `:`, else empty
+    pub fn tenant(&self) -> &str {
+        match &self.tenant_id {
+            Some(tenant_id) => tenant_id,
+            None => self
+                .request_id
+                .split_once(':')
+                .map_or("", |(prefix, _)| prefix),
+        }
+    }
+
+    /// Parsed `not_before`, if the request carries one
+    pub fn not_before_time(&self) -> Option<Result<DateTime<Utc>, chrono::ParseError>> {
+        let not_before = self.not_before.as_deref()?;
+        Some(DateTime::parse_from_rfc3339(not_before).map(|not_before| not_before.to_utc()))
+    }
+
+    /// When a request with a `not_before` after `now` should leave the delayed queue
+    ///
+    /// That is `not_before`, or earlier if the request expires first, so the expiry
+    /// is reported when it happens. `None` when the request is due now.
+    pub fn release_at(
+        &self,
+        max_age_secs: Option<u64>,
+        now: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        let not_before = match self.not_before_time()? {
+            Ok(not_before) => not_before,
+            Err(e) => {
+                warn!(request_id = %self.request_id, "Ignoring invalid not_before: {}", e);
+                return None;
+            }
+        };
+        let expires_at = max_age_secs.and_then(|max_age_secs| self.expires_at(max_age_secs));
+        let release_at = expires_at.map_or(not_before, |expires_at| not_before.min(expires_at));
+        (release_at > now).then_some(release_at)
+    }
+
+    /// First instant at which a request older than `max_age_secs` counts as expired
+    ///
+    /// `None` without a valid `submitted_at`.
+    pub fn expires_at(&self, max_age_secs: u64) -> Option<DateTime<Utc>> {
+        let submitted_at = DateTime::parse_from_rfc3339(self.submitted_at.as_deref()?).ok()?;
+        Some(submitted_at.to_utc() + TimeDelta::seconds(max_age_secs as i64 + 1))
+    }
+
+    /// Time since `submitted_at`, if the request carries one
+    pub fn age_at(&self, now: DateTime<Utc>) -> Option<Result<TimeDelta, chrono::ParseError>> {
+        let submitted_at = self.submitted_at.as_deref()?;
+        let submitted_at = DateTime::parse_from_rfc3339(submitted_at);
+        Some(submitted_at.map(|submitted_at| now - submitted_at.to_utc()))
+    }
+}
+
+/// Most labels a request may carry
+pub const MAX_LABELS: usize = 16;
+
+/// Longest label key or value, in bytes
+pub const MAX_LABEL_LEN: usize = 63;
+
+/// Prefix of the result message attribute each label is copied to
+pub const LABEL_ATTRIBUTE_PREFIX: &str = "label.";
+
+/// Check request labels against the bounds; the error is safe to return to clients
+///
+/// At most `MAX_LABELS` labels. Keys are 1 to `MAX_LABEL_LEN` lowercase ASCII
+/// letters, digits, `_` and `-`, starting with a letter; values are up to
+/// `MAX_LABEL_LEN` of the same characters, so labels are safe as message
+/// attributes and in logs.
+pub fn check_labels(labels: &BTreeMap<String, String>) -> Result<(), String> {
+    if labels.len() > MAX_LABELS {
+        return Err(format!("more than {} labels", MAX_LABELS));
+    }
+    let is_safe = |s: &str| {
+        s.len() <= MAX_LABEL_LEN
+            && s.bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_' || b == b'-')
+    };
+    for (key, value) in labels {
+        if !key.starts_with(|c: char| c.is_ascii_lowercase()) || !is_safe(key) {
+            return Err(format!(
+                "label key '{}' must be 1 to {} lowercase ASCII letters, digits, '_' and '-', \
+                 starting with a letter",
+                key.chars().take(MAX_LABEL_LEN).collect::<String>(),
+                MAX_LABEL_LEN
+            ));
+        }
+        if !is_safe(value) {
+            return Err(format!(
+                "label '{}' must be at most {} lowercase ASCII letters, digits, '_' and '-'",
+                key, MAX_LABEL_LEN
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Labels as one log field, e.g. `env=prod,team=risk`
+fn format_labels(labels: &BTreeMap<String, String>) -> String {
+    labels
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Longest request ID the service proves, in bytes
+pub const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Which request IDs the service proves
+///
+/// Either way an ID is 1 to `MAX_REQUEST_ID_LEN` ASCII letters, digits, `_` and
+/// `-`, with at most one `:` after a tenant prefix, so it is safe in paths and logs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RequestIdFormat {
+    /// A UUID, hyphenated or as 32 hex digits, optionally after a `<tenant>:` prefix
+    #[default]
+    Uuid,
+    /// Any ID
+    Any,
+}
+
+impl RequestIdFormat {
+    /// Check `request_id` against the format; the error is safe to return to clients
+    pub fn check(self, request_id: &str) -> Result<(), String> {
+        if request_id.is_empty() {
+            return Err("request_id is empty".to_string());
+        }
+        if request_id.len() > MAX_REQUEST_ID_LEN {
+            return Err(format!(
+                "request_id is longer than {} characters",
+                MAX_REQUEST_ID_LEN
+            ));
+        }
+        let is_safe = |part: &str| {
+            !part.is_empty()
+                && part
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+        };
+        let safe = match request_id.split_once(':') {
+            Some((tenant, id)) => is_safe(tenant) && is_safe(id),
+            None => is_safe(request_id),
+        };
+        if !safe {
+            return Err(
+                "request_id may only contain ASCII letters, digits, '_' and '-', \
+                 with one ':' after a tenant prefix"
+                    .to_string(),
+            );
+        }
+        if self == Self::Any {
+            return Ok(());
+        }
+        let uuid = request_id
+            .split_once(':')
+            .map_or(request_id, |(_, uuid)| uuid);
+        let hex_groups: Vec<usize> = uuid.split('-').map(str::len).collect();
+        let is_uuid = uuid.chars().all(|c| c == '-' || c.is_ascii_hexdigit())
+            && (hex_groups == [8, 4, 4, 4, 12] || hex_groups == [32]);
+        match is_uuid {
+            true => Ok(()),
+            false => Err(format!(
+                "request_id '{}' is not a UUID; set REQUEST_ID_FORMAT=any to accept other IDs",
+                request_id
+            )),
+        }
+    }
+}
+
+impl std::str::FromStr for RequestIdFormat {
+    type Err = ServiceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "uuid" => Ok(Self::Uuid),
+            "any" => Ok(Self::Any),
+            other => Err(ServiceError::Config(format!(
+                "Invalid REQUEST_ID_FORMAT '{}', expected 'uuid' or 'any'",
+                other
+            ))),
+        }
+    }
+}
+
+/// What the service does with verification results outside the range the formula
+/// expects: `recaptcha_score` above `SCALE`, `sms_verified` or `bio_verified` above 1
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignalPolicy {
+    /// Fail the request with `InvalidSignal`
+    #[default]
+    Reject,
+    /// Prove the request with each such result lowered to its maximum
+    Clamp,
+}
+
+impl SignalPolicy {
+    /// Apply the policy to `results`, clamping them or naming the ones out of range
+    ///
+    /// The error names the fields but not their values, which are private.
+    pub fn apply(self, results: &mut VerificationResults) -> Result<(), String> {
+        let mut out_of_range = Vec::new();
+        if results.recaptcha_score > SCALE {
+            out_of_range.push(format!("recaptcha_score is above SCALE ({})", SCALE));
+        }
+        for (field, value) in [
+            ("sms_verified", results.sms_verified),
+            ("bio_verified", results.bio_verified),
+        ] {
+            if value > 1 {
+                out_of_range.push(format!("{} is not 0 or 1", field));
+            }
+        }
+        if out_of_range.is_empty() {
+            return Ok(());
+        }
+        match self {
+            Self::Reject => Err(out_of_range.join("; ")),
+            Self::Clamp => {
+                results.recaptcha_score = results.recaptcha_score.min(SCALE);
+                results.sms_verified = results.sms_verified.min(1);
+                results.bio_verified = results.bio_verified.min(1);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for SignalPolicy {
+    type Err = ServiceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "reject" => Ok(Self::Reject),
+            "clamp" => Ok(Self::Clamp),
+            other => Err(ServiceError::Config(format!(
+                "Invalid SIGNAL_POLICY '{}', expected 'reject' or 'clamp'",
+                other
+            ))),
+        }
+    }
+}
+
+/// Fields of `verification_results` that `SIGNAL_ALIASES` may map provider names to
+pub const CANONICAL_SIGNAL_FIELDS: [&str; 3] = ["recaptcha_score", "sms_verified", "bio_verified"];
+
+/// Provider names for the verification results, mapped to the fields they stand for
+///
+/// Set with `SIGNAL_ALIASES` as `alias=field` pairs, e.g.
+/// `captcha=recaptcha_score,phone_verified=sms_verified`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SignalAliases(BTreeMap<String, &'static str>);
+
+impl SignalAliases {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The canonical field `alias` stands for
+    pub fn canonical(&self, alias: &str) -> Option<&'static str> {
+        self.0.get(alias).copied()
+    }
+
+    /// Request `data` with its aliased `verification_results` fields renamed
+    ///
+    /// `data` is returned as is when nothing is aliased, or when it is not a request
+    /// object, so that parsing reports it as before. A request sending a field under
+    /// more than one name is malformed.
+    pub fn normalize<'a>(&self, data: &'a [u8]) -> Result<Cow<'a, [u8]>, ServiceError> {
+        if self.0.is_empty() {
+            return Ok(Cow::Borrowed(data));
+        }
+        let Ok(mut request) = serde_json::from_slice::<serde_json::Value>(data) else {
+            return Ok(Cow::Borrowed(data));
+        };
+        let results = ["verification_results", "verificationResults"]
+            .into_iter()
+            .find_map(|key| request.get(key).map(|_| key))
+            .and_then(|key| request.get_mut(key))
+            .and_then(serde_json::Value::as_object_mut);
+        let Some(results) = results else {
+            return Ok(Cow::Borrowed(data));
+        };
+        let aliased: Vec<String> = results
+            .keys()
+            .filter(|key| self.0.contains_key(*key))
+            .cloned()
+            .collect();
+        if aliased.is_empty() {
+            return Ok(Cow::Borrowed(data));
+        }
+        for alias in aliased {
+            let field = self.0[&alias];
+            if results.contains_key(field) {
+                return Err(ServiceError::MalformedRequest(format!(
+                    "verification_results sends {} more than once, as {}",
+                    field, alias
+                )));
+            }
+            let value = results.remove(&alias).expect("aliased key is present");
+            results.insert(field.to_string(), value);
+        }
+        Ok(Cow::Owned(serde_json::to_vec(&request)?))
+    }
+}
+
+impl std::str::FromStr for SignalAliases {
+    type Err = ServiceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut aliases = BTreeMap::new();
+        for pair in s.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+            let (alias, field) = pair.split_once('=').ok_or_else(|| {
+                ServiceError::Config(format!(
+                    "Invalid SIGNAL_ALIASES pair '{}', expected 'alias=field'",
+                    pair
+                ))
+            })?;
+            let (alias, field) = (alias.trim(), field.trim());
+            let field = CANONICAL_SIGNAL_FIELDS
+                .into_iter()
+                .find(|canonical| *canonical == field)
+                .ok_or_else(|| {
+                    ServiceError::Config(format!(
+                        "Invalid SIGNAL_ALIASES field '{}', expected one of {}",
+                        field,
+                        CANONICAL_SIGNAL_FIELDS.join(", ")
+                    ))
+                })?;
+            if alias.is_empty() || CANONICAL_SIGNAL_FIELDS.contains(&alias) {
+                return Err(ServiceError::Config(format!(
+                    "Invalid SIGNAL_ALIASES alias '{}' for {}",
+                    alias, field
+                )));
+            }
+            if aliases.insert(alias.to_string(), field).is_some() {
+                return Err(ServiceError::Config(format!(
+                    "SIGNAL_ALIASES maps '{}' more than once",
+                    alias
+                )));
+            }
+        }
+        Ok(Self(aliases))
+    }
+}
+
+/// A UUID (version 8) derived from `seed`, e.g. to give a replay a request ID of
+/// its own
+pub fn derived_request_id(seed: &str) -> String {
+    let mut bytes: [u8; 16] = Sha256::digest(seed.as_bytes())[..16]
+        .try_into()
+        .expect("SHA-256 is 32 bytes");
+    bytes[6] = (bytes[6] & 0x0f) | 0x80;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = hex::encode(bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// Scheduling lane of a request, most urgent first
+///
+/// Waiting requests in a more urgent lane are always admitted first.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    /// Someone is waiting on the result, e.g. during onboarding
+    Interactive,
+    #[default]
+    Normal,
+    /// Bulk work; admitted only when no other lane is waiting
+    Batch,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 3] = [Priority::Interactive, Priority::Normal, Priority::Batch];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Priority::Interactive => "interactive",
+            Priority::Normal => "normal",
+            Priority::Batch => "batch",
+        }
+    }
+}
+
+impl std::str::FromStr for Priority {
+    type Err = ServiceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "interactive" => Ok(Self::Interactive),
+            "normal" => Ok(Self::Normal),
+            "batch" => Ok(Self::Batch),
+            other => Err(ServiceError::Config(format!(
+                "Unknown priority '{}', expected 'interactive', 'normal' or 'batch'",
+                other
+            ))),
+        }
+    }
+}
+
+/// A validated request a frontend forwards to the workers on `WORK_TOPIC`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkItem {
+    /// The request as received, before its signals are applied
+    pub request: ProverRequest,
+
+    /// Lane resolved by the frontend, including the `priority` message attribute
+    pub priority: Priority,
+
+    /// When the frontend forwarded the request (RFC 3339)
+    pub forwarded_at: String,
+}
+
+impl WorkItem {
+    /// Time the item has waited for a worker, as of `now`
+    pub fn queued_for(&self, now: DateTime<Utc>) -> Option<TimeDelta> {
+        let forwarded_at = DateTime::parse_from_rfc3339(&self.forwarded_at).ok()?;
+        Some(now - forwarded_at.to_utc())
+    }
+}
+
+/// A request to prove every line of a JSON Lines file of `VerificationResults`
+///
+/// `input_uri` and `output_uri` are `gs://` URLs, or `file://` paths with
+/// `BULK_ALLOW_FILE_URIS`. The results, an errors file and a summary are written
+/// under `output_uri`, and a `BulkJobCompletion` is published.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct BulkJob {
+    /// Unique job ID for tracking; the request ID of each line is derived from it
+    #[serde(alias = "requestId")]
+    pub request_id: String,
+
+    /// The JSON Lines file of `VerificationResults` to prove
+    #[serde(alias = "inputUri")]
+    pub input_uri: String,
+
+    /// Prefix the output files are written under
+    #[serde(alias = "outputUri")]
+    pub output_uri: String,
+
+    /// Named weight set every line is proved with; defaults to the newest profile
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        alias = "weightsProfile"
+    )]
+    pub weights_profile: Option<String>,
+}
+
+/// `summary.json` of a bulk job, written next to its results
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BulkJobSummary {
+    pub request_id: String,
+    pub input_uri: String,
+    pub output_uri: String,
+    /// JSON Lines of a `ProverResponse` per proved line, in input order
+    pub results_uri: String,
+    /// JSON Lines of a `BulkLineError` per line that was not proved
+    pub errors_uri: String,
+    /// Non-empty lines read
+    pub lines: usize,
+    pub succeeded: usize,
+    /// Lines proved without success, e.g. failed or timed out
+    pub unsuccessful: usize,
+    /// Lines in the errors file
+    pub rejected: usize,
+    /// SHA-256 of the results file as written, committing to every result in it
+    pub results_sha256: String,
+    /// The result of each proved line
+    pub proofs: Vec<ProofReference>,
+    /// When the job started and completed (RFC 3339)
+    pub started_at: String,
+    pub completed_at: String,
+}
+
+/// Where to find the result of one line of a bulk job
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProofReference {
+    /// Line number in the input file, from 1
+    pub line: usize,
+    /// Request ID the line was proved under, as in its result
+    pub request_id: String,
+    pub status: ProofStatus,
+    /// SHA-256 of the proof, on success
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proof_sha256: Option<String>,
+}
+
+/// A line of a bulk job's input that was not proved
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BulkLineError {
+    /// Line number in the input file, from 1
+    pub line: usize,
+    /// Why the line was not proved; never quotes the line
+    pub error: String,
+}
+
+/// Outcome of a bulk job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BulkJobStatus {
+    /// Every line was proved or recorded in the errors file
+    Completed,
+    /// The job stopped early, e.g. on an unreadable input; see `error`
+    Failed,
+}
+
+/// Message published to the result topic when a bulk job ends
+///
+/// Carries counts and where the output is, never the results themselves.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BulkJobCompletion {
+    pub request_id: String,
+    pub status: BulkJobStatus,
+    pub output_uri: String,
+    /// Where `BulkJobSummary` was written, when the job completed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary_uri: Option<String>,
+    pub lines: usize,
+    pub succeeded: usize,
+    pub unsuccessful: usize,
+    pub rejected: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl BulkJobCompletion {
+    /// Completion of a job whose output was written, with its summary at `summary_uri`
+    pub fn completed(summary: &BulkJobSummary, summary_uri: Option<String>) -> Self {
+        Self {
+            request_id: summary.request_id.clone(),
+            status: BulkJobStatus::Completed,
+            output_uri: summary.output_uri.clone(),
+            summary_uri,
+            lines: summary.lines,
+            succeeded: summary.succeeded,
+            unsuccessful: summary.unsuccessful,
+            rejected: summary.rejected,
+            error: None,
+        }
+    }
+
+    /// Completion of a job that stopped with `error`
+    pub fn failed(job: &BulkJob, error: &ServiceError) -> Self {
+        Self {
+            request_id: job.request_id.clone(),
+            status: BulkJobStatus::Failed,
+            output_uri: job.output_uri.clone(),
+            summary_uri: None,
+            lines: 0,
+            succeeded: 0,
+            unsuccessful: 0,
+            rejected: 0,
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+/// Schema version of the responses this prover publishes
+pub const RESPONSE_SCHEMA_VERSION: u32 = 2;
+
+/// Response message to be published to result topic
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
 pub struct ProverResponse {
+    /// Response schema version; absent in v1 responses
+    #[serde(default = "legacy_schema_version", alias = "schemaVersion")]
+    pub schema_version: u32,
+
     /// Request ID from the original request
+    #[serde(alias = "requestId")]
     pub request_id: String,
 
     /// Status of the proof generation
     pub status: ProofStatus,
 
     /// Proof data if successful
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "proofData")]
     pub proof_data: Option<ProofData>,
 
     /// Error information if failed
@@ -35,37 +892,289 @@ pub struct ProverResponse {
     /// Metrics about the proof generation process
     #[serde(skip_serializing_if = "Option::is_none")]
     pub metrics: Option<ProofMetrics>,
+
+    /// `binding_id` from the request
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "bindingId")]
+    pub binding_id: Option<String>,
+
+    /// `user_id` from the request
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "userId")]
+    pub user_id: Option<String>,
+
+    /// `trace_id` from the request
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "traceId")]
+    pub trace_id: Option<String>,
+
+    /// `parent_request_id` from the request
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        alias = "parentRequestId"
+    )]
+    pub parent_request_id: Option<String>,
+
+    /// `labels` from the request, when within bounds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub labels: Option<BTreeMap<String, String>>,
+
+    /// Increases with publish order; a consumer that sees a lower value than one
+    /// it already applied for the same binding has received a stale result
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        alias = "publishSequence"
+    )]
+    pub publish_sequence: Option<u64>,
+
+    /// Worker and build that produced the response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+
+    /// Human index computed without a proof, on failed and timed-out responses
+    /// when `EMIT_PROVISIONAL_INDEX` is enabled, and on `below_threshold` responses
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        alias = "provisionalIndex"
+    )]
+    pub provisional_index: Option<ProvisionalIndex>,
+
+    /// The request's weights as fixed-point and as decimals
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weights: Option<EchoedWeights>,
+}
+
+/// A human index that no proof backs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ProvisionalIndex {
+    #[serde(alias = "humanIndex")]
+    pub human_index: u32,
+    /// Always `false`; spelled out so consumers cannot mistake it for a proven index
+    pub proven: bool,
 }
 
 /// Status of proof generation
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum ProofStatus {
     Success,
     Failed,
     Timeout,
+    /// The worker shut down before the proof finished
+    Interrupted,
+    /// The human index is below `MIN_PROVABLE_INDEX`, so the request was not proved
+    #[serde(rename = "below_threshold")]
+    BelowThreshold,
+}
+
+impl ProofStatus {
+    /// The status as serialized, e.g. for metric labels
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProofStatus::Success => "success",
+            ProofStatus::Failed => "failed",
+            ProofStatus::Timeout => "timeout",
+            ProofStatus::Interrupted => "interrupted",
+            ProofStatus::BelowThreshold => "below_threshold",
+        }
+    }
 }
 
 /// Proof data generated by the prover
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
 pub struct ProofData {
     /// Base64-encoded proof bytes
     pub proof: String,
 
     /// Base64-encoded public inputs
+    #[serde(alias = "publicInputs")]
     pub public_inputs: String,
 
     /// Base64-encoded verification key
+    #[serde(alias = "verificationKey")]
     pub verification_key: String,
 
     /// Calculated human index output
+    #[serde(alias = "humanIndex")]
     pub human_index: u32,
+
+    /// Hex-encoded SHA-256 commitment to the weights, as committed in the public values
+    #[serde(default, alias = "weightsCommitment")]
+    pub weights_commitment: String,
+
+    /// Hex-encoded EIP-712 signature over a `HumanIndexAttestation`, if enabled
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        alias = "attestationSignature"
+    )]
+    pub attestation_signature: Option<String>,
+
+    /// Expiry (unix seconds) included in the signed attestation
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        alias = "attestationExpiry"
+    )]
+    pub attestation_expiry: Option<u64>,
+
+    /// Hash of the transaction that submitted this proof on-chain, if enabled
+    #[serde(default, skip_serializing_if = "Option::is_none", alias = "onchainTx")]
+    pub onchain_tx: Option<String>,
+
+    /// Compression applied to the proof bytes before base64, if any
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        alias = "contentEncoding"
+    )]
+    pub content_encoding: Option<ContentEncoding>,
+
+    /// `fast` when the proof skipped the Groth16 wrap and cannot be verified on-chain;
+    /// absent for EVM proofs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<ProverProfile>,
+
+    /// The `weights_profile` the weights were resolved from; absent for raw weights
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        alias = "weightsProfile"
+    )]
+    pub weights_profile: Option<String>,
+
+    /// How far the service verified the proof before publishing it; absent for
+    /// `fast` proofs, which have no Groth16 proof to verify
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification: Option<VerificationLevel>,
+
+    /// The committed public values, decoded from `public_inputs` for reading
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decoded_public_values: Option<DecodedPublicValues>,
+}
+
+/// Layout version of `DecodedPublicValues`; bump when a field changes
+pub const DECODED_PUBLIC_VALUES_VERSION: u32 = 1;
+
+/// What a proof's `public_inputs` commit, as the lib decoder reads them
+///
+/// Only for reading: verifiers check the proof against `public_inputs`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct DecodedPublicValues {
+    /// `DECODED_PUBLIC_VALUES_VERSION` the block was written with
+    pub schema_version: u32,
+
+    /// Committed weights
+    pub weights: DecodedWeights,
+
+    /// Index the request expected
+    pub expected_output: u32,
+
+    /// Index the guest computed, reported as `human_index`
+    pub computed_output: u32,
+
+    /// Hex-encoded commitment to the weights
+    pub weights_commitment: String,
+
+    /// Unix seconds the proof is valid from
+    pub issued_at: u64,
+
+    /// Unix seconds the proof is valid until
+    pub valid_until: u64,
+}
+
+impl DecodedPublicValues {
+    pub fn new(public_values: &PublicValues) -> Self {
+        Self {
+            schema_version: DECODED_PUBLIC_VALUES_VERSION,
+            weights: DecodedWeights::new(&public_values.inputs),
+            expected_output: public_values.inputs.expected_output,
+            computed_output: public_values.computed_output,
+            weights_commitment: format!("0x{}", hex::encode(public_values.weights_commitment)),
+            issued_at: public_values.validity.issued_at,
+            valid_until: public_values.validity.valid_until,
+        }
+    }
+}
+
+/// How far a Groth16 proof was checked before it was published
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationLevel {
+    /// The pairing check passed under the worker's `vm_vk`
+    Verified,
+    /// No verifying key was available: the proof has the right number of words,
+    /// its public values decode and it carries a verification key, but the pairing
+    /// check did not run
+    StructurallyVerified,
+}
+
+impl ProofData {
+    /// Compress the proof bytes in place
+    ///
+    /// The proof is left uncompressed when compression would not make it smaller.
+    /// Returns whether the proof was compressed.
+    pub fn compress_proof(&mut self, encoding: ContentEncoding) -> Result<bool, ServiceError> {
+        if self.content_encoding.is_some() {
+            return Ok(false);
+        }
+
+        let raw = self.decode_proof()?;
+        let compressed = encoding.compress(&raw)?;
+        if compressed.len() >= raw.len() {
+            return Ok(false);
+        }
+
+        self.proof = STANDARD.encode(compressed);
+        self.content_encoding = Some(encoding);
+        Ok(true)
+    }
+
+    /// Proof bytes with any `content_encoding` undone
+    pub fn decompressed_proof(&self) -> Result<Vec<u8>, ServiceError> {
+        let bytes = self.decode_proof()?;
+        match self.content_encoding {
+            Some(encoding) => encoding.decompress(&bytes),
+            None => Ok(bytes),
+        }
+    }
+
+    /// The public values the guest committed
+    ///
+    /// `public_inputs` holds them as base64 of the `0x`-prefixed hex pv stream.
+    /// Values failing `PublicValues::check` are rejected. A consumer can compare
+    /// `computed_output` with `human_index` instead of trusting the latter.
+    pub fn public_values(&self) -> Result<PublicValues, DecodeError> {
+        let hex_values = STANDARD.decode(&self.public_inputs)?;
+        let hex_values = String::from_utf8_lossy(&hex_values);
+        let pv_stream = hex::decode(hex_values.trim_start_matches("0x"))?;
+        let public_values: PublicValues = bincode::deserialize(&pv_stream)?;
+        public_values.check()?;
+        Ok(public_values)
+    }
+
+    /// `public_values`, in the shape of `decoded_public_values`
+    pub fn decode_public_values(&self) -> Result<DecodedPublicValues, DecodeError> {
+        self.public_values()
+            .map(|public_values| DecodedPublicValues::new(&public_values))
+    }
+
+    fn decode_proof(&self) -> Result<Vec<u8>, ServiceError> {
+        STANDARD
+            .decode(&self.proof)
+            .map_err(|e| ServiceError::Compression(format!("Invalid base64 proof: {}", e)))
+    }
 }
 
 /// Error information when proof generation fails
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
 pub struct ProofError {
     /// Error type/code
+    #[serde(alias = "errorType")]
     pub error_type: String,
 
     /// Human-readable error message
@@ -77,19 +1186,117 @@ pub struct ProofError {
 }
 
 /// Metrics about the proof generation process
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
 pub struct ProofMetrics {
     /// When the request was received (ISO 8601 timestamp)
+    #[serde(alias = "receivedAt")]
     pub received_at: String,
 
     /// When proof generation started (ISO 8601 timestamp)
+    #[serde(alias = "startedAt")]
     pub started_at: String,
 
     /// When proof generation completed (ISO 8601 timestamp)
+    #[serde(alias = "completedAt")]
     pub completed_at: String,
 
     /// Total duration in milliseconds
+    #[serde(alias = "durationMs")]
     pub duration_ms: u64,
+
+    /// Proving attempts, more than 1 when a transient failure was retried;
+    /// absent when the request was not proved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attempts: Option<u32>,
+
+    /// Time the request waited for a permit before proving started, in
+    /// milliseconds; absent when it was not queued
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queue_wait_ms: Option<u64>,
+
+    /// Resources the proof used, for billing
+    #[serde(flatten)]
+    pub usage: ResourceUsage,
+
+    /// `usage` priced with `COST_FORMULA`; null when a priced figure is missing
+    #[serde(alias = "costUnits")]
+    pub cost_units: Option<f64>,
+
+    /// Progress the proof last reported; present when it timed out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progress: Option<ProofProgress>,
+}
+
+impl ProofMetrics {
+    /// Metrics of a request answered without proving it, completed now
+    pub fn rejected(received_at: DateTime<Utc>) -> Self {
+        let now = Utc::now().to_rfc3339();
+        Self {
+            received_at: received_at.to_rfc3339(),
+            started_at: now.clone(),
+            completed_at: now,
+            duration_ms: 0,
+            attempts: None,
+            queue_wait_ms: None,
+            usage: ResourceUsage::default(),
+            cost_units: None,
+            progress: None,
+        }
+    }
+}
+
+/// Progress a proof last reported before it timed out, to tell a stuck proof from
+/// a slow one
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ProofProgress {
+    /// Phase the backend last reported, e.g. `emulate` or `prove`
+    pub phase: String,
+
+    /// Progress reports over the whole proof
+    pub heartbeats: u32,
+
+    /// Time from the last report to the timeout, in milliseconds
+    #[serde(alias = "idleMs")]
+    pub idle_ms: u64,
+
+    /// Seconds the timeout was extended by, when the proof was still making
+    /// progress at it
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        alias = "extendedSecs"
+    )]
+    pub extended_secs: Option<u64>,
+}
+
+/// Resources a proof used; each is null when it could not be measured
+///
+/// Memory and CPU are for the whole process while the proof ran, so proofs running
+/// at once count each other's usage, and neither covers the Groth16 container.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ResourceUsage {
+    /// Highest resident set size of the prover process
+    #[serde(alias = "peakRssBytes")]
+    pub peak_rss_bytes: Option<u64>,
+
+    /// User plus system CPU time of the prover process
+    #[serde(alias = "cpuTimeMs")]
+    pub cpu_time_ms: Option<u64>,
+
+    /// Wall time spent writing the request to stdin words, before emulation
+    #[serde(alias = "stdinPrepareMs")]
+    pub stdin_prepare_ms: Option<u64>,
+
+    /// Cycles the guest ran for, from an emulation of the request
+    #[serde(alias = "guestCycles")]
+    pub guest_cycles: Option<u64>,
+
+    /// Bytes of proof artifacts written to `OUTPUT_DIR`, excluding the setup files
+    #[serde(alias = "artifactBytesWritten")]
+    pub artifact_bytes_written: Option<u64>,
 }
 
 impl ProverResponse {
@@ -100,11 +1307,21 @@ impl ProverResponse {
         metrics: ProofMetrics,
     ) -> Self {
         Self {
+            schema_version: RESPONSE_SCHEMA_VERSION,
             request_id,
             status: ProofStatus::Success,
             proof_data: Some(proof_data),
             error: None,
             metrics: Some(metrics),
+            binding_id: None,
+            user_id: None,
+            trace_id: None,
+            parent_request_id: None,
+            labels: None,
+            publish_sequence: None,
+            provenance: None,
+            provisional_index: None,
+            weights: None,
         }
     }
 
@@ -115,11 +1332,21 @@ impl ProverResponse {
         metrics: Option<ProofMetrics>,
     ) -> Self {
         Self {
+            schema_version: RESPONSE_SCHEMA_VERSION,
             request_id,
             status: ProofStatus::Failed,
             proof_data: None,
             error: Some(error),
             metrics,
+            binding_id: None,
+            user_id: None,
+            trace_id: None,
+            parent_request_id: None,
+            labels: None,
+            publish_sequence: None,
+            provenance: None,
+            provisional_index: None,
+            weights: None,
         }
     }
 
@@ -130,6 +1357,7 @@ impl ProverResponse {
         metrics: Option<ProofMetrics>,
     ) -> Self {
         Self {
+            schema_version: RESPONSE_SCHEMA_VERSION,
             request_id,
             status: ProofStatus::Timeout,
             proof_data: None,
@@ -139,6 +1367,239 @@ impl ProverResponse {
                 details: None,
             }),
             metrics,
+            binding_id: None,
+            user_id: None,
+            trace_id: None,
+            parent_request_id: None,
+            labels: None,
+            publish_sequence: None,
+            provenance: None,
+            provisional_index: None,
+            weights: None,
+        }
+    }
+
+    /// Create a response for a proof abandoned at shutdown
+    pub fn interrupted(request_id: String, message: String, metrics: Option<ProofMetrics>) -> Self {
+        Self {
+            schema_version: RESPONSE_SCHEMA_VERSION,
+            request_id,
+            status: ProofStatus::Interrupted,
+            proof_data: None,
+            error: Some(ProofError {
+                error_type: "Interrupted".to_string(),
+                message,
+                details: None,
+            }),
+            metrics,
+            binding_id: None,
+            user_id: None,
+            trace_id: None,
+            parent_request_id: None,
+            labels: None,
+            publish_sequence: None,
+            provenance: None,
+            provisional_index: None,
+            weights: None,
+        }
+    }
+
+    /// Create a response for a request whose `human_index` is below
+    /// `min_provable_index`, which is not proved
+    ///
+    /// The index is attached as a provisional index; v1 consumers see a failure.
+    pub fn below_threshold(
+        request_id: String,
+        human_index: u32,
+        min_provable_index: u32,
+        metrics: Option<ProofMetrics>,
+    ) -> Self {
+        Self {
+            schema_version: RESPONSE_SCHEMA_VERSION,
+            request_id,
+            status: ProofStatus::BelowThreshold,
+            proof_data: None,
+            error: Some(ProofError {
+                error_type: "BelowThreshold".to_string(),
+                message: format!(
+                    "Human index {} is below MIN_PROVABLE_INDEX ({})",
+                    human_index, min_provable_index
+                ),
+                details: None,
+            }),
+            metrics,
+            binding_id: None,
+            user_id: None,
+            trace_id: None,
+            parent_request_id: None,
+            labels: None,
+            publish_sequence: None,
+            provenance: None,
+            provisional_index: Some(ProvisionalIndex {
+                human_index,
+                proven: false,
+            }),
+            weights: None,
         }
     }
-}
\ No newline at end of file
+
+    /// Echo the identifiers of `request` besides `request_id`
+    pub fn correlate(&mut self, request: &ProverRequest) {
+        self.binding_id = request.binding_id.clone();
+        self.user_id = request.user_id.clone();
+        self.trace_id = request.trace_id.clone();
+        self.parent_request_id = request.parent_request_id.clone();
+        // Out-of-bounds labels would not fit in the result's attributes
+        self.labels = request
+            .labels
+            .clone()
+            .filter(|labels| check_labels(labels).is_ok());
+    }
+
+    /// Attach the human index of `request`, computed natively and marked unproven
+    pub fn with_provisional_index(mut self, request: &ProverRequest) -> Self {
+        self.provisional_index = Some(ProvisionalIndex {
+            human_index: calculate_human_index(
+                &request.verification_results,
+                &request.public_inputs,
+            ),
+            proven: false,
+        });
+        self
+    }
+
+    /// Decode a published response of any schema version
+    ///
+    /// v1 responses (no `schema_version`) are read into the current shape with the
+    /// newer fields defaulted. Unknown versions are returned as raw JSON.
+    pub fn decode(bytes: &[u8]) -> Result<VersionedResponse, ServiceError> {
+        let raw: serde_json::Value = serde_json::from_slice(bytes)?;
+        let version = raw
+            .get("schema_version")
+            .or_else(|| raw.get("schemaVersion"));
+        let schema_version = match version {
+            None => 1,
+            Some(version) => u32::deserialize(version)?,
+        };
+
+        match schema_version {
+            1 => {
+                let mut response: ProverResponse = serde_json::from_value(raw)?;
+                response.schema_version = 1;
+                Ok(VersionedResponse::V1(response))
+            }
+            2 => Ok(VersionedResponse::V2(serde_json::from_value(raw)?)),
+            schema_version => Ok(VersionedResponse::Unknown {
+                schema_version,
+                raw,
+            }),
+        }
+    }
+
+    /// Serialize as the given schema version
+    ///
+    /// v1 keeps the exact field set and order consumers relied on before versioning;
+    /// fields added since are dropped and `interrupted` and `below_threshold` are
+    /// reported as `failed`.
+    pub fn encode(&self, schema_version: u32) -> Result<Vec<u8>, ServiceError> {
+        match schema_version {
+            1 => Ok(serde_json::to_vec(&ProverResponseV1::from(self))?),
+            RESPONSE_SCHEMA_VERSION => Ok(serde_json::to_vec(self)?),
+            other => Err(ServiceError::Config(format!(
+                "Cannot encode response schema version {}",
+                other
+            ))),
+        }
+    }
+}
+
+/// A decoded response, tagged with the schema version it was published as
+#[derive(Debug, Clone)]
+pub enum VersionedResponse {
+    V1(ProverResponse),
+    V2(ProverResponse),
+    /// A version newer than this decoder understands
+    Unknown {
+        schema_version: u32,
+        raw: serde_json::Value,
+    },
+}
+
+impl VersionedResponse {
+    /// The response, if its version is understood
+    pub fn response(&self) -> Option<&ProverResponse> {
+        match self {
+            Self::V1(response) | Self::V2(response) => Some(response),
+            Self::Unknown { .. } => None,
+        }
+    }
+
+    /// Schema version the response was published as
+    pub fn schema_version(&self) -> u32 {
+        match self {
+            Self::V1(_) => 1,
+            Self::V2(_) => 2,
+            Self::Unknown { schema_version, .. } => *schema_version,
+        }
+    }
+}
+
+fn legacy_schema_version() -> u32 {
+    1
+}
+
+/// v1 wire shape of `ProverResponse`
+#[derive(Serialize)]
+struct ProverResponseV1<'a> {
+    request_id: &'a str,
+    status: ProofStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proof_data: Option<ProofDataV1<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'a ProofError>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metrics: Option<ProofMetricsV1<'a>>,
+}
+
+/// v1 wire shape of `ProofData`
+#[derive(Serialize)]
+struct ProofDataV1<'a> {
+    proof: &'a str,
+    public_inputs: &'a str,
+    verification_key: &'a str,
+    human_index: u32,
+}
+
+/// v1 wire shape of `ProofMetrics`
+#[derive(Serialize)]
+struct ProofMetricsV1<'a> {
+    received_at: &'a str,
+    started_at: &'a str,
+    completed_at: &'a str,
+    duration_ms: u64,
+}
+
+impl<'a> From<&'a ProverResponse> for ProverResponseV1<'a> {
+    fn from(response: &'a ProverResponse) -> Self {
+        Self {
+            request_id: &response.request_id,
+            status: match response.status {
+                ProofStatus::Interrupted | ProofStatus::BelowThreshold => ProofStatus::Failed,
+                ref status => status.clone(),
+            },
+            proof_data: response.proof_data.as_ref().map(|proof_data| ProofDataV1 {
+                proof: &proof_data.proof,
+                public_inputs: &proof_data.public_inputs,
+                verification_key: &proof_data.verification_key,
+                human_index: proof_data.human_index,
+            }),
+            error: response.error.as_ref(),
+            metrics: response.metrics.as_ref().map(|metrics| ProofMetricsV1 {
+                received_at: &metrics.received_at,
+                started_at: &metrics.started_at,
+                completed_at: &metrics.completed_at,
+                duration_ms: metrics.duration_ms,
+            }),
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use crate::error::ServiceError;
+use crate::types::ResourceUsage;
+use serde::Serialize;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tracing::warn;
+
+/// How often a running sampler reads the process figures
+pub const SAMPLE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Clock ticks per second of the CPU times in `/proc/self/stat`, fixed by the
+/// kernel ABI
+const USER_HZ: u64 = 100;
+
+const MIB: f64 = 1024.0 * 1024.0;
+const GIB: f64 = 1024.0 * MIB;
+
+/// Process figures at one instant; `None` where they cannot be read
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProcessReading {
+    pub rss_bytes: Option<u64>,
+    /// User plus system CPU time of all threads so far
+    pub cpu_time: Option<Duration>,
+}
+
+impl ProcessReading {
+    /// Read `/proc/self`; all `None` where it is unavailable, e.g. on macOS
+    pub fn read() -> Self {
+        Self {
+            rss_bytes: std::fs::read_to_string("/proc/self/status")
+                .ok()
+                .and_then(|status| parse_rss_bytes(&status)),
+            cpu_time: std::fs::read_to_string("/proc/self/stat")
+                .ok()
+                .and_then(|stat| parse_cpu_time(&stat)),
+        }
+    }
+}
+
+/// Extract `VmRSS` in bytes from the contents of `/proc/self/status`
+pub fn parse_rss_bytes(status: &str) -> Option<u64> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))
+        .and_then(|value| value.trim().strip_suffix("kB"))
+        .and_then(|kb| kb.trim().parse::<u64>().ok())
+        .map(|kb| kb * 1024)
+}
+
+/// Extract `utime + stime` from the contents of `/proc/self/stat`
+pub fn parse_cpu_time(stat: &str) -> Option<Duration> {
+    // The command name may hold spaces, so count fields after its closing paren;
+    // utime and stime are fields 14 and 15, i.e. 12 and 13 after it
+    let (_, fields) = stat.rsplit_once(')')?;
+    let mut fields = fields.split_whitespace().skip(11);
+    let utime = fields.next()?.parse::<u64>().ok()?;
+    let stime = fields.next()?.parse::<u64>().ok()?;
+    Some(Duration::from_millis((utime + stime) * 1000 / USER_HZ))
+}
+
+/// What a sampler measured between `start` and `finish`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UsageSample {
+    /// Highest resident set size read
+    pub peak_rss_bytes: Option<u64>,
+    /// CPU time the process used in between
+    pub cpu_time_ms: Option<u64>,
+}
+
+/// Background thread reading process figures while a proof runs
+///
+/// The figures are for the whole process, so proofs running at once count each
+/// other's usage. Dropping the sampler without `finish` stops the thread.
+pub struct UsageSampler {
+    read: Arc<dyn Fn() -> ProcessReading + Send + Sync>,
+    start: ProcessReading,
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<Option<u64>>>,
+}
+
+impl UsageSampler {
+    /// Start sampling `/proc/self` every `SAMPLE_INTERVAL`
+    pub fn start() -> Self {
+        Self::start_with(SAMPLE_INTERVAL, ProcessReading::read)
+    }
+
+    /// Start sampling with `read` every `interval`
+    pub fn start_with(
+        interval: Duration,
+        read: impl Fn() -> ProcessReading + Send + Sync + 'static,
+    ) -> Self {
+        let read: Arc<dyn Fn() -> ProcessReading + Send + Sync> = Arc::new(read);
+        let start = read();
+        let (stop, stopped) = mpsc::channel::<()>();
+
+        let thread_read = read.clone();
+        let thread = std::thread::Builder::new()
+            .name("usage-sampler".to_string())
+            .spawn(move || {
+                let mut peak = thread_read().rss_bytes;
+                // Runs until `finish` sends or the sampler is dropped
+                while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                    peak = peak.max(thread_read().rss_bytes);
+                }
+                peak
+            });
+        let thread = match thread {
+            Ok(thread) => Some(thread),
+            Err(e) => {
+                warn!("Failed to start the usage sampler: {}", e);
+                None
+            }
+        };
+
+        Self {
+            read,
+            start,
+            stop: Some(stop),
+            thread,
+        }
+    }
+
+    /// Stop sampling and report the peak memory and CPU time since `start`
+    pub fn finish(mut self) -> UsageSample {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+        let sampled_peak = self
+            .thread
+            .take()
+            .and_then(|thread| thread.join().ok())
+            .flatten();
+
+        let end = (self.read)();
+        let peak_rss_bytes = [self.start.rss_bytes, sampled_peak, end.rss_bytes]
+            .into_iter()
+            .flatten()
+            .max();
+        let cpu_time_ms = match (self.start.cpu_time, end.cpu_time) {
+            (Some(start), Some(end)) => Some(end.saturating_sub(start).as_millis() as u64),
+            _ => None,
+        };
+
+        UsageSample {
+            peak_rss_bytes,
+            cpu_time_ms,
+        }
+    }
+}
+
+/// Total size of the files under `dir`, skipping top-level entries named in `skip`
+///
+/// Unreadable entries are left out.
+pub fn dir_bytes(dir: &Path, skip: &[&str]) -> u64 {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .filter(|entry| !skip.iter().any(|name| entry.file_name() == *name))
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_bytes(&entry.path(), &[]),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// A term of the `COST_FORMULA`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CostTerm {
+    CpuSeconds,
+    PeakRssGib,
+    GuestMcycles,
+    ArtifactMib,
+}
+
+impl CostTerm {
+    /// The term's quantity in `usage`, if it was measured
+    fn quantity(self, usage: &ResourceUsage) -> Option<f64> {
+        match self {
+            CostTerm::CpuSeconds => usage.cpu_time_ms.map(|ms| ms as f64 / 1000.0),
+            CostTerm::PeakRssGib => usage.peak_rss_bytes.map(|bytes| bytes as f64 / GIB),
+            CostTerm::GuestMcycles => usage.guest_cycles.map(|cycles| cycles as f64 / 1e6),
+            CostTerm::ArtifactMib => usage.artifact_bytes_written.map(|bytes| bytes as f64 / MIB),
+        }
+    }
+}
+
+impl FromStr for CostTerm {
+    type Err = ServiceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "cpu_seconds" => Ok(Self::CpuSeconds),
+            "peak_rss_gib" => Ok(Self::PeakRssGib),
+            "guest_mcycles" => Ok(Self::GuestMcycles),
+            "artifact_mib" => Ok(Self::ArtifactMib),
+            other => Err(ServiceError::Config(format!(
+                "Invalid COST_FORMULA term '{}', expected 'cpu_seconds', 'peak_rss_gib', 'guest_mcycles' or 'artifact_mib'",
+                other
+            ))),
+        }
+    }
+}
+
+/// Linear formula turning resource usage into billable `cost_units`
+///
+/// Parsed from `term=rate` pairs, e.g. `cpu_seconds=1,peak_rss_gib=0.5`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CostFormula {
+    pub terms: Vec<(CostTerm, f64)>,
+}
+
+impl Default for CostFormula {
+    fn default() -> Self {
+        Self {
+            terms: vec![(CostTerm::CpuSeconds, 1.0)],
+        }
+    }
+}
+
+impl CostFormula {
+    /// Cost of `usage`; `None` if a term with a rate was not measured
+    pub fn cost_units(&self, usage: &ResourceUsage) -> Option<f64> {
+        self.terms
+            .iter()
+            .filter(|(_, rate)| *rate != 0.0)
+            .map(|(term, rate)| term.quantity(usage).map(|quantity| quantity * rate))
+            .sum()
+    }
+}
+
+impl FromStr for CostFormula {
+    type Err = ServiceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let terms = s
+            .split(',')
+            .map(str::trim)
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (term, rate) = pair.split_once('=').ok_or_else(|| {
+                    ServiceError::Config(format!(
+                        "Invalid COST_FORMULA pair '{}', expected 'term=rate'",
+                        pair
+                    ))
+                })?;
+                let rate = rate.trim().parse::<f64>().map_err(|e| {
+                    ServiceError::Config(format!("Invalid COST_FORMULA rate '{}': {}", rate, e))
+                })?;
+                if !rate.is_finite() || rate < 0.0 {
+                    return Err(ServiceError::Config(format!(
+                        "COST_FORMULA rate for '{}' must be a non-negative number",
+                        term.trim()
+                    )));
+                }
+                Ok((term.trim().parse::<CostTerm>()?, rate))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { terms })
+    }
+}
@@ -0,0 +1,437 @@
+use crate::evm::{
+    build_verify_calldata, decode_calldata, EvmError, VerifyCall, GROTH16_PROOF_WORDS,
+};
+use crate::types::{ProofData, VerificationLevel};
+use ark_bn254::{g1, g2, Bn254, Fq, Fq2, Fr, G1Affine, G1Projective, G2Affine};
+use ark_ec::pairing::Pairing;
+use ark_ec::short_weierstrass::SWCurveConfig;
+use ark_ec::CurveGroup;
+use ark_ff::{BigInteger, Field, PrimeField, Zero};
+use human_index_lib::HumanIndexPublicInputs;
+use std::path::Path;
+use thiserror::Error;
+
+/// Solidity verifier template; the `{{NAME}}` placeholders are filled from the key
+const VERIFIER_TEMPLATE: &str = include_str!("../templates/Groth16Verifier.sol.tmpl");
+
+/// Public inputs the template handles (`riscvVkey` and the public values digest)
+pub const PUBLIC_INPUTS: usize = 2;
+
+// gnark-crypto point encoding flags, stored in the top two bits of the first byte
+const FLAG_MASK: u8 = 0b11 << 6;
+const FLAG_UNCOMPRESSED: u8 = 0b00 << 6;
+const FLAG_COMPRESSED_INFINITY: u8 = 0b01 << 6;
+const FLAG_COMPRESSED_SMALLEST: u8 = 0b10 << 6;
+const FLAG_COMPRESSED_LARGEST: u8 = 0b11 << 6;
+
+/// Errors while exporting the Solidity verifier or verifying a proof
+#[derive(Debug, Error)]
+pub enum VerifierError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid verifying key: {0}")]
+    InvalidKey(String),
+
+    #[error("Unsupported verifying key: {0}")]
+    Unsupported(String),
+
+    #[error("Invalid proof: {0}")]
+    InvalidProof(String),
+
+    #[error("Calldata error: {0}")]
+    Calldata(#[from] EvmError),
+}
+
+/// Affine G1 point
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct G1Point {
+    pub x: Fq,
+    pub y: Fq,
+}
+
+impl G1Point {
+    fn to_affine(self) -> Option<G1Affine> {
+        let point = G1Affine::new_unchecked(self.x, self.y);
+        point.is_on_curve().then_some(point)
+    }
+}
+
+/// Affine G2 point
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct G2Point {
+    pub x: Fq2,
+    pub y: Fq2,
+}
+
+impl G2Point {
+    fn neg(&self) -> Self {
+        Self {
+            x: self.x,
+            y: -self.y,
+        }
+    }
+
+    fn to_affine(self) -> Option<G2Affine> {
+        let point = G2Affine::new_unchecked(self.x, self.y);
+        (point.is_on_curve() && point.is_in_correct_subgroup_assuming_on_curve()).then_some(point)
+    }
+}
+
+/// The parts of a gnark BN254 Groth16 verifying key the Solidity verifier needs
+#[derive(Debug, Clone)]
+pub struct VerifyingKey {
+    pub alpha_g1: G1Point,
+    pub beta_g2: G2Point,
+    pub gamma_g2: G2Point,
+    pub delta_g2: G2Point,
+    /// Constant term followed by one point per public input
+    pub k: Vec<G1Point>,
+}
+
+impl VerifyingKey {
+    /// Parse a verifying key as written by gnark (`vm_vk`)
+    ///
+    /// Accepts both compressed and raw point encodings. Trailing sections
+    /// (commitment keys) are ignored.
+    pub fn from_gnark_bytes(bytes: &[u8]) -> Result<Self, VerifierError> {
+        let mut reader = Reader { bytes, offset: 0 };
+
+        let alpha_g1 = reader.g1()?;
+        let _beta_g1 = reader.g1()?;
+        let beta_g2 = reader.g2()?;
+        let gamma_g2 = reader.g2()?;
+        let _delta_g1 = reader.g1()?;
+        let delta_g2 = reader.g2()?;
+
+        let len = u32::from_be_bytes(reader.take(4)?.try_into().expect("4 bytes")) as usize;
+        let k = (0..len)
+            .map(|_| reader.g1())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            alpha_g1,
+            beta_g2,
+            gamma_g2,
+            delta_g2,
+            k,
+        })
+    }
+
+    /// Read and parse a verifying key file
+    pub fn from_file(path: &Path) -> Result<Self, VerifierError> {
+        Self::from_gnark_bytes(&std::fs::read(path)?)
+    }
+
+    /// Number of public inputs the key verifies
+    pub fn public_inputs(&self) -> usize {
+        self.k.len().saturating_sub(1)
+    }
+
+    /// Check a proof the way `Groth16Verifier.verifyProof` does
+    ///
+    /// `proof` is in the contract's word order and `inputs` are big-endian
+    /// scalars, which must be reduced, as the contract requires.
+    pub fn verify_groth16(
+        &self,
+        proof: &[[u8; 32]; GROTH16_PROOF_WORDS],
+        inputs: &[[u8; 32]],
+    ) -> Result<(), VerifierError> {
+        if inputs.len() != self.public_inputs() {
+            return Err(VerifierError::InvalidProof(format!(
+                "expected {} public inputs, got {}",
+                self.public_inputs(),
+                inputs.len()
+            )));
+        }
+
+        let invalid_key = |what: &str| VerifierError::InvalidKey(format!("{} not on curve", what));
+        let alpha = self
+            .alpha_g1
+            .to_affine()
+            .ok_or_else(|| invalid_key("alpha"))?;
+        let beta = self
+            .beta_g2
+            .to_affine()
+            .ok_or_else(|| invalid_key("beta"))?;
+        let gamma = self
+            .gamma_g2
+            .to_affine()
+            .ok_or_else(|| invalid_key("gamma"))?;
+        let delta = self
+            .delta_g2
+            .to_affine()
+            .ok_or_else(|| invalid_key("delta"))?;
+
+        // L = K0 + sum(input_i * K_i), as in publicInputMSM
+        let mut l = G1Projective::from(self.k[0].to_affine().ok_or_else(|| invalid_key("K0"))?);
+        for (i, (input, point)) in inputs.iter().zip(&self.k[1..]).enumerate() {
+            let scalar = word_to_field::<Fr>(input).ok_or_else(|| {
+                VerifierError::InvalidProof(format!("public input {} not reduced", i))
+            })?;
+            let point = point.to_affine().ok_or_else(|| invalid_key("K"))?;
+            l += point * scalar;
+        }
+
+        let field = |i: usize| {
+            word_to_field::<Fq>(&proof[i])
+                .ok_or_else(|| VerifierError::InvalidProof(format!("element {} not in field", i)))
+        };
+        let a = G1Point {
+            x: field(0)?,
+            y: field(1)?,
+        }
+        .to_affine()
+        .ok_or_else(|| VerifierError::InvalidProof("A not on curve".to_string()))?;
+        let b = G2Point {
+            x: Fq2::new(field(3)?, field(2)?),
+            y: Fq2::new(field(5)?, field(4)?),
+        }
+        .to_affine()
+        .ok_or_else(|| VerifierError::InvalidProof("B not in G2".to_string()))?;
+        let c = G1Point {
+            x: field(6)?,
+            y: field(7)?,
+        }
+        .to_affine()
+        .ok_or_else(|| VerifierError::InvalidProof("C not on curve".to_string()))?;
+
+        // e(A, B) * e(C, -delta) * e(alpha, -beta) * e(L, -gamma) == 1
+        let check =
+            Bn254::multi_pairing([a, c, alpha, l.into_affine()], [b, -delta, -beta, -gamma]);
+        if !check.is_zero() {
+            return Err(VerifierError::InvalidProof(
+                "pairing check failed".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check a `verifyPicoProof` call the way `PicoVerifier.sol` does
+    pub fn verify_call(&self, call: &VerifyCall) -> Result<(), VerifierError> {
+        self.verify_groth16(&call.proof, &call.public_inputs())
+    }
+
+    /// Render `Groth16Verifier.sol` for this key
+    pub fn to_solidity(&self) -> Result<String, VerifierError> {
+        if self.public_inputs() != PUBLIC_INPUTS {
+            return Err(VerifierError::Unsupported(format!(
+                "expected {} public inputs, key has {}",
+                PUBLIC_INPUTS,
+                self.public_inputs()
+            )));
+        }
+
+        // The contract stores -beta, -gamma and -delta so it never negates proof points
+        let beta = self.beta_g2.neg();
+        let gamma = self.gamma_g2.neg();
+        let delta = self.delta_g2.neg();
+
+        let mut values = vec![
+            ("ALPHA_X".to_string(), self.alpha_g1.x),
+            ("ALPHA_Y".to_string(), self.alpha_g1.y),
+        ];
+        for (name, point) in [
+            ("BETA_NEG", beta),
+            ("GAMMA_NEG", gamma),
+            ("DELTA_NEG", delta),
+        ] {
+            values.push((format!("{}_X_0", name), point.x.c0));
+            values.push((format!("{}_X_1", name), point.x.c1));
+            values.push((format!("{}_Y_0", name), point.y.c0));
+            values.push((format!("{}_Y_1", name), point.y.c1));
+        }
+        values.push(("CONSTANT_X".to_string(), self.k[0].x));
+        values.push(("CONSTANT_Y".to_string(), self.k[0].y));
+        for (i, point) in self.k[1..].iter().enumerate() {
+            values.push((format!("PUB_{}_X", i), point.x));
+            values.push((format!("PUB_{}_Y", i), point.y));
+        }
+
+        let mut contract = VERIFIER_TEMPLATE.to_string();
+        for (name, value) in values {
+            let placeholder = format!("{{{{{}}}}}", name);
+            contract = contract.replace(&placeholder, &value.into_bigint().to_string());
+        }
+        Ok(contract)
+    }
+}
+
+/// Regenerate `Groth16Verifier.sol` from an existing verifying key
+pub fn export_verifier(vk_path: &Path, out_path: &Path) -> Result<(), VerifierError> {
+    let contract = VerifyingKey::from_file(vk_path)?.to_solidity()?;
+    if let Some(parent) = out_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(out_path, contract)?;
+    Ok(())
+}
+
+/// Verify a proof through the calldata the contract would receive
+///
+/// Encodes `proof_data` as `verifyPicoProof` calldata, decodes it again and
+/// checks the decoded call against `vk`, so an encoding mismatch between the
+/// service and the Solidity verifier fails here rather than on-chain. The
+/// committed human index must be in range and match `proof_data.human_index`.
+/// Returns the decoded call.
+pub fn verify_proof_data(
+    vk: &VerifyingKey,
+    proof_data: &ProofData,
+) -> Result<VerifyCall, VerifierError> {
+    let call = decode_call(proof_data)?;
+    vk.verify_call(&call)?;
+    check_human_index(&call, proof_data)?;
+    Ok(call)
+}
+
+/// Check what can be checked of a proof without its verifying key
+///
+/// The calldata must round-trip with a proof of `GROTH16_PROOF_WORDS` words and a
+/// 32-byte verification key, the public values must decode and the committed human
+/// index must match `proof_data.human_index`. Says nothing about whether the proof
+/// verifies. Returns the decoded call.
+pub fn check_structure(proof_data: &ProofData) -> Result<VerifyCall, VerifierError> {
+    let call = decode_call(proof_data)?;
+    // The prefix every guest commits; the validity window is newer
+    bincode::deserialize::<(HumanIndexPublicInputs, u32, [u8; 32])>(&call.public_values)
+        .map_err(|e| VerifierError::InvalidProof(format!("Invalid public values: {}", e)))?;
+    check_human_index(&call, proof_data)?;
+    Ok(call)
+}
+
+/// Verify a proof as far as the verifiers at hand allow
+///
+/// With `vk` this is `verify_proof_data`, and the proof is `Verified`. Without one
+/// only `check_structure` runs, and the proof is `StructurallyVerified`.
+pub fn verify_with_fallback(
+    vk: Option<&VerifyingKey>,
+    proof_data: &ProofData,
+) -> Result<VerificationLevel, VerifierError> {
+    match vk {
+        Some(vk) => verify_proof_data(vk, proof_data).map(|_| VerificationLevel::Verified),
+        None => check_structure(proof_data).map(|_| VerificationLevel::StructurallyVerified),
+    }
+}
+
+fn decode_call(proof_data: &ProofData) -> Result<VerifyCall, VerifierError> {
+    let calldata = build_verify_calldata(proof_data)?;
+    Ok(decode_calldata(&calldata)?)
+}
+
+fn check_human_index(call: &VerifyCall, proof_data: &ProofData) -> Result<(), VerifierError> {
+    let human_index = call.human_index()?;
+    if human_index != proof_data.human_index {
+        return Err(VerifierError::InvalidProof(format!(
+            "committed human index {} differs from human_index {}",
+            human_index, proof_data.human_index
+        )));
+    }
+    Ok(())
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], VerifierError> {
+        let end = self.offset + len;
+        let slice = self.bytes.get(self.offset..end).ok_or_else(|| {
+            VerifierError::InvalidKey(format!("truncated at byte {}", self.offset))
+        })?;
+        self.offset = end;
+        Ok(slice)
+    }
+
+    fn g1(&mut self) -> Result<G1Point, VerifierError> {
+        let head = self.take(32)?;
+        let flag = head[0] & FLAG_MASK;
+        let x = fq(head)?;
+
+        if flag == FLAG_UNCOMPRESSED {
+            let y = fq(self.take(32)?)?;
+            return Ok(G1Point { x, y });
+        }
+        check_finite(flag)?;
+
+        let y2 = x.square() * x + g1::Config::COEFF_B;
+        let y = y2
+            .sqrt()
+            .ok_or_else(|| VerifierError::InvalidKey("G1 point not on curve".to_string()))?;
+        let y = if is_largest_fq(y) == (flag == FLAG_COMPRESSED_LARGEST) {
+            y
+        } else {
+            -y
+        };
+        Ok(G1Point { x, y })
+    }
+
+    fn g2(&mut self) -> Result<G2Point, VerifierError> {
+        // gnark writes Fp2 elements as (A1, A0)
+        let head = self.take(64)?;
+        let flag = head[0] & FLAG_MASK;
+        let x = Fq2::new(fq(&head[32..])?, fq(head)?);
+
+        if flag == FLAG_UNCOMPRESSED {
+            let tail = self.take(64)?;
+            let y = Fq2::new(fq(&tail[32..])?, fq(tail)?);
+            return Ok(G2Point { x, y });
+        }
+        check_finite(flag)?;
+
+        let y2 = x.square() * x + g2::Config::COEFF_B;
+        let y = y2
+            .sqrt()
+            .ok_or_else(|| VerifierError::InvalidKey("G2 point not on curve".to_string()))?;
+        let y = if is_largest_fq2(y) == (flag == FLAG_COMPRESSED_LARGEST) {
+            y
+        } else {
+            -y
+        };
+        Ok(G2Point { x, y })
+    }
+}
+
+fn check_finite(flag: u8) -> Result<(), VerifierError> {
+    match flag {
+        FLAG_COMPRESSED_SMALLEST | FLAG_COMPRESSED_LARGEST => Ok(()),
+        FLAG_COMPRESSED_INFINITY => Err(VerifierError::InvalidKey(
+            "unexpected point at infinity".to_string(),
+        )),
+        _ => unreachable!("two-bit flag"),
+    }
+}
+
+/// Parse a big-endian field element, ignoring the flag bits
+fn fq(bytes: &[u8]) -> Result<Fq, VerifierError> {
+    let mut be = [0u8; 32];
+    be.copy_from_slice(&bytes[..32]);
+    be[0] &= !FLAG_MASK;
+
+    let value = Fq::from_be_bytes_mod_order(&be);
+    if value.into_bigint().to_bytes_be() != be {
+        return Err(VerifierError::InvalidKey(
+            "coordinate not in field".to_string(),
+        ));
+    }
+    Ok(value)
+}
+
+/// Parse a big-endian uint256 that must already be reduced
+fn word_to_field<F: PrimeField>(word: &[u8; 32]) -> Option<F> {
+    let value = F::from_be_bytes_mod_order(word);
+    (value.into_bigint().to_bytes_be() == word).then_some(value)
+}
+
+fn is_largest_fq(value: Fq) -> bool {
+    value.into_bigint() > Fq::MODULUS_MINUS_ONE_DIV_TWO
+}
+
+fn is_largest_fq2(value: Fq2) -> bool {
+    if value.c1.is_zero() {
+        is_largest_fq(value.c0)
+    } else {
+        is_largest_fq(value.c1)
+    }
+}
@@ -0,0 +1,398 @@
+use crate::delayed::DelayedQueue;
+use crate::error::ServiceError;
+use hmac::{Hmac, Mac};
+use reqwest::Url;
+use sha2::Sha256;
+use std::env;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::str::FromStr;
+use std::time::Duration;
+use thiserror::Error;
+use tracing::{info, warn};
+
+/// Header carrying the unix time the delivery was signed at
+pub const TIMESTAMP_HEADER: &str = "X-Prover-Timestamp";
+/// Header carrying `sha256=<hex>`, see `signature`
+pub const SIGNATURE_HEADER: &str = "X-Prover-Signature";
+/// Header carrying the schema version of the response, as the `schema_version`
+/// attribute does on Pub/Sub
+pub const SCHEMA_VERSION_HEADER: &str = "X-Prover-Schema-Version";
+
+/// Limit on each delivery attempt, connecting included
+const ATTEMPT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Configuration for result callbacks, loaded from environment variables
+#[derive(Clone)]
+pub struct WebhookConfig {
+    /// Secret key the deliveries are signed with
+    pub signing_key: Vec<u8>,
+}
+
+impl WebhookConfig {
+    /// Load callback settings; returns `None` when `CALLBACK_SIGNING_KEY` is not set
+    pub fn from_env() -> Result<Option<Self>, ServiceError> {
+        match env::var("CALLBACK_SIGNING_KEY") {
+            Ok(key) if !key.is_empty() => Ok(Some(Self {
+                signing_key: key.into_bytes(),
+            })),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Where results may be POSTed and how delivery is retried, see `Config::callback_policy`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallbackPolicy {
+    /// Domains a callback may point to, subdomains included
+    pub allowed_domains: Vec<String>,
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubles with each retry
+    pub backoff: Duration,
+    /// Allow plain HTTP and private network addresses
+    pub allow_insecure: bool,
+}
+
+/// Why a callback URL is refused or a delivery attempt failed
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum CallbackError {
+    #[error("Invalid callback URL: {0}")]
+    InvalidUrl(String),
+
+    #[error("Callback URL must use https")]
+    NotHttps,
+
+    #[error("Callback domain '{0}' is not in CALLBACK_ALLOWED_DOMAINS")]
+    DomainNotAllowed(String),
+
+    #[error("Callback host resolves to the private address {0}")]
+    PrivateAddress(IpAddr),
+
+    #[error("Failed to resolve callback host: {0}")]
+    Resolve(String),
+
+    #[error("Callback request failed: {0}")]
+    Request(String),
+
+    #[error("Callback endpoint answered {0}")]
+    Status(u16),
+}
+
+impl CallbackError {
+    /// Whether a later attempt may succeed; a refused address stays refused
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::Resolve(_) | Self::Request(_) | Self::Status(_))
+    }
+}
+
+/// Check `url` against `policy` before anything is sent to it
+///
+/// Only `https` URLs on an allowed domain pass, plain `http` too when
+/// `allow_insecure` is set. An IP address passes only if listed itself. Where the
+/// host resolves to is checked on each delivery attempt, see `Webhooks::deliver`.
+pub fn check_callback_url(url: &str, policy: &CallbackPolicy) -> Result<Url, CallbackError> {
+    let url = Url::parse(url).map_err(|e| CallbackError::InvalidUrl(e.to_string()))?;
+    match url.scheme() {
+        "https" => {}
+        "http" if policy.allow_insecure => {}
+        _ => return Err(CallbackError::NotHttps),
+    }
+    if !url.username().is_empty() || url.password().is_some() {
+        return Err(CallbackError::InvalidUrl(
+            "credentials are not allowed".to_string(),
+        ));
+    }
+    let host = url
+        .host_str()
+        .ok_or_else(|| CallbackError::InvalidUrl("no host".to_string()))?
+        .trim_end_matches('.')
+        .to_string();
+    let allowed = policy.allowed_domains.iter().any(|domain| {
+        host == *domain
+            || (host.parse::<IpAddr>().is_err()
+                && host
+                    .strip_suffix(domain.as_str())
+                    .is_some_and(|prefix| prefix.ends_with('.')))
+    });
+    if !allowed {
+        return Err(CallbackError::DomainNotAllowed(host));
+    }
+    Ok(url)
+}
+
+/// Whether `ip` is reachable on the public internet
+///
+/// Loopback, private, link-local, shared (CGNAT), unspecified, broadcast,
+/// documentation and multicast addresses are not, nor IPv6 unique local addresses
+/// or IPv4 addresses mapped into IPv6 that are not.
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_public_v4(mapped),
+            None => is_public_v6(ip),
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // 100.64.0.0/10, shared address space
+        || (a == 100 && (64..128).contains(&b))
+        // 0.0.0.0/8, "this network"
+        || a == 0)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    !(ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        // fc00::/7, unique local
+        || (first & 0xfe00) == 0xfc00
+        // fe80::/10, link-local
+        || (first & 0xffc0) == 0xfe80)
+}
+
+/// HMAC-SHA256 of `<timestamp>.<body>`, as sent in `SIGNATURE_HEADER`
+///
+/// Receivers recompute it with the shared key and should reject deliveries whose
+/// timestamp is too old, so a captured delivery cannot be replayed later.
+pub fn signature(key: &[u8], timestamp: i64, body: &[u8]) -> String {
+    format!(
+        "sha256={}",
+        hex::encode(signing_mac(key, timestamp, body).finalize().into_bytes())
+    )
+}
+
+/// Whether `signature` is the `SIGNATURE_HEADER` value for `timestamp` and `body`
+///
+/// Compared in constant time, for requests signed like deliveries are.
+pub fn verify_signature(key: &[u8], timestamp: i64, body: &[u8], signature: &str) -> bool {
+    let Some(Ok(tag)) = signature.strip_prefix("sha256=").map(hex::decode) else {
+        return false;
+    };
+    signing_mac(key, timestamp, body).verify_slice(&tag).is_ok()
+}
+
+fn signing_mac(key: &[u8], timestamp: i64, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+/// Outcome of a callback, as journaled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryStatus {
+    /// Still being attempted, or cut short by a restart
+    Pending,
+    Delivered,
+    /// Every attempt failed, or the address was refused
+    Failed,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Delivered => "delivered",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+impl fmt::Display for DeliveryStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for DeliveryStatus {
+    type Err = ServiceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(Self::Pending),
+            "delivered" => Ok(Self::Delivered),
+            "failed" => Ok(Self::Failed),
+            other => Err(ServiceError::Storage(format!(
+                "Invalid callback status '{}'",
+                other
+            ))),
+        }
+    }
+}
+
+/// Result of `Webhooks::deliver`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Delivery {
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    pub last_error: Option<CallbackError>,
+}
+
+/// POSTs results to the `callback_url` of their requests
+///
+/// Each delivery is signed with `signature` and retried with exponential backoff.
+/// Its status is journaled, so deliveries that failed or were cut short can be
+/// found later. A failed delivery never fails the proof, whose result is published
+/// to the result topic all the same.
+pub struct Webhooks {
+    signing_key: Vec<u8>,
+}
+
+impl Webhooks {
+    pub fn new(config: &WebhookConfig) -> Self {
+        Self {
+            signing_key: config.signing_key.clone(),
+        }
+    }
+
+    /// POST `body`, the response of `request_id`, to `url`, until it is accepted
+    /// or `policy.max_attempts` have failed
+    ///
+    /// `url` must have passed `check_callback_url`. The delivery is journaled in
+    /// `journal` as pending when it starts and with its outcome when it ends.
+    pub async fn deliver(
+        &self,
+        journal: &DelayedQueue,
+        request_id: &str,
+        url: &Url,
+        body: &[u8],
+        schema_version: u32,
+        policy: &CallbackPolicy,
+    ) -> Delivery {
+        let journal_id = journal
+            .begin_callback(request_id, url.as_str())
+            .inspect_err(|e| warn!(request_id = %request_id, "Failed to journal callback: {}", e))
+            .ok();
+
+        let mut delivery = Delivery {
+            status: DeliveryStatus::Failed,
+            attempts: 0,
+            last_error: None,
+        };
+        let mut backoff = policy.backoff;
+        while delivery.attempts < policy.max_attempts.max(1) {
+            if delivery.attempts > 0 {
+                tokio::time::sleep(backoff).await;
+                backoff = backoff.saturating_mul(2);
+            }
+            delivery.attempts += 1;
+            match self.attempt(url, body, schema_version, policy).await {
+                Ok(()) => {
+                    delivery.status = DeliveryStatus::Delivered;
+                    delivery.last_error = None;
+                    break;
+                }
+                Err(e) => {
+                    warn!(
+                        request_id = %request_id,
+                        attempt = delivery.attempts,
+                        "Callback delivery failed: {}", e
+                    );
+                    let retryable = e.is_retryable();
+                    delivery.last_error = Some(e);
+                    if !retryable {
+                        break;
+                    }
+                }
+            }
+        }
+
+        match delivery.status {
+            DeliveryStatus::Delivered => info!(
+                request_id = %request_id,
+                attempts = delivery.attempts,
+                "Result delivered to callback"
+            ),
+            _ => warn!(
+                request_id = %request_id,
+                attempts = delivery.attempts,
+                "Giving up on callback delivery"
+            ),
+        }
+        if let Some(id) = journal_id {
+            let last_error = delivery.last_error.as_ref().map(ToString::to_string);
+            if let Err(e) = journal.finish_callback(
+                id,
+                delivery.status,
+                delivery.attempts,
+                last_error.as_deref(),
+            ) {
+                warn!(request_id = %request_id, "Failed to journal callback: {}", e);
+            }
+        }
+        delivery
+    }
+
+    /// One signed POST of `body` to `url`
+    async fn attempt(
+        &self,
+        url: &Url,
+        body: &[u8],
+        schema_version: u32,
+        policy: &CallbackPolicy,
+    ) -> Result<(), CallbackError> {
+        let addr = resolve(url, policy.allow_insecure).await?;
+        let host = url.host_str().unwrap_or_default();
+        // Connect to the address just checked, so a second lookup cannot swap in
+        // another; redirects are not followed for the same reason
+        let client = reqwest::Client::builder()
+            .resolve(host, addr)
+            .redirect(reqwest::redirect::Policy::none())
+            .timeout(ATTEMPT_TIMEOUT)
+            .build()
+            .map_err(|e| CallbackError::Request(e.to_string()))?;
+
+        let timestamp = chrono::Utc::now().timestamp();
+        let response = client
+            .post(url.clone())
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(
+                SIGNATURE_HEADER,
+                signature(&self.signing_key, timestamp, body),
+            )
+            .header(SCHEMA_VERSION_HEADER, schema_version.to_string())
+            .body(body.to_vec())
+            .send()
+            .await
+            .map_err(|e| CallbackError::Request(e.without_url().to_string()))?;
+        if !response.status().is_success() {
+            return Err(CallbackError::Status(response.status().as_u16()));
+        }
+        Ok(())
+    }
+}
+
+/// Address to connect to for `url`, refusing private ones unless `allow_insecure`
+async fn resolve(url: &Url, allow_insecure: bool) -> Result<SocketAddr, CallbackError> {
+    let host = url.host_str().unwrap_or_default();
+    // IPv6 literals are bracketed in URLs
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let port = url.port_or_known_default().unwrap_or(443);
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| CallbackError::Resolve(e.to_string()))?
+        .collect();
+    if !allow_insecure {
+        // Any private address refuses the host, whichever would be connected to
+        if let Some(private) = addrs.iter().find(|addr| !is_public(addr.ip())) {
+            return Err(CallbackError::PrivateAddress(private.ip()));
+        }
+    }
+    addrs
+        .into_iter()
+        .next()
+        .ok_or_else(|| CallbackError::Resolve(format!("no addresses for {}", host)))
+}
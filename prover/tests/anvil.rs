@@ -0,0 +1,372 @@
+// HumanIndexRegistry on a local anvil node, driven by `OnchainSubmitter` as the
+// service drives it: a proof is recorded under keccak256 of its binding id, and an
+// expired index, another submitter and a tampered proof are refused with their
+// decoded revert reasons. Run with
+// `cargo test -p prover --features anvil --test anvil`; needs `anvil` and `forge`
+// on PATH.
+//
+// The proofs are synthetic: the verifying key is built from a known trapdoor, so a
+// proof can be made for public values with a fresh validity window. Its
+// Groth16Verifier.sol is rendered with `VerifyingKey::to_solidity` and built with
+// the other contracts in a scratch directory.
+
+use alloy_network::TransactionBuilder;
+use alloy_primitives::{Address, Bytes, B256};
+use alloy_provider::{Provider, ProviderBuilder};
+use alloy_rpc_types_eth::TransactionRequest;
+use alloy_signer_local::PrivateKeySigner;
+use alloy_sol_types::{SolCall, SolValue};
+use ark_bn254::{Fr, G1Affine, G2Affine};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{BigInteger, Field, PrimeField};
+use prover::evm::VerifyCall;
+use prover::onchain::{binding_key, HumanIndexRegistry, OnchainConfig, OnchainSubmitter};
+use prover::prover::encode_proof_data;
+use prover::types::ProofData;
+use prover::verifier::{G1Point, G2Point, VerifyingKey};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+const CHAIN_ID: u64 = 31337;
+
+/// anvil's first three development accounts
+const DEPLOYER_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+const SUBMITTER_KEY: &str = "0x59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d";
+const STRANGER_KEY: &str = "0x5de4111afa1a4b94908f83103eb1f1706367c2e68ca870fc3fb9a804cdab365a";
+
+/// `riscvVkey` the registry is deployed for; below the BN254 scalar field modulus
+const RISCV_VKEY: [u8; 32] = [0x01; 32];
+
+/// Inputs, output and weights commitment of testdata/groth16_proof.json, which
+/// predates the validity window
+const PUBLIC_VALUES_PREFIX: &str = "dc050000d0070000c4090000a00f0000f9000000f9000000\
+    bc1bcc071585bc39aa2c15ce83b3db02fa699d8a853b11c99cc8fd8a7bde54ac";
+const HUMAN_INDEX: u32 = 249;
+
+const ISSUED_AT: u64 = 1_750_000_000;
+const VALID_UNTIL: u64 = ISSUED_AT + 86_400;
+
+/// Scalars of the synthetic key: alpha, beta, gamma, delta, then K0..K2
+fn trapdoor() -> [Fr; 7] {
+    [3u64, 5, 7, 11, 13, 17, 19].map(Fr::from)
+}
+
+fn g1(scalar: Fr) -> G1Point {
+    let point = (G1Affine::generator() * scalar).into_affine();
+    G1Point {
+        x: point.x,
+        y: point.y,
+    }
+}
+
+fn g2(scalar: Fr) -> G2Point {
+    let point = (G2Affine::generator() * scalar).into_affine();
+    G2Point {
+        x: point.x,
+        y: point.y,
+    }
+}
+
+fn verifying_key() -> VerifyingKey {
+    let [alpha, beta, gamma, delta, k0, k1, k2] = trapdoor();
+    VerifyingKey {
+        alpha_g1: g1(alpha),
+        beta_g2: g2(beta),
+        gamma_g2: g2(gamma),
+        delta_g2: g2(delta),
+        k: vec![g1(k0), g1(k1), g1(k2)],
+    }
+}
+
+fn word(field: impl PrimeField) -> [u8; 32] {
+    field.into_bigint().to_bytes_be().try_into().unwrap()
+}
+
+/// A proof of `public_values` under the synthetic key
+///
+/// With A = a·G1 and B = b·G2, the pairing check holds for
+/// C = (a·b − alpha·beta − x·gamma) / delta · G1, where x is the public input
+/// combination.
+fn proof_data(public_values: &[u8]) -> ProofData {
+    let [alpha, beta, gamma, delta, k0, k1, k2] = trapdoor();
+    let call = VerifyCall {
+        riscv_vkey: RISCV_VKEY,
+        public_values: public_values.to_vec(),
+        proof: [[0; 32]; 8],
+    };
+    let [vkey, digest] = call.public_inputs();
+    let x =
+        k0 + Fr::from_be_bytes_mod_order(&vkey) * k1 + Fr::from_be_bytes_mod_order(&digest) * k2;
+    let (a, b) = (Fr::from(23u64), Fr::from(29u64));
+    let c = (a * b - alpha * beta - x * gamma) * delta.inverse().unwrap();
+
+    let (a, b, c) = (g1(a), g2(b), g1(c));
+    let words = [
+        word(a.x),
+        word(a.y),
+        word(b.x.c1),
+        word(b.x.c0),
+        word(b.y.c1),
+        word(b.y.c0),
+        word(c.x),
+        word(c.y),
+    ]
+    .map(|word| format!("0x{}", hex::encode(word)));
+
+    let commitment: [u8; 32] = public_values[24..56].try_into().unwrap();
+    encode_proof_data(
+        &words,
+        &hex::encode(public_values),
+        &hex::encode(RISCV_VKEY),
+        HUMAN_INDEX,
+        &commitment,
+    )
+    .unwrap()
+}
+
+fn public_values() -> Vec<u8> {
+    let mut values = hex::decode(PUBLIC_VALUES_PREFIX).unwrap();
+    values.extend_from_slice(&ISSUED_AT.to_le_bytes());
+    values.extend_from_slice(&VALID_UNTIL.to_le_bytes());
+    values
+}
+
+/// Creation bytecode of the contracts, with the synthetic key's verifier
+struct Artifacts {
+    pico_verifier: Vec<u8>,
+    registry: Vec<u8>,
+}
+
+fn artifacts() -> &'static Artifacts {
+    static ARTIFACTS: OnceLock<Artifacts> = OnceLock::new();
+    ARTIFACTS.get_or_init(|| {
+        let contracts = Path::new(env!("CARGO_MANIFEST_DIR")).join("../contracts");
+        let root = std::env::temp_dir().join(format!("prover-anvil-{}", std::process::id()));
+        std::fs::remove_dir_all(&root).ok();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::copy(contracts.join("foundry.toml"), root.join("foundry.toml")).unwrap();
+        for contract in [
+            "IPicoVerifier.sol",
+            "PicoVerifier.sol",
+            "HumanIndexRegistry.sol",
+        ] {
+            std::fs::copy(
+                contracts.join("src").join(contract),
+                root.join("src").join(contract),
+            )
+            .unwrap();
+        }
+        std::fs::write(
+            root.join("src/Groth16Verifier.sol"),
+            verifying_key().to_solidity().unwrap(),
+        )
+        .unwrap();
+
+        let status = Command::new("forge")
+            .arg("build")
+            .arg("--root")
+            .arg(&root)
+            .status()
+            .expect("forge on PATH");
+        assert!(status.success(), "forge build failed");
+
+        Artifacts {
+            pico_verifier: bytecode(&root, "PicoVerifier"),
+            registry: bytecode(&root, "HumanIndexRegistry"),
+        }
+    })
+}
+
+fn bytecode(root: &Path, contract: &str) -> Vec<u8> {
+    let path: PathBuf = root
+        .join("out")
+        .join(format!("{}.sol", contract))
+        .join(format!("{}.json", contract));
+    let artifact: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+    let object = artifact["bytecode"]["object"].as_str().unwrap();
+    hex::decode(object.trim_start_matches("0x")).unwrap()
+}
+
+/// An anvil node whose chain starts at `timestamp`, killed on drop
+struct Anvil {
+    child: Child,
+    url: String,
+}
+
+impl Anvil {
+    fn start(timestamp: u64) -> Self {
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let child = Command::new("anvil")
+            .args(["--port", &port.to_string()])
+            .args(["--chain-id", &CHAIN_ID.to_string()])
+            .args(["--timestamp", &timestamp.to_string()])
+            .stdout(Stdio::null())
+            .spawn()
+            .expect("anvil on PATH");
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while TcpStream::connect(("127.0.0.1", port)).is_err() {
+            assert!(Instant::now() < deadline, "anvil did not start");
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        Self {
+            child,
+            url: format!("http://127.0.0.1:{}", port),
+        }
+    }
+
+    async fn deploy(&self, bytecode: Vec<u8>) -> Address {
+        let provider = ProviderBuilder::new()
+            .wallet(signer(DEPLOYER_KEY))
+            .connect_http(self.url.parse().unwrap());
+        let receipt = provider
+            .send_transaction(TransactionRequest::default().with_deploy_code(bytecode))
+            .await
+            .unwrap()
+            .get_receipt()
+            .await
+            .unwrap();
+        receipt.contract_address.expect("contract created")
+    }
+
+    /// Deploy the verifier and a registry submitted to by `SUBMITTER_KEY`
+    async fn deploy_registry(&self) -> Address {
+        let artifacts = artifacts();
+        let verifier = self.deploy(artifacts.pico_verifier.clone()).await;
+        let mut registry = artifacts.registry.clone();
+        registry.extend(
+            (
+                verifier,
+                B256::from(RISCV_VKEY),
+                signer(SUBMITTER_KEY).address(),
+            )
+                .abi_encode_params(),
+        );
+        self.deploy(registry).await
+    }
+
+    async fn submitter(&self, registry: Address, key: &str) -> OnchainSubmitter {
+        OnchainSubmitter::new(&OnchainConfig {
+            rpc_url: self.url.clone(),
+            chain_id: CHAIN_ID,
+            contract_address: registry,
+            signing: "local".to_string(),
+            signer_key: Some(key.to_string()),
+            kms_key: None,
+            confirmations: 1,
+        })
+        .await
+        .unwrap()
+    }
+
+    async fn record(
+        &self,
+        registry: Address,
+        binding_id: &str,
+    ) -> HumanIndexRegistry::recordsReturn {
+        let provider = ProviderBuilder::new().connect_http(self.url.parse().unwrap());
+        let call = HumanIndexRegistry::recordsCall {
+            bindingId: binding_key(binding_id),
+        };
+        let data: Bytes = provider
+            .call(
+                TransactionRequest::default()
+                    .with_to(registry)
+                    .with_input(call.abi_encode()),
+            )
+            .await
+            .unwrap();
+        HumanIndexRegistry::recordsCall::abi_decode_returns(&data).unwrap()
+    }
+}
+
+impl Drop for Anvil {
+    fn drop(&mut self) {
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
+
+fn signer(key: &str) -> PrivateKeySigner {
+    key.trim_start_matches("0x").parse().unwrap()
+}
+
+#[test]
+fn synthetic_proofs_verify() {
+    let proof = proof_data(&public_values());
+    let call = VerifyCall::from_proof_data(&proof).unwrap();
+    verifying_key().verify_call(&call).unwrap();
+}
+
+#[tokio::test]
+async fn records_the_index_under_the_binding() {
+    let anvil = Anvil::start(ISSUED_AT + 60);
+    let registry = anvil.deploy_registry().await;
+    let submitter = anvil.submitter(registry, SUBMITTER_KEY).await;
+
+    let tx_hash = submitter
+        .submit_proof(&proof_data(&public_values()), "user-42")
+        .await
+        .unwrap();
+    assert!(tx_hash.starts_with("0x"), "{}", tx_hash);
+
+    let record = anvil.record(registry, "user-42").await;
+    assert_eq!(record.humanIndex, HUMAN_INDEX);
+    assert_eq!(record.validUntil, VALID_UNTIL);
+    assert!(record.recordedAt >= ISSUED_AT + 60);
+
+    // Nothing is recorded under any other binding, e.g. the request ID
+    let other = anvil.record(registry, "request-1").await;
+    assert_eq!(other.humanIndex, 0);
+    assert_eq!(other.recordedAt, 0);
+}
+
+#[tokio::test]
+async fn refuses_an_expired_index() {
+    let anvil = Anvil::start(VALID_UNTIL + 1);
+    let registry = anvil.deploy_registry().await;
+    let submitter = anvil.submitter(registry, SUBMITTER_KEY).await;
+
+    let err = submitter
+        .submit_proof(&proof_data(&public_values()), "user-42")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("IndexExpired(uint64)"), "{}", err);
+}
+
+#[tokio::test]
+async fn refuses_other_submitters() {
+    let anvil = Anvil::start(ISSUED_AT + 60);
+    let registry = anvil.deploy_registry().await;
+    let stranger = anvil.submitter(registry, STRANGER_KEY).await;
+
+    let err = stranger
+        .submit_proof(&proof_data(&public_values()), "user-42")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Unauthorized(address)"), "{}", err);
+}
+
+#[tokio::test]
+async fn refuses_a_tampered_proof() {
+    let anvil = Anvil::start(ISSUED_AT + 60);
+    let registry = anvil.deploy_registry().await;
+    let submitter = anvil.submitter(registry, SUBMITTER_KEY).await;
+
+    // The proof of the genuine values, sent with a higher index
+    let mut proof = proof_data(&public_values());
+    let mut tampered = public_values();
+    tampered[20] += 1;
+    proof.public_inputs = proof_data(&tampered).public_inputs;
+
+    let err = submitter.submit_proof(&proof, "user-42").await.unwrap_err();
+    assert!(err.to_string().contains("ProofInvalid()"), "{}", err);
+}
@@ -0,0 +1,136 @@
+// `PublicValues::check_internal_consistency`: re-validating the committed arithmetic
+// without the private inputs. An output is consistent exactly when some in-range
+// verification results give it with the committed weights, which is checked here
+// against every score the service proves.
+
+use human_index_lib::{
+    calculate_human_index, commit_weights, ConsistencyError, HumanIndexPublicInputs, PublicValues,
+    PublicValuesError, VerificationResults, SCALE,
+};
+use prover::fixtures::{golden_vectors, FIXTURE_VALIDITY};
+use std::collections::BTreeSet;
+
+fn inputs(weights: [u32; 4]) -> HumanIndexPublicInputs {
+    let [w1, w2, w3, w4] = weights;
+    HumanIndexPublicInputs {
+        w1,
+        w2,
+        w3,
+        w4,
+        expected_output: 0,
+    }
+}
+
+/// Public values as the guest commits them, but with `computed_output` as given
+fn committed(inputs: &HumanIndexPublicInputs, computed_output: u32) -> PublicValues {
+    PublicValues {
+        inputs: inputs.clone(),
+        computed_output,
+        weights_commitment: commit_weights(inputs),
+        validity: FIXTURE_VALIDITY,
+    }
+}
+
+/// Every index the weights give, over all in-range verification results
+fn achievable(inputs: &HumanIndexPublicInputs) -> BTreeSet<u32> {
+    let mut indices = BTreeSet::new();
+    for recaptcha_score in 0..=SCALE {
+        for (sms_verified, bio_verified) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+            let results = VerificationResults {
+                recaptcha_score,
+                sms_verified,
+                bio_verified,
+            };
+            indices.insert(calculate_human_index(&results, inputs));
+        }
+    }
+    indices
+}
+
+#[test]
+fn golden_vector_outputs_are_consistent() {
+    for vector in golden_vectors().unwrap() {
+        let output = calculate_human_index(&vector.verification_results, &vector.public_inputs);
+        committed(&vector.public_inputs, output)
+            .check_internal_consistency()
+            .unwrap_or_else(|e| panic!("{}: {}", vector.name, e));
+    }
+}
+
+#[test]
+fn exactly_the_achievable_outputs_are_consistent() {
+    for weights in [
+        [1500, 2000, 2500, 4000],
+        // Without a score weight, only a few indices are reachable
+        [1500, 0, 2500, 4000],
+        [0, 37, 0, 0],
+        [100, 100, 100, 100],
+        [6000, 3000, 2000, 1000],
+    ] {
+        let inputs = inputs(weights);
+        let achievable = achievable(&inputs);
+        for output in 0..=255 {
+            let result = committed(&inputs, output).check_internal_consistency();
+            assert_eq!(
+                result.is_ok(),
+                achievable.contains(&output),
+                "{:?} -> {}: {:?}",
+                weights,
+                output,
+                result
+            );
+        }
+    }
+}
+
+#[test]
+fn an_impossible_output_is_reported_with_the_maximum() {
+    // Without a score weight the index is 0, 38, 102, 140 or 204
+    let inputs = inputs([1500, 0, 2500, 4000]);
+    assert_eq!(
+        committed(&inputs, 100).check_internal_consistency(),
+        Err(ConsistencyError::Unachievable {
+            computed_output: 100,
+            max: 204
+        })
+    );
+
+    // Weights summing to 0.04 never give more than 10
+    let inputs = self::inputs([100, 100, 100, 100]);
+    let error = committed(&inputs, 200)
+        .check_internal_consistency()
+        .unwrap_err();
+    assert_eq!(
+        error,
+        ConsistencyError::Unachievable {
+            computed_output: 200,
+            max: 10
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "computed output 200 is not an index the committed weights give (at most 10)"
+    );
+}
+
+#[test]
+fn a_mismatched_commitment_or_out_of_range_output_is_inconsistent() {
+    let inputs = inputs([1500, 2000, 2500, 4000]);
+    let mut values = committed(&inputs, 229);
+    values.check_internal_consistency().unwrap();
+
+    values.weights_commitment = commit_weights(&self::inputs([1500, 2000, 2500, 4001]));
+    assert_eq!(
+        values.check_internal_consistency(),
+        Err(ConsistencyError::WeightsCommitmentMismatch)
+    );
+
+    assert_eq!(
+        committed(&inputs, 256).check_internal_consistency(),
+        Err(ConsistencyError::PublicValues(
+            PublicValuesError::OutputOutOfRange {
+                computed_output: 256
+            }
+        ))
+    );
+}
@@ -0,0 +1,138 @@
+// Proof artifacts sealed at rest with a local key: encryption round-trips, a
+// sealed inputs.json reads back to the plaintext proof, and anything altered,
+// unsealed or under another key is refused.
+
+use prover::encryption::{ArtifactCipher, EncryptionConfig, SEALED_MAGIC};
+use prover::error::ServiceError;
+use prover::prover::{
+    read_proof_artifacts, read_sealed_proof_artifacts, ArtifactEncoding, ProofJsonLayout,
+};
+use std::path::PathBuf;
+
+const KEY: &str = "local:000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+const OTHER_KEY: &str = "local:ffeeddccbbaa99887766554433221100ffeeddccbbaa99887766554433221100";
+const WEIGHTS_COMMITMENT: [u8; 32] = [7; 32];
+const INPUTS: &str =
+    r#"{"proof": ["0x01", "0x02"], "publicValues": "0xabcd", "riscvVKey": "0x42"}"#;
+
+fn cipher(kms_key: &str) -> Result<ArtifactCipher, ServiceError> {
+    ArtifactCipher::from_config(&EncryptionConfig {
+        kms_key: kms_key.to_string(),
+    })
+}
+
+/// An output directory for test `name` holding a plaintext inputs.json and setup file
+fn output_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("prover-sealed-{}-{}", name, std::process::id()));
+    std::fs::remove_dir_all(&dir).ok();
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("inputs.json"), INPUTS).unwrap();
+    std::fs::write(dir.join("vm_pk"), b"setup").unwrap();
+    dir
+}
+
+#[test]
+fn local_key_round_trips() {
+    let cipher = cipher(KEY).unwrap();
+    let plaintext = b"groth16 proof bytes".repeat(1000);
+
+    let sealed = cipher.encrypt(&plaintext).unwrap();
+    assert!(sealed.starts_with(SEALED_MAGIC));
+    assert!(!sealed.windows(19).any(|w| w == b"groth16 proof bytes"));
+    assert_eq!(cipher.decrypt(&sealed).unwrap(), plaintext);
+
+    // A fresh data key and nonce per artifact
+    assert_ne!(cipher.encrypt(&plaintext).unwrap(), sealed);
+    assert!(cipher.key_id().starts_with("local:"));
+    assert!(!cipher.key_id().contains("000102030405"));
+}
+
+#[test]
+fn sealed_inputs_read_back_to_the_plaintext_proof() {
+    let dir = output_dir("round-trip");
+    let inputs_path = dir.join("inputs.json");
+    let plaintext = read_proof_artifacts(
+        &inputs_path,
+        u64::MAX,
+        ProofJsonLayout::PicoV1,
+        ArtifactEncoding::Parallel,
+        173,
+        &WEIGHTS_COMMITMENT,
+    )
+    .unwrap();
+
+    let cipher = cipher(KEY).unwrap();
+    cipher.seal_dir(&dir, &["vm_pk"]).unwrap();
+    let on_disk = std::fs::read(&inputs_path).unwrap();
+    assert!(on_disk.starts_with(SEALED_MAGIC));
+    assert_eq!(cipher.decrypt(&on_disk).unwrap(), INPUTS.as_bytes());
+    // Setup files stay as they are
+    assert_eq!(std::fs::read(dir.join("vm_pk")).unwrap(), b"setup");
+
+    let sealed = read_sealed_proof_artifacts(
+        &inputs_path,
+        u64::MAX,
+        ProofJsonLayout::PicoV1,
+        ArtifactEncoding::Parallel,
+        &cipher,
+        173,
+        &WEIGHTS_COMMITMENT,
+    )
+    .unwrap();
+    assert_eq!(sealed.proof, plaintext.proof);
+    assert_eq!(sealed.public_inputs, plaintext.public_inputs);
+    assert_eq!(sealed.verification_key, plaintext.verification_key);
+    std::fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn wrong_key_is_refused() {
+    let sealed = cipher(KEY).unwrap().encrypt(INPUTS.as_bytes()).unwrap();
+    let err = cipher(OTHER_KEY).unwrap().decrypt(&sealed).unwrap_err();
+    assert!(matches!(err, ServiceError::Encryption(_)), "{}", err);
+}
+
+#[test]
+fn altered_artifact_is_refused() {
+    let cipher = cipher(KEY).unwrap();
+    let mut sealed = cipher.encrypt(INPUTS.as_bytes()).unwrap();
+    let last = sealed.len() - 1;
+    sealed[last] ^= 1;
+    assert!(matches!(
+        cipher.decrypt(&sealed),
+        Err(ServiceError::Encryption(_))
+    ));
+
+    assert!(cipher.decrypt(&sealed[..SEALED_MAGIC.len() + 1]).is_err());
+}
+
+#[test]
+fn plaintext_inputs_are_refused_when_sealing() {
+    let dir = output_dir("plaintext");
+    let err = read_sealed_proof_artifacts(
+        &dir.join("inputs.json"),
+        u64::MAX,
+        ProofJsonLayout::PicoV1,
+        ArtifactEncoding::Parallel,
+        &cipher(KEY).unwrap(),
+        173,
+        &WEIGHTS_COMMITMENT,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("not sealed"), "{}", err);
+    std::fs::remove_dir_all(dir).ok();
+}
+
+#[test]
+fn invalid_kms_key_is_refused_without_echoing_it() {
+    for kms_key in [
+        "local:abcd",
+        "local:not-hex",
+        "vault:000102030405060708090a0b0c0d0e0f",
+        "000102030405060708090a0b0c0d0e0f",
+    ] {
+        let err = cipher(kms_key).err().expect("invalid key");
+        assert!(matches!(err, ServiceError::Config(_)), "{}", err);
+        assert!(!err.to_string().contains("0102030405"), "{}", err);
+    }
+}
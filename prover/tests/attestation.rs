@@ -0,0 +1,139 @@
+// EIP-712 attestations against a fixed vector: the digest of a known attestation
+// under a known domain, and its signature by a known key, are pinned as hex so a
+// change to the struct, the domain or the encoding breaks here before contracts
+// and off-chain verifiers disagree with the service. The digest is also rebuilt
+// by hand from the type strings, independently of `sol!`.
+//
+// DIGEST and SIGNATURE are printed by `npm run attestation:vector`
+// (scripts/attestation-vector.ts), which encodes and signs the same attestation
+// with ethers 6 (`TypedDataEncoder.hash` and `Wallet.signTypedData`).
+
+use alloy_primitives::{address, b256, keccak256, Address, B256, U256};
+use alloy_sol_types::SolStruct;
+use prover::eip712::{
+    verify_attestation, AttestationConfig, AttestationSigner, Attestor, HumanIndexAttestation,
+    LocalAttestationSigner,
+};
+use prover::types::ProofData;
+use std::path::Path;
+
+/// Private key used in the ethers and web3 documentation
+const KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+const SIGNER: Address = address!("2c7536E3605D9C16a7a3D7b1898e529396a65c23");
+
+const DIGEST: B256 = b256!("0548f24c308090a227656731a141d204b674e710d3bf1defed6401dd999e15cd");
+const SIGNATURE: &str = "0x3d131cf71faa89baf269cc0e9e6fccc8f140a1232d4eafdc695237ed0c51baac\
+                         473a4199cc9928e7286f7c17a9fcb6502bc20e1d894fdf798392cc981d76fdc31b";
+
+fn config() -> AttestationConfig {
+    AttestationConfig {
+        signing: "local".to_string(),
+        signer_key: Some(KEY.to_string()),
+        kms_key: None,
+        domain_name: "HumanIndex".to_string(),
+        domain_version: "1".to_string(),
+        chain_id: 8453,
+        verifying_contract: Address::repeat_byte(0x11),
+        ttl_secs: 3600,
+    }
+}
+
+fn attestation() -> HumanIndexAttestation {
+    HumanIndexAttestation {
+        bindingId: keccak256("user-42"),
+        humanIndex: 73,
+        vkeyHash: B256::repeat_byte(0xab),
+        expiry: 1_800_000_000,
+    }
+}
+
+fn word(value: u64) -> [u8; 32] {
+    U256::from(value).to_be_bytes()
+}
+
+#[test]
+fn digest_matches_the_fixed_vector() {
+    assert_eq!(
+        attestation().eip712_signing_hash(&config().domain()),
+        DIGEST
+    );
+}
+
+#[test]
+fn digest_matches_the_type_strings() {
+    let domain_type = keccak256(
+        "EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)",
+    );
+    let mut domain = domain_type.to_vec();
+    domain.extend_from_slice(keccak256("HumanIndex").as_slice());
+    domain.extend_from_slice(keccak256("1").as_slice());
+    domain.extend_from_slice(&word(8453));
+    domain.extend_from_slice(B256::left_padding_from(&[0x11; 20]).as_slice());
+
+    let struct_type = keccak256(
+        "HumanIndexAttestation(bytes32 bindingId,uint32 humanIndex,bytes32 vkeyHash,uint64 expiry)",
+    );
+    let mut data = struct_type.to_vec();
+    data.extend_from_slice(keccak256("user-42").as_slice());
+    data.extend_from_slice(&word(73));
+    data.extend_from_slice(&[0xab; 32]);
+    data.extend_from_slice(&word(1_800_000_000));
+
+    let mut message = vec![0x19, 0x01];
+    message.extend_from_slice(keccak256(&domain).as_slice());
+    message.extend_from_slice(keccak256(&data).as_slice());
+    assert_eq!(keccak256(&message), DIGEST);
+}
+
+#[tokio::test]
+async fn signature_matches_the_fixed_vector() {
+    let signer = LocalAttestationSigner::new(KEY).unwrap();
+    assert_eq!(signer.address(), SIGNER);
+
+    let signature = signer.sign_digest(DIGEST).await.unwrap();
+    assert_eq!(
+        format!("0x{}", hex::encode(signature.as_bytes())),
+        SIGNATURE
+    );
+    assert!(verify_attestation(&attestation(), &config().domain(), SIGNATURE, SIGNER).unwrap());
+}
+
+#[tokio::test]
+async fn attestations_are_bound_to_the_binding_id() {
+    let json = std::fs::read_to_string(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/groth16_proof.json"),
+    )
+    .unwrap();
+    let mut proof_data: ProofData = serde_json::from_str(&json).unwrap();
+    let attestor = Attestor::new(&config()).await.unwrap();
+    attestor.attest("user-42", &mut proof_data).await.unwrap();
+
+    let signature = proof_data.attestation_signature.clone().unwrap();
+    let expiry = proof_data.attestation_expiry.unwrap();
+    let domain = config().domain();
+    let signed = HumanIndexAttestation::for_proof("user-42", &proof_data, expiry).unwrap();
+    assert_eq!(signed.bindingId, keccak256("user-42"));
+    assert!(verify_attestation(&signed, &domain, &signature, SIGNER).unwrap());
+
+    let other = HumanIndexAttestation::for_proof("user-43", &proof_data, expiry).unwrap();
+    assert!(!verify_attestation(&other, &domain, &signature, SIGNER).unwrap());
+}
+
+#[tokio::test]
+async fn backends_need_their_key() {
+    let missing = |signing: &str| AttestationConfig {
+        signing: signing.to_string(),
+        signer_key: None,
+        kms_key: None,
+        ..config()
+    };
+    for (signing, variable) in [
+        ("local", "ATTESTATION_SIGNER_KEY"),
+        ("gcp-kms", "ATTESTATION_KMS_KEY"),
+    ] {
+        let err = Attestor::new(&missing(signing)).await.err().unwrap();
+        assert!(err.to_string().contains(variable), "{}", err);
+    }
+    let err = Attestor::new(&missing("vault")).await.err().unwrap();
+    assert!(err.to_string().contains("vault"), "{}", err);
+}
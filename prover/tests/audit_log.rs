@@ -0,0 +1,412 @@
+// The hash-chained audit log: requests are recorded as chained records for every
+// outcome, the chain verifies across reopened logs and by the verify-audit-log
+// binary, and editing, removing or reordering a record is detected at that record.
+// Requests processed by the service leave their inputs as a keyed hash and their
+// human_index, never the private verification results in clear.
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use prover::audit::{
+    log_files, verify_chain, AuditEvent, AuditLog, AuditRecord, ChainError, GENESIS_HASH,
+};
+use prover::canonical::canonical_bytes;
+use prover::config::Config;
+use prover::mock::MockProofGenerator;
+use prover::prover::{ProofBackend, ProofContext};
+use prover::service::ProverService;
+use prover::types::{
+    ProofError, ProofMetrics, ProofStatus, ProverRequest, ProverResponse, ResourceUsage,
+};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+const KEY: &[u8] = b"audit-test-key";
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("prover-audit-{}-{}", name, std::process::id()));
+    std::fs::remove_dir_all(&dir).ok();
+    dir
+}
+
+fn open(dir: &Path) -> AuditLog {
+    AuditLog::open_dir(dir, KEY, "worker-1").unwrap()
+}
+
+fn request(request_id: &str) -> ProverRequest {
+    serde_json::from_value(json!({
+        "request_id": request_id,
+        "client_id": "client-a",
+        "verification_results": { "recaptcha_score": 8000, "sms_verified": 1, "bio_verified": 1 },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 },
+    }))
+    .unwrap()
+}
+
+fn metrics() -> ProofMetrics {
+    ProofMetrics {
+        received_at: "2026-01-01T00:00:00+00:00".to_string(),
+        started_at: "2026-01-01T00:00:00+00:00".to_string(),
+        completed_at: "2026-01-01T00:00:42+00:00".to_string(),
+        duration_ms: 42,
+        attempts: Some(1),
+        usage: ResourceUsage::default(),
+        cost_units: None,
+        progress: None,
+        queue_wait_ms: None,
+    }
+}
+
+/// A response to `request` with `status`, successful ones proved by the mock
+fn response(request: &ProverRequest, status: ProofStatus) -> ProverResponse {
+    let request_id = request.request_id.clone();
+    match status {
+        ProofStatus::Success => {
+            let backend = MockProofGenerator::new(Duration::ZERO, Duration::from_secs(60));
+            let context = ProofContext::new(Instant::now() + Duration::from_secs(60), 0);
+            let proof_data = backend.generate_proof(request.clone(), &context).unwrap();
+            ProverResponse::success(request_id, proof_data, metrics())
+        }
+        ProofStatus::Failed => ProverResponse::failed(
+            request_id,
+            ProofError {
+                error_type: "ProofGenerationError".to_string(),
+                message: "prove_evm failed".to_string(),
+                details: None,
+            },
+            Some(metrics()),
+        ),
+        ProofStatus::Timeout => {
+            ProverResponse::timeout(request_id, "timed out".to_string(), Some(metrics()))
+        }
+        status => panic!("no {:?} responses in these tests", status),
+    }
+}
+
+/// Record requests from acceptance to completion, then one rejected message
+fn record_requests(audit: &AuditLog, requests: &[(&str, ProofStatus)]) {
+    for (request_id, status) in requests {
+        let request = request(request_id);
+        audit.record_accepted(&request);
+        audit.record_completed(&request, &response(&request, status.clone()), 42);
+    }
+    let error = serde_json::from_slice::<ProverRequest>(b"not json").unwrap_err();
+    audit.record_rejected(&error.into());
+}
+
+fn records(dir: &Path) -> Vec<AuditRecord> {
+    log_files(dir)
+        .unwrap()
+        .iter()
+        .flat_map(|file| {
+            std::fs::read_to_string(file)
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Replace the single log file in `dir` with `edit` applied to its lines
+fn edit_log(dir: &Path, edit: impl FnOnce(&mut Vec<String>)) {
+    let files = log_files(dir).unwrap();
+    assert_eq!(files.len(), 1);
+    let mut lines: Vec<String> = std::fs::read_to_string(&files[0])
+        .unwrap()
+        .lines()
+        .map(str::to_string)
+        .collect();
+    edit(&mut lines);
+    std::fs::write(&files[0], lines.join("\n") + "\n").unwrap();
+}
+
+/// The line at which verification of `dir` breaks
+fn broken_at(dir: &Path) -> usize {
+    match verify_chain(&log_files(dir).unwrap()) {
+        Err(ChainError::Broken { line, .. }) => line,
+        other => panic!("expected a broken chain, got {:?}", other),
+    }
+}
+
+#[test]
+fn requests_are_recorded_as_a_chain() {
+    let dir = temp_dir("emit");
+    let audit = open(&dir);
+    record_requests(
+        &audit,
+        &[
+            ("audit-1", ProofStatus::Success),
+            ("audit-2", ProofStatus::Failed),
+            ("audit-3", ProofStatus::Timeout),
+        ],
+    );
+
+    let records = records(&dir);
+    let events: Vec<AuditEvent> = records.iter().map(|record| record.entry.event).collect();
+    assert_eq!(
+        events,
+        [
+            AuditEvent::Accepted,
+            AuditEvent::Completed,
+            AuditEvent::Accepted,
+            AuditEvent::Completed,
+            AuditEvent::Accepted,
+            AuditEvent::Completed,
+            AuditEvent::Rejected,
+        ]
+    );
+
+    // Each record links to the one before it, the first to the genesis hash
+    assert_eq!(records[0].prev_hash, GENESIS_HASH);
+    for pair in records.windows(2) {
+        assert_eq!(pair[1].prev_hash, pair[0].hash);
+    }
+
+    let success = &records[1].entry;
+    assert_eq!(success.request_id.as_deref(), Some("audit-1"));
+    assert_eq!(success.client_id.as_deref(), Some("client-a"));
+    assert_eq!(success.worker_id, "worker-1");
+    assert_eq!(success.outcome, Some(ProofStatus::Success));
+    assert_eq!(success.duration_ms, Some(42));
+    assert_eq!(success.human_index, Some(244));
+    assert!(success.vkey_hash.is_some());
+    assert_eq!(success.error_type, None);
+
+    // The inputs are only ever recorded as a keyed hash
+    let input_hash = success.input_hash.as_deref().unwrap();
+    assert_eq!(input_hash.len(), 64);
+    assert_eq!(records[0].entry.input_hash.as_deref(), Some(input_hash));
+    assert!(!std::fs::read_to_string(&log_files(&dir).unwrap()[0])
+        .unwrap()
+        .contains("recaptcha_score"));
+
+    let failed = &records[3].entry;
+    assert_eq!(failed.outcome, Some(ProofStatus::Failed));
+    assert_eq!(failed.error_type.as_deref(), Some("ProofGenerationError"));
+    assert!(failed.vkey_hash.is_none());
+    let timed_out = &records[5].entry;
+    assert_eq!(timed_out.outcome, Some(ProofStatus::Timeout));
+    assert_eq!(timed_out.error_type.as_deref(), Some("Timeout"));
+
+    assert_eq!(
+        records[6].entry.error_type.as_deref(),
+        Some("SerializationError")
+    );
+    assert_eq!(records[6].entry.request_id, None);
+
+    assert_eq!(verify_chain(&log_files(&dir).unwrap()).unwrap(), 7);
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn reopened_log_continues_the_chain() {
+    let dir = temp_dir("reopen");
+    let audit = open(&dir);
+    record_requests(&audit, &[("audit-1", ProofStatus::Success)]);
+    let last = records(&dir).last().unwrap().hash.clone();
+
+    // As after a restart
+    let audit = open(&dir);
+    record_requests(&audit, &[("audit-2", ProofStatus::Success)]);
+
+    let records = records(&dir);
+    assert_eq!(records[3].prev_hash, last);
+    assert_eq!(verify_chain(&log_files(&dir).unwrap()).unwrap(), 6);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_verify-audit-log"))
+        .arg(&dir)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("6 records"));
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn tampering_is_detected_at_the_tampered_record() {
+    let dir = temp_dir("tamper");
+    let audit = open(&dir);
+    record_requests(
+        &audit,
+        &[
+            ("audit-1", ProofStatus::Success),
+            ("audit-2", ProofStatus::Success),
+        ],
+    );
+    let original = std::fs::read(&log_files(&dir).unwrap()[0]).unwrap();
+    let restore = || std::fs::write(&log_files(&dir).unwrap()[0], &original).unwrap();
+
+    // An edited field no longer matches the record's hash
+    edit_log(&dir, |lines| {
+        let mut record: serde_json::Value = serde_json::from_str(&lines[1]).unwrap();
+        record["duration_ms"] = json!(1);
+        lines[1] = record.to_string();
+    });
+    assert_eq!(broken_at(&dir), 2);
+
+    // Rehashing the edited record breaks the link from the next one
+    restore();
+    edit_log(&dir, |lines| {
+        let mut record: AuditRecord = serde_json::from_str(&lines[1]).unwrap();
+        record.entry.duration_ms = Some(1);
+        let hash = Sha256::new()
+            .chain_update(record.prev_hash.as_bytes())
+            .chain_update(serde_json::to_vec(&record.entry).unwrap())
+            .finalize();
+        record.hash = hex::encode(hash);
+        lines[1] = serde_json::to_string(&record).unwrap();
+    });
+    assert_eq!(broken_at(&dir), 3);
+
+    // A removed record breaks the link where it was
+    restore();
+    edit_log(&dir, |lines| {
+        lines.remove(2);
+    });
+    assert_eq!(broken_at(&dir), 3);
+
+    // So do reordered records
+    restore();
+    edit_log(&dir, |lines| lines.swap(1, 2));
+    assert_eq!(broken_at(&dir), 2);
+
+    // A line that is not a record is reported as such
+    restore();
+    edit_log(&dir, |lines| lines[3] = "not a record".to_string());
+    assert!(matches!(
+        verify_chain(&log_files(&dir).unwrap()),
+        Err(ChainError::Parse { line: 4, .. })
+    ));
+
+    restore();
+    assert_eq!(verify_chain(&log_files(&dir).unwrap()).unwrap(), 5);
+    edit_log(&dir, |lines| {
+        lines.remove(0);
+    });
+    let output = Command::new(env!("CARGO_BIN_EXE_verify-audit-log"))
+        .arg(&dir)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+fn config() -> Config {
+    static BASE: OnceLock<Config> = OnceLock::new();
+    let mut config = BASE
+        .get_or_init(|| {
+            std::env::set_var("GCP_PROJECT_ID", "audit-test");
+            std::env::set_var("PROVER_SUBSCRIPTION", "unused");
+            std::env::set_var("RESULT_TOPIC", "unused");
+            Config::from_env().expect("base configuration")
+        })
+        .clone();
+    config.memory_wait_secs = 0;
+    config
+}
+
+#[tokio::test]
+async fn processed_requests_are_recorded_with_hashed_inputs() {
+    let dir = temp_dir("process");
+    let audit = open(&dir);
+    let backend = Arc::new(MockProofGenerator::new(
+        Duration::ZERO,
+        Duration::from_secs(60),
+    ));
+    let requests: Vec<ProverRequest> = [
+        ("0b6f6a4e-5d8a-4c1e-9d3b-1f2a3b4c5d6e", 7321),
+        ("9a8b7c6d-5e4f-4a3b-8c2d-1e0f9a8b7c6d", 6457),
+    ]
+    .into_iter()
+    .map(|(request_id, recaptcha_score)| {
+        serde_json::from_value(json!({
+            "request_id": request_id,
+            "verification_results": {
+                "recaptcha_score": recaptcha_score,
+                "sms_verified": 1,
+                "bio_verified": 0,
+            },
+            "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 },
+        }))
+        .unwrap()
+    })
+    .collect();
+
+    let mut responses = Vec::new();
+    for request in &requests {
+        let response = ProverService::process_message(
+            &serde_json::to_vec(request).unwrap(),
+            config(),
+            backend.clone(),
+            Some(&audit),
+            None,
+            &CancellationToken::new(),
+            Utc::now(),
+        )
+        .await
+        .expect("request parses");
+        assert_eq!(
+            response.status,
+            ProofStatus::Success,
+            "{:?}",
+            response.error
+        );
+        responses.push(response);
+    }
+    audit.flush().await.unwrap();
+
+    // One accepted and one completed record per request
+    let records = records(&dir);
+    assert_eq!(records.len(), 2 * requests.len());
+    for ((request, response), pair) in requests.iter().zip(&responses).zip(records.chunks(2)) {
+        let mut mac = Hmac::<Sha256>::new_from_slice(KEY).unwrap();
+        mac.update(&canonical_bytes(request));
+        let input_hash = hex::encode(mac.finalize().into_bytes());
+
+        assert_eq!(pair[0].entry.event, AuditEvent::Accepted);
+        assert_eq!(pair[1].entry.event, AuditEvent::Completed);
+        for record in pair {
+            assert_eq!(record.entry.request_id.as_ref(), Some(&request.request_id));
+            assert_eq!(record.entry.input_hash.as_ref(), Some(&input_hash));
+        }
+        assert_eq!(pair[1].entry.outcome, Some(ProofStatus::Success));
+        assert_eq!(
+            pair[1].entry.human_index,
+            response.proof_data.as_ref().map(|proof| proof.human_index)
+        );
+    }
+    assert_ne!(records[0].entry.input_hash, records[2].entry.input_hash);
+
+    // The log itself holds no private input in clear
+    let log = std::fs::read_to_string(&log_files(&dir).unwrap()[0]).unwrap();
+    for line in log.lines() {
+        let record: serde_json::Value = serde_json::from_str(line).unwrap();
+        for field in [
+            "verification_results",
+            "recaptcha_score",
+            "sms_verified",
+            "bio_verified",
+        ] {
+            assert!(record.get(field).is_none(), "{} in {}", field, line);
+        }
+    }
+    assert!(!log.contains("recaptcha_score"));
+    for request in &requests {
+        let score = request.verification_results.recaptcha_score;
+        assert!(
+            !log.contains(&format!(":{}", score)),
+            "score {} in the log",
+            score
+        );
+    }
+    std::fs::remove_dir_all(&dir).ok();
+}
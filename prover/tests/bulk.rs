@@ -0,0 +1,454 @@
+// Bulk jobs end to end with the mock backend: a JSON Lines file of verification
+// results read from a `file://` URI, proved in chunks, with the results, errors and
+// summary written under the output URI, and the completion published for it.
+
+mod common;
+
+use prover::bulk::{
+    completion, completion_message, run_job, JobLocation, COMPLETION_MESSAGE_TYPE, ERRORS_FILE,
+    RESULTS_FILE, SUMMARY_FILE,
+};
+use prover::config::Config;
+use prover::error::ServiceError;
+use prover::lease::{GlobalAdmission, MemoryLeases};
+use prover::mock::MockProofGenerator;
+use prover::object_store::MemoryObjects;
+use prover::prover::{ProofBackend, ProofContext};
+use prover::types::{
+    BulkJob, BulkJobStatus, BulkJobSummary, BulkLineError, ProofData, ProofStatus, ProverRequest,
+    ProverResponse,
+};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// Three good lines around a blank one, then one that is not JSON and one missing
+/// a field
+const INPUT: &str = r#"{"recaptcha_score": 8000, "sms_verified": 1, "bio_verified": 1}
+{"recaptcha_score": 0.5, "sms_verified": 0, "bio_verified": 1}
+
+{"recaptcha_score": 1200, "sms_verified": 0, "bio_verified": 0}
+not json
+{"recaptcha_score": 9000, "sms_verified": 1}
+"#;
+
+fn config(chunk_size: usize) -> Config {
+    let mut config = common::bulk_config();
+    config.bulk_chunk_size = chunk_size;
+    config
+}
+
+fn backend() -> Arc<MockProofGenerator> {
+    Arc::new(MockProofGenerator::new(
+        Duration::from_millis(10),
+        Duration::from_secs(60),
+    ))
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("prover-bulk-{}-{}", name, std::process::id()));
+    std::fs::remove_dir_all(&dir).ok();
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// A job reading `input` from a file in `dir`, writing to `dir/out`
+fn file_job(dir: &Path, input: &str) -> BulkJob {
+    let input_path = dir.join("input.jsonl");
+    std::fs::write(&input_path, input).unwrap();
+    BulkJob {
+        request_id: "bulk-job-1".to_string(),
+        input_uri: format!("file://{}", input_path.display()),
+        output_uri: format!("file://{}", dir.join("out").display()),
+        weights_profile: None,
+    }
+}
+
+fn json_lines<T: serde::de::DeserializeOwned>(data: &[u8]) -> Vec<T> {
+    std::str::from_utf8(data)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn file_job_writes_results_errors_and_summary() {
+    let dir = temp_dir("file");
+    let job = file_job(&dir, INPUT);
+    let observed = Mutex::new(Vec::new());
+    let summary = run_job(
+        &job,
+        &config(2),
+        backend(),
+        None,
+        None,
+        None,
+        &CancellationToken::new(),
+        |response| observed.lock().unwrap().push(response.request_id.clone()),
+    )
+    .await
+    .expect("job completes");
+
+    assert_eq!(summary.lines, 5);
+    assert_eq!(summary.succeeded, 3);
+    assert_eq!(summary.unsuccessful, 0);
+    assert_eq!(summary.rejected, 2);
+
+    // One result per good line, in input order, each under its own request ID
+    let out = dir.join("out");
+    let results_file = std::fs::read(out.join(RESULTS_FILE)).unwrap();
+    let results: Vec<ProverResponse> = json_lines(&results_file);
+    let lines: Vec<usize> = summary.proofs.iter().map(|proof| proof.line).collect();
+    assert_eq!(lines, [1, 2, 4]);
+    for (result, proof) in results.iter().zip(&summary.proofs) {
+        assert_eq!(result.request_id, proof.request_id);
+        assert_eq!(result.status, ProofStatus::Success);
+        assert_eq!(result.parent_request_id.as_deref(), Some("bulk-job-1"));
+        let proof_data = result.proof_data.as_ref().unwrap();
+        assert_eq!(proof_data.weights_profile.as_deref(), Some("v1"));
+        assert_eq!(
+            proof.proof_sha256.as_deref(),
+            Some(hex::encode(Sha256::digest(proof_data.proof.as_bytes())).as_str())
+        );
+    }
+    assert_eq!(results.len(), 3);
+    assert_eq!(*observed.lock().unwrap(), summary_request_ids(&summary));
+    assert_eq!(
+        summary.results_sha256,
+        hex::encode(Sha256::digest(&results_file))
+    );
+
+    // Bad lines are recorded by number, without their content
+    let errors: Vec<BulkLineError> = json_lines(&std::fs::read(out.join(ERRORS_FILE)).unwrap());
+    let error_lines: Vec<usize> = errors.iter().map(|error| error.line).collect();
+    assert_eq!(error_lines, [5, 6]);
+    // Verification results are private, so the error names only where it is
+    assert!(
+        errors[1].error.contains("/verification_results"),
+        "{}",
+        errors[1].error
+    );
+    assert!(!errors[0].error.contains("not json"), "{}", errors[0].error);
+
+    let written: BulkJobSummary =
+        serde_json::from_slice(&std::fs::read(out.join(SUMMARY_FILE)).unwrap()).unwrap();
+    assert_eq!(written, summary);
+    assert_eq!(
+        summary.results_uri,
+        format!("file://{}", out.join(RESULTS_FILE).display())
+    );
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+fn summary_request_ids(summary: &BulkJobSummary) -> Vec<String> {
+    summary
+        .proofs
+        .iter()
+        .map(|proof| proof.request_id.clone())
+        .collect()
+}
+
+#[tokio::test]
+async fn chunk_size_does_not_change_the_proofs() {
+    let dir = temp_dir("chunks");
+    let job = file_job(&dir, INPUT);
+    let mut summaries = Vec::new();
+    for chunk_size in [1, 3, 100] {
+        let summary = run_job(
+            &job,
+            &config(chunk_size),
+            backend(),
+            None,
+            None,
+            None,
+            &CancellationToken::new(),
+            |_| {},
+        )
+        .await
+        .unwrap();
+        summaries.push(summary);
+    }
+    // Results differ only in their timings, and in the proofs themselves as a proof
+    // commits to the validity window starting when the line was proved
+    let outcomes = |summary: &BulkJobSummary| {
+        summary
+            .proofs
+            .iter()
+            .map(|proof| {
+                (
+                    proof.line,
+                    proof.request_id.clone(),
+                    proof.status.clone(),
+                    proof.proof_sha256.is_some(),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    for summary in &summaries[1..] {
+        assert_eq!(outcomes(summary), outcomes(&summaries[0]));
+    }
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+/// The mock, counting the proofs it runs at once and the most seen
+struct CountingBackend {
+    mock: MockProofGenerator,
+    running: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl ProofBackend for CountingBackend {
+    fn name(&self) -> &'static str {
+        "counting"
+    }
+
+    fn generate_proof(
+        &self,
+        request: ProverRequest,
+        context: &ProofContext,
+    ) -> Result<ProofData, ServiceError> {
+        let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak.fetch_max(running, Ordering::SeqCst);
+        let proof = self.mock.generate_proof(request, context);
+        self.running.fetch_sub(1, Ordering::SeqCst);
+        proof
+    }
+}
+
+fn counting_backend() -> Arc<CountingBackend> {
+    Arc::new(CountingBackend {
+        mock: MockProofGenerator::new(Duration::from_millis(30), Duration::from_secs(60)),
+        running: AtomicUsize::new(0),
+        peak: AtomicUsize::new(0),
+    })
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn lines_in_flight_are_bounded_by_the_permits_held() {
+    let dir = temp_dir("permits");
+    let job = file_job(&dir, INPUT);
+    let mut config = config(4);
+    config.max_concurrent_proofs = 1;
+    assert_eq!(config.bulk_permits(), 1);
+    let backend = counting_backend();
+    let summary = run_job(
+        &job,
+        &config,
+        backend.clone(),
+        None,
+        None,
+        None,
+        &CancellationToken::new(),
+        |_| {},
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(summary.succeeded, 3);
+    assert_eq!(backend.peak.load(Ordering::SeqCst), 1);
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn each_line_takes_a_global_lease() {
+    let dir = temp_dir("leases");
+    let job = file_job(&dir, INPUT);
+    let mut config = config(4);
+    config.max_concurrent_proofs = 4;
+    let leases = Arc::new(MemoryLeases::default());
+    let global = GlobalAdmission::new(leases.clone(), 1, Duration::from_secs(10), "bulk")
+        .with_poll_interval(Duration::from_millis(5));
+    let backend = counting_backend();
+    let summary = run_job(
+        &job,
+        &config,
+        backend.clone(),
+        None,
+        None,
+        Some(Arc::new(global)),
+        &CancellationToken::new(),
+        |_| {},
+    )
+    .await
+    .unwrap();
+
+    // Four permits held, but the cluster allows one proof at a time
+    assert_eq!(summary.succeeded, 3);
+    assert_eq!(backend.peak.load(Ordering::SeqCst), 1);
+    assert_eq!(leases.held(), 0);
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[tokio::test]
+async fn oversized_input_fails_the_job_without_reading_it() {
+    let dir = temp_dir("oversized");
+    let job = file_job(&dir, INPUT);
+    let mut config = config(2);
+    config.bulk_max_input_bytes = 64;
+    let err = run_job(
+        &job,
+        &config,
+        backend(),
+        None,
+        None,
+        None,
+        &CancellationToken::new(),
+        |_| {},
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("BULK_MAX_INPUT_BYTES"), "{}", err);
+    assert!(!dir.join("out").exists());
+
+    let store = MemoryObjects::default();
+    store.insert("gs://analytics/batches/input.jsonl", INPUT.as_bytes());
+    let location = JobLocation::parse("gs://analytics/batches/input.jsonl", false).unwrap();
+    assert!(location.read(Some(&store), 64).await.is_err());
+    assert_eq!(
+        location
+            .read(Some(&store), INPUT.len() as u64)
+            .await
+            .unwrap(),
+        INPUT.as_bytes()
+    );
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[tokio::test]
+async fn gcs_job_reads_and_writes_the_object_store() {
+    let store = MemoryObjects::default();
+    store.insert("gs://analytics/batches/input.jsonl", INPUT.as_bytes());
+    let job = BulkJob {
+        request_id: "bulk-job-2".to_string(),
+        input_uri: "gs://analytics/batches/input.jsonl".to_string(),
+        output_uri: "gs://analytics/results/job-2/".to_string(),
+        weights_profile: Some("v1".to_string()),
+    };
+    assert!(job.uses_gcs());
+    let summary = run_job(
+        &job,
+        &config(4),
+        backend(),
+        Some(&store),
+        None,
+        None,
+        &CancellationToken::new(),
+        |_| {},
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        summary.results_uri,
+        "gs://analytics/results/job-2/results.jsonl"
+    );
+    let results = store.object(&summary.results_uri).unwrap();
+    assert_eq!(json_lines::<ProverResponse>(&results).len(), 3);
+    assert!(store.object(&summary.errors_uri).is_some());
+    assert!(store
+        .object("gs://analytics/results/job-2/summary.json")
+        .is_some());
+}
+
+#[tokio::test]
+async fn unreadable_input_fails_the_job_without_output() {
+    let dir = temp_dir("missing");
+    let mut job = file_job(&dir, "");
+    job.input_uri = format!("file://{}", dir.join("missing.jsonl").display());
+    let outcome = run_job(
+        &job,
+        &config(2),
+        backend(),
+        None,
+        None,
+        None,
+        &CancellationToken::new(),
+        |_| {},
+    )
+    .await;
+    let err = outcome.as_ref().unwrap_err();
+    assert!(matches!(err, ServiceError::Storage(_)), "{}", err);
+    assert!(!dir.join("out").exists());
+
+    let completion = completion(&job, &outcome);
+    assert_eq!(completion.status, BulkJobStatus::Failed);
+    assert!(completion.summary_uri.is_none());
+    assert!(completion.error.unwrap().contains("missing.jsonl"));
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[tokio::test]
+async fn interrupted_job_stops_between_chunks() {
+    let dir = temp_dir("interrupted");
+    let job = file_job(&dir, INPUT);
+    let interrupt = CancellationToken::new();
+    interrupt.cancel();
+    let err = run_job(
+        &job,
+        &config(2),
+        backend(),
+        None,
+        None,
+        None,
+        &interrupt,
+        |_| {},
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("interrupted"), "{}", err);
+    assert!(!dir.join("out").exists());
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn file_uris_need_to_be_allowed() {
+    let err = JobLocation::parse("file:///tmp/input.jsonl", false).unwrap_err();
+    assert!(err.to_string().contains("BULK_ALLOW_FILE_URIS"), "{}", err);
+    assert_eq!(
+        JobLocation::parse("file:///tmp/input.jsonl", true).unwrap(),
+        JobLocation::File(PathBuf::from("/tmp/input.jsonl"))
+    );
+    assert!(JobLocation::parse("https://example.com/input.jsonl", true).is_err());
+    assert!(JobLocation::parse("gs://bucket-only", true).is_err());
+}
+
+#[tokio::test]
+async fn completion_is_a_slim_message_pointing_at_the_summary() {
+    let dir = temp_dir("completion");
+    let job = file_job(&dir, INPUT);
+    let outcome = run_job(
+        &job,
+        &config(2),
+        backend(),
+        None,
+        None,
+        None,
+        &CancellationToken::new(),
+        |_| {},
+    )
+    .await;
+    let completion = completion(&job, &outcome);
+    assert_eq!(completion.status, BulkJobStatus::Completed);
+    assert_eq!(
+        completion.summary_uri,
+        Some(format!(
+            "file://{}",
+            dir.join("out").join(SUMMARY_FILE).display()
+        ))
+    );
+    assert_eq!(
+        (completion.lines, completion.succeeded, completion.rejected),
+        (5, 3, 2)
+    );
+
+    let message = completion_message(&completion).unwrap();
+    assert_eq!(message.attributes["message_type"], COMPLETION_MESSAGE_TYPE);
+    let published: serde_json::Value = serde_json::from_slice(&message.data).unwrap();
+    assert_eq!(published["status"], "completed");
+    assert!(published.get("proofs").is_none());
+    std::fs::remove_dir_all(&dir).ok();
+}
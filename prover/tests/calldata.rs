@@ -0,0 +1,187 @@
+// Round-trips the Groth16 fixture through verifyPicoProof calldata and checks it
+// with the Rust verifier, to catch encoding mismatches with Groth16Verifier.sol
+// before deployment.
+//
+// testdata/groth16_vk.bin and testdata/groth16_proof.json are a synthetic key and
+// proof in gnark and ProofData format, generated from a known trapdoor over the
+// public values of a real request.
+//
+// testdata/verify_pico_proof_calldata.hex is the call for that fixture, encoded by
+// alloy-sol-types (the ABI encoder behind `cast calldata`) rather than by us:
+//
+//   cast calldata 'verifyPicoProof(bytes32,bytes,uint256[8])' \
+//     <riscvVKey> <publicValues> '[<proof words>]'
+//
+// Also checks that a human index committed out of range is refused on decode.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use human_index_lib::PublicValuesError;
+use prover::error::DecodeError;
+use prover::evm::{
+    build_verify_calldata, decode_calldata, public_values_digest, EvmError, VerifyCall,
+    VERIFY_PICO_PROOF_SELECTOR,
+};
+use prover::mock::MockProofGenerator;
+use prover::prover::{encode_proof_data, ProofBackend, ProofContext};
+use prover::types::ProofData;
+use prover::verifier::{verify_proof_data, VerifierError, VerifyingKey};
+use serde_json::json;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+fn fixture() -> (VerifyingKey, ProofData) {
+    let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata");
+    let vk = VerifyingKey::from_file(&testdata.join("groth16_vk.bin")).expect("fixture key parses");
+    let proof_json =
+        std::fs::read_to_string(testdata.join("groth16_proof.json")).expect("fixture proof exists");
+    let proof_data = serde_json::from_str(&proof_json).expect("fixture proof parses");
+    (vk, proof_data)
+}
+
+/// Re-encode `proof_data` with other proof words and public values
+fn reencode(proof_data: &ProofData, proof: &[String], public_values: &[u8]) -> ProofData {
+    let vkey = STANDARD.decode(&proof_data.verification_key).unwrap();
+    encode_proof_data(
+        proof,
+        &format!("0x{}", hex::encode(public_values)),
+        std::str::from_utf8(&vkey).unwrap(),
+        proof_data.human_index,
+        &[0; 32],
+    )
+    .unwrap()
+}
+
+fn proof_words(proof_data: &ProofData) -> Vec<String> {
+    serde_json::from_slice(&proof_data.decompressed_proof().unwrap()).unwrap()
+}
+
+#[test]
+fn fixture_verifies_through_calldata() {
+    let (vk, proof_data) = fixture();
+
+    let call = verify_proof_data(&vk, &proof_data).expect("fixture proof verifies");
+    assert_eq!(call, VerifyCall::from_proof_data(&proof_data).unwrap());
+}
+
+/// The reference calldata for the fixture proof
+fn reference_calldata() -> Vec<u8> {
+    let path =
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/verify_pico_proof_calldata.hex");
+    let hex_calldata = std::fs::read_to_string(path).expect("reference calldata exists");
+    hex::decode(hex_calldata.trim().trim_start_matches("0x")).unwrap()
+}
+
+#[test]
+fn calldata_matches_the_reference_encoding() {
+    let (_, proof_data) = fixture();
+    let reference = reference_calldata();
+    assert_eq!(reference[..4], [0xee, 0x87, 0xde, 0x37]);
+    assert_eq!(reference[..4], VERIFY_PICO_PROOF_SELECTOR);
+
+    // Byte for byte, including the offset of `bytes` and the padding of the
+    // 56 bytes of public values to 64
+    let calldata = build_verify_calldata(&proof_data).unwrap();
+    assert_eq!(hex::encode(&calldata), hex::encode(&reference));
+    assert_eq!(
+        decode_calldata(&reference).unwrap(),
+        VerifyCall::from_proof_data(&proof_data).unwrap()
+    );
+}
+
+#[test]
+fn decoded_calldata_matches_contract_inputs() {
+    let (vk, proof_data) = fixture();
+    let call = decode_calldata(&build_verify_calldata(&proof_data).unwrap()).unwrap();
+
+    let public_values_hex = STANDARD.decode(&proof_data.public_inputs).unwrap();
+    let public_values_hex = std::str::from_utf8(&public_values_hex).unwrap();
+    let public_values = hex::decode(public_values_hex.trim_start_matches("0x")).unwrap();
+    assert_eq!(call.public_values, public_values);
+
+    let [vkey_input, digest_input] = call.public_inputs();
+    assert_eq!(vkey_input, call.riscv_vkey);
+    assert_eq!(digest_input, public_values_digest(&public_values));
+    assert!(digest_input[0] < 0x20, "digest is truncated to 253 bits");
+
+    // The decoded call verifies on its own, as the contract sees it
+    vk.verify_call(&call).expect("decoded call verifies");
+}
+
+#[test]
+fn tampered_public_values_are_rejected() {
+    let (vk, proof_data) = fixture();
+    let mut call = VerifyCall::from_proof_data(&proof_data).unwrap();
+    call.public_values[0] ^= 1;
+
+    let tampered = reencode(&proof_data, &proof_words(&proof_data), &call.public_values);
+    assert!(matches!(
+        verify_proof_data(&vk, &tampered),
+        Err(VerifierError::InvalidProof(_))
+    ));
+}
+
+#[test]
+fn swapped_g2_coordinates_are_rejected() {
+    let (vk, proof_data) = fixture();
+    let call = VerifyCall::from_proof_data(&proof_data).unwrap();
+
+    // B in (c0, c1) order instead of the (c1, c0) order the contract expects
+    let mut words = proof_words(&proof_data);
+    words.swap(2, 3);
+    words.swap(4, 5);
+
+    let swapped = reencode(&proof_data, &words, &call.public_values);
+    assert!(matches!(
+        verify_proof_data(&vk, &swapped),
+        Err(VerifierError::InvalidProof(_))
+    ));
+}
+
+#[test]
+fn committed_human_index_matches_proof_data() {
+    let (vk, proof_data) = fixture();
+    let call = verify_proof_data(&vk, &proof_data).unwrap();
+    assert_eq!(call.human_index().unwrap(), proof_data.human_index);
+
+    let mut mislabelled = proof_data.clone();
+    mislabelled.human_index ^= 1;
+    assert!(matches!(
+        verify_proof_data(&vk, &mislabelled),
+        Err(VerifierError::InvalidProof(_))
+    ));
+}
+
+#[test]
+fn out_of_range_human_index_is_rejected_on_decode() {
+    let request = serde_json::from_value(json!({
+        "request_id": "out-of-range",
+        "verification_results": { "recaptcha_score": 7500, "sms_verified": 1, "bio_verified": 1 },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 },
+    }))
+    .unwrap();
+    let context = ProofContext::new(Instant::now() + Duration::from_secs(60), 0);
+    let proof_data = MockProofGenerator::new(Duration::ZERO, Duration::from_secs(60))
+        .generate_proof(request, &context)
+        .unwrap();
+
+    // Values a guest without the range check could have committed
+    let mut public_values = proof_data.public_values().unwrap();
+    public_values.computed_output = 900;
+    let forged = reencode(
+        &proof_data,
+        &proof_words(&proof_data),
+        &bincode::serialize(&public_values).unwrap(),
+    );
+    let expected = PublicValuesError::OutputOutOfRange {
+        computed_output: 900,
+    };
+
+    assert!(matches!(
+        forged.public_values(),
+        Err(DecodeError::PublicValues(e)) if e == expected
+    ));
+    assert!(matches!(
+        VerifyCall::from_proof_data(&forged).unwrap().human_index(),
+        Err(EvmError::PublicValues(e)) if e == expected
+    ));
+}
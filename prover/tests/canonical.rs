@@ -0,0 +1,61 @@
+// Requests are hashed over canonical JSON of their inputs, so the same inputs
+// hash alike however the request JSON orders its fields or spaces them out.
+
+use prover::canonical::{canonical_bytes, canonical_json};
+use prover::quarantine::request_hash;
+use prover::types::ProverRequest;
+use serde_json::json;
+
+fn parse(json: &str) -> ProverRequest {
+    serde_json::from_str(json).unwrap()
+}
+
+const REQUEST: &str = r#"{
+    "request_id": "canonical",
+    "verification_results": { "recaptcha_score": 7500, "sms_verified": 1, "bio_verified": 0 },
+    "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 }
+}"#;
+
+/// `REQUEST` with every object's fields in another order, and no whitespace
+const REORDERED: &str = r#"{"public_inputs":{"expected_output":0,"w4":4000,"w3":2500,"w2":2000,"w1":1500},"verification_results":{"bio_verified":0,"sms_verified":1,"recaptcha_score":7500},"request_id":"canonical"}"#;
+
+#[test]
+fn field_order_does_not_change_the_hash() {
+    let request = parse(REQUEST);
+    let reordered = parse(REORDERED);
+    assert_eq!(canonical_bytes(&request), canonical_bytes(&reordered));
+    assert_eq!(request_hash(&request), request_hash(&reordered));
+}
+
+#[test]
+fn canonical_bytes_sort_keys_without_whitespace() {
+    assert_eq!(
+        String::from_utf8(canonical_bytes(&parse(REQUEST))).unwrap(),
+        concat!(
+            r#"{"public_inputs":{"expected_output":0,"w1":1500,"w2":2000,"w3":2500,"w4":4000},"#,
+            r#""verification_results":{"bio_verified":0,"recaptcha_score":7500,"sms_verified":1}}"#
+        )
+    );
+}
+
+#[test]
+fn only_the_inputs_are_hashed() {
+    let request = parse(REQUEST);
+    let mut renamed = parse(REORDERED);
+    renamed.request_id = "another".to_string();
+    renamed.trace_id = Some("trace".to_string());
+    assert_eq!(request_hash(&request), request_hash(&renamed));
+
+    let mut changed = parse(REQUEST);
+    changed.public_inputs.w1 += 1;
+    assert_ne!(request_hash(&request), request_hash(&changed));
+}
+
+#[test]
+fn nested_keys_are_sorted() {
+    let value = json!({ "b": [{ "z": 1, "a": "x" }, 2], "a": { "d": null, "c": true } });
+    assert_eq!(
+        String::from_utf8(canonical_json(&value).unwrap()).unwrap(),
+        r#"{"a":{"c":true,"d":null},"b":[{"a":"x","z":1},2]}"#
+    );
+}
@@ -0,0 +1,61 @@
+// Fixtures shared by the test binaries that drive the request pipeline without
+// Pub/Sub: a base configuration, read from the environment once per binary, and
+// the request most tests start from. Each binary uses only some of them.
+#![allow(dead_code)]
+
+use prover::config::Config;
+use serde_json::{json, Value};
+use std::sync::{Mutex, OnceLock};
+
+/// Held while the environment is changed and read, by every test in the binary
+static ENV: Mutex<()> = Mutex::new(());
+
+/// The configuration of a worker, with nothing to wait for before proving
+///
+/// The environment is set and read once, under `ENV`, so tests in one binary
+/// never race on it: change fields of the returned copy rather than variables.
+pub fn config() -> Config {
+    static BASE: OnceLock<Config> = OnceLock::new();
+    let mut config = BASE
+        .get_or_init(|| {
+            env_config(&[
+                ("GCP_PROJECT_ID", "prover-test"),
+                ("PROVER_SUBSCRIPTION", "unused"),
+                ("RESULT_TOPIC", "unused"),
+            ])
+        })
+        .clone();
+    config.memory_wait_secs = 0;
+    config
+}
+
+/// The configuration read with `vars` set, for tests of how it is read
+///
+/// The variables are removed again before `ENV` is released.
+pub fn env_config(vars: &[(&str, &str)]) -> Config {
+    let _env = ENV.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    for (name, value) in vars {
+        std::env::set_var(name, value);
+    }
+    let config = Config::from_env();
+    for (name, _) in vars {
+        std::env::remove_var(name);
+    }
+    config.expect("configuration")
+}
+
+/// `config` for bulk jobs reading and writing `file://` URIs
+pub fn bulk_config() -> Config {
+    let mut config = config();
+    config.bulk_allow_file_uris = true;
+    config
+}
+
+/// A request with the v1 weights as JSON, for tests to change before parsing
+pub fn request_json(request_id: &str) -> Value {
+    json!({
+        "request_id": request_id,
+        "verification_results": { "recaptcha_score": 8000, "sms_verified": 1, "bio_verified": 1 },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 },
+    })
+}
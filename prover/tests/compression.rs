@@ -0,0 +1,141 @@
+// Proof compression in results: both algorithms round-trip a fast-proof-sized
+// payload and mark the proof data, a corrupted or truncated stream is an error rather
+// than garbage, and a proof that would not shrink is left as it was.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use prover::compression::ContentEncoding;
+use prover::types::ProofData;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+const ENCODINGS: [ContentEncoding; 2] = [ContentEncoding::Gzip, ContentEncoding::Zstd];
+
+/// Proof data around an empty proof
+fn fixture() -> ProofData {
+    serde_json::from_value(json!({
+        "proof": "",
+        "public_inputs": STANDARD.encode(b"public values"),
+        "verification_key": STANDARD.encode(b"0x00"),
+        "human_index": 236,
+        "weights_commitment": format!("0x{}", "ab".repeat(32)),
+    }))
+    .unwrap()
+}
+
+/// Proof bytes shaped like a serialized fast proof: repetitive structure around
+/// varying words
+fn large_proof() -> Vec<u8> {
+    (0u32..64 * 1024)
+        .flat_map(|i| {
+            let word = (i % 251).to_le_bytes();
+            [word[0], word[1], 0, 0, 0, 0, 0, 0]
+        })
+        .collect()
+}
+
+/// Hash output, which does not compress
+fn incompressible_proof() -> Vec<u8> {
+    (0u8..8)
+        .flat_map(|i| Sha256::digest([i]).to_vec())
+        .collect()
+}
+
+fn with_proof(proof: &[u8]) -> ProofData {
+    let mut proof_data = fixture();
+    proof_data.proof = STANDARD.encode(proof);
+    proof_data
+}
+
+#[test]
+fn both_encodings_round_trip() {
+    let raw = large_proof();
+    for encoding in ENCODINGS {
+        let mut proof_data = with_proof(&raw);
+        assert!(proof_data.compress_proof(encoding).unwrap());
+        assert_eq!(proof_data.content_encoding, Some(encoding));
+        let compressed = STANDARD.decode(&proof_data.proof).unwrap();
+        assert!(
+            compressed.len() * 4 < raw.len(),
+            "{} gave {} bytes",
+            encoding,
+            compressed.len()
+        );
+        assert_eq!(proof_data.decompressed_proof().unwrap(), raw);
+
+        // The field survives the wire
+        let json = serde_json::to_value(&proof_data).unwrap();
+        assert_eq!(json["content_encoding"], encoding.as_str());
+        let published: ProofData = serde_json::from_value(json).unwrap();
+        assert_eq!(published.decompressed_proof().unwrap(), raw);
+
+        // Compressing again is a no-op
+        assert!(!proof_data.compress_proof(encoding).unwrap());
+        assert_eq!(proof_data.decompressed_proof().unwrap(), raw);
+    }
+}
+
+#[test]
+fn corrupted_streams_are_errors() {
+    let raw = large_proof();
+    for encoding in ENCODINGS {
+        let compressed = encoding.compress(&raw).unwrap();
+
+        let mut corrupted = compressed.clone();
+        let middle = corrupted.len() / 2;
+        for byte in &mut corrupted[middle..middle + 16] {
+            *byte ^= 0xff;
+        }
+        let truncated = &compressed[..compressed.len() / 2];
+
+        for (name, damaged) in [
+            ("corrupted", &corrupted[..]),
+            ("truncated", truncated),
+            ("plain", b"not compressed"),
+        ] {
+            let mut proof_data = with_proof(damaged);
+            proof_data.content_encoding = Some(encoding);
+            let err = proof_data
+                .decompressed_proof()
+                .err()
+                .unwrap_or_else(|| panic!("{} {} decoded", encoding, name));
+            assert_eq!(err.error_type(), "CompressionError", "{}", err);
+        }
+    }
+}
+
+#[test]
+fn incompressible_proofs_are_left_uncompressed() {
+    let original = with_proof(&incompressible_proof());
+    for encoding in ENCODINGS {
+        let mut proof_data = original.clone();
+        assert!(!proof_data.compress_proof(encoding).unwrap());
+        assert_eq!(proof_data.proof, original.proof);
+        assert_eq!(proof_data.content_encoding, None);
+        assert_eq!(
+            proof_data.decompressed_proof().unwrap(),
+            STANDARD.decode(&original.proof).unwrap()
+        );
+
+        let json = serde_json::to_value(&proof_data).unwrap();
+        assert!(json.get("content_encoding").is_none());
+    }
+}
+
+#[test]
+fn encodings_parse_by_name() {
+    assert_eq!(
+        "gzip".parse::<ContentEncoding>().unwrap(),
+        ContentEncoding::Gzip
+    );
+    assert_eq!(
+        "ZSTD".parse::<ContentEncoding>().unwrap(),
+        ContentEncoding::Zstd
+    );
+    assert_eq!(
+        "brotli"
+            .parse::<ContentEncoding>()
+            .unwrap_err()
+            .error_type(),
+        "CompressionError"
+    );
+}
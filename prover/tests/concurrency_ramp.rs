@@ -0,0 +1,114 @@
+// The startup concurrency ramp over simulated time: permits grow one step at a
+// time across the warm-up window, and only as far as earlier proofs succeeded.
+
+use prover::ramp::ConcurrencyRamp;
+use prover::scheduler::{Lanes, Scheduler};
+use prover::types::Priority;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+
+const WARMUP: Duration = Duration::from_secs(30);
+
+fn secs(secs: f64) -> Duration {
+    Duration::from_secs_f64(secs)
+}
+
+#[test]
+fn concurrency_increases_step_wise_over_the_warmup() {
+    let start = Instant::now();
+    let mut ramp = ConcurrencyRamp::new(4, WARMUP, start);
+    for _ in 0..3 {
+        ramp.record_success();
+    }
+
+    // One step every 10 seconds, from 1 permit to 4
+    let expected = [
+        (0.0, 1),
+        (5.0, 1),
+        (9.9, 1),
+        (10.0, 2),
+        (19.9, 2),
+        (20.0, 3),
+        (29.9, 3),
+        (30.0, 4),
+        (90.0, 4),
+    ];
+    let mut granted = 1;
+    for (at, permits) in expected {
+        let new = ramp.advance(start + secs(at));
+        assert_eq!(ramp.granted(), permits, "at {}s", at);
+        assert_eq!(new, permits - granted, "at {}s", at);
+        granted = permits;
+    }
+    assert!(ramp.is_complete());
+}
+
+#[test]
+fn concurrency_grows_only_as_proofs_succeed() {
+    let start = Instant::now();
+    let mut ramp = ConcurrencyRamp::new(4, WARMUP, start);
+
+    // Without a success the warm-up window alone unlocks nothing
+    assert_eq!(ramp.advance(start + secs(60.0)), 0);
+    assert_eq!(ramp.granted(), 1);
+    assert!(!ramp.is_complete());
+
+    // Past the window, each success unlocks one more permit
+    for permits in 2..=4 {
+        ramp.record_success();
+        assert_eq!(ramp.advance(start + secs(60.0)), 1);
+        assert_eq!(ramp.granted(), permits);
+    }
+    assert!(ramp.is_complete());
+
+    // Within the window, successes do not get ahead of the schedule
+    let mut ramp = ConcurrencyRamp::new(4, WARMUP, start);
+    for _ in 0..3 {
+        ramp.record_success();
+    }
+    assert_eq!(ramp.limit(start + secs(12.0)), 2);
+}
+
+#[test]
+fn new_target_takes_back_excess_permits() {
+    let start = Instant::now();
+    let mut ramp = ConcurrencyRamp::new(8, WARMUP, start);
+    for _ in 0..7 {
+        ramp.record_success();
+    }
+    ramp.advance(start + secs(24.0));
+    assert_eq!(ramp.granted(), 6);
+
+    assert_eq!(ramp.set_target(4), 2);
+    assert_eq!(ramp.granted(), 4);
+    assert!(ramp.is_complete());
+
+    // A higher target is ramped toward like the original one
+    assert_eq!(ramp.set_target(10), 0);
+    assert_eq!(ramp.limit(start + secs(15.0)), 5);
+    assert_eq!(ramp.limit(start + secs(30.0)), 8);
+}
+
+#[tokio::test]
+async fn heavy_request_runs_within_the_permits_granted_so_far() {
+    // One permit granted of a pool of 4, as at the start of the warm-up
+    let semaphore = Arc::new(Semaphore::new(1));
+    let scheduler = Scheduler::start(
+        semaphore,
+        Lanes {
+            capacity: 1,
+            interactive_reserved: 0,
+            batch_promotion: None,
+            queue_wait_warning: None,
+        },
+    );
+
+    for priority in [Priority::Normal, Priority::Interactive] {
+        let permit =
+            tokio::time::timeout(Duration::from_secs(5), scheduler.acquire("", priority, 4))
+                .await
+                .expect("heavy request admitted");
+        assert!(permit.is_some());
+    }
+}
@@ -0,0 +1,130 @@
+// `GET /config` on the status server: the effective configuration, as last reloaded,
+// with project IDs masked and none of the credentials other configs read.
+
+mod common;
+
+use prover::config::{mask_project_id, Config};
+use prover::delayed::DelayedQueue;
+use prover::endpoints::Endpoints;
+use prover::janitor::Janitor;
+use prover::provenance::Provenance;
+use prover::scheduler::{Lanes, Scheduler};
+use prover::status::{self, StatusState};
+use serde_json::Value;
+use std::sync::{Arc, RwLock};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Semaphore;
+
+const PROJECT_ID: &str = "prover-production-4821";
+const FAILOVER_PROJECT_ID: &str = "prover-failover-9913";
+const SIGNING_KEY: &str = "callback-signing-secret-5f2c";
+
+fn config() -> Config {
+    let endpoints = format!(
+        "{}/prover-requests/prover-results,{}/prover-requests/prover-results",
+        PROJECT_ID, FAILOVER_PROJECT_ID
+    );
+    common::env_config(&[
+        ("GCP_PROJECT_ID", PROJECT_ID),
+        ("PROVER_SUBSCRIPTION", "prover-requests"),
+        ("RESULT_TOPIC", "prover-results"),
+        ("PUBSUB_ENDPOINTS", &endpoints),
+        ("CALLBACK_SIGNING_KEY", SIGNING_KEY),
+        ("MAX_CONCURRENT_PROOFS", "3"),
+    ])
+}
+
+/// Serve the status endpoints for `config` and return their address
+async fn serve(config: Arc<RwLock<Config>>) -> std::net::SocketAddr {
+    let snapshot = config.read().unwrap().clone();
+    let dir = std::env::temp_dir().join(format!("prover-config-endpoint-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let scheduler = Scheduler::start(
+        Arc::new(Semaphore::new(1)),
+        Lanes {
+            capacity: 1,
+            interactive_reserved: 0,
+            batch_promotion: None,
+            queue_wait_warning: None,
+        },
+    );
+    let state = StatusState::new(
+        "mock",
+        None,
+        Provenance::collect(&snapshot, None),
+        scheduler,
+        Arc::new(DelayedQueue::open(&dir.join("delayed.sqlite")).unwrap()),
+        Arc::new(Janitor::new(&dir, &dir)),
+        Arc::new(Endpoints::new(snapshot.endpoints(), 0)),
+    )
+    .with_config(config);
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let router = status::router(Arc::new(state));
+    tokio::spawn(async move { axum::serve(listener, router).await });
+    addr
+}
+
+async fn get_config(addr: std::net::SocketAddr) -> (String, Value) {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(b"GET /config HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+    let body = response.split("\r\n\r\n").nth(1).unwrap().to_string();
+    let config = serde_json::from_str(&body).unwrap();
+    (body, config)
+}
+
+#[tokio::test]
+async fn config_endpoint_reports_settings_and_masks_project_ids() {
+    let config = Arc::new(RwLock::new(config()));
+    let addr = serve(config.clone()).await;
+    let (body, reported) = get_config(addr).await;
+
+    // Settings as resolved from the environment and defaults
+    assert_eq!(reported["role"], "all");
+    assert_eq!(reported["prover_subscription"], "prover-requests");
+    assert_eq!(reported["result_topic"], "prover-results");
+    assert_eq!(reported["max_concurrent_proofs"], 3);
+    assert_eq!(reported["prover_backend"], "pico");
+    assert_eq!(reported["prover_profile"], "evm");
+    assert_eq!(reported["request_id_format"], "uuid");
+    assert_eq!(reported["signal_policy"], "reject");
+    assert_eq!(reported["docker_preflight"], "warn");
+    assert_eq!(reported["cost_formula"]["terms"][0][0], "cpu_seconds");
+
+    // Project IDs are masked wherever they appear
+    assert_eq!(reported["gcp_project_id"], mask_project_id(PROJECT_ID));
+    let endpoints = reported["pubsub_endpoints"].as_array().unwrap();
+    assert_eq!(endpoints.len(), 2);
+    assert_eq!(
+        endpoints[1]["project_id"],
+        mask_project_id(FAILOVER_PROJECT_ID)
+    );
+    assert_eq!(endpoints[1]["subscription"], "prover-requests");
+    for secret in [PROJECT_ID, FAILOVER_PROJECT_ID, SIGNING_KEY] {
+        assert!(!body.contains(secret), "{} in {}", secret, body);
+    }
+
+    // A reload shows up in the next response
+    config.write().unwrap().max_concurrent_proofs = 5;
+    let (_, reported) = get_config(addr).await;
+    assert_eq!(reported["max_concurrent_proofs"], 5);
+}
+
+#[test]
+fn project_ids_keep_only_a_short_prefix() {
+    assert_eq!(
+        mask_project_id("prover-production-4821"),
+        "prov******************"
+    );
+    assert_eq!(mask_project_id("abcdef"), "ab****");
+    assert_eq!(mask_project_id("ab"), "**");
+    assert_eq!(mask_project_id(""), "");
+}
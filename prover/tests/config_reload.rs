@@ -0,0 +1,99 @@
+// Settings a SIGHUP reload applies without a restart: the log filter is swapped in
+// place, and proof concurrency is resized, with a shrink that is still waiting for
+// running proofs netted against a later grow.
+
+use prover::logging::reload_log_level;
+use prover::scheduler::Capacity;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use tracing::Level;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+/// Wait for the permits available to settle at `expected`
+async fn wait_for_available(semaphore: &Semaphore, expected: usize) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while semaphore.available_permits() != expected {
+        assert!(
+            Instant::now() < deadline,
+            "{} permits available, expected {}",
+            semaphore.available_permits(),
+            expected
+        );
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    // Nothing is left to retire them afterwards
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert_eq!(semaphore.available_permits(), expected);
+}
+
+#[test]
+fn log_level_reload_takes_effect() {
+    let (filter, handle) = reload::Layer::new(EnvFilter::new("info"));
+    let subscriber = Registry::default().with(filter);
+    tracing::subscriber::with_default(subscriber, || {
+        assert!(tracing::enabled!(Level::INFO));
+        assert!(!tracing::enabled!(Level::DEBUG));
+
+        reload_log_level(&handle, "debug").unwrap();
+        assert!(tracing::enabled!(Level::DEBUG));
+
+        reload_log_level(&handle, "warn").unwrap();
+        assert!(!tracing::enabled!(Level::INFO));
+
+        // An invalid level keeps the current filter
+        let err = reload_log_level(&handle, "prover=loud").unwrap_err();
+        assert!(err.to_string().contains("LOG_LEVEL"), "{}", err);
+        assert!(tracing::enabled!(Level::WARN));
+        assert!(!tracing::enabled!(Level::INFO));
+    });
+}
+
+#[tokio::test]
+async fn shrink_waits_for_running_proofs() {
+    let semaphore = Arc::new(Semaphore::new(4));
+    let capacity = Capacity::new(semaphore.clone());
+    let running = semaphore.clone().acquire_many_owned(4).await.unwrap();
+
+    // 4 -> 2 while all four permits are held
+    capacity.shrink(2);
+    assert_eq!(capacity.pending_shrink(), 2);
+    drop(running);
+    wait_for_available(&semaphore, 2).await;
+    assert_eq!(capacity.pending_shrink(), 0);
+
+    capacity.grow(3);
+    wait_for_available(&semaphore, 5).await;
+}
+
+#[tokio::test]
+async fn grow_cancels_a_pending_shrink() {
+    let semaphore = Arc::new(Semaphore::new(4));
+    let capacity = Capacity::new(semaphore.clone());
+    let running = semaphore.clone().acquire_many_owned(4).await.unwrap();
+
+    // 4 -> 2 -> 5 before any proof finishes: the shrink must not take the new permits
+    capacity.shrink(2);
+    tokio::task::yield_now().await;
+    capacity.grow(3);
+    assert_eq!(capacity.pending_shrink(), 0);
+    drop(running);
+    wait_for_available(&semaphore, 5).await;
+}
+
+#[tokio::test]
+async fn grow_pays_off_part_of_a_pending_shrink() {
+    let semaphore = Arc::new(Semaphore::new(4));
+    let capacity = Capacity::new(semaphore.clone());
+    let running = semaphore.clone().acquire_many_owned(4).await.unwrap();
+
+    // 4 -> 1 -> 2: one of the three retired permits is kept
+    capacity.shrink(3);
+    tokio::task::yield_now().await;
+    capacity.grow(1);
+    assert_eq!(capacity.pending_shrink(), 2);
+    drop(running);
+    wait_for_available(&semaphore, 2).await;
+    assert_eq!(capacity.pending_shrink(), 0);
+}
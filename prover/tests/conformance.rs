@@ -0,0 +1,177 @@
+// Guest/host agreement: emulates the guest ELF for every golden vector and for
+// seeded random requests, and checks the public values it commits against what
+// the host expects for the request. Run with
+// `cargo test -p prover --features conformance`; `CONFORMANCE_ELF` names the ELF
+// when it is not the one `cargo pico build` writes to `app/elf`.
+//
+// The first vector the guest disagrees on fails the run with its inputs and a
+// field-by-field diff of what the host expected (`-`) and the guest committed (`+`).
+
+use human_index_lib::{
+    calculate_human_index, HumanIndexPublicInputs, ProofInputs, VerificationResults, SCALE,
+};
+use pico_sdk::client::DefaultProverClient;
+use prover::error::ServiceError;
+use prover::fixtures::{golden_vectors, GoldenVector, FIXTURE_VALIDITY};
+use prover::prover::{check_public_values, encode_proof_data, expected_public_values};
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+
+/// The ELF `cargo pico build` writes, relative to the prover crate
+const DEFAULT_ELF: &str = "../app/elf/riscv32im-pico-zkvm-elf";
+
+const RANDOM_VECTORS: usize = 64;
+
+/// Fewer vectors than this and the run proves too little to pass
+const MIN_VECTORS: usize = 50;
+
+/// Inside the window of the vector requests
+const NOW: u64 = FIXTURE_VALIDITY.issued_at + 60;
+
+fn elf_path() -> PathBuf {
+    match std::env::var_os("CONFORMANCE_ELF") {
+        Some(path) => PathBuf::from(path),
+        None => Path::new(env!("CARGO_MANIFEST_DIR")).join(DEFAULT_ELF),
+    }
+}
+
+/// Requests with the score, flags and weights in the ranges the service accepts
+fn random_vectors() -> Vec<GoldenVector> {
+    // xorshift32, so failures reproduce
+    let mut state = 0x2545_f491u32;
+    let mut next = |max: u32| {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state % (max + 1)
+    };
+
+    (0..RANDOM_VECTORS)
+        .map(|i| GoldenVector {
+            name: format!("random_{:02}", i),
+            verification_results: VerificationResults {
+                recaptcha_score: next(SCALE),
+                sms_verified: next(1),
+                bio_verified: next(1),
+            },
+            public_inputs: HumanIndexPublicInputs {
+                w1: next(SCALE),
+                w2: next(SCALE),
+                w3: next(SCALE),
+                w4: next(SCALE),
+                expected_output: 0,
+            },
+        })
+        .collect()
+}
+
+/// The pv stream the guest commits for `proof_inputs`
+fn emulate(client: &DefaultProverClient, proof_inputs: &ProofInputs) -> Result<Vec<u8>, String> {
+    let mut stdin_builder = client.new_stdin_builder();
+    proof_inputs.write_to(|value| stdin_builder.write(value));
+    // The guest panics on an index other than the expected output the host wrote
+    std::panic::catch_unwind(AssertUnwindSafe(|| client.emulate(stdin_builder).1))
+        .map_err(|_| "the guest panicked".to_string())
+}
+
+/// Diff lines of where the guest disagrees with the host on `vector`; empty if nowhere
+fn disagreement(client: &DefaultProverClient, vector: &GoldenVector) -> Vec<String> {
+    // Derived as the service derives it from a received request
+    let request = vector.request();
+    let host = calculate_human_index(&request.verification_results, &request.public_inputs);
+    let validity = request.validity(NOW);
+    let proof_inputs = ProofInputs::new(
+        request.verification_results,
+        request.public_inputs,
+        validity,
+    );
+    let derived = proof_inputs.expected_output;
+    let expected = expected_public_values(&proof_inputs);
+
+    let mut diff = Vec::new();
+    if host != derived {
+        diff.push(format!("- calculate_human_index: {}", host));
+        diff.push(format!("+ derived expected_output: {}", derived));
+    }
+
+    let pv_stream = match emulate(client, &proof_inputs) {
+        Ok(pv_stream) => pv_stream,
+        Err(e) => {
+            diff.push(format!("! {}", e));
+            return diff;
+        }
+    };
+    let committed = encode_proof_data(
+        &[] as &[String],
+        &format!("0x{}", hex::encode(&pv_stream)),
+        "",
+        derived,
+        &expected.weights_commitment,
+    )
+    .expect("an empty proof encodes");
+    match check_public_values(&committed, &expected) {
+        Ok(()) => {}
+        Err(ServiceError::PublicValuesMismatch(mismatch)) => {
+            for field in mismatch.0 {
+                diff.push(format!("- {}: {}", field.field, field.expected));
+                diff.push(format!("+ {}: {}", field.field, field.committed));
+            }
+        }
+        Err(e) => diff.push(format!("! {}", e)),
+    }
+    diff
+}
+
+#[test]
+fn guest_agrees_with_host_on_every_vector() {
+    let path = elf_path();
+    let elf = std::fs::read(&path).unwrap_or_else(|e| {
+        panic!(
+            "Failed to read the guest ELF at {}: {}; run `cargo pico build` in app/ or set CONFORMANCE_ELF",
+            path.display(),
+            e
+        )
+    });
+    let client = DefaultProverClient::new(&elf);
+
+    let mut vectors = golden_vectors().expect("golden vectors load");
+    vectors.extend(random_vectors());
+    assert!(
+        vectors.len() >= MIN_VECTORS,
+        "only {} vectors to check",
+        vectors.len()
+    );
+
+    for (agreed, vector) in vectors.iter().enumerate() {
+        let diff = disagreement(&client, vector);
+        assert!(
+            diff.is_empty(),
+            "guest and host disagree on `{}` ({} of {} vectors agreed before it)\n\
+             verification_results: {:?}\n\
+             public_inputs: {:?}\n\
+             {}",
+            vector.name,
+            agreed,
+            vectors.len(),
+            vector.verification_results,
+            vector.public_inputs,
+            diff.join("\n")
+        );
+    }
+}
+
+#[test]
+fn random_vectors_are_distinct() {
+    let vectors = random_vectors();
+    for (i, a) in vectors.iter().enumerate() {
+        for b in &vectors[i + 1..] {
+            assert!(
+                a.verification_results != b.verification_results
+                    || a.public_inputs != b.public_inputs,
+                "{} repeats {}",
+                b.name,
+                a.name
+            );
+        }
+    }
+}
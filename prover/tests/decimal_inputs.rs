@@ -0,0 +1,175 @@
+// Requests may send the score and weights as decimals instead of fixed-point.
+// Checks the exact conversion, that both shapes of a request hash alike, and the
+// weights echoed in responses.
+
+use human_index_lib::{to_fixed_exact, ConvertError, SCALE};
+use prover::decimal::{EchoedWeights, Weights};
+use prover::quarantine::request_hash;
+use prover::types::{ProofError, ProverRequest, ProverResponse};
+use serde_json::{json, Value};
+
+fn parse(request: Value) -> Result<ProverRequest, serde_json::Error> {
+    serde_json::from_value(request)
+}
+
+fn request(verification_results: Value, public_inputs: Value) -> Value {
+    json!({
+        "request_id": "decimal",
+        "verification_results": verification_results,
+        "public_inputs": public_inputs,
+    })
+}
+
+fn integer_style() -> Value {
+    request(
+        json!({ "recaptcha_score": 7500, "sms_verified": 1, "bio_verified": 0 }),
+        json!({ "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 }),
+    )
+}
+
+fn float_style() -> Value {
+    request(
+        json!({ "recaptcha_score": 0.75, "sms_verified": 1, "bio_verified": 0 }),
+        json!({ "w1": 0.15, "w2": 0.2, "w3": 0.25, "w4": 0.4, "expected_output": 0 }),
+    )
+}
+
+fn error(request: Value) -> String {
+    parse(request).unwrap_err().to_string()
+}
+
+#[test]
+fn integer_style_is_fixed_point() {
+    let request = parse(integer_style()).unwrap();
+    assert_eq!(request.verification_results.recaptcha_score, 7500);
+    assert_eq!(request.public_inputs.w1, 1500);
+    assert_eq!(request.public_inputs.w4, 4000);
+}
+
+#[test]
+fn float_style_converts_to_the_same_fixed_point() {
+    let integer = parse(integer_style()).unwrap();
+    let float = parse(float_style()).unwrap();
+    assert_eq!(
+        serde_json::to_value(&float).unwrap(),
+        serde_json::to_value(&integer).unwrap()
+    );
+    assert_eq!(request_hash(&float), request_hash(&integer));
+}
+
+#[test]
+fn mixed_style_converts_each_field_by_its_form() {
+    let mixed = request(
+        json!({ "recaptcha_score": 0.75, "sms_verified": 1, "bio_verified": 0 }),
+        json!({ "w1": 1500, "w2": 0.2, "w3": 2500, "w4": 0.4, "expected_output": 0 }),
+    );
+    let mixed = parse(mixed).unwrap();
+    assert_eq!(
+        request_hash(&mixed),
+        request_hash(&parse(integer_style()).unwrap())
+    );
+}
+
+#[test]
+fn a_decimal_point_makes_a_value_decimal() {
+    // 1.0 is the full score, where 1 is one fixed-point unit
+    let one = request(
+        json!({ "recaptcha_score": 1.0, "sms_verified": 1, "bio_verified": 1 }),
+        json!({ "w1": 1, "w2": 1e-4, "w3": 0.57, "w4": 1.2, "expected_output": 0 }),
+    );
+    let one = parse(one).unwrap();
+    assert_eq!(one.verification_results.recaptcha_score, SCALE);
+    assert_eq!(one.public_inputs.w1, 1);
+    assert_eq!(one.public_inputs.w2, 1);
+    // 0.57 * 10000 is 5699.999... in binary floating point
+    assert_eq!(one.public_inputs.w3, 5700);
+    // Weights may exceed 1.0
+    assert_eq!(one.public_inputs.w4, 12_000);
+}
+
+#[test]
+fn more_than_four_decimal_places_is_rejected() {
+    let mut request = float_style();
+    request["public_inputs"]["w1"] = json!(0.12345);
+    let message = error(request);
+    assert!(message.starts_with("w1: value 0.12345"), "{}", message);
+
+    let mut request = float_style();
+    request["verification_results"]["recaptcha_score"] = json!(0.00001);
+    let message = error(request);
+    assert!(message.starts_with("recaptcha_score:"), "{}", message);
+
+    assert_eq!(
+        to_fixed_exact(0.12345, SCALE),
+        Err(ConvertError::TooPrecise {
+            value: 0.12345,
+            scale: SCALE
+        })
+    );
+}
+
+#[test]
+fn out_of_range_decimals_are_rejected() {
+    let mut request = float_style();
+    request["verification_results"]["recaptcha_score"] = json!(1.5);
+    assert!(error(request).contains("above the maximum 1"));
+
+    let mut request = float_style();
+    request["public_inputs"]["w2"] = json!(-0.2);
+    assert!(error(request).contains("negative"));
+
+    let mut request = integer_style();
+    request["public_inputs"]["w3"] = json!(-2500);
+    assert!(error(request).contains("invalid value"));
+}
+
+#[test]
+fn flags_and_expected_output_stay_integers() {
+    let mut request = float_style();
+    request["verification_results"]["sms_verified"] = json!(1.0);
+    assert!(parse(request).is_err());
+
+    let mut request = float_style();
+    request["public_inputs"]["expected_output"] = json!(0.5);
+    assert!(parse(request).is_err());
+}
+
+#[test]
+fn responses_echo_weights_in_both_representations() {
+    let request = parse(float_style()).unwrap();
+    let mut response = ProverResponse::failed(
+        request.request_id.clone(),
+        ProofError {
+            error_type: "ProofGenerationError".to_string(),
+            message: "forced".to_string(),
+            details: None,
+        },
+        None,
+    );
+    response.weights = Some(EchoedWeights::new(&request.public_inputs));
+
+    let encoded: Value = serde_json::from_slice(&response.encode(2).unwrap()).unwrap();
+    assert_eq!(
+        encoded["weights"],
+        json!({
+            "fixed": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000 },
+            "decimal": { "w1": 0.15, "w2": 0.2, "w3": 0.25, "w4": 0.4 },
+        })
+    );
+    assert!(encoded.get("verification_results").is_none());
+
+    let decoded = ProverResponse::decode(&response.encode(2).unwrap()).unwrap();
+    let weights = decoded.response().unwrap().weights.clone().unwrap();
+    assert_eq!(
+        weights.decimal,
+        Weights {
+            w1: 0.15,
+            w2: 0.2,
+            w3: 0.25,
+            w4: 0.4
+        }
+    );
+
+    let legacy: Value = serde_json::from_slice(&response.encode(1).unwrap()).unwrap();
+    assert!(legacy.get("weights").is_none());
+}
@@ -0,0 +1,156 @@
+// `decoded_public_values`: the committed public values of a proof, decoded into
+// readable fields when the proof is read, pinned here as they are published and as
+// the response schema describes them. A proof whose public values do not decode
+// fails the consistency check instead of going out without the block.
+
+use human_index_lib::{commit_weights, HumanIndexPublicInputs, PublicValues, Validity};
+use prover::decimal::decimal_string;
+use prover::prover::{
+    check_decoded_public_values, read_proof_artifacts, ArtifactEncoding, ProofJsonLayout,
+};
+use prover::schema::schemas;
+use prover::types::ProofData;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+fn public_values() -> PublicValues {
+    let inputs = HumanIndexPublicInputs {
+        w1: 1500,
+        w2: 2000,
+        w3: 2500,
+        w4: 4000,
+        expected_output: 173,
+    };
+    PublicValues {
+        weights_commitment: commit_weights(&inputs),
+        inputs,
+        computed_output: 173,
+        validity: Validity {
+            issued_at: 1_790_000_000,
+            valid_until: 1_790_086_400,
+        },
+    }
+}
+
+/// Write an `inputs.json` committing `public_values` for test `name`
+fn write_inputs(name: &str, public_values: &[u8]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "prover-decoded-public-values-{}-{}",
+        name,
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("inputs.json");
+    let inputs = json!({
+        "riscvVKey": format!("0x{}", hex::encode([0x42; 32])),
+        "publicValues": format!("0x{}", hex::encode(public_values)),
+        "proof": vec![format!("0x{}", hex::encode([1; 32])); 8],
+    });
+    std::fs::write(&path, serde_json::to_vec(&inputs).unwrap()).unwrap();
+    path
+}
+
+fn read(name: &str, public_values: &[u8]) -> ProofData {
+    let path = write_inputs(name, public_values);
+    let proof_data = read_proof_artifacts(
+        &path,
+        u64::MAX,
+        ProofJsonLayout::PicoV1,
+        ArtifactEncoding::Sequential,
+        173,
+        &[0; 32],
+    )
+    .unwrap();
+    std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    proof_data
+}
+
+#[test]
+fn read_proofs_carry_the_decoded_public_values() {
+    let public_values = public_values();
+    let proof_data = read("shape", &bincode::serialize(&public_values).unwrap());
+    check_decoded_public_values(&proof_data).unwrap();
+
+    let published = serde_json::to_value(&proof_data).unwrap();
+    assert_eq!(
+        published["decoded_public_values"],
+        json!({
+            "schema_version": 1,
+            "weights": {
+                "fixed": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000 },
+                "decimal": { "w1": "0.15", "w2": "0.2", "w3": "0.25", "w4": "0.4" },
+            },
+            "expected_output": 173,
+            "computed_output": 173,
+            "weights_commitment": format!("0x{}", hex::encode(public_values.weights_commitment)),
+            "issued_at": 1_790_000_000u64,
+            "valid_until": 1_790_086_400u64,
+        })
+    );
+}
+
+#[test]
+fn undecodable_public_values_fail_the_consistency_check() {
+    let proof_data = read("undecodable", &[0xab; 12]);
+    assert!(proof_data.decoded_public_values.is_none());
+    assert!(check_decoded_public_values(&proof_data).is_err());
+}
+
+#[test]
+fn block_must_match_the_public_inputs() {
+    let mut proof_data = read("mismatch", &bincode::serialize(&public_values()).unwrap());
+    let decoded = proof_data.decoded_public_values.as_mut().unwrap();
+    decoded.computed_output = 255;
+    let err = check_decoded_public_values(&proof_data).unwrap_err();
+    assert!(err.to_string().contains("differ"), "{}", err);
+
+    proof_data.decoded_public_values = None;
+    let err = check_decoded_public_values(&proof_data).unwrap_err();
+    assert!(err.to_string().contains("missing"), "{}", err);
+}
+
+#[test]
+fn decimal_strings_are_exact() {
+    for (fixed, decimal) in [
+        (0, "0"),
+        (1, "0.0001"),
+        (1500, "0.15"),
+        (3333, "0.3333"),
+        (10_000, "1"),
+        (12_050, "1.205"),
+    ] {
+        assert_eq!(decimal_string(fixed), decimal);
+    }
+}
+
+#[test]
+fn response_schema_describes_the_block() {
+    let (_, schema) = schemas()
+        .into_iter()
+        .find(|(file, _)| *file == "prover_response.schema.json")
+        .unwrap();
+    let schema: Value = schema.as_value().clone();
+    let block = &schema["$defs"]["DecodedPublicValues"];
+    let mut fields: Vec<&str> = block["properties"]
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(String::as_str)
+        .collect();
+    fields.sort_unstable();
+    assert_eq!(
+        fields,
+        [
+            "computed_output",
+            "expected_output",
+            "issued_at",
+            "schema_version",
+            "valid_until",
+            "weights",
+            "weights_commitment",
+        ]
+    );
+    assert!(schema["$defs"]["ProofData"]["properties"]
+        .get("decoded_public_values")
+        .is_some());
+}
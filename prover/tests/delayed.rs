@@ -0,0 +1,322 @@
+// Drives the delayed queue with explicit timestamps in place of the clock, checking
+// when requests with `not_before` are released, that they survive a restart, and
+// that a request expiring while delayed is released at its expiry. Also the result
+// journal: in progress, then completed, bounded to the latest results, and what a
+// restart or an admin command opening it next to the service leaves behind.
+
+use chrono::{DateTime, TimeDelta, Utc};
+use prover::delayed::{DelayedQueue, DelayedStats, JournaledResult, ResultLookup};
+use prover::types::{Priority, ProverRequest};
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+fn t0() -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339("2026-03-01T12:00:00Z")
+        .unwrap()
+        .to_utc()
+}
+
+fn request(submitted_at: DateTime<Utc>, not_before: DateTime<Utc>) -> ProverRequest {
+    serde_json::from_value(json!({
+        "request_id": "partner:1",
+        "verification_results": { "recaptcha_score": 9000, "sms_verified": 1, "bio_verified": 0 },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 },
+        "submitted_at": submitted_at.to_rfc3339(),
+        "not_before": not_before.to_rfc3339(),
+        "priority": "batch",
+    }))
+    .unwrap()
+}
+
+/// A fresh queue file for test `name`
+fn queue_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("prover-delayed-{}-{}", name, std::process::id()));
+    std::fs::remove_dir_all(&dir).ok();
+    dir.join("delayed.sqlite")
+}
+
+#[test]
+fn released_at_not_before() {
+    let path = queue_path("release");
+    let queue = DelayedQueue::open(&path).unwrap();
+    let request = request(t0(), t0() + TimeDelta::hours(2));
+    let data = serde_json::to_vec(&request).unwrap();
+
+    let release_at = request.release_at(None, t0()).expect("not due yet");
+    assert_eq!(release_at, t0() + TimeDelta::hours(2));
+    let id = queue.insert(release_at, Priority::Batch, &data).unwrap();
+
+    assert!(queue
+        .take_due(release_at - TimeDelta::milliseconds(1))
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        queue.stats().unwrap(),
+        DelayedStats {
+            waiting: 1,
+            next_release_at: Some(release_at.to_rfc3339()),
+        }
+    );
+
+    let due = queue.take_due(release_at).unwrap();
+    assert_eq!(due.len(), 1);
+    assert_eq!((due[0].id, due[0].priority), (id, Priority::Batch));
+    assert_eq!(due[0].data, data);
+
+    // Released once; waiting no more
+    assert!(queue
+        .take_due(release_at + TimeDelta::hours(1))
+        .unwrap()
+        .is_empty());
+    assert_eq!(queue.stats().unwrap(), DelayedStats::default());
+
+    queue.complete(id).unwrap();
+    std::fs::remove_dir_all(path.parent().unwrap()).ok();
+}
+
+#[test]
+fn unfinished_requests_survive_a_restart() {
+    let path = queue_path("restart");
+    let release_at = t0() + TimeDelta::minutes(30);
+    {
+        let queue = DelayedQueue::open(&path).unwrap();
+        queue
+            .insert(release_at, Priority::Interactive, b"waiting")
+            .unwrap();
+        let released = queue.insert(t0(), Priority::Normal, b"released").unwrap();
+        assert_eq!(queue.take_due(t0()).unwrap()[0].id, released);
+    }
+
+    // Both the waiting request and the one released but never completed come back
+    let queue = DelayedQueue::open(&path).unwrap();
+    assert_eq!(queue.stats().unwrap().waiting, 2);
+    let due: Vec<_> = queue
+        .take_due(release_at)
+        .unwrap()
+        .into_iter()
+        .map(|item| (item.data, item.priority))
+        .collect();
+    assert_eq!(
+        due,
+        [
+            (b"released".to_vec(), Priority::Normal),
+            (b"waiting".to_vec(), Priority::Interactive),
+        ]
+    );
+    std::fs::remove_dir_all(path.parent().unwrap()).ok();
+}
+
+#[test]
+fn request_expiring_while_delayed_is_released_at_expiry() {
+    let path = queue_path("expiry");
+    let queue = DelayedQueue::open(&path).unwrap();
+    let max_age_secs = 600;
+    let request = request(t0(), t0() + TimeDelta::hours(6));
+
+    // Released when it expires rather than at not_before
+    let release_at = request.release_at(Some(max_age_secs), t0()).unwrap();
+    assert_eq!(
+        release_at,
+        t0() + TimeDelta::seconds(max_age_secs as i64 + 1)
+    );
+    queue
+        .insert(
+            release_at,
+            Priority::Batch,
+            &serde_json::to_vec(&request).unwrap(),
+        )
+        .unwrap();
+
+    assert!(queue
+        .take_due(release_at - TimeDelta::seconds(1))
+        .unwrap()
+        .is_empty());
+    let due = queue.take_due(release_at).unwrap();
+    assert_eq!(due.len(), 1);
+
+    // At release it is past MAX_REQUEST_AGE_SECS, so the service fails it as Expired
+    let released: ProverRequest = serde_json::from_slice(&due[0].data).unwrap();
+    let age = released.age_at(release_at).unwrap().unwrap();
+    assert!(age.num_seconds() > max_age_secs as i64);
+    // One second earlier it still counted as fresh
+    let age = released
+        .age_at(release_at - TimeDelta::seconds(1))
+        .unwrap()
+        .unwrap();
+    assert!(age.num_seconds() <= max_age_secs as i64);
+    std::fs::remove_dir_all(path.parent().unwrap()).ok();
+}
+
+#[test]
+fn due_or_expired_requests_are_not_delayed() {
+    let due = request(t0(), t0());
+    assert_eq!(due.release_at(None, t0()), None);
+
+    // Already past its expiry: processed now and failed as Expired
+    let stale = request(t0() - TimeDelta::hours(1), t0() + TimeDelta::hours(1));
+    assert_eq!(stale.release_at(Some(60), t0()), None);
+
+    let mut invalid = request(t0(), t0());
+    invalid.not_before = Some("tomorrow".to_string());
+    assert_eq!(invalid.release_at(None, t0()), None);
+}
+
+#[test]
+fn postponed_request_is_released_again() {
+    let path = queue_path("postpone");
+    let queue = DelayedQueue::open(&path).unwrap();
+    let id = queue.insert(t0(), Priority::Normal, b"forward me").unwrap();
+    assert_eq!(queue.take_due(t0()).unwrap().len(), 1);
+
+    // A frontend that failed to forward it retries later
+    let retry_at = t0() + TimeDelta::seconds(5);
+    queue.postpone(id, retry_at).unwrap();
+    assert_eq!(queue.next_release_at().unwrap(), Some(retry_at));
+    assert!(queue.take_due(t0()).unwrap().is_empty());
+
+    let due = queue.take_due(retry_at).unwrap();
+    assert_eq!(due.len(), 1);
+    assert_eq!(due[0].id, id);
+    assert_eq!(due[0].data, b"forward me");
+
+    queue.complete(id).unwrap();
+    assert_eq!(queue.stats().unwrap(), DelayedStats::default());
+    std::fs::remove_dir_all(path.parent().unwrap()).ok();
+}
+
+#[test]
+fn released_request_keeps_its_endpoint() {
+    let path = queue_path("endpoint");
+    let queue = DelayedQueue::open(&path).unwrap();
+    queue
+        .insert_from(
+            t0(),
+            Priority::Normal,
+            b"secondary",
+            Some("zk-dr/requests-sub"),
+        )
+        .unwrap();
+    queue.insert(t0(), Priority::Normal, b"primary").unwrap();
+
+    let due: Vec<_> = queue
+        .take_due(t0())
+        .unwrap()
+        .into_iter()
+        .map(|item| (item.data, item.endpoint))
+        .collect();
+    assert_eq!(
+        due,
+        [
+            (
+                b"secondary".to_vec(),
+                Some("zk-dr/requests-sub".to_string())
+            ),
+            (b"primary".to_vec(), None),
+        ]
+    );
+    std::fs::remove_dir_all(path.parent().unwrap()).ok();
+}
+
+fn journaled(request_id: &str) -> JournaledResult {
+    JournaledResult {
+        request_id: request_id.to_string(),
+        data: format!(r#"{{"request_id":"{}"}}"#, request_id).into_bytes(),
+        attributes: HashMap::from([("schema_version".to_string(), "2".to_string())]),
+        ordering_key: "binding-1".to_string(),
+        topic_path: "projects/zk/topics/results".to_string(),
+        completed_at: String::new(),
+    }
+}
+
+#[test]
+fn results_are_journaled_in_progress_then_completed() {
+    let path = queue_path("results");
+    let queue = DelayedQueue::open(&path).unwrap();
+    assert_eq!(queue.result("req-1").unwrap(), ResultLookup::Unknown);
+
+    queue.begin_result("req-1").unwrap();
+    assert_eq!(queue.result("req-1").unwrap(), ResultLookup::InProgress);
+
+    queue.record_result(&journaled("req-1"), 10).unwrap();
+    let ResultLookup::Completed(result) = queue.result("req-1").unwrap() else {
+        panic!("result not completed");
+    };
+    assert!(!result.completed_at.is_empty());
+    assert_eq!(
+        result,
+        JournaledResult {
+            completed_at: result.completed_at.clone(),
+            ..journaled("req-1")
+        }
+    );
+
+    // A request that produced no result is dropped; a completed one is kept
+    queue.begin_result("req-2").unwrap();
+    queue.abandon_result("req-2").unwrap();
+    queue.abandon_result("req-1").unwrap();
+    assert_eq!(queue.result("req-2").unwrap(), ResultLookup::Unknown);
+    assert!(matches!(
+        queue.result("req-1").unwrap(),
+        ResultLookup::Completed(_)
+    ));
+    std::fs::remove_dir_all(path.parent().unwrap()).ok();
+}
+
+#[test]
+fn only_the_latest_results_are_kept() {
+    let path = queue_path("results-kept");
+    let queue = DelayedQueue::open(&path).unwrap();
+    queue.begin_result("in-progress").unwrap();
+    for i in 0..5 {
+        queue
+            .record_result(&journaled(&format!("req-{}", i)), 3)
+            .unwrap();
+    }
+    // Proving a request again makes it the latest
+    queue.record_result(&journaled("req-2"), 3).unwrap();
+
+    let kept: Vec<_> = (0..5)
+        .filter(|i| {
+            matches!(
+                queue.result(&format!("req-{}", i)).unwrap(),
+                ResultLookup::Completed(_)
+            )
+        })
+        .collect();
+    assert_eq!(kept, [2, 3, 4]);
+    // Requests in progress do not count against the limit
+    assert_eq!(
+        queue.result("in-progress").unwrap(),
+        ResultLookup::InProgress
+    );
+    std::fs::remove_dir_all(path.parent().unwrap()).ok();
+}
+
+#[test]
+fn results_in_progress_are_dropped_on_restart() {
+    let path = queue_path("results-restart");
+    let queue = DelayedQueue::open(&path).unwrap();
+    queue.begin_result("req-1").unwrap();
+    queue.begin_result("req-2").unwrap();
+    queue.record_result(&journaled("req-2"), 10).unwrap();
+    let id = queue.insert(t0(), Priority::Normal, b"released").unwrap();
+    assert_eq!(queue.take_due(t0()).unwrap()[0].id, id);
+
+    // An admin command opening the journal leaves the service's state alone
+    let shared = DelayedQueue::open_shared(&path).unwrap();
+    assert_eq!(shared.result("req-1").unwrap(), ResultLookup::InProgress);
+    assert!(shared.take_due(t0()).unwrap().is_empty());
+    drop(shared);
+
+    // A restarted service proves nothing it was proving before
+    drop(queue);
+    let queue = DelayedQueue::open(&path).unwrap();
+    assert_eq!(queue.result("req-1").unwrap(), ResultLookup::Unknown);
+    assert!(matches!(
+        queue.result("req-2").unwrap(),
+        ResultLookup::Completed(_)
+    ));
+    assert_eq!(queue.take_due(t0()).unwrap()[0].id, id);
+    std::fs::remove_dir_all(path.parent().unwrap()).ok();
+}
@@ -0,0 +1,203 @@
+// The Docker memory preflight against canned `GET /info` responses: enough memory
+// passes, too little or an unreadable answer fails in `fail` mode and is reported
+// as not ready in `warn` mode, and `off` never asks. `DockerDaemon` is also driven
+// against a fake daemon on a TCP port.
+
+use prover::docker::{preflight, DockerApi, DockerDaemon, DockerMemoryCheck, DockerPreflight};
+use prover::error::ServiceError;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+/// Answers every `GET /info` with the same response, counting the calls
+struct CannedDocker {
+    response: Result<Vec<u8>, String>,
+    calls: AtomicUsize,
+}
+
+impl CannedDocker {
+    fn responding(response: &str) -> Self {
+        Self {
+            response: Ok(response.as_bytes().to_vec()),
+            calls: AtomicUsize::new(0),
+        }
+    }
+
+    fn with_memory(bytes: u64) -> Self {
+        Self::responding(&info_response(&format!(
+            r#"{{"ID":"test","NCPU":8,"MemTotal":{}}}"#,
+            bytes
+        )))
+    }
+
+    fn unreachable() -> Self {
+        Self {
+            response: Err("Cannot connect to Docker at unix:///var/run/docker.sock".to_string()),
+            calls: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl DockerApi for CannedDocker {
+    fn info<'a>(
+        &'a self,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<u8>, ServiceError>> + Send + 'a>> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        let response = self.response.clone().map_err(ServiceError::Docker);
+        Box::pin(async move { response })
+    }
+}
+
+fn info_response(body: &str) -> String {
+    format!(
+        "HTTP/1.0 200 OK\r\nContent-Type: application/json\r\n\r\n{}",
+        body
+    )
+}
+
+async fn warned(docker: &CannedDocker) -> DockerMemoryCheck {
+    preflight(docker, DockerPreflight::Warn, 8)
+        .await
+        .expect("warn mode never fails")
+        .expect("warn mode reports the check")
+}
+
+async fn failed(docker: &CannedDocker) -> String {
+    let err = preflight(docker, DockerPreflight::Fail, 8)
+        .await
+        .expect_err("fail mode fails");
+    assert_eq!(err.error_type(), "DockerError");
+    err.to_string()
+}
+
+#[tokio::test]
+async fn enough_memory_passes() {
+    let docker = CannedDocker::with_memory(16 * GIB);
+    for mode in [DockerPreflight::Warn, DockerPreflight::Fail] {
+        let check = preflight(&docker, mode, 8).await.unwrap().unwrap();
+        assert!(check.ok);
+        assert_eq!(check.memory_bytes, Some(16 * GIB));
+        assert_eq!(check.min_memory_bytes, 8 * GIB);
+        assert_eq!(check.error, None);
+    }
+
+    // The minimum itself is enough
+    let docker = CannedDocker::with_memory(8 * GIB);
+    assert!(warned(&docker).await.ok);
+}
+
+#[tokio::test]
+async fn memory_below_the_minimum_is_reported() {
+    let docker = CannedDocker::with_memory(2 * GIB);
+    let expected = "Docker has 2.0 GiB of memory but proving needs at least 8.0 GiB; \
+                    raise the Docker memory limit or lower DOCKER_MIN_MEMORY_GB";
+    assert_eq!(
+        failed(&docker).await,
+        format!("Docker preflight failed: {}", expected)
+    );
+
+    let check = warned(&docker).await;
+    assert!(!check.ok);
+    assert_eq!(check.memory_bytes, Some(2 * GIB));
+    assert_eq!(
+        check.error.as_deref(),
+        Some(format!("Docker preflight failed: {}", expected).as_str())
+    );
+
+    // One byte short is still short
+    let docker = CannedDocker::with_memory(8 * GIB - 1);
+    assert!(!warned(&docker).await.ok);
+}
+
+#[tokio::test]
+async fn unreadable_info_responses_are_reported() {
+    let cases = [
+        (
+            "HTTP/1.0 500 Internal Server Error\r\n\r\n{}".to_string(),
+            "Docker /info returned 'HTTP/1.0 500 Internal Server Error'",
+        ),
+        (
+            info_response("{\"MemTotal\": "),
+            "Invalid Docker /info body",
+        ),
+        (
+            info_response(r#"{"ID":"test"}"#),
+            "Docker /info has no MemTotal",
+        ),
+        (
+            info_response(r#"{"MemTotal":"16G"}"#),
+            "Docker /info has no MemTotal",
+        ),
+        (
+            "HTTP/1.0 200 OK".to_string(),
+            "Malformed response from Docker",
+        ),
+    ];
+    for (response, expected) in cases {
+        let docker = CannedDocker::responding(&response);
+        let message = failed(&docker).await;
+        assert!(message.contains(expected), "{:?}: {}", response, message);
+
+        let check = warned(&docker).await;
+        assert!(!check.ok);
+        assert_eq!(check.memory_bytes, None);
+        assert!(check.error.unwrap().contains(expected));
+    }
+}
+
+#[tokio::test]
+async fn an_unreachable_daemon_is_reported() {
+    let docker = CannedDocker::unreachable();
+    assert!(failed(&docker).await.contains("Cannot connect to Docker"));
+
+    let check = warned(&docker).await;
+    assert!(!check.ok);
+    assert_eq!(check.memory_bytes, None);
+}
+
+#[tokio::test]
+async fn off_never_queries_docker() {
+    let docker = CannedDocker::unreachable();
+    assert!(preflight(&docker, DockerPreflight::Off, 8)
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(docker.calls.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn the_daemon_is_asked_for_its_info() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = vec![0; 1024];
+        let n = stream.read(&mut request).await.unwrap();
+        request.truncate(n);
+        stream
+            .write_all(info_response(&format!(r#"{{"MemTotal":{}}}"#, 4 * GIB)).as_bytes())
+            .await
+            .unwrap();
+        String::from_utf8(request).unwrap()
+    });
+
+    let docker = DockerDaemon::new(format!("tcp://{}", addr));
+    let check = preflight(&docker, DockerPreflight::Warn, 8)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(check.memory_bytes, Some(4 * GIB));
+    assert!(!check.ok);
+    assert!(server.await.unwrap().starts_with("GET /info HTTP/1.0\r\n"));
+
+    let docker = DockerDaemon::new("ssh://docker-host");
+    let check = preflight(&docker, DockerPreflight::Warn, 8)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(check.error.unwrap().contains("Unsupported DOCKER_HOST"));
+}
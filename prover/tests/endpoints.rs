@@ -0,0 +1,109 @@
+// The service receives from one Pub/Sub endpoint at a time and fails over to the
+// next after `PUBSUB_FAILOVER_ERRORS` receive errors in a row, wrapping around to
+// the primary. A message from the active endpoint resets the count.
+
+use prover::endpoints::{Endpoints, Failover, PubsubEndpoint};
+
+fn endpoint(project_id: &str) -> PubsubEndpoint {
+    format!("{}/prover-requests-sub/prover-results", project_id)
+        .parse()
+        .unwrap()
+}
+
+fn endpoints(count: usize) -> Endpoints {
+    Endpoints::new(
+        (0..count)
+            .map(|i| endpoint(&format!("project-{}", i)))
+            .collect(),
+        0,
+    )
+}
+
+#[test]
+fn endpoint_is_parsed_from_three_segments() {
+    let endpoint = endpoint("zk-secondary");
+    assert_eq!(
+        endpoint,
+        PubsubEndpoint {
+            project_id: "zk-secondary".to_string(),
+            subscription: "prover-requests-sub".to_string(),
+            result_topic: "prover-results".to_string(),
+        }
+    );
+    assert_eq!(
+        endpoint.subscription_path(),
+        "projects/zk-secondary/subscriptions/prover-requests-sub"
+    );
+    assert_eq!(
+        endpoint.topic_path(&endpoint.result_topic),
+        "projects/zk-secondary/topics/prover-results"
+    );
+    assert_eq!(endpoint.key(), "zk-secondary/prover-requests-sub");
+
+    for invalid in ["", "project/sub", "project//topic", "a/b/c/d"] {
+        assert!(invalid.parse::<PubsubEndpoint>().is_err(), "{}", invalid);
+    }
+}
+
+#[test]
+fn switches_after_consecutive_errors() {
+    let endpoints = endpoints(3);
+    assert_eq!(
+        endpoints.record_error(0, "unavailable", 3),
+        Failover::Retry { errors: 1 }
+    );
+    assert_eq!(
+        endpoints.record_error(0, "unavailable", 3),
+        Failover::Retry { errors: 2 }
+    );
+    // A delivered message means the endpoint recovered
+    endpoints.record_message(0);
+    assert_eq!(
+        endpoints.record_error(0, "unavailable", 3),
+        Failover::Retry { errors: 1 }
+    );
+    endpoints.record_error(0, "unavailable", 3);
+    assert_eq!(endpoints.record_error(0, "not found", 3), Failover::Switch);
+
+    assert_eq!(endpoints.candidates(0), [1, 2]);
+    endpoints.switch(0, 1);
+    let status = endpoints.status();
+    assert_eq!(status.active, 1);
+    assert_eq!(status.endpoint, endpoint("project-1"));
+    assert_eq!(status.consecutive_errors, 0);
+    assert_eq!(status.failovers, 1);
+    assert_eq!(status.last_error.as_deref(), Some("not found"));
+}
+
+#[test]
+fn failover_wraps_around_to_the_primary() {
+    let endpoints = endpoints(3);
+    assert_eq!(endpoints.candidates(2), [0, 1]);
+    endpoints.switch(0, 2);
+    endpoints.switch(2, 0);
+    assert_eq!(endpoints.active(), 0);
+    assert_eq!(endpoints.status().failovers, 2);
+
+    // A stale switch from an endpoint no longer active is ignored
+    endpoints.switch(2, 1);
+    assert_eq!(endpoints.active(), 0);
+}
+
+#[test]
+fn single_endpoint_never_switches() {
+    let endpoints = endpoints(1);
+    for errors in 1..=3 {
+        assert_eq!(
+            endpoints.record_error(0, "unavailable", 1),
+            Failover::Retry { errors }
+        );
+    }
+    assert!(endpoints.candidates(0).is_empty());
+}
+
+#[test]
+fn delayed_requests_resolve_their_endpoint_by_key() {
+    let endpoints = endpoints(2);
+    assert_eq!(endpoints.find(&endpoint("project-1").key()), Some(1));
+    assert_eq!(endpoints.find("elsewhere/prover-requests-sub"), None);
+}
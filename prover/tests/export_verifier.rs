@@ -0,0 +1,143 @@
+// `setup --export-verifier` against the Groth16 fixture key: the contract it writes
+// is the key's `to_solidity` rendering with every constant filled in, it differs
+// from the committed contracts/src/Groth16Verifier.sol only in the key constants,
+// and a missing key fails without writing anything.
+
+use prover::verifier::VerifyingKey;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+fn manifest_dir() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+}
+
+/// An OUTPUT_DIR holding the fixture key as vm_vk
+fn output_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("prover-export-{}-{}", name, std::process::id()));
+    std::fs::remove_dir_all(&dir).ok();
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::copy(
+        manifest_dir().join("testdata/groth16_vk.bin"),
+        dir.join("vm_vk"),
+    )
+    .unwrap();
+    dir
+}
+
+fn export(output_dir: &Path, path: Option<&Path>) -> Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_setup"));
+    command
+        .env("OUTPUT_DIR", output_dir)
+        .arg("--export-verifier");
+    if let Some(path) = path {
+        command.arg(path);
+    }
+    command.output().unwrap()
+}
+
+/// Names of the key constants, the `{{NAME}}` placeholders of the template
+fn key_constants() -> BTreeSet<String> {
+    let template =
+        std::fs::read_to_string(manifest_dir().join("templates/Groth16Verifier.sol.tmpl")).unwrap();
+    template
+        .split("{{")
+        .skip(1)
+        .map(|rest| rest.split("}}").next().unwrap().to_string())
+        .collect()
+}
+
+/// `contract` with the value of every key constant replaced by `KEY`
+fn without_key(contract: &str) -> String {
+    let names = key_constants();
+    contract
+        .lines()
+        .map(|line| {
+            let declared = line
+                .trim()
+                .strip_prefix("uint256 constant ")
+                .and_then(|rest| rest.split(" = ").next());
+            match declared {
+                Some(name) if names.contains(name) => {
+                    format!("{} = KEY;", line.split(" = ").next().unwrap())
+                }
+                _ => line.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn exported_contract_is_the_keys_rendering() {
+    let dir = output_dir("render");
+    let out = dir.join("contracts/Groth16Verifier.sol");
+    let output = export(&dir, Some(&out));
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let contract = std::fs::read_to_string(&out).unwrap();
+    let vk = VerifyingKey::from_file(&dir.join("vm_vk")).unwrap();
+    assert_eq!(contract, vk.to_solidity().unwrap());
+    assert!(contract.contains(
+        "function verifyProof(\n        uint256[8] calldata proof,\n        uint256[2] calldata input\n    ) public view"
+    ));
+
+    // Every key constant is a decimal field element
+    assert!(!contract.contains("{{"));
+    for name in key_constants() {
+        let declaration = format!("uint256 constant {} = ", name);
+        let value = contract
+            .split(&declaration)
+            .nth(1)
+            .unwrap_or_else(|| panic!("{} is not declared", name))
+            .split(';')
+            .next()
+            .unwrap();
+        assert!(
+            !value.is_empty() && value.chars().all(|c| c.is_ascii_digit()),
+            "{} = {}",
+            name,
+            value
+        );
+    }
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn exported_contract_differs_from_the_committed_one_only_in_the_key() {
+    let dir = output_dir("committed");
+    let out = dir.join("Groth16Verifier.sol");
+    assert!(export(&dir, Some(&out)).status.success());
+
+    let exported = std::fs::read_to_string(&out).unwrap();
+    let committed =
+        std::fs::read_to_string(manifest_dir().join("../contracts/src/Groth16Verifier.sol"))
+            .unwrap();
+    assert_eq!(without_key(&exported), without_key(&committed));
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn contract_is_written_to_the_output_dir_by_default() {
+    let dir = output_dir("default");
+    assert!(export(&dir, None).status.success());
+    assert!(dir.join("Groth16Verifier.sol").exists());
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn missing_key_fails_without_writing() {
+    let dir = output_dir("missing");
+    std::fs::remove_file(dir.join("vm_vk")).unwrap();
+    let out = dir.join("Groth16Verifier.sol");
+
+    let output = export(&dir, Some(&out));
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Failed to export verifier"));
+    assert!(!out.exists());
+    std::fs::remove_dir_all(&dir).ok();
+}
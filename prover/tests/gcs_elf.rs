@@ -0,0 +1,96 @@
+// The ELF downloaded from a `gs://` URL, here from an in-memory object store:
+// retried like a local load, and checked against the expected SHA-256 if given.
+
+use prover::error::ServiceError;
+use prover::object_store::{GcsObject, MemoryObjects};
+use prover::prover::{load_and_cache_elf, load_gcs_elf, StartupRetry};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+const ELF: &[u8] = b"\x7fELF not a real guest";
+const URL: &str = "gs://prover-artifacts/elf/riscv32im-pico-zkvm-elf";
+
+fn retry(attempts: u32) -> StartupRetry {
+    StartupRetry {
+        attempts,
+        backoff: Duration::from_millis(10),
+    }
+}
+
+fn store() -> MemoryObjects {
+    let store = MemoryObjects::default();
+    store.insert(URL, ELF);
+    store
+}
+
+#[tokio::test]
+async fn loads_the_elf_from_a_gs_url() {
+    let digest = hex::encode(Sha256::digest(ELF));
+    let cached_elf = load_gcs_elf(&store(), URL, Some(&digest), retry(1))
+        .await
+        .expect("ELF downloads");
+    assert_eq!(cached_elf.data, ELF);
+    assert_eq!(cached_elf.sha256(), digest);
+
+    // Unverified when no digest is expected
+    let cached_elf = load_gcs_elf(&store(), URL, None, retry(1)).await.unwrap();
+    assert_eq!(cached_elf.data, ELF);
+}
+
+#[tokio::test]
+async fn another_sha256_is_rejected() {
+    let expected = hex::encode(Sha256::digest(b"another guest"));
+    let error = load_gcs_elf(&store(), URL, Some(&expected), retry(1))
+        .await
+        .err()
+        .expect("digest differs");
+    assert!(matches!(error, ServiceError::ProofGeneration(_)));
+    assert!(error.to_string().contains("SHA-256 mismatch"), "{}", error);
+}
+
+#[tokio::test]
+async fn missing_object_fails_after_the_retries() {
+    let url = "gs://prover-artifacts/elf/missing";
+    let error = load_gcs_elf(&store(), url, None, retry(2))
+        .await
+        .err()
+        .expect("no ELF to download");
+    assert!(matches!(error, ServiceError::ProofGeneration(_)));
+    assert!(error.to_string().contains(url), "{}", error);
+}
+
+#[tokio::test]
+async fn malformed_gs_urls_are_rejected() {
+    for url in ["gs://", "gs://bucket", "gs://bucket/", "gs:///object"] {
+        assert!(
+            matches!(GcsObject::parse(url), Some(Err(ServiceError::Config(_)))),
+            "{}",
+            url
+        );
+        // Never reaches Cloud Storage
+        assert!(matches!(
+            load_and_cache_elf(url, None, retry(1)).await,
+            Err(ServiceError::Config(_))
+        ));
+    }
+    assert!(GcsObject::parse("../app/elf/guest").is_none());
+    assert_eq!(GcsObject::parse(URL).unwrap().unwrap().to_string(), URL);
+}
+
+#[tokio::test]
+async fn local_elf_is_verified_too() {
+    let path = std::env::temp_dir().join(format!("gcs-elf-local-{}", std::process::id()));
+    std::fs::write(&path, ELF).unwrap();
+    let path_str = path.to_str().unwrap();
+
+    let digest = hex::encode(Sha256::digest(ELF)).to_uppercase();
+    assert!(load_and_cache_elf(path_str, Some(&digest), retry(1))
+        .await
+        .is_ok());
+    let other = hex::encode(Sha256::digest(b"another guest"));
+    assert!(load_and_cache_elf(path_str, Some(&other), retry(1))
+        .await
+        .is_err());
+
+    std::fs::remove_file(path).ok();
+}
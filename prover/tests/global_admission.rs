@@ -0,0 +1,145 @@
+// Two simulated workers, each with its own local permit pool, share an in-memory
+// lease backend; the cluster-wide cap holds however the local pools are sized.
+
+use prover::lease::{GlobalAdmission, LeaseBackend, MemoryLeases};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+const POLL: Duration = Duration::from_millis(5);
+
+fn admission(
+    backend: &Arc<MemoryLeases>,
+    limit: u32,
+    ttl: Duration,
+    worker: &str,
+) -> Arc<GlobalAdmission> {
+    Arc::new(GlobalAdmission::new(backend.clone(), limit, ttl, worker).with_poll_interval(POLL))
+}
+
+/// Proofs in flight across the cluster, and the most seen at once
+#[derive(Default)]
+struct InFlight {
+    current: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl InFlight {
+    fn start(&self) {
+        let current = self.current.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak.fetch_max(current, Ordering::SeqCst);
+    }
+
+    fn finish(&self) {
+        self.current.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Run `proofs` proofs on a worker allowing `max_concurrent_proofs` locally
+fn spawn_worker(
+    global: Arc<GlobalAdmission>,
+    max_concurrent_proofs: usize,
+    proofs: usize,
+    in_flight: Arc<InFlight>,
+) -> Vec<tokio::task::JoinHandle<()>> {
+    let local = Arc::new(Semaphore::new(max_concurrent_proofs));
+    (0..proofs)
+        .map(|_| {
+            let (global, local, in_flight) = (global.clone(), local.clone(), in_flight.clone());
+            tokio::spawn(async move {
+                let permit = local.acquire_owned().await.unwrap();
+                let lease = global.acquire().await;
+                in_flight.start();
+                tokio::time::sleep(Duration::from_millis(30)).await;
+                in_flight.finish();
+                lease.release().await;
+                drop(permit);
+            })
+        })
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn global_cap_holds_across_two_instances() {
+    let backend = Arc::new(MemoryLeases::default());
+    let in_flight = Arc::new(InFlight::default());
+    let ttl = Duration::from_secs(10);
+
+    // Locally each worker would run 3 at once; the cluster allows 2
+    let mut tasks = spawn_worker(
+        admission(&backend, 2, ttl, "pod-a"),
+        3,
+        6,
+        in_flight.clone(),
+    );
+    tasks.extend(spawn_worker(
+        admission(&backend, 2, ttl, "pod-b"),
+        3,
+        6,
+        in_flight.clone(),
+    ));
+    for task in tasks {
+        tokio::time::timeout(Duration::from_secs(10), task)
+            .await
+            .expect("every proof is admitted")
+            .unwrap();
+    }
+
+    assert_eq!(in_flight.peak.load(Ordering::SeqCst), 2);
+    assert_eq!(in_flight.current.load(Ordering::SeqCst), 0);
+    assert_eq!(backend.held(), 0);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn waiting_worker_is_admitted_on_release() {
+    let backend = Arc::new(MemoryLeases::default());
+    let ttl = Duration::from_secs(10);
+    let pod_a = admission(&backend, 1, ttl, "pod-a");
+    let pod_b = admission(&backend, 1, ttl, "pod-b");
+
+    let held = pod_a.acquire().await;
+    let waiting = tokio::spawn(async move { pod_b.acquire().await });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!waiting.is_finished());
+
+    held.release().await;
+    let lease = tokio::time::timeout(Duration::from_secs(1), waiting)
+        .await
+        .expect("admitted after release")
+        .unwrap();
+    assert!(lease.holder().starts_with("pod-b:"));
+    assert_eq!(backend.held(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn renewal_keeps_a_long_proof_leased() {
+    let backend = Arc::new(MemoryLeases::default());
+    let ttl = Duration::from_millis(150);
+    let pod_a = admission(&backend, 1, ttl, "pod-a");
+
+    let lease = pod_a.acquire().await;
+    tokio::time::sleep(ttl * 4).await;
+    assert!(!backend
+        .try_acquire("pod-b:0", 1, ttl)
+        .expect("memory backend never fails"));
+    lease.release().await;
+    assert_eq!(backend.held(), 0);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn lease_of_a_crashed_worker_expires() {
+    let backend = Arc::new(MemoryLeases::default());
+    let ttl = Duration::from_millis(100);
+    let pod_a = admission(&backend, 1, ttl, "pod-a");
+    let pod_b = admission(&backend, 1, ttl, "pod-b");
+
+    // Dropped without release: renewal stops, as if the worker died
+    drop(pod_a.acquire().await);
+    assert_eq!(backend.held(), 1);
+
+    let lease = tokio::time::timeout(Duration::from_secs(1), pod_b.acquire())
+        .await
+        .expect("admitted once the abandoned lease expires");
+    lease.release().await;
+}
@@ -0,0 +1,302 @@
+// Cross-checks `calculate_human_index` as the host calls it against a replay of the
+// guest's main, over edge values up to u32::MAX, and both against exact arithmetic.
+// The replay also checks that the guest refuses validity windows it cannot prove,
+// and outputs above MAX_HUMAN_INDEX should the formula ever produce them.
+//
+// The guest is built in release mode, where integer overflow wraps; tests run with
+// overflow checks, where it panics. A run that neither panics nor disagrees with
+// the exact result shows there is no overflow for the guest to wrap.
+
+use human_index_lib::{
+    calculate_human_index, commit_weights, HumanIndexPublicInputs, ProofInputs, PublicValues,
+    Validity, VerificationResults, MAX_HUMAN_INDEX, MAX_VALIDITY_SECS, SCALE,
+};
+use prover::fixtures::{golden_vectors, public_values, Fixtures, FIXTURE_VALIDITY};
+use std::collections::VecDeque;
+
+const EDGES: [u32; 9] = [
+    0,
+    1,
+    SCALE - 1,
+    SCALE,
+    SCALE + 1,
+    u32::MAX / MAX_HUMAN_INDEX,
+    u32::MAX / 2,
+    u32::MAX - 1,
+    u32::MAX,
+];
+
+type Formula = fn(&VerificationResults, &HumanIndexPublicInputs) -> u32;
+
+/// The index in exact arithmetic
+fn exact_index(results: &VerificationResults, inputs: &HumanIndexPublicInputs) -> u32 {
+    unclamped_index(results, inputs).min(MAX_HUMAN_INDEX)
+}
+
+/// The exact index without the clamp to `MAX_HUMAN_INDEX`, saturating at u32::MAX
+fn unclamped_index(results: &VerificationResults, inputs: &HumanIndexPublicInputs) -> u32 {
+    if results.recaptcha_score == 0 {
+        return 0;
+    }
+    let sum = inputs.w1 as u128
+        + inputs.w2 as u128 * results.recaptcha_score as u128 / SCALE as u128
+        + inputs.w3 as u128 * results.sms_verified as u128
+        + inputs.w4 as u128 * results.bio_verified as u128;
+    (sum * MAX_HUMAN_INDEX as u128 / SCALE as u128).min(u32::MAX as u128) as u32
+}
+
+/// Run `app/src/main.rs` against the stdin the host writes, returning the committed values
+fn guest_main(host_inputs: &ProofInputs) -> PublicValues {
+    guest_main_with(host_inputs, calculate_human_index)
+}
+
+/// `guest_main` with `formula` in place of `calculate_human_index`
+fn guest_main_with(host_inputs: &ProofInputs, formula: Formula) -> PublicValues {
+    let mut stdin = VecDeque::new();
+    host_inputs.write_to(|value| stdin.push_back(*value));
+    let read_as = || {
+        stdin
+            .pop_front()
+            .expect("guest reads only what the host wrote")
+    };
+
+    let ProofInputs {
+        results: verification_results,
+        inputs: public_inputs,
+        validity,
+        ..
+    } = ProofInputs::read_from(read_as);
+    if let Err(e) = validity.check() {
+        panic!("Invalid validity window: {}", e);
+    }
+    let computed_output = formula(&verification_results, &public_inputs);
+    let public_values = PublicValues {
+        weights_commitment: commit_weights(&public_inputs),
+        inputs: public_inputs,
+        computed_output,
+        validity,
+    };
+    if let Err(e) = public_values.check() {
+        panic!("Invalid public values: {}", e);
+    }
+
+    // Committed values reach the host bincode-encoded
+    bincode::deserialize(&bincode::serialize(&public_values).unwrap()).unwrap()
+}
+
+fn guest_execution(results: &VerificationResults, inputs: &HumanIndexPublicInputs) -> PublicValues {
+    guest_main(&ProofInputs {
+        results: results.clone(),
+        inputs: inputs.clone(),
+        expected_output: inputs.expected_output,
+        validity: FIXTURE_VALIDITY,
+    })
+}
+
+fn with_validity(validity: Validity) -> ProofInputs {
+    let vector = &golden_vectors().expect("golden vectors load")[0];
+    ProofInputs::new(
+        vector.verification_results.clone(),
+        vector.public_inputs.clone(),
+        validity,
+    )
+}
+
+fn check(results: VerificationResults, inputs: HumanIndexPublicInputs) {
+    let host = calculate_human_index(&results, &inputs);
+    let guest = guest_execution(&results, &inputs);
+    let exact = exact_index(&results, &inputs);
+
+    assert_eq!(
+        host, exact,
+        "host differs from exact for {:?} {:?}",
+        results, inputs
+    );
+    assert_eq!(
+        guest.computed_output, host,
+        "guest differs from host for {:?} {:?}",
+        results, inputs
+    );
+    assert_eq!(
+        bincode::serialize(&guest.inputs).unwrap(),
+        bincode::serialize(&inputs).unwrap()
+    );
+}
+
+#[test]
+fn edge_values_agree() {
+    for &score in &EDGES {
+        for &verified in &[0, 1, 2, u32::MAX] {
+            for &w1 in &EDGES {
+                for &w2 in &EDGES {
+                    for &w34 in &EDGES {
+                        check(
+                            VerificationResults {
+                                recaptcha_score: score,
+                                sms_verified: verified,
+                                bio_verified: verified.min(1),
+                            },
+                            HumanIndexPublicInputs {
+                                w1,
+                                w2,
+                                w3: w34,
+                                w4: w34,
+                                expected_output: 0,
+                            },
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn random_values_agree() {
+    // xorshift32, so failures reproduce
+    let mut state = 0x9e37_79b9u32;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        // Mix full-range values with values near SCALE, where the index is not clamped
+        if state.is_multiple_of(2) {
+            state
+        } else {
+            state % (2 * SCALE)
+        }
+    };
+
+    for _ in 0..200_000 {
+        check(
+            VerificationResults {
+                recaptcha_score: next(),
+                sms_verified: next() % 3,
+                bio_verified: next() % 3,
+            },
+            HumanIndexPublicInputs {
+                w1: next(),
+                w2: next(),
+                w3: next(),
+                w4: next(),
+                expected_output: next(),
+            },
+        );
+    }
+}
+
+#[test]
+fn saturated_weights_clamp_to_max() {
+    let results = VerificationResults {
+        recaptcha_score: u32::MAX,
+        sms_verified: u32::MAX,
+        bio_verified: u32::MAX,
+    };
+    let inputs = HumanIndexPublicInputs {
+        w1: u32::MAX,
+        w2: u32::MAX,
+        w3: u32::MAX,
+        w4: u32::MAX,
+        expected_output: MAX_HUMAN_INDEX,
+    };
+    assert_eq!(calculate_human_index(&results, &inputs), MAX_HUMAN_INDEX);
+    assert_eq!(
+        guest_execution(&results, &inputs).computed_output,
+        MAX_HUMAN_INDEX
+    );
+}
+
+#[test]
+fn recorded_mock_proofs_match_guest_execution() {
+    let fixtures = Fixtures::workspace().expect("fixtures load");
+    for vector in golden_vectors().expect("golden vectors load") {
+        let proof = fixtures
+            .mock_proof(&vector.name)
+            .expect("mock proof fixture");
+        let recorded = public_values(&proof).expect("fixture public values decode");
+        let guest = guest_execution(&vector.verification_results, &vector.public_inputs);
+
+        assert_eq!(
+            recorded.computed_output, guest.computed_output,
+            "{}",
+            vector.name
+        );
+        assert_eq!(
+            recorded.weights_commitment, guest.weights_commitment,
+            "{}",
+            vector.name
+        );
+        assert_eq!(proof.human_index, guest.computed_output, "{}", vector.name);
+        assert_eq!(recorded.validity, guest.validity, "{}", vector.name);
+    }
+}
+
+#[test]
+fn validity_window_is_committed() {
+    let validity = Validity {
+        issued_at: 1_700_000_000,
+        valid_until: 1_700_003_600,
+    };
+    assert_eq!(guest_main(&with_validity(validity)).validity, validity);
+}
+
+#[test]
+#[should_panic(expected = "is not after issued_at")]
+fn inverted_window_is_rejected_in_circuit() {
+    guest_main(&with_validity(Validity {
+        issued_at: 1_700_003_600,
+        valid_until: 1_700_000_000,
+    }));
+}
+
+#[test]
+#[should_panic(expected = "is not after issued_at")]
+fn empty_window_is_rejected_in_circuit() {
+    guest_main(&with_validity(Validity {
+        issued_at: 1_700_000_000,
+        valid_until: 1_700_000_000,
+    }));
+}
+
+#[test]
+#[should_panic(expected = "longer than the maximum")]
+fn unclamped_window_is_rejected_in_circuit() {
+    guest_main(&with_validity(Validity {
+        issued_at: 1_700_000_000,
+        valid_until: 1_700_000_001 + MAX_VALIDITY_SECS,
+    }));
+}
+
+/// Fully verified, with every weight at 1.0: a weighted sum of 4.0
+fn overweighted() -> ProofInputs {
+    let results = VerificationResults {
+        recaptcha_score: SCALE,
+        sms_verified: 1,
+        bio_verified: 1,
+    };
+    let inputs = HumanIndexPublicInputs {
+        w1: SCALE,
+        w2: SCALE,
+        w3: SCALE,
+        w4: SCALE,
+        expected_output: 0,
+    };
+    ProofInputs::new(results, inputs, FIXTURE_VALIDITY)
+}
+
+#[test]
+fn overweighted_inputs_are_clamped_in_circuit() {
+    let inputs = overweighted();
+    assert_eq!(
+        unclamped_index(&inputs.results, &inputs.inputs),
+        4 * MAX_HUMAN_INDEX
+    );
+    assert_eq!(guest_main(&inputs).computed_output, MAX_HUMAN_INDEX);
+}
+
+#[test]
+#[should_panic(expected = "computed output 1020 is above the maximum human index 255")]
+fn unclamped_output_is_rejected_in_circuit() {
+    // As if a formula change dropped the clamp
+    guest_main_with(&overweighted(), unclamped_index);
+}
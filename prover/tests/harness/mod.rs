@@ -0,0 +1,674 @@
+// End-to-end harness: runs `ProverService` with the mock backend against the Pub/Sub
+// emulator, on fresh topics and subscriptions per test. With `Settings::split` it
+// runs a frontend and a worker joined by a work topic instead, and with
+// `Settings::failover` a second endpoint for the service to fail over to. With
+// `Settings::alternate_results` a second result topic is allowed for requests to
+// name as their `result_topic`. With `Settings::result_ordering` results are
+// published with ordering keys, to a subscription with message ordering enabled.
+//
+// The emulator at `PUBSUB_EMULATOR_HOST` is used when set; otherwise the harness
+// starts (or reuses) the `prover-test-pubsub` Docker container on port 8086, which
+// is left running for the next run.
+
+use google_cloud_googleapis::pubsub::v1::PubsubMessage;
+use google_cloud_pubsub::client::Client;
+use google_cloud_pubsub::publisher::Publisher;
+use google_cloud_pubsub::subscription::{Subscription, SubscriptionConfig};
+use prover::config::{Config, Role};
+use prover::endpoints::PubsubEndpoint;
+use prover::error::ServiceError;
+use prover::mock::MockProofGenerator;
+use prover::provenance::Provenance;
+use prover::prover::{ProofBackend, ProofContext, WarmUp};
+use prover::service::{pubsub_client, ProverService};
+use prover::session::SessionReport;
+use prover::types::{
+    ProofData, ProverRequest, ProverResponse, RequestIdFormat, SignalAliases, SignalPolicy,
+};
+use serde_json::json;
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+const PROJECT_ID: &str = "test-project";
+const EMULATOR_CONTAINER: &str = "prover-test-pubsub";
+const EMULATOR_IMAGE: &str = "gcr.io/google.com/cloudsdktool/google-cloud-cli:emulators";
+const EMULATOR_HOST: &str = "localhost:8086";
+
+/// Shortest ack deadline the emulator accepts; unACKed requests come back after it
+pub const ACK_DEADLINE: Duration = Duration::from_secs(10);
+
+/// A service running against its own request topic and result topic
+pub struct Harness {
+    client: Client,
+    requests: Publisher,
+    request_subscription: Subscription,
+    results: Subscription,
+    /// The combined service, or the frontend then the worker
+    services: Vec<Arc<ProverService<TrackedBackend>>>,
+    stats: Arc<BackendStats>,
+    cancel: CancellationToken,
+    runs: Vec<JoinHandle<Result<(), ServiceError>>>,
+    topics: Vec<String>,
+    /// Subscriptions besides the request and result ones
+    subscriptions: Vec<Subscription>,
+    /// Secondary endpoint, with `Settings::failover`
+    failover: Option<FailoverEndpoint>,
+    /// Allowed result topic and its subscription, with `Settings::alternate_results`
+    alternate: Option<(String, Subscription)>,
+    dir: PathBuf,
+}
+
+/// Request and result topics of the secondary endpoint
+struct FailoverEndpoint {
+    requests: Publisher,
+    request_subscription: Subscription,
+    results: Subscription,
+}
+
+/// Service settings a scenario may change before the service starts
+pub struct Settings {
+    pub max_concurrent_proofs: usize,
+    pub mock_proof_delay: Duration,
+    pub max_request_age_secs: Option<u64>,
+    pub emit_provisional_index: bool,
+    pub require_named_profile: bool,
+    pub min_provable_index: Option<u32>,
+    /// Scenarios use readable request IDs, so this defaults to `Any`
+    pub request_id_format: RequestIdFormat,
+    pub signal_policy: SignalPolicy,
+    pub signal_aliases: SignalAliases,
+    /// Run `ROLE=frontend` and `ROLE=worker` services instead of one `ROLE=all`
+    pub split: bool,
+    /// Add a secondary endpoint, failed over to on the first receive error
+    pub failover: bool,
+    /// Add a result topic to `RESULT_TOPIC_ALLOWLIST`
+    pub alternate_results: bool,
+    /// Set `RESULT_ORDERING` and read results in ordering-key order
+    pub result_ordering: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            max_concurrent_proofs: 2,
+            mock_proof_delay: Duration::from_millis(50),
+            max_request_age_secs: None,
+            emit_provisional_index: false,
+            require_named_profile: false,
+            min_provable_index: None,
+            request_id_format: RequestIdFormat::Any,
+            signal_policy: SignalPolicy::default(),
+            signal_aliases: SignalAliases::default(),
+            split: false,
+            failover: false,
+            alternate_results: false,
+            result_ordering: false,
+        }
+    }
+}
+
+impl Harness {
+    /// Create topics and subscriptions, then start the service with `settings`
+    pub async fn start(settings: Settings) -> Self {
+        let base = base_config();
+        let name = unique_name();
+        let dir = std::env::temp_dir().join(&name);
+
+        let client = pubsub_client().await.expect("Pub/Sub client");
+        let request_topic = topic_path(&format!("{}-requests", name));
+        let result_topic = topic_path(&format!("{}-results", name));
+        let request_subscription =
+            create_subscription(&client, &request_topic, &format!("{}-requests-sub", name)).await;
+        let results = create_subscription_with(
+            &client,
+            &result_topic,
+            &format!("{}-results-sub", name),
+            settings.result_ordering,
+        )
+        .await;
+        let requests = client.topic(&request_topic).new_publisher(None);
+
+        let mut config = base;
+        config.prover_subscription = format!("{}-requests-sub", name);
+        config.result_topic = format!("{}-results", name);
+        config.max_concurrent_proofs = settings.max_concurrent_proofs;
+        config.mock_proof_delay_ms = settings.mock_proof_delay.as_millis() as u64;
+        config.max_request_age_secs = settings.max_request_age_secs;
+        config.emit_provisional_index = settings.emit_provisional_index;
+        config.require_named_profile = settings.require_named_profile;
+        config.min_provable_index = settings.min_provable_index;
+        config.request_id_format = settings.request_id_format;
+        config.signal_policy = settings.signal_policy;
+        config.signal_aliases = settings.signal_aliases;
+        config.result_ordering = settings.result_ordering;
+
+        let mut topics = vec![request_topic, result_topic];
+        let mut alternate = None;
+        if settings.alternate_results {
+            let id = format!("{}-alternate-results", name);
+            let topic = topic_path(&id);
+            let subscription =
+                create_subscription(&client, &topic, &format!("{}-alternate-results-sub", name))
+                    .await;
+            topics.push(topic);
+            config.result_topic_allowlist = vec![id.clone()];
+            alternate = Some((id, subscription));
+        }
+        let mut subscriptions = Vec::new();
+        let mut failover = None;
+        if settings.failover {
+            let request_topic = topic_path(&format!("{}-failover-requests", name));
+            let result_topic = topic_path(&format!("{}-failover-results", name));
+            failover = Some(FailoverEndpoint {
+                requests: client.topic(&request_topic).new_publisher(None),
+                request_subscription: create_subscription(
+                    &client,
+                    &request_topic,
+                    &format!("{}-failover-requests-sub", name),
+                )
+                .await,
+                results: create_subscription(
+                    &client,
+                    &result_topic,
+                    &format!("{}-failover-results-sub", name),
+                )
+                .await,
+            });
+            topics.extend([request_topic, result_topic]);
+
+            config.pubsub_endpoints = [
+                (&config.prover_subscription, &config.result_topic),
+                (
+                    &format!("{}-failover-requests-sub", name),
+                    &format!("{}-failover-results", name),
+                ),
+            ]
+            .into_iter()
+            .map(|(subscription, result_topic)| PubsubEndpoint {
+                project_id: PROJECT_ID.to_string(),
+                subscription: subscription.clone(),
+                result_topic: result_topic.clone(),
+            })
+            .collect();
+            config.pubsub_failover_errors = 1;
+        }
+        let configs = if settings.split {
+            let work_topic = topic_path(&format!("{}-work", name));
+            subscriptions.push(
+                create_subscription(&client, &work_topic, &format!("{}-work-sub", name)).await,
+            );
+            topics.push(work_topic);
+
+            let mut frontend = config.clone();
+            frontend.role = Role::Frontend;
+            frontend.work_topic = Some(format!("{}-work", name));
+            let mut worker = config;
+            worker.role = Role::Worker;
+            worker.work_subscription = Some(format!("{}-work-sub", name));
+            vec![("frontend", frontend), ("worker", worker)]
+        } else {
+            vec![("all", config)]
+        };
+
+        let stats = Arc::new(BackendStats::default());
+        let cancel = CancellationToken::new();
+        let mut services = Vec::new();
+        let mut runs = Vec::new();
+        for (role, mut config) in configs {
+            let role_dir = dir.join(role);
+            config.output_dir = role_dir.display().to_string();
+            config.quarantine_dir = role_dir.join("quarantine").display().to_string();
+            config.delayed_queue_path = role_dir.join("delayed.sqlite").display().to_string();
+
+            let backend = TrackedBackend {
+                inner: MockProofGenerator::new(
+                    settings.mock_proof_delay,
+                    Duration::from_secs(config.proof_timeout_secs),
+                ),
+                stats: stats.clone(),
+            };
+            let provenance = Provenance::collect(&config, None);
+            let service = Arc::new(
+                ProverService::new(config, backend, provenance)
+                    .await
+                    .expect("service starts"),
+            );
+            runs.push(tokio::spawn({
+                let service = service.clone();
+                let cancel = cancel.clone();
+                async move { service.run(cancel).await }
+            }));
+            services.push(service);
+        }
+
+        Self {
+            client,
+            requests,
+            request_subscription,
+            results,
+            services,
+            stats,
+            cancel,
+            runs,
+            topics,
+            subscriptions,
+            failover,
+            alternate,
+            dir,
+        }
+    }
+
+    fn failover(&self) -> &FailoverEndpoint {
+        self.failover
+            .as_ref()
+            .expect("harness started with Settings::failover")
+    }
+
+    /// Publish a request
+    pub async fn publish(&self, request: &ProverRequest) {
+        self.publish_raw(serde_json::to_vec(request).unwrap()).await;
+    }
+
+    /// Publish arbitrary message data
+    pub async fn publish_raw(&self, data: Vec<u8>) {
+        let message = PubsubMessage {
+            data,
+            ..Default::default()
+        };
+        self.requests
+            .publish(message)
+            .await
+            .get()
+            .await
+            .expect("request published");
+    }
+
+    /// Scenario: the same request delivered twice, as Pub/Sub may do
+    pub async fn deliver_twice(&self, request: &ProverRequest) {
+        self.publish(request).await;
+        self.publish(request).await;
+    }
+
+    /// Scenario: a message that is not a `ProverRequest`
+    pub async fn publish_malformed(&self) {
+        self.publish_raw(b"{\"request_id\": \"malformed\", \"verification_results\":".to_vec())
+            .await;
+    }
+
+    /// Scenario: `count` requests published at once; returns their IDs
+    pub async fn burst(&self, prefix: &str, count: usize) -> Vec<String> {
+        let mut ids = Vec::with_capacity(count);
+        for i in 0..count {
+            let request = request(&format!("{}{}", prefix, i));
+            self.publish(&request).await;
+            ids.push(request.request_id);
+        }
+        ids
+    }
+
+    /// Wait up to `wait` for `count` results, ACKing them
+    ///
+    /// Panics if fewer arrive, naming the ones that did.
+    pub async fn responses(&self, count: usize, wait: Duration) -> Vec<ProverResponse> {
+        let responses = pull(&self.results, count, wait).await;
+        assert_eq!(
+            responses.len(),
+            count,
+            "expected {} results, got {:?}",
+            count,
+            responses
+                .iter()
+                .map(|message| String::from_utf8_lossy(&message.data).into_owned())
+                .collect::<Vec<_>>()
+        );
+        responses
+            .iter()
+            .map(|message| serde_json::from_slice(&message.data).expect("result decodes"))
+            .collect()
+    }
+
+    /// Assert no further result is published within `wait`
+    pub async fn assert_no_more_responses(&self, wait: Duration) {
+        let extra = pull(&self.results, 1, wait).await;
+        assert!(
+            extra.is_empty(),
+            "unexpected result: {}",
+            String::from_utf8_lossy(&extra[0].data)
+        );
+    }
+
+    /// Scenario: the primary endpoint's subscription is deleted while the service
+    /// receives from it
+    pub async fn delete_primary_subscription(&self) {
+        self.request_subscription
+            .delete(None)
+            .await
+            .expect("subscription deleted");
+    }
+
+    /// Publish a request to the secondary endpoint
+    pub async fn publish_to_failover(&self, request: &ProverRequest) {
+        let message = PubsubMessage {
+            data: serde_json::to_vec(request).unwrap(),
+            ..Default::default()
+        };
+        self.failover()
+            .requests
+            .publish(message)
+            .await
+            .get()
+            .await
+            .expect("request published");
+    }
+
+    /// Wait up to `wait` for `count` results on the secondary endpoint, ACKing them
+    pub async fn failover_responses(&self, count: usize, wait: Duration) -> Vec<ProverResponse> {
+        let responses = pull(&self.failover().results, count, wait).await;
+        assert_eq!(
+            responses.len(),
+            count,
+            "expected {} failover results",
+            count
+        );
+        responses
+            .iter()
+            .map(|message| serde_json::from_slice(&message.data).expect("result decodes"))
+            .collect()
+    }
+
+    /// Shutdown report of the (last) service, the one that proves
+    pub fn session_report(&self) -> SessionReport {
+        self.services.last().expect("a service").session_report()
+    }
+
+    /// The allowed result topic, for a request's `result_topic`
+    pub fn alternate_topic(&self) -> &str {
+        &self.alternate().0
+    }
+
+    /// Wait up to `wait` for `count` results on the allowed result topic, ACKing them
+    pub async fn alternate_responses(&self, count: usize, wait: Duration) -> Vec<ProverResponse> {
+        let responses = pull(&self.alternate().1, count, wait).await;
+        assert_eq!(
+            responses.len(),
+            count,
+            "expected {} alternate results",
+            count
+        );
+        responses
+            .iter()
+            .map(|message| serde_json::from_slice(&message.data).expect("result decodes"))
+            .collect()
+    }
+
+    /// Assert no result is published to the allowed result topic within `wait`
+    pub async fn assert_no_alternate_responses(&self, wait: Duration) {
+        let extra = pull(&self.alternate().1, 1, wait).await;
+        assert!(
+            extra.is_empty(),
+            "unexpected alternate result: {}",
+            String::from_utf8_lossy(&extra[0].data)
+        );
+    }
+
+    fn alternate(&self) -> &(String, Subscription) {
+        self.alternate
+            .as_ref()
+            .expect("harness started with Settings::alternate_results")
+    }
+
+    /// Index of the endpoint the (first) service receives from
+    pub fn active_endpoint(&self) -> usize {
+        self.services[0].endpoints().active()
+    }
+
+    /// Backend calls made so far
+    pub fn proofs_started(&self) -> usize {
+        self.stats.started.load(Ordering::SeqCst)
+    }
+
+    /// Most proofs the backend ran at once
+    pub fn peak_concurrency(&self) -> usize {
+        self.stats.peak.load(Ordering::SeqCst)
+    }
+
+    /// Stop the services and wait for in-flight messages
+    pub async fn stop(&mut self) {
+        self.cancel.cancel();
+        for run in self.runs.drain(..) {
+            run.await
+                .expect("service task")
+                .expect("service stops cleanly");
+        }
+        for service in &self.services {
+            service.drain().await;
+        }
+    }
+
+    /// After `stop`, the requests Pub/Sub redelivers because they were never ACKed
+    pub async fn unacked_requests(&self) -> usize {
+        pull(
+            &self.request_subscription,
+            usize::MAX,
+            ACK_DEADLINE + Duration::from_secs(5),
+        )
+        .await
+        .len()
+    }
+
+    /// After `stop`, the work items Pub/Sub redelivers because no worker ACKed them
+    pub async fn unacked_work_items(&self) -> usize {
+        let mut unacked = 0;
+        for subscription in &self.subscriptions {
+            unacked += pull(
+                subscription,
+                usize::MAX,
+                ACK_DEADLINE + Duration::from_secs(5),
+            )
+            .await
+            .len();
+        }
+        unacked
+    }
+
+    /// Stop the service and delete the topics, subscriptions and files
+    pub async fn finish(mut self) {
+        self.stop().await;
+        let _ = self.request_subscription.delete(None).await;
+        let _ = self.results.delete(None).await;
+        for subscription in &self.subscriptions {
+            let _ = subscription.delete(None).await;
+        }
+        if let Some(failover) = &self.failover {
+            let _ = failover.request_subscription.delete(None).await;
+            let _ = failover.results.delete(None).await;
+        }
+        if let Some((_, subscription)) = &self.alternate {
+            let _ = subscription.delete(None).await;
+        }
+        for topic in &self.topics {
+            let _ = self.client.topic(topic).delete(None).await;
+        }
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// A valid request with production weights
+pub fn request(request_id: &str) -> ProverRequest {
+    serde_json::from_value(json!({
+        "request_id": request_id,
+        "verification_results": { "recaptcha_score": 10000, "sms_verified": 1, "bio_verified": 0 },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 },
+    }))
+    .unwrap()
+}
+
+/// Mock backend that records how many proofs run at once
+pub struct TrackedBackend {
+    inner: MockProofGenerator,
+    stats: Arc<BackendStats>,
+}
+
+#[derive(Default)]
+struct BackendStats {
+    started: AtomicUsize,
+    running: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl ProofBackend for TrackedBackend {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn generate_proof(
+        &self,
+        request: ProverRequest,
+        context: &ProofContext,
+    ) -> Result<ProofData, ServiceError> {
+        self.stats.started.fetch_add(1, Ordering::SeqCst);
+        let running = self.stats.running.fetch_add(1, Ordering::SeqCst) + 1;
+        self.stats.peak.fetch_max(running, Ordering::SeqCst);
+        let result = self.inner.generate_proof(request, context);
+        self.stats.running.fetch_sub(1, Ordering::SeqCst);
+        result
+    }
+
+    fn warm_up(&self) -> Result<Option<WarmUp>, ServiceError> {
+        self.inner.warm_up()
+    }
+}
+
+/// Pull up to `count` messages within `wait`, ACKing each
+async fn pull(subscription: &Subscription, count: usize, wait: Duration) -> Vec<PubsubMessage> {
+    let deadline = Instant::now() + wait;
+    let mut messages = Vec::new();
+    while messages.len() < count {
+        let Some(remaining) = deadline.checked_duration_since(Instant::now()) else {
+            break;
+        };
+        let batch = match tokio::time::timeout(remaining, subscription.pull(10, None)).await {
+            Ok(batch) => batch.expect("pull"),
+            Err(_) => break,
+        };
+        if batch.is_empty() {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        for received in batch {
+            received.ack().await.expect("ack");
+            messages.push(received.message.clone());
+        }
+    }
+    messages
+}
+
+async fn create_subscription(client: &Client, topic: &str, id: &str) -> Subscription {
+    create_subscription_with(client, topic, id, false).await
+}
+
+/// `create_subscription`, delivering messages with the same ordering key in order
+/// when `ordered`
+async fn create_subscription_with(
+    client: &Client,
+    topic: &str,
+    id: &str,
+    ordered: bool,
+) -> Subscription {
+    client
+        .topic(topic)
+        .create(None, None)
+        .await
+        .expect("topic created");
+    let subscription =
+        client.subscription(&format!("projects/{}/subscriptions/{}", PROJECT_ID, id));
+    let config = SubscriptionConfig {
+        ack_deadline_seconds: ACK_DEADLINE.as_secs() as i32,
+        enable_message_ordering: ordered,
+        ..Default::default()
+    };
+    subscription
+        .create(topic, config, None)
+        .await
+        .expect("subscription created");
+    subscription
+}
+
+fn topic_path(id: &str) -> String {
+    format!("projects/{}/topics/{}", PROJECT_ID, id)
+}
+
+/// Names unique to this process and harness
+fn unique_name() -> String {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    format!(
+        "it-{}-{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::SeqCst)
+    )
+}
+
+/// Configuration shared by every harness, read once after the emulator is up
+fn base_config() -> Config {
+    static BASE: OnceLock<Config> = OnceLock::new();
+    BASE.get_or_init(|| {
+        let host =
+            std::env::var("PUBSUB_EMULATOR_HOST").unwrap_or_else(|_| start_emulator().to_string());
+        wait_for_emulator(&host);
+
+        std::env::set_var("PUBSUB_EMULATOR_HOST", &host);
+        std::env::set_var("GCP_PROJECT_ID", PROJECT_ID);
+        std::env::set_var("PROVER_SUBSCRIPTION", "unused");
+        std::env::set_var("RESULT_TOPIC", "unused");
+        std::env::set_var("PROVER_BACKEND", "mock");
+        std::env::set_var("MAX_CONCURRENT_PROOFS", "1");
+        Config::from_env().expect("base configuration")
+    })
+    .clone()
+}
+
+/// Start the emulator container unless it is already running
+fn start_emulator() -> &'static str {
+    let running = Command::new("docker")
+        .args(["inspect", "-f", "{{.State.Running}}", EMULATOR_CONTAINER])
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim() == "true")
+        .unwrap_or(false);
+    if !running {
+        let port_mapping = format!("{}:8085", EMULATOR_HOST.rsplit(':').next().unwrap());
+        let status = Command::new("docker")
+            .args(["run", "-d", "--rm", "--name", EMULATOR_CONTAINER, "-p"])
+            .arg(&port_mapping)
+            .args([
+                EMULATOR_IMAGE,
+                "gcloud",
+                "beta",
+                "emulators",
+                "pubsub",
+                "start",
+                "--host-port=0.0.0.0:8085",
+            ])
+            .arg(format!("--project={}", PROJECT_ID))
+            .status()
+            .expect("docker is needed to start the Pub/Sub emulator; or set PUBSUB_EMULATOR_HOST");
+        assert!(status.success(), "failed to start the Pub/Sub emulator");
+    }
+    EMULATOR_HOST
+}
+
+fn wait_for_emulator(host: &str) {
+    let deadline = Instant::now() + Duration::from_secs(60);
+    while TcpStream::connect(host).is_err() {
+        assert!(
+            Instant::now() < deadline,
+            "Pub/Sub emulator at {} is not reachable",
+            host
+        );
+        std::thread::sleep(Duration::from_millis(250));
+    }
+}
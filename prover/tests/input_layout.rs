@@ -0,0 +1,49 @@
+// Checks that the Groth16 setup writes the same stdin layout as proof generation,
+// since keys from a setup over another layout do not match runtime proofs.
+
+use human_index_lib::{SCALE, STDIN_WORDS};
+use prover::prover::{setup_inputs, InputLayout};
+
+fn setup_layout() -> InputLayout {
+    let mut layout = InputLayout::default();
+    setup_inputs().write_to(|value| layout.record(value));
+    layout
+}
+
+#[test]
+fn setup_and_runtime_layouts_match() {
+    let setup = setup_layout();
+    assert_eq!(setup.arity(), InputLayout::runtime().arity());
+    assert_eq!(setup, InputLayout::runtime());
+    setup
+        .check_runtime()
+        .expect("setup layout is the runtime layout");
+}
+
+#[test]
+fn runtime_writes_results_weights_output_and_validity() {
+    // Three results, four weights, the output, and two words each for the window
+    assert_eq!(InputLayout::runtime().arity(), 12);
+    assert_eq!(STDIN_WORDS, 12);
+}
+
+#[test]
+fn diverging_layout_is_rejected() {
+    let mut layout = setup_layout();
+    layout.record(&0u64);
+    assert!(layout.check_runtime().is_err());
+}
+
+#[test]
+fn setup_inputs_use_the_runtime_scale() {
+    let setup = setup_inputs();
+    let (verification_results, public_inputs) = (setup.results, setup.inputs);
+    let weights = [
+        public_inputs.w1,
+        public_inputs.w2,
+        public_inputs.w3,
+        public_inputs.w4,
+    ];
+    assert_eq!(weights.iter().sum::<u32>(), SCALE);
+    assert!(verification_results.recaptcha_score <= SCALE);
+}
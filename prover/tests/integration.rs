@@ -0,0 +1,598 @@
+// End-to-end scenarios against the Pub/Sub emulator; run with
+// `cargo test -p prover --features integration`. See `harness` for how the
+// emulator is found or started.
+
+mod harness;
+
+use harness::{request, Harness, Settings};
+use human_index_lib::calculate_human_index;
+use prover::fixtures::{golden_vectors, Fixtures};
+use prover::mock::FAIL_PROOF_PREFIX;
+use prover::types::{ProofStatus, ProverRequest, ProverResponse, RequestIdFormat, SignalPolicy};
+use serde_json::json;
+use std::collections::HashSet;
+use std::time::Duration;
+
+const RESULT_WAIT: Duration = Duration::from_secs(30);
+const QUIET: Duration = Duration::from_secs(2);
+
+fn expected_human_index() -> u32 {
+    let request = request("expected");
+    calculate_human_index(&request.verification_results, &request.public_inputs)
+}
+
+fn assert_success(response: &ProverResponse) {
+    assert_eq!(
+        response.status,
+        ProofStatus::Success,
+        "{}: {:?}",
+        response.request_id,
+        response.error
+    );
+    let proof = response.proof_data.as_ref().expect("proof data");
+    assert_eq!(proof.human_index, expected_human_index());
+    assert!(response.provenance.is_some());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn proves_and_publishes_a_request() {
+    let mut harness = Harness::start(Settings::default()).await;
+    harness.publish(&request("single")).await;
+
+    let responses = harness.responses(1, RESULT_WAIT).await;
+    assert_eq!(responses[0].request_id, "single");
+    assert_success(&responses[0]);
+
+    harness.stop().await;
+    assert_eq!(harness.unacked_requests().await, 0);
+    harness.finish().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn published_proofs_match_recorded_fixtures() {
+    let fixtures = Fixtures::workspace().expect("fixtures load");
+    let vectors = golden_vectors().expect("golden vectors load");
+    let harness = Harness::start(Settings::default()).await;
+    for vector in &vectors {
+        harness.publish(&vector.request()).await;
+    }
+
+    let responses = harness.responses(vectors.len(), RESULT_WAIT).await;
+    for vector in &vectors {
+        let request_id = vector.request().request_id;
+        let response = responses
+            .iter()
+            .find(|response| response.request_id == request_id)
+            .expect("result for every vector");
+        let recorded = fixtures
+            .mock_proof(&vector.name)
+            .expect("mock proof fixture");
+        let published = response.proof_data.as_ref().expect("proof data");
+        assert_eq!(published.proof, recorded.proof, "{}", vector.name);
+        assert_eq!(
+            published.public_inputs, recorded.public_inputs,
+            "{}",
+            vector.name
+        );
+        assert_eq!(
+            published.human_index, recorded.human_index,
+            "{}",
+            vector.name
+        );
+    }
+
+    harness.finish().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn duplicate_delivery_is_answered_per_delivery() {
+    let mut harness = Harness::start(Settings::default()).await;
+    harness.deliver_twice(&request("duplicate")).await;
+
+    let responses = harness.responses(2, RESULT_WAIT).await;
+    for response in &responses {
+        assert_eq!(response.request_id, "duplicate");
+        assert_success(response);
+    }
+    // The mock proof depends only on the request, so both deliveries agree
+    assert_eq!(
+        responses[0].proof_data.as_ref().unwrap().proof,
+        responses[1].proof_data.as_ref().unwrap().proof
+    );
+    assert_ne!(responses[0].publish_sequence, responses[1].publish_sequence);
+    harness.assert_no_more_responses(QUIET).await;
+
+    harness.stop().await;
+    assert_eq!(harness.unacked_requests().await, 0);
+    harness.finish().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn results_for_one_user_arrive_in_publish_order() {
+    let mut harness = Harness::start(Settings {
+        result_ordering: true,
+        ..Settings::default()
+    })
+    .await;
+    for i in 0..6 {
+        let mut request = request(&format!("ordered-{}", i));
+        request.binding_id = Some("user-42".to_string());
+        harness.publish(&request).await;
+    }
+
+    let responses = harness.responses(6, RESULT_WAIT).await;
+    let sequence: Vec<u64> = responses
+        .iter()
+        .map(|response| {
+            assert_success(response);
+            assert_eq!(response.binding_id.as_deref(), Some("user-42"));
+            response.publish_sequence.expect("publish_sequence")
+        })
+        .collect();
+    assert!(
+        sequence.windows(2).all(|pair| pair[0] < pair[1]),
+        "results out of publish order: {:?}",
+        sequence
+    );
+
+    harness.stop().await;
+    harness.finish().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn malformed_message_is_acked_without_a_result() {
+    let mut harness = Harness::start(Settings::default()).await;
+    harness.publish_malformed().await;
+    harness.publish(&request("after-malformed")).await;
+
+    let responses = harness.responses(1, RESULT_WAIT).await;
+    assert_eq!(responses[0].request_id, "after-malformed");
+    assert_success(&responses[0]);
+    harness.assert_no_more_responses(QUIET).await;
+    assert_eq!(harness.proofs_started(), 1);
+
+    // ACKed before parsing, so Pub/Sub does not redeliver it either
+    harness.stop().await;
+    assert_eq!(harness.unacked_requests().await, 0);
+    harness.finish().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn burst_never_exceeds_max_concurrent_proofs() {
+    let harness = Harness::start(Settings {
+        max_concurrent_proofs: 2,
+        mock_proof_delay: Duration::from_millis(300),
+        ..Default::default()
+    })
+    .await;
+    let ids = harness.burst("burst-", 8).await;
+
+    let responses = harness.responses(ids.len(), RESULT_WAIT).await;
+    let answered: HashSet<_> = responses.iter().map(|r| r.request_id.clone()).collect();
+    assert_eq!(answered, ids.into_iter().collect());
+    responses.iter().for_each(assert_success);
+    assert_eq!(harness.peak_concurrency(), 2);
+
+    harness.finish().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn mixed_burst_with_duplicates_and_malformed_messages() {
+    let mut harness = Harness::start(Settings {
+        max_concurrent_proofs: 1,
+        mock_proof_delay: Duration::from_millis(100),
+        ..Default::default()
+    })
+    .await;
+    let mut ids = harness.burst("mixed-", 3).await;
+    harness.publish_malformed().await;
+    harness.deliver_twice(&request("mixed-dup")).await;
+    ids.extend(["mixed-dup".to_string(), "mixed-dup".to_string()]);
+
+    let responses = harness.responses(ids.len(), RESULT_WAIT).await;
+    let mut answered: Vec<_> = responses.iter().map(|r| r.request_id.clone()).collect();
+    answered.sort();
+    ids.sort();
+    assert_eq!(answered, ids);
+    responses.iter().for_each(assert_success);
+    assert_eq!(harness.peak_concurrency(), 1);
+    harness.assert_no_more_responses(QUIET).await;
+
+    harness.stop().await;
+    assert_eq!(harness.unacked_requests().await, 0);
+    harness.finish().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn result_topic_override_is_honoured() {
+    let harness = Harness::start(Settings {
+        alternate_results: true,
+        ..Default::default()
+    })
+    .await;
+    let mut routed = request("routed");
+    routed.result_topic = Some(harness.alternate_topic().to_string());
+    harness.publish(&routed).await;
+    harness.publish(&request("unrouted")).await;
+
+    // Each result on its own topic, and only there
+    let responses = harness.alternate_responses(1, RESULT_WAIT).await;
+    assert_eq!(responses[0].request_id, "routed");
+    assert_success(&responses[0]);
+    let responses = harness.responses(1, RESULT_WAIT).await;
+    assert_eq!(responses[0].request_id, "unrouted");
+    assert_success(&responses[0]);
+    harness.assert_no_more_responses(QUIET).await;
+    harness.assert_no_alternate_responses(QUIET).await;
+
+    harness.finish().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn disallowed_result_topic_fails_on_the_global_topic() {
+    let harness = Harness::start(Settings {
+        alternate_results: true,
+        ..Default::default()
+    })
+    .await;
+    let mut misrouted = request("misrouted");
+    misrouted.result_topic = Some("attacker-results".to_string());
+    harness.publish(&misrouted).await;
+
+    let responses = harness.responses(1, RESULT_WAIT).await;
+    assert_eq!(responses[0].request_id, "misrouted");
+    assert_eq!(responses[0].status, ProofStatus::Failed);
+    assert_eq!(
+        responses[0].error.as_ref().unwrap().error_type,
+        "ResultTopicNotAllowed"
+    );
+    assert_eq!(harness.proofs_started(), 0);
+    harness.assert_no_alternate_responses(QUIET).await;
+
+    harness.finish().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn shutdown_report_counts_the_session() {
+    let mut harness = Harness::start(Settings {
+        max_concurrent_proofs: 2,
+        mock_proof_delay: Duration::from_millis(100),
+        ..Default::default()
+    })
+    .await;
+    harness.burst("report-", 2).await;
+    harness
+        .publish(&request(&format!("{}report", FAIL_PROOF_PREFIX)))
+        .await;
+    harness.responses(3, RESULT_WAIT).await;
+    harness.stop().await;
+
+    let report = harness.session_report();
+    assert_eq!(report.processed, 3);
+    assert_eq!(report.succeeded, 2);
+    assert_eq!(report.failed, 1);
+    assert_eq!(report.timed_out, 0);
+    assert_eq!(report.interrupted, 0);
+    let duration = report.proof_duration_ms.clone().expect("successful proofs");
+    assert!(duration.p50 <= duration.max && duration.mean <= duration.max);
+    assert!((1..=2).contains(&report.peak_in_flight), "{:?}", report);
+
+    harness.finish().await;
+}
+
+/// `request(request_id)` with a `recaptcha_score` and `sms_verified` out of range
+fn out_of_range(request_id: &str) -> ProverRequest {
+    let mut request = request(request_id);
+    request.verification_results.recaptcha_score = 15000;
+    request.verification_results.sms_verified = 3;
+    request
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn out_of_range_signals_are_rejected_by_default() {
+    let harness = Harness::start(Settings::default()).await;
+    harness.publish(&out_of_range("out-of-range")).await;
+
+    let responses = harness.responses(1, RESULT_WAIT).await;
+    assert_eq!(responses[0].status, ProofStatus::Failed);
+    let error = responses[0].error.as_ref().unwrap();
+    assert_eq!(error.error_type, "InvalidSignal");
+    assert!(
+        error.message.contains("recaptcha_score"),
+        "{}",
+        error.message
+    );
+    assert!(error.message.contains("sms_verified"), "{}", error.message);
+    assert!(!error.message.contains("15000"), "{}", error.message);
+    assert_eq!(harness.proofs_started(), 0);
+
+    harness.finish().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn out_of_range_signals_are_clamped_on_request() {
+    let harness = Harness::start(Settings {
+        signal_policy: SignalPolicy::Clamp,
+        ..Default::default()
+    })
+    .await;
+    harness.publish(&out_of_range("clamped")).await;
+
+    // Proved as the in-range request
+    let responses = harness.responses(1, RESULT_WAIT).await;
+    assert_success(&responses[0]);
+
+    harness.finish().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn aliased_request_keeps_its_result_topic() {
+    let harness = Harness::start(Settings {
+        signal_aliases: "captcha=recaptcha_score".parse().unwrap(),
+        alternate_results: true,
+        ..Default::default()
+    })
+    .await;
+    let mut aliased = serde_json::to_value(request("aliased-topic")).unwrap();
+    let results = aliased["verification_results"].as_object_mut().unwrap();
+    let score = results.remove("recaptcha_score").unwrap();
+    results.insert("captcha".to_string(), score);
+    aliased["result_topic"] = json!(harness.alternate_topic());
+    harness
+        .publish_raw(serde_json::to_vec(&aliased).unwrap())
+        .await;
+
+    // Routed as the request asked, and proved as the canonical one
+    let responses = harness.alternate_responses(1, RESULT_WAIT).await;
+    assert_eq!(responses[0].request_id, "aliased-topic");
+    assert_success(&responses[0]);
+    harness.assert_no_more_responses(QUIET).await;
+
+    harness.finish().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn failed_proof_publishes_a_failed_result() {
+    let harness = Harness::start(Settings::default()).await;
+    let request_id = format!("{}1", FAIL_PROOF_PREFIX);
+    harness.publish(&request(&request_id)).await;
+
+    let responses = harness.responses(1, RESULT_WAIT).await;
+    assert_eq!(responses[0].request_id, request_id);
+    assert_eq!(responses[0].status, ProofStatus::Failed);
+    assert!(responses[0].proof_data.is_none());
+    let error = responses[0].error.as_ref().expect("error");
+    assert_eq!(error.error_type, "ProofGenerationError");
+
+    harness.finish().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn failed_proof_carries_provisional_index_when_enabled() {
+    let harness = Harness::start(Settings {
+        emit_provisional_index: true,
+        ..Default::default()
+    })
+    .await;
+    let request_id = format!("{}provisional", FAIL_PROOF_PREFIX);
+    harness.publish(&request(&request_id)).await;
+    harness.publish(&request("proven")).await;
+
+    let mut responses = harness.responses(2, RESULT_WAIT).await;
+    responses.sort_by(|a, b| a.request_id.cmp(&b.request_id));
+    let (failed, proven) = (&responses[0], &responses[1]);
+    assert_eq!(failed.status, ProofStatus::Failed);
+    let provisional = failed.provisional_index.expect("provisional index");
+    assert_eq!(provisional.human_index, expected_human_index());
+    assert!(!provisional.proven);
+    assert_success(proven);
+    assert!(proven.provisional_index.is_none());
+
+    harness.finish().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn expired_request_fails_without_proving() {
+    let harness = Harness::start(Settings {
+        max_request_age_secs: Some(60),
+        ..Default::default()
+    })
+    .await;
+    let mut expired = request("expired");
+    expired.submitted_at = Some((chrono::Utc::now() - chrono::TimeDelta::hours(1)).to_rfc3339());
+    harness.publish(&expired).await;
+
+    let responses = harness.responses(1, RESULT_WAIT).await;
+    assert_eq!(responses[0].request_id, "expired");
+    assert_eq!(responses[0].status, ProofStatus::Failed);
+    assert_eq!(harness.proofs_started(), 0);
+
+    harness.finish().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn raw_weights_fail_when_a_named_profile_is_required() {
+    let harness = Harness::start(Settings {
+        require_named_profile: true,
+        ..Default::default()
+    })
+    .await;
+    let mut named = request("named");
+    named.weights_profile = Some("v1".to_string());
+    harness.publish(&named).await;
+    harness.publish(&request("raw")).await;
+
+    let mut responses = harness.responses(2, RESULT_WAIT).await;
+    responses.sort_by(|a, b| a.request_id.cmp(&b.request_id));
+    let (named, raw) = (&responses[0], &responses[1]);
+    assert_success(named);
+    let proof = named.proof_data.as_ref().unwrap();
+    assert_eq!(proof.weights_profile.as_deref(), Some("v1"));
+    assert_eq!(raw.status, ProofStatus::Failed);
+    assert_eq!(
+        raw.error.as_ref().unwrap().error_type,
+        "NamedProfileRequired"
+    );
+    assert_eq!(harness.proofs_started(), 1);
+
+    harness.finish().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn low_index_is_answered_without_proving() {
+    let harness = Harness::start(Settings {
+        min_provable_index: Some(100),
+        ..Default::default()
+    })
+    .await;
+    // Only the recaptcha score: (0.15 + 0.2 * 0.2) * 255 = 48
+    let mut low = request("low");
+    low.verification_results.recaptcha_score = 2000;
+    low.verification_results.sms_verified = 0;
+    low.verification_results.bio_verified = 0;
+    harness.publish(&low).await;
+    harness.publish(&request("high")).await;
+
+    let mut responses = harness.responses(2, RESULT_WAIT).await;
+    responses.sort_by(|a, b| a.request_id.cmp(&b.request_id));
+    let (high, low) = (&responses[0], &responses[1]);
+    assert_success(high);
+    assert!(expected_human_index() >= 100);
+    assert_eq!(low.status, ProofStatus::BelowThreshold);
+    assert!(low.proof_data.is_none());
+    let provisional = low.provisional_index.expect("computed index");
+    assert_eq!(provisional.human_index, 48);
+    assert!(!provisional.proven);
+    assert_eq!(harness.proofs_started(), 1);
+
+    harness.finish().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn trace_ids_are_echoed_and_request_ids_checked() {
+    let harness = Harness::start(Settings {
+        request_id_format: RequestIdFormat::Uuid,
+        ..Default::default()
+    })
+    .await;
+    let mut traced = request("tenant-a:0b9e4c1e-5f43-4d8a-9a51-7c2f0e6d1b3a");
+    traced.trace_id = Some("upstream-trace-1".to_string());
+    traced.parent_request_id = Some("upstream-request-1".to_string());
+    harness.publish(&traced).await;
+    harness.publish(&request("not-a-uuid")).await;
+
+    let mut responses = harness.responses(2, RESULT_WAIT).await;
+    responses.sort_by(|a, b| a.request_id.cmp(&b.request_id));
+    let (invalid, traced) = (&responses[0], &responses[1]);
+    assert_success(traced);
+    assert_eq!(traced.trace_id.as_deref(), Some("upstream-trace-1"));
+    assert_eq!(
+        traced.parent_request_id.as_deref(),
+        Some("upstream-request-1")
+    );
+    assert_eq!(invalid.status, ProofStatus::Failed);
+    assert_eq!(
+        invalid.error.as_ref().unwrap().error_type,
+        "InvalidRequestId"
+    );
+    assert_eq!(harness.proofs_started(), 1);
+
+    harness.finish().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn path_traversal_request_ids_are_rejected() {
+    let harness = Harness::start(Settings {
+        request_id_format: RequestIdFormat::Any,
+        ..Default::default()
+    })
+    .await;
+    harness.publish(&request("../../etc/cron.d/x")).await;
+
+    let responses = harness.responses(1, RESULT_WAIT).await;
+    assert_eq!(responses[0].status, ProofStatus::Failed);
+    assert_eq!(
+        responses[0].error.as_ref().unwrap().error_type,
+        "InvalidRequestId"
+    );
+    assert_eq!(harness.proofs_started(), 0);
+
+    harness.finish().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn frontend_and_worker_deliver_end_to_end() {
+    let mut harness = Harness::start(Settings {
+        max_concurrent_proofs: 2,
+        mock_proof_delay: Duration::from_millis(100),
+        split: true,
+        ..Default::default()
+    })
+    .await;
+    let mut ids = harness.burst("split-", 5).await;
+    harness.publish_malformed().await;
+    harness.deliver_twice(&request("split-dup")).await;
+    // Two publishes are two messages, so both are forwarded and answered
+    ids.extend(["split-dup".to_string(), "split-dup".to_string()]);
+
+    let responses = harness.responses(ids.len(), RESULT_WAIT).await;
+    let mut answered: Vec<_> = responses.iter().map(|r| r.request_id.clone()).collect();
+    answered.sort();
+    ids.sort();
+    assert_eq!(answered, ids);
+    responses.iter().for_each(assert_success);
+    assert!(harness.peak_concurrency() <= 2);
+    harness.assert_no_more_responses(QUIET).await;
+
+    // The frontend dropped the malformed message instead of forwarding it
+    assert_eq!(harness.proofs_started(), ids.len());
+    harness.stop().await;
+    assert_eq!(harness.unacked_requests().await, 0);
+    assert_eq!(harness.unacked_work_items().await, 0);
+    harness.finish().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn frontend_forwards_delayed_request_when_due() {
+    let harness = Harness::start(Settings {
+        split: true,
+        ..Default::default()
+    })
+    .await;
+    let mut delayed = request("split-delayed");
+    let not_before = chrono::Utc::now() + chrono::TimeDelta::seconds(3);
+    delayed.not_before = Some(not_before.to_rfc3339());
+    harness.publish(&delayed).await;
+
+    let responses = harness.responses(1, RESULT_WAIT).await;
+    assert_eq!(responses[0].request_id, "split-delayed");
+    assert_success(&responses[0]);
+    assert!(chrono::Utc::now() >= not_before);
+
+    harness.finish().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn fails_over_to_the_secondary_endpoint() {
+    let harness = Harness::start(Settings {
+        failover: true,
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(harness.active_endpoint(), 0);
+    harness.publish(&request("primary")).await;
+    let responses = harness.responses(1, RESULT_WAIT).await;
+    assert_eq!(responses[0].request_id, "primary");
+
+    harness.delete_primary_subscription().await;
+    harness.publish_to_failover(&request("secondary")).await;
+
+    // Answered on the secondary endpoint's result topic, not the primary's
+    let responses = harness.failover_responses(1, RESULT_WAIT).await;
+    assert_eq!(responses[0].request_id, "secondary");
+    assert_success(&responses[0]);
+    assert_eq!(harness.active_endpoint(), 1);
+    harness.assert_no_more_responses(QUIET).await;
+
+    harness.finish().await;
+}
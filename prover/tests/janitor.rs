@@ -0,0 +1,246 @@
+// Sweeps a synthetic output directory: request directories left by failed and
+// abandoned proofs and stale quarantine entries are removed by age, then oldest
+// first while over budget, and nothing else is ever touched.
+
+use prover::janitor::{Category, Janitor, JanitorPolicy, Reason};
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+const HOUR: Duration = Duration::from_secs(3600);
+const DAY: Duration = Duration::from_secs(24 * 3600);
+
+/// A fresh output directory, removed on drop
+struct OutputDir {
+    root: PathBuf,
+    now: SystemTime,
+}
+
+impl Drop for OutputDir {
+    fn drop(&mut self) {
+        std::fs::remove_dir_all(&self.root).ok();
+    }
+}
+
+impl OutputDir {
+    /// The tree a worker leaves behind, with entries of different ages:
+    ///
+    /// ```text
+    /// vm_pk, vm_vk, delayed.sqlite, Groth16Verifier.sol   setup and state, 2 days old
+    /// crashed/            request directory, 2 days old, 1000 bytes
+    /// timed-out/          request directory, 3 hours old, 400 bytes
+    /// running/            request directory, 10 minutes old, 200 bytes
+    /// audit/              not a request directory, 2 days old
+    /// quarantine/attempts/old, quarantine/attempts/new    8 days, 1 hour old
+    /// quarantine/stale.json, quarantine/fresh.json        8 days, 1 hour old
+    /// ```
+    fn new(name: &str) -> Self {
+        let root = std::env::temp_dir().join(format!("janitor-{}-{}", name, std::process::id()));
+        std::fs::remove_dir_all(&root).ok();
+        let dir = Self {
+            root,
+            now: SystemTime::now(),
+        };
+
+        for (name, bytes) in [
+            ("vm_pk", 3000),
+            ("vm_vk", 100),
+            ("delayed.sqlite", 50),
+            ("Groth16Verifier.sol", 20),
+        ] {
+            dir.file(name, bytes, 2 * DAY);
+        }
+        dir.request("crashed", 1000, 2 * DAY);
+        dir.request("timed-out", 400, 3 * HOUR);
+        dir.request("running", 200, Duration::from_secs(600));
+        dir.file("audit/audit.jsonl", 10, 2 * DAY);
+        dir.age("audit", 2 * DAY);
+
+        dir.file("quarantine/attempts/old", 1, 8 * DAY);
+        dir.file("quarantine/attempts/new", 1, HOUR);
+        dir.file("quarantine/stale.json", 30, 8 * DAY);
+        dir.file("quarantine/fresh.json", 30, HOUR);
+        dir.age("quarantine/attempts", HOUR);
+        dir.age("quarantine", HOUR);
+        dir
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.root.join(name)
+    }
+
+    fn file(&self, name: &str, bytes: usize, age: Duration) {
+        let path = self.path(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, vec![0u8; bytes]).unwrap();
+        self.age(name, age);
+    }
+
+    /// A request directory as `prove_evm` leaves it, setup files linked in
+    fn request(&self, name: &str, bytes: usize, age: Duration) {
+        std::fs::create_dir_all(self.path(name)).unwrap();
+        for setup_file in ["vm_pk", "vm_vk"] {
+            std::fs::hard_link(self.path(setup_file), self.path(name).join(setup_file)).unwrap();
+        }
+        self.file(&format!("{}/inputs.json", name), bytes, age);
+        self.age(name, age);
+    }
+
+    fn age(&self, name: &str, age: Duration) {
+        File::open(self.path(name))
+            .unwrap()
+            .set_modified(self.now - age)
+            .unwrap();
+    }
+
+    fn janitor(&self) -> Janitor {
+        Janitor::new(&self.root, &self.path("quarantine"))
+    }
+
+    fn exists(&self, name: &str) -> bool {
+        self.path(name).exists()
+    }
+}
+
+fn policy() -> JanitorPolicy {
+    JanitorPolicy {
+        request_ttl: DAY,
+        min_request_age: HOUR,
+        quarantine_ttl: None,
+        max_bytes: None,
+    }
+}
+
+fn removed(dir: &OutputDir, sweep: &prover::janitor::Sweep) -> BTreeSet<String> {
+    sweep
+        .removed
+        .iter()
+        .map(|removal| relative(&dir.root, &removal.path))
+        .collect()
+}
+
+fn relative(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root).unwrap().display().to_string()
+}
+
+fn assert_untouched(dir: &OutputDir) {
+    for name in [
+        "vm_pk",
+        "vm_vk",
+        "delayed.sqlite",
+        "Groth16Verifier.sol",
+        "audit/audit.jsonl",
+        "running/inputs.json",
+    ] {
+        assert!(dir.exists(name), "{} was removed", name);
+    }
+}
+
+#[test]
+fn expired_request_directories_are_removed() {
+    let dir = OutputDir::new("ttl");
+    let sweep = dir.janitor().sweep(&policy(), dir.now);
+
+    assert_eq!(
+        removed(&dir, &sweep),
+        BTreeSet::from(["crashed".to_string()])
+    );
+    assert_eq!(sweep.removed[0].reason, Reason::Expired);
+    assert_eq!(sweep.removed[0].category, Category::Request);
+    // The linked setup files do not count
+    assert_eq!(sweep.reclaimed_bytes(), 1000);
+    assert!(dir.exists("timed-out"));
+    assert_untouched(&dir);
+
+    // Without a quarantine TTL, attempt counts and quarantined requests are kept
+    for name in [
+        "quarantine/attempts/old",
+        "quarantine/attempts/new",
+        "quarantine/stale.json",
+        "quarantine/fresh.json",
+    ] {
+        assert!(dir.exists(name), "{} was removed", name);
+    }
+}
+
+#[test]
+fn stale_quarantine_entries_are_removed_with_a_ttl() {
+    let dir = OutputDir::new("quarantine");
+    let policy = JanitorPolicy {
+        quarantine_ttl: Some(7 * DAY),
+        ..policy()
+    };
+    let sweep = dir.janitor().sweep(&policy, dir.now);
+
+    assert_eq!(
+        removed(&dir, &sweep),
+        BTreeSet::from([
+            "crashed".to_string(),
+            "quarantine/attempts/old".to_string(),
+            "quarantine/stale.json".to_string(),
+        ])
+    );
+    assert_eq!(sweep.removed_count(Category::Attempts), 1);
+    assert_eq!(sweep.removed_count(Category::Quarantined), 1);
+    assert!(dir.exists("quarantine/attempts/new"));
+    assert!(dir.exists("quarantine/fresh.json"));
+    assert_untouched(&dir);
+}
+
+#[test]
+fn budget_evicts_oldest_first_but_never_a_running_proof() {
+    let dir = OutputDir::new("budget");
+    let janitor = dir.janitor();
+    // Setup and state 3170, requests 1600, audit 10, quarantine 62
+    assert_eq!(janitor.usage_bytes(), 4842);
+
+    // Removing the expired request is not enough; the next oldest goes too
+    let policy = JanitorPolicy {
+        max_bytes: Some(3500),
+        ..policy()
+    };
+    let sweep = janitor.sweep(&policy, dir.now);
+    assert_eq!(
+        removed(&dir, &sweep),
+        BTreeSet::from(["crashed".to_string(), "timed-out".to_string()])
+    );
+    let timed_out = sweep
+        .removed
+        .iter()
+        .find(|removal| removal.path.ends_with("timed-out"))
+        .unwrap();
+    assert_eq!(timed_out.reason, Reason::Budget);
+    assert_eq!(sweep.usage_bytes, 3442);
+    assert!(!sweep.over_budget);
+    assert_untouched(&dir);
+
+    // Nothing else may go, so the budget cannot be met
+    let policy = JanitorPolicy {
+        max_bytes: Some(1000),
+        ..policy
+    };
+    let sweep = janitor.sweep(&policy, dir.now);
+    assert!(sweep.removed.is_empty());
+    assert!(sweep.over_budget);
+    assert_untouched(&dir);
+}
+
+#[test]
+fn usage_is_reported_as_of_the_last_sweep() {
+    let dir = OutputDir::new("usage");
+    let janitor = dir.janitor();
+    assert_eq!(janitor.usage().last_sweep_at, None);
+
+    let policy = JanitorPolicy {
+        max_bytes: Some(10_000),
+        ..policy()
+    };
+    janitor.sweep(&policy, dir.now);
+    let usage = janitor.usage();
+    assert_eq!(usage.usage_bytes, 3842);
+    assert_eq!(usage.max_bytes, Some(10_000));
+    assert_eq!(usage.last_reclaimed_bytes, 1000);
+    assert!(!usage.over_budget);
+    assert!(usage.last_sweep_at.is_some());
+}
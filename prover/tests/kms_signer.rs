@@ -0,0 +1,272 @@
+// `KmsSigner` against a fake Cloud KMS serving a local secp256k1 key: the address
+// comes from the served public key, DER signatures come back low-s with the
+// recovery bit that recovers to that address, and the access token is sent on
+// every call. With `attestation`, it also signs EIP-712 attestations.
+
+use alloy_primitives::{keccak256, Address, B256, U256};
+use alloy_signer_local::PrivateKeySigner;
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{HeaderMap, Method, StatusCode, Uri};
+use axum::Router;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use google_cloud_token::TokenSource;
+use k256::ecdsa::signature::hazmat::PrehashSigner;
+use k256::ecdsa::{Signature, SigningKey};
+use k256::pkcs8::EncodePublicKey;
+use prover::kms::KmsSigner;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+
+const KEY_VERSION: &str =
+    "projects/test/locations/global/keyRings/prover/cryptoKeys/submitter/cryptoKeyVersions/1";
+const TOKEN: &str = "Bearer test-token";
+
+/// Half the secp256k1 group order: Ethereum rejects an `s` above it
+const HALF_ORDER: &str = "7fffffffffffffffffffffffffffffff5d576e7357a4501ddfe92f46681b20a0";
+
+struct FakeKms {
+    key: SigningKey,
+    /// Return the high-s form of every signature
+    high_s: bool,
+    /// Refuse calls without this authorization header
+    token: Option<&'static str>,
+}
+
+#[derive(Debug)]
+struct StaticToken;
+
+#[async_trait::async_trait]
+impl TokenSource for StaticToken {
+    async fn token(&self) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(TOKEN.to_string())
+    }
+}
+
+async fn handle(
+    State(kms): State<Arc<FakeKms>>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> (StatusCode, String) {
+    if let Some(token) = kms.token {
+        if headers.get("authorization").and_then(|v| v.to_str().ok()) != Some(token) {
+            return (StatusCode::UNAUTHORIZED, "missing token".to_string());
+        }
+    }
+    let path = uri.path();
+    if method == Method::GET && path == format!("/v1/{}/publicKey", KEY_VERSION) {
+        let der = k256::PublicKey::from(kms.key.verifying_key())
+            .to_public_key_der()
+            .unwrap();
+        let pem = format!(
+            "-----BEGIN PUBLIC KEY-----\n{}\n-----END PUBLIC KEY-----\n",
+            STANDARD.encode(der.as_bytes())
+        );
+        let body = json!({ "pem": pem, "algorithm": "EC_SIGN_SECP256K1_SHA256" });
+        return (StatusCode::OK, body.to_string());
+    }
+    if method == Method::POST && path == format!("/v1/{}:asymmetricSign", KEY_VERSION) {
+        let request: Value = serde_json::from_slice(&body).unwrap();
+        let digest = STANDARD
+            .decode(request["digest"]["sha256"].as_str().unwrap())
+            .unwrap();
+        let signature: Signature = kms.key.sign_prehash(&digest).unwrap();
+        let signature = if kms.high_s {
+            let (r, s) = signature.split_scalars();
+            Signature::from_scalars(r.to_bytes(), (-s).to_bytes()).unwrap()
+        } else {
+            signature
+        };
+        let body = json!({ "signature": STANDARD.encode(signature.to_der().as_bytes()) });
+        return (StatusCode::OK, body.to_string());
+    }
+    (
+        StatusCode::NOT_FOUND,
+        format!("no route for {} {}", method, path),
+    )
+}
+
+/// Serve a fake KMS holding `key` and return its endpoint
+async fn serve(kms: FakeKms) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let router = Router::new().fallback(handle).with_state(Arc::new(kms));
+    tokio::spawn(async move { axum::serve(listener, router).await });
+    format!("http://{}", addr)
+}
+
+fn local_key(seed: u8) -> PrivateKeySigner {
+    PrivateKeySigner::from_bytes(&B256::repeat_byte(seed)).unwrap()
+}
+
+#[tokio::test]
+async fn signatures_recover_to_the_kms_key() {
+    let local = local_key(0x42);
+    let endpoint = serve(FakeKms {
+        key: local.credential().clone(),
+        high_s: false,
+        token: None,
+    })
+    .await;
+    let signer = KmsSigner::with_endpoint(KEY_VERSION, &endpoint, None)
+        .await
+        .unwrap();
+    assert_eq!(signer.address(), local.address());
+
+    for message in ["first", "second", "third", "fourth"] {
+        let digest = keccak256(message);
+        let signature = signer.sign_digest(digest).await.unwrap();
+        assert_eq!(
+            signature.recover_address_from_prehash(&digest).unwrap(),
+            local.address()
+        );
+    }
+}
+
+#[tokio::test]
+async fn high_s_signatures_are_normalized() {
+    let local = local_key(0x07);
+    let endpoint = serve(FakeKms {
+        key: local.credential().clone(),
+        high_s: true,
+        token: None,
+    })
+    .await;
+    let signer = KmsSigner::with_endpoint(KEY_VERSION, &endpoint, None)
+        .await
+        .unwrap();
+
+    let half_order = U256::from_str_radix(HALF_ORDER, 16).unwrap();
+    for message in ["first", "second", "third", "fourth"] {
+        let digest = keccak256(message);
+        let signature = signer.sign_digest(digest).await.unwrap();
+        assert!(signature.s() <= half_order, "high s for {}", message);
+        assert_eq!(
+            signature.recover_address_from_prehash(&digest).unwrap(),
+            local.address()
+        );
+    }
+}
+
+#[tokio::test]
+async fn calls_carry_the_access_token() {
+    let local = local_key(0x11);
+    let endpoint = serve(FakeKms {
+        key: local.credential().clone(),
+        high_s: false,
+        token: Some(TOKEN),
+    })
+    .await;
+
+    let err = KmsSigner::with_endpoint(KEY_VERSION, &endpoint, None)
+        .await
+        .err()
+        .expect("unauthenticated call refused");
+    assert!(err.to_string().contains("401"), "{}", err);
+
+    let signer = KmsSigner::with_endpoint(KEY_VERSION, &endpoint, Some(Arc::new(StaticToken)))
+        .await
+        .unwrap();
+    let digest = keccak256("authenticated");
+    let signature = signer.sign_digest(digest).await.unwrap();
+    assert_eq!(
+        signature.recover_address_from_prehash(&digest).unwrap(),
+        local.address()
+    );
+}
+
+#[tokio::test]
+async fn unknown_key_versions_are_reported() {
+    let endpoint = serve(FakeKms {
+        key: local_key(0x11).credential().clone(),
+        high_s: false,
+        token: None,
+    })
+    .await;
+    let err = KmsSigner::with_endpoint("projects/test/missing", &endpoint, None)
+        .await
+        .err()
+        .expect("unknown key refused");
+    assert_eq!(err.error_type(), "KmsError");
+    assert!(err.to_string().contains("404"), "{}", err);
+}
+
+#[tokio::test]
+async fn transactions_are_signed_by_the_kms_key() {
+    use alloy_consensus::{SignableTransaction, TxLegacy};
+    use alloy_network::TxSigner;
+    use alloy_primitives::TxKind;
+
+    let local = local_key(0x42);
+    let endpoint = serve(FakeKms {
+        key: local.credential().clone(),
+        high_s: true,
+        token: None,
+    })
+    .await;
+    let signer = KmsSigner::with_endpoint(KEY_VERSION, &endpoint, None)
+        .await
+        .unwrap();
+
+    let mut tx = TxLegacy {
+        chain_id: Some(31337),
+        nonce: 3,
+        gas_price: 1_000_000_000,
+        gas_limit: 100_000,
+        to: TxKind::Call(Address::repeat_byte(0x22)),
+        ..Default::default()
+    };
+    let signature = signer.sign_transaction(&mut tx).await.unwrap();
+    assert_eq!(
+        signature
+            .recover_address_from_prehash(&tx.signature_hash())
+            .unwrap(),
+        local.address()
+    );
+}
+
+#[cfg(feature = "attestation")]
+#[tokio::test]
+async fn attestations_are_signed_by_the_kms_key() {
+    use prover::eip712::HumanIndexAttestation;
+    use prover::eip712::{verify_attestation, AttestationConfig, AttestationSigner};
+
+    let local = local_key(0x42);
+    let endpoint = serve(FakeKms {
+        key: local.credential().clone(),
+        high_s: true,
+        token: None,
+    })
+    .await;
+    let signer = KmsSigner::with_endpoint(KEY_VERSION, &endpoint, None)
+        .await
+        .unwrap();
+    assert_eq!(AttestationSigner::address(&signer), local.address());
+
+    let domain = AttestationConfig {
+        signing: "gcp-kms".to_string(),
+        signer_key: None,
+        kms_key: Some(KEY_VERSION.to_string()),
+        domain_name: "HumanIndex".to_string(),
+        domain_version: "1".to_string(),
+        chain_id: 8453,
+        verifying_contract: Address::repeat_byte(0x11),
+        ttl_secs: 3600,
+    }
+    .domain();
+    let attestation = HumanIndexAttestation {
+        bindingId: keccak256("user-42"),
+        humanIndex: 73,
+        vkeyHash: B256::repeat_byte(0xab),
+        expiry: 1_800_000_000,
+    };
+    let digest = alloy_sol_types::SolStruct::eip712_signing_hash(&attestation, &domain);
+    let signature = AttestationSigner::sign_digest(&signer, digest)
+        .await
+        .unwrap();
+    let signature = format!("0x{}", hex::encode(signature.as_bytes()));
+    assert!(verify_attestation(&attestation, &domain, &signature, local.address()).unwrap());
+}
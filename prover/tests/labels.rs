@@ -0,0 +1,100 @@
+// Cost attribution labels: a request's labels are echoed in its response and copied
+// onto the result message as `label.<key>` attributes, and labels out of bounds are
+// refused without breaking the result message.
+
+use prover::service::result_message;
+use prover::types::{check_labels, ProverRequest, ProverResponse, MAX_LABELS, MAX_LABEL_LEN};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+/// The example request with `labels`, which are not checked until published
+fn request(labels: &[(&str, &str)]) -> ProverRequest {
+    let labels = labels
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    ProverRequest {
+        labels: Some(labels),
+        ..ProverRequest::example()
+    }
+}
+
+/// The result of `request`, with its identifiers echoed as the service does
+fn response(request: &ProverRequest) -> ProverResponse {
+    let mut response =
+        ProverResponse::timeout(request.request_id.clone(), "slow".to_string(), None);
+    response.correlate(request);
+    response
+}
+
+fn labels(count: usize) -> BTreeMap<String, String> {
+    (0..count)
+        .map(|i| (format!("key-{}", i), "value".to_string()))
+        .collect()
+}
+
+#[test]
+fn labels_round_trip_onto_result_message_attributes() {
+    let request = request(&[("team", "risk"), ("env", "prod")]);
+    let response = response(&request);
+    assert_eq!(response.labels, request.labels);
+
+    for schema_version in [1, 2] {
+        let message = result_message(&response, schema_version, false).unwrap();
+        assert_eq!(message.attributes["label.team"], "risk");
+        assert_eq!(message.attributes["label.env"], "prod");
+        assert_eq!(
+            message.attributes["schema_version"],
+            schema_version.to_string()
+        );
+        assert_eq!(message.attributes.len(), 3);
+    }
+
+    let published: Value = serde_json::from_slice(&response.encode(2).unwrap()).unwrap();
+    assert_eq!(
+        published["labels"],
+        json!({ "team": "risk", "env": "prod" })
+    );
+}
+
+#[test]
+fn results_without_labels_have_no_label_attributes() {
+    let request = ProverRequest::example();
+    assert!(request.labels.is_none());
+    let message = result_message(&response(&request), 2, false).unwrap();
+    assert!(message
+        .attributes
+        .keys()
+        .all(|key| !key.starts_with("label.")));
+}
+
+#[test]
+fn labels_are_bounded() {
+    assert_eq!(check_labels(&labels(MAX_LABELS)), Ok(()));
+    let err = check_labels(&labels(MAX_LABELS + 1)).unwrap_err();
+    assert!(err.contains("more than 16 labels"), "{}", err);
+
+    let long = "a".repeat(MAX_LABEL_LEN);
+    let bounded = BTreeMap::from([(long.clone(), long.clone()), ("empty".into(), "".into())]);
+    assert_eq!(check_labels(&bounded), Ok(()));
+
+    for (key, value) in [
+        ("a".repeat(MAX_LABEL_LEN + 1), "v".to_string()),
+        ("k".to_string(), "a".repeat(MAX_LABEL_LEN + 1)),
+        ("".to_string(), "v".to_string()),
+        ("1st".to_string(), "v".to_string()),
+        ("Team".to_string(), "v".to_string()),
+        ("team".to_string(), "Risk Ops".to_string()),
+        ("goog.team".to_string(), "v".to_string()),
+    ] {
+        let labels = BTreeMap::from([(key.clone(), value.clone())]);
+        assert!(check_labels(&labels).is_err(), "{:?}={:?}", key, value);
+    }
+}
+
+#[test]
+fn out_of_bounds_labels_are_not_echoed() {
+    let request = request(&[("team", "Risk Ops")]);
+    let message = result_message(&response(&request), 2, false).unwrap();
+    assert!(!message.attributes.contains_key("label.team"));
+}
@@ -0,0 +1,113 @@
+// Memory-based proof concurrency: `MAX_CONCURRENT_PROOFS=auto` sizes to the proofs
+// that fit in total memory, clamped to 1..=MAX_AUTO_CONCURRENT_PROOFS, from the
+// figures in /proc/meminfo; before each proof the service waits, bounded, for
+// enough available memory and proceeds once it is there or the wait runs out.
+
+use prover::error::ServiceError;
+use prover::memory::{
+    auto_concurrency, parse_meminfo, wait_for_available, MemoryInfo, MAX_AUTO_CONCURRENT_PROOFS,
+};
+use std::time::Duration;
+
+const GIB: u64 = 1024 * 1024 * 1024;
+const POLL: Duration = Duration::from_millis(10);
+
+#[test]
+fn concurrency_is_total_memory_over_memory_per_proof() {
+    assert_eq!(auto_concurrency(64 * GIB, 16), 4);
+    assert_eq!(auto_concurrency(64 * GIB, 10), 6);
+    // A proof does not start on a fraction of its memory
+    assert_eq!(auto_concurrency(64 * GIB - 1, 16), 3);
+    assert_eq!(auto_concurrency(48 * GIB, 16), 3);
+}
+
+#[test]
+fn concurrency_is_clamped() {
+    // An undersized host still proves, one at a time
+    assert_eq!(auto_concurrency(8 * GIB, 16), 1);
+    assert_eq!(auto_concurrency(0, 16), 1);
+    // A large host stops at the upper bound
+    assert_eq!(auto_concurrency(1024 * GIB, 16), MAX_AUTO_CONCURRENT_PROOFS);
+    assert_eq!(auto_concurrency(u64::MAX, 1), MAX_AUTO_CONCURRENT_PROOFS);
+    // Zero memory per proof is treated as 1 GiB, not divided by
+    assert_eq!(auto_concurrency(4 * GIB, 0), 4);
+}
+
+#[test]
+fn meminfo_figures_are_read_in_bytes() {
+    let meminfo = "MemTotal:       65536000 kB\n\
+                   MemFree:         1024000 kB\n\
+                   MemAvailable:   32768000 kB\n\
+                   Buffers:          204800 kB\n";
+    assert_eq!(
+        parse_meminfo(meminfo).unwrap(),
+        MemoryInfo {
+            total_bytes: 65_536_000 * 1024,
+            available_bytes: 32_768_000 * 1024,
+        }
+    );
+    let info = parse_meminfo(meminfo).unwrap();
+    assert_eq!(auto_concurrency(info.total_bytes, 16), 3);
+}
+
+#[test]
+fn meminfo_without_available_memory_is_an_error() {
+    let err =
+        parse_meminfo("MemTotal:       65536000 kB\nMemFree:         1024000 kB\n").unwrap_err();
+    assert!(err.to_string().contains("MemAvailable"), "{}", err);
+}
+
+#[tokio::test]
+async fn proof_starts_at_once_with_enough_memory() {
+    let mut reads = 0;
+    let ready = wait_for_available("sizing-1", 16 * GIB, Duration::from_secs(5), POLL, || {
+        reads += 1;
+        Ok(32 * GIB)
+    })
+    .await;
+    assert!(ready);
+    assert_eq!(reads, 1);
+}
+
+#[tokio::test]
+async fn proof_is_delayed_until_memory_frees_up() {
+    let mut readings = vec![4 * GIB, 8 * GIB, 16 * GIB].into_iter();
+    let mut reads = 0;
+    let ready = wait_for_available("sizing-2", 16 * GIB, Duration::from_secs(5), POLL, || {
+        reads += 1;
+        Ok(readings.next().unwrap())
+    })
+    .await;
+    assert!(ready);
+    assert_eq!(reads, 3);
+}
+
+#[tokio::test]
+async fn wait_is_bounded() {
+    let started = tokio::time::Instant::now();
+    let mut reads = 0;
+    let ready = wait_for_available(
+        "sizing-3",
+        16 * GIB,
+        Duration::from_millis(100),
+        POLL,
+        || {
+            reads += 1;
+            Ok(GIB)
+        },
+    )
+    .await;
+    assert!(!ready);
+    assert!(reads > 1);
+    assert!(started.elapsed() >= Duration::from_millis(100));
+    assert!(started.elapsed() < Duration::from_secs(2));
+}
+
+#[tokio::test]
+async fn unreadable_memory_skips_the_wait() {
+    let ready = wait_for_available("sizing-4", 16 * GIB, Duration::from_secs(5), POLL, || {
+        Err(ServiceError::Config("no /proc".to_string()))
+    })
+    .await;
+    assert!(ready);
+}
@@ -0,0 +1,113 @@
+// `/metrics` reflects the results recorded as the service produces them: one
+// success and one failure show up as counters by status and `error_type`, in the
+// duration histogram, and alongside the in-flight gauge and queue depths.
+
+use prover::mock::{MockProofGenerator, FAIL_PROOF_PREFIX};
+use prover::prometheus::{self, InFlight, MetricsState};
+use prover::prover::{ProofBackend, ProofContext};
+use prover::scheduler::{Lanes, Scheduler};
+use prover::types::{ProofError, ProofMetrics, ProverRequest, ProverResponse};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Semaphore;
+
+fn request(request_id: &str) -> ProverRequest {
+    serde_json::from_value(json!({
+        "request_id": request_id,
+        "verification_results": { "recaptcha_score": 8000, "sms_verified": 1, "bio_verified": 1 },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 },
+    }))
+    .unwrap()
+}
+
+fn metrics(duration_ms: u64) -> ProofMetrics {
+    ProofMetrics {
+        received_at: "2026-10-16T09:41:07Z".to_string(),
+        started_at: "2026-10-16T09:41:07Z".to_string(),
+        completed_at: "2026-10-16T09:41:09Z".to_string(),
+        duration_ms,
+        attempts: Some(1),
+        queue_wait_ms: None,
+        usage: Default::default(),
+        cost_units: None,
+        progress: None,
+    }
+}
+
+/// Prove `request_id` with the mock and build the response as the service does
+fn prove(request_id: &str) -> ProverResponse {
+    let backend = MockProofGenerator::new(Duration::ZERO, Duration::from_secs(60));
+    let context = ProofContext::new(Instant::now() + Duration::from_secs(60), 0);
+    match backend.generate_proof(request(request_id), &context) {
+        Ok(proof_data) => {
+            ProverResponse::success(request_id.to_string(), proof_data, metrics(2000))
+        }
+        Err(e) => ProverResponse::failed(
+            request_id.to_string(),
+            ProofError {
+                error_type: e.error_type(),
+                message: e.to_string(),
+                details: None,
+            },
+            Some(metrics(500)),
+        ),
+    }
+}
+
+async fn scrape(addr: std::net::SocketAddr) -> String {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+    response
+}
+
+#[tokio::test]
+async fn scrape_reflects_one_success_and_one_failure() {
+    let handle = prometheus::install().unwrap();
+    let scheduler = Scheduler::start(
+        Arc::new(Semaphore::new(2)),
+        Lanes {
+            capacity: 2,
+            interactive_reserved: 0,
+            batch_promotion: None,
+            queue_wait_warning: None,
+        },
+    );
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let router = prometheus::router(Arc::new(MetricsState::new(handle, scheduler)));
+    tokio::spawn(async move { axum::serve(listener, router).await });
+
+    let in_flight = InFlight::start();
+    prometheus::record_proof(&prove("metrics-success"));
+    let failed = prove(&format!("{}metrics", FAIL_PROOF_PREFIX));
+    prometheus::record_proof(&failed);
+    let error_type = &failed.error.as_ref().unwrap().error_type;
+
+    let body = scrape(addr).await;
+    assert!(
+        body.contains(r#"prover_proofs_total{status="success",error_type="none"} 1"#),
+        "{}",
+        body
+    );
+    assert!(body.contains(&format!(
+        r#"prover_proofs_total{{status="failed",error_type="{}"}} 1"#,
+        error_type
+    )));
+    assert!(body.contains(r#"prover_proof_duration_seconds_count{status="success"} 1"#));
+    assert!(body.contains(r#"prover_proof_duration_seconds_sum{status="failed"} 0.5"#));
+    assert!(body.contains(r#"prover_proof_duration_seconds_bucket{status="success",le="5"} 1"#));
+    assert!(body.contains("prover_in_flight 1"));
+    assert!(body.contains(r#"prover_queue_depth{lane="normal"} 0"#));
+
+    drop(in_flight);
+    assert!(scrape(addr).await.contains("prover_in_flight 0"));
+}
@@ -0,0 +1,268 @@
+// Metrics snapshots: completions counted by outcome, proof type and client between
+// snapshots, snapshot files read back by the range their periods end in, and the
+// report's rates, means, percentile buckets and hourly rollup.
+
+use chrono::{DateTime, TimeDelta, TimeZone, Utc};
+use prover::metrics_log::{
+    next_snapshot_at, read_snapshots, MetricsLog, MetricsReport, MetricsSnapshot, SeriesKey,
+    NO_CLIENT,
+};
+use prover::types::{ProofError, ProofMetrics, ProverResponse, ResourceUsage};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+fn at(hour: u32, min: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 10, 16, hour, min, 0).unwrap()
+}
+
+fn metrics(duration_ms: u64) -> ProofMetrics {
+    ProofMetrics {
+        received_at: "2026-10-16T12:00:00Z".to_string(),
+        started_at: "2026-10-16T12:00:00Z".to_string(),
+        completed_at: "2026-10-16T12:00:00Z".to_string(),
+        duration_ms,
+        attempts: Some(1),
+        queue_wait_ms: None,
+        usage: ResourceUsage::default(),
+        cost_units: None,
+        progress: None,
+    }
+}
+
+fn success_with(duration_ms: u64, profile: Option<&str>) -> ProverResponse {
+    let proof_data = serde_json::from_value(json!({
+        "proof": "",
+        "public_inputs": "",
+        "verification_key": "",
+        "human_index": 50,
+        "profile": profile,
+    }))
+    .unwrap();
+    ProverResponse::success("ok".to_string(), proof_data, metrics(duration_ms))
+}
+
+fn success(duration_ms: u64) -> ProverResponse {
+    success_with(duration_ms, None)
+}
+
+fn failure() -> ProverResponse {
+    let error = ProofError {
+        error_type: "ProofGenerationError".to_string(),
+        message: "failed".to_string(),
+        details: None,
+    };
+    ProverResponse::failed("failed".to_string(), error, None)
+}
+
+fn key(outcome: &str, proof_type: &str, client_id: &str) -> SeriesKey {
+    SeriesKey {
+        outcome: outcome.to_string(),
+        proof_type: proof_type.to_string(),
+        client_id: client_id.to_string(),
+    }
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "prover-metrics-log-{}-{}",
+        name,
+        std::process::id()
+    ));
+    std::fs::remove_dir_all(&dir).ok();
+    dir
+}
+
+fn log(dir: &Path, from: DateTime<Utc>) -> MetricsLog {
+    MetricsLog::new(dir, "evm", from)
+}
+
+#[test]
+fn completions_are_counted_by_outcome_proof_type_and_client() {
+    let log = log(Path::new("unused"), at(10, 0));
+    log.record(&success(1_000), Some("a"));
+    log.record(&success(200_000), Some("a"));
+    log.record(&success(4_000), Some("b"));
+    log.record(&success_with(2_000, Some("fast")), Some("a"));
+    log.record(&failure(), Some("a"));
+    log.record(
+        &ProverResponse::timeout("slow".to_string(), String::new(), Some(metrics(950_000))),
+        None,
+    );
+    log.record_error();
+
+    let snapshot = log.take(at(10, 15));
+    assert_eq!(snapshot.from, at(10, 0).to_rfc3339());
+    assert_eq!(snapshot.to, at(10, 15).to_rfc3339());
+    let series: Vec<_> = snapshot
+        .series
+        .iter()
+        .map(|series| (series.key.clone(), series.counts.count))
+        .collect();
+    assert_eq!(
+        series,
+        [
+            (key("failed", "evm", NO_CLIENT), 1),
+            (key("failed", "evm", "a"), 1),
+            (key("success", "evm", "a"), 2),
+            (key("success", "evm", "b"), 1),
+            (key("success", "fast", "a"), 1),
+            (key("timeout", "evm", NO_CLIENT), 1),
+        ]
+    );
+
+    // 1s in the first bucket, 200s in the one up to 300s; failures report no duration
+    let evm_a = &snapshot.series[2].counts;
+    assert_eq!(evm_a.duration_ms_sum, 201_000);
+    assert_eq!(evm_a.timed(), 2);
+    assert_eq!(evm_a.duration_buckets[0], 1);
+    let bucket_300 = snapshot
+        .duration_buckets_secs
+        .iter()
+        .position(|b| *b == 300.0);
+    assert_eq!(evm_a.duration_buckets[bucket_300.unwrap()], 1);
+    assert_eq!(snapshot.series[1].counts.timed(), 0);
+    // Above the last bound
+    let timeout = &snapshot.series[5].counts;
+    assert_eq!(timeout.duration_buckets.last(), Some(&1));
+
+    // The next snapshot starts where this one ended, with nothing counted
+    let next = log.take(at(10, 30));
+    assert_eq!(next.from, at(10, 15).to_rfc3339());
+    assert!(next.series.is_empty());
+}
+
+#[test]
+fn snapshots_are_read_back_by_the_range_their_periods_end_in() {
+    let dir = temp_dir("read");
+    let log = log(&dir, at(10, 0));
+    log.record(&success(1_000), Some("a"));
+    let first = log.snapshot(at(10, 15)).unwrap();
+    log.record(&success(2_000), Some("a"));
+    log.snapshot(at(10, 30)).unwrap();
+    // A period without completions is written too
+    log.snapshot(at(10, 45)).unwrap();
+    std::fs::write(dir.join("notes.txt"), "not a snapshot").unwrap();
+
+    let written: MetricsSnapshot = serde_json::from_slice(&std::fs::read(&first).unwrap()).unwrap();
+    assert_eq!(written.series.len(), 1);
+
+    let ends = |from, to| -> Vec<String> {
+        read_snapshots(&dir, from, to)
+            .unwrap()
+            .into_iter()
+            .map(|snapshot| snapshot.to)
+            .collect()
+    };
+    assert_eq!(
+        ends(at(10, 0), at(10, 30)),
+        [at(10, 15).to_rfc3339(), at(10, 30).to_rfc3339()]
+    );
+    assert_eq!(
+        ends(at(10, 15), at(11, 0)),
+        [at(10, 30).to_rfc3339(), at(10, 45).to_rfc3339()]
+    );
+    assert!(ends(at(11, 0), at(12, 0)).is_empty());
+
+    std::fs::write(dir.join("broken.json"), "{").unwrap();
+    let err = read_snapshots(&dir, at(10, 0), at(11, 0)).unwrap_err();
+    assert!(err.to_string().contains("broken.json"), "{}", err);
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn report_adds_up_rates_means_percentiles_and_hours() {
+    let log = log(Path::new("unused"), at(10, 0));
+    for duration_ms in [1_000, 10_000, 100_000] {
+        log.record(&success(duration_ms), Some("a"));
+    }
+    log.record(&failure(), Some("a"));
+    let first = log.take(at(10, 30));
+    log.record(&success(1_000_000), Some("a"));
+    let second = log.take(at(11, 0));
+    log.record(&success(20_000), Some("b"));
+    log.record(&success(20_000), Some("b"));
+    let third = log.take(at(11, 30));
+
+    let report = MetricsReport::of(&[first, second, third]).unwrap();
+    assert_eq!(report.snapshots, 3);
+    assert_eq!(report.covered_secs, 90 * 60);
+
+    let success_a = report
+        .series
+        .iter()
+        .find(|series| series.key == key("success", "evm", "a"))
+        .unwrap();
+    assert_eq!(success_a.count, 4);
+    assert!((success_a.per_hour - 4.0 / 1.5).abs() < 1e-9);
+    assert_eq!(success_a.mean_ms, Some(1_111_000 / 4));
+    // Nearest rank: the 2nd of 4 is 10s, in the bucket up to 15s; the 4th is 1000s,
+    // above the last bucket
+    assert_eq!(success_a.p50_secs, Some(15.0));
+    assert_eq!(success_a.p90_secs, Some(f64::INFINITY));
+    assert_eq!(success_a.p99_secs, Some(f64::INFINITY));
+
+    let failed_a = report
+        .series
+        .iter()
+        .find(|series| series.key == key("failed", "evm", "a"))
+        .unwrap();
+    assert_eq!(failed_a.count, 1);
+    assert_eq!(failed_a.mean_ms, None);
+    assert_eq!(failed_a.p50_secs, None);
+
+    let success_b = report
+        .series
+        .iter()
+        .find(|series| series.key == key("success", "evm", "b"))
+        .unwrap();
+    assert_eq!(success_b.mean_ms, Some(20_000));
+    assert_eq!(success_b.p99_secs, Some(30.0));
+
+    // Snapshots count in the hour their period started in
+    let hours: Vec<_> = report
+        .hours
+        .iter()
+        .map(|hour| (hour.hour.clone(), hour.count, hour.succeeded, hour.mean_ms))
+        .collect();
+    assert_eq!(
+        hours,
+        [
+            (at(10, 0).to_rfc3339(), 5, 4, Some(1_111_000 / 4)),
+            (at(11, 0).to_rfc3339(), 2, 2, Some(20_000)),
+        ]
+    );
+
+    let table = report.to_string();
+    assert!(table.starts_with("3 snapshots covering 1.50h"), "{}", table);
+    assert!(table.contains("<=15s"), "{}", table);
+    assert!(table.contains(">900s"), "{}", table);
+}
+
+#[test]
+fn report_of_no_snapshots_is_empty() {
+    let report = MetricsReport::of(&[]).unwrap();
+    assert_eq!(report.snapshots, 0);
+    assert_eq!(report.covered_secs, 0);
+    assert!(report.series.is_empty() && report.hours.is_empty());
+}
+
+#[test]
+fn snapshots_with_other_duration_buckets_are_refused() {
+    let log = log(Path::new("unused"), at(10, 0));
+    log.record(&success(1_000), Some("a"));
+    let first = log.take(at(10, 15));
+    let mut second = log.take(at(10, 30));
+    second.duration_buckets_secs = vec![1.0, 10.0];
+
+    let err = MetricsReport::of(&[first, second]).unwrap_err();
+    assert!(err.to_string().contains("duration buckets"), "{}", err);
+}
+
+#[test]
+fn snapshots_end_on_multiples_of_the_interval() {
+    let quarter = TimeDelta::minutes(15);
+    let now = at(10, 7) + TimeDelta::seconds(30);
+    assert_eq!(next_snapshot_at(now, quarter), at(10, 15));
+    assert_eq!(next_snapshot_at(at(10, 15), quarter), at(10, 30));
+    assert_eq!(next_snapshot_at(at(10, 59), TimeDelta::hours(1)), at(11, 0));
+}
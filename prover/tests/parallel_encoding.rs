@@ -0,0 +1,103 @@
+// `ARTIFACT_ENCODING=parallel` encodes the proof, public values and verifying key of
+// inputs.json at once; the `ProofData` must be byte-identical to the sequential
+// encoding's. Each fixture proof is turned back into the inputs.json it came from
+// and read both ways.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use prover::fixtures::{golden_vectors, Fixtures};
+use prover::prover::{read_proof_artifacts, ArtifactEncoding, ProofJsonLayout};
+use prover::types::ProofData;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+/// The fixture proofs: a mock proof per golden vector, and the Groth16 proof
+fn fixture_proofs() -> Vec<(String, ProofData)> {
+    let fixtures = Fixtures::workspace().unwrap();
+    let mut proofs: Vec<_> = golden_vectors()
+        .unwrap()
+        .into_iter()
+        .map(|vector| {
+            let proof = fixtures.mock_proof(&vector.name).unwrap();
+            (vector.name, proof)
+        })
+        .collect();
+    let groth16 = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/groth16_proof.json");
+    let groth16 = serde_json::from_str(&std::fs::read_to_string(groth16).unwrap()).unwrap();
+    proofs.push(("groth16".to_string(), groth16));
+    proofs
+}
+
+/// Write the inputs.json `proof_data` was encoded from, for fixture `name`
+fn write_inputs(name: &str, proof_data: &ProofData) -> PathBuf {
+    let decode = |field: &str| String::from_utf8(STANDARD.decode(field).unwrap()).unwrap();
+    let proof: Value = serde_json::from_str(&decode(&proof_data.proof)).unwrap();
+    let inputs = json!({
+        "proof": proof,
+        "publicValues": decode(&proof_data.public_inputs),
+        "riscvVKey": decode(&proof_data.verification_key),
+    });
+
+    let dir = std::env::temp_dir().join(format!(
+        "prover-parallel-encoding-{}-{}",
+        name,
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("inputs.json");
+    std::fs::write(&path, serde_json::to_vec_pretty(&inputs).unwrap()).unwrap();
+    path
+}
+
+fn read(path: &Path, encoding: ArtifactEncoding, proof_data: &ProofData) -> Vec<u8> {
+    let weights_commitment: [u8; 32] =
+        hex::decode(proof_data.weights_commitment.trim_start_matches("0x"))
+            .unwrap()
+            .try_into()
+            .unwrap();
+    let read = read_proof_artifacts(
+        path,
+        u64::MAX,
+        ProofJsonLayout::PicoV1,
+        encoding,
+        proof_data.human_index,
+        &weights_commitment,
+    )
+    .unwrap();
+    serde_json::to_vec(&read).unwrap()
+}
+
+#[test]
+fn parallel_encoding_is_byte_identical_to_sequential() {
+    let proofs = fixture_proofs();
+    assert!(proofs.len() > 2);
+    for (name, proof_data) in proofs {
+        let path = write_inputs(&name, &proof_data);
+        let sequential = read(&path, ArtifactEncoding::Sequential, &proof_data);
+        let parallel = read(&path, ArtifactEncoding::Parallel, &proof_data);
+        assert_eq!(parallel, sequential, "{}", name);
+
+        // Both give back the fixture itself
+        assert_eq!(
+            sequential,
+            serde_json::to_vec(&proof_data).unwrap(),
+            "{}",
+            name
+        );
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+}
+
+#[test]
+fn the_encoding_is_read_from_its_setting() {
+    assert_eq!(
+        "parallel".parse::<ArtifactEncoding>().unwrap(),
+        ArtifactEncoding::Parallel
+    );
+    assert_eq!(
+        "Sequential".parse::<ArtifactEncoding>().unwrap(),
+        ArtifactEncoding::Sequential
+    );
+    assert_eq!(ArtifactEncoding::default(), ArtifactEncoding::Parallel);
+    let err = "threads".parse::<ArtifactEncoding>().unwrap_err();
+    assert!(err.to_string().contains("ARTIFACT_ENCODING"), "{}", err);
+}
@@ -0,0 +1,365 @@
+// Runs mixed-priority requests through the scheduler and the mock backend, and
+// checks the order proofs start in, the interactive reservation and batch promotion,
+// and the queue wait reported for each permit.
+
+use prover::mock::MockProofGenerator;
+use prover::prover::{ProofBackend, ProofContext};
+use prover::scheduler::{LaneDepths, Lanes, Scheduler, TenantPermit, Ticket};
+use prover::types::{Priority, ProverRequest};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
+
+fn start(
+    capacity: u32,
+    interactive_reserved: u32,
+    batch_promotion: Option<Duration>,
+) -> Arc<Scheduler> {
+    let semaphore = Arc::new(Semaphore::new(capacity as usize));
+    Scheduler::start(
+        semaphore,
+        Lanes {
+            capacity,
+            interactive_reserved,
+            batch_promotion,
+            queue_wait_warning: None,
+        },
+    )
+}
+
+fn request(request_id: &str, priority: Priority) -> ProverRequest {
+    let mut request: ProverRequest = serde_json::from_value(json!({
+        "request_id": request_id,
+        "verification_results": { "recaptcha_score": 9000, "sms_verified": 1, "bio_verified": 0 },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 },
+    }))
+    .unwrap();
+    request.priority = Some(priority);
+    request
+}
+
+/// Wait for a permit like the service does, then prove with the mock backend
+///
+/// Request IDs are pushed to `started` as their proofs start.
+fn submit(
+    scheduler: &Arc<Scheduler>,
+    started: &Arc<Mutex<Vec<String>>>,
+    request: ProverRequest,
+    proof_duration: Duration,
+) -> JoinHandle<()> {
+    let ticket = scheduler.ticket();
+    submit_in_turn(
+        scheduler,
+        ticket,
+        started,
+        &Arc::default(),
+        request,
+        proof_duration,
+    )
+}
+
+/// `submit` for a request received when `ticket` was taken
+///
+/// Request IDs are also pushed to `completed` as their proofs finish.
+fn submit_in_turn(
+    scheduler: &Arc<Scheduler>,
+    ticket: Ticket,
+    started: &Arc<Mutex<Vec<String>>>,
+    completed: &Arc<Mutex<Vec<String>>>,
+    request: ProverRequest,
+    proof_duration: Duration,
+) -> JoinHandle<()> {
+    let scheduler = scheduler.clone();
+    let started = started.clone();
+    let completed = completed.clone();
+    tokio::spawn(async move {
+        let priority = request.priority.unwrap_or_default();
+        let _permit = scheduler
+            .acquire_in_turn(ticket, "", priority, 1)
+            .await
+            .expect("permit granted");
+        let request_id = request.request_id.clone();
+        started.lock().unwrap().push(request_id.clone());
+
+        let backend = MockProofGenerator::new(proof_duration, Duration::from_secs(60));
+        tokio::task::spawn_blocking(move || {
+            let context = ProofContext::new(Instant::now() + Duration::from_secs(60), 0);
+            backend
+                .generate_proof(request, &context)
+                .expect("mock proof")
+        })
+        .await
+        .unwrap();
+        completed.lock().unwrap().push(request_id);
+    })
+}
+
+/// Take the only permit, so later requests queue up
+async fn block(scheduler: &Scheduler) -> TenantPermit {
+    scheduler.acquire("", Priority::Normal, 1).await.unwrap()
+}
+
+async fn wait_for_depths(scheduler: &Scheduler, depths: LaneDepths) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while scheduler.depths() != depths {
+        assert!(
+            Instant::now() < deadline,
+            "depths stuck at {:?}, expected {:?}",
+            scheduler.depths(),
+            depths
+        );
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+}
+
+#[tokio::test]
+async fn lanes_start_in_priority_order() {
+    let scheduler = start(1, 0, None);
+    let started = Arc::new(Mutex::new(Vec::new()));
+    let blocker = block(&scheduler).await;
+
+    let mut tasks = Vec::new();
+    let submissions = [
+        ("batch-1", Priority::Batch),
+        ("normal-1", Priority::Normal),
+        ("interactive-1", Priority::Interactive),
+        ("batch-2", Priority::Batch),
+        ("normal-2", Priority::Normal),
+        ("interactive-2", Priority::Interactive),
+    ];
+    for (queued, (request_id, priority)) in submissions.into_iter().enumerate() {
+        tasks.push(submit(
+            &scheduler,
+            &started,
+            request(request_id, priority),
+            Duration::from_millis(10),
+        ));
+        // Enqueue one at a time so arrival order is fixed
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while {
+            let depths = scheduler.depths();
+            depths.interactive + depths.normal + depths.batch
+        } <= queued
+        {
+            assert!(Instant::now() < deadline, "request not queued");
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    }
+    wait_for_depths(
+        &scheduler,
+        LaneDepths {
+            interactive: 2,
+            normal: 2,
+            batch: 2,
+        },
+    )
+    .await;
+
+    drop(blocker);
+    for task in tasks {
+        task.await.unwrap();
+    }
+    assert_eq!(
+        *started.lock().unwrap(),
+        [
+            "interactive-1",
+            "interactive-2",
+            "normal-1",
+            "normal-2",
+            "batch-1",
+            "batch-2"
+        ]
+    );
+    assert_eq!(scheduler.depths(), LaneDepths::default());
+}
+
+#[tokio::test]
+async fn reserved_permits_wait_for_interactive() {
+    let scheduler = start(2, 1, None);
+    let started = Arc::new(Mutex::new(Vec::new()));
+
+    // Three long normal proofs get only the one unreserved permit
+    let normal: Vec<_> = (1..=3)
+        .map(|i| {
+            submit(
+                &scheduler,
+                &started,
+                request(&format!("normal-{}", i), Priority::Normal),
+                Duration::from_millis(500),
+            )
+        })
+        .collect();
+    wait_for_depths(
+        &scheduler,
+        LaneDepths {
+            interactive: 0,
+            normal: 2,
+            batch: 0,
+        },
+    )
+    .await;
+    assert_eq!(started.lock().unwrap().len(), 1);
+
+    // An interactive request starts on the reserved permit at once
+    let interactive = submit(
+        &scheduler,
+        &started,
+        request("interactive-1", Priority::Interactive),
+        Duration::from_millis(10),
+    );
+    tokio::time::timeout(Duration::from_millis(300), interactive)
+        .await
+        .expect("interactive request starts without waiting for normal proofs")
+        .unwrap();
+    assert_eq!(started.lock().unwrap()[1], "interactive-1");
+    assert_eq!(
+        started.lock().unwrap().len(),
+        2,
+        "normal proofs stay off the reserved permit"
+    );
+
+    for task in normal {
+        task.await.unwrap();
+    }
+    assert_eq!(started.lock().unwrap().len(), 4);
+}
+
+#[tokio::test]
+async fn waiting_batch_is_promoted() {
+    let scheduler = start(1, 0, Some(Duration::from_millis(100)));
+    let started = Arc::new(Mutex::new(Vec::new()));
+    let blocker = block(&scheduler).await;
+
+    let batch = submit(
+        &scheduler,
+        &started,
+        request("batch-1", Priority::Batch),
+        Duration::from_millis(10),
+    );
+    wait_for_depths(
+        &scheduler,
+        LaneDepths {
+            interactive: 0,
+            normal: 0,
+            batch: 1,
+        },
+    )
+    .await;
+    tokio::time::sleep(Duration::from_millis(150)).await;
+
+    // The promoted batch request now counts as normal and arrived first
+    let normal = submit(
+        &scheduler,
+        &started,
+        request("normal-1", Priority::Normal),
+        Duration::from_millis(10),
+    );
+    wait_for_depths(
+        &scheduler,
+        LaneDepths {
+            interactive: 0,
+            normal: 2,
+            batch: 0,
+        },
+    )
+    .await;
+
+    drop(blocker);
+    batch.await.unwrap();
+    normal.await.unwrap();
+    assert_eq!(*started.lock().unwrap(), ["batch-1", "normal-1"]);
+}
+
+#[tokio::test]
+async fn lanes_complete_in_submission_order() {
+    let scheduler = start(2, 0, None);
+    let started = Arc::new(Mutex::new(Vec::new()));
+    let completed = Arc::new(Mutex::new(Vec::new()));
+    let blocker = scheduler.acquire("", Priority::Normal, 2).await.unwrap();
+
+    // Received in order, alternating lanes, but reaching the scheduler in reverse
+    // as concurrently handled messages may
+    let submissions: Vec<_> = (0..10)
+        .map(|i| {
+            let (lane, priority) = match i % 2 {
+                0 => ("normal", Priority::Normal),
+                _ => ("batch", Priority::Batch),
+            };
+            (
+                scheduler.ticket(),
+                request(&format!("{}-{}", lane, i), priority),
+            )
+        })
+        .collect();
+    let mut tasks = Vec::new();
+    for (i, (ticket, request)) in submissions.into_iter().enumerate().rev() {
+        // Later requests prove longer, so two running at once finish in start order
+        tasks.push(submit_in_turn(
+            &scheduler,
+            ticket,
+            &started,
+            &completed,
+            request,
+            Duration::from_millis(20 + 20 * i as u64),
+        ));
+        tokio::task::yield_now().await;
+    }
+    wait_for_depths(
+        &scheduler,
+        LaneDepths {
+            interactive: 0,
+            normal: 5,
+            batch: 5,
+        },
+    )
+    .await;
+
+    drop(blocker);
+    for task in tasks {
+        task.await.unwrap();
+    }
+    let normal = ["normal-0", "normal-2", "normal-4", "normal-6", "normal-8"];
+    let batch = ["batch-1", "batch-3", "batch-5", "batch-7", "batch-9"];
+    assert_eq!(*started.lock().unwrap(), [normal, batch].concat());
+    let completed = completed.lock().unwrap();
+    for lane in [normal, batch] {
+        let in_lane: Vec<&str> = completed
+            .iter()
+            .map(String::as_str)
+            .filter(|request_id| lane.contains(request_id))
+            .collect();
+        assert_eq!(in_lane, lane);
+    }
+}
+
+#[tokio::test]
+async fn long_queue_waits_are_counted() {
+    let semaphore = Arc::new(Semaphore::new(1));
+    let scheduler = Scheduler::start(
+        semaphore,
+        Lanes {
+            capacity: 1,
+            interactive_reserved: 0,
+            batch_promotion: None,
+            queue_wait_warning: Some(Duration::from_millis(50)),
+        },
+    );
+
+    // The wait runs from the ticket, not from the call to acquire
+    let ticket = scheduler.ticket();
+    tokio::time::sleep(Duration::from_millis(80)).await;
+    let permit = scheduler
+        .acquire_in_turn(ticket, "", Priority::Normal, 1)
+        .await
+        .unwrap();
+    assert!(permit.queue_wait() >= Duration::from_millis(80));
+    assert!(permit.long_wait());
+    assert_eq!(scheduler.long_waits(), 1);
+    drop(permit);
+
+    let permit = scheduler.acquire("", Priority::Normal, 1).await.unwrap();
+    assert!(!permit.long_wait());
+    assert_eq!(scheduler.long_waits(), 1);
+}
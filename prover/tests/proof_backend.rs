@@ -0,0 +1,192 @@
+// The request pipeline against the `ProofBackend` trait, with a backend defined
+// here around the mock: every request reaches the backend once, as parsed, its
+// proof comes back as a successful response, and a backend error or timeout
+// becomes a failed response rather than failing the pipeline.
+
+use chrono::Utc;
+use human_index_lib::calculate_human_index;
+use prover::config::Config;
+use prover::error::ServiceError;
+use prover::mock::{MockProofGenerator, FAIL_IO_PREFIX, FAIL_PROOF_PREFIX, FAIL_TIMEOUT_PREFIX};
+use prover::prover::{ProofBackend, ProofContext};
+use prover::service::ProverService;
+use prover::types::{ProofData, ProofStatus, ProverRequest, ProverResponse, RequestIdFormat};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// reCAPTCHA score the backend fails on
+const FAILING_SCORE: u32 = 1234;
+
+/// The mock, recording what it was asked to prove and failing on `FAILING_SCORE`
+struct RecordingBackend {
+    mock: MockProofGenerator,
+    requests: Mutex<Vec<ProverRequest>>,
+}
+
+impl ProofBackend for RecordingBackend {
+    fn name(&self) -> &'static str {
+        "recording"
+    }
+
+    fn generate_proof(
+        &self,
+        request: ProverRequest,
+        context: &ProofContext,
+    ) -> Result<ProofData, ServiceError> {
+        self.requests.lock().unwrap().push(request.clone());
+        if request.verification_results.recaptcha_score == FAILING_SCORE {
+            return Err(ServiceError::ProofGeneration("backend refused".to_string()));
+        }
+        self.mock.generate_proof(request, context)
+    }
+}
+
+fn backend() -> Arc<RecordingBackend> {
+    Arc::new(RecordingBackend {
+        mock: MockProofGenerator::new(Duration::ZERO, Duration::from_secs(1)),
+        requests: Mutex::new(Vec::new()),
+    })
+}
+
+fn config() -> Config {
+    static BASE: OnceLock<Config> = OnceLock::new();
+    let mut config = BASE
+        .get_or_init(|| {
+            std::env::set_var("GCP_PROJECT_ID", "backend-test");
+            std::env::set_var("PROVER_SUBSCRIPTION", "unused");
+            std::env::set_var("RESULT_TOPIC", "unused");
+            Config::from_env().expect("base configuration")
+        })
+        .clone();
+    config.proof_timeout_secs = 1;
+    // The mock fails requests by ID prefix
+    config.request_id_format = RequestIdFormat::Any;
+    config
+}
+
+fn request(request_id: &str, recaptcha_score: u32) -> String {
+    format!(
+        r#"{{
+            "request_id": "{}",
+            "verification_results": {{
+                "recaptcha_score": {},
+                "sms_verified": 1,
+                "bio_verified": 1
+            }},
+            "public_inputs": {{
+                "w1": 1500,
+                "w2": 2000,
+                "w3": 2500,
+                "w4": 4000,
+                "expected_output": 244
+            }}
+        }}"#,
+        request_id, recaptcha_score
+    )
+}
+
+async fn process(data: &str, backend: Arc<RecordingBackend>) -> ProverResponse {
+    let interrupt = CancellationToken::new();
+    ProverService::process_message(
+        data.as_bytes(),
+        config(),
+        backend,
+        None,
+        None,
+        &interrupt,
+        Utc::now(),
+    )
+    .await
+    .expect("message processed")
+}
+
+#[tokio::test]
+async fn proofs_become_successful_responses() {
+    let backend = backend();
+    let data = request("backend-success", 8000);
+    let first = process(&data, backend.clone()).await;
+    let second = process(&data, backend.clone()).await;
+
+    let requests = backend.requests.lock().unwrap().clone();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].request_id, "backend-success");
+    assert_eq!(requests[0].verification_results.recaptcha_score, 8000);
+
+    assert_eq!(first.request_id, "backend-success");
+    assert_eq!(first.status, ProofStatus::Success);
+    assert!(first.error.is_none());
+    let proof_data = first.proof_data.as_ref().unwrap();
+    assert_eq!(
+        proof_data.human_index,
+        calculate_human_index(
+            &requests[0].verification_results,
+            &requests[0].public_inputs
+        )
+    );
+
+    // The mock derives its proof from the request, so a repeat proves the same
+    let repeat = second.proof_data.as_ref().unwrap();
+    assert_eq!(proof_data.proof, repeat.proof);
+    assert_eq!(proof_data.public_inputs, repeat.public_inputs);
+    assert_eq!(proof_data.verification_key, repeat.verification_key);
+}
+
+#[tokio::test]
+async fn backend_errors_become_failed_responses() {
+    let backend = backend();
+    let response = process(&request("backend-failure", FAILING_SCORE), backend.clone()).await;
+
+    assert_eq!(backend.requests.lock().unwrap().len(), 1);
+    assert_eq!(response.status, ProofStatus::Failed);
+    assert!(response.proof_data.is_none());
+    let error = response.error.as_ref().unwrap();
+    assert_eq!(error.error_type, "ProofGenerationError");
+    assert!(
+        error.message.contains("backend refused"),
+        "{}",
+        error.message
+    );
+}
+
+#[tokio::test]
+async fn mock_failure_prefixes_select_the_error() {
+    let proof = process(
+        &request(&format!("{}1", FAIL_PROOF_PREFIX), 8000),
+        backend(),
+    )
+    .await;
+    assert_eq!(proof.status, ProofStatus::Failed);
+    assert_eq!(proof.error.unwrap().error_type, "ProofGenerationError");
+
+    let io = process(&request(&format!("{}1", FAIL_IO_PREFIX), 8000), backend()).await;
+    assert_eq!(io.status, ProofStatus::Failed);
+    assert_eq!(io.error.unwrap().error_type, "IoError");
+
+    let timeout = process(
+        &request(&format!("{}1", FAIL_TIMEOUT_PREFIX), 8000),
+        backend(),
+    )
+    .await;
+    assert_eq!(timeout.status, ProofStatus::Timeout);
+    assert!(timeout.proof_data.is_none());
+}
+
+#[tokio::test]
+async fn malformed_messages_never_reach_the_backend() {
+    let backend = backend();
+    let interrupt = CancellationToken::new();
+    let result = ProverService::process_message(
+        b"not json",
+        config(),
+        backend.clone(),
+        None,
+        None,
+        &interrupt,
+        Utc::now(),
+    )
+    .await;
+
+    assert!(matches!(result, Err(ServiceError::MalformedRequest(_))));
+    assert!(backend.requests.lock().unwrap().is_empty());
+}
@@ -0,0 +1,205 @@
+// Exercises the in-memory proof cache against a store that counts its IO, checking
+// eviction by entry count and by byte budget and that hits never touch the store.
+// Sizes use synthetic proofs, plus the recorded mock proofs for realistic ones.
+
+use prover::error::ServiceError;
+use prover::fixtures::{golden_vectors, Fixtures};
+use prover::proof_cache::{CacheLimits, CacheUsage, ProofCache, ProofStore};
+use prover::types::ProofData;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Store that keeps proofs in a map and counts loads and saves
+#[derive(Clone, Default)]
+struct CountingStore {
+    proofs: Arc<Mutex<HashMap<String, ProofData>>>,
+    loads: Arc<AtomicUsize>,
+    saves: Arc<AtomicUsize>,
+}
+
+impl CountingStore {
+    fn loads(&self) -> usize {
+        self.loads.load(Ordering::SeqCst)
+    }
+
+    fn saves(&self) -> usize {
+        self.saves.load(Ordering::SeqCst)
+    }
+}
+
+impl ProofStore for CountingStore {
+    fn load(&self, key: &str) -> Result<Option<ProofData>, ServiceError> {
+        self.loads.fetch_add(1, Ordering::SeqCst);
+        Ok(self.proofs.lock().unwrap().get(key).cloned())
+    }
+
+    fn save(&self, key: &str, proof: &ProofData) -> Result<(), ServiceError> {
+        self.saves.fetch_add(1, Ordering::SeqCst);
+        self.proofs
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), proof.clone());
+        Ok(())
+    }
+}
+
+/// A proof whose `proof` field is `len` bytes long
+fn proof(human_index: u32, len: usize) -> ProofData {
+    serde_json::from_value(json!({
+        "proof": "A".repeat(len),
+        "public_inputs": "",
+        "verification_key": "",
+        "human_index": human_index,
+    }))
+    .unwrap()
+}
+
+fn size(proof: &ProofData) -> u64 {
+    serde_json::to_vec(proof).unwrap().len() as u64
+}
+
+fn cache(max_entries: usize, max_bytes: u64) -> (ProofCache<CountingStore>, CountingStore) {
+    let store = CountingStore::default();
+    let limits = CacheLimits {
+        max_entries,
+        max_bytes,
+    };
+    (ProofCache::new(limits, store.clone()), store)
+}
+
+/// `human_index` of the cached proof for `key`, without falling through to the store
+fn cached(cache: &ProofCache<CountingStore>, store: &CountingStore, key: &str) -> Option<u32> {
+    let loads = store.loads();
+    let proof = cache.get(key).unwrap();
+    if store.loads() != loads {
+        return None;
+    }
+    proof.map(|proof| proof.human_index)
+}
+
+#[test]
+fn evicts_least_recently_used_by_entry_count() {
+    let (cache, store) = cache(2, u64::MAX);
+    cache.insert("a", &proof(1, 10)).unwrap();
+    cache.insert("b", &proof(2, 10)).unwrap();
+
+    // Using "a" makes "b" the least recently used
+    assert_eq!(cached(&cache, &store, "a"), Some(1));
+    cache.insert("c", &proof(3, 10)).unwrap();
+
+    assert_eq!(cache.usage().entries, 2);
+    assert_eq!(cached(&cache, &store, "a"), Some(1));
+    assert_eq!(cached(&cache, &store, "c"), Some(3));
+    assert_eq!(cached(&cache, &store, "b"), None);
+}
+
+#[test]
+fn evicts_least_recently_used_by_byte_budget() {
+    let small = proof(1, 100);
+    let large = proof(3, 250);
+    // Room for both small proofs, but not for a small and the large one
+    let budget = 2 * size(&small) + 10;
+    assert!(size(&small) + size(&large) > budget && size(&large) <= budget);
+
+    let (cache, store) = cache(usize::MAX, budget);
+    cache.insert("a", &small).unwrap();
+    cache.insert("b", &proof(2, 100)).unwrap();
+    assert_eq!(
+        cache.usage(),
+        CacheUsage {
+            entries: 2,
+            bytes: 2 * size(&small)
+        }
+    );
+
+    cache.insert("c", &large).unwrap();
+    assert_eq!(
+        cache.usage(),
+        CacheUsage {
+            entries: 1,
+            bytes: size(&large)
+        }
+    );
+    assert_eq!(cached(&cache, &store, "c"), Some(3));
+    assert_eq!(cached(&cache, &store, "a"), None);
+    assert_eq!(cached(&cache, &store, "b"), None);
+}
+
+#[test]
+fn oversized_proof_is_only_stored() {
+    let (cache, store) = cache(4, 50);
+    cache.insert("a", &proof(1, 100)).unwrap();
+
+    assert_eq!(cache.usage(), CacheUsage::default());
+    assert_eq!(store.saves(), 1);
+    assert_eq!(cache.get("a").unwrap().unwrap().human_index, 1);
+    assert_eq!(cache.usage(), CacheUsage::default());
+}
+
+#[test]
+fn hit_is_served_from_memory_without_store_io() {
+    let (cache, store) = cache(4, u64::MAX);
+    cache.insert("a", &proof(7, 10)).unwrap();
+    assert_eq!((store.loads(), store.saves()), (0, 1));
+
+    for _ in 0..3 {
+        assert_eq!(cache.get("a").unwrap().unwrap().human_index, 7);
+    }
+    assert_eq!((store.loads(), store.saves()), (0, 1));
+}
+
+#[test]
+fn miss_falls_through_to_store_and_is_kept() {
+    let (cache, store) = cache(1, u64::MAX);
+    cache.insert("a", &proof(1, 10)).unwrap();
+    cache.insert("b", &proof(2, 10)).unwrap();
+    assert_eq!(store.loads(), 0);
+
+    // "a" was evicted from memory but is still in the store
+    assert_eq!(cache.get("a").unwrap().unwrap().human_index, 1);
+    assert_eq!(store.loads(), 1);
+    assert_eq!(cache.get("a").unwrap().unwrap().human_index, 1);
+    assert_eq!(store.loads(), 1);
+    assert_eq!(cache.get("missing").unwrap().map(|p| p.human_index), None);
+}
+
+#[test]
+fn recorded_proofs_are_evicted_oldest_first() {
+    let fixtures = Fixtures::workspace().expect("fixtures load");
+    let proofs: Vec<(String, ProofData)> = golden_vectors()
+        .expect("golden vectors load")
+        .into_iter()
+        .map(|vector| {
+            let proof = fixtures
+                .mock_proof(&vector.name)
+                .expect("mock proof fixture");
+            (vector.name, proof)
+        })
+        .collect();
+    let largest = proofs.iter().map(|(_, proof)| size(proof)).max().unwrap();
+    let smallest = proofs.iter().map(|(_, proof)| size(proof)).min().unwrap();
+
+    // Room for any three recorded proofs, never four
+    let budget = 3 * largest;
+    assert!(4 * smallest > budget);
+    let (cache, store) = cache(usize::MAX, budget);
+    for (name, proof) in &proofs {
+        cache.insert(name, proof).unwrap();
+    }
+
+    assert_eq!(cache.usage().entries, 3);
+    let (evicted, kept) = proofs.split_at(proofs.len() - 3);
+    for (name, proof) in kept {
+        assert_eq!(
+            cached(&cache, &store, name),
+            Some(proof.human_index),
+            "{}",
+            name
+        );
+    }
+    for (name, _) in evicted {
+        assert_eq!(cached(&cache, &store, name), None, "{}", name);
+    }
+}
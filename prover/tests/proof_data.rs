@@ -0,0 +1,111 @@
+// Checks that `read_proof_artifacts`, which streams inputs.json into base64, produces
+// the same `ProofData` as the previous read-parse-stringify-encode path.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use prover::error::ServiceError;
+use prover::prover::{read_proof_artifacts, ArtifactEncoding, ProofJsonLayout};
+use prover::types::ProofData;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+const WEIGHTS_COMMITMENT: [u8; 32] = [7; 32];
+
+/// The encoding before streaming: whole file as a string, a `Value`, then a JSON string
+fn reference_encoding(inputs_path: &Path) -> (String, String, String) {
+    let inputs: Value =
+        serde_json::from_str(&std::fs::read_to_string(inputs_path).unwrap()).unwrap();
+    let proof_json = serde_json::to_string(inputs["proof"].as_array().unwrap()).unwrap();
+    (
+        STANDARD.encode(proof_json.as_bytes()),
+        STANDARD.encode(inputs["publicValues"].as_str().unwrap().as_bytes()),
+        STANDARD.encode(inputs["riscvVKey"].as_str().unwrap().as_bytes()),
+    )
+}
+
+/// Write `inputs` to a fresh `inputs.json` for test `name`
+fn write_inputs(name: &str, inputs: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("prover-proof-data-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("inputs.json");
+    std::fs::write(&path, inputs).unwrap();
+    path
+}
+
+fn read(path: &Path, max_bytes: u64) -> Result<ProofData, ServiceError> {
+    read_proof_artifacts(
+        path,
+        max_bytes,
+        ProofJsonLayout::PicoV1,
+        ArtifactEncoding::Sequential,
+        173,
+        &WEIGHTS_COMMITMENT,
+    )
+}
+
+fn assert_matches_reference(name: &str, inputs: &str) {
+    let path = write_inputs(name, inputs);
+    let proof_data = read(&path, u64::MAX).expect("inputs read");
+    let (proof, public_inputs, verification_key) = reference_encoding(&path);
+
+    assert_eq!(proof_data.proof, proof);
+    assert_eq!(proof_data.public_inputs, public_inputs);
+    assert_eq!(proof_data.verification_key, verification_key);
+    assert_eq!(proof_data.human_index, 173);
+    assert_eq!(
+        proof_data.weights_commitment,
+        format!("0x{}", hex::encode(WEIGHTS_COMMITMENT))
+    );
+    std::fs::remove_dir_all(path.parent().unwrap()).ok();
+}
+
+#[test]
+fn groth16_inputs_match_reference() {
+    let proof: Vec<String> = (0u8..8)
+        .map(|i| format!("0x{}", hex::encode([i.wrapping_mul(31) | 1; 32])))
+        .collect();
+    let inputs = json!({
+        "riscvVKey": format!("0x{}", hex::encode([0x42; 32])),
+        "publicValues": format!("0x{}", hex::encode([0xab; 96])),
+        "proof": proof,
+    });
+    assert_matches_reference("groth16", &serde_json::to_string_pretty(&inputs).unwrap());
+}
+
+#[test]
+fn large_and_escaped_inputs_match_reference() {
+    // Many words, and strings that need escaping, in a compact file with extra fields
+    let proof: Vec<String> = (0..50_000)
+        .map(|i| format!("{}\"\\\u{e9}\n{}", i, "7".repeat(i % 80)))
+        .collect();
+    let inputs = json!({
+        "proof": proof,
+        "publicValues": "0x",
+        "riscvVKey": "0x00",
+        "groth16Proof": "ignored",
+    });
+    assert_matches_reference("large", &serde_json::to_string(&inputs).unwrap());
+}
+
+#[test]
+fn empty_proof_matches_reference() {
+    assert_matches_reference(
+        "empty",
+        r#"{"proof": [], "publicValues": "0x", "riscvVKey": "0x"}"#,
+    );
+}
+
+#[test]
+fn oversized_inputs_are_refused() {
+    let inputs = r#"{"proof": ["1", "2"], "publicValues": "0x", "riscvVKey": "0x"}"#;
+    let path = write_inputs("oversized", inputs);
+
+    let err = read(&path, inputs.len() as u64 - 1).unwrap_err();
+    assert!(
+        err.to_string().contains("MAX_PROOF_ARTIFACT_BYTES"),
+        "{}",
+        err
+    );
+    read(&path, inputs.len() as u64).expect("a file at the limit is read");
+    std::fs::remove_dir_all(path.parent().unwrap()).ok();
+}
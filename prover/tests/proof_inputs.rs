@@ -0,0 +1,76 @@
+// `ProofInputs` is the stdin contract between the hosts and the guest: whatever a
+// host writes with `write_to`, the guest's `read_from` must read back unchanged.
+
+use human_index_lib::{
+    HumanIndexPublicInputs, ProofInputs, Validity, VerificationResults, STDIN_WORDS,
+};
+use prover::prover::{setup_inputs, InputLayout};
+use std::collections::VecDeque;
+
+fn roundtrip(proof_inputs: &ProofInputs) -> ProofInputs {
+    let mut stdin = VecDeque::new();
+    proof_inputs.write_to(|value| stdin.push_back(*value));
+    assert_eq!(stdin.len(), STDIN_WORDS);
+    let read = ProofInputs::read_from(|| stdin.pop_front().expect("read past the written inputs"));
+    assert!(stdin.is_empty(), "guest left {} inputs unread", stdin.len());
+    read
+}
+
+/// Distinct values, so a swapped pair of inputs cannot go unnoticed
+fn distinct() -> ProofInputs {
+    ProofInputs {
+        results: VerificationResults {
+            recaptcha_score: 1,
+            sms_verified: 2,
+            bio_verified: 3,
+        },
+        inputs: HumanIndexPublicInputs {
+            w1: 4,
+            w2: 5,
+            w3: 6,
+            w4: 7,
+            expected_output: 8,
+        },
+        expected_output: 8,
+        validity: Validity {
+            issued_at: 9 | 10 << 32,
+            valid_until: 11 | 12 << 32,
+        },
+    }
+}
+
+#[test]
+fn distinct_values_roundtrip_in_place() {
+    assert_eq!(roundtrip(&distinct()), distinct());
+}
+
+#[test]
+fn setup_inputs_roundtrip() {
+    let setup = setup_inputs();
+    let read = roundtrip(&setup);
+    assert_eq!(read.results, setup.results);
+    assert_eq!(read.expected_output, setup.expected_output);
+    assert_eq!(read.validity, setup.validity);
+    assert_eq!(read.inputs.w1, setup.inputs.w1);
+    assert_eq!(read.inputs.w4, setup.inputs.w4);
+}
+
+#[test]
+fn written_expected_output_is_the_one_committed() {
+    // The request's `expected_output` is not trusted; the host's native index is
+    let mut inputs = distinct().inputs;
+    inputs.expected_output = 200;
+    let proof_inputs = ProofInputs::new(distinct().results, inputs, distinct().validity);
+    assert_ne!(proof_inputs.expected_output, 200);
+
+    let read = roundtrip(&proof_inputs);
+    assert_eq!(read.expected_output, proof_inputs.expected_output);
+    assert_eq!(read.inputs.expected_output, proof_inputs.expected_output);
+}
+
+#[test]
+fn every_host_writes_the_runtime_layout() {
+    let mut layout = InputLayout::default();
+    distinct().write_to(|value| layout.record(value));
+    assert_eq!(layout, InputLayout::runtime());
+}
@@ -0,0 +1,165 @@
+// `PROOF_JSON_KEYS` names the keys the proof artifacts are read from in inputs.json.
+// The same artifacts under the `pico_v1` and the `sp1` keys must decode to the same
+// `ProofData`, and a file in the other layout must fail naming the missing key.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use prover::error::ServiceError;
+use prover::prover::{read_proof_artifacts, ArtifactEncoding, ProofJsonLayout};
+use prover::types::ProofData;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+const WEIGHTS_COMMITMENT: [u8; 32] = [7; 32];
+
+fn proof_words() -> Vec<String> {
+    (0u8..8)
+        .map(|i| format!("0x{}", hex::encode([i.wrapping_mul(31) | 1; 32])))
+        .collect()
+}
+
+fn public_values() -> String {
+    format!("0x{}", hex::encode([0xab; 96]))
+}
+
+fn verifying_key() -> String {
+    format!("0x{}", hex::encode([0x42; 32]))
+}
+
+/// Write `inputs` to a fresh `inputs.json` for test `name`
+fn write_inputs(name: &str, inputs: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "prover-proof-json-keys-{}-{}",
+        name,
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("inputs.json");
+    std::fs::write(&path, inputs).unwrap();
+    path
+}
+
+fn read(path: &Path, layout: ProofJsonLayout) -> Result<ProofData, ServiceError> {
+    read_proof_artifacts(
+        path,
+        u64::MAX,
+        layout,
+        ArtifactEncoding::Parallel,
+        173,
+        &WEIGHTS_COMMITMENT,
+    )
+}
+
+fn decode(field: &str) -> String {
+    String::from_utf8(STANDARD.decode(field).unwrap()).unwrap()
+}
+
+fn remove(path: &Path) {
+    std::fs::remove_dir_all(path.parent().unwrap()).ok();
+}
+
+#[test]
+fn pico_v1_and_sp1_keys_decode_to_the_same_proof_data() {
+    let pico = write_inputs(
+        "pico",
+        &json!({
+            "proof": proof_words(),
+            "publicValues": public_values(),
+            "riscvVKey": verifying_key(),
+        })
+        .to_string(),
+    );
+    let sp1 = write_inputs(
+        "sp1",
+        &json!({
+            "vkey": verifying_key(),
+            "publicValues": public_values(),
+            "proof": proof_words(),
+        })
+        .to_string(),
+    );
+
+    let from_pico = read(&pico, ProofJsonLayout::PicoV1).unwrap();
+    let from_sp1 = read(&sp1, ProofJsonLayout::Sp1).unwrap();
+    assert_eq!(
+        serde_json::to_value(&from_pico).unwrap(),
+        serde_json::to_value(&from_sp1).unwrap()
+    );
+    assert_eq!(decode(&from_sp1.public_inputs), public_values());
+    assert_eq!(decode(&from_sp1.verification_key), verifying_key());
+    assert_eq!(from_sp1.human_index, 173);
+    remove(&pico);
+    remove(&sp1);
+}
+
+#[test]
+fn sp1_fixture_with_a_hex_proof_decodes() {
+    // SP1 writes the proof as one hex string, alongside keys the service has no use for
+    let proof = format!("0x{}", hex::encode([0x5a; 260]));
+    let path = write_inputs(
+        "sp1-hex",
+        &json!({
+            "a": 17,
+            "n": 20,
+            "vkey": verifying_key(),
+            "publicValues": public_values(),
+            "proof": proof,
+        })
+        .to_string(),
+    );
+
+    let proof_data = read(&path, ProofJsonLayout::Sp1).unwrap();
+    assert_eq!(
+        decode(&proof_data.proof),
+        serde_json::to_string(&proof).unwrap()
+    );
+    assert_eq!(decode(&proof_data.public_inputs), public_values());
+    assert_eq!(decode(&proof_data.verification_key), verifying_key());
+    remove(&path);
+}
+
+#[test]
+fn a_file_in_the_other_layout_names_the_missing_key() {
+    let path = write_inputs(
+        "mismatch",
+        &json!({
+            "proof": proof_words(),
+            "publicValues": public_values(),
+            "riscvVKey": verifying_key(),
+        })
+        .to_string(),
+    );
+
+    let err = read(&path, ProofJsonLayout::Sp1).unwrap_err().to_string();
+    assert!(err.contains("PROOF_JSON_KEYS=sp1"), "{}", err);
+    assert!(err.contains("missing field `vkey`"), "{}", err);
+    read(&path, ProofJsonLayout::PicoV1).expect("the file's own layout reads it");
+    remove(&path);
+}
+
+#[test]
+fn trailing_data_is_refused() {
+    let path = write_inputs(
+        "trailing",
+        r#"{"proof": [], "publicValues": "0x", "riscvVKey": "0x"} {"proof": []}"#,
+    );
+
+    let err = read(&path, ProofJsonLayout::PicoV1)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("trailing characters"), "{}", err);
+    remove(&path);
+}
+
+#[test]
+fn layouts_parse_by_name() {
+    for layout in [ProofJsonLayout::PicoV1, ProofJsonLayout::Sp1] {
+        assert_eq!(layout.name().parse::<ProofJsonLayout>().unwrap(), layout);
+    }
+    assert_eq!(
+        "SP1".parse::<ProofJsonLayout>().unwrap(),
+        ProofJsonLayout::Sp1
+    );
+    assert_eq!(ProofJsonLayout::default(), ProofJsonLayout::PicoV1);
+    let err = "pico_v2".parse::<ProofJsonLayout>().unwrap_err();
+    assert!(err.to_string().contains("PROOF_JSON_KEYS"), "{}", err);
+}
@@ -0,0 +1,126 @@
+// A proof that is still making progress when the timeout fires is slow rather than
+// stuck: it gets one extension, and if it does not finish in that either, the
+// timeout reports the progress it last made. A proof reporting no progress times
+// out as before.
+
+use prover::mock::{MockProofGenerator, FAIL_TIMEOUT_PREFIX, SLOW_PROOF_PREFIX};
+use prover::prover::{await_proof, ProofBackend, ProofContext, TimeoutPolicy};
+use prover::types::ProverRequest;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+fn request(request_id: &str) -> ProverRequest {
+    serde_json::from_value(json!({
+        "request_id": request_id,
+        "verification_results": { "recaptcha_score": 8000, "sms_verified": 1, "bio_verified": 1 },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 },
+    }))
+    .unwrap()
+}
+
+fn policy(extension: Duration) -> TimeoutPolicy {
+    TimeoutPolicy {
+        timeout: Duration::from_secs(1),
+        extension,
+        progress_window: Duration::from_millis(500),
+    }
+}
+
+/// Start the mock on `request_id` as `process_message` does, with a mock timeout
+/// of `mock_timeout`; returns the proof future and its context
+fn prove(
+    request_id: &str,
+    mock_timeout: Duration,
+) -> (impl std::future::Future<Output = bool>, Arc<ProofContext>) {
+    let backend = MockProofGenerator::new(Duration::ZERO, mock_timeout);
+    let context = Arc::new(ProofContext::new(Instant::now() + mock_timeout, 0));
+    let proof_context = context.clone();
+    let request = request(request_id);
+    let proving = async move {
+        tokio::task::spawn_blocking(move || backend.generate_proof(request, &proof_context))
+            .await
+            .unwrap()
+            .is_ok()
+    };
+    (proving, context)
+}
+
+#[tokio::test]
+async fn slow_proof_finishes_within_one_extension() {
+    // Finishes a second after the first window, within the extension
+    let request_id = format!("{}1", SLOW_PROOF_PREFIX);
+    let (proving, context) = prove(&request_id, Duration::from_secs(1));
+    let started = Instant::now();
+
+    let proved = await_proof(
+        &request_id,
+        proving,
+        &policy(Duration::from_secs(2)),
+        &context,
+    )
+    .await
+    .expect("extended proof finishes");
+    assert!(proved);
+    assert!(started.elapsed() >= Duration::from_secs(1));
+    assert!(started.elapsed() < Duration::from_secs(3));
+    assert!(context.progress().unwrap().heartbeats > 1);
+}
+
+#[tokio::test]
+async fn stuck_proof_is_not_extended() {
+    let request_id = format!("{}1", FAIL_TIMEOUT_PREFIX);
+    let (proving, context) = prove(&request_id, Duration::from_secs(1));
+    let started = Instant::now();
+
+    let timed_out = await_proof(
+        &request_id,
+        proving,
+        &policy(Duration::from_secs(2)),
+        &context,
+    )
+    .await
+    .expect_err("stuck proof times out");
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert_eq!(timed_out.extended, None);
+    assert_eq!(timed_out.progress, None);
+    assert_eq!(
+        timed_out.waited(&policy(Duration::from_secs(2))),
+        Duration::from_secs(1)
+    );
+}
+
+#[tokio::test]
+async fn extension_is_granted_only_once_and_reports_progress() {
+    // Runs for four seconds, past the timeout and its extension
+    let request_id = format!("{}2", SLOW_PROOF_PREFIX);
+    let (proving, context) = prove(&request_id, Duration::from_secs(3));
+    let policy = policy(Duration::from_secs(1));
+
+    let timed_out = await_proof(&request_id, proving, &policy, &context)
+        .await
+        .expect_err("proof outlives the extension");
+    assert_eq!(timed_out.extended, Some(Duration::from_secs(1)));
+    assert_eq!(timed_out.waited(&policy), Duration::from_secs(2));
+
+    let progress = timed_out.progress.expect("last progress is reported");
+    assert_eq!(progress.phase, "prove");
+    assert_eq!(progress.extended_secs, Some(1));
+    assert!(progress.heartbeats >= 10, "{:?}", progress);
+    assert!(progress.idle_ms < 500, "{:?}", progress);
+}
+
+#[tokio::test]
+async fn extension_is_off_by_default() {
+    let request_id = format!("{}3", SLOW_PROOF_PREFIX);
+    let (proving, context) = prove(&request_id, Duration::from_secs(1));
+
+    let timed_out = await_proof(&request_id, proving, &policy(Duration::ZERO), &context)
+        .await
+        .expect_err("proof times out without an extension");
+    assert_eq!(timed_out.extended, None);
+    // Progress is reported all the same
+    let progress = timed_out.progress.unwrap();
+    assert_eq!(progress.extended_secs, None);
+    assert!(progress.heartbeats > 1);
+}
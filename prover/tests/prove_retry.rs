@@ -0,0 +1,185 @@
+// In-place retry of transient `prove_evm` failures with `PROVE_RETRY_ATTEMPTS`, run
+// through the service with a backend that fails with given errors before proving:
+// Docker hiccups are retried and the attempts made land in `ProofMetrics`, other
+// failures are not retried, and no retry starts past the proof's deadline.
+
+use chrono::Utc;
+use prover::config::Config;
+use prover::error::ServiceError;
+use prover::mock::MockProofGenerator;
+use prover::prover::{is_transient_prove_error, retry_transient, ProofBackend, ProofContext};
+use prover::service::ProverService;
+use prover::types::{ProofData, ProofStatus, ProverRequest, ProverResponse};
+use serde_json::json;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+const BACKOFF: Duration = Duration::from_millis(10);
+const DAEMON_HICCUP: &str =
+    "prove_evm failed: Cannot connect to the Docker daemon at unix:///var/run/docker.sock";
+const OOM_KILLED: &str = "prove_evm failed: container exited with code 137 (out of memory)";
+
+/// Fails its first attempts with `failures`, then proves with the mock, retrying as
+/// `ProofGenerator` retries `prove_evm`
+struct FlakyBackend {
+    failures: Vec<&'static str>,
+    attempts: AtomicU32,
+    mock: MockProofGenerator,
+}
+
+impl ProofBackend for FlakyBackend {
+    fn name(&self) -> &'static str {
+        "flaky"
+    }
+
+    fn generate_proof(
+        &self,
+        request: ProverRequest,
+        context: &ProofContext,
+    ) -> Result<ProofData, ServiceError> {
+        retry_transient(
+            &request.request_id,
+            context,
+            BACKOFF,
+            is_transient_prove_error,
+            |number| {
+                self.attempts.fetch_add(1, Ordering::SeqCst);
+                match self.failures.get(number as usize - 1) {
+                    Some(failure) => Err(ServiceError::ProofGeneration(failure.to_string())),
+                    None => self.mock.generate_proof(request.clone(), context),
+                }
+            },
+        )
+    }
+}
+
+fn config(prove_retry_attempts: u32) -> Config {
+    static BASE: OnceLock<Config> = OnceLock::new();
+    let mut config = BASE
+        .get_or_init(|| {
+            std::env::set_var("GCP_PROJECT_ID", "retry-test");
+            std::env::set_var("PROVER_SUBSCRIPTION", "unused");
+            std::env::set_var("RESULT_TOPIC", "unused");
+            Config::from_env().expect("base configuration")
+        })
+        .clone();
+    config.memory_wait_secs = 0;
+    config.prove_retry_attempts = prove_retry_attempts;
+    config
+}
+
+/// Prove with a backend failing first with `failures`; returns the response and
+/// the attempts the backend saw
+async fn prove(failures: Vec<&'static str>, prove_retry_attempts: u32) -> (ProverResponse, u32) {
+    let backend = Arc::new(FlakyBackend {
+        failures,
+        attempts: AtomicU32::new(0),
+        mock: MockProofGenerator::new(Duration::ZERO, Duration::from_secs(60)),
+    });
+    let request = json!({
+        "request_id": "3f2504e0-4f89-41d3-9a0c-0305e82c3301",
+        "verification_results": { "recaptcha_score": 8000, "sms_verified": 1, "bio_verified": 0 },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 },
+    });
+    let response = ProverService::process_message(
+        &serde_json::to_vec(&request).unwrap(),
+        config(prove_retry_attempts),
+        backend.clone(),
+        None,
+        None,
+        &CancellationToken::new(),
+        Utc::now(),
+    )
+    .await
+    .expect("request parses");
+    (response, backend.attempts.load(Ordering::SeqCst))
+}
+
+fn attempts(response: &ProverResponse) -> Option<u32> {
+    response.metrics.as_ref().unwrap().attempts
+}
+
+#[tokio::test]
+async fn transient_failure_is_retried_in_place() {
+    let (response, made) = prove(vec![DAEMON_HICCUP], 2).await;
+    assert_eq!(
+        response.status,
+        ProofStatus::Success,
+        "{:?}",
+        response.error
+    );
+    assert_eq!(made, 2);
+    assert_eq!(attempts(&response), Some(2));
+
+    let (response, made) = prove(vec![DAEMON_HICCUP, "toomanyrequests: rate limited"], 2).await;
+    assert_eq!(
+        response.status,
+        ProofStatus::Success,
+        "{:?}",
+        response.error
+    );
+    assert_eq!(made, 3);
+    assert_eq!(attempts(&response), Some(3));
+}
+
+#[tokio::test]
+async fn first_time_success_is_one_attempt() {
+    let (response, made) = prove(vec![], 2).await;
+    assert_eq!(
+        response.status,
+        ProofStatus::Success,
+        "{:?}",
+        response.error
+    );
+    assert_eq!(made, 1);
+    assert_eq!(attempts(&response), Some(1));
+}
+
+#[tokio::test]
+async fn retries_stop_at_prove_retry_attempts() {
+    let (response, made) = prove(vec![DAEMON_HICCUP; 4], 2).await;
+    assert_eq!(response.status, ProofStatus::Failed);
+    assert_eq!(made, 3);
+    assert_eq!(attempts(&response), Some(3));
+    assert!(response.error.unwrap().message.contains("Docker daemon"));
+
+    // Retries are off with PROVE_RETRY_ATTEMPTS=0
+    let (response, made) = prove(vec![DAEMON_HICCUP], 0).await;
+    assert_eq!(response.status, ProofStatus::Failed);
+    assert_eq!(made, 1);
+    assert_eq!(attempts(&response), Some(1));
+}
+
+#[tokio::test]
+async fn non_transient_failure_is_not_retried() {
+    let (response, made) = prove(vec![OOM_KILLED], 2).await;
+    assert_eq!(response.status, ProofStatus::Failed);
+    assert_eq!(made, 1);
+    assert_eq!(attempts(&response), Some(1));
+    assert_eq!(response.error.unwrap().error_type, "ProofGenerationError");
+
+    assert!(!is_transient_prove_error(&ServiceError::Io(
+        std::io::Error::other("connection reset by peer")
+    )));
+}
+
+#[test]
+fn no_retry_starts_past_the_deadline() {
+    let context = ProofContext::new(Instant::now() + Duration::from_millis(50), 5);
+    let mut made = 0;
+    let result: Result<(), ServiceError> = retry_transient(
+        "deadline",
+        &context,
+        Duration::from_millis(100),
+        is_transient_prove_error,
+        |_| {
+            made += 1;
+            Err(ServiceError::ProofGeneration(DAEMON_HICCUP.to_string()))
+        },
+    );
+    assert!(result.is_err());
+    assert_eq!(made, 1);
+    assert_eq!(context.attempts(), 1);
+}
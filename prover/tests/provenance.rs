@@ -0,0 +1,138 @@
+// The `provenance` block attached to every response: its JSON structure is pinned
+// field by field, the ELF hash is the hash of the cached guest ELF, the setup hash
+// that of `vm_vk`, and backends that load neither leave both out.
+
+use prover::config::{Config, ProverBackendKind};
+use prover::provenance::{Provenance, PICO_SDK_VERSION};
+use prover::prover::{CachedElf, ProverProfile};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// A provenance block as a response carries it
+const PINNED: &str = r#"{"instance_id":"prover-7f9c4","hostname":"prover-7f9c4-host","prover_version":"0.1.0","pico_sdk_version":"v1.1.6","elf_sha256":"4d3b1f0d2e6c8a5b7f9e1c3a5d7b9f1e3c5a7d9b1f3e5c7a9d1b3f5e7c9a1d3b","setup_sha256":"9e8d7c6b5a4f3e2d1c0b9a8f7e6d5c4b3a2f1e0d9c8b7a6f5e4d3c2b1a0f9e8d","backend":"pico/evm"}"#;
+
+fn config(backend: ProverBackendKind, profile: ProverProfile, output_dir: &Path) -> Config {
+    static BASE: OnceLock<Config> = OnceLock::new();
+    let mut config = BASE
+        .get_or_init(|| {
+            std::env::set_var("GCP_PROJECT_ID", "provenance-test");
+            std::env::set_var("PROVER_SUBSCRIPTION", "unused");
+            std::env::set_var("RESULT_TOPIC", "unused");
+            Config::from_env().expect("base configuration")
+        })
+        .clone();
+    config.prover_backend = backend;
+    config.prover_profile = profile;
+    config.output_dir = output_dir.display().to_string();
+    config.worker_id = "prover-7f9c4".to_string();
+    config
+}
+
+/// An output directory holding the fixture key as vm_vk, and a guest ELF beside it
+fn output_dir(name: &str) -> (PathBuf, PathBuf) {
+    let dir =
+        std::env::temp_dir().join(format!("prover-provenance-{}-{}", name, std::process::id()));
+    std::fs::remove_dir_all(&dir).ok();
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::copy(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/groth16_vk.bin"),
+        dir.join("vm_vk"),
+    )
+    .unwrap();
+    let elf = dir.join("riscv32im-pico-zkvm-elf");
+    std::fs::write(&elf, b"\x7fELF guest for provenance").unwrap();
+    (dir, elf)
+}
+
+fn sha256(path: &Path) -> String {
+    hex::encode(Sha256::digest(std::fs::read(path).unwrap()))
+}
+
+fn keys(value: &Value) -> Vec<&str> {
+    value
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(String::as_str)
+        .collect()
+}
+
+#[test]
+fn json_structure_is_pinned() {
+    let provenance: Provenance = serde_json::from_str(PINNED).unwrap();
+    assert_eq!(serde_json::to_string(&provenance).unwrap(), PINNED);
+    assert_eq!(provenance.instance_id, "prover-7f9c4");
+    assert_eq!(provenance.backend, "pico/evm");
+
+    // Without the hashes, the keys are left out rather than null
+    let provenance = Provenance {
+        elf_sha256: None,
+        setup_sha256: None,
+        backend: "mock".to_string(),
+        ..provenance
+    };
+    assert_eq!(
+        serde_json::to_value(&provenance).unwrap(),
+        json!({
+            "instance_id": "prover-7f9c4",
+            "hostname": "prover-7f9c4-host",
+            "prover_version": "0.1.0",
+            "pico_sdk_version": "v1.1.6",
+            "backend": "mock",
+        })
+    );
+}
+
+#[test]
+fn elf_hash_is_the_cached_elfs() {
+    let (dir, elf_path) = output_dir("evm");
+    let elf = CachedElf::load(elf_path.to_str().unwrap()).unwrap();
+    let config = config(ProverBackendKind::Pico, ProverProfile::Evm, &dir);
+    let provenance = Provenance::collect(&config, Some(&elf));
+
+    assert_eq!(
+        provenance.elf_sha256.as_deref(),
+        Some(elf.sha256().as_str())
+    );
+    assert_eq!(provenance.elf_sha256, Some(sha256(&elf_path)));
+    assert_eq!(provenance.setup_sha256, Some(sha256(&dir.join("vm_vk"))));
+    assert_eq!(provenance.backend, "pico/evm");
+    assert_eq!(provenance.instance_id, "prover-7f9c4");
+    assert_eq!(provenance.prover_version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(provenance.pico_sdk_version, PICO_SDK_VERSION);
+    assert!(!provenance.hostname.is_empty());
+
+    // Every field of the pinned block is present
+    let value = serde_json::to_value(&provenance).unwrap();
+    let pinned: Value = serde_json::from_str(PINNED).unwrap();
+    assert_eq!(keys(&value), keys(&pinned));
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn fast_profile_has_no_setup_hash() {
+    let (dir, elf_path) = output_dir("fast");
+    let elf = CachedElf::load(elf_path.to_str().unwrap()).unwrap();
+    let config = config(ProverBackendKind::Pico, ProverProfile::Fast, &dir);
+    let provenance = Provenance::collect(&config, Some(&elf));
+
+    assert_eq!(provenance.backend, "pico/fast");
+    assert_eq!(provenance.elf_sha256, Some(sha256(&elf_path)));
+    assert_eq!(provenance.setup_sha256, None);
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn mock_backend_has_no_hashes() {
+    let (dir, _) = output_dir("mock");
+    let config = config(ProverBackendKind::Mock, ProverProfile::Evm, &dir);
+    let provenance = Provenance::collect(&config, None);
+
+    assert_eq!(provenance.backend, "mock");
+    let value = serde_json::to_value(&provenance).unwrap();
+    assert!(value.get("elf_sha256").is_none());
+    assert!(value.get("setup_sha256").is_none());
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,47 @@
+// Prover profiles: `evm` and `fast` parse by name, and only `evm`, whose Groth16
+// wrap links `vm_pk`/`vm_vk` into each proof directory, needs the setup files.
+
+use prover::prover::{find_setup_files, ProverProfile};
+use std::path::PathBuf;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("prover-profile-{}-{}", name, std::process::id()));
+    std::fs::remove_dir_all(&dir).ok();
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn profiles_parse_by_name() {
+    assert_eq!("evm".parse::<ProverProfile>().unwrap(), ProverProfile::Evm);
+    assert_eq!(
+        "FAST".parse::<ProverProfile>().unwrap(),
+        ProverProfile::Fast
+    );
+    assert!("groth16".parse::<ProverProfile>().is_err());
+}
+
+#[test]
+fn only_the_evm_profile_needs_setup_files() {
+    assert!(ProverProfile::Evm.needs_setup_files());
+    assert!(!ProverProfile::Fast.needs_setup_files());
+}
+
+#[test]
+fn missing_setup_files_point_at_the_setup_command() {
+    let dir = temp_dir("missing");
+    let err = find_setup_files(&dir).unwrap_err().to_string();
+    assert!(err.contains("vm_pk not found"), "{}", err);
+    assert!(err.contains("Run the setup command first"), "{}", err);
+
+    // With only the proving key, the verifying key is reported
+    std::fs::write(dir.join("vm_pk"), b"pk").unwrap();
+    let err = find_setup_files(&dir).unwrap_err().to_string();
+    assert!(err.contains("vm_vk not found"), "{}", err);
+
+    std::fs::write(dir.join("vm_vk"), b"vk").unwrap();
+    let (vm_pk, vm_vk) = find_setup_files(&dir).unwrap();
+    assert_eq!(std::fs::read(vm_pk).unwrap(), b"pk");
+    assert_eq!(std::fs::read(vm_vk).unwrap(), b"vk");
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,120 @@
+// Forces mock proving failures and checks the provisional index attached to the
+// failed response: the natively computed value, marked `proven: false`, and only
+// in schema version 2. The golden vectors' indexes come from the recorded proofs.
+
+use human_index_lib::calculate_human_index;
+use prover::fixtures::{golden_vectors, Fixtures};
+use prover::mock::{MockProofGenerator, FAIL_PROOF_PREFIX};
+use prover::prover::{ProofBackend, ProofContext};
+use prover::types::{ProofError, ProverRequest, ProverResponse, ProvisionalIndex};
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+
+fn request(request_id: &str) -> ProverRequest {
+    serde_json::from_value(json!({
+        "request_id": request_id,
+        "verification_results": { "recaptcha_score": 8000, "sms_verified": 1, "bio_verified": 1 },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 },
+    }))
+    .unwrap()
+}
+
+/// Run `request` through the mock backend, which must fail, and build the failed response
+fn failed_response(request: &ProverRequest) -> ProverResponse {
+    let backend = MockProofGenerator::new(Duration::ZERO, Duration::from_secs(1));
+    let context = ProofContext::new(Instant::now() + Duration::from_secs(1), 0);
+    let error = backend
+        .generate_proof(request.clone(), &context)
+        .expect_err("forced proving failure");
+    ProverResponse::failed(
+        request.request_id.clone(),
+        ProofError {
+            error_type: error.error_type(),
+            message: error.to_string(),
+            details: None,
+        },
+        None,
+    )
+}
+
+#[test]
+fn forced_failure_carries_unproven_index() {
+    let request = request(&format!("{}provisional", FAIL_PROOF_PREFIX));
+    let expected = calculate_human_index(&request.verification_results, &request.public_inputs);
+    assert!(expected > 0);
+
+    let response = failed_response(&request).with_provisional_index(&request);
+    let encoded: Value = serde_json::from_slice(&response.encode(2).unwrap()).unwrap();
+    assert_eq!(encoded["status"], "failed");
+    assert_eq!(
+        encoded["provisional_index"],
+        json!({ "human_index": expected, "proven": false })
+    );
+    assert!(encoded.get("proof_data").is_none());
+
+    let decoded = ProverResponse::decode(&response.encode(2).unwrap()).unwrap();
+    assert_eq!(
+        decoded.response().unwrap().provisional_index,
+        Some(ProvisionalIndex {
+            human_index: expected,
+            proven: false
+        })
+    );
+}
+
+#[test]
+fn failure_without_flag_has_no_index() {
+    let request = request(&format!("{}plain", FAIL_PROOF_PREFIX));
+    let encoded: Value =
+        serde_json::from_slice(&failed_response(&request).encode(2).unwrap()).unwrap();
+    assert!(encoded.get("provisional_index").is_none());
+}
+
+#[test]
+fn schema_v1_drops_provisional_index() {
+    let request = request(&format!("{}legacy", FAIL_PROOF_PREFIX));
+    let response = failed_response(&request).with_provisional_index(&request);
+    let encoded: Value = serde_json::from_slice(&response.encode(1).unwrap()).unwrap();
+    assert!(encoded.get("provisional_index").is_none());
+}
+
+#[test]
+fn provisional_index_matches_recorded_proofs() {
+    let fixtures = Fixtures::workspace().expect("fixtures load");
+    for vector in golden_vectors().expect("golden vectors load") {
+        let proven = fixtures
+            .mock_proof(&vector.name)
+            .expect("mock proof fixture");
+        let mut request = vector.request();
+        request.request_id = format!("{}{}", FAIL_PROOF_PREFIX, vector.name);
+
+        let response = failed_response(&request).with_provisional_index(&request);
+        assert_eq!(
+            response.provisional_index,
+            Some(ProvisionalIndex {
+                human_index: proven.human_index,
+                proven: false
+            }),
+            "{}",
+            vector.name
+        );
+    }
+}
+
+#[test]
+fn below_threshold_response_carries_the_computed_index() {
+    let response = ProverResponse::below_threshold("low".to_string(), 48, 100, None);
+    let encoded: Value = serde_json::from_slice(&response.encode(2).unwrap()).unwrap();
+    assert_eq!(encoded["status"], "below_threshold");
+    assert_eq!(encoded["error"]["error_type"], "BelowThreshold");
+    assert_eq!(
+        encoded["provisional_index"],
+        json!({ "human_index": 48, "proven": false })
+    );
+    assert!(encoded.get("proof_data").is_none());
+
+    // v1 consumers only know failures
+    let encoded: Value = serde_json::from_slice(&response.encode(1).unwrap()).unwrap();
+    assert_eq!(encoded["status"], "failed");
+    assert_eq!(encoded["error"]["error_type"], "BelowThreshold");
+}
@@ -0,0 +1,120 @@
+// Decodes the public values committed in a `ProofData`, as a consumer holding only
+// the result message would, and checks each way a malformed value is reported.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use human_index_lib::{commit_weights, HumanIndexPublicInputs, PublicValuesError};
+use prover::error::{DecodeError, ServiceError};
+use prover::types::ProofData;
+use serde_json::json;
+
+const ISSUED_AT: u64 = 1_790_000_000;
+const VALID_UNTIL: u64 = 1_790_086_400;
+
+fn inputs() -> HumanIndexPublicInputs {
+    HumanIndexPublicInputs {
+        w1: 1500,
+        w2: 2000,
+        w3: 2500,
+        w4: 4000,
+        expected_output: 229,
+    }
+}
+
+/// The pv stream the guest commits, laid out by hand: the inputs, the output, the
+/// weights commitment and the validity window, little-endian
+fn pv_stream(computed_output: u32) -> Vec<u8> {
+    let inputs = inputs();
+    let mut stream = Vec::new();
+    for word in [
+        inputs.w1,
+        inputs.w2,
+        inputs.w3,
+        inputs.w4,
+        inputs.expected_output,
+        computed_output,
+    ] {
+        stream.extend_from_slice(&word.to_le_bytes());
+    }
+    stream.extend_from_slice(&commit_weights(&inputs));
+    stream.extend_from_slice(&ISSUED_AT.to_le_bytes());
+    stream.extend_from_slice(&VALID_UNTIL.to_le_bytes());
+    stream
+}
+
+/// A `ProofData` as received in a result message, with `public_inputs` as given
+fn proof_data(public_inputs: &str) -> ProofData {
+    serde_json::from_value(json!({
+        "proof": STANDARD.encode(b"[]"),
+        "public_inputs": public_inputs,
+        "verification_key": STANDARD.encode(format!("0x{}", "42".repeat(32))),
+        "human_index": 229,
+    }))
+    .unwrap()
+}
+
+fn encode(stream: &[u8]) -> String {
+    STANDARD.encode(format!("0x{}", hex::encode(stream)))
+}
+
+#[test]
+fn sample_proof_data_decodes_to_the_committed_values() {
+    let proof_data = proof_data(&encode(&pv_stream(229)));
+    let public_values = proof_data.public_values().unwrap();
+
+    assert_eq!(public_values.inputs, inputs());
+    assert_eq!(public_values.computed_output, 229);
+    assert_eq!(public_values.weights_commitment, commit_weights(&inputs()));
+    assert_eq!(public_values.validity.issued_at, ISSUED_AT);
+    assert_eq!(public_values.validity.valid_until, VALID_UNTIL);
+
+    // What a consumer checks before trusting the reported index
+    assert_eq!(public_values.computed_output, proof_data.human_index);
+}
+
+#[test]
+fn malformed_public_values_name_the_failing_layer() {
+    let err = proof_data("not base64!").public_values().unwrap_err();
+    assert!(matches!(err, DecodeError::Base64(_)), "{}", err);
+
+    let err = proof_data(&STANDARD.encode("0xzz"))
+        .public_values()
+        .unwrap_err();
+    assert!(matches!(err, DecodeError::Hex(_)), "{}", err);
+
+    // Truncated before the validity window
+    let stream = pv_stream(229);
+    let err = proof_data(&encode(&stream[..56]))
+        .public_values()
+        .unwrap_err();
+    assert!(matches!(err, DecodeError::Layout(_)), "{}", err);
+
+    let err = proof_data(&encode(&pv_stream(256)))
+        .public_values()
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        DecodeError::PublicValues(PublicValuesError::OutputOutOfRange {
+            computed_output: 256
+        })
+    ));
+}
+
+#[test]
+fn decode_errors_convert_to_service_errors() {
+    let err: ServiceError = DecodeError::PublicValues(PublicValuesError::OutputOutOfRange {
+        computed_output: 256,
+    })
+    .into();
+    assert_eq!(err.error_type(), "PublicValuesError");
+
+    let err: ServiceError = proof_data("not base64!")
+        .public_values()
+        .unwrap_err()
+        .into();
+    assert_eq!(err.error_type(), "ProofGenerationError");
+    assert!(
+        err.to_string().contains("Invalid base64 public values"),
+        "{}",
+        err
+    );
+}
@@ -0,0 +1,116 @@
+// Before a proof is reported, the public values it commits are decoded and compared
+// field by field with the request: the weights, the expected output, the index the
+// host computed and the validity window. Any drift between host and guest, such as
+// reading stdin in another order, fails the proof naming the differing fields.
+
+use human_index_lib::{calculate_human_index, commit_weights, ProofInputs, PublicValues};
+use prover::error::ServiceError;
+use prover::fixtures::{golden_vectors, GoldenVector, FIXTURE_VALIDITY};
+use prover::prover::{check_public_values, encode_proof_data, expected_public_values};
+use prover::types::ProofData;
+
+fn proof_inputs(vector: &GoldenVector) -> ProofInputs {
+    ProofInputs::new(
+        vector.verification_results.clone(),
+        vector.public_inputs.clone(),
+        FIXTURE_VALIDITY,
+    )
+}
+
+/// A `ProofData` committing `public_values`, reporting the host's expected output
+fn proof_data(public_values: &PublicValues, human_index: u32) -> ProofData {
+    let pv_stream = bincode::serialize(public_values).unwrap();
+    encode_proof_data(
+        &[] as &[String],
+        &format!("0x{}", hex::encode(pv_stream)),
+        "",
+        human_index,
+        &public_values.weights_commitment,
+    )
+    .unwrap()
+}
+
+/// What the guest commits after reading `stdin` words back, as `app/src/main.rs`
+fn guest_commits(stdin: Vec<u32>) -> PublicValues {
+    let mut words = stdin.into_iter();
+    let read = ProofInputs::read_from(|| words.next().unwrap());
+    PublicValues {
+        computed_output: calculate_human_index(&read.results, &read.inputs),
+        weights_commitment: commit_weights(&read.inputs),
+        inputs: read.inputs,
+        validity: read.validity,
+    }
+}
+
+fn stdin(proof_inputs: &ProofInputs) -> Vec<u32> {
+    let mut words = Vec::new();
+    proof_inputs.write_to(|word| words.push(*word));
+    words
+}
+
+#[test]
+fn golden_vectors_commit_what_the_request_asked_for() {
+    for vector in golden_vectors().unwrap() {
+        let proof_inputs = proof_inputs(&vector);
+        let committed = guest_commits(stdin(&proof_inputs));
+        let proof_data = proof_data(&committed, proof_inputs.expected_output);
+
+        check_public_values(&proof_data, &expected_public_values(&proof_inputs))
+            .unwrap_or_else(|e| panic!("{}: {}", vector.name, e));
+    }
+}
+
+#[test]
+fn one_altered_field_is_named() {
+    let vector = &golden_vectors().unwrap()[0];
+    let proof_inputs = proof_inputs(vector);
+    let mut committed = guest_commits(stdin(&proof_inputs));
+    committed.inputs.w2 += 1;
+    let proof_data = proof_data(&committed, proof_inputs.expected_output);
+
+    let err = check_public_values(&proof_data, &expected_public_values(&proof_inputs))
+        .expect_err("altered weight is caught");
+    assert_eq!(err.error_type(), "PublicValuesMismatch");
+    let ServiceError::PublicValuesMismatch(mismatch) = &err else {
+        panic!("unexpected error: {}", err);
+    };
+    assert_eq!(mismatch.fields(), ["w2"]);
+    assert_eq!(mismatch.0[0].expected, vector.public_inputs.w2.to_string());
+    assert_eq!(
+        mismatch.0[0].committed,
+        (vector.public_inputs.w2 + 1).to_string()
+    );
+    assert!(
+        err.to_string().contains(&format!(
+            "w2 (expected {}, committed {})",
+            vector.public_inputs.w2,
+            vector.public_inputs.w2 + 1
+        )),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn guest_reading_stdin_out_of_order_is_caught() {
+    // A guest swapping the first two weights proves a valid index over other weights
+    let vector = golden_vectors()
+        .unwrap()
+        .into_iter()
+        .find(|vector| vector.public_inputs.w1 != vector.public_inputs.w2)
+        .expect("a vector with distinct w1 and w2");
+    let proof_inputs = proof_inputs(&vector);
+    let mut words = stdin(&proof_inputs);
+    words.swap(3, 4);
+    let committed = guest_commits(words);
+    let proof_data = proof_data(&committed, proof_inputs.expected_output);
+
+    let err = check_public_values(&proof_data, &expected_public_values(&proof_inputs))
+        .expect_err("swapped weights are caught");
+    let ServiceError::PublicValuesMismatch(mismatch) = &err else {
+        panic!("unexpected error: {}", err);
+    };
+    let fields = mismatch.fields();
+    assert!(fields.starts_with(&["w1", "w2"]), "{:?}", fields);
+    assert!(fields.contains(&"weights_commitment"), "{:?}", fields);
+}
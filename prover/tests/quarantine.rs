@@ -0,0 +1,210 @@
+// Poison request quarantine with `MAX_PROCESSING_ATTEMPTS`: a request whose proofs
+// keep failing is proved that many times, then answered `PoisonRequest` without
+// reaching the backend and written to the quarantine directory. The answer is an
+// ordinary result, published and ACKed like any other rather than dropped as an
+// error. A success resets the count, and the quarantine binary releases a request
+// to be attempted again.
+
+use chrono::Utc;
+use prover::config::Config;
+use prover::error::ServiceError;
+use prover::mock::{MockProofGenerator, FAIL_PROOF_PREFIX};
+use prover::prover::{ProofBackend, ProofContext};
+use prover::quarantine::{request_hash, Quarantine};
+use prover::service::ProverService;
+use prover::types::{ProofData, ProofStatus, ProverRequest, ProverResponse, RequestIdFormat};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+const MAX_ATTEMPTS: u32 = 3;
+
+/// The mock, counting the proofs it is asked for
+struct CountingBackend {
+    mock: MockProofGenerator,
+    proofs: AtomicUsize,
+}
+
+impl ProofBackend for CountingBackend {
+    fn name(&self) -> &'static str {
+        "counting"
+    }
+
+    fn generate_proof(
+        &self,
+        request: ProverRequest,
+        context: &ProofContext,
+    ) -> Result<ProofData, ServiceError> {
+        self.proofs.fetch_add(1, Ordering::SeqCst);
+        self.mock.generate_proof(request, context)
+    }
+}
+
+fn config() -> Config {
+    static BASE: OnceLock<Config> = OnceLock::new();
+    let mut config = BASE
+        .get_or_init(|| {
+            std::env::set_var("GCP_PROJECT_ID", "quarantine-test");
+            std::env::set_var("PROVER_SUBSCRIPTION", "unused");
+            std::env::set_var("RESULT_TOPIC", "unused");
+            Config::from_env().expect("base configuration")
+        })
+        .clone();
+    config.memory_wait_secs = 0;
+    // The mock fails requests by ID prefix
+    config.request_id_format = RequestIdFormat::Any;
+    config
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("prover-quarantine-{}-{}", name, std::process::id()));
+    std::fs::remove_dir_all(&dir).ok();
+    dir
+}
+
+/// A request with the same inputs under every ID, so all attempts count together
+fn request(request_id: &str) -> ProverRequest {
+    serde_json::from_value(json!({
+        "request_id": request_id,
+        "verification_results": { "recaptcha_score": 8000, "sms_verified": 1, "bio_verified": 1 },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 },
+    }))
+    .unwrap()
+}
+
+fn failing(attempt: u32) -> ProverRequest {
+    request(&format!("{}{}", FAIL_PROOF_PREFIX, attempt))
+}
+
+/// Process `request`, returning the response and whether the backend proved it
+async fn process(quarantine: &Quarantine, request: &ProverRequest) -> (ProverResponse, bool) {
+    let backend = Arc::new(CountingBackend {
+        mock: MockProofGenerator::new(Duration::ZERO, Duration::from_secs(60)),
+        proofs: AtomicUsize::new(0),
+    });
+    let response = ProverService::process_message(
+        &serde_json::to_vec(request).unwrap(),
+        config(),
+        backend.clone(),
+        None,
+        Some(quarantine),
+        &CancellationToken::new(),
+        Utc::now(),
+    )
+    .await
+    .expect("quarantined requests are answered, not failed");
+    (response, backend.proofs.load(Ordering::SeqCst) > 0)
+}
+
+fn assert_poison(response: &ProverResponse, proved: bool) {
+    assert_eq!(response.status, ProofStatus::Failed);
+    let error = response.error.as_ref().unwrap();
+    assert_eq!(error.error_type, "PoisonRequest", "{}", error.message);
+    assert_eq!(
+        error.details.as_deref(),
+        Some(request_hash(&request("any")).as_str())
+    );
+    assert!(response.proof_data.is_none());
+    assert!(!proved, "quarantined request reached the backend");
+}
+
+/// Fail `attempts` times in a row, each proved and failed by the backend
+async fn fail(quarantine: &Quarantine, attempts: u32) {
+    for attempt in 1..=attempts {
+        let (response, proved) = process(quarantine, &failing(attempt)).await;
+        assert_eq!(response.status, ProofStatus::Failed, "attempt {}", attempt);
+        assert_eq!(
+            response.error.as_ref().unwrap().error_type,
+            "ProofGenerationError",
+            "attempt {}",
+            attempt
+        );
+        assert!(proved, "attempt {} was not proved", attempt);
+    }
+}
+
+fn quarantined_file(dir: &Path) -> PathBuf {
+    dir.join(format!("{}.json", request_hash(&request("any"))))
+}
+
+#[tokio::test]
+async fn quarantined_at_the_configured_attempt_count() {
+    let dir = temp_dir("threshold");
+    let quarantine = Quarantine::open(&dir, MAX_ATTEMPTS).unwrap();
+
+    fail(&quarantine, MAX_ATTEMPTS).await;
+    assert!(!quarantined_file(&dir).exists());
+    assert_eq!(quarantine.list().unwrap(), Vec::<String>::new());
+
+    let (response, proved) = process(&quarantine, &failing(MAX_ATTEMPTS + 1)).await;
+    assert_poison(&response, proved);
+    assert_eq!(quarantine.list().unwrap(), [request_hash(&request("any"))]);
+    let written: ProverRequest =
+        serde_json::from_slice(&std::fs::read(quarantined_file(&dir)).unwrap()).unwrap();
+    assert_eq!(written.request_id, failing(MAX_ATTEMPTS + 1).request_id);
+
+    // Never attempted again, even under a new ID that would succeed
+    let (response, proved) = process(&quarantine, &request("retry")).await;
+    assert_poison(&response, proved);
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[tokio::test]
+async fn success_resets_the_attempt_count() {
+    let dir = temp_dir("reset");
+    let quarantine = Quarantine::open(&dir, MAX_ATTEMPTS).unwrap();
+
+    fail(&quarantine, MAX_ATTEMPTS - 1).await;
+    let (response, proved) = process(&quarantine, &request("succeeds")).await;
+    assert_eq!(
+        response.status,
+        ProofStatus::Success,
+        "{:?}",
+        response.error
+    );
+    assert!(proved);
+
+    // A full set of attempts again before the request is quarantined
+    fail(&quarantine, MAX_ATTEMPTS).await;
+    let (response, proved) = process(&quarantine, &failing(MAX_ATTEMPTS + 1)).await;
+    assert_poison(&response, proved);
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[tokio::test]
+async fn released_requests_are_attempted_again() {
+    let dir = temp_dir("release");
+    let quarantine = Quarantine::open(&dir, MAX_ATTEMPTS).unwrap();
+    fail(&quarantine, MAX_ATTEMPTS).await;
+    let (response, proved) = process(&quarantine, &failing(MAX_ATTEMPTS + 1)).await;
+    assert_poison(&response, proved);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_quarantine"))
+        .arg("--dir")
+        .arg(&dir)
+        .arg("release")
+        .arg(request_hash(&request("any")))
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(!quarantined_file(&dir).exists());
+
+    let (response, proved) = process(&quarantine, &request("released")).await;
+    assert_eq!(
+        response.status,
+        ProofStatus::Success,
+        "{:?}",
+        response.error
+    );
+    assert!(proved);
+    std::fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,195 @@
+// `GET /recent` on the status server: the last responses a worker answered with,
+// oldest first, bounded by count and without proofs or anything else private.
+
+mod common;
+
+use prover::bulk::run_job;
+use prover::config::Config;
+use prover::delayed::DelayedQueue;
+use prover::endpoints::Endpoints;
+use prover::history::ResponseHistory;
+use prover::janitor::Janitor;
+use prover::mock::MockProofGenerator;
+use prover::provenance::Provenance;
+use prover::scheduler::{Lanes, Scheduler};
+use prover::status::{self, StatusState};
+use prover::types::{BulkJob, ProofError, ProverResponse};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Semaphore;
+use tokio_util::sync::CancellationToken;
+
+/// Serve the status endpoints reporting `history` and return their address
+async fn serve(name: &str, config: &Config, history: Arc<ResponseHistory>) -> std::net::SocketAddr {
+    let dir = std::env::temp_dir().join(format!(
+        "prover-recent-status-{}-{}",
+        name,
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    let scheduler = Scheduler::start(
+        Arc::new(Semaphore::new(1)),
+        Lanes {
+            capacity: 1,
+            interactive_reserved: 0,
+            batch_promotion: None,
+            queue_wait_warning: None,
+        },
+    );
+    let state = StatusState::new(
+        "mock",
+        None,
+        Provenance::collect(config, None),
+        scheduler,
+        Arc::new(DelayedQueue::open(&dir.join("delayed.sqlite")).unwrap()),
+        Arc::new(Janitor::new(&dir, &dir)),
+        Arc::new(Endpoints::new(config.endpoints(), 0)),
+    )
+    .with_history(history);
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let router = status::router(Arc::new(state));
+    tokio::spawn(async move { axum::serve(listener, router).await });
+    addr
+}
+
+async fn get_recent(addr: std::net::SocketAddr) -> (String, Value) {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(b"GET /recent HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+    let body = response.split("\r\n\r\n").nth(1).unwrap().to_string();
+    let recent = serde_json::from_str(&body).unwrap();
+    (body, recent)
+}
+
+#[tokio::test]
+async fn recent_returns_the_last_responses_in_order() {
+    let config = common::bulk_config();
+    let dir = std::env::temp_dir().join(format!("prover-recent-{}", std::process::id()));
+    std::fs::remove_dir_all(&dir).ok();
+    std::fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("input.jsonl");
+    std::fs::write(
+        &input,
+        "{\"recaptcha_score\": 8000, \"sms_verified\": 1, \"bio_verified\": 1}\n\
+         {\"recaptcha_score\": 7000, \"sms_verified\": 0, \"bio_verified\": 1}\n\
+         {\"recaptcha_score\": 6000, \"sms_verified\": 1, \"bio_verified\": 0}\n\
+         {\"recaptcha_score\": 5000, \"sms_verified\": 0, \"bio_verified\": 0}\n\
+         {\"recaptcha_score\": 4000, \"sms_verified\": 1, \"bio_verified\": 1}\n",
+    )
+    .unwrap();
+    let job = BulkJob {
+        request_id: "recent-job".to_string(),
+        input_uri: format!("file://{}", input.display()),
+        output_uri: format!("file://{}", dir.join("out").display()),
+        weights_profile: None,
+    };
+    let backend = Arc::new(MockProofGenerator::new(
+        Duration::from_millis(10),
+        Duration::from_secs(60),
+    ));
+
+    // Recorded as the service records bulk lines, keeping only three
+    let history = Arc::new(ResponseHistory::new(3));
+    let summary = run_job(
+        &job,
+        &config,
+        backend,
+        None,
+        None,
+        None,
+        &CancellationToken::new(),
+        |response| history.record(response),
+    )
+    .await
+    .unwrap();
+    assert_eq!(summary.succeeded, 5);
+
+    let addr = serve("order", &config, history).await;
+    let (body, recent) = get_recent(addr).await;
+    assert_eq!(recent["capacity"], 3);
+    let responses = recent["responses"].as_array().unwrap();
+    let request_ids: Vec<&str> = responses
+        .iter()
+        .map(|response| response["request_id"].as_str().unwrap())
+        .collect();
+    let expected: Vec<&str> = summary.proofs[2..]
+        .iter()
+        .map(|proof| proof.request_id.as_str())
+        .collect();
+    assert_eq!(request_ids, expected);
+    for response in responses {
+        assert_eq!(response["status"], "success");
+        assert_eq!(response["parent_request_id"], "recent-job");
+        assert!(response["duration_ms"].is_u64());
+        assert!(response["received_at"].is_string());
+    }
+
+    // Metadata only: no proof, public values or signals
+    for private in [
+        "proof_data",
+        "public_inputs",
+        "human_index",
+        "recaptcha",
+        "decoded_public_values",
+    ] {
+        assert!(!body.contains(private), "{} in {}", private, body);
+    }
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn errors_are_kept_as_their_type_only() {
+    let history = ResponseHistory::new(2);
+    let error = ProofError {
+        error_type: "ProofGenerationError".to_string(),
+        message: "Proof failed verification".to_string(),
+        details: Some("stderr of the prover".to_string()),
+    };
+    history.record(&ProverResponse::failed(
+        "failed-1".to_string(),
+        error.clone(),
+        None,
+    ));
+    let recent = history.recent();
+    assert_eq!(recent.len(), 1);
+    assert_eq!(
+        recent[0].error_type.as_deref(),
+        Some("ProofGenerationError")
+    );
+    assert!(recent[0].duration_ms.is_none());
+
+    // A capacity of 0 keeps nothing
+    let disabled = ResponseHistory::new(0);
+    disabled.record(&ProverResponse::failed("failed-2".to_string(), error, None));
+    assert!(disabled.recent().is_empty());
+}
+
+#[tokio::test]
+async fn below_threshold_responses_do_not_reveal_the_index() {
+    let history = Arc::new(ResponseHistory::new(2));
+    history.record(&ProverResponse::below_threshold(
+        "below-1".to_string(),
+        37,
+        120,
+        None,
+    ));
+
+    let addr = serve("below", &common::config(), history).await;
+    let (body, recent) = get_recent(addr).await;
+    let response = &recent["responses"][0];
+    assert_eq!(response["status"], "below_threshold");
+    assert_eq!(response["error_type"], "BelowThreshold");
+    assert!(response.get("error").is_none(), "{}", body);
+    assert!(!body.contains("37"), "{}", body);
+    assert!(!body.contains("MIN_PROVABLE_INDEX"), "{}", body);
+}
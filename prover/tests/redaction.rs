@@ -0,0 +1,174 @@
+// Requests and parse errors as they reach the logs: private inputs never appear,
+// while the request ID, weights and metadata stay readable.
+
+use prover::error::ServiceError;
+use prover::redact::{self, REDACTED};
+use prover::types::{Priority, ProverRequest, WorkItem};
+use serde_json::json;
+
+/// Distinctive private values, easy to spot in any output
+const RECAPTCHA: u32 = 7321;
+const SIGNAL_RECAPTCHA: u32 = 6543;
+
+fn request() -> ProverRequest {
+    serde_json::from_value(json!({
+        "request_id": "req-redact",
+        "client_id": "tenant-7",
+        "verification_results": {
+            "recaptcha_score": RECAPTCHA,
+            "sms_verified": 1,
+            "bio_verified": 1
+        },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 },
+        "signals": { "recaptcha": SIGNAL_RECAPTCHA, "w4": 4100 },
+        "weights_profile": "v1",
+        "binding_id": "user-42",
+    }))
+    .unwrap()
+}
+
+fn assert_private_values_absent(output: &str) {
+    for private in [
+        RECAPTCHA.to_string(),
+        SIGNAL_RECAPTCHA.to_string(),
+        "recaptcha_score".to_string(),
+        "sms_verified".to_string(),
+        "bio_verified".to_string(),
+    ] {
+        assert!(
+            !output.contains(&private),
+            "{} leaked into {}",
+            private,
+            output
+        );
+    }
+}
+
+#[test]
+fn formatted_request_redacts_private_inputs() {
+    let request = request();
+    let outputs = [
+        format!("{:?}", request),
+        format!("{:#?}", request),
+        format!("{:?}", request.loggable()),
+        format!("{}", request.loggable()),
+    ];
+    for output in &outputs {
+        assert_private_values_absent(output);
+        assert!(output.contains(REDACTED), "{}", output);
+        assert!(output.contains("req-redact"), "{}", output);
+        assert!(output.contains("1500"), "{}", output);
+    }
+
+    // Metadata and public signals stay readable in `Debug`
+    let debug = &outputs[0];
+    for public in ["tenant-7", "user-42", "v1", "4100"] {
+        assert!(debug.contains(public), "{} missing from {}", public, debug);
+    }
+}
+
+#[test]
+fn formatted_work_item_redacts_private_inputs() {
+    let item = WorkItem {
+        request: request(),
+        priority: Priority::Normal,
+        forwarded_at: "2026-10-16T09:41:07Z".to_string(),
+    };
+    assert_private_values_absent(&format!("{:?}", item));
+}
+
+/// The message of the error parsing `value` as a request
+fn parse_error(value: serde_json::Value) -> String {
+    let data = serde_json::to_vec(&value).unwrap();
+    match ProverRequest::from_json(&data) {
+        Err(ServiceError::MalformedRequest(message)) => message,
+        other => panic!("expected a malformed request, got {:?}", other),
+    }
+}
+
+#[test]
+fn parse_errors_name_the_field_but_not_the_value() {
+    let mut value = serde_json::to_value(request()).unwrap();
+    value["verification_results"]["recaptcha_score"] = json!("7321.5 secret");
+    let message = parse_error(value);
+    assert!(message.contains("/verification_results"), "{}", message);
+    assert!(
+        !message.contains("secret") && !message.contains("7321"),
+        "{}",
+        message
+    );
+
+    // An over-precise decimal fails once its enclosing object is read
+    let mut value = serde_json::to_value(request()).unwrap();
+    value["verification_results"]["recaptcha_score"] = json!(0.7321_5);
+    let message = parse_error(value);
+    assert!(message.contains("/verification_results"), "{}", message);
+    assert!(!message.contains("7321"), "{}", message);
+
+    let mut value = serde_json::to_value(request()).unwrap();
+    value["signals"]["recaptcha"] = json!("6543 secret");
+    let message = parse_error(value);
+    assert!(message.contains("/signals/recaptcha"), "{}", message);
+    assert!(
+        !message.contains("secret") && !message.contains("6543"),
+        "{}",
+        message
+    );
+
+    // Public fields keep what was expected, still without the value
+    let mut value = serde_json::to_value(request()).unwrap();
+    value["public_inputs"]["w1"] = json!("1500 typo");
+    let message = parse_error(value);
+    assert!(message.contains("/public_inputs/w1"), "{}", message);
+    assert!(message.contains("expected"), "{}", message);
+    assert!(!message.contains("typo"), "{}", message);
+}
+
+#[test]
+fn parse_errors_report_missing_fields_and_syntax() {
+    let mut value = serde_json::to_value(request()).unwrap();
+    value["verification_results"]
+        .as_object_mut()
+        .unwrap()
+        .remove("bio_verified");
+    let message = parse_error(value);
+    assert!(message.contains("/verification_results"), "{}", message);
+
+    let mut value = serde_json::to_value(request()).unwrap();
+    value.as_object_mut().unwrap().remove("request_id");
+    let message = parse_error(value);
+    assert!(
+        message.contains("missing field `request_id`"),
+        "{}",
+        message
+    );
+
+    let err = ProverRequest::from_json(
+        br#"{"request_id": "req", "verification_results": {"recaptcha_score": 7321"#,
+    )
+    .unwrap_err();
+    assert!(!err.to_string().contains("7321"), "{}", err);
+    assert!(err.to_string().contains("line 1"), "{}", err);
+}
+
+#[test]
+fn work_item_errors_redact_the_nested_request() {
+    let mut value = json!({
+        "request": serde_json::to_value(request()).unwrap(),
+        "priority": "normal",
+        "forwarded_at": "2026-10-16T09:41:07Z",
+    });
+    value["request"]["verification_results"]["sms_verified"] = json!("yes, 7321");
+    let err = redact::from_json::<WorkItem>(&serde_json::to_vec(&value).unwrap()).unwrap_err();
+    let message = err.to_string();
+    assert!(
+        message.contains("/request/verification_results"),
+        "{}",
+        message
+    );
+    assert!(
+        !message.contains("7321") && !message.contains("yes"),
+        "{}",
+        message
+    );
+}
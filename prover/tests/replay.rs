@@ -0,0 +1,158 @@
+// The `replay` binary in `--dry-run` mode against a recorded NDJSON request log: the
+// requests it would republish are the recorded ones unchanged, `--rewrite-ids`
+// derives new IDs and keeps the originals in `replay_of`, `--client` filters, and
+// unreadable lines are reported and fail the run.
+
+use prover::types::{derived_request_id, ProverRequest};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Requests as the service logged them, one per line
+const RECORDED: [&str; 3] = [
+    r#"{"request_id":"4f6c1d2e-8a1b-4c3d-9e0f-112233445566","verification_results":{"recaptcha_score":8000,"sms_verified":1,"bio_verified":0},"public_inputs":{"w1":1500,"w2":2000,"w3":2500,"w4":4000,"expected_output":142},"client_id":"acme"}"#,
+    r#"{"request_id":"5a7d2e3f-9b2c-4d4e-8f10-223344556677","verification_results":{"recaptcha_score":10000,"sms_verified":1,"bio_verified":1},"public_inputs":{"w1":1500,"w2":2000,"w3":2500,"w4":4000,"expected_output":255},"client_id":"globex","weight_class":2}"#,
+    r#"{"request_id":"6b8e3f40-ac3d-4e5f-9011-334455667788","verification_results":{"recaptcha_score":2000,"sms_verified":0,"bio_verified":0},"public_inputs":{"w1":1500,"w2":2000,"w3":2500,"w4":4000,"expected_output":48},"client_id":"acme"}"#,
+];
+
+fn recorded_log(name: &str, lines: &[&str]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "prover-replay-{}-{}.ndjson",
+        name,
+        std::process::id()
+    ));
+    std::fs::write(&path, lines.join("\n") + "\n").unwrap();
+    path
+}
+
+fn replay(args: &[&str], log: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_replay"))
+        .args(args)
+        .arg(log)
+        .output()
+        .unwrap()
+}
+
+/// The requests a dry run would send, as JSON
+fn sent(output: &Output) -> Vec<Value> {
+    String::from_utf8(output.stdout.clone())
+        .unwrap()
+        .lines()
+        .map(|line| {
+            serde_json::from_str::<ProverRequest>(line).expect("replayed request parses");
+            serde_json::from_str(line).unwrap()
+        })
+        .collect()
+}
+
+fn recorded(line: &str) -> Value {
+    serde_json::from_str(line).unwrap()
+}
+
+#[test]
+fn dry_run_sends_the_recorded_requests() {
+    let log = recorded_log("dry-run", &RECORDED);
+    let output = replay(&["--dry-run"], &log);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Dry run: 3 requests, 0 failed"));
+
+    assert_eq!(sent(&output).len(), RECORDED.len());
+    // Unchanged byte for byte
+    let stdout = String::from_utf8(output.stdout.clone()).unwrap();
+    let lines: Vec<_> = stdout.lines().collect();
+    assert_eq!(lines, RECORDED);
+    std::fs::remove_file(&log).ok();
+}
+
+#[test]
+fn rewritten_ids_keep_the_original_in_replay_of() {
+    let log = recorded_log("rewrite", &RECORDED[1..]);
+    let output = replay(&["--dry-run", "--rewrite-ids"], &log);
+    assert!(output.status.success());
+
+    let sent = sent(&output);
+    assert_eq!(sent.len(), 2);
+    for (mut replayed, line) in sent.into_iter().zip(&RECORDED[1..]) {
+        let original = recorded(line);
+        let request_id = replayed["request_id"].as_str().unwrap().to_string();
+        assert_ne!(replayed["request_id"], original["request_id"]);
+        assert_eq!(request_id.len(), derived_request_id("seed").len());
+        assert_eq!(replayed["replay_of"], original["request_id"]);
+
+        let fields = replayed.as_object_mut().unwrap();
+        fields.remove("replay_of");
+        fields.insert("request_id".to_string(), original["request_id"].clone());
+        assert_eq!(replayed, original);
+    }
+    std::fs::remove_file(&log).ok();
+}
+
+#[test]
+fn client_filter_keeps_only_that_clients_requests() {
+    let log = recorded_log("client", &RECORDED);
+    let output = replay(&["--dry-run", "--client", "acme"], &log);
+    assert!(output.status.success());
+
+    let ids: Vec<_> = sent(&output)
+        .iter()
+        .map(|request| request["request_id"].clone())
+        .collect();
+    assert_eq!(
+        ids,
+        [
+            recorded(RECORDED[0])["request_id"].clone(),
+            recorded(RECORDED[2])["request_id"].clone()
+        ]
+    );
+    std::fs::remove_file(&log).ok();
+}
+
+#[test]
+fn unreadable_lines_are_reported_and_fail_the_run() {
+    let log = recorded_log(
+        "unreadable",
+        &[RECORDED[1], "{\"request_id\": \"truncated", RECORDED[2]],
+    );
+    let output = replay(&["--dry-run"], &log);
+    assert!(!output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(&format!("FAILED {}:2:", log.display())),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("Dry run: 2 requests, 1 failed"),
+        "{}",
+        stderr
+    );
+    assert_eq!(sent(&output).len(), 2);
+
+    let missing = log.with_extension("missing");
+    let output = replay(&["--dry-run"], &missing);
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains(&format!("FAILED {}", missing.display()))
+    );
+    std::fs::remove_file(&log).ok();
+}
+
+#[test]
+fn publishing_needs_a_topic_and_a_positive_rate() {
+    let log = recorded_log("arguments", &RECORDED);
+
+    let output = replay(&[], &log);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--topic"));
+
+    let output = replay(&["--dry-run", "--rps", "0"], &log);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--rps must be a positive number"));
+    assert!(output.stdout.is_empty());
+    std::fs::remove_file(&log).ok();
+}
@@ -0,0 +1,142 @@
+// Proving the same request twice: the committed public values, human index, weights
+// commitment and verifying key must come out the same, and verify the same, whether
+// or not the backend reproduces the proof bytes too.
+
+use human_index_lib::calculate_human_index;
+use prover::error::ServiceError;
+use prover::fixtures::{golden_vectors, public_values, FIXTURE_VALIDITY};
+use prover::mock::MockProofGenerator;
+use prover::prover::{ProofBackend, ProofContext};
+use prover::reproduce::Deterministic;
+use prover::response_check::Outcome;
+use prover::types::{ProofData, ProverRequest};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Inside the window of the golden vector requests
+const NOW: u64 = FIXTURE_VALIDITY.issued_at + 60;
+
+fn deterministic() -> Deterministic<'static> {
+    Deterministic {
+        vk: None,
+        now: NOW,
+        timeout: Duration::from_secs(5),
+    }
+}
+
+fn mock() -> MockProofGenerator {
+    MockProofGenerator::new(Duration::ZERO, Duration::from_secs(5))
+}
+
+/// The mock backend, with each run changing the request as `vary` says
+struct Varying {
+    mock: MockProofGenerator,
+    runs: AtomicU64,
+    vary: fn(&mut ProverRequest, u64),
+}
+
+impl Varying {
+    fn new(vary: fn(&mut ProverRequest, u64)) -> Self {
+        Self {
+            mock: mock(),
+            runs: AtomicU64::new(0),
+            vary,
+        }
+    }
+}
+
+impl ProofBackend for Varying {
+    fn name(&self) -> &'static str {
+        "varying"
+    }
+
+    fn generate_proof(
+        &self,
+        mut request: ProverRequest,
+        context: &ProofContext,
+    ) -> Result<ProofData, ServiceError> {
+        (self.vary)(&mut request, self.runs.fetch_add(1, Ordering::SeqCst));
+        self.mock.generate_proof(request, context)
+    }
+}
+
+#[test]
+fn golden_vectors_reproduce_exactly_on_the_mock_backend() {
+    for vector in golden_vectors().unwrap() {
+        let reproduction = deterministic()
+            .reproduce(&mock(), vector.request(), 2)
+            .unwrap();
+        assert_eq!(reproduction.runs.len(), 2);
+        assert!(
+            reproduction.differences().is_empty(),
+            "{}: {:?}",
+            vector.name,
+            reproduction.differences()
+        );
+        assert!(reproduction.proofs_identical(), "{}", vector.name);
+
+        let expected = calculate_human_index(&vector.verification_results, &vector.public_inputs);
+        for run in &reproduction.runs {
+            assert!(run.passed, "{}: {:?}", vector.name, run.checks);
+            assert_eq!(run.proof_data.human_index, expected);
+        }
+    }
+}
+
+#[test]
+fn a_missing_issued_at_is_pinned_before_the_first_run() {
+    let vector = &golden_vectors().unwrap()[0];
+    let mut request = vector.request();
+    request.issued_at = None;
+    request.valid_until = None;
+
+    let reproduction = deterministic().reproduce(&mock(), request, 3).unwrap();
+    assert!(reproduction.differences().is_empty());
+    for run in &reproduction.runs {
+        let committed = public_values(&run.proof_data).unwrap();
+        assert_eq!(committed.validity.issued_at, NOW);
+    }
+}
+
+#[test]
+fn randomized_proof_bytes_still_reproduce_the_committed_values() {
+    // An unrelated field feeds the mock's proof words, as randomness feeds a real proof
+    let backend = Varying::new(|request, run| request.trace_id = Some(format!("run-{}", run)));
+    let vector = &golden_vectors().unwrap()[0];
+    let reproduction = deterministic()
+        .reproduce(&backend, vector.request(), 2)
+        .unwrap();
+
+    assert!(!reproduction.proofs_identical());
+    assert!(reproduction.differences().is_empty());
+    assert_eq!(
+        reproduction.runs[0].checks, reproduction.runs[1].checks,
+        "both proofs verify the same"
+    );
+    assert!(reproduction.runs.iter().all(|run| run.passed));
+}
+
+#[test]
+fn drifting_public_values_are_reported() {
+    let backend = Varying::new(|request, run| {
+        request.issued_at = request.issued_at.map(|issued_at| issued_at + run)
+    });
+    let vector = &golden_vectors().unwrap()[0];
+    let reproduction = deterministic()
+        .reproduce(&backend, vector.request(), 2)
+        .unwrap();
+    assert_eq!(reproduction.differences(), ["public_inputs"]);
+    assert!(reproduction.runs.iter().all(|run| run
+        .checks
+        .iter()
+        .all(|(_, outcome)| *outcome != Outcome::Fail)));
+}
+
+#[test]
+fn a_single_run_is_rejected() {
+    let vector = &golden_vectors().unwrap()[0];
+    assert!(matches!(
+        deterministic().reproduce(&mock(), vector.request(), 1),
+        Err(ServiceError::Config(_))
+    ));
+}
@@ -0,0 +1,180 @@
+// Requests built with `ProverRequest::builder()` from decimal inputs: they must pass
+// the service's own checks unchanged, and a request the service would reject must
+// fail to build with the same complaint.
+
+use human_index_lib::SCALE;
+use prover::canonical::canonical_json;
+use prover::error::ServiceError;
+use prover::quarantine::request_hash;
+use prover::schema::RequestValidator;
+use prover::types::{ProverRequest, RequestIdFormat, SignalPolicy};
+
+/// Run `json` through the checks the service applies to a received message
+fn assert_service_accepts(json: &[u8]) {
+    let value: serde_json::Value = serde_json::from_slice(json).unwrap();
+    assert_eq!(RequestValidator::new().validate(&value), []);
+
+    let request = ProverRequest::from_json(json)
+        .and_then(ProverRequest::resolve_inputs)
+        .expect("service parses the request");
+    RequestIdFormat::Uuid.check(&request.request_id).unwrap();
+    let mut results = request.verification_results;
+    SignalPolicy::Reject.apply(&mut results).unwrap();
+}
+
+fn malformed(result: Result<impl std::fmt::Debug, ServiceError>) -> String {
+    match result.expect_err("request is invalid") {
+        ServiceError::MalformedRequest(message) => message,
+        e => panic!("unexpected error: {}", e),
+    }
+}
+
+#[test]
+fn built_requests_pass_the_service_checks() {
+    let built = ProverRequest::builder()
+        .score("0.57")
+        .sms_verified(true)
+        .bio_verified(false)
+        .weights([0.15, 0.2, 0.25, 0.4])
+        .binding_id("user-42")
+        .build()
+        .unwrap();
+    assert_service_accepts(&built.json);
+
+    let request = &built.request;
+    // 0.57 * 10000.0 is 5699.999… in floating point; scaling still gives 5700
+    assert_eq!(request.verification_results.recaptcha_score, 5700);
+    assert_eq!(request.verification_results.sms_verified, 1);
+    assert_eq!(request.verification_results.bio_verified, 0);
+    let inputs = &request.public_inputs;
+    assert_eq!(
+        [inputs.w1, inputs.w2, inputs.w3, inputs.w4],
+        [1500, 2000, 2500, 4000]
+    );
+    assert_eq!(request.binding_id.as_deref(), Some("user-42"));
+
+    assert_eq!(built.json, canonical_json(request).unwrap());
+    assert_eq!(built.hash, request_hash(request));
+}
+
+#[test]
+fn strings_and_floats_scale_alike() {
+    let build = |score: &str, weights: [&str; 4]| {
+        ProverRequest::builder()
+            .request_id("6f1c2a9e-8d4b-4e3a-9c1f-2b7d5e8a4c10")
+            .score(score)
+            .weights(weights)
+            .build()
+            .unwrap()
+    };
+    let from_strings = build(" 1.0 ", ["0.15", "0.2", "0.25", "1.5"]);
+    let from_floats = ProverRequest::builder()
+        .request_id("6f1c2a9e-8d4b-4e3a-9c1f-2b7d5e8a4c10")
+        .score(1.0)
+        .weights([0.15, 0.2, 0.25, 1.5])
+        .build()
+        .unwrap();
+    assert_eq!(from_strings.json, from_floats.json);
+    assert_eq!(from_strings.hash, from_floats.hash);
+    assert_eq!(
+        from_strings.request.verification_results.recaptcha_score,
+        SCALE
+    );
+    // Weights may exceed 1.0
+    assert_eq!(from_strings.request.public_inputs.w4, 15000);
+}
+
+#[test]
+fn generated_request_ids_are_unique_uuids() {
+    let build = || {
+        ProverRequest::builder()
+            .score(0.5)
+            .weights([0.25; 4])
+            .build()
+            .unwrap()
+    };
+    let (first, second) = (build(), build());
+    for built in [&first, &second] {
+        RequestIdFormat::Uuid
+            .check(&built.request.request_id)
+            .unwrap();
+    }
+    assert_ne!(first.request.request_id, second.request.request_id);
+    // Only the inputs are hashed, so the same inputs are recognized under any ID
+    assert_eq!(first.hash, second.hash);
+}
+
+#[test]
+fn a_weights_profile_stands_in_for_weights() {
+    let built = ProverRequest::builder()
+        .score(0.8)
+        .weights_profile("v1")
+        .build()
+        .unwrap();
+    assert_service_accepts(&built.json);
+
+    let message = malformed(
+        ProverRequest::builder()
+            .score(0.8)
+            .weights_profile("nonexistent")
+            .build(),
+    );
+    assert!(message.starts_with("/weights_profile:"), "{}", message);
+}
+
+#[test]
+fn invalid_inputs_fail_at_the_publisher() {
+    let builder = || {
+        ProverRequest::builder()
+            .score(0.8)
+            .weights([0.15, 0.2, 0.25, 0.4])
+    };
+    let cases = [
+        (builder().score(1.5).build(), "recaptcha_score: "),
+        (builder().score("0.8x").build(), "recaptcha_score: '0.8x'"),
+        (
+            builder().weights(["0.1", "0.2", "0.00001", "0.4"]).build(),
+            "w3: ",
+        ),
+        (
+            builder().weights([0.0; 4]).build(),
+            "/public_inputs: weights sum to zero",
+        ),
+        (
+            builder().expected_output(256).build(),
+            "/public_inputs/expected_output: ",
+        ),
+        (builder().validity(100, 100).build(), "/valid_until: "),
+        (
+            ProverRequest::builder().weights([0.25; 4]).build(),
+            "recaptcha_score is not set",
+        ),
+    ];
+    for (result, expected) in cases {
+        let message = malformed(result);
+        assert!(message.starts_with(expected), "{}", message);
+    }
+}
+
+#[test]
+fn the_request_id_format_is_checked() {
+    let request = || {
+        ProverRequest::builder()
+            .request_id("order-1234")
+            .score(0.8)
+            .weights([0.15, 0.2, 0.25, 0.4])
+    };
+    let message = malformed(request().build());
+    assert!(message.contains("REQUEST_ID_FORMAT=any"), "{}", message);
+    assert!(request()
+        .request_id_format(RequestIdFormat::Any)
+        .build()
+        .is_ok());
+}
+
+#[test]
+fn the_example_is_valid() {
+    let example = ProverRequest::example();
+    assert_service_accepts(&canonical_json(&example).unwrap());
+    assert_eq!(example.request_id, "6f1c2a9e-8d4b-4e3a-9c1f-2b7d5e8a4c10");
+}
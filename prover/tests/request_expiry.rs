@@ -0,0 +1,151 @@
+// Stale-request rejection with `MAX_REQUEST_AGE_SECS`: a request submitted within
+// the maximum age is proved, one submitted earlier is answered `Expired` without
+// reaching the backend, and requests without a usable `submitted_at` are proved.
+
+use chrono::{DateTime, TimeDelta, Utc};
+use prover::config::Config;
+use prover::error::ServiceError;
+use prover::mock::MockProofGenerator;
+use prover::prover::{ProofBackend, ProofContext};
+use prover::service::ProverService;
+use prover::types::{ProofData, ProofStatus, ProverRequest, ProverResponse};
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+const MAX_AGE_SECS: u64 = 60;
+
+/// The mock, counting the proofs it was asked for
+struct CountingBackend {
+    mock: MockProofGenerator,
+    proofs: AtomicUsize,
+}
+
+impl ProofBackend for CountingBackend {
+    fn name(&self) -> &'static str {
+        "counting"
+    }
+
+    fn generate_proof(
+        &self,
+        request: ProverRequest,
+        context: &ProofContext,
+    ) -> Result<ProofData, ServiceError> {
+        self.proofs.fetch_add(1, Ordering::SeqCst);
+        self.mock.generate_proof(request, context)
+    }
+}
+
+fn config(max_request_age_secs: Option<u64>) -> Config {
+    static BASE: OnceLock<Config> = OnceLock::new();
+    let mut config = BASE
+        .get_or_init(|| {
+            std::env::set_var("GCP_PROJECT_ID", "expiry-test");
+            std::env::set_var("PROVER_SUBSCRIPTION", "unused");
+            std::env::set_var("RESULT_TOPIC", "unused");
+            Config::from_env().expect("base configuration")
+        })
+        .clone();
+    config.max_request_age_secs = max_request_age_secs;
+    config
+}
+
+fn received_at() -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339("2026-10-16T12:00:00Z")
+        .unwrap()
+        .to_utc()
+}
+
+/// A request submitted `age_secs` before `received_at`
+fn submitted(age_secs: i64) -> String {
+    (received_at() - TimeDelta::seconds(age_secs)).to_rfc3339()
+}
+
+/// Process a request submitted at `submitted_at`, returning the response and
+/// whether the backend was asked for a proof
+async fn process(
+    submitted_at: Option<&str>,
+    max_request_age_secs: Option<u64>,
+) -> (ProverResponse, bool) {
+    let mut request = json!({
+        "request_id": "7c9e6679-7425-40de-944b-e07fc1f90ae7",
+        "verification_results": { "recaptcha_score": 8000, "sms_verified": 1, "bio_verified": 1 },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 },
+    });
+    if let Some(submitted_at) = submitted_at {
+        request["submitted_at"] = json!(submitted_at);
+    }
+    let backend = Arc::new(CountingBackend {
+        mock: MockProofGenerator::new(Duration::ZERO, Duration::from_secs(60)),
+        proofs: AtomicUsize::new(0),
+    });
+    let response = ProverService::process_message(
+        &serde_json::to_vec(&request).unwrap(),
+        config(max_request_age_secs),
+        backend.clone(),
+        None,
+        None,
+        &CancellationToken::new(),
+        received_at(),
+    )
+    .await
+    .expect("request parses");
+    (response, backend.proofs.load(Ordering::SeqCst) > 0)
+}
+
+#[tokio::test]
+async fn fresh_requests_are_proved() {
+    for age_secs in [0, 10, MAX_AGE_SECS as i64] {
+        let (response, proved) = process(Some(&submitted(age_secs)), Some(MAX_AGE_SECS)).await;
+        assert_eq!(
+            response.status,
+            ProofStatus::Success,
+            "{}s old: {:?}",
+            age_secs,
+            response.error
+        );
+        assert!(proved);
+    }
+}
+
+#[tokio::test]
+async fn expired_requests_are_rejected_without_proving() {
+    for age_secs in [MAX_AGE_SECS as i64 + 1, 3600, 86_400 * 3] {
+        let (response, proved) = process(Some(&submitted(age_secs)), Some(MAX_AGE_SECS)).await;
+        assert_eq!(response.status, ProofStatus::Failed);
+        assert!(!proved, "{}s old request was proved", age_secs);
+
+        let error = response.error.as_ref().unwrap();
+        assert_eq!(error.error_type, "Expired");
+        assert_eq!(
+            error.message,
+            format!(
+                "Request was submitted {}s ago, more than MAX_REQUEST_AGE_SECS ({}s)",
+                age_secs, MAX_AGE_SECS
+            )
+        );
+        let metrics = response.metrics.as_ref().unwrap();
+        assert_eq!(metrics.received_at, received_at().to_rfc3339());
+        assert_eq!(metrics.duration_ms, 0);
+        assert!(response.proof_data.is_none());
+    }
+}
+
+#[tokio::test]
+async fn requests_without_a_usable_submission_time_are_proved() {
+    let cases = [
+        // No maximum age
+        (Some(submitted(86_400)), None),
+        // Nothing to measure the age from
+        (None, Some(MAX_AGE_SECS)),
+        // An unparseable time is ignored rather than failing the request
+        (Some("yesterday".to_string()), Some(MAX_AGE_SECS)),
+    ];
+    for (submitted_at, max_request_age_secs) in cases {
+        let (response, proved) = process(submitted_at.as_deref(), max_request_age_secs).await;
+        assert_eq!(response.status, ProofStatus::Success, "{:?}", submitted_at);
+        assert!(proved);
+    }
+}
@@ -0,0 +1,243 @@
+// Request identifiers: `request_id` is the only key a request is recognized by,
+// while `trace_id` and `parent_request_id` are carried through the backend's logs
+// and echoed in the response without affecting deduplication. IDs that could
+// leave the output directory are rejected in any format, and request directories
+// are never named by the raw ID.
+
+use prover::mock::MockProofGenerator;
+use prover::prover::{request_dir_name, ProofBackend, ProofContext};
+use prover::quarantine::request_hash;
+use prover::types::{
+    derived_request_id, ProverRequest, ProverResponse, RequestIdFormat, MAX_REQUEST_ID_LEN,
+};
+use serde_json::{json, Value};
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+fn request(request_id: &str, trace_id: Option<&str>) -> ProverRequest {
+    serde_json::from_value(json!({
+        "request_id": request_id,
+        "trace_id": trace_id,
+        "parent_request_id": trace_id.map(|trace_id| format!("parent-of-{}", trace_id)),
+        "verification_results": { "recaptcha_score": 8000, "sms_verified": 1, "bio_verified": 1 },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 },
+    }))
+    .unwrap()
+}
+
+/// Log output shared with the subscriber
+#[derive(Clone, Default)]
+struct Logs(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Logs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn uuid_format_accepts_uuids_with_an_optional_tenant() {
+    for request_id in [
+        "0b9e4c1e-5f43-4d8a-9a51-7c2f0e6d1b3a",
+        "0B9E4C1E5F434D8A9A517C2F0E6D1B3A",
+        "tenant-a:0b9e4c1e-5f43-4d8a-9a51-7c2f0e6d1b3a",
+    ] {
+        assert_eq!(
+            RequestIdFormat::Uuid.check(request_id),
+            Ok(()),
+            "{}",
+            request_id
+        );
+    }
+    for request_id in [
+        "",
+        "req-1",
+        "fail-proof-1",
+        "0b9e4c1e-5f43-4d8a-9a51-7c2f0e6d1b3",
+        "0b9e4c1e5f43-4d8a-9a51-7c2f0e6d1b3a",
+        "0b9e4c1e-5f43-4d8a-9a51-7c2f0e6d1b3g",
+        "0b9e4c1e-5f43-4d8a-9a51-7c2f0e6d1b3a-replay-1",
+    ] {
+        assert!(
+            RequestIdFormat::Uuid.check(request_id).is_err(),
+            "{}",
+            request_id
+        );
+    }
+
+    assert_eq!(RequestIdFormat::Any.check("fail-proof-1"), Ok(()));
+    assert!(RequestIdFormat::Any.check("").is_err());
+    assert_eq!(
+        "ANY".parse::<RequestIdFormat>().unwrap(),
+        RequestIdFormat::Any
+    );
+    assert!("ulid".parse::<RequestIdFormat>().is_err());
+}
+
+/// IDs no format accepts: paths, empty, non-ASCII, too long
+fn unsafe_ids() -> Vec<String> {
+    let mut ids: Vec<String> = [
+        "../../etc/cron.d/x",
+        "..",
+        "req/1",
+        "req\\1",
+        "/etc/passwd",
+        "tenant-a:../0b9e4c1e-5f43-4d8a-9a51-7c2f0e6d1b3a",
+        "a:b:c",
+        ":req-1",
+        "req-1:",
+        "",
+        " ",
+        "req 1",
+        "req-1\n",
+        "req\0",
+        "réq-1",
+        "заявка",
+        "req-1\u{202e}",
+        "🙂",
+    ]
+    .iter()
+    .map(|id| id.to_string())
+    .collect();
+    ids.push("a".repeat(4096));
+    ids.push("b".repeat(MAX_REQUEST_ID_LEN + 1));
+    ids
+}
+
+#[test]
+fn unsafe_ids_are_rejected_in_any_format() {
+    for request_id in unsafe_ids() {
+        for format in [RequestIdFormat::Uuid, RequestIdFormat::Any] {
+            assert!(
+                format.check(&request_id).is_err(),
+                "{:?} accepted {:?}",
+                format,
+                request_id
+            );
+        }
+    }
+    // The error does not echo an ID that long
+    let error = RequestIdFormat::Any.check(&"a".repeat(4096)).unwrap_err();
+    assert!(error.len() < 100, "{}", error);
+
+    for request_id in [
+        "req-1",
+        "Req_1-a",
+        "acme:job-42",
+        &"c".repeat(MAX_REQUEST_ID_LEN),
+    ] {
+        assert_eq!(
+            RequestIdFormat::Any.check(request_id),
+            Ok(()),
+            "{}",
+            request_id
+        );
+    }
+}
+
+#[test]
+fn request_dirs_stay_in_the_output_directory() {
+    let mut names = Vec::new();
+    for request_id in unsafe_ids()
+        .iter()
+        .map(String::as_str)
+        .chain(["req-1", "req_1"])
+    {
+        let name = request_dir_name(request_id);
+        assert!(
+            !name.is_empty()
+                && name.len() <= 64 + 17
+                && name
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-'),
+            "{:?} -> {:?}",
+            request_id,
+            name
+        );
+        assert!(!name.starts_with('-'), "{}", name);
+        assert_eq!(name, request_dir_name(request_id));
+        names.push(name);
+    }
+    // Distinct IDs get distinct directories, even with the same safe characters
+    let count = names.len();
+    names.sort();
+    names.dedup();
+    assert_eq!(names.len(), count);
+    assert_ne!(request_dir_name("req/1"), request_dir_name("req1"));
+    assert!(request_dir_name("req-1").starts_with("req-1-"));
+}
+
+#[test]
+fn derived_request_ids_are_stable_uuids() {
+    let id = derived_request_id("req-1-replay-1790000000");
+    assert_eq!(RequestIdFormat::Uuid.check(&id), Ok(()));
+    assert_eq!(id, derived_request_id("req-1-replay-1790000000"));
+    assert_ne!(id, derived_request_id("req-1-replay-1790000001"));
+    // Version 8, RFC 9562 variant
+    assert_eq!(&id[14..15], "8");
+    assert!("89ab".contains(&id[19..20]), "{}", id);
+}
+
+#[test]
+fn dedup_keys_ignore_trace_ids() {
+    let plain = request("req-1", None);
+    let traced = request("req-1", Some("trace-a"));
+    let retraced = request("req-2", Some("trace-b"));
+    assert_eq!(request_hash(&plain), request_hash(&traced));
+    assert_eq!(request_hash(&traced), request_hash(&retraced));
+}
+
+#[test]
+fn identifiers_reach_the_backend_logs_and_the_response() {
+    let request = request("req-traced", Some("trace-7"));
+    let backend = MockProofGenerator::new(Duration::ZERO, Duration::from_secs(1));
+    let context = ProofContext::new(Instant::now() + Duration::from_secs(1), 0);
+
+    // As `process_message` runs the backend: in the request's span
+    let logs = Logs::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::DEBUG)
+        .with_ansi(false)
+        .with_writer({
+            let logs = logs.clone();
+            move || logs.clone()
+        })
+        .finish();
+    tracing::subscriber::with_default(subscriber, || {
+        request
+            .span()
+            .in_scope(|| backend.generate_proof(request.clone(), &context))
+    })
+    .unwrap();
+
+    let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+    let line = logs
+        .lines()
+        .find(|line| line.contains("Mock proof generated"))
+        .expect("backend log line");
+    for field in [
+        "request_id=req-traced",
+        "trace_id=\"trace-7\"",
+        "parent_request_id=\"parent-of-trace-7\"",
+    ] {
+        assert!(line.contains(field), "{} missing from {}", field, line);
+    }
+
+    let mut response = ProverResponse::interrupted(request.request_id.clone(), String::new(), None);
+    response.correlate(&request);
+    let encoded: Value = serde_json::from_slice(&response.encode(2).unwrap()).unwrap();
+    assert_eq!(encoded["request_id"], "req-traced");
+    assert_eq!(encoded["trace_id"], "trace-7");
+    assert_eq!(encoded["parent_request_id"], "parent-of-trace-7");
+
+    // v1 keeps its exact field set
+    let encoded: Value = serde_json::from_slice(&response.encode(1).unwrap()).unwrap();
+    assert!(encoded.get("trace_id").is_none());
+    assert!(encoded.get("parent_request_id").is_none());
+}
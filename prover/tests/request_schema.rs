@@ -0,0 +1,221 @@
+// Validates known-bad request payloads, the kind non-Rust publishers send, and
+// checks the exact JSON pointer each violation is reported at.
+
+use prover::fixtures::golden_vectors;
+use prover::schema::{schemas, RequestValidator, ViolationSource};
+use serde_json::{json, Value};
+
+fn valid_request() -> Value {
+    json!({
+        "request_id": "schema-check",
+        "verification_results": { "recaptcha_score": 8000, "sms_verified": 1, "bio_verified": 1 },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 },
+    })
+}
+
+/// `valid_request` with the value at `pointer` replaced, or removed for `None`
+fn with(pointer: &str, value: Option<Value>) -> Value {
+    let mut request = valid_request();
+    let (parent, field) = pointer.rsplit_once('/').unwrap();
+    let parent = request
+        .pointer_mut(parent)
+        .unwrap()
+        .as_object_mut()
+        .unwrap();
+    match value {
+        Some(value) => parent.insert(field.to_string(), value),
+        None => parent.remove(field),
+    };
+    request
+}
+
+/// Paths and sources of the violations reported for `request`
+fn violations(request: &Value) -> Vec<(String, ViolationSource)> {
+    RequestValidator::new()
+        .validate(request)
+        .into_iter()
+        .map(|violation| (violation.path, violation.source))
+        .collect()
+}
+
+fn schema_at(path: &str) -> Vec<(String, ViolationSource)> {
+    vec![(path.to_string(), ViolationSource::Schema)]
+}
+
+fn semantic_at(path: &str) -> Vec<(String, ViolationSource)> {
+    vec![(path.to_string(), ViolationSource::Semantic)]
+}
+
+#[test]
+fn valid_requests_have_no_violations() {
+    assert_eq!(violations(&valid_request()), vec![]);
+    let optional = with("/not_before", Some(json!("2026-01-01T00:00:00Z")));
+    assert_eq!(violations(&optional), vec![]);
+    for vector in golden_vectors().expect("golden vectors load") {
+        let request = serde_json::to_value(vector.request()).unwrap();
+        assert_eq!(violations(&request), vec![], "{}", vector.name);
+    }
+}
+
+#[test]
+fn string_for_u32_is_reported_at_the_field() {
+    let request = with("/public_inputs/w1", Some(json!("1500")));
+    assert_eq!(violations(&request), schema_at("/public_inputs/w1"));
+}
+
+#[test]
+fn over_precise_decimal_weight_is_reported_at_the_field() {
+    let request = with("/public_inputs/w2", Some(json!(0.12345)));
+    assert_eq!(violations(&request), semantic_at("/public_inputs/w2"));
+    let request = with("/public_inputs/w2", Some(json!(0.2)));
+    assert_eq!(violations(&request), vec![]);
+}
+
+#[test]
+fn decimal_score_above_one_is_reported_at_the_field() {
+    let request = with("/verification_results/recaptcha_score", Some(json!(1.5)));
+    assert_eq!(
+        violations(&request),
+        semantic_at("/verification_results/recaptcha_score")
+    );
+}
+
+#[test]
+fn negative_value_is_reported_at_the_field() {
+    let request = with("/verification_results/sms_verified", Some(json!(-1)));
+    assert_eq!(
+        violations(&request),
+        schema_at("/verification_results/sms_verified")
+    );
+}
+
+#[test]
+fn missing_field_is_reported_at_its_own_path() {
+    let request = with("/verification_results/bio_verified", None);
+    assert_eq!(
+        violations(&request),
+        schema_at("/verification_results/bio_verified")
+    );
+    let request = with("/request_id", None);
+    assert_eq!(violations(&request), schema_at("/request_id"));
+}
+
+#[test]
+fn every_schema_violation_is_reported() {
+    let mut request = with("/public_inputs/w3", Some(json!("2500")));
+    request["verification_results"]["recaptcha_score"] = json!("0.8");
+    request["priority"] = json!("urgent");
+    let mut paths: Vec<_> = violations(&request).into_iter().map(|(p, _)| p).collect();
+    paths.sort();
+    assert_eq!(
+        paths,
+        [
+            "/priority",
+            "/public_inputs/w3",
+            "/verification_results/recaptcha_score"
+        ]
+    );
+}
+
+#[test]
+fn recaptcha_score_above_scale_is_a_semantic_violation() {
+    let request = with("/verification_results/recaptcha_score", Some(json!(10_001)));
+    assert_eq!(
+        violations(&request),
+        semantic_at("/verification_results/recaptcha_score")
+    );
+}
+
+#[test]
+fn verified_flags_must_be_zero_or_one() {
+    let mut request = with("/verification_results/sms_verified", Some(json!(2)));
+    request["verification_results"]["bio_verified"] = json!(10_000);
+    assert_eq!(
+        violations(&request),
+        [
+            semantic_at("/verification_results/sms_verified"),
+            semantic_at("/verification_results/bio_verified"),
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn zero_weight_sum_is_reported_at_the_public_inputs() {
+    let mut request = valid_request();
+    for weight in ["w1", "w2", "w3", "w4"] {
+        request["public_inputs"][weight] = json!(0);
+    }
+    assert_eq!(violations(&request), semantic_at("/public_inputs"));
+
+    // Weights need not sum to SCALE
+    let request = with("/public_inputs/w4", Some(json!(6000)));
+    assert_eq!(violations(&request), vec![]);
+}
+
+#[test]
+fn weights_profile_replaces_the_weights() {
+    let mut request = valid_request();
+    request["public_inputs"] = json!({ "expected_output": 0 });
+    request["weights_profile"] = json!("v1");
+    assert_eq!(violations(&request), vec![]);
+
+    request["weights_profile"] = json!("v0");
+    assert_eq!(violations(&request), semantic_at("/weights_profile"));
+
+    // Without a profile, missing weights count as zero
+    request.as_object_mut().unwrap().remove("weights_profile");
+    assert_eq!(violations(&request), semantic_at("/public_inputs"));
+}
+
+#[test]
+fn expected_output_above_max_index_is_reported() {
+    let request = with("/public_inputs/expected_output", Some(json!(256)));
+    assert_eq!(
+        violations(&request),
+        semantic_at("/public_inputs/expected_output")
+    );
+}
+
+#[test]
+fn timestamps_must_be_rfc3339() {
+    let request = with("/not_before", Some(json!("tomorrow")));
+    assert_eq!(violations(&request), semantic_at("/not_before"));
+    let request = with("/submitted_at", Some(json!("2026-01-01 00:00")));
+    assert_eq!(violations(&request), semantic_at("/submitted_at"));
+}
+
+#[test]
+fn empty_request_id_is_reported() {
+    let request = with("/request_id", Some(json!("")));
+    assert_eq!(violations(&request), semantic_at("/request_id"));
+}
+
+#[test]
+fn written_schemas_describe_the_request_types() {
+    let schemas = schemas();
+    let files: Vec<_> = schemas.iter().map(|(file, _)| *file).collect();
+    assert_eq!(
+        files,
+        [
+            "prover_request.schema.json",
+            "prover_response.schema.json",
+            "human_index_public_inputs.schema.json",
+            "verification_results.schema.json"
+        ]
+    );
+    let request = schemas[0].1.as_value();
+    assert_eq!(
+        request["required"],
+        json!(["request_id", "verification_results", "public_inputs"])
+    );
+}
+
+#[test]
+fn written_request_schema_validates_samples() {
+    let schema = schemas().remove(0).1;
+    let validator = jsonschema::validator_for(schema.as_value()).unwrap();
+    assert!(validator.is_valid(&valid_request()));
+    assert!(!validator.is_valid(&with("/verification_results", None)));
+    assert!(!validator.is_valid(&with("/public_inputs/expected_output", None)));
+}
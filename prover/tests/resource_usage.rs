@@ -0,0 +1,196 @@
+// Per-proof resource accounting: the sampler's lifecycle, reading /proc, pricing
+// usage with COST_FORMULA, and how the figures appear in published metrics.
+
+use prover::error::ServiceError;
+use prover::types::{ProofMetrics, ResourceUsage};
+use prover::usage::{
+    parse_cpu_time, parse_rss_bytes, CostFormula, CostTerm, ProcessReading, UsageSampler,
+};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+const INTERVAL: Duration = Duration::from_millis(10);
+
+#[test]
+fn sampler_reads_until_finished() {
+    let reads = Arc::new(AtomicU64::new(0));
+    let counter = reads.clone();
+    // RSS rises to 5 MiB at the fourth read, then falls back; CPU grows 10ms per read
+    let sampler = UsageSampler::start_with(INTERVAL, move || {
+        let n = counter.fetch_add(1, Ordering::SeqCst);
+        ProcessReading {
+            rss_bytes: Some(if n == 3 { 5 << 20 } else { 1 << 20 }),
+            cpu_time: Some(Duration::from_millis(10 * n)),
+        }
+    });
+
+    std::thread::sleep(INTERVAL * 20);
+    let sample = sampler.finish();
+    let after_finish = reads.load(Ordering::SeqCst);
+    assert!(after_finish > 4, "only {} reads", after_finish);
+    assert_eq!(sample.peak_rss_bytes, Some(5 << 20));
+    // From the read at start (0ms) to the one in `finish`
+    assert_eq!(sample.cpu_time_ms, Some(10 * (after_finish - 1)));
+
+    // The thread has stopped
+    std::thread::sleep(INTERVAL * 5);
+    assert_eq!(reads.load(Ordering::SeqCst), after_finish);
+}
+
+#[test]
+fn dropped_sampler_stops_reading() {
+    let reads = Arc::new(AtomicU64::new(0));
+    let counter = reads.clone();
+    let sampler = UsageSampler::start_with(INTERVAL, move || {
+        counter.fetch_add(1, Ordering::SeqCst);
+        ProcessReading::default()
+    });
+    std::thread::sleep(INTERVAL * 3);
+    drop(sampler);
+
+    std::thread::sleep(INTERVAL * 3);
+    let stopped = reads.load(Ordering::SeqCst);
+    std::thread::sleep(INTERVAL * 5);
+    assert_eq!(reads.load(Ordering::SeqCst), stopped);
+}
+
+#[test]
+fn missing_proc_leaves_fields_unset() {
+    let sampler = UsageSampler::start_with(INTERVAL, ProcessReading::default);
+    std::thread::sleep(INTERVAL * 2);
+    let sample = sampler.finish();
+    assert_eq!(sample.peak_rss_bytes, None);
+    assert_eq!(sample.cpu_time_ms, None);
+}
+
+#[test]
+fn reads_this_process_where_proc_exists() {
+    let sample = UsageSampler::start().finish();
+    if std::path::Path::new("/proc/self/status").exists() {
+        assert!(sample.peak_rss_bytes.unwrap() > 0);
+        assert!(sample.cpu_time_ms.is_some());
+    } else {
+        assert_eq!(sample.peak_rss_bytes, None);
+    }
+}
+
+#[test]
+fn parses_proc_files() {
+    let status = "Name:\tprover\nVmPeak:\t  900000 kB\nVmRSS:\t  524288 kB\nThreads:\t8\n";
+    assert_eq!(parse_rss_bytes(status), Some(512 << 20));
+    assert_eq!(parse_rss_bytes("Name:\tprover\n"), None);
+
+    // A command name with spaces and parens does not shift the fields
+    let stat = "4242 (prover (x) y) S 1 4242 4242 0 -1 4194560 100 0 0 0 250 130 0 0 20 0 8 0";
+    assert_eq!(parse_cpu_time(stat), Some(Duration::from_millis(3800)));
+    assert_eq!(parse_cpu_time("4242 (prover) S 1"), None);
+}
+
+fn usage() -> ResourceUsage {
+    ResourceUsage {
+        peak_rss_bytes: Some(2 << 30),
+        cpu_time_ms: Some(90_000),
+        stdin_prepare_ms: Some(1),
+        guest_cycles: Some(3_000_000),
+        artifact_bytes_written: Some(1 << 20),
+    }
+}
+
+#[test]
+fn cost_formula_prices_usage() {
+    let formula: CostFormula = "cpu_seconds=1, peak_rss_gib=0.5,guest_mcycles=2,artifact_mib=0.25"
+        .parse()
+        .unwrap();
+    assert_eq!(formula.terms[1], (CostTerm::PeakRssGib, 0.5));
+    // 90 + 2 * 0.5 + 3 * 2 + 1 * 0.25
+    assert_eq!(formula.cost_units(&usage()), Some(97.25));
+
+    // CPU only by default
+    assert_eq!(CostFormula::default().cost_units(&usage()), Some(90.0));
+}
+
+#[test]
+fn cost_needs_every_priced_figure() {
+    let formula: CostFormula = "cpu_seconds=1,guest_mcycles=2".parse().unwrap();
+    let no_cycles = ResourceUsage {
+        guest_cycles: None,
+        ..usage()
+    };
+    assert_eq!(formula.cost_units(&no_cycles), None);
+
+    // A zero rate does not need its figure
+    let formula: CostFormula = "cpu_seconds=1,guest_mcycles=0".parse().unwrap();
+    assert_eq!(formula.cost_units(&no_cycles), Some(90.0));
+}
+
+#[test]
+fn invalid_cost_formula_is_rejected() {
+    for formula in [
+        "wall_seconds=1",
+        "cpu_seconds",
+        "cpu_seconds=cheap",
+        "cpu_seconds=-1",
+    ] {
+        let error = formula.parse::<CostFormula>().unwrap_err();
+        assert!(matches!(error, ServiceError::Config(_)), "{}", formula);
+        assert!(error.to_string().contains("COST_FORMULA"), "{}", error);
+    }
+}
+
+fn metrics(usage: ResourceUsage, cost_units: Option<f64>) -> ProofMetrics {
+    ProofMetrics {
+        received_at: "2026-10-16T09:41:07Z".to_string(),
+        started_at: "2026-10-16T09:41:07Z".to_string(),
+        completed_at: "2026-10-16T09:42:37Z".to_string(),
+        duration_ms: 90_000,
+        attempts: Some(1),
+        queue_wait_ms: None,
+        usage,
+        cost_units,
+        progress: None,
+    }
+}
+
+#[test]
+fn metrics_carry_usage_fields() {
+    let encoded = serde_json::to_value(metrics(usage(), Some(90.0))).unwrap();
+    assert_eq!(encoded["peak_rss_bytes"], json!(2u64 << 30));
+    assert_eq!(encoded["cpu_time_ms"], 90_000);
+    assert_eq!(encoded["stdin_prepare_ms"], 1);
+    assert_eq!(encoded["guest_cycles"], 3_000_000);
+    assert_eq!(encoded["artifact_bytes_written"], 1 << 20);
+    assert_eq!(encoded["cost_units"], 90.0);
+    assert!(encoded.get("usage").is_none());
+
+    let decoded: ProofMetrics = serde_json::from_value(encoded).unwrap();
+    assert_eq!(decoded.usage, usage());
+    assert_eq!(decoded.cost_units, Some(90.0));
+}
+
+#[test]
+fn unmeasured_usage_is_null() {
+    let encoded = serde_json::to_value(metrics(ResourceUsage::default(), None)).unwrap();
+    for field in [
+        "peak_rss_bytes",
+        "cpu_time_ms",
+        "stdin_prepare_ms",
+        "guest_cycles",
+        "artifact_bytes_written",
+        "cost_units",
+    ] {
+        assert_eq!(encoded.get(field), Some(&Value::Null), "{}", field);
+    }
+
+    // Metrics published before these fields existed still decode
+    let old = json!({
+        "received_at": "2026-10-16T09:41:07Z",
+        "started_at": "2026-10-16T09:41:07Z",
+        "completed_at": "2026-10-16T09:42:37Z",
+        "duration_ms": 90_000,
+    });
+    let decoded: ProofMetrics = serde_json::from_value(old).unwrap();
+    assert_eq!(decoded.usage, ResourceUsage::default());
+    assert_eq!(decoded.cost_units, None);
+}
@@ -0,0 +1,90 @@
+// The written `ProverResponse` schema accepts the responses the service publishes,
+// in either schema version, and rejects ones missing a required field.
+
+use jsonschema::Validator;
+use prover::mock::MockProofGenerator;
+use prover::prover::{ProofBackend, ProofContext};
+use prover::schema::schemas;
+use prover::types::{ProofError, ProofMetrics, ProverRequest, ProverResponse};
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+
+fn validator() -> Validator {
+    let (_, schema) = schemas()
+        .into_iter()
+        .find(|(file, _)| *file == "prover_response.schema.json")
+        .expect("response schema written");
+    jsonschema::validator_for(schema.as_value()).unwrap()
+}
+
+fn metrics() -> ProofMetrics {
+    ProofMetrics {
+        received_at: "2026-10-16T09:41:07Z".to_string(),
+        started_at: "2026-10-16T09:41:07Z".to_string(),
+        completed_at: "2026-10-16T09:41:08Z".to_string(),
+        duration_ms: 1000,
+        attempts: Some(1),
+        queue_wait_ms: None,
+        usage: Default::default(),
+        cost_units: None,
+        progress: None,
+    }
+}
+
+fn success() -> Value {
+    let request: ProverRequest = serde_json::from_value(json!({
+        "request_id": "req-schema",
+        "verification_results": { "recaptcha_score": 7321, "sms_verified": 1, "bio_verified": 1 },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 },
+    }))
+    .unwrap();
+    let backend = MockProofGenerator::new(Duration::ZERO, Duration::from_secs(60));
+    let context = ProofContext::new(Instant::now() + Duration::from_secs(60), 0);
+    let proof_data = backend.generate_proof(request, &context).unwrap();
+    let response = ProverResponse::success("req-schema".to_string(), proof_data, metrics());
+    serde_json::to_value(response).unwrap()
+}
+
+#[test]
+fn published_responses_match_the_schema() {
+    let validator = validator();
+    let success = success();
+    assert!(validator.is_valid(&success), "{}", success);
+
+    // The v1 shape is a subset of the current one
+    let response: ProverResponse = serde_json::from_value(success).unwrap();
+    let v1: Value = serde_json::from_slice(&response.encode(1).unwrap()).unwrap();
+    assert!(validator.is_valid(&v1), "{}", v1);
+
+    let failed = serde_json::to_value(ProverResponse::failed(
+        "req-schema".to_string(),
+        ProofError {
+            error_type: "ProofGenerationError".to_string(),
+            message: "prove_evm failed".to_string(),
+            details: None,
+        },
+        Some(metrics()),
+    ))
+    .unwrap();
+    assert!(validator.is_valid(&failed), "{}", failed);
+}
+
+#[test]
+fn response_missing_a_required_field_is_rejected() {
+    let validator = validator();
+    for field in ["request_id", "status"] {
+        let mut response = success();
+        response.as_object_mut().unwrap().remove(field);
+        assert!(!validator.is_valid(&response), "valid without {}", field);
+    }
+    let mut response = success();
+    response["proof_data"]
+        .as_object_mut()
+        .unwrap()
+        .remove("human_index");
+    assert!(!validator.is_valid(&response));
+
+    let mut response = success();
+    response["status"] = json!("done");
+    assert!(!validator.is_valid(&response));
+}
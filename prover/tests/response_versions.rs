@@ -0,0 +1,134 @@
+// Versioned result messages, pinned byte for byte: each current response as it is
+// published, and as the v1 shape consumers read before versioning. v1 messages
+// decode with the newer fields defaulted, and a version this decoder does not
+// know is kept as raw JSON rather than misread.
+
+use prover::types::{ProofStatus, ProverResponse, VersionedResponse, RESPONSE_SCHEMA_VERSION};
+use serde_json::Value;
+
+const V2_SUCCESS: &str = concat!(
+    r#"{"schema_version":2,"request_id":"versioned-1","status":"success","#,
+    r#""proof_data":{"proof":"cHJvb2Y=","public_inputs":"cHY=","verification_key":"dms=","#,
+    r#""human_index":73,"weights_commitment":"0xab","profile":"fast"},"#,
+    r#""metrics":{"received_at":"2026-10-16T12:00:00+00:00","started_at":"2026-10-16T12:00:01+00:00","#,
+    r#""completed_at":"2026-10-16T12:00:02+00:00","duration_ms":1234,"#,
+    r#""peak_rss_bytes":null,"cpu_time_ms":null,"stdin_prepare_ms":null,"guest_cycles":null,"artifact_bytes_written":null,"#,
+    r#""cost_units":null}}"#
+);
+
+const V1_SUCCESS: &str = concat!(
+    r#"{"request_id":"versioned-1","status":"success","#,
+    r#""proof_data":{"proof":"cHJvb2Y=","public_inputs":"cHY=","verification_key":"dms=","human_index":73},"#,
+    r#""metrics":{"received_at":"2026-10-16T12:00:00+00:00","started_at":"2026-10-16T12:00:01+00:00","#,
+    r#""completed_at":"2026-10-16T12:00:02+00:00","duration_ms":1234}}"#
+);
+
+const V2_FAILED: &str = concat!(
+    r#"{"schema_version":2,"request_id":"versioned-2","status":"failed","#,
+    r#""error":{"error_type":"ProofGenerationError","message":"prove_evm failed","details":"docker"}}"#
+);
+
+const V1_FAILED: &str = concat!(
+    r#"{"request_id":"versioned-2","status":"failed","#,
+    r#""error":{"error_type":"ProofGenerationError","message":"prove_evm failed","details":"docker"}}"#
+);
+
+const V2_TIMEOUT: &str = concat!(
+    r#"{"schema_version":2,"request_id":"versioned-3","status":"timeout","#,
+    r#""error":{"error_type":"Timeout","message":"Proof generation timed out after 60 seconds"}}"#
+);
+
+const V1_TIMEOUT: &str = concat!(
+    r#"{"request_id":"versioned-3","status":"timeout","#,
+    r#""error":{"error_type":"Timeout","message":"Proof generation timed out after 60 seconds"}}"#
+);
+
+/// Each current response with its v1 encoding
+const PINNED: [(&str, &str); 3] = [
+    (V2_SUCCESS, V1_SUCCESS),
+    (V2_FAILED, V1_FAILED),
+    (V2_TIMEOUT, V1_TIMEOUT),
+];
+
+fn encoded(response: &ProverResponse, schema_version: u32) -> String {
+    String::from_utf8(response.encode(schema_version).unwrap()).unwrap()
+}
+
+#[test]
+fn current_responses_are_published_as_pinned() {
+    assert_eq!(RESPONSE_SCHEMA_VERSION, 2);
+    for (current, _) in PINNED {
+        let decoded = ProverResponse::decode(current.as_bytes()).unwrap();
+        assert!(matches!(decoded, VersionedResponse::V2(_)), "{:?}", decoded);
+        assert_eq!(decoded.schema_version(), RESPONSE_SCHEMA_VERSION);
+
+        let response = decoded.response().unwrap();
+        assert_eq!(encoded(response, RESPONSE_SCHEMA_VERSION), current);
+        assert_eq!(serde_json::to_string(response).unwrap(), current);
+    }
+}
+
+#[test]
+fn v1_encoding_is_pinned() {
+    for (current, v1) in PINNED {
+        let response: ProverResponse = serde_json::from_str(current).unwrap();
+        assert_eq!(encoded(&response, 1), v1);
+    }
+}
+
+#[test]
+fn v1_responses_decode_with_newer_fields_defaulted() {
+    for (_, v1) in PINNED {
+        let decoded = ProverResponse::decode(v1.as_bytes()).unwrap();
+        assert!(matches!(decoded, VersionedResponse::V1(_)), "{:?}", decoded);
+        assert_eq!(decoded.schema_version(), 1);
+
+        let response = decoded.response().unwrap();
+        assert_eq!(response.schema_version, 1);
+        // And are written back unchanged
+        assert_eq!(encoded(response, 1), v1);
+    }
+
+    let response = ProverResponse::decode(V1_SUCCESS.as_bytes()).unwrap();
+    let response = response.response().unwrap();
+    assert_eq!(response.status, ProofStatus::Success);
+    let proof_data = response.proof_data.as_ref().unwrap();
+    assert_eq!(proof_data.human_index, 73);
+    assert_eq!(proof_data.weights_commitment, "");
+    assert_eq!(proof_data.profile, None);
+}
+
+#[test]
+fn unknown_versions_are_kept_raw() {
+    let next = concat!(
+        r#"{"schema_version":3,"request_id":"versioned-4","status":"superseded","#,
+        r#""superseded_by":"versioned-5"}"#
+    );
+    let decoded = ProverResponse::decode(next.as_bytes()).unwrap();
+    assert_eq!(decoded.schema_version(), 3);
+    assert!(decoded.response().is_none());
+    let VersionedResponse::Unknown { raw, .. } = decoded else {
+        panic!("{:?} decoded as a known version", decoded);
+    };
+    assert_eq!(raw, serde_json::from_str::<Value>(next).unwrap());
+
+    let response: ProverResponse = serde_json::from_str(V2_SUCCESS).unwrap();
+    let err = response.encode(3).expect_err("no v3 encoding");
+    assert!(
+        err.to_string()
+            .contains("Cannot encode response schema version 3"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn malformed_versions_are_errors() {
+    for json in [
+        r#"{"schema_version":"2","request_id":"versioned-5","status":"failed"}"#,
+        r#"{"schema_version":-1,"request_id":"versioned-5","status":"failed"}"#,
+        r#"{"schema_version":2,"request_id":"versioned-5","status":"superseded"}"#,
+    ] {
+        assert!(ProverResponse::decode(json.as_bytes()).is_err(), "{}", json);
+    }
+}
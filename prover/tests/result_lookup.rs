@@ -0,0 +1,194 @@
+// `GET /v1/proofs/{id}/result` on the status server: a journaled result as it was
+// published, for requests signed with the callback key, and what `republish`
+// publishes again for a result.
+
+use chrono::{TimeZone, Utc};
+use prover::config::Config;
+use prover::delayed::{DelayedQueue, JournaledResult};
+use prover::endpoints::Endpoints;
+use prover::janitor::Janitor;
+use prover::provenance::Provenance;
+use prover::scheduler::{Lanes, Scheduler};
+use prover::service::republished_message;
+use prover::status::{self, StatusState, SIGNATURE_MAX_AGE_SECS};
+use prover::webhook::{signature, verify_signature};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Semaphore;
+
+const SIGNING_KEY: &[u8] = b"callback-signing-secret-5f2c";
+const RESULT: &str = r#"{"request_id":"req-done","success":true}"#;
+
+fn journaled(request_id: &str) -> JournaledResult {
+    JournaledResult {
+        request_id: request_id.to_string(),
+        data: RESULT.as_bytes().to_vec(),
+        attributes: HashMap::from([
+            ("request_id".to_string(), request_id.to_string()),
+            ("schema_version".to_string(), "3".to_string()),
+        ]),
+        ordering_key: "user-1".to_string(),
+        topic_path: "projects/test/topics/prover-results".to_string(),
+        completed_at: "2026-01-01T00:00:00+00:00".to_string(),
+    }
+}
+
+/// Serve the status endpoints, with a result in progress and one completed
+async fn serve(name: &str, signing_key: Option<&[u8]>) -> std::net::SocketAddr {
+    std::env::set_var("GCP_PROJECT_ID", "test-project");
+    std::env::set_var("PROVER_SUBSCRIPTION", "prover-requests");
+    std::env::set_var("RESULT_TOPIC", "prover-results");
+    let config = Config::from_env().expect("configuration");
+    let dir = std::env::temp_dir().join(format!(
+        "prover-result-lookup-{}-{}",
+        name,
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    let queue = DelayedQueue::open(&dir.join("delayed.sqlite")).unwrap();
+    queue.begin_result("req-running").unwrap();
+    queue.begin_result("req-done").unwrap();
+    queue.record_result(&journaled("req-done"), 10).unwrap();
+
+    let scheduler = Scheduler::start(
+        Arc::new(Semaphore::new(1)),
+        Lanes {
+            capacity: 1,
+            interactive_reserved: 0,
+            batch_promotion: None,
+            queue_wait_warning: None,
+        },
+    );
+    let mut state = StatusState::new(
+        "mock",
+        None,
+        Provenance::collect(&config, None),
+        scheduler,
+        Arc::new(queue),
+        Arc::new(Janitor::new(&dir, &dir)),
+        Arc::new(Endpoints::new(config.endpoints(), 0)),
+    );
+    if let Some(key) = signing_key {
+        state = state.with_signing_key(key.to_vec());
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let router = status::router(Arc::new(state));
+    tokio::spawn(async move { axum::serve(listener, router).await });
+    addr
+}
+
+/// GET `path` with the given extra header lines and return the raw response
+async fn get(addr: std::net::SocketAddr, path: &str, headers: &str) -> String {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: localhost\r\n{}Connection: close\r\n\r\n",
+        path, headers
+    );
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response
+}
+
+/// Signature headers for `path` as signed at `timestamp`
+fn signed(key: &[u8], path: &str, timestamp: i64) -> String {
+    format!(
+        "X-Prover-Timestamp: {}\r\nX-Prover-Signature: {}\r\n",
+        timestamp,
+        signature(key, timestamp, path.as_bytes())
+    )
+}
+
+fn body(response: &str) -> &str {
+    response.split("\r\n\r\n").nth(1).unwrap()
+}
+
+#[tokio::test]
+async fn results_are_served_by_their_state() {
+    let addr = serve("states", Some(SIGNING_KEY)).await;
+    let now = Utc::now().timestamp();
+
+    let path = "/v1/proofs/req-done/result";
+    let response = get(addr, path, &signed(SIGNING_KEY, path, now)).await;
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+    let lowercase = response.to_lowercase();
+    assert!(lowercase.contains("content-type: application/json"));
+    assert!(lowercase.contains("x-prover-schema-version: 3"));
+    assert_eq!(body(&response), RESULT);
+
+    let path = "/v1/proofs/req-running/result";
+    let response = get(addr, path, &signed(SIGNING_KEY, path, now)).await;
+    assert!(response.starts_with("HTTP/1.1 202"), "{}", response);
+    assert!(body(&response).contains(r#""status":"in_progress""#));
+
+    let path = "/v1/proofs/req-other/result";
+    let response = get(addr, path, &signed(SIGNING_KEY, path, now)).await;
+    assert!(response.starts_with("HTTP/1.1 404"), "{}", response);
+    assert!(body(&response).contains(r#""status":"unknown""#));
+}
+
+#[tokio::test]
+async fn unsigned_or_stale_requests_are_unauthorized() {
+    let addr = serve("unsigned", Some(SIGNING_KEY)).await;
+    let path = "/v1/proofs/req-done/result";
+    let now = Utc::now().timestamp();
+
+    let unsigned = get(addr, path, "").await;
+    assert!(unsigned.starts_with("HTTP/1.1 401"), "{}", unsigned);
+
+    let wrong_key = get(addr, path, &signed(b"another-key", path, now)).await;
+    assert!(wrong_key.starts_with("HTTP/1.1 401"), "{}", wrong_key);
+
+    // Signed for another result
+    let other = "/v1/proofs/req-other/result";
+    let wrong_path = get(addr, path, &signed(SIGNING_KEY, other, now)).await;
+    assert!(wrong_path.starts_with("HTTP/1.1 401"), "{}", wrong_path);
+
+    let stale = now - SIGNATURE_MAX_AGE_SECS - 1;
+    let stale = get(addr, path, &signed(SIGNING_KEY, path, stale)).await;
+    assert!(stale.starts_with("HTTP/1.1 401"), "{}", stale);
+}
+
+#[tokio::test]
+async fn results_are_not_served_without_a_signing_key() {
+    let addr = serve("no-key", None).await;
+    let path = "/v1/proofs/req-done/result";
+    let headers = signed(SIGNING_KEY, path, Utc::now().timestamp());
+    let response = get(addr, path, &headers).await;
+    assert!(response.starts_with("HTTP/1.1 404"), "{}", response);
+}
+
+#[test]
+fn signatures_verify_only_for_their_key_timestamp_and_body() {
+    let at = 1_700_000_000;
+    let header = signature(SIGNING_KEY, at, b"body");
+    assert!(verify_signature(SIGNING_KEY, at, b"body", &header));
+    assert!(!verify_signature(SIGNING_KEY, at + 1, b"body", &header));
+    assert!(!verify_signature(SIGNING_KEY, at, b"other", &header));
+    assert!(!verify_signature(b"another-key", at, b"body", &header));
+    assert!(!verify_signature(SIGNING_KEY, at, b"body", "sha256=zz"));
+    let bare = header.trim_start_matches("sha256=");
+    assert!(!verify_signature(SIGNING_KEY, at, b"body", bare));
+}
+
+#[test]
+fn republished_results_are_marked_and_otherwise_unchanged() {
+    let result = journaled("req-done");
+    let at = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+    let message = republished_message(&result, at);
+
+    assert_eq!(message.data, result.data);
+    assert_eq!(message.ordering_key, "user-1");
+    assert_eq!(message.attributes["request_id"], "req-done");
+    assert_eq!(message.attributes["schema_version"], "3");
+    assert_eq!(message.attributes["republished"], "true");
+    assert_eq!(
+        message.attributes["republished_at"],
+        "2026-03-01T12:00:00+00:00"
+    );
+    assert_eq!(message.attributes.len(), 4);
+}
@@ -0,0 +1,154 @@
+// The ordering key results are published with under `RESULT_ORDERING`, and the
+// `publish_sequence` consumers use to drop stale results: a request's
+// `binding_id`, else its `user_id`, is echoed in its response and keys the
+// result, so one user's results arrive in publish order on an ordering-enabled
+// subscription.
+
+use chrono::Utc;
+use prover::config::Config;
+use prover::mock::MockProofGenerator;
+use prover::service::{result_message, ProverService, PublishSequence};
+use prover::types::{ProverRequest, ProverResponse};
+use serde_json::{json, Value};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+fn config() -> Config {
+    static BASE: OnceLock<Config> = OnceLock::new();
+    BASE.get_or_init(|| {
+        std::env::set_var("GCP_PROJECT_ID", "ordering-test");
+        std::env::set_var("PROVER_SUBSCRIPTION", "unused");
+        std::env::set_var("RESULT_TOPIC", "unused");
+        Config::from_env().expect("base configuration")
+    })
+    .clone()
+}
+
+fn request(identifiers: Value) -> Value {
+    let mut request = json!({
+        "request_id": "ordering-1",
+        "verification_results": { "recaptcha_score": 8000, "sms_verified": 1, "bio_verified": 1 },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 },
+    });
+    request
+        .as_object_mut()
+        .unwrap()
+        .extend(identifiers.as_object().unwrap().clone());
+    request
+}
+
+/// The result of `request`, with its identifiers echoed as the service does
+fn response(request: Value) -> ProverResponse {
+    let request: ProverRequest = serde_json::from_value(request).unwrap();
+    let mut response =
+        ProverResponse::timeout(request.request_id.clone(), "slow".to_string(), None);
+    response.correlate(&request);
+    response
+}
+
+#[tokio::test]
+async fn binding_id_is_echoed_in_the_response() {
+    let interrupt = CancellationToken::new();
+    let data = serde_json::to_vec(&request(json!({ "binding_id": "user-42" }))).unwrap();
+    let response = ProverService::process_message(
+        &data,
+        config(),
+        Arc::new(MockProofGenerator::new(
+            Duration::ZERO,
+            Duration::from_secs(1),
+        )),
+        None,
+        None,
+        &interrupt,
+        Utc::now(),
+    )
+    .await
+    .expect("message processed");
+
+    assert_eq!(response.binding_id.as_deref(), Some("user-42"));
+    let encoded: Value = serde_json::from_slice(&response.encode(2).unwrap()).unwrap();
+    assert_eq!(encoded["binding_id"], "user-42");
+}
+
+#[test]
+fn results_are_keyed_by_binding_id() {
+    let message = result_message(
+        &response(request(json!({ "binding_id": "user-42" }))),
+        2,
+        true,
+    )
+    .unwrap();
+    assert_eq!(message.ordering_key, "user-42");
+    assert_eq!(message.attributes["schema_version"], "2");
+}
+
+#[test]
+fn results_without_a_binding_id_are_keyed_by_user_id() {
+    for identifiers in [
+        json!({ "user_id": "user-42" }),
+        json!({ "userId": "user-42" }),
+    ] {
+        let response = response(request(identifiers.clone()));
+        assert_eq!(response.binding_id, None, "{}", identifiers);
+        assert_eq!(response.user_id.as_deref(), Some("user-42"));
+        let message = result_message(&response, 2, true).unwrap();
+        assert_eq!(message.ordering_key, "user-42", "{}", identifiers);
+    }
+}
+
+#[test]
+fn binding_id_keys_results_over_user_id() {
+    let response = response(request(
+        json!({ "user_id": "user-42", "binding_id": "binding-7" }),
+    ));
+    assert_eq!(response.user_id.as_deref(), Some("user-42"));
+    assert_eq!(response.binding_id.as_deref(), Some("binding-7"));
+    let encoded: Value = serde_json::from_slice(&response.encode(2).unwrap()).unwrap();
+    assert_eq!(encoded["user_id"], "user-42");
+    assert_eq!(encoded["binding_id"], "binding-7");
+
+    let message = result_message(&response, 2, true).unwrap();
+    assert_eq!(message.ordering_key, "binding-7");
+}
+
+#[test]
+fn results_without_a_user_are_keyed_by_request_id() {
+    let message = result_message(&response(request(json!({}))), 2, true).unwrap();
+    assert_eq!(message.ordering_key, "ordering-1");
+}
+
+#[test]
+fn results_are_unordered_without_result_ordering() {
+    for identifiers in [
+        json!({ "binding_id": "user-42" }),
+        json!({ "user_id": "user-42" }),
+        json!({}),
+    ] {
+        let message = result_message(&response(request(identifiers)), 2, false).unwrap();
+        assert_eq!(message.ordering_key, "");
+    }
+}
+
+#[test]
+fn publish_sequence_strictly_increases() {
+    let sequence = Arc::new(PublishSequence::default());
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let sequence = sequence.clone();
+            std::thread::spawn(move || (0..1000).map(|_| sequence.next()).collect::<Vec<_>>())
+        })
+        .collect();
+
+    let mut all = Vec::new();
+    for handle in handles {
+        let values = handle.join().unwrap();
+        assert!(values.windows(2).all(|pair| pair[0] < pair[1]));
+        all.extend(values);
+    }
+
+    // No value is handed out twice, across threads
+    all.sort_unstable();
+    all.dedup();
+    assert_eq!(all.len(), 4000);
+}
@@ -0,0 +1,132 @@
+// A request's own `result_topic`: topics in `RESULT_TOPIC_ALLOWLIST` and the global
+// result topic are accepted and the request is proved; any other topic fails the
+// request without proving it.
+
+use chrono::Utc;
+use prover::config::Config;
+use prover::error::ServiceError;
+use prover::mock::MockProofGenerator;
+use prover::prover::{ProofBackend, ProofContext};
+use prover::service::ProverService;
+use prover::types::{ProofData, ProofStatus, ProverRequest, ProverResponse};
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+const GLOBAL_TOPIC: &str = "prover-results";
+const PARTNER_TOPIC: &str = "partner-a-results";
+
+/// The mock, counting the proofs it is asked for
+struct CountingBackend {
+    mock: MockProofGenerator,
+    proofs: AtomicUsize,
+}
+
+impl ProofBackend for CountingBackend {
+    fn name(&self) -> &'static str {
+        "counting"
+    }
+
+    fn generate_proof(
+        &self,
+        request: ProverRequest,
+        context: &ProofContext,
+    ) -> Result<ProofData, ServiceError> {
+        self.proofs.fetch_add(1, Ordering::SeqCst);
+        self.mock.generate_proof(request, context)
+    }
+}
+
+fn config() -> Config {
+    static BASE: OnceLock<Config> = OnceLock::new();
+    let mut config = BASE
+        .get_or_init(|| {
+            std::env::set_var("GCP_PROJECT_ID", "result-topic-test");
+            std::env::set_var("PROVER_SUBSCRIPTION", "prover-requests");
+            std::env::set_var("RESULT_TOPIC", GLOBAL_TOPIC);
+            Config::from_env().expect("base configuration")
+        })
+        .clone();
+    config.memory_wait_secs = 0;
+    config.result_topic_allowlist = vec![PARTNER_TOPIC.to_string()];
+    config
+}
+
+/// Process a request naming `result_topic`, returning the response and whether the
+/// backend was asked for a proof
+async fn process(result_topic: Option<&str>) -> (ProverResponse, bool) {
+    let mut request = json!({
+        "request_id": "6ba7b810-9dad-41d1-80b4-00c04fd430c8",
+        "verification_results": { "recaptcha_score": 8000, "sms_verified": 1, "bio_verified": 1 },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 },
+    });
+    if let Some(result_topic) = result_topic {
+        request["result_topic"] = json!(result_topic);
+    }
+    let backend = Arc::new(CountingBackend {
+        mock: MockProofGenerator::new(Duration::ZERO, Duration::from_secs(60)),
+        proofs: AtomicUsize::new(0),
+    });
+    let response = ProverService::process_message(
+        &serde_json::to_vec(&request).unwrap(),
+        config(),
+        backend.clone(),
+        None,
+        None,
+        &CancellationToken::new(),
+        Utc::now(),
+    )
+    .await
+    .expect("request parses");
+    (response, backend.proofs.load(Ordering::SeqCst) > 0)
+}
+
+#[tokio::test]
+async fn allowlisted_topic_is_proved() {
+    let (response, proved) = process(Some(PARTNER_TOPIC)).await;
+    assert_eq!(
+        response.status,
+        ProofStatus::Success,
+        "{:?}",
+        response.error
+    );
+    assert!(proved);
+}
+
+#[tokio::test]
+async fn no_topic_or_the_global_one_is_proved() {
+    for result_topic in [None, Some(GLOBAL_TOPIC)] {
+        let (response, proved) = process(result_topic).await;
+        assert_eq!(
+            response.status,
+            ProofStatus::Success,
+            "{:?}: {:?}",
+            result_topic,
+            response.error
+        );
+        assert!(proved);
+    }
+}
+
+#[tokio::test]
+async fn disallowed_topic_is_rejected_without_proving() {
+    for topic in [
+        "attacker-results",
+        "partner-a-results-2",
+        "projects/other/topics/partner-a-results",
+    ] {
+        let (response, proved) = process(Some(topic)).await;
+        assert_eq!(response.status, ProofStatus::Failed, "{}", topic);
+        assert!(!proved, "{} was proved", topic);
+
+        let error = response.error.as_ref().unwrap();
+        assert_eq!(error.error_type, "ResultTopicNotAllowed");
+        assert_eq!(
+            error.message,
+            format!("Result topic '{}' is not in RESULT_TOPIC_ALLOWLIST", topic)
+        );
+        assert!(response.proof_data.is_none());
+    }
+}
@@ -0,0 +1,130 @@
+// The shutdown report: requests counted by outcome as they are answered, the
+// duration of successful proofs summarized, and the most requests in flight.
+
+use prover::session::{DurationSummary, SessionStats};
+use prover::types::{ProofError, ProofMetrics, ProverResponse, ResourceUsage};
+use serde_json::json;
+use std::sync::Arc;
+
+fn metrics(duration_ms: u64) -> ProofMetrics {
+    ProofMetrics {
+        received_at: "2026-10-16T12:00:00Z".to_string(),
+        started_at: "2026-10-16T12:00:00Z".to_string(),
+        completed_at: "2026-10-16T12:00:00Z".to_string(),
+        duration_ms,
+        attempts: Some(1),
+        queue_wait_ms: None,
+        usage: ResourceUsage::default(),
+        cost_units: None,
+        progress: None,
+    }
+}
+
+fn success(duration_ms: u64) -> ProverResponse {
+    let proof_data = serde_json::from_value(json!({
+        "proof": "",
+        "public_inputs": "",
+        "verification_key": "",
+        "human_index": 50,
+    }))
+    .unwrap();
+    ProverResponse::success("ok".to_string(), proof_data, metrics(duration_ms))
+}
+
+fn failure() -> ProverResponse {
+    let error = ProofError {
+        error_type: "ProofGenerationError".to_string(),
+        message: "failed".to_string(),
+        details: None,
+    };
+    ProverResponse::failed("failed".to_string(), error, Some(metrics(5)))
+}
+
+#[test]
+fn report_counts_requests_by_outcome() {
+    let stats = Arc::new(SessionStats::default());
+    for duration_ms in [1000, 3000, 2000] {
+        stats.record(&success(duration_ms));
+    }
+    stats.record(&failure());
+    stats.record_error();
+    stats.record(&ProverResponse::timeout(
+        "slow".to_string(),
+        String::new(),
+        Some(metrics(60_000)),
+    ));
+    stats.record(&ProverResponse::interrupted(
+        "late".to_string(),
+        String::new(),
+        None,
+    ));
+    stats.record(&ProverResponse::below_threshold(
+        "low".to_string(),
+        10,
+        20,
+        None,
+    ));
+
+    let report = stats.report();
+    assert_eq!(report.processed, 8);
+    assert_eq!(report.succeeded, 3);
+    assert_eq!(report.failed, 2);
+    assert_eq!(report.timed_out, 1);
+    assert_eq!(report.interrupted, 1);
+    // Only successful proofs are timed
+    assert_eq!(
+        report.proof_duration_ms,
+        Some(DurationSummary {
+            mean: 2000,
+            p50: 2000,
+            p90: 3000,
+            p99: 3000,
+            max: 3000,
+        })
+    );
+    assert_eq!(report.peak_in_flight, 0);
+}
+
+#[test]
+fn peak_in_flight_is_the_most_at_once() {
+    let stats = Arc::new(SessionStats::default());
+    let first = stats.start();
+    let second = stats.start();
+    drop(first);
+    let third = stats.start();
+    drop((second, third));
+    let _fourth = stats.start();
+    assert_eq!(stats.report().peak_in_flight, 2);
+}
+
+#[test]
+fn durations_summarize_by_nearest_rank() {
+    assert_eq!(DurationSummary::of(&[]), None);
+    assert_eq!(
+        DurationSummary::of(&[7]),
+        Some(DurationSummary {
+            mean: 7,
+            p50: 7,
+            p90: 7,
+            p99: 7,
+            max: 7,
+        })
+    );
+
+    let durations: Vec<u64> = (1..=100).rev().collect();
+    let summary = DurationSummary::of(&durations).unwrap();
+    assert_eq!(summary.mean, 50);
+    assert_eq!(summary.p50, 50);
+    assert_eq!(summary.p90, 90);
+    assert_eq!(summary.p99, 99);
+    assert_eq!(summary.max, 100);
+}
+
+#[test]
+fn an_empty_session_reports_no_durations() {
+    let report = SessionStats::default().report();
+    assert_eq!(report.processed, 0);
+    assert_eq!(report.proof_duration_ms, None);
+    let logged = serde_json::to_value(&report).unwrap();
+    assert_eq!(logged["proof_duration_ms"], serde_json::Value::Null);
+}
@@ -0,0 +1,191 @@
+// Two circuits whose setups produced identical keys: once interned, the keys are
+// stored once under `setup/<sha256>`, both circuits resolve them through their own
+// links, and each proof directory gets the shared content hard linked in, all of
+// it or none.
+
+use human_index_lib::CircuitId;
+use prover::janitor::{Janitor, JanitorPolicy};
+use prover::prover::{resolve_setup_files, ProofGenerator, StartupRetry, SETUP_FILES};
+use prover::setup_store::{file_sha256, link_setup_files, SetupStore};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+const PROVING_KEY: &[u8] = b"identical proving key";
+const VERIFYING_KEY: &[u8] = b"identical verifying key";
+
+/// A fresh output directory, removed on drop
+struct OutputDir(PathBuf);
+
+impl Drop for OutputDir {
+    fn drop(&mut self) {
+        std::fs::remove_dir_all(&self.0).ok();
+    }
+}
+
+/// Output directory in which both circuits' setups wrote the same keys
+fn output_dir(name: &str) -> (OutputDir, SetupStore) {
+    let root = std::env::temp_dir().join(format!("setup-store-{}-{}", name, std::process::id()));
+    std::fs::remove_dir_all(&root).ok();
+    let store = SetupStore::new(&root);
+    for circuit in CircuitId::ALL {
+        let dir = store.circuit_dir(circuit);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("vm_pk"), PROVING_KEY).unwrap();
+        std::fs::write(dir.join("vm_vk"), VERIFYING_KEY).unwrap();
+    }
+    (OutputDir(root), store)
+}
+
+fn sorted(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut paths = paths.to_vec();
+    paths.sort();
+    paths
+}
+
+fn is_symlink(path: &Path) -> bool {
+    path.symlink_metadata().unwrap().file_type().is_symlink()
+}
+
+/// What `prove_evm` reads from the proof directory
+fn read_keys(proof_dir: &Path) -> (Vec<u8>, Vec<u8>) {
+    (
+        std::fs::read(proof_dir.join("vm_pk")).unwrap(),
+        std::fs::read(proof_dir.join("vm_vk")).unwrap(),
+    )
+}
+
+#[test]
+fn identical_keys_share_one_blob() {
+    let (_dir, store) = output_dir("share");
+    assert!(store.blobs().is_empty());
+
+    let human_index = store.intern_circuit(CircuitId::HumanIndex).unwrap();
+    let wide = store.intern_circuit(CircuitId::Wide).unwrap();
+    assert_eq!(human_index, wide);
+    // One blob per distinct key, named by its hash
+    assert_eq!(store.blobs(), sorted(&human_index));
+    assert_eq!(
+        human_index[0].file_name().unwrap().to_str().unwrap(),
+        file_sha256(&human_index[0]).unwrap()
+    );
+
+    for circuit in CircuitId::ALL {
+        for (name, blob) in SETUP_FILES.iter().zip(&human_index) {
+            let link = store.circuit_dir(circuit).join(name);
+            assert!(is_symlink(&link), "{}", link.display());
+            // Relative, so the links survive mounting the directory elsewhere
+            assert!(std::fs::read_link(&link).unwrap().is_relative());
+            assert_eq!(&store.resolve(circuit, name).unwrap(), blob);
+        }
+    }
+
+    // Interning again changes nothing
+    assert_eq!(store.intern_circuit(CircuitId::Wide).unwrap(), wide);
+    assert_eq!(store.blobs().len(), 2);
+}
+
+#[test]
+fn both_circuits_prove_from_the_shared_blob() {
+    let (dir, store) = output_dir("prove");
+    let blobs = store.intern_circuit(CircuitId::HumanIndex).unwrap();
+    store.intern_circuit(CircuitId::Wide).unwrap();
+
+    // As `prove_evm_once` prepares each proof directory
+    for circuit in CircuitId::ALL {
+        let proof_dir = dir.0.join(format!("request-{}", circuit.name()));
+        std::fs::create_dir_all(&proof_dir).unwrap();
+        link_setup_files(&store, circuit, &proof_dir).unwrap();
+
+        for (name, blob) in SETUP_FILES.iter().zip(&blobs) {
+            let linked = proof_dir.join(name);
+            // A regular file, which resolves inside the Docker mount
+            assert!(!is_symlink(&linked));
+            assert_eq!(
+                linked.metadata().unwrap().ino(),
+                blob.metadata().unwrap().ino()
+            );
+        }
+        assert_eq!(
+            read_keys(&proof_dir),
+            (PROVING_KEY.to_vec(), VERIFYING_KEY.to_vec())
+        );
+        std::fs::remove_dir_all(&proof_dir).unwrap();
+    }
+
+    // Removing the proof directories leaves the blobs
+    assert_eq!(store.blobs(), sorted(&blobs));
+    assert_eq!(std::fs::read(&blobs[0]).unwrap(), PROVING_KEY);
+}
+
+#[tokio::test]
+async fn startup_resolves_the_keys_through_the_store() {
+    let (dir, store) = output_dir("startup");
+    let blobs = store.intern_circuit(ProofGenerator::CIRCUIT).unwrap();
+    let retry = StartupRetry {
+        attempts: 0,
+        backoff: Duration::ZERO,
+    };
+    assert_eq!(resolve_setup_files(&dir.0, retry).await.unwrap(), blobs);
+}
+
+#[test]
+fn detached_circuits_leave_the_blobs() {
+    let (_dir, store) = output_dir("detach");
+    let blobs = store.intern_circuit(CircuitId::HumanIndex).unwrap();
+    store.intern_circuit(CircuitId::Wide).unwrap();
+
+    // A new setup of one circuit must not write through its links
+    store.detach(CircuitId::HumanIndex).unwrap();
+    let human_index_dir = store.circuit_dir(CircuitId::HumanIndex);
+    assert!(!human_index_dir.join("vm_pk").exists());
+    assert_eq!(store.blobs(), sorted(&blobs));
+    assert_eq!(store.resolve(CircuitId::Wide, "vm_pk").unwrap(), blobs[0]);
+}
+
+#[test]
+fn janitor_never_removes_the_store_or_circuit_keys() {
+    let (dir, store) = output_dir("janitor");
+    store.intern_circuit(CircuitId::HumanIndex).unwrap();
+    store.intern_circuit(CircuitId::Wide).unwrap();
+
+    let janitor = Janitor::new(&dir.0, &dir.0.join("quarantine"));
+    let policy = JanitorPolicy {
+        request_ttl: Duration::ZERO,
+        min_request_age: Duration::ZERO,
+        quarantine_ttl: None,
+        max_bytes: Some(0),
+    };
+    let sweep = janitor.sweep(&policy, SystemTime::now() + Duration::from_secs(3600));
+    assert!(sweep.removed.is_empty());
+    assert!(sweep.over_budget);
+    // The keys count once, in the store; the links in the output directory count
+    // as links, and the wide circuit's links are skipped like any setup file
+    let links: u64 = SETUP_FILES
+        .iter()
+        .map(|name| dir.0.join(name).symlink_metadata().unwrap().len())
+        .sum();
+    let key_bytes = (PROVING_KEY.len() + VERIFYING_KEY.len()) as u64;
+    assert_eq!(sweep.usage_bytes, key_bytes + links);
+    assert!(store.resolve(CircuitId::Wide, "vm_vk").is_ok());
+}
+
+#[test]
+fn failed_second_link_leaves_no_setup_files() {
+    let (dir, store) = output_dir("partial");
+    // `vm_pk` links, `vm_vk` cannot be resolved
+    std::fs::remove_file(store.circuit_dir(CircuitId::HumanIndex).join("vm_vk")).unwrap();
+    let proof_dir = dir.0.join("request-partial");
+    std::fs::create_dir_all(&proof_dir).unwrap();
+
+    let err =
+        link_setup_files(&store, CircuitId::HumanIndex, &proof_dir).expect_err("vm_vk is missing");
+    assert!(err.to_string().contains("vm_vk"), "{}", err);
+    // Not even the `vm_pk` linked before the failure remains
+    assert_eq!(std::fs::read_dir(&proof_dir).unwrap().count(), 0);
+    // The source keys are untouched
+    assert_eq!(
+        std::fs::read(store.circuit_dir(CircuitId::HumanIndex).join("vm_pk")).unwrap(),
+        PROVING_KEY
+    );
+}
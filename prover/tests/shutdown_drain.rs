@@ -0,0 +1,145 @@
+// The shutdown drain against slow mock proofs, which share the interrupt token
+// subscription messages are processed with: proofs that finish within the grace
+// period drain cleanly, and those still running when it runs out are interrupted
+// and reported with `interrupted` responses.
+
+use chrono::Utc;
+use prover::config::Config;
+use prover::mock::MockProofGenerator;
+use prover::service::{drain_within, ProverService};
+use prover::types::{ProofStatus, ProverResponse, RequestIdFormat};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+const GRACE: Duration = Duration::from_millis(300);
+
+fn config() -> Config {
+    static BASE: OnceLock<Config> = OnceLock::new();
+    let mut config = BASE
+        .get_or_init(|| {
+            std::env::set_var("GCP_PROJECT_ID", "drain-test");
+            std::env::set_var("PROVER_SUBSCRIPTION", "unused");
+            std::env::set_var("RESULT_TOPIC", "unused");
+            Config::from_env().expect("base configuration")
+        })
+        .clone();
+    // Readable IDs, one per message
+    config.request_id_format = RequestIdFormat::Any;
+    config
+}
+
+fn message(request_id: &str, recaptcha_score: u32) -> Vec<u8> {
+    serde_json::to_vec(&serde_json::json!({
+        "request_id": request_id,
+        "verification_results": {
+            "recaptcha_score": recaptcha_score,
+            "sms_verified": 1,
+            "bio_verified": 1
+        },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 },
+    }))
+    .unwrap()
+}
+
+struct Drained {
+    in_grace: bool,
+    elapsed: Duration,
+    interrupted: bool,
+    responses: Vec<ProverResponse>,
+}
+
+/// Start three proofs taking `proof_time` each, then drain them within `GRACE`
+async fn drain(name: &str, proof_time: Duration) -> Drained {
+    let config = config();
+    let backend = Arc::new(MockProofGenerator::new(proof_time, Duration::from_secs(60)));
+    let interrupt = CancellationToken::new();
+    let responses = Mutex::new(Vec::new());
+
+    let process = |index: u32| {
+        let data = message(&format!("drain-{}-{}", name, index), 3000 * index);
+        let config = config.clone();
+        let backend = backend.clone();
+        let interrupt = &interrupt;
+        let responses = &responses;
+        async move {
+            let response = ProverService::process_message(
+                &data,
+                config,
+                backend,
+                None,
+                None,
+                interrupt,
+                Utc::now(),
+            )
+            .await
+            .expect("message processed");
+            responses.lock().unwrap().push(response);
+        }
+    };
+    let in_flight = async {
+        tokio::join!(process(1), process(2), process(3));
+    };
+    let started = Instant::now();
+    let in_grace = drain_within(GRACE, in_flight, || interrupt.cancel()).await;
+
+    Drained {
+        in_grace,
+        elapsed: started.elapsed(),
+        interrupted: interrupt.is_cancelled(),
+        responses: responses.into_inner().unwrap(),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn proofs_within_the_grace_period_drain_cleanly() {
+    let drained = drain("clean", Duration::from_millis(20)).await;
+    assert!(drained.in_grace);
+    assert!(!drained.interrupted);
+    assert!(drained.elapsed < GRACE);
+
+    assert_eq!(drained.responses.len(), 3);
+    for response in &drained.responses {
+        assert_eq!(
+            response.status,
+            ProofStatus::Success,
+            "{:?}",
+            response.error
+        );
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn proofs_outlasting_the_grace_period_are_interrupted() {
+    let proof_time = Duration::from_secs(2);
+    let drained = drain("deadline", proof_time).await;
+    assert!(!drained.in_grace);
+    assert!(drained.interrupted);
+    // Done once interrupted, without waiting for the proofs
+    assert!(drained.elapsed >= GRACE);
+    assert!(drained.elapsed < proof_time, "{:?}", drained.elapsed);
+    assert_eq!(drained.responses.len(), 3);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn interrupted_requests_are_reported() {
+    let drained = drain("reported", Duration::from_secs(2)).await;
+
+    assert_eq!(drained.responses.len(), 3);
+    for response in &drained.responses {
+        assert_eq!(response.status, ProofStatus::Interrupted);
+        assert!(response.proof_data.is_none());
+        assert!(response.request_id.starts_with("drain-reported-"));
+        let error = response
+            .error
+            .as_ref()
+            .expect("interrupted responses say why");
+        assert_eq!(error.error_type, "Interrupted");
+        assert_eq!(error.message, "Worker shut down before the proof finished");
+        let metrics = response
+            .metrics
+            .as_ref()
+            .expect("interrupted responses have metrics");
+        assert!(metrics.duration_ms < 2000, "{}", metrics.duration_ms);
+    }
+}
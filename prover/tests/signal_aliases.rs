@@ -0,0 +1,149 @@
+// `SIGNAL_ALIASES`: provider names for the verification results are renamed to the
+// canonical fields before a request is parsed, so an aliased request proves exactly
+// what the canonical one does.
+
+mod common;
+
+use prover::bulk::run_job;
+use prover::error::ServiceError;
+use prover::mock::MockProofGenerator;
+use prover::types::{BulkJob, ProverRequest, SignalAliases};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+const ALIASES: &str =
+    "captcha=recaptcha_score, recaptcha_v3=recaptcha_score,phone_verified=sms_verified";
+
+fn aliases() -> SignalAliases {
+    ALIASES.parse().unwrap()
+}
+
+fn request(verification_results: Value) -> Vec<u8> {
+    let mut request = common::request_json("aliased-1");
+    request["verification_results"] = verification_results;
+    serde_json::to_vec(&request).unwrap()
+}
+
+/// The request `data` parses to, after renaming its aliases
+fn parse(data: &[u8]) -> Result<Value, ServiceError> {
+    let data = aliases().normalize(data)?;
+    let request = ProverRequest::from_json(&data)?;
+    Ok(serde_json::to_value(&request).unwrap())
+}
+
+#[test]
+fn aliased_request_normalizes_to_the_canonical_request() {
+    let canonical =
+        request(json!({ "recaptcha_score": 8000, "sms_verified": 1, "bio_verified": 1 }));
+    let expected = parse(&canonical).unwrap();
+
+    for aliased in [
+        json!({ "captcha": 8000, "phone_verified": 1, "bio_verified": 1 }),
+        json!({ "recaptcha_v3": 0.8, "sms_verified": 1, "bio_verified": 1 }),
+    ] {
+        assert_eq!(
+            parse(&request(aliased.clone())).unwrap(),
+            expected,
+            "{}",
+            aliased
+        );
+    }
+}
+
+#[test]
+fn requests_without_aliases_are_left_as_sent() {
+    let canonical =
+        request(json!({ "recaptcha_score": 8000, "sms_verified": 1, "bio_verified": 1 }));
+    assert!(matches!(
+        aliases().normalize(&canonical).unwrap(),
+        std::borrow::Cow::Borrowed(_)
+    ));
+    // Not JSON: parsing reports it as without aliases
+    assert!(matches!(
+        aliases().normalize(b"not json").unwrap(),
+        std::borrow::Cow::Borrowed(_)
+    ));
+}
+
+#[test]
+fn a_field_sent_under_two_names_is_malformed() {
+    for results in [
+        json!({ "captcha": 8000, "recaptcha_score": 8000, "sms_verified": 1, "bio_verified": 1 }),
+        json!({ "captcha": 8000, "recaptcha_v3": 8000, "sms_verified": 1, "bio_verified": 1 }),
+    ] {
+        let err = parse(&request(results)).unwrap_err();
+        assert!(matches!(err, ServiceError::MalformedRequest(_)), "{}", err);
+        assert!(err.to_string().contains("recaptcha_score"), "{}", err);
+        // The private values are not quoted
+        assert!(!err.to_string().contains("8000"), "{}", err);
+    }
+}
+
+#[test]
+fn aliases_must_name_a_verification_result() {
+    assert!(aliases().canonical("captcha") == Some("recaptcha_score"));
+    assert!("".parse::<SignalAliases>().unwrap().is_empty());
+    for invalid in [
+        "captcha",
+        "captcha=score",
+        "captcha=w1",
+        "=recaptcha_score",
+        "sms_verified=bio_verified",
+        "captcha=recaptcha_score,captcha=sms_verified",
+    ] {
+        let err = invalid.parse::<SignalAliases>().unwrap_err();
+        assert!(err.to_string().contains("SIGNAL_ALIASES"), "{}", err);
+    }
+}
+
+#[tokio::test]
+async fn aliased_lines_are_proved_as_canonical_ones() {
+    let mut config = common::bulk_config();
+    config.signal_aliases = aliases();
+
+    let dir = std::env::temp_dir().join(format!("prover-signal-aliases-{}", std::process::id()));
+    std::fs::remove_dir_all(&dir).ok();
+    std::fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("input.jsonl");
+    std::fs::write(
+        &input,
+        "{\"captcha\": 8000, \"phone_verified\": 1, \"bio_verified\": 1}\n\
+         {\"recaptcha_score\": 8000, \"sms_verified\": 1, \"bio_verified\": 1}\n",
+    )
+    .unwrap();
+    let job = BulkJob {
+        request_id: "aliases-job".to_string(),
+        input_uri: format!("file://{}", input.display()),
+        output_uri: format!("file://{}", dir.join("out").display()),
+        weights_profile: None,
+    };
+    let backend = Arc::new(MockProofGenerator::new(
+        Duration::from_millis(10),
+        Duration::from_secs(60),
+    ));
+    let responses = std::sync::Mutex::new(Vec::new());
+    let summary = run_job(
+        &job,
+        &config,
+        backend,
+        None,
+        None,
+        None,
+        &CancellationToken::new(),
+        |response| responses.lock().unwrap().push(response.clone()),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!((summary.succeeded, summary.rejected), (2, 0));
+    // Unaliased, the first line would miss recaptcha_score and be rejected
+    let responses = responses.into_inner().unwrap();
+    let indexes: Vec<u32> = responses
+        .iter()
+        .map(|response| response.proof_data.as_ref().unwrap().human_index)
+        .collect();
+    assert_eq!(indexes[0], indexes[1]);
+    std::fs::remove_dir_all(&dir).ok();
+}
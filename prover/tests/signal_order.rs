@@ -0,0 +1,157 @@
+// Checks that the stdin byte stream depends only on the signal values, never on
+// the order of keys in the request JSON, and that named signals resolve by name.
+
+use human_index_lib::{ProofInputs, Validity, STDIN_WORDS};
+use prover::prover::InputLayout;
+use prover::schema::RequestValidator;
+use prover::types::{ProverRequest, Signal, STDIN_ORDER};
+use serde_json::json;
+
+/// The stdin bytes a host writes for `json`
+fn stdin_bytes(json: &str) -> Vec<u8> {
+    let request = serde_json::from_str::<ProverRequest>(json)
+        .and_then(ProverRequest::resolve_signals)
+        .unwrap();
+    let validity = request.validity(1_700_000_000);
+    let mut bytes = Vec::new();
+    ProofInputs::new(
+        request.verification_results,
+        request.public_inputs,
+        validity,
+    )
+    .write_to(|value| bytes.extend(bincode::serialize(value).unwrap()));
+    bytes
+}
+
+#[test]
+fn reordered_json_keys_give_identical_stdin() {
+    let canonical = r#"{
+        "request_id": "order",
+        "verification_results": { "recaptcha_score": 8000, "sms_verified": 1, "bio_verified": 0 },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 }
+    }"#;
+    let reordered = r#"{
+        "public_inputs": { "expected_output": 0, "w4": 4000, "w3": 2500, "w1": 1500, "w2": 2000 },
+        "verification_results": { "bio_verified": 0, "sms_verified": 1, "recaptcha_score": 8000 },
+        "request_id": "order"
+    }"#;
+    assert_eq!(stdin_bytes(canonical), stdin_bytes(reordered));
+}
+
+#[test]
+fn named_signals_in_any_order_give_identical_stdin() {
+    // Structured values are all zero, so everything proven comes from the signals
+    let with_signals = |signals: &str| {
+        format!(
+            r#"{{
+                "request_id": "signals",
+                "verification_results": {{ "recaptcha_score": 0, "sms_verified": 0, "bio_verified": 0 }},
+                "public_inputs": {{ "w1": 0, "w2": 0, "w3": 0, "w4": 0, "expected_output": 0 }},
+                "signals": {}
+            }}"#,
+            signals
+        )
+    };
+    let forward = with_signals(
+        r#"{ "recaptcha": 8000, "sms": 1, "bio": 1, "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000 }"#,
+    );
+    let backward = with_signals(
+        r#"{ "w4": 4000, "w3": 2500, "w2": 2000, "w1": 1500, "bio": 1, "sms": 1, "recaptcha": 8000 }"#,
+    );
+    let structured = r#"{
+        "request_id": "signals",
+        "verification_results": { "recaptcha_score": 8000, "sms_verified": 1, "bio_verified": 1 },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 }
+    }"#;
+    assert_eq!(stdin_bytes(&forward), stdin_bytes(&backward));
+    assert_eq!(stdin_bytes(&forward), stdin_bytes(structured));
+}
+
+#[test]
+fn stdin_follows_the_canonical_order() {
+    let request: ProverRequest = serde_json::from_value(json!({
+        "request_id": "canonical",
+        "verification_results": { "recaptcha_score": 1, "sms_verified": 2, "bio_verified": 3 },
+        "public_inputs": { "w1": 4, "w2": 5, "w3": 6, "w4": 7, "expected_output": 0 },
+    }))
+    .unwrap();
+    let proof_inputs = ProofInputs {
+        results: request.verification_results,
+        inputs: request.public_inputs,
+        expected_output: 8,
+        validity: Validity {
+            issued_at: 9 | 10 << 32,
+            valid_until: 11 | 12 << 32,
+        },
+    };
+    let mut written = Vec::new();
+    proof_inputs.write_to(|value| written.push(*value));
+    assert_eq!(written, [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]);
+
+    let names: Vec<_> = STDIN_ORDER.map(Signal::name).to_vec();
+    assert_eq!(
+        names,
+        [
+            "recaptcha",
+            "sms",
+            "bio",
+            "w1",
+            "w2",
+            "w3",
+            "w4",
+            "expected_output"
+        ]
+    );
+    assert_eq!(InputLayout::runtime().arity(), STDIN_WORDS);
+}
+
+#[test]
+fn signals_override_structured_values() {
+    let request: ProverRequest = serde_json::from_value(json!({
+        "request_id": "override",
+        "verification_results": { "recaptcha_score": 8000, "sms_verified": 1, "bio_verified": 1 },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 },
+        "signals": { "bio": 0, "w1": 1000 },
+    }))
+    .unwrap();
+    let request = request.resolve_signals().unwrap();
+    assert!(request.signals.is_none());
+    assert_eq!(request.verification_results.bio_verified, 0);
+    assert_eq!(request.verification_results.sms_verified, 1);
+    assert_eq!(request.public_inputs.w1, 1000);
+    assert_eq!(request.public_inputs.w2, 2000);
+}
+
+#[test]
+fn unknown_signal_is_rejected() {
+    let request = json!({
+        "request_id": "unknown",
+        "verification_results": { "recaptcha_score": 8000, "sms_verified": 1, "bio_verified": 1 },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 },
+        "signals": { "recaptcha_score": 9000 },
+    });
+    let error = serde_json::from_value::<ProverRequest>(request.clone())
+        .unwrap()
+        .resolve_signals()
+        .unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("unknown signal 'recaptcha_score'"));
+
+    let violations = RequestValidator::new().validate(&request);
+    let paths: Vec<_> = violations.iter().map(|v| v.path.as_str()).collect();
+    assert_eq!(paths, ["/signals/recaptcha_score"]);
+}
+
+#[test]
+fn out_of_range_signal_is_reported_at_its_key() {
+    let request = json!({
+        "request_id": "range",
+        "verification_results": { "recaptcha_score": 8000, "sms_verified": 1, "bio_verified": 1 },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 },
+        "signals": { "sms": 2 },
+    });
+    let violations = RequestValidator::new().validate(&request);
+    let paths: Vec<_> = violations.iter().map(|v| v.path.as_str()).collect();
+    assert_eq!(paths, ["/signals/sms"]);
+}
@@ -0,0 +1,83 @@
+// Verification results outside the range the formula expects: clamped to their
+// maximum or rejected, as `SIGNAL_POLICY` says, and never echoed in the error.
+
+use human_index_lib::{VerificationResults, SCALE};
+use prover::error::ServiceError;
+use prover::types::SignalPolicy;
+
+fn results(recaptcha_score: u32, sms_verified: u32, bio_verified: u32) -> VerificationResults {
+    VerificationResults {
+        recaptcha_score,
+        sms_verified,
+        bio_verified,
+    }
+}
+
+#[test]
+fn clamp_lowers_results_to_their_maximum() {
+    let mut clamped = results(15000, 3, 0);
+    assert_eq!(SignalPolicy::Clamp.apply(&mut clamped), Ok(()));
+    assert_eq!(clamped, results(SCALE, 1, 0));
+
+    let mut clamped = results(15000, 1, 0);
+    SignalPolicy::Clamp.apply(&mut clamped).unwrap();
+    assert_eq!(clamped, results(SCALE, 1, 0));
+
+    let mut clamped = results(7500, 3, 2);
+    SignalPolicy::Clamp.apply(&mut clamped).unwrap();
+    assert_eq!(clamped, results(7500, 1, 1));
+}
+
+#[test]
+fn reject_names_the_fields_out_of_range() {
+    let mut rejected = results(15000, 3, 0);
+    let message = SignalPolicy::Reject.apply(&mut rejected).unwrap_err();
+    assert_eq!(
+        message,
+        "recaptcha_score is above SCALE (10000); sms_verified is not 0 or 1"
+    );
+    // Left as sent, and the private values stay out of the message
+    assert_eq!(rejected, results(15000, 3, 0));
+    assert!(
+        !message.contains("15000") && !message.contains('3'),
+        "{}",
+        message
+    );
+
+    let message = SignalPolicy::Reject
+        .apply(&mut results(15000, 0, 0))
+        .unwrap_err();
+    assert_eq!(message, "recaptcha_score is above SCALE (10000)");
+    let message = SignalPolicy::Reject
+        .apply(&mut results(0, 3, 0))
+        .unwrap_err();
+    assert_eq!(message, "sms_verified is not 0 or 1");
+}
+
+#[test]
+fn results_in_range_pass_either_policy() {
+    for policy in [SignalPolicy::Reject, SignalPolicy::Clamp] {
+        for in_range in [results(0, 0, 0), results(SCALE, 1, 1), results(8000, 1, 0)] {
+            let mut applied = in_range.clone();
+            assert_eq!(policy.apply(&mut applied), Ok(()));
+            assert_eq!(applied, in_range);
+        }
+    }
+}
+
+#[test]
+fn policy_parses_from_config() {
+    assert_eq!(SignalPolicy::default(), SignalPolicy::Reject);
+    assert_eq!(
+        "clamp".parse::<SignalPolicy>().unwrap(),
+        SignalPolicy::Clamp
+    );
+    assert_eq!(
+        "REJECT".parse::<SignalPolicy>().unwrap(),
+        SignalPolicy::Reject
+    );
+    assert!(matches!(
+        "ignore".parse::<SignalPolicy>(),
+        Err(ServiceError::Config(_))
+    ));
+}
@@ -0,0 +1,144 @@
+// Startup failures: each step's errors mapped to the category, and so the exit code,
+// an orchestrator decides whether to retry by.
+
+use prover::config::Config;
+use prover::docker::DockerPreflight;
+use prover::error::ServiceError;
+use prover::prover::ProverProfile;
+use prover::startup::{check_config, load_artifacts, self_test, StartupCategory, StartupFailure};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// A valid configuration, read once from a minimal environment
+fn config() -> Config {
+    static BASE: OnceLock<Config> = OnceLock::new();
+    let mut config = BASE
+        .get_or_init(|| {
+            std::env::set_var("GCP_PROJECT_ID", "startup-test");
+            std::env::set_var("PROVER_SUBSCRIPTION", "unused");
+            std::env::set_var("RESULT_TOPIC", "unused");
+            // Nothing listens here, so the Docker preflight fails
+            std::env::set_var("DOCKER_HOST", "unix:///nonexistent/docker.sock");
+            Config::from_env().expect("base configuration")
+        })
+        .clone();
+    config.output_dir = temp_dir("output").to_string_lossy().into_owned();
+    config.startup_retry_attempts = 1;
+    config.startup_retry_delay_secs = 0;
+    config
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("prover-startup-{}-{}", name, std::process::id()))
+}
+
+#[test]
+fn a_valid_config_passes_and_gets_an_output_dir() {
+    let config = check_config(config()).expect("config is valid");
+    assert!(PathBuf::from(&config.output_dir).is_dir());
+}
+
+#[test]
+fn invalid_settings_are_config_failures() {
+    let mut config = config();
+    config.max_concurrent_proofs = 0;
+    let failure = check_config(config).expect_err("no proofs allowed");
+    assert_eq!(failure.category, StartupCategory::Config);
+    assert_eq!(failure.category.exit_code(), 2);
+    assert!(
+        failure.message.contains("MAX_CONCURRENT_PROOFS"),
+        "{}",
+        failure.message
+    );
+}
+
+#[test]
+fn an_unwritable_output_dir_is_a_config_failure() {
+    let file = temp_dir("file");
+    std::fs::write(&file, "not a directory").unwrap();
+    let mut config = config();
+    config.output_dir = file.join("data").to_string_lossy().into_owned();
+
+    let failure = check_config(config).expect_err("output dir is under a file");
+    assert_eq!(failure.category, StartupCategory::Config);
+    assert!(
+        failure.message.contains("not writable"),
+        "{}",
+        failure.message
+    );
+    std::fs::remove_file(file).ok();
+}
+
+#[tokio::test]
+async fn a_missing_elf_is_an_artifacts_failure() {
+    let mut config = config();
+    config.elf_path = temp_dir("missing-elf").to_string_lossy().into_owned();
+    let Err(failure) = load_artifacts(&config).await else {
+        panic!("loaded without an ELF");
+    };
+    assert_eq!(failure.category, StartupCategory::Artifacts);
+    assert_eq!(failure.category.exit_code(), 3);
+}
+
+#[tokio::test]
+async fn a_malformed_elf_url_is_a_config_failure() {
+    let mut config = config();
+    config.elf_path = "gs://bucket-only".to_string();
+    let Err(failure) = load_artifacts(&config).await else {
+        panic!("loaded without an object name");
+    };
+    assert_eq!(failure.category, StartupCategory::Config);
+}
+
+#[tokio::test]
+async fn a_failed_docker_preflight_is_a_self_test_failure() {
+    let mut config = config();
+    config.prover_profile = ProverProfile::Evm;
+    config.docker_preflight = DockerPreflight::Fail;
+    let failure = self_test(&config).await.expect_err("no Docker daemon");
+    assert_eq!(failure.category, StartupCategory::SelfTest);
+    assert_eq!(failure.category.exit_code(), 5);
+
+    // Only warned about otherwise, and never run for fast proofs
+    config.docker_preflight = DockerPreflight::Warn;
+    assert!(self_test(&config).await.is_ok());
+    config.docker_preflight = DockerPreflight::Fail;
+    config.prover_profile = ProverProfile::Fast;
+    assert!(self_test(&config).await.unwrap().is_none());
+}
+
+#[test]
+fn pubsub_failures_keep_their_category_unless_misconfigured() {
+    let pubsub = StartupFailure::new(
+        StartupCategory::PubSub,
+        ServiceError::PubSub("Failed to create Pub/Sub client".to_string()),
+    );
+    assert_eq!(pubsub.category, StartupCategory::PubSub);
+    assert_eq!(pubsub.category.exit_code(), 4);
+
+    let misconfigured = StartupFailure::new(
+        StartupCategory::PubSub,
+        ServiceError::Config("CALLBACK_SIGNING_KEY must be set".to_string()),
+    );
+    assert_eq!(misconfigured.category, StartupCategory::Config);
+}
+
+#[test]
+fn the_report_is_a_single_json_line() {
+    let failure = StartupFailure::new(
+        StartupCategory::Artifacts,
+        ServiceError::ProofGeneration("ELF SHA-256 mismatch".to_string()),
+    );
+    let report = failure.report();
+    assert!(!report.contains('\n'));
+    let report: serde_json::Value = serde_json::from_str(&report).unwrap();
+    assert_eq!(report["category"], "artifacts");
+    assert_eq!(
+        report["message"],
+        "Proof generation failed: ELF SHA-256 mismatch"
+    );
+    assert_eq!(
+        report["remediation"],
+        StartupCategory::Artifacts.remediation()
+    );
+}
@@ -0,0 +1,117 @@
+// Startup files on a volume that is mounted after the worker starts: loading the
+// ELF and resolving the setup files retry until they appear, within a bound.
+
+use prover::error::ServiceError;
+use prover::prover::{load_and_cache_elf, resolve_setup_files, StartupRetry, SETUP_FILES};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+const ELF: &[u8] = b"\x7fELF not a real guest";
+
+fn retry(attempts: u32) -> StartupRetry {
+    StartupRetry {
+        attempts,
+        backoff: Duration::from_millis(300),
+    }
+}
+
+/// An empty directory unique to test `name`
+fn volume(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("prover-startup-{}-{}", name, std::process::id()));
+    std::fs::remove_dir_all(&dir).ok();
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Write `files` into `dir` after `delay`, as a volume mount would
+fn mount_later(dir: &Path, files: &[&str], delay: Duration) -> std::thread::JoinHandle<()> {
+    let paths: Vec<PathBuf> = files.iter().map(|file| dir.join(file)).collect();
+    std::thread::spawn(move || {
+        std::thread::sleep(delay);
+        for path in paths {
+            std::fs::write(path, ELF).unwrap();
+        }
+    })
+}
+
+#[tokio::test]
+async fn elf_available_on_second_attempt_loads() {
+    let dir = volume("elf-late");
+    let elf_path = dir.join("guest.elf");
+    // The first attempt runs at once; the retry 300ms later finds the file
+    let mount = mount_later(&dir, &["guest.elf"], Duration::from_millis(100));
+
+    let cached_elf = load_and_cache_elf(elf_path.to_str().unwrap(), None, retry(3))
+        .await
+        .expect("ELF loads once mounted");
+    assert_eq!(cached_elf.data, ELF);
+
+    mount.join().unwrap();
+    std::fs::remove_dir_all(dir).ok();
+}
+
+#[tokio::test]
+async fn missing_elf_fails_after_the_retries() {
+    let dir = volume("elf-missing");
+    let elf_path = dir.join("guest.elf");
+
+    let error = load_and_cache_elf(elf_path.to_str().unwrap(), None, retry(1))
+        .await
+        .err()
+        .expect("no ELF to load");
+    assert!(matches!(error, ServiceError::ProofGeneration(_)));
+    assert!(error.to_string().contains("guest.elf"), "{}", error);
+
+    std::fs::remove_dir_all(dir).ok();
+}
+
+#[tokio::test]
+async fn setup_files_resolve_once_mounted() {
+    let dir = volume("setup-late");
+    let mount = mount_later(&dir, &SETUP_FILES, Duration::from_millis(100));
+
+    let resolved = resolve_setup_files(&dir, retry(3))
+        .await
+        .expect("setup files resolve once mounted");
+    let canonical = dir.canonicalize().unwrap();
+    assert_eq!(
+        resolved,
+        SETUP_FILES.map(|name| canonical.join(name)).to_vec()
+    );
+
+    mount.join().unwrap();
+    std::fs::remove_dir_all(dir).ok();
+}
+
+#[tokio::test]
+async fn retries_are_bounded() {
+    let calls = AtomicU32::new(0);
+    let retry = StartupRetry {
+        attempts: 2,
+        backoff: Duration::from_millis(1),
+    };
+
+    let result: Result<(), ServiceError> = retry
+        .run("Failing step", || {
+            calls.fetch_add(1, Ordering::SeqCst);
+            std::future::ready(Err(ServiceError::Config("not yet".to_string())))
+        })
+        .await;
+    assert!(result.is_err());
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+    // No retries at all with 0 attempts
+    let once = StartupRetry {
+        attempts: 0,
+        ..retry
+    };
+    calls.store(0, Ordering::SeqCst);
+    let _ = once
+        .run("Failing step", || {
+            calls.fetch_add(1, Ordering::SeqCst);
+            std::future::ready(Err::<(), _>(ServiceError::Config("not yet".to_string())))
+        })
+        .await;
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
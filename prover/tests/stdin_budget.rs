@@ -0,0 +1,41 @@
+// The stdin budget: a request is written to stdin words within MAX_STDIN_BYTES,
+// with the time it took, and one over the budget fails fast as RequestTooLarge,
+// before anything is written to the zkVM.
+
+use human_index_lib::STDIN_WORDS;
+use prover::error::ServiceError;
+use prover::prover::{prepare_stdin, setup_inputs, STDIN_PREPARE_TIMEOUT};
+use std::time::{Duration, Instant};
+
+/// Bytes the stdin of every request takes
+const STDIN_BYTES: u64 = (STDIN_WORDS * 4) as u64;
+
+#[test]
+fn requests_within_the_budget_are_prepared() {
+    let (stdin, elapsed) = prepare_stdin(setup_inputs(), STDIN_BYTES).unwrap();
+    assert_eq!(stdin.byte_len(), STDIN_BYTES);
+    assert!(elapsed < STDIN_PREPARE_TIMEOUT, "{:?}", elapsed);
+
+    let mut words = Vec::new();
+    stdin.write_to(|value| words.push(*value));
+    assert_eq!(words.len(), STDIN_WORDS);
+}
+
+#[test]
+fn requests_over_the_budget_are_too_large() {
+    let started = Instant::now();
+    let error = prepare_stdin(setup_inputs(), STDIN_BYTES - 1)
+        .err()
+        .expect("over the budget");
+    assert!(started.elapsed() < Duration::from_secs(1));
+
+    assert!(
+        matches!(error, ServiceError::RequestTooLarge(_)),
+        "{}",
+        error
+    );
+    assert_eq!(error.error_type(), "RequestTooLarge");
+    let message = error.to_string();
+    assert!(message.contains("MAX_STDIN_BYTES"), "{}", message);
+    assert!(message.contains(&STDIN_BYTES.to_string()), "{}", message);
+}
@@ -0,0 +1,86 @@
+// What the prover binaries hand the guest, through a recording stand-in for the
+// SDK's stdin builder: the verification results, the weights, the expected output
+// and the validity window, as twelve words in the order the guest reads them.
+
+use human_index_lib::{
+    calculate_human_index, HumanIndexPublicInputs, ProofInputs, Validity, VerificationResults,
+};
+
+/// Records the values written to it, like `EmulatorStdinBuilder::write` receives them
+#[derive(Default)]
+struct RecordingBuilder {
+    writes: Vec<u32>,
+}
+
+impl RecordingBuilder {
+    fn write(&mut self, value: &u32) {
+        self.writes.push(*value);
+    }
+}
+
+fn results() -> VerificationResults {
+    VerificationResults {
+        recaptcha_score: 8000,
+        sms_verified: 1,
+        bio_verified: 0,
+    }
+}
+
+fn weights() -> HumanIndexPublicInputs {
+    HumanIndexPublicInputs {
+        w1: 1500,
+        w2: 2000,
+        w3: 2500,
+        w4: 4000,
+        expected_output: 0,
+    }
+}
+
+fn recorded(expected_output: u32) -> Vec<u32> {
+    let mut builder = RecordingBuilder::default();
+    let proof_inputs = ProofInputs {
+        results: results(),
+        inputs: weights(),
+        expected_output,
+        validity: Validity {
+            issued_at: 1_700_000_000,
+            valid_until: 1_700_003_600,
+        },
+    };
+    proof_inputs.write_to(|value| builder.write(value));
+    builder.writes
+}
+
+#[test]
+fn inputs_are_written_in_the_order_the_guest_reads_them() {
+    let expected_output = calculate_human_index(&results(), &weights());
+    assert_eq!(expected_output, 142);
+    assert_eq!(
+        recorded(expected_output),
+        [
+            // Verification results
+            8000,
+            1,
+            0, //
+            // Weights, then the expected output
+            1500,
+            2000,
+            2500,
+            4000,
+            142, //
+            // Validity window, each bound as low then high word
+            1_700_000_000,
+            0,
+            1_700_003_600,
+            0,
+        ]
+    );
+}
+
+#[test]
+fn the_expected_output_is_written_as_given() {
+    // Not recomputed, and not taken from the public inputs
+    let writes = recorded(7);
+    assert_eq!(writes.len(), 12);
+    assert_eq!(writes[7], 7);
+}
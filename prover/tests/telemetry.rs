@@ -0,0 +1,123 @@
+// Telemetry events carry only bucketed, non-reversible stats: no identifiers, no
+// scores or weights, no exact index and no exact time.
+
+use chrono::{TimeZone, Utc};
+use prover::mock::MockProofGenerator;
+use prover::prover::{ProofBackend, ProofContext};
+use prover::telemetry::{
+    index_bucket, sampled, SignalPresence, TelemetryEvent, INDEX_BUCKET_WIDTH,
+};
+use prover::types::{ProofMetrics, ProofStatus, ProverRequest, ProverResponse};
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::time::{Duration, Instant};
+
+fn request(request_id: &str, sms_verified: u32) -> ProverRequest {
+    serde_json::from_value(json!({
+        "request_id": request_id,
+        "client_id": "tenant-7",
+        "verification_results": {
+            "recaptcha_score": 7321,
+            "sms_verified": sms_verified,
+            "bio_verified": 1
+        },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 },
+    }))
+    .unwrap()
+}
+
+fn response(request: &ProverRequest) -> ProverResponse {
+    let backend = MockProofGenerator::new(Duration::ZERO, Duration::from_secs(60));
+    let context = ProofContext::new(Instant::now() + Duration::from_secs(60), 0);
+    let proof_data = backend.generate_proof(request.clone(), &context).unwrap();
+    let metrics = ProofMetrics {
+        received_at: "2026-10-16T09:41:07Z".to_string(),
+        started_at: "2026-10-16T09:41:07Z".to_string(),
+        completed_at: "2026-10-16T09:41:08Z".to_string(),
+        duration_ms: 1000,
+        attempts: Some(1),
+        queue_wait_ms: None,
+        usage: Default::default(),
+        cost_units: None,
+        progress: None,
+    };
+    ProverResponse::success(request.request_id.clone(), proof_data, metrics)
+}
+
+#[test]
+fn event_holds_only_bucketed_fields() {
+    let request = request("req-telemetry-1", 0);
+    let response = response(&request);
+    let human_index = response.proof_data.as_ref().unwrap().human_index;
+    let now = Utc.with_ymd_and_hms(2026, 10, 16, 9, 41, 7).unwrap();
+
+    let event = TelemetryEvent::new(&request, &response, now);
+    assert_eq!(event.outcome, ProofStatus::Success);
+    assert_eq!(
+        event.signals,
+        SignalPresence {
+            recaptcha: true,
+            sms: false,
+            bio: true
+        }
+    );
+    let bucket = event.index_bucket.unwrap();
+    assert_eq!(bucket % INDEX_BUCKET_WIDTH, 0);
+    assert!((bucket..bucket + INDEX_BUCKET_WIDTH).contains(&human_index));
+
+    let encoded = serde_json::to_value(&event).unwrap();
+    let keys: BTreeSet<_> = encoded.as_object().unwrap().keys().cloned().collect();
+    assert_eq!(
+        keys,
+        BTreeSet::from(["hour", "outcome", "index_bucket", "signals"].map(String::from))
+    );
+    let signals = encoded["signals"].as_object().unwrap();
+    assert!(signals.values().all(Value::is_boolean));
+    assert_eq!(encoded["hour"], "2026-10-16T09:00:00+00:00");
+
+    let text = encoded.to_string();
+    for raw in ["req-telemetry-1", "tenant-7", "7321", "1500", "09:41"] {
+        assert!(!text.contains(raw), "{} leaked into {}", raw, text);
+    }
+    if human_index != bucket {
+        assert!(!text.contains(&format!(":{}", human_index)), "{}", text);
+    }
+}
+
+#[test]
+fn failed_proof_has_no_bucket() {
+    let request = request("req-telemetry-2", 1);
+    let mut response = response(&request);
+    response.status = ProofStatus::Failed;
+    response.proof_data = None;
+
+    let event = TelemetryEvent::new(&request, &response, Utc::now());
+    let encoded = serde_json::to_value(&event).unwrap();
+    assert!(encoded.get("index_bucket").is_none());
+    assert_eq!(encoded["outcome"], "failed");
+}
+
+#[test]
+fn buckets_cover_the_index_range() {
+    assert_eq!(index_bucket(0), 0);
+    assert_eq!(index_bucket(INDEX_BUCKET_WIDTH - 1), 0);
+    assert_eq!(index_bucket(INDEX_BUCKET_WIDTH), INDEX_BUCKET_WIDTH);
+    assert_eq!(index_bucket(255), 256 - INDEX_BUCKET_WIDTH);
+    assert_eq!(index_bucket(u32::MAX), 256 - INDEX_BUCKET_WIDTH);
+}
+
+#[test]
+fn sampling_follows_the_rate() {
+    let ids: Vec<String> = (0..2000).map(|i| format!("req-{}", i)).collect();
+    assert!(ids.iter().all(|id| !sampled(id, 0.0)));
+    assert!(ids.iter().all(|id| sampled(id, 1.0)));
+
+    let picked = ids.iter().filter(|id| sampled(id, 0.25)).count();
+    assert!((400..600).contains(&picked), "{} of 2000 sampled", picked);
+
+    // A sampled request stays sampled at any higher rate
+    assert!(ids
+        .iter()
+        .filter(|id| sampled(id, 0.25))
+        .all(|id| sampled(id, 0.5)));
+}
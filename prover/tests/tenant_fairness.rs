@@ -0,0 +1,140 @@
+// Fair scheduling across tenants with `FAIR_SCHEDULING`: tenant A floods the queue,
+// and tenant B's single request, arriving behind the whole flood, still gets the
+// first permit to free up. Without fair scheduling every message queues as one
+// tenant and B waits for the flood to drain.
+
+use prover::mock::MockProofGenerator;
+use prover::prover::{ProofBackend, ProofContext};
+use prover::scheduler::{Lanes, Scheduler};
+use prover::types::{Priority, ProverRequest};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
+
+const CAPACITY: u32 = 2;
+const FLOOD: usize = 20;
+const PROOF_DURATION: Duration = Duration::from_millis(30);
+
+fn start() -> Arc<Scheduler> {
+    Scheduler::start(
+        Arc::new(Semaphore::new(CAPACITY as usize)),
+        Lanes {
+            capacity: CAPACITY,
+            interactive_reserved: 0,
+            batch_promotion: None,
+            queue_wait_warning: None,
+        },
+    )
+}
+
+fn request(request_id: &str) -> ProverRequest {
+    serde_json::from_value(json!({
+        "request_id": request_id,
+        "verification_results": { "recaptcha_score": 9000, "sms_verified": 1, "bio_verified": 0 },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 },
+    }))
+    .unwrap()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Event {
+    Started(String),
+    Completed(String),
+}
+
+/// Wait for a permit like the service does, queued as the request's tenant when
+/// `fair`, then prove with the mock backend
+///
+/// On the current-thread runtime tasks first run in the order they were spawned,
+/// so messages queue in submission order.
+fn submit(
+    scheduler: &Arc<Scheduler>,
+    events: &Arc<Mutex<Vec<Event>>>,
+    request: ProverRequest,
+    fair: bool,
+) -> JoinHandle<()> {
+    let scheduler = scheduler.clone();
+    let events = events.clone();
+    tokio::spawn(async move {
+        let tenant = if fair { request.tenant() } else { "" };
+        let _permit = scheduler
+            .acquire(tenant, Priority::Normal, 1)
+            .await
+            .expect("semaphore open");
+        let request_id = request.request_id.clone();
+        events
+            .lock()
+            .unwrap()
+            .push(Event::Started(request_id.clone()));
+
+        let backend = MockProofGenerator::new(PROOF_DURATION, Duration::from_secs(60));
+        let context = ProofContext::new(Instant::now() + Duration::from_secs(60), 0);
+        tokio::task::spawn_blocking(move || backend.generate_proof(request, &context))
+            .await
+            .unwrap()
+            .expect("mock proof");
+        events.lock().unwrap().push(Event::Completed(request_id));
+    })
+}
+
+/// Tenant A floods the queue, then tenant B sends one request; returns how many
+/// proofs completed before B's started
+async fn completions_before_b(fair: bool, b: ProverRequest) -> usize {
+    let scheduler = start();
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let mut tasks: Vec<JoinHandle<()>> = (0..FLOOD)
+        .map(|i| submit(&scheduler, &events, request(&format!("acme:{}", i)), fair))
+        .collect();
+    let b_id = b.request_id.clone();
+    tasks.push(submit(&scheduler, &events, b, fair));
+    for task in tasks {
+        task.await.unwrap();
+    }
+
+    let events = events.lock().unwrap();
+    let b_started = events
+        .iter()
+        .position(|event| *event == Event::Started(b_id.clone()))
+        .expect("B started");
+    events[..b_started]
+        .iter()
+        .filter(|event| matches!(event, Event::Completed(_)))
+        .count()
+}
+
+#[tokio::test]
+async fn flooding_tenant_does_not_starve_another() {
+    // The first permit to free up goes to B, who holds none; the other proof
+    // running alongside may finish before B is seen to start
+    let completions = completions_before_b(true, request("globex:1")).await;
+    assert!(
+        completions <= CAPACITY as usize,
+        "B waited for {} completions behind the flood",
+        completions
+    );
+}
+
+#[tokio::test]
+async fn tenant_id_takes_precedence_over_the_prefix() {
+    // Queued as globex despite the acme prefix
+    let mut b = request("acme:late");
+    b.tenant_id = Some("globex".to_string());
+    let completions = completions_before_b(true, b).await;
+    assert!(
+        completions <= CAPACITY as usize,
+        "B waited for {} completions",
+        completions
+    );
+}
+
+#[tokio::test]
+async fn without_fair_scheduling_the_flood_goes_first() {
+    let completions = completions_before_b(false, request("globex:1")).await;
+    assert!(
+        completions >= FLOOD - CAPACITY as usize,
+        "B started after only {} completions",
+        completions
+    );
+}
@@ -0,0 +1,196 @@
+// The validity window a proof commits to: how the service fills and clamps it, how
+// it reaches the public values and verifier calldata, and when a cached proof may
+// answer a request.
+
+use human_index_lib::{Validity, ValidityError, MAX_VALIDITY_SECS};
+use prover::evm::{build_verify_calldata, decode_calldata, VerifyCall, VALID_UNTIL_OFFSET};
+use prover::mock::MockProofGenerator;
+use prover::proof_cache::{CacheLimits, CachingBackend, NoStore, ProofCache};
+use prover::prover::{ProofBackend, ProofContext};
+use prover::schema::RequestValidator;
+use prover::types::ProverRequest;
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+
+const NOW: u64 = 1_800_000_000;
+
+fn request_json(request_id: &str, window: Value) -> Value {
+    let mut request = json!({
+        "request_id": request_id,
+        "verification_results": { "recaptcha_score": 7500, "sms_verified": 1, "bio_verified": 1 },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 },
+    });
+    for (key, value) in window.as_object().unwrap() {
+        request[key] = value.clone();
+    }
+    request
+}
+
+fn request(request_id: &str, window: Value) -> ProverRequest {
+    serde_json::from_value(request_json(request_id, window)).unwrap()
+}
+
+fn context() -> ProofContext {
+    ProofContext::new(Instant::now() + Duration::from_secs(60), 0)
+}
+
+fn mock() -> MockProofGenerator {
+    MockProofGenerator::new(Duration::ZERO, Duration::from_secs(60))
+}
+
+fn unix_now() -> u64 {
+    chrono::Utc::now().timestamp() as u64
+}
+
+#[test]
+fn missing_window_starts_now_and_lasts_the_maximum() {
+    let request = request("default", json!({})).resolve_validity(NOW);
+    assert_eq!(request.issued_at, Some(NOW));
+    assert_eq!(request.valid_until, Some(NOW + MAX_VALIDITY_SECS));
+}
+
+#[test]
+fn long_window_is_clamped() {
+    let window = json!({ "issued_at": NOW - 100, "valid_until": NOW + 10 * MAX_VALIDITY_SECS });
+    let request = request("long", window).resolve_validity(NOW);
+    assert_eq!(request.issued_at, Some(NOW - 100));
+    assert_eq!(request.valid_until, Some(NOW - 100 + MAX_VALIDITY_SECS));
+
+    // Near u64::MAX the bound saturates rather than wrapping
+    assert_eq!(
+        Validity::clamped(u64::MAX - 1, u64::MAX),
+        Validity {
+            issued_at: u64::MAX - 1,
+            valid_until: u64::MAX
+        }
+    );
+}
+
+#[test]
+fn short_window_is_kept() {
+    let request = request("short", json!({ "valid_until": NOW + 3600 }));
+    assert_eq!(
+        request.validity(NOW),
+        Validity {
+            issued_at: NOW,
+            valid_until: NOW + 3600
+        }
+    );
+    assert!(request.validity(NOW).check().is_ok());
+}
+
+#[test]
+fn inverted_window_is_kept_for_the_guest_to_reject() {
+    let window = json!({ "issued_at": NOW, "valid_until": NOW - 1 });
+    let resolved = request("inverted", window.clone()).resolve_validity(NOW);
+    assert_eq!(resolved.valid_until, Some(NOW - 1));
+    assert_eq!(
+        resolved.validity(NOW).check(),
+        Err(ValidityError::Inverted {
+            issued_at: NOW,
+            valid_until: NOW - 1
+        })
+    );
+
+    let error = mock()
+        .generate_proof(resolved, &context())
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("Invalid validity window"), "{}", error);
+
+    let violations = RequestValidator::new().validate(&request_json("inverted", window));
+    let paths: Vec<_> = violations.iter().map(|v| v.path.as_str()).collect();
+    assert_eq!(paths, ["/valid_until"]);
+}
+
+#[test]
+fn window_round_trips_through_public_values_and_calldata() {
+    let validity = Validity {
+        issued_at: NOW,
+        valid_until: NOW + 3600,
+    };
+    let window = json!({ "issued_at": validity.issued_at, "valid_until": validity.valid_until });
+    let proof = mock()
+        .generate_proof(request("roundtrip", window), &context())
+        .unwrap();
+
+    let public_values = proof.public_values().unwrap();
+    assert_eq!(public_values.validity, validity);
+
+    let call = VerifyCall::from_proof_data(&proof).unwrap();
+    assert_eq!(call.validity().unwrap(), validity);
+    let decoded = decode_calldata(&build_verify_calldata(&proof).unwrap()).unwrap();
+    assert_eq!(decoded.validity().unwrap(), validity);
+
+    // Where PicoVerifier.sol reads it
+    let encoded = bincode::serialize(&public_values).unwrap();
+    let valid_until = &encoded[VALID_UNTIL_OFFSET..VALID_UNTIL_OFFSET + 8];
+    assert_eq!(valid_until, validity.valid_until.to_le_bytes());
+    assert_eq!(encoded.len(), VALID_UNTIL_OFFSET + 8);
+}
+
+#[test]
+fn short_public_values_have_no_window() {
+    let call = VerifyCall {
+        riscv_vkey: [0; 32],
+        public_values: vec![0; VALID_UNTIL_OFFSET],
+        proof: Default::default(),
+    };
+    assert!(call.validity().is_err());
+}
+
+#[test]
+fn cached_proof_serves_only_within_its_window() {
+    let limits = CacheLimits {
+        max_entries: 16,
+        max_bytes: u64::MAX,
+    };
+    let backend = CachingBackend::new(mock(), Some(ProofCache::new(limits, NoStore)));
+    let now = unix_now();
+    let day = json!({ "issued_at": now, "valid_until": now + 86_400 });
+
+    let first = backend
+        .generate_proof(request("first", day), &context())
+        .unwrap();
+
+    // A replay allowing a longer window gets the cached proof
+    let week = json!({ "issued_at": now, "valid_until": now + 7 * 86_400 });
+    let replay = backend
+        .generate_proof(request("replay", week), &context())
+        .unwrap();
+    assert_eq!(replay.proof, first.proof);
+
+    // One asking for a shorter window is proved again
+    let hour = json!({ "issued_at": now, "valid_until": now + 3600 });
+    let shorter = backend
+        .generate_proof(request("shorter", hour), &context())
+        .unwrap();
+    assert_ne!(shorter.proof, first.proof);
+    assert_eq!(
+        shorter.public_values().unwrap().validity.valid_until,
+        now + 3600
+    );
+}
+
+#[test]
+fn expired_cached_proof_is_proved_again() {
+    let limits = CacheLimits {
+        max_entries: 16,
+        max_bytes: u64::MAX,
+    };
+    let backend = CachingBackend::new(mock(), Some(ProofCache::new(limits, NoStore)));
+    let expired = json!({ "issued_at": 1_700_000_000u64, "valid_until": 1_700_003_600u64 });
+    let first = backend
+        .generate_proof(request("expired", expired), &context())
+        .unwrap();
+
+    let again = backend
+        .generate_proof(request("again", json!({})), &context())
+        .unwrap();
+    assert_ne!(again.proof, first.proof);
+    assert!(again
+        .public_values()
+        .unwrap()
+        .validity
+        .is_valid_at(unix_now()));
+}
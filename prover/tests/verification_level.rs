@@ -0,0 +1,111 @@
+// Verification before publishing: a proof is `verified` when the pairing check runs
+// under a verifying key, and only `structurally_verified` when no key is at hand,
+// in which case the proof words, public values and verification key are still
+// checked.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use prover::types::{ProofData, VerificationLevel};
+use prover::verifier::{check_structure, verify_with_fallback, VerifierError, VerifyingKey};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+fn testdata() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata")
+}
+
+fn vk() -> VerifyingKey {
+    VerifyingKey::from_file(&testdata().join("groth16_vk.bin")).unwrap()
+}
+
+fn proof_data() -> ProofData {
+    let json = std::fs::read_to_string(testdata().join("groth16_proof.json")).unwrap();
+    serde_json::from_str(&json).unwrap()
+}
+
+fn proof_words(proof_data: &ProofData) -> Vec<String> {
+    serde_json::from_slice(&proof_data.decompressed_proof().unwrap()).unwrap()
+}
+
+fn with_proof_words(proof_data: &ProofData, words: &[String]) -> ProofData {
+    let mut proof_data = proof_data.clone();
+    proof_data.proof = STANDARD.encode(serde_json::to_vec(words).unwrap());
+    proof_data.content_encoding = None;
+    proof_data
+}
+
+#[test]
+fn proof_with_a_key_is_verified() {
+    let vk = vk();
+    assert_eq!(
+        verify_with_fallback(Some(&vk), &proof_data()).unwrap(),
+        VerificationLevel::Verified
+    );
+}
+
+#[test]
+fn proof_without_a_key_is_structurally_verified() {
+    assert_eq!(
+        verify_with_fallback(None, &proof_data()).unwrap(),
+        VerificationLevel::StructurallyVerified
+    );
+}
+
+#[test]
+fn structural_check_does_not_run_the_pairing_check() {
+    // Swapping two words breaks the proof but not its shape
+    let proof_data = proof_data();
+    let mut words = proof_words(&proof_data);
+    words.swap(0, 6);
+    let tampered = with_proof_words(&proof_data, &words);
+
+    assert!(check_structure(&tampered).is_ok());
+    let vk = vk();
+    assert!(matches!(
+        verify_with_fallback(Some(&vk), &tampered),
+        Err(VerifierError::InvalidProof(_))
+    ));
+}
+
+#[test]
+fn structural_check_rejects_malformed_proofs() {
+    let proof_data = proof_data();
+
+    let mut words = proof_words(&proof_data);
+    words.pop();
+    let short = with_proof_words(&proof_data, &words);
+    assert!(verify_with_fallback(None, &short).is_err());
+
+    let mut truncated = proof_data.clone();
+    truncated.public_inputs = STANDARD.encode(b"0x00");
+    assert!(verify_with_fallback(None, &truncated).is_err());
+
+    let mut keyless = proof_data.clone();
+    keyless.verification_key = String::new();
+    assert!(verify_with_fallback(None, &keyless).is_err());
+
+    let mut mislabelled = proof_data;
+    mislabelled.human_index ^= 1;
+    assert!(matches!(
+        verify_with_fallback(None, &mislabelled),
+        Err(VerifierError::InvalidProof(_))
+    ));
+}
+
+#[test]
+fn verification_level_is_published_in_snake_case() {
+    let mut proof_data = proof_data();
+    let published = serde_json::to_value(&proof_data).unwrap();
+    assert!(published.get("verification").is_none());
+
+    proof_data.verification = Some(VerificationLevel::StructurallyVerified);
+    let published = serde_json::to_value(&proof_data).unwrap();
+    assert_eq!(
+        published["verification"],
+        Value::from("structurally_verified")
+    );
+    let read: ProofData = serde_json::from_value(published).unwrap();
+    assert_eq!(
+        read.verification,
+        Some(VerificationLevel::StructurallyVerified)
+    );
+}
@@ -0,0 +1,350 @@
+// Checks responses built around the Groth16 fixture in testdata/ end to end, as
+// the verify-response binary does: the fixture passes, and each tampered variant
+// fails the check that guards the tampered field.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use prover::compression::ContentEncoding;
+use prover::decimal::EchoedWeights;
+use prover::evm::VerifyCall;
+use prover::prover::encode_proof_data;
+use prover::response_check::{check_response, CheckOptions, Outcome, Report, CHECKS};
+use prover::types::{ProofData, ProofStatus, ProverRequest, ProverResponse};
+use prover::verifier::VerifyingKey;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn testdata() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata")
+}
+
+fn vk() -> VerifyingKey {
+    VerifyingKey::from_file(&testdata().join("groth16_vk.bin")).unwrap()
+}
+
+fn proof_data() -> ProofData {
+    let json = std::fs::read_to_string(testdata().join("groth16_proof.json")).unwrap();
+    serde_json::from_str(&json).unwrap()
+}
+
+/// A v2 response carrying the fixture proof, committed with index 249
+fn fixture_response() -> ProverResponse {
+    serde_json::from_value(json!({
+        "schema_version": 2,
+        "request_id": "fixture",
+        "status": "success",
+        "proof_data": proof_data(),
+    }))
+    .unwrap()
+}
+
+/// Cleartext inputs giving the fixture's index under its committed weights
+fn fixture_request() -> ProverRequest {
+    serde_json::from_value(json!({
+        "request_id": "fixture",
+        "verification_results": { "recaptcha_score": 0.9, "sms_verified": 1, "bio_verified": 1 },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 },
+    }))
+    .unwrap()
+}
+
+fn check(response: &ProverResponse) -> Report {
+    let vk = vk();
+    let request = fixture_request();
+    check_response(
+        response,
+        &CheckOptions {
+            vk: Some(&vk),
+            request: Some(&request),
+            now: Some(1_800_000_000),
+            #[cfg(feature = "attestation")]
+            attestation: None,
+        },
+    )
+}
+
+fn assert_fails(report: &Report, name: &str) {
+    assert!(!report.passed, "{:#?}", report);
+    assert_eq!(report.outcome(name), Some(Outcome::Fail), "{:#?}", report);
+}
+
+#[test]
+fn fixture_response_passes() {
+    let mut response = fixture_response();
+    response.weights = Some(EchoedWeights::new(&fixture_request().public_inputs));
+    let report = check(&response);
+
+    assert!(report.passed, "{:#?}", report);
+    let names: Vec<_> = report.checks.iter().map(|check| check.name).collect();
+    assert_eq!(names, CHECKS);
+    for name in [
+        "status",
+        "proof_data",
+        "groth16",
+        "public_values",
+        "human_index",
+        "weights_commitment",
+        "echoed_weights",
+        "cleartext_inputs",
+    ] {
+        assert_eq!(report.outcome(name), Some(Outcome::Pass), "{:#?}", report);
+    }
+    assert!(report
+        .check("cleartext_inputs")
+        .unwrap()
+        .detail
+        .contains("249"));
+
+    // The fixture predates the validity window and carries no attestation
+    assert_eq!(report.outcome("validity"), Some(Outcome::Skip));
+    assert_eq!(report.outcome("attestation"), Some(Outcome::Skip));
+}
+
+#[test]
+fn absent_optional_inputs_are_skipped() {
+    let report = check_response(&fixture_response(), &CheckOptions::default());
+
+    assert!(report.passed, "{:#?}", report);
+    for name in [
+        "groth16",
+        "echoed_weights",
+        "cleartext_inputs",
+        "attestation",
+    ] {
+        assert_eq!(report.outcome(name), Some(Outcome::Skip), "{}", name);
+    }
+    assert_eq!(report.outcome("human_index"), Some(Outcome::Pass));
+}
+
+#[test]
+fn compressed_proof_is_decoded() {
+    let mut response = fixture_response();
+    let proof_data = response.proof_data.as_mut().unwrap();
+    assert!(proof_data.compress_proof(ContentEncoding::Gzip).unwrap());
+
+    let report = check(&response);
+    assert!(report.passed, "{:#?}", report);
+    assert!(report.check("proof_data").unwrap().detail.contains("gzip"));
+}
+
+#[test]
+fn tampered_human_index_fails() {
+    let mut response = fixture_response();
+    response.proof_data.as_mut().unwrap().human_index = 255;
+    let report = check(&response);
+    assert_fails(&report, "human_index");
+    // The proof itself is still valid
+    assert_eq!(report.outcome("groth16"), Some(Outcome::Pass));
+}
+
+#[test]
+fn tampered_public_values_fail_verification() {
+    let mut response = fixture_response();
+    let proof_data = response.proof_data.as_mut().unwrap();
+    let mut call = VerifyCall::from_proof_data(proof_data).unwrap();
+    // Claim the full index
+    call.public_values[20] = 255;
+    call.public_values[16] = 255;
+    let words: Vec<String> =
+        serde_json::from_slice(&proof_data.decompressed_proof().unwrap()).unwrap();
+    let vkey = STANDARD.decode(&proof_data.verification_key).unwrap();
+    let mut tampered = encode_proof_data(
+        &words,
+        &format!("0x{}", hex::encode(&call.public_values)),
+        std::str::from_utf8(&vkey).unwrap(),
+        255,
+        &[0; 32],
+    )
+    .unwrap();
+    tampered.weights_commitment = proof_data.weights_commitment.clone();
+    *proof_data = tampered;
+
+    let report = check(&response);
+    assert_fails(&report, "groth16");
+    assert_eq!(report.outcome("human_index"), Some(Outcome::Pass));
+    assert_fails(&report, "cleartext_inputs");
+}
+
+#[test]
+fn tampered_proof_words_fail_verification() {
+    let mut response = fixture_response();
+    let proof_data = response.proof_data.as_mut().unwrap();
+    let mut words: Vec<String> =
+        serde_json::from_slice(&proof_data.decompressed_proof().unwrap()).unwrap();
+    words.swap(6, 7);
+    proof_data.proof = STANDARD.encode(serde_json::to_vec(&words).unwrap());
+
+    assert_fails(&check(&response), "groth16");
+}
+
+#[test]
+fn tampered_fields_outside_the_proof_fail() {
+    let mut response = fixture_response();
+    response.proof_data.as_mut().unwrap().weights_commitment = format!("0x{}", "00".repeat(32));
+    assert_fails(&check(&response), "weights_commitment");
+
+    let mut response = fixture_response();
+    let mut weights = EchoedWeights::new(&fixture_request().public_inputs);
+    weights.fixed.w4 = 5000;
+    response.weights = Some(weights);
+    assert_fails(&check(&response), "echoed_weights");
+}
+
+#[test]
+fn mismatched_cleartext_inputs_fail() {
+    let vk = vk();
+    let mut request = fixture_request();
+    request.verification_results.bio_verified = 0;
+    let report = check_response(
+        &fixture_response(),
+        &CheckOptions {
+            vk: Some(&vk),
+            request: Some(&request),
+            ..Default::default()
+        },
+    );
+    assert_fails(&report, "cleartext_inputs");
+    assert_eq!(report.outcome("groth16"), Some(Outcome::Pass));
+
+    let mut request = fixture_request();
+    request.request_id = "other".to_string();
+    let report = check_response(
+        &fixture_response(),
+        &CheckOptions {
+            request: Some(&request),
+            ..Default::default()
+        },
+    );
+    assert_fails(&report, "cleartext_inputs");
+}
+
+#[test]
+fn failed_response_skips_the_proof_checks() {
+    let response: ProverResponse = serde_json::from_value(json!({
+        "request_id": "fixture",
+        "status": "failed",
+        "error": { "error_type": "ProofGenerationError", "message": "out of memory" },
+    }))
+    .unwrap();
+    assert_eq!(response.status, ProofStatus::Failed);
+
+    let report = check(&response);
+    assert_fails(&report, "status");
+    assert!(report
+        .check("status")
+        .unwrap()
+        .detail
+        .contains("out of memory"));
+    assert_eq!(report.checks.len(), CHECKS.len());
+    for check in &report.checks[1..] {
+        assert_eq!(check.outcome, Outcome::Skip, "{:#?}", report);
+    }
+}
+
+#[cfg(feature = "attestation")]
+#[tokio::test]
+async fn attestation_is_verified_against_the_signer() {
+    use alloy_primitives::Address;
+    use prover::eip712::{AttestationConfig, AttestationSigner, Attestor, LocalAttestationSigner};
+    use prover::response_check::AttestationKey;
+
+    const KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+    let config = AttestationConfig {
+        signing: "local".to_string(),
+        signer_key: Some(KEY.to_string()),
+        kms_key: None,
+        domain_name: "HumanIndex".to_string(),
+        domain_version: "1".to_string(),
+        chain_id: 8453,
+        verifying_contract: Address::repeat_byte(0x11),
+        ttl_secs: 3600,
+    };
+    let signer = LocalAttestationSigner::new(KEY).unwrap().address();
+    let mut response = fixture_response();
+    response.binding_id = Some("user-42".to_string());
+    Attestor::new(&config)
+        .await
+        .unwrap()
+        .attest("user-42", response.proof_data.as_mut().unwrap())
+        .await
+        .unwrap();
+
+    let check = |response: &ProverResponse, signer: Address| {
+        check_response(
+            response,
+            &CheckOptions {
+                attestation: Some(AttestationKey {
+                    domain: config.domain(),
+                    signer,
+                }),
+                ..Default::default()
+            },
+        )
+    };
+    let report = check(&response, signer);
+    assert_eq!(
+        report.outcome("attestation"),
+        Some(Outcome::Pass),
+        "{:#?}",
+        report
+    );
+    assert_fails(&check(&response, Address::repeat_byte(0x22)), "attestation");
+
+    // The signature does not carry over to another binding, nor to none
+    let mut rebound = response.clone();
+    rebound.binding_id = Some("user-43".to_string());
+    assert_fails(&check(&rebound, signer), "attestation");
+    rebound.binding_id = None;
+    assert_fails(&check(&rebound, signer), "attestation");
+
+    // Without a signer to check against, the signature is skipped
+    let report = check_response(&response, &CheckOptions::default());
+    assert_eq!(report.outcome("attestation"), Some(Outcome::Skip));
+}
+
+/// Run the binary on `response`, returning whether it passed and its JSON summary
+fn run_binary(name: &str, response: &ProverResponse) -> (bool, serde_json::Value) {
+    let dir = std::env::temp_dir().join(format!("verify-response-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let response_path = dir.join("response.json");
+    let request_path = dir.join("request.json");
+    std::fs::write(&response_path, serde_json::to_vec(response).unwrap()).unwrap();
+    std::fs::write(
+        &request_path,
+        serde_json::to_vec(&fixture_request()).unwrap(),
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_verify-response"))
+        .arg(&response_path)
+        .arg("--vk")
+        .arg(testdata().join("groth16_vk.bin"))
+        .arg("--request")
+        .arg(&request_path)
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).ok();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let summary = stdout.lines().last().expect("JSON summary");
+    (
+        output.status.success(),
+        serde_json::from_str(summary).unwrap(),
+    )
+}
+
+#[test]
+fn binary_reports_per_check_with_a_json_summary() {
+    let (passed, summary) = run_binary("pass", &fixture_response());
+    assert!(passed, "{}", summary);
+    assert_eq!(summary["passed"], true);
+    assert_eq!(summary["request_id"], "fixture");
+    assert_eq!(summary["checks"][2]["name"], "groth16");
+    assert_eq!(summary["checks"][2]["outcome"], "pass");
+
+    let mut tampered = fixture_response();
+    tampered.proof_data.as_mut().unwrap().human_index = 100;
+    let (passed, summary) = run_binary("tampered", &tampered);
+    assert!(!passed);
+    assert_eq!(summary["passed"], false);
+}
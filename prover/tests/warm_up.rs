@@ -0,0 +1,80 @@
+// The prover client is built once, at the warm-up, and reused by the proofs that
+// follow instead of each building its own. Concurrent proofs check out clients of
+// their own, and a client whose proof panicked is not handed to the next one.
+
+use prover::mock::MockProofGenerator;
+use prover::prover::{ClientPool, ProofBackend, ProofContext};
+use prover::types::ProverRequest;
+use serde_json::json;
+use std::time::{Duration, Instant};
+
+fn request(request_id: &str) -> ProverRequest {
+    serde_json::from_value(json!({
+        "request_id": request_id,
+        "verification_results": { "recaptcha_score": 8000, "sms_verified": 1, "bio_verified": 1 },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 },
+    }))
+    .unwrap()
+}
+
+fn context() -> ProofContext {
+    ProofContext::new(Instant::now() + Duration::from_secs(60), 0)
+}
+
+#[test]
+fn sequential_proofs_reuse_the_warmed_up_client() {
+    let backend = MockProofGenerator::new(Duration::ZERO, Duration::from_secs(60));
+    let warm_up = backend.warm_up().unwrap().expect("the mock warms up");
+    assert_eq!(warm_up.guest_cycles, None);
+    assert_eq!(backend.clients().created(), 1);
+    assert_eq!(backend.clients().idle(), 1);
+
+    for request_id in ["first", "second"] {
+        backend
+            .generate_proof(request(request_id), &context())
+            .unwrap();
+    }
+    assert_eq!(backend.clients().created(), 1);
+    assert_eq!(backend.clients().idle(), 1);
+}
+
+#[test]
+fn failed_proof_returns_its_client() {
+    let backend = MockProofGenerator::new(Duration::ZERO, Duration::from_secs(60));
+    backend
+        .generate_proof(request("fail-proof-1"), &context())
+        .expect_err("mock proof fails");
+    backend
+        .generate_proof(request("after"), &context())
+        .unwrap();
+    assert_eq!(backend.clients().created(), 1);
+}
+
+#[test]
+fn concurrent_checkouts_get_their_own_clients() {
+    let pool = ClientPool::default();
+    let first = pool.checkout(|| 1);
+    let second = pool.checkout(|| 2);
+    assert_eq!((*first, *second), (1, 2));
+    assert_eq!(pool.created(), 2);
+    drop((first, second));
+
+    // Both are kept for later proofs
+    assert_eq!(pool.idle(), 2);
+    let _reused = pool.checkout(|| unreachable!("an idle client is reused"));
+    assert_eq!(pool.created(), 2);
+}
+
+#[test]
+fn client_of_a_panicked_proof_is_dropped() {
+    let pool = ClientPool::default();
+    let panicked = std::panic::catch_unwind(|| {
+        let _client = pool.checkout(|| 1);
+        panic!("proof panicked");
+    });
+    assert!(panicked.is_err());
+    assert_eq!(pool.idle(), 0);
+
+    assert_eq!(*pool.checkout(|| 2), 2);
+    assert_eq!(pool.created(), 2);
+}
@@ -0,0 +1,91 @@
+// Weighted admission: a request's `weight_class` is the number of permits it takes.
+// A heavy request waits until enough permits are free, holds all of them while it
+// runs and releases them when it completes. A weight above the capacity is capped
+// so the request runs alone.
+
+use prover::service::admission_cost;
+use prover::types::ProverRequest;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinHandle;
+
+/// Permits in the pool, as `max_concurrent_proofs`
+const CAPACITY: usize = 3;
+
+/// Long enough for the semaphore to have granted anything it was going to
+const SETTLE: Duration = Duration::from_millis(100);
+
+fn message(weight_class: Option<u32>) -> Vec<u8> {
+    let mut request = json!({
+        "request_id": "weighted",
+        "verification_results": { "recaptcha_score": 9000, "sms_verified": 1, "bio_verified": 0 },
+        "public_inputs": { "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 },
+    });
+    if let Some(weight_class) = weight_class {
+        request["weight_class"] = json!(weight_class);
+    }
+    serde_json::to_vec(&request).unwrap()
+}
+
+/// The permits `data` costs, parsed as the service does
+fn cost(data: &[u8], max_concurrent_proofs: usize) -> u32 {
+    let request = serde_json::from_slice::<ProverRequest>(data).ok();
+    admission_cost(request.as_ref(), max_concurrent_proofs)
+}
+
+/// Wait for the permits `data` costs, as the service does before proving it
+fn admit(semaphore: &Arc<Semaphore>, data: Vec<u8>) -> JoinHandle<OwnedSemaphorePermit> {
+    let cost = cost(&data, CAPACITY);
+    let semaphore = semaphore.clone();
+    tokio::spawn(async move { semaphore.acquire_many_owned(cost).await.unwrap() })
+}
+
+async fn granted(waiting: JoinHandle<OwnedSemaphorePermit>) -> OwnedSemaphorePermit {
+    tokio::time::timeout(Duration::from_secs(5), waiting)
+        .await
+        .expect("permits granted")
+        .unwrap()
+}
+
+#[test]
+fn weight_class_is_the_cost() {
+    assert_eq!(cost(&message(None), 4), 1);
+    assert_eq!(cost(&message(Some(0)), 4), 1);
+    assert_eq!(cost(&message(Some(3)), 4), 3);
+    assert_eq!(cost(b"not json", 4), 1);
+}
+
+#[test]
+fn oversized_weight_is_capped_at_the_capacity() {
+    assert_eq!(cost(&message(Some(10)), 4), 4);
+}
+
+#[tokio::test]
+async fn heavy_request_waits_for_three_free_permits() {
+    let semaphore = Arc::new(Semaphore::new(CAPACITY));
+    let first = granted(admit(&semaphore, message(None))).await;
+    let second = granted(admit(&semaphore, message(None))).await;
+
+    // One permit is free, but the heavy request needs three
+    let heavy = admit(&semaphore, message(Some(3)));
+    tokio::time::sleep(SETTLE).await;
+    assert!(!heavy.is_finished());
+
+    drop(first);
+    tokio::time::sleep(SETTLE).await;
+    assert!(!heavy.is_finished());
+
+    drop(second);
+    let heavy = granted(heavy).await;
+    assert_eq!(heavy.num_permits(), 3);
+
+    // While it runs it holds the whole capacity, and completing releases it
+    let cheap = admit(&semaphore, message(None));
+    tokio::time::sleep(SETTLE).await;
+    assert!(!cheap.is_finished());
+    drop(heavy);
+    drop(granted(cheap).await);
+    assert_eq!(semaphore.available_permits(), CAPACITY);
+}
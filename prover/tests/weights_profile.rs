@@ -0,0 +1,105 @@
+// Requests naming a `weights_profile` instead of sending raw weights: the profile
+// resolves to its registered weights, unknown names are rejected, and a `v1`
+// request proves exactly what the same request with the raw v1 weights proves.
+
+use human_index_lib::{calculate_human_index, commit_weights, WeightsProfile, WEIGHTS_PROFILES};
+use prover::mock::MockProofGenerator;
+use prover::prover::{ProofBackend, ProofContext};
+use prover::types::ProverRequest;
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+
+fn parse(public_inputs: Value, weights_profile: Option<&str>) -> serde_json::Result<ProverRequest> {
+    let mut request = json!({
+        "request_id": "req-profile",
+        "verification_results": { "recaptcha_score": 7321, "sms_verified": 1, "bio_verified": 0 },
+        "public_inputs": public_inputs,
+    });
+    if let Some(profile) = weights_profile {
+        request["weights_profile"] = json!(profile);
+    }
+    serde_json::from_value::<ProverRequest>(request)?.resolve_weights_profile()
+}
+
+fn raw_v1() -> Value {
+    json!({ "w1": 1500, "w2": 2000, "w3": 2500, "w4": 4000, "expected_output": 0 })
+}
+
+#[test]
+fn profile_resolves_to_its_weights() {
+    let v1 = WeightsProfile::from_id("v1").expect("v1 is registered");
+    assert_eq!(v1.weights, [1500, 2000, 2500, 4000]);
+
+    let request = parse(json!({ "expected_output": 0 }), Some("v1")).unwrap();
+    assert!(v1.matches(&request.public_inputs));
+    assert_eq!(request.weights_profile.as_deref(), Some("v1"));
+
+    // Every registered id is unique and resolves to itself
+    for profile in WEIGHTS_PROFILES {
+        assert_eq!(WeightsProfile::from_id(profile.id), Some(profile));
+    }
+}
+
+#[test]
+fn unknown_profile_is_rejected() {
+    let err = parse(json!({ "expected_output": 0 }), Some("v0")).unwrap_err();
+    assert!(
+        err.to_string().contains("unknown weights_profile 'v0'"),
+        "{}",
+        err
+    );
+    assert!(err.to_string().contains("v1"), "{}", err);
+}
+
+#[test]
+fn raw_weights_must_agree_with_the_profile() {
+    // Sending the profile's own weights too is fine
+    parse(raw_v1(), Some("v1")).expect("matching raw weights");
+
+    let typo = json!({ "w1": 1500, "w2": 2000, "w3": 2500, "w4": 400, "expected_output": 0 });
+    let err = parse(typo, Some("v1")).unwrap_err();
+    assert!(
+        err.to_string().contains("differ from weights_profile 'v1'"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn zero_raw_weights_are_kept_without_a_profile() {
+    // Proved as sent, to an index of 0, as before profiles existed
+    let zero = json!({ "w1": 0, "w2": 0, "w3": 0, "w4": 0, "expected_output": 0 });
+    let request = parse(zero, None).expect("zero raw weights");
+    let inputs = &request.public_inputs;
+    assert_eq!([inputs.w1, inputs.w2, inputs.w3, inputs.w4], [0; 4]);
+    assert!(request.weights_profile.is_none());
+    assert_eq!(
+        calculate_human_index(&request.verification_results, &request.public_inputs),
+        0
+    );
+
+    // Leaving out only some weights is malformed, profile or not
+    let partial = json!({ "w1": 1500, "expected_output": 0 });
+    assert!(parse(partial.clone(), None).is_err());
+    assert!(parse(partial, Some("v1")).is_err());
+}
+
+#[test]
+fn v1_profile_proves_like_raw_v1_weights() {
+    let raw = parse(raw_v1(), None).unwrap();
+    let named = parse(json!({ "expected_output": 0 }), Some("v1")).unwrap();
+    assert_eq!(raw.public_inputs, named.public_inputs);
+    assert_eq!(
+        commit_weights(&raw.public_inputs),
+        commit_weights(&named.public_inputs)
+    );
+
+    let backend = MockProofGenerator::new(Duration::ZERO, Duration::from_secs(60));
+    let prove = |request: ProverRequest| {
+        let context = ProofContext::new(Instant::now() + Duration::from_secs(60), 0);
+        backend.generate_proof(request, &context).unwrap()
+    };
+    let (raw, named) = (prove(raw), prove(named));
+    assert_eq!(raw.human_index, named.human_index);
+    assert_eq!(raw.weights_commitment, named.weights_commitment);
+}
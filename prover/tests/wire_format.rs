@@ -0,0 +1,132 @@
+// The wire format of requests and results: every field `snake_case`, pinned here
+// byte for byte, with the `camelCase` names of a consumer's spec still understood
+// on the way in.
+
+use prover::types::{ProverRequest, ProverResponse, VersionedResponse};
+use serde_json::{Map, Value};
+
+const REQUEST: &str = concat!(
+    r#"{"request_id":"wire-1","#,
+    r#""verification_results":{"recaptcha_score":8000,"sms_verified":1,"bio_verified":0},"#,
+    r#""public_inputs":{"w1":1500,"w2":2000,"w3":2500,"w4":4000,"expected_output":0},"#,
+    r#""client_id":"client-1","trace_id":"trace-1","parent_request_id":"parent-1","#,
+    r#""submitted_at":"2026-10-16T12:00:00Z","callback_url":"https://example.com/hook","#,
+    r#""not_before":"2026-10-16T12:05:00Z","weights_profile":"v1","#,
+    r#""issued_at":1790000000,"valid_until":1790086400}"#
+);
+
+const SUCCESS: &str = concat!(
+    r#"{"schema_version":2,"request_id":"wire-1","status":"success","#,
+    r#""proof_data":{"proof":"cHJvb2Y=","public_inputs":"cHY=","verification_key":"dms=","#,
+    r#""human_index":73,"weights_commitment":"0xab","attestation_signature":"0xsig","#,
+    r#""attestation_expiry":1790086400,"onchain_tx":"0xtx","profile":"evm","weights_profile":"v1"},"#,
+    r#""metrics":{"received_at":"2026-10-16T12:00:00+00:00","started_at":"2026-10-16T12:00:01+00:00","#,
+    r#""completed_at":"2026-10-16T12:00:02+00:00","duration_ms":1234,"attempts":1,"#,
+    r#""peak_rss_bytes":1048576,"cpu_time_ms":900,"stdin_prepare_ms":0,"guest_cycles":null,"#,
+    r#""artifact_bytes_written":2048,"cost_units":1.5},"#,
+    r#""binding_id":"binding-1","trace_id":"trace-1","parent_request_id":"parent-1","publish_sequence":7,"#,
+    r#""provenance":{"instance_id":"worker-1","hostname":"host","prover_version":"0.1.0","#,
+    r#""pico_sdk_version":"1.0.0","elf_sha256":"ab","setup_sha256":"cd","backend":"pico"},"#,
+    r#""weights":{"fixed":{"w1":1500,"w2":2000,"w3":2500,"w4":4000},"#,
+    r#""decimal":{"w1":0.15,"w2":0.2,"w3":0.25,"w4":0.4}}}"#
+);
+
+const TIMEOUT: &str = concat!(
+    r#"{"schema_version":2,"request_id":"wire-2","status":"timeout","#,
+    r#""error":{"error_type":"Timeout","message":"took too long","details":"prove"},"#,
+    r#""metrics":{"received_at":"2026-10-16T12:00:00+00:00","started_at":"2026-10-16T12:00:01+00:00","#,
+    r#""completed_at":"2026-10-16T12:10:01+00:00","duration_ms":600000,"#,
+    r#""peak_rss_bytes":null,"cpu_time_ms":null,"stdin_prepare_ms":0,"guest_cycles":123456,"#,
+    r#""artifact_bytes_written":null,"cost_units":null,"progress":{"phase":"prove","heartbeats":3,"idle_ms":500,"extended_secs":60}},"#,
+    r#""provisional_index":{"human_index":48,"proven":false}}"#
+);
+
+/// `value` with every object key in `camelCase`
+fn camel_case(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    let mut parts = key.split('_');
+                    let mut camel = parts.next().unwrap_or_default().to_string();
+                    for part in parts {
+                        camel.push_str(&part[..1].to_uppercase());
+                        camel.push_str(&part[1..]);
+                    }
+                    (camel, camel_case(value))
+                })
+                .collect::<Map<_, _>>(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(camel_case).collect()),
+        other => other.clone(),
+    }
+}
+
+fn camel_case_json(json: &str) -> Vec<u8> {
+    serde_json::to_vec(&camel_case(&serde_json::from_str(json).unwrap())).unwrap()
+}
+
+#[test]
+fn requests_round_trip_exactly() {
+    let request: ProverRequest = serde_json::from_str(REQUEST).unwrap();
+    assert_eq!(serde_json::to_string(&request).unwrap(), REQUEST);
+}
+
+#[test]
+fn results_round_trip_exactly() {
+    for json in [SUCCESS, TIMEOUT] {
+        let response: ProverResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(serde_json::to_string(&response).unwrap(), json);
+        assert_eq!(
+            String::from_utf8(response.encode(2).unwrap()).unwrap(),
+            json
+        );
+    }
+}
+
+#[test]
+fn camel_case_requests_are_read_as_snake_case() {
+    let camel = camel_case_json(REQUEST);
+    let sent = String::from_utf8_lossy(&camel);
+    assert!(sent.contains(r#""verificationResults""#) && sent.contains(r#""recaptchaScore""#));
+
+    let request = ProverRequest::from_json(&camel).unwrap();
+    assert_eq!(serde_json::to_string(&request).unwrap(), REQUEST);
+}
+
+#[test]
+fn camel_case_results_decode_and_are_written_as_snake_case() {
+    for json in [SUCCESS, TIMEOUT] {
+        let camel = camel_case_json(json);
+        let decoded = ProverResponse::decode(&camel).unwrap();
+        assert!(matches!(decoded, VersionedResponse::V2(_)), "{:?}", decoded);
+        let response = decoded.response().unwrap();
+        assert_eq!(serde_json::to_string(response).unwrap(), json);
+    }
+}
+
+#[test]
+fn v1_results_still_decode() {
+    let v1 = concat!(
+        r#"{"request_id":"wire-1","status":"success","#,
+        r#""proof_data":{"proof":"cHJvb2Y=","public_inputs":"cHY=","verification_key":"dms=","human_index":73},"#,
+        r#""metrics":{"received_at":"2026-10-16T12:00:00+00:00","started_at":"2026-10-16T12:00:01+00:00","#,
+        r#""completed_at":"2026-10-16T12:00:02+00:00","duration_ms":1234}}"#
+    );
+    let decoded = ProverResponse::decode(v1.as_bytes()).unwrap();
+    assert_eq!(decoded.schema_version(), 1);
+    let response = decoded.response().unwrap();
+    assert_eq!(response.proof_data.as_ref().unwrap().human_index, 73);
+    assert_eq!(response.metrics.as_ref().unwrap().duration_ms, 1234);
+    // And are written back in their own shape
+    assert_eq!(String::from_utf8(response.encode(1).unwrap()).unwrap(), v1);
+}
+
+#[test]
+fn private_values_stay_out_of_camel_case_errors() {
+    let mut request: Value = serde_json::from_slice(&camel_case_json(REQUEST)).unwrap();
+    request["verificationResults"]["smsVerified"] = Value::String("secret-7".to_string());
+    let error = ProverRequest::from_json(&serde_json::to_vec(&request).unwrap())
+        .expect_err("sms_verified must be a number");
+    assert!(!error.to_string().contains("secret-7"), "{}", error);
+}
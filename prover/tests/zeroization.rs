@@ -0,0 +1,103 @@
+// The private inputs are zeroized once written to stdin: a wrapper standing in for
+// `ProofInputs` records when it is zeroized, and a stand-in `prove_evm` checks the
+// record while the proof is still running.
+
+use human_index_lib::{HumanIndexPublicInputs, ProofInputs, Validity, VerificationResults};
+use prover::prover::{setup_inputs, StdinWords};
+use std::borrow::Borrow;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// `ProofInputs` that record being zeroized
+struct Recorded {
+    inputs: ProofInputs,
+    zeroized: Arc<AtomicBool>,
+}
+
+impl Borrow<ProofInputs> for Recorded {
+    fn borrow(&self) -> &ProofInputs {
+        &self.inputs
+    }
+}
+
+impl Drop for Recorded {
+    fn drop(&mut self) {
+        self.inputs.results.zeroize();
+        let results = &self.inputs.results;
+        let cleared = [
+            results.recaptcha_score,
+            results.sms_verified,
+            results.bio_verified,
+        ] == [0; 3];
+        self.zeroized.store(cleared, Ordering::SeqCst);
+    }
+}
+
+fn inputs() -> ProofInputs {
+    ProofInputs::new(
+        VerificationResults {
+            recaptcha_score: 9000,
+            sms_verified: 1,
+            bio_verified: 1,
+        },
+        HumanIndexPublicInputs {
+            w1: 1500,
+            w2: 2000,
+            w3: 2500,
+            w4: 4000,
+            expected_output: 0,
+        },
+        Validity {
+            issued_at: 1_790_000_000,
+            valid_until: 1_790_086_400,
+        },
+    )
+}
+
+fn words(stdin: &StdinWords) -> Vec<u32> {
+    let mut words = Vec::new();
+    stdin.write_to(|value| words.push(*value));
+    words
+}
+
+#[test]
+fn private_inputs_are_zeroized_before_prove_evm_returns() {
+    let zeroized = Arc::new(AtomicBool::new(false));
+    let stdin = StdinWords::take(Recorded {
+        inputs: inputs(),
+        zeroized: zeroized.clone(),
+    });
+
+    // As `ProofGenerator` proves: each attempt writes the words to a fresh stdin
+    let prove_evm = |attempt: u32| {
+        assert!(
+            zeroized.load(Ordering::SeqCst),
+            "private inputs still held during attempt {}",
+            attempt
+        );
+        words(&stdin)
+    };
+    for attempt in 1..=2 {
+        let mut expected = Vec::new();
+        inputs().write_to(|value| expected.push(*value));
+        assert_eq!(prove_evm(attempt), expected);
+    }
+}
+
+#[test]
+fn stdin_words_match_the_inputs_they_were_taken_from() {
+    let mut expected = Vec::new();
+    setup_inputs().write_to(|value| expected.push(*value));
+    assert_eq!(words(&StdinWords::take(setup_inputs())), expected);
+}
+
+#[test]
+fn verification_results_zeroize() {
+    fn zeroize_on_drop<T: ZeroizeOnDrop>() {}
+    zeroize_on_drop::<VerificationResults>();
+
+    let mut results = inputs().results;
+    results.zeroize();
+    assert_eq!(results, VerificationResults::default());
+}
@@ -0,0 +1,39 @@
+import { ethers } from 'ethers';
+
+// Prints the EIP-712 vector pinned in prover/tests/attestation.rs: the digest of a
+// fixed attestation under a fixed domain, and its signature by a fixed key, encoded
+// by ethers rather than by the prover's own code.
+//
+// Usage: npm run attestation:vector
+
+// Private key used in the ethers and web3 documentation
+const KEY = '0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318';
+
+const domain = {
+  name: 'HumanIndex',
+  version: '1',
+  chainId: 8453,
+  verifyingContract: '0x' + '11'.repeat(20),
+};
+
+const types = {
+  HumanIndexAttestation: [
+    { name: 'bindingId', type: 'bytes32' },
+    { name: 'humanIndex', type: 'uint32' },
+    { name: 'vkeyHash', type: 'bytes32' },
+    { name: 'expiry', type: 'uint64' },
+  ],
+};
+
+const attestation = {
+  bindingId: ethers.keccak256(ethers.toUtf8Bytes('user-42')),
+  humanIndex: 73,
+  vkeyHash: '0x' + 'ab'.repeat(32),
+  expiry: 1_800_000_000,
+};
+
+const wallet = new ethers.Wallet(KEY);
+console.log(`ethers    ${ethers.version}`);
+console.log(`signer    ${wallet.address}`);
+console.log(`digest    ${ethers.TypedDataEncoder.hash(domain, types, attestation)}`);
+console.log(`signature ${await wallet.signTypedData(domain, types, attestation)}`);
@@ -6,6 +6,7 @@
 
 import { PubSub } from "@google-cloud/pubsub";
 import { writeFileSync, mkdirSync } from "fs";
+import { randomUUID } from "crypto";
 
 // Configuration
 const PROJECT_ID = process.env.GCP_PROJECT_ID || "test-project";
@@ -209,7 +210,8 @@ async function publishTestMessage(
   const topic = pubsub.topic(PROVER_TOPIC);
 
   if (!requestId) {
-    requestId = `test-${scenario}-${Math.floor(Date.now() / 1000)}`;
+    // The prover only accepts UUIDs unless REQUEST_ID_FORMAT=any
+    requestId = randomUUID();
   }
 
   const messageData = createTestMessage(requestId, scenario);